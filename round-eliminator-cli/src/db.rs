@@ -0,0 +1,180 @@
+use round_eliminator_lib::algorithms::event::EventHandler;
+use round_eliminator_lib::corpus::{classify, classify_corpus, read_corpus, Budget};
+use round_eliminator_lib::database::{ProblemRecord, Query};
+use round_eliminator_lib::problem::Problem;
+use round_eliminator_lib::store::{problem_id, ProblemId};
+use rusqlite::{params, types::Type, Connection, OptionalExtension, Row, Transaction};
+
+/// A sqlite database of problems, indexed by their canonical hash.
+pub struct Database {
+    conn : Connection,
+}
+
+fn record_of_row(row : &Row) -> rusqlite::Result<ProblemRecord> {
+    let hash = row.get::<_, String>(0)?.parse().map_err(|e : &str| rusqlite::Error::FromSqlConversionFailure(0, Type::Text, e.into()))?;
+    record_of_row_with_hash(row, hash)
+}
+
+fn record_of_row_with_hash(row : &Row, hash : ProblemId) -> rusqlite::Result<ProblemRecord> {
+    Ok(ProblemRecord {
+        hash,
+        text: row.get(1)?,
+        labels: row.get::<_, i64>(2)? as usize,
+        active_degree: row.get(3)?,
+        passive_degree: row.get(4)?,
+        trivial: row.get(5)?,
+        unsolvable: row.get(6)?,
+        lower_bound: row.get::<_, Option<i64>>(7)?.map(|x| x as usize),
+        upper_bound: row.get::<_, Option<i64>>(8)?.map(|x| x as usize),
+        derivation: row.get(9)?,
+    })
+}
+
+const COLUMNS : &str = "hash, text, labels, active_degree, passive_degree, trivial, unsolvable, lower_bound, upper_bound, derivation";
+
+/// The version of the schema, kept in `PRAGMA user_version`. In version 0 the hashes were truncated to 53 bits and stored as integers.
+const SCHEMA_VERSION : i64 = 1;
+
+const SCHEMA : &str = "CREATE TABLE IF NOT EXISTS problems (
+        hash TEXT PRIMARY KEY,
+        text TEXT NOT NULL,
+        labels INTEGER NOT NULL,
+        active_degree TEXT NOT NULL,
+        passive_degree TEXT NOT NULL,
+        trivial INTEGER,
+        unsolvable INTEGER NOT NULL,
+        lower_bound INTEGER,
+        upper_bound INTEGER,
+        derivation TEXT
+    );
+    CREATE INDEX IF NOT EXISTS problems_labels ON problems (labels);";
+
+fn get_record(conn : &Connection, hash : ProblemId) -> rusqlite::Result<Option<ProblemRecord>> {
+    conn.query_row(&format!("SELECT {} FROM problems WHERE hash = ?1", COLUMNS), params![hash.to_string()], record_of_row)
+        .optional()
+}
+
+fn insert_record(conn : &Connection, record : &ProblemRecord) -> Result<(), String> {
+    let mut record = record.clone();
+    if let Some(old) = get_record(conn, record.hash).map_err(|e| e.to_string())? {
+        record.merge(&old)?;
+    }
+    conn.execute(
+        &format!("INSERT OR REPLACE INTO problems ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)", COLUMNS),
+        params![
+            record.hash.to_string(),
+            record.text,
+            record.labels as i64,
+            record.active_degree,
+            record.passive_degree,
+            record.trivial,
+            record.unsolvable,
+            record.lower_bound.map(|x| x as i64),
+            record.upper_bound.map(|x| x as i64),
+            record.derivation,
+        ],
+    ).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Moves the problems of a database of version 0 to the current schema, computing their hashes again from their text.
+fn migrate_from_integer_hashes(tx : &Transaction) -> Result<(), String> {
+    tx.execute_batch("ALTER TABLE problems RENAME TO problems_v0; DROP INDEX IF EXISTS problems_labels;").map_err(|e| e.to_string())?;
+    tx.execute_batch(SCHEMA).map_err(|e| e.to_string())?;
+    let records = {
+        let mut statement = tx.prepare(&format!("SELECT {} FROM problems_v0", COLUMNS)).map_err(|e| e.to_string())?;
+        let records = statement.query_map([], |row| {
+            let p = Problem::from_string(row.get::<_, String>(1)?).map_err(|e| rusqlite::Error::FromSqlConversionFailure(1, Type::Text, e.into()))?;
+            record_of_row_with_hash(row, problem_id(&p))
+        }).map_err(|e| e.to_string())?;
+        records.collect::<rusqlite::Result<Vec<_>>>().map_err(|e| e.to_string())?
+    };
+    for record in &records {
+        insert_record(tx, record)?;
+    }
+    tx.execute_batch("DROP TABLE problems_v0;").map_err(|e| e.to_string())
+}
+
+impl Database {
+    /// Opens the database, creating it if it does not exist, and migrating it if it has been created by an older version.
+    pub fn open(path : &str) -> Result<Self, String> {
+        let mut conn = Connection::open(path).map_err(|e| e.to_string())?;
+        let version : i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0)).map_err(|e| e.to_string())?;
+        if version > SCHEMA_VERSION {
+            return Err("The database has been created by a newer version".into());
+        }
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let exists = tx.query_row("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'problems'", [], |row| row.get::<_, i64>(0)).map_err(|e| e.to_string())? > 0;
+        if version == 0 && exists {
+            migrate_from_integer_hashes(&tx)?;
+        }
+        tx.execute_batch(SCHEMA).map_err(|e| e.to_string())?;
+        tx.pragma_update(None, "user_version", SCHEMA_VERSION).map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(Self { conn })
+    }
+
+    pub fn get(&self, hash : ProblemId) -> rusqlite::Result<Option<ProblemRecord>> {
+        get_record(&self.conn, hash)
+    }
+
+    /// Stores the record, combining it with what is already known about the same problem.
+    /// Fails, without changing the database, if a different problem with the same hash is stored already.
+    pub fn insert(&self, record : &ProblemRecord) -> Result<(), String> {
+        insert_record(&self.conn, record)
+    }
+
+    pub fn query(&self, query : &Query) -> rusqlite::Result<Vec<ProblemRecord>> {
+        // the bounds on the number of labels are the only conditions that can use the index
+        let min = query.min_labels.unwrap_or(0) as i64;
+        let max = query.max_labels.map_or(i64::MAX, |x| x as i64);
+        let mut statement = self.conn.prepare(&format!("SELECT {} FROM problems WHERE labels BETWEEN ?1 AND ?2 ORDER BY labels, hash", COLUMNS))?;
+        let records = statement.query_map(params![min, max], record_of_row)?.collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(records.into_iter().filter(|r| query.matches(r)).collect())
+    }
+}
+
+fn print_record(record : &ProblemRecord) {
+    let bound = |b : Option<usize>| b.map_or("?".to_string(), |b| b.to_string());
+    let status = if record.unsolvable { "unsolvable".to_string() } else { format!("rounds [{}, {}]", bound(record.lower_bound), bound(record.upper_bound)) };
+    println!("# {} {} labels, {}{}", record.hash, record.labels, status, record.derivation.as_ref().map_or(String::new(), |d| format!(", from {}", d)));
+    println!("{}", record.text);
+}
+
+/// Classifies the problem in `file` and stores it.
+pub fn add(db : &Database, file : &str, budget : Budget) -> Result<(), String> {
+    let text = std::fs::read_to_string(file).map_err(|e| e.to_string())?;
+    let p = Problem::from_string(text)?;
+    let classification = classify(&p, budget, &mut EventHandler::null());
+    let record = ProblemRecord::new(&p, Some(classification), Some(file.to_string()));
+    db.insert(&record)?;
+    print_record(&db.get(record.hash).map_err(|e| e.to_string())?.unwrap());
+    Ok(())
+}
+
+/// Classifies all the entries of a corpus and stores them.
+pub fn add_corpus(db : &Database, file : &str, budget : Budget) -> Result<(), String> {
+    let reader = std::io::BufReader::new(std::fs::File::open(file).map_err(|e| e.to_string())?);
+    let mut result = Ok(());
+    classify_corpus(read_corpus(reader), budget, |entry, classification| {
+        let (Ok(entry), Ok(classification)) = (entry, classification) else {
+            return;
+        };
+        let p = entry.problem().unwrap();
+        let record = ProblemRecord::new(&p, Some(classification), Some(format!("corpus {}", entry)));
+        if let Err(e) = db.insert(&record) {
+            result = Err(e);
+        }
+    }, &mut EventHandler::null());
+    result
+}
+
+pub fn query(db : &Database, conditions : &[String]) -> Result<(), String> {
+    let query = Query::parse(conditions)?;
+    let records = db.query(&query).map_err(|e| e.to_string())?;
+    for record in &records {
+        print_record(record);
+    }
+    println!("{} problems", records.len());
+    Ok(())
+}
@@ -1,21 +1,134 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use round_eliminator_lib::problem::Problem;
+use round_eliminator_lib::serial::{Request, Response};
 use std::thread;
 use round_eliminator_lib::line::Degree;
 use round_eliminator_lib::algorithms::event::EventHandler;
+use round_eliminator_lib::algorithms::{autolb::LbStrategy, autoub::{EvaluationPolicy, SearchStats}, pruning_rules::PruningRules};
+use round_eliminator_lib::corpus::Budget;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::fmt;
+use std::fs::File;
+
+mod repl;
+mod db;
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, subcommand_negates_reqs = true)]
 struct Args {
-    #[arg(short, long)]
-    file: String,
+    #[command(subcommand)]
+    command : Option<Command>,
+    #[arg(short, long, required = true)]
+    file: Option<String>,
     #[arg(short, long)]
     coloring : Option<usize>,
     #[arg(short, long)]
     passive_coloring : Option<usize>,
+    /// Append the requests performed by this run to the given trace file (one JSON per line)
+    #[arg(long)]
+    trace : Option<String>,
+    /// How the automatic lower bounds choose the merges
+    #[arg(long, value_enum, default_value_t = Strategy::Labels)]
+    lb_strategy : Strategy,
+    /// Also search for bounds on the problem with the sides swapped, which differ by one round
+    #[arg(long)]
+    both_sides : bool,
+}
+
+/// The choices of `LbStrategy` that do not need further parameters.
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum Strategy {
+    /// Merge labels whose sets of original labels are close to each other
+    Labels,
+    /// Prefer the merges that give the narrowest diagram
+    DiagramWidth,
+}
+
+impl From<Strategy> for LbStrategy {
+    fn from(s : Strategy) -> Self {
+        match s {
+            Strategy::Labels => LbStrategy::Labels,
+            Strategy::DiagramWidth => LbStrategy::DiagramWidth,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run again all the requests of a trace file, and check that they give the same results
+    Replay { trace : String },
+    /// Explore a problem interactively, applying one operation at a time
+    Repl { file : Option<String> },
+    /// Store classified problems in a database, and search it
+    Db {
+        /// The sqlite file of the database
+        #[arg(long, default_value = "problems.db")]
+        db : String,
+        #[command(subcommand)]
+        command : DbCommand,
+    },
+    /// Classify the problem contained in a file given a coloring with each number of colors in a range, to see whether a coloring helps
+    Colorings {
+        file : String,
+        #[arg(long, default_value_t = 2)]
+        min_colors : usize,
+        #[arg(long, default_value_t = 10)]
+        max_colors : usize,
+        #[arg(long, default_value_t = 5)]
+        max_steps : usize,
+        #[arg(long, default_value_t = 30)]
+        max_labels : usize,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum DbCommand {
+    /// Classify the problem contained in a file and store it
+    Add {
+        file : String,
+        #[arg(long, default_value_t = 5)]
+        max_steps : usize,
+        #[arg(long, default_value_t = 30)]
+        max_labels : usize,
+    },
+    /// Classify all the problems of a corpus and store them
+    AddCorpus {
+        file : String,
+        #[arg(long, default_value_t = 5)]
+        max_steps : usize,
+        #[arg(long, default_value_t = 30)]
+        max_labels : usize,
+    },
+    /// Print the stored problems satisfying all the conditions, for example --labels<=4 --nontrivial
+    Query {
+        #[arg(allow_hyphen_values = true, trailing_var_arg = true)]
+        conditions : Vec<String>,
+    },
+}
+
+fn database(path : &str, command : DbCommand) -> Result<(), String> {
+    let database = db::Database::open(path)?;
+    match command {
+        DbCommand::Add { file, max_steps, max_labels } => db::add(&database, &file, Budget { max_steps, max_labels }),
+        DbCommand::AddCorpus { file, max_steps, max_labels } => db::add_corpus(&database, &file, Budget { max_steps, max_labels }),
+        DbCommand::Query { conditions } => db::query(&database, &conditions),
+    }
+}
+
+fn colorings(file : &str, min_colors : usize, max_colors : usize, budget : Budget) -> Result<(), String> {
+    let text = std::fs::read_to_string(file).map_err(|e| e.to_string())?;
+    let p = Problem::from_string(text)?;
+    let reductions = p.coloring_reductions(min_colors, max_colors, budget, &mut EventHandler::null())?;
+    println!("no coloring: {:?}", reductions.uncolored);
+    for (c, classification) in &reductions.colored {
+        println!("{} colors: {:?}", c, classification);
+    }
+    match reductions.helpful_up_to {
+        Some(c) => println!("a coloring helps with at most {} colors", c),
+        None => println!("a coloring does not help"),
+    }
+    Ok(())
 }
 
 #[derive(Copy,Clone,Eq,PartialEq)]
@@ -85,10 +198,42 @@ impl BoundRange {
 
 }
 
-fn automatic_upper_bound(p : &Problem, c : Option<usize>, pc : Option<usize>, b_limit : bool, bound : Arc<Mutex<BoundRange>>) {
+/// The file where the requests performed by this run are appended, if `--trace` is given.
+type Trace = Option<Arc<Mutex<File>>>;
+
+/// Runs a request in the same way as the server does, so that it can be replayed, passes to `f` each response, and appends the request to the trace.
+fn traced<F : Fn(Response)>(trace : &Mutex<File>, request : &Request, f : F) -> Result<(), String> {
+    let entry = round_eliminator_lib::trace::request_traced_responses(request, f).map_err(|e| format!("cannot serialize the request: {}", e))?;
+    let mut trace = trace.lock().unwrap();
+    round_eliminator_lib::trace::write_entry(&entry, &mut *trace).map_err(|e| format!("cannot write to the trace file: {}", e))
+}
+
+fn report_trace_error(r : Result<(), String>) {
+    if let Err(e) = r {
+        eprintln!("{}", e);
+    }
+}
+
+fn automatic_upper_bound(p : &Problem, c : Option<usize>, pc : Option<usize>, b_limit : bool, both_sides : bool, bound : Arc<Mutex<BoundRange>>, trace : &Trace) {
     let mut eh = EventHandler::null();
     let max_labels = (p.active.finite_degree()-1) * p.passive.finite_degree() +1 +3;
-    p.autoautoub(b_limit, max_labels, false, 0, false, 0, c, pc, |len,is_trivial,_|{
+    if let Some(trace) = trace {
+        let request = Request::AutoUb(p.clone(), b_limit, max_labels, false, 0, false, 0, c.is_some(), c.unwrap_or(0), pc.is_some(), pc.unwrap_or(0), EvaluationPolicy::Lazy, both_sides, None, None);
+        report_trace_error(traced(trace, &request, |response|{
+            if let Response::AutoUb(len, _, _, summary) = response {
+                if summary.given_coloring {
+                    bound.lock().unwrap().new_ub(Bound::LogStar);
+                } else {
+                    bound.lock().unwrap().new_ub(Bound::Rounds(len));
+                }
+                println!("{}", bound.lock().unwrap());
+                check_exit(bound.clone());
+            }
+        }));
+        return;
+    }
+    let mut stats = SearchStats::default();
+    p.autoautoub(b_limit, max_labels, None, false, 0, false, 0, c, pc, EvaluationPolicy::Lazy, both_sides, &PruningRules::default(), &mut stats, |len,is_trivial,_|{
         if is_trivial {
             bound.lock().unwrap().new_ub(Bound::Rounds(len));
         } else {
@@ -100,18 +245,40 @@ fn automatic_upper_bound(p : &Problem, c : Option<usize>, pc : Option<usize>, b_
 }
 
 
-fn automatic_lower_bound_1(p : &Problem, c : Option<usize>, pc : Option<usize>, bound : Arc<Mutex<BoundRange>>) {
+fn automatic_lower_bound_1(p : &Problem, c : Option<usize>, pc : Option<usize>, strategy : &LbStrategy, both_sides : bool, bound : Arc<Mutex<BoundRange>>, trace : &Trace) {
     let mut eh = EventHandler::null();
-    p.autoautolb(false, 0, false, 0, true, 30, c, pc, |len,_|{
+    if let Some(trace) = trace {
+        let request = Request::AutoLb(p.clone(), false, 0, false, 0, true, 30, c.is_some(), c.unwrap_or(0), pc.is_some(), pc.unwrap_or(0), strategy.clone(), both_sides, None, None);
+        report_trace_error(traced(trace, &request, |response|{
+            if let Response::AutoLb(len, _, _) = response {
+                bound.lock().unwrap().new_lb(Bound::Rounds(len));
+                println!("{}", bound.lock().unwrap());
+                check_exit(bound.clone());
+            }
+        }));
+        return;
+    }
+    p.autoautolb(false, 0, None, false, 0, true, 30, c, pc, strategy.clone(), both_sides, &PruningRules::default(), |len,_|{
         bound.lock().unwrap().new_lb(Bound::Rounds(len));
         println!("{}", bound.lock().unwrap());
         check_exit(bound.clone());
     }, &mut eh);
 }
 
-fn automatic_lower_bound_2(p : &Problem, c : Option<usize>, pc : Option<usize>, bound : Arc<Mutex<BoundRange>>) {
+fn automatic_lower_bound_2(p : &Problem, c : Option<usize>, pc : Option<usize>, strategy : &LbStrategy, both_sides : bool, bound : Arc<Mutex<BoundRange>>, trace : &Trace) {
     let mut eh = EventHandler::null();
-    p.autoautolb(false, 0, true, 100, true, 30, c, pc, |len,_|{
+    if let Some(trace) = trace {
+        let request = Request::AutoLb(p.clone(), false, 0, true, 100, true, 30, c.is_some(), c.unwrap_or(0), pc.is_some(), pc.unwrap_or(0), strategy.clone(), both_sides, None, None);
+        report_trace_error(traced(trace, &request, |response|{
+            if let Response::AutoLb(len, _, _) = response {
+                bound.lock().unwrap().new_lb(Bound::Rounds(len));
+                println!("{}", bound.lock().unwrap());
+                check_exit(bound.clone());
+            }
+        }));
+        return;
+    }
+    p.autoautolb(false, 0, None, true, 100, true, 30, c, pc, strategy.clone(), both_sides, &PruningRules::default(), |len,_|{
         bound.lock().unwrap().new_lb(Bound::Rounds(len));
         println!("{}", bound.lock().unwrap());
         check_exit(bound.clone());
@@ -130,8 +297,20 @@ fn automatic_fixed_point(p : &Problem, c : Option<usize>, pc : Option<usize>, bo
     }
 }
 
-fn marks(p : &Problem, c : Option<usize>, pc : Option<usize>, bound : Arc<Mutex<BoundRange>>) {
+fn marks(p : &Problem, c : Option<usize>, pc : Option<usize>, bound : Arc<Mutex<BoundRange>>, trace : &Trace) {
     let mut eh = EventHandler::null();
+    if let Some(trace) = trace {
+        report_trace_error(traced(trace, &Request::Marks(p.clone()), |response|{
+            if let Response::P(p) = response {
+                if p.marks_works == Some(true) {
+                    bound.lock().unwrap().new_lb(Bound::NonBorel);
+                    println!("{}", bound.lock().unwrap());
+                    check_exit(bound.clone());
+                }
+            }
+        }));
+        return;
+    }
     if p.marks(&mut eh) {
         bound.lock().unwrap().new_lb(Bound::NonBorel);
         println!("{}", bound.lock().unwrap());
@@ -190,7 +369,7 @@ fn speedups_with_fixpoint(p : &Problem, c : Option<usize>, pc : Option<usize>, b
     }
 }
 
-fn automatic_bounds(p : &Problem, c : Option<usize>, pc : Option<usize>) {
+fn automatic_bounds(p : &Problem, c : Option<usize>, pc : Option<usize>, strategy : &LbStrategy, both_sides : bool, trace : &Trace) {
     let bound = Arc::new(Mutex::new(BoundRange::new()));
     thread::scope(|s| {
         let b0 = bound.clone();
@@ -205,21 +384,21 @@ fn automatic_bounds(p : &Problem, c : Option<usize>, pc : Option<usize>) {
 
         /*
         s.spawn(|| {
-            automatic_upper_bound(p,c,pc,false,b1);
+            automatic_upper_bound(p,c,pc,false,both_sides,b1,trace);
         });
         s.spawn(|| {
             just_speedups(p,c,pc,b5);
         });
         if c.is_some() || pc.is_some() {
             s.spawn(|| {
-                automatic_upper_bound(p,None,None,false,b0);
+                automatic_upper_bound(p,None,None,false,both_sides,b0,trace);
             });
         }
         s.spawn(|| {
-            automatic_lower_bound_1(p,c,pc,b2);
+            automatic_lower_bound_1(p,c,pc,strategy,both_sides,b2,trace);
         });
         s.spawn(|| {
-            automatic_lower_bound_2(p,c,pc,b3);
+            automatic_lower_bound_2(p,c,pc,strategy,both_sides,b3,trace);
         });
         s.spawn(|| {
             automatic_fixed_point(p,c,pc,b4);
@@ -230,20 +409,67 @@ fn automatic_bounds(p : &Problem, c : Option<usize>, pc : Option<usize>) {
         });
         
         s.spawn(|| {
-            automatic_upper_bound(p,c,pc,true,b7);
+            automatic_upper_bound(p,c,pc,true,both_sides,b7,trace);
         });
     
          */
     
         s.spawn(|| {
-            marks(p,c,pc,b8);
+            marks(p,c,pc,b8,trace);
         });
     });
 }
 
+/// Returns whether all the requests gave the same results as when they were recorded.
+fn replay(trace : &str) -> Result<bool, String> {
+    let file = File::open(trace).map_err(|e| format!("cannot open the trace file {}: {}", trace, e))?;
+    let outcomes = round_eliminator_lib::trace::replay(std::io::BufReader::new(file), |outcome|{
+        let status = if outcome.matches() { "ok" } else { "MISMATCH" };
+        println!("{} {} ({} ms)", outcome.index, status, outcome.entry.elapsed_ms);
+    })?;
+    Ok(outcomes.iter().all(|outcome| outcome.matches()))
+}
+
+fn open_trace(trace : &str) -> Result<Trace, String> {
+    let file = std::fs::OpenOptions::new().create(true).append(true).open(trace).map_err(|e| format!("cannot open the trace file {}: {}", trace, e))?;
+    Ok(Some(Arc::new(Mutex::new(file))))
+}
+
 fn main() {
     let args = Args::parse();
-    let file = args.file;
+    match args.command {
+        Some(Command::Replay { trace }) => {
+            match replay(&trace) {
+                Ok(true) => {}
+                Ok(false) => std::process::exit(1),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+        Some(Command::Repl { file }) => {
+            repl::repl(file);
+            return;
+        }
+        Some(Command::Db { db, command }) => {
+            if let Err(e) = database(&db, command) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some(Command::Colorings { file, min_colors, max_colors, max_steps, max_labels }) => {
+            if let Err(e) = colorings(&file, min_colors, max_colors, Budget { max_steps, max_labels }) {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+        None => {}
+    }
+    let file = args.file.unwrap();
     let coloring = args.coloring;
     let passive_coloring = args.passive_coloring;
 
@@ -253,6 +479,15 @@ fn main() {
         std::io::read_to_string(std::io::stdin()).unwrap()
     };
     
+    let trace = match args.trace.as_deref().map(open_trace) {
+        Some(Ok(trace)) => trace,
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        None => None,
+    };
+
     let mut problem = Problem::from_string(problem).unwrap();
     println!("{}", problem);
     if let Some(c) = coloring {
@@ -263,5 +498,5 @@ fn main() {
     }
     problem.compute_partial_diagram(&mut EventHandler::null());
     //std::env::set_var("RE_NUM_THREADS", "1");    
-    automatic_bounds(&problem, coloring, passive_coloring);
+    automatic_bounds(&problem, coloring, passive_coloring, &args.lb_strategy.into(), args.both_sides, &trace);
 }
@@ -0,0 +1,247 @@
+use round_eliminator_lib::algorithms::event::EventHandler;
+use round_eliminator_lib::pipeline::Op;
+use round_eliminator_lib::problem::Problem;
+use round_eliminator_lib::session::Session;
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::collections::HashMap;
+
+const COMMANDS : [&str; 17] = ["load", "speedup", "merge", "harden", "rename", "diagram", "trivial", "print", "undo", "fork", "switch", "branches", "compare", "notes", "save", "help", "quit"];
+
+const HELP : &str = "\
+load FILE             read a problem from a file
+speedup               apply round elimination
+merge A B             replace label A with label B
+harden keep X Y Z     keep only the given labels
+harden remove X       remove the given label
+rename                rename labels by generators
+diagram               show the diagram
+trivial               check whether the problem is 0 round solvable
+print                 show the current problem
+undo                  go back to the previous problem
+fork NAME             copy the current branch into a new branch, and switch to it
+switch NAME           continue from the last problem of another branch
+branches              list the branches
+compare               show what each branch obtained
+notes NAME            copy the notes of the last problem of a branch to the current problem
+save FILE             write the current problem to a file
+quit                  exit";
+
+const RED : &str = "\x1b[31m";
+const GREEN : &str = "\x1b[32m";
+const RESET : &str = "\x1b[0m";
+
+/// Completes commands at the beginning of the line, and label names elsewhere.
+struct ReplHelper {
+    labels : Vec<String>,
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start = line[..pos].rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let word = &line[start..pos];
+        let words : Vec<String> = if start == 0 {
+            COMMANDS.iter().map(|c| c.to_string()).collect()
+        } else if line.starts_with("harden") && !line[..start].contains("keep") && !line[..start].contains("remove") {
+            vec!["keep".into(), "remove".into()]
+        } else {
+            self.labels.clone()
+        };
+        let candidates = words.into_iter().filter(|w| w.starts_with(word)).map(|w| Pair { display: w.clone(), replacement: w }).collect();
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+/// The lines that have been removed from and added to each side, colored.
+fn colored_diff(old : &Problem, new : &Problem) -> String {
+    let text = |p : &Problem| {
+        let s = p.to_string();
+        let mut sides = s.split("\n\n").map(|side| side.lines().map(|l| l.to_string()).collect::<Vec<_>>());
+        (sides.next().unwrap_or_default(), sides.next().unwrap_or_default())
+    };
+    let (old_active, old_passive) = text(old);
+    let (new_active, new_passive) = text(new);
+    let mut out = String::new();
+    for (name, old, new) in [("active", old_active, new_active), ("passive", old_passive, new_passive)] {
+        out += &format!("{}:\n", name);
+        for line in &new {
+            if old.contains(line) {
+                out += &format!("  {}\n", line);
+            } else {
+                out += &format!("{}+ {}{}\n", GREEN, line, RESET);
+            }
+        }
+        for line in old.iter().filter(|line| !new.contains(line)) {
+            out += &format!("{}- {}{}\n", RED, line, RESET);
+        }
+    }
+    out
+}
+
+fn diagram_text(p : &Problem, eh : &mut EventHandler) -> String {
+    let mut p = std::borrow::Cow::Borrowed(p);
+    if p.diagram_direct.is_none() {
+        p.to_mut().compute_diagram(eh);
+    }
+    let mapping : HashMap<_,_> = p.mapping_label_text.iter().cloned().collect();
+    let (groups, edges) = p.diagram_direct.as_ref().unwrap();
+    let mut out = String::new();
+    for (l, group) in groups {
+        if group.len() > 1 {
+            let names : Vec<_> = group.iter().map(|x| mapping[x].clone()).collect();
+            out += &format!("{} = {}\n", mapping[l], names.join(" "));
+        }
+    }
+    for (a, b) in edges {
+        out += &format!("{} -> {}\n", mapping[a], mapping[b]);
+    }
+    out
+}
+
+/// Applies an operation, returning the differences with the previous problem.
+fn apply(session : &mut Session, op : Op, eh : &mut EventHandler) -> Result<String, String> {
+    let old = session.current().clone();
+    let new = session.apply(op, eh)?;
+    Ok(colored_diff(&old, new))
+}
+
+/// Executes a command, returning what should be printed.
+fn execute(session : &mut Option<Session>, line : &str, eh : &mut EventHandler) -> Result<String, String> {
+    let words : Vec<&str> = line.split_whitespace().collect();
+    let Some((&command, args)) = words.split_first() else {
+        return Ok(String::new());
+    };
+    // file names may contain spaces, so they are the rest of the line
+    let rest = line.trim().strip_prefix(command).unwrap_or_default().trim();
+    if command == "load" {
+        if rest.is_empty() {
+            return Err("usage: load FILE".into());
+        }
+        let file = rest;
+        let text = std::fs::read_to_string(file).map_err(|e| e.to_string())?;
+        let new = Problem::from_string_with_provenance(&text)?.apply_op(&Op::Cleanup, eh)?;
+        let out = new.to_string();
+        match session {
+            Some(session) => session.load(new),
+            None => *session = Some(Session::new(new)),
+        }
+        return Ok(out);
+    }
+    if command == "help" {
+        return Ok(HELP.into());
+    }
+    let Some(session) = session.as_mut() else {
+        return Err("no problem loaded, use load FILE".into());
+    };
+    let p = session.current();
+    match (command, args) {
+        ("speedup", []) => apply(session, Op::Speedup, eh),
+        ("merge", [from, to]) => apply(session, Op::MergeGroup(vec![from.to_string()], to.to_string()), eh),
+        ("harden", ["keep", labels @ ..]) if !labels.is_empty() => {
+            apply(session, Op::HardenKeep(labels.iter().map(|l| l.to_string()).collect(), true), eh)
+        }
+        ("harden", ["remove", label]) => apply(session, Op::HardenRemove(label.to_string(), true), eh),
+        ("rename", []) => apply(session, Op::RenameGenerators, eh),
+        ("diagram", []) => Ok(diagram_text(p, eh)),
+        ("trivial", []) => {
+            let p = p.apply_op(&Op::ComputeTriviality, eh)?;
+            let trivial = !p.trivial_sets.as_ref().unwrap().is_empty();
+            Ok(if trivial { "0 round solvable" } else { "not 0 round solvable" }.to_string())
+        }
+        ("print", []) => Ok(p.to_string()),
+        ("undo", []) => {
+            let new = p.clone();
+            let old = session.undo()?;
+            Ok(colored_diff(&new, old))
+        }
+        ("fork", [name]) => {
+            session.fork(name)?;
+            Ok(format!("now on branch {}", name))
+        }
+        ("switch", [name]) => {
+            session.switch(name)?;
+            Ok(session.current().to_string())
+        }
+        ("branches", []) => Ok(session
+            .branches()
+            .iter()
+            .map(|b| {
+                let marker = if b.name == session.branch().name { "*" } else { " " };
+                match &b.forked_from {
+                    Some((from, steps)) => format!("{} {} ({} steps, forked from {} after {} steps)", marker, b.name, b.steps.len() - 1, from, steps - 1),
+                    None => format!("{} {} ({} steps)", marker, b.name, b.steps.len() - 1),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")),
+        ("compare", []) => {
+            let mut out = format!("{:<16} {:>6} {:>9} {:>7} {:>7} {:>8}  verdict\n", "branch", "steps", "speedups", "labels", "lines", "trivial");
+            for o in session.compare(eh) {
+                let verdict = o.verdict.map_or("-".to_string(), |v| format!("{:?}", v));
+                out += &format!("{:<16} {:>6} {:>9} {:>7} {:>7} {:>8}  {}\n", o.name, o.steps, o.speedups, o.labels, o.active_lines + o.passive_lines, o.trivial, verdict);
+            }
+            Ok(out)
+        }
+        ("notes", [from]) => {
+            let copied = session.merge_annotations(from)?;
+            Ok(format!("copied {} notes from {}", copied, from))
+        }
+        ("save", [_, ..]) => {
+            std::fs::write(rest, p.to_string_with_provenance()).map_err(|e| e.to_string())?;
+            Ok(format!("saved to {}", rest))
+        }
+        _ => Err(format!("unknown command or wrong arguments: {}, type help for the list of commands", line.trim())),
+    }
+}
+
+pub fn repl(file : Option<String>) {
+    let mut eh = EventHandler::null();
+    let mut session = None;
+    let mut rl : Editor<ReplHelper, DefaultHistory> = Editor::new().unwrap();
+    rl.set_helper(Some(ReplHelper { labels: vec![] }));
+    if let Some(file) = file {
+        match execute(&mut session, &format!("load {}", file), &mut eh) {
+            Ok(out) => println!("{}", out),
+            Err(e) => println!("{}{}{}", RED, e, RESET),
+        }
+    }
+    loop {
+        if let (Some(helper), Some(session)) = (rl.helper_mut(), session.as_ref()) {
+            helper.labels = session.current().mapping_label_text.iter().map(|(_, t)| t.clone()).collect();
+        }
+        match rl.readline("re> ") {
+            Ok(line) => {
+                let _ = rl.add_history_entry(line.as_str());
+                if matches!(line.trim(), "quit" | "exit") {
+                    break;
+                }
+                match execute(&mut session, &line, &mut eh) {
+                    Ok(out) => println!("{}", out),
+                    Err(e) => println!("{}{}{}", RED, e, RESET),
+                }
+            }
+            Err(ReadlineError::Interrupted | ReadlineError::Eof) => break,
+            Err(e) => {
+                println!("{}", e);
+                break;
+            }
+        }
+    }
+}
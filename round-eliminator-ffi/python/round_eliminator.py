@@ -0,0 +1,136 @@
+"""Python bindings for round-eliminator-lib, based on ctypes.
+
+Build the shared library with `cargo build --release` in round-eliminator-ffi, and then:
+
+    from round_eliminator import Problem
+    p = Problem("M U U\nP P P\n\nM UP\nU U")
+    print(p.speedup().speedup())
+"""
+
+import ctypes
+import json
+import os
+import sys
+
+_ABI_VERSION = 1
+
+
+def _load(path=None):
+    if path is None:
+        name = {"darwin": "libround_eliminator_ffi.dylib", "win32": "round_eliminator_ffi.dll"}.get(
+            sys.platform, "libround_eliminator_ffi.so"
+        )
+        here = os.path.dirname(os.path.abspath(__file__))
+        path = os.environ.get("ROUND_ELIMINATOR_LIB", os.path.join(here, "..", "target", "release", name))
+    lib = ctypes.CDLL(path)
+
+    P = ctypes.c_void_p
+    sigs = {
+        "re_abi_version": ([], ctypes.c_uint32),
+        "re_last_error": ([], ctypes.c_char_p),
+        "re_string_free": ([ctypes.c_void_p], None),
+        "re_problem_free": ([P], None),
+        "re_problem_parse": ([ctypes.c_char_p], P),
+        "re_problem_from_json": ([ctypes.c_char_p], P),
+        "re_problem_to_json": ([P], ctypes.c_void_p),
+        "re_problem_to_string": ([P], ctypes.c_void_p),
+        "re_problem_label_count": ([P], ctypes.c_int),
+        "re_speedup": ([P], P),
+        "re_merge_equivalent_labels": ([P], P),
+        "re_harden_keep": ([P, ctypes.POINTER(ctypes.c_uint32), ctypes.c_size_t, ctypes.c_int], P),
+        "re_relax_merge": ([P, ctypes.c_uint32, ctypes.c_uint32], P),
+        "re_is_zero_round_solvable": ([P], ctypes.c_int),
+        "re_coloring_solvability": ([P], ctypes.c_int),
+        "re_classify": ([P, ctypes.c_size_t, ctypes.c_size_t], ctypes.c_void_p),
+        "re_autoub": ([P, ctypes.c_size_t, ctypes.c_size_t, ctypes.c_size_t], ctypes.c_void_p),
+        "re_autolb": ([P, ctypes.c_size_t, ctypes.c_size_t, ctypes.c_size_t], ctypes.c_void_p),
+    }
+    for name, (args, res) in sigs.items():
+        f = getattr(lib, name)
+        f.argtypes = args
+        f.restype = res
+
+    if lib.re_abi_version() != _ABI_VERSION:
+        raise RuntimeError("unsupported version of the round eliminator library")
+    return lib
+
+
+_lib = None
+
+
+def lib():
+    global _lib
+    if _lib is None:
+        _lib = _load()
+    return _lib
+
+
+def _check(ptr):
+    if not ptr:
+        err = lib().re_last_error()
+        raise ValueError(err.decode() if err else "unknown error")
+    return ptr
+
+
+def _string(ptr):
+    _check(ptr)
+    try:
+        return ctypes.cast(ptr, ctypes.c_char_p).value.decode()
+    finally:
+        lib().re_string_free(ptr)
+
+
+class Problem:
+    def __init__(self, text=None, _ptr=None):
+        if _ptr is None:
+            _ptr = _check(lib().re_problem_parse(text.encode()))
+        self._ptr = _ptr
+
+    def __del__(self):
+        if getattr(self, "_ptr", None):
+            lib().re_problem_free(self._ptr)
+            self._ptr = None
+
+    @classmethod
+    def from_json(cls, data):
+        if not isinstance(data, str):
+            data = json.dumps(data)
+        return cls(_ptr=_check(lib().re_problem_from_json(data.encode())))
+
+    def to_json(self):
+        return json.loads(_string(lib().re_problem_to_json(self._ptr)))
+
+    def __str__(self):
+        return _string(lib().re_problem_to_string(self._ptr))
+
+    def label_count(self):
+        return lib().re_problem_label_count(self._ptr)
+
+    def speedup(self):
+        return Problem(_ptr=_check(lib().re_speedup(self._ptr)))
+
+    def merge_equivalent_labels(self):
+        return Problem(_ptr=_check(lib().re_merge_equivalent_labels(self._ptr)))
+
+    def harden_keep(self, labels, add_predecessors=True):
+        labels = list(labels)
+        arr = (ctypes.c_uint32 * len(labels))(*labels)
+        return Problem(_ptr=_check(lib().re_harden_keep(self._ptr, arr, len(labels), int(add_predecessors))))
+
+    def is_zero_round_solvable(self):
+        return lib().re_is_zero_round_solvable(self._ptr) == 1
+
+    def coloring_solvability(self):
+        return lib().re_coloring_solvability(self._ptr)
+
+    def relax_merge(self, from_label, to_label):
+        return Problem(_ptr=_check(lib().re_relax_merge(self._ptr, from_label, to_label)))
+
+    def classify(self, max_steps=5, max_labels=30):
+        return json.loads(_string(lib().re_classify(self._ptr, max_steps, max_labels)))
+
+    def autoub(self, max_labels=10, branching=4, max_steps=10):
+        return json.loads(_string(lib().re_autoub(self._ptr, max_labels, branching, max_steps)))
+
+    def autolb(self, max_labels=10, branching=4, max_steps=10):
+        return json.loads(_string(lib().re_autolb(self._ptr, max_labels, branching, max_steps)))
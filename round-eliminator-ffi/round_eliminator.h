@@ -0,0 +1,46 @@
+/* C interface to round-eliminator-lib, see src/lib.rs for the documentation of each function. */
+
+#ifndef ROUND_ELIMINATOR_H
+#define ROUND_ELIMINATOR_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define RE_ABI_VERSION 1
+
+typedef struct Problem Problem;
+
+uint32_t re_abi_version(void);
+const char *re_last_error(void);
+void re_string_free(char *s);
+void re_problem_free(Problem *p);
+
+Problem *re_problem_parse(const char *text);
+Problem *re_problem_from_json(const char *json);
+char *re_problem_to_json(const Problem *p);
+char *re_problem_to_string(const Problem *p);
+int re_problem_label_count(const Problem *p);
+
+Problem *re_speedup(const Problem *p);
+Problem *re_merge_equivalent_labels(const Problem *p);
+Problem *re_harden_keep(const Problem *p, const uint32_t *labels, size_t len, int add_predecessors);
+Problem *re_relax_merge(const Problem *p, uint32_t from, uint32_t to);
+
+int re_is_zero_round_solvable(const Problem *p);
+int re_coloring_solvability(const Problem *p);
+
+void re_request_json(const char *req, void (*f)(const char *, void *), void *ctx);
+
+char *re_classify(const Problem *p, size_t max_steps, size_t max_labels);
+char *re_autoub(const Problem *p, size_t max_labels, size_t branching, size_t max_steps);
+char *re_autolb(const Problem *p, size_t max_labels, size_t branching, size_t max_steps);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
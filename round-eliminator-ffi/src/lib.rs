@@ -0,0 +1,461 @@
+//! A C interface to round-eliminator-lib.
+//!
+//! Problems are passed around as opaque pointers, that must be released with `re_problem_free`.
+//! Strings returned by the library must be released with `re_string_free`.
+//! Functions that can fail return a null pointer (or a negative number), and the reason can be obtained with `re_last_error`.
+//! This includes internal errors of the library, that are caught before they reach the caller.
+//! The interface is versioned by `RE_ABI_VERSION`: existing functions never change signature within the same version.
+
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    ffi::{CStr, CString},
+    os::raw::{c_char, c_int},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+use round_eliminator_lib::{
+    algorithms::{
+        autolb::LbStrategy,
+        autoub::{EvaluationPolicy, SearchStats},
+        event::EventHandler,
+        pruning_rules::PruningRules,
+    },
+    corpus::{classify, Budget},
+    group::Label,
+    line::Degree,
+    problem::Problem,
+    verdict::Verdict,
+};
+
+pub const RE_ABI_VERSION: u32 = 1;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(e: &str) {
+    LAST_ERROR.with(|l| *l.borrow_mut() = CString::new(e).ok());
+}
+
+/// Runs `f`, and if it panics, sets the last error and returns `on_panic`, since unwinding across the C boundary is undefined behavior.
+fn guarded<T, F: FnOnce() -> T>(on_panic: T, f: F) -> T {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(r) => r,
+        Err(e) => {
+            let message = e
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| e.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown cause".into());
+            set_error(&format!("Internal error: {}", message));
+            on_panic
+        }
+    }
+}
+
+fn to_c_string(s: String) -> *mut c_char {
+    CString::new(s).map_or(ptr::null_mut(), |s| s.into_raw())
+}
+
+unsafe fn from_c_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        set_error("Null string");
+        return None;
+    }
+    let s = CStr::from_ptr(s).to_str().ok();
+    if s.is_none() {
+        set_error("The string is not valid UTF-8");
+    }
+    s
+}
+
+unsafe fn problem<'a>(p: *const Problem) -> Option<&'a Problem> {
+    if p.is_null() {
+        set_error("Null problem");
+    }
+    p.as_ref()
+}
+
+fn boxed(p: Problem) -> *mut Problem {
+    Box::into_raw(Box::new(p))
+}
+
+/// Computes the diagram, removes useless labels and configurations, and sorts the active lines, as done by the GUI after each operation.
+fn simplify(p: &mut Problem, eh: &mut EventHandler) {
+    if p.passive.degree == Degree::Finite(2) {
+        p.diagram_indirect = None;
+        p.compute_diagram(eh);
+        p.discard_useless_stuff(true, eh);
+    } else {
+        p.discard_useless_stuff(false, eh);
+    }
+    p.sort_active_by_strength();
+}
+
+#[no_mangle]
+pub extern "C" fn re_abi_version() -> u32 {
+    RE_ABI_VERSION
+}
+
+/// Returns the message of the last error that happened in the current thread, or null.
+/// The returned string is owned by the library and is valid until the next call that fails.
+#[no_mangle]
+pub extern "C" fn re_last_error() -> *const c_char {
+    LAST_ERROR.with(|l| l.borrow().as_ref().map_or(ptr::null(), |s| s.as_ptr()))
+}
+
+/// # Safety
+/// `s` must be null or a string returned by this library, that has not been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn re_string_free(s: *mut c_char) {
+    guarded((), || {
+        if !s.is_null() {
+            drop(CString::from_raw(s));
+        }
+    })
+}
+
+/// # Safety
+/// `p` must be null or a problem returned by this library, that has not been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn re_problem_free(p: *mut Problem) {
+    guarded((), || {
+        if !p.is_null() {
+            drop(Box::from_raw(p));
+        }
+    })
+}
+
+/// Parses a problem written in the usual text format (active lines, an empty line, passive lines).
+///
+/// # Safety
+/// `text` must be a valid null terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn re_problem_parse(text: *const c_char) -> *mut Problem {
+    guarded(ptr::null_mut(), || {
+        let text = match from_c_str(text) {
+            Some(text) => text,
+            None => return ptr::null_mut(),
+        };
+        match Problem::from_string(text) {
+            Ok(mut p) => {
+                simplify(&mut p, &mut EventHandler::null());
+                boxed(p)
+            }
+            Err(e) => {
+                set_error(e);
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Parses a problem in the JSON format used by the server.
+///
+/// # Safety
+/// `json` must be a valid null terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn re_problem_from_json(json: *const c_char) -> *mut Problem {
+    guarded(ptr::null_mut(), || {
+        let json = match from_c_str(json) {
+            Some(json) => json,
+            None => return ptr::null_mut(),
+        };
+        match serde_json::from_str(json) {
+            Ok(p) => boxed(p),
+            Err(_) => {
+                set_error("Invalid problem");
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// # Safety
+/// `p` must be a valid problem.
+#[no_mangle]
+pub unsafe extern "C" fn re_problem_to_json(p: *const Problem) -> *mut c_char {
+    guarded(ptr::null_mut(), || {
+        match problem(p) {
+            Some(p) => match serde_json::to_string(p) {
+                Ok(json) => to_c_string(json),
+                Err(_) => {
+                    set_error("Cannot serialize the problem");
+                    ptr::null_mut()
+                }
+            },
+            None => ptr::null_mut(),
+        }
+    })
+}
+
+/// # Safety
+/// `p` must be a valid problem.
+#[no_mangle]
+pub unsafe extern "C" fn re_problem_to_string(p: *const Problem) -> *mut c_char {
+    guarded(ptr::null_mut(), || {
+        match problem(p) {
+            Some(p) => to_c_string(format!("{}", p)),
+            None => ptr::null_mut(),
+        }
+    })
+}
+
+/// Returns the number of labels of the problem, or -1 on error.
+///
+/// # Safety
+/// `p` must be a valid problem.
+#[no_mangle]
+pub unsafe extern "C" fn re_problem_label_count(p: *const Problem) -> c_int {
+    guarded(-1, || {
+        problem(p).map_or(-1, |p| p.labels().len() as c_int)
+    })
+}
+
+/// # Safety
+/// `p` must be a valid problem.
+#[no_mangle]
+pub unsafe extern "C" fn re_speedup(p: *const Problem) -> *mut Problem {
+    guarded(ptr::null_mut(), || {
+        let p = match problem(p) {
+            Some(p) => p,
+            None => return ptr::null_mut(),
+        };
+        let eh = &mut EventHandler::null();
+        let mut p = p.clone();
+        if p.diagram_indirect.is_none() {
+            p.compute_partial_diagram(eh);
+        }
+        let mut new = p.speedup(eh);
+        simplify(&mut new, eh);
+        boxed(new)
+    })
+}
+
+/// # Safety
+/// `p` must be a valid problem.
+#[no_mangle]
+pub unsafe extern "C" fn re_merge_equivalent_labels(p: *const Problem) -> *mut Problem {
+    guarded(ptr::null_mut(), || {
+        let p = match problem(p) {
+            Some(p) => p,
+            None => return ptr::null_mut(),
+        };
+        let mut new = p.merge_equivalent_labels();
+        simplify(&mut new, &mut EventHandler::null());
+        boxed(new)
+    })
+}
+
+/// Keeps only the given labels. If `add_predecessors` is nonzero, labels that become useless are replaced by their predecessors in the diagram.
+///
+/// # Safety
+/// `p` must be a valid problem, and `labels` must point to `len` labels.
+#[no_mangle]
+pub unsafe extern "C" fn re_harden_keep(
+    p: *const Problem,
+    labels: *const Label,
+    len: usize,
+    add_predecessors: c_int,
+) -> *mut Problem {
+    guarded(ptr::null_mut(), || {
+        let p = match problem(p) {
+            Some(p) => p,
+            None => return ptr::null_mut(),
+        };
+        if labels.is_null() && len > 0 {
+            set_error("Null labels");
+            return ptr::null_mut();
+        }
+        let keep: HashSet<Label> = if len == 0 {
+            HashSet::new()
+        } else {
+            std::slice::from_raw_parts(labels, len).iter().cloned().collect()
+        };
+        let eh = &mut EventHandler::null();
+        let mut p = p.clone();
+        if add_predecessors != 0 && p.diagram_indirect.is_none() {
+            p.compute_partial_diagram(eh);
+        }
+        let mut new = p.harden_keep(&keep, add_predecessors != 0);
+        simplify(&mut new, eh);
+        boxed(new)
+    })
+}
+
+/// Relaxes the problem by replacing the label `from` with the label `to` everywhere.
+///
+/// # Safety
+/// `p` must be a valid problem.
+#[no_mangle]
+pub unsafe extern "C" fn re_relax_merge(p: *const Problem, from: Label, to: Label) -> *mut Problem {
+    guarded(ptr::null_mut(), || {
+        let p = match problem(p) {
+            Some(p) => p,
+            None => return ptr::null_mut(),
+        };
+        let labels = p.labels();
+        if !labels.contains(&from) || !labels.contains(&to) {
+            set_error("The problem does not contain the given labels");
+            return ptr::null_mut();
+        }
+        let mut new = p.relax_merge(from, to);
+        simplify(&mut new, &mut EventHandler::null());
+        boxed(new)
+    })
+}
+
+/// Returns 1 if the problem can be solved in 0 rounds, 0 if it cannot, and -1 on error.
+///
+/// # Safety
+/// `p` must be a valid problem.
+#[no_mangle]
+pub unsafe extern "C" fn re_is_zero_round_solvable(p: *const Problem) -> c_int {
+    guarded(-1, || {
+        let p = match problem(p) {
+            Some(p) => p,
+            None => return -1,
+        };
+        let mut p = p.clone();
+        p.trivial_sets = None;
+        p.compute_triviality(&mut EventHandler::null());
+        (!p.trivial_sets.unwrap().is_empty()) as c_int
+    })
+}
+
+/// Returns the largest number of colors c such that the problem can be solved in 0 rounds given a c-coloring, or -1 on error.
+///
+/// # Safety
+/// `p` must be a valid problem.
+#[no_mangle]
+pub unsafe extern "C" fn re_coloring_solvability(p: *const Problem) -> c_int {
+    guarded(-1, || {
+        let p = match problem(p) {
+            Some(p) => p,
+            None => return -1,
+        };
+        let mut p = p.clone();
+        p.coloring_sets = None;
+        p.compute_coloring_solvability(&mut EventHandler::null());
+        p.coloring_sets.unwrap().len() as c_int
+    })
+}
+
+/// Runs a request of the JSON api, calling `f` for each response. `ctx` is passed back to `f` unchanged.
+/// The response strings are valid only during the call to `f`.
+///
+/// # Safety
+/// `req` must be a valid null terminated string, and `f` a valid function pointer.
+#[no_mangle]
+pub unsafe extern "C" fn re_request_json(
+    req: *const c_char,
+    f: extern "C" fn(*const c_char, *mut std::ffi::c_void),
+    ctx: *mut std::ffi::c_void,
+) {
+    guarded((), || {
+        let req = match from_c_str(req) {
+            Some(req) => req,
+            None => return,
+        };
+        round_eliminator_lib::serial::request_json(req, |s, send_to_client| {
+            if send_to_client {
+                if let Ok(s) = CString::new(s) {
+                    f(s.as_ptr(), ctx);
+                }
+            }
+        });
+    })
+}
+
+fn verdict_to_c_string(verdict: Verdict) -> *mut c_char {
+    match serde_json::to_string(&verdict) {
+        Ok(json) => to_c_string(json),
+        Err(_) => {
+            set_error("Cannot serialize the verdict");
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Classifies the problem by applying at most `max_steps` speedups, stopping if a problem has more than `max_labels` labels.
+/// Returns the `Verdict` in the JSON format used by the server, for example `{"ProvedRounds":2}`.
+///
+/// # Safety
+/// `p` must be a valid problem.
+#[no_mangle]
+pub unsafe extern "C" fn re_classify(p: *const Problem, max_steps: usize, max_labels: usize) -> *mut c_char {
+    guarded(ptr::null_mut(), || {
+        let p = match problem(p) {
+            Some(p) => p,
+            None => return ptr::null_mut(),
+        };
+        let classification = classify(p, Budget { max_steps, max_labels }, &mut EventHandler::null());
+        verdict_to_c_string(classification.into())
+    })
+}
+
+/// Searches for an upper bound as the automatic upper bound of the GUI does, and returns the best `Verdict` found, in JSON.
+///
+/// # Safety
+/// `p` must be a valid problem.
+#[no_mangle]
+pub unsafe extern "C" fn re_autoub(p: *const Problem, max_labels: usize, branching: usize, max_steps: usize) -> *mut c_char {
+    guarded(ptr::null_mut(), || {
+        let p = match problem(p) {
+            Some(p) => p,
+            None => return ptr::null_mut(),
+        };
+        let mut stats = SearchStats::default();
+        let verdict = p.autoautoub(
+            true,
+            max_labels,
+            None,
+            true,
+            branching,
+            true,
+            max_steps,
+            None,
+            None,
+            EvaluationPolicy::default(),
+            false,
+            &PruningRules::default(),
+            &mut stats,
+            |_, _, _| {},
+            &mut EventHandler::null(),
+        );
+        verdict_to_c_string(verdict)
+    })
+}
+
+/// Searches for a lower bound as the automatic lower bound of the GUI does, and returns the best `Verdict` found, in JSON.
+///
+/// # Safety
+/// `p` must be a valid problem.
+#[no_mangle]
+pub unsafe extern "C" fn re_autolb(p: *const Problem, max_labels: usize, branching: usize, max_steps: usize) -> *mut c_char {
+    guarded(ptr::null_mut(), || {
+        let p = match problem(p) {
+            Some(p) => p,
+            None => return ptr::null_mut(),
+        };
+        let verdict = p.autoautolb(
+            true,
+            max_labels,
+            None,
+            true,
+            branching,
+            true,
+            max_steps,
+            None,
+            None,
+            LbStrategy::default(),
+            false,
+            &PruningRules::default(),
+            |_, _| {},
+            &mut EventHandler::null(),
+        );
+        verdict_to_c_string(verdict)
+    })
+}
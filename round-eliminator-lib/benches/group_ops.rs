@@ -0,0 +1,91 @@
+//! Microbenchmarks of the group operations on groups of a problem with 256 labels, comparing the bitset path with the scalar one,
+//! first on groups containing half of the labels, and then on groups of each size, to choose `BITSET_MIN_LEN`.
+//! For `is_superset`, half of the pairs are made of a group and one of its subsets, so that the merge does not stop early.
+//! Run with `cargo bench --bench group_ops`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use round_eliminator_lib::group::Group;
+
+const LABELS: u32 = 256;
+const GROUPS: usize = 200;
+
+/// Groups containing each of the 256 labels with probability `1/2^density`, generated with a fixed seed.
+fn groups(density: u32) -> Vec<Group> {
+    let mut seed: u64 = 42;
+    (0..GROUPS)
+        .map(|_| {
+            Group(
+                (0..LABELS)
+                    .filter(|_| {
+                        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                        seed >> (64 - density) == 0
+                    })
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+/// Groups with exactly `len` labels, and for each of them a subset with half of the labels.
+fn groups_of_len(len: usize) -> Vec<Group> {
+    let mut seed: u64 = 7;
+    let mut groups = vec![];
+    while groups.len() < GROUPS {
+        let mut labels: Vec<u32> = vec![];
+        while labels.len() < len {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let l = (seed >> 33) as u32 % LABELS;
+            if !labels.contains(&l) {
+                labels.push(l);
+            }
+        }
+        labels.sort_unstable();
+        groups.push(Group(labels.iter().cloned().step_by(2).collect()));
+        groups.push(Group(labels));
+    }
+    groups
+}
+
+fn time<T>(groups: &[Group], f: impl Fn(&Group, &Group) -> T) -> Duration {
+    let start = Instant::now();
+    for g1 in groups {
+        for g2 in groups {
+            black_box(f(black_box(g1), black_box(g2)));
+        }
+    }
+    start.elapsed()
+}
+
+fn compare<T, U>(name: &str, groups: &[Group], fast: impl Fn(&Group, &Group) -> T, scalar: impl Fn(&Group, &Group) -> U) {
+    let fast = time(groups, fast);
+    let scalar = time(groups, scalar);
+    println!(
+        "{:<14} bitset {:>10.2?}   scalar {:>10.2?}   speedup {:.1}x",
+        name,
+        fast,
+        scalar,
+        scalar.as_secs_f64() / fast.as_secs_f64()
+    );
+}
+
+fn main() {
+    let groups = groups(1);
+    compare("intersection", &groups, |a, b| a.intersection(b), |a, b| a.intersection_scalar(b));
+    compare("union", &groups, |a, b| a.union(b), |a, b| a.union_scalar(b));
+    compare("difference", &groups, |a, b| a.difference(b), |a, b| a.difference_scalar(b));
+    let mut with_subsets = groups_of_len(128);
+    with_subsets.truncate(GROUPS / 2);
+    with_subsets.extend(groups.into_iter().take(GROUPS / 2));
+    compare("is_superset", &with_subsets, |a, b| a.is_superset_bitset(b), |a, b| a.is_superset(b));
+
+    for len in [2, 4, 6, 8, 12, 16, 32, 64] {
+        let groups = groups_of_len(len);
+        println!("groups of {} labels", len);
+        compare("  intersection", &groups, |a, b| a.intersection(b), |a, b| a.intersection_scalar(b));
+        compare("  union", &groups, |a, b| a.union(b), |a, b| a.union_scalar(b));
+        compare("  difference", &groups, |a, b| a.difference(b), |a, b| a.difference_scalar(b));
+        compare("  is_superset", &groups, |a, b| a.is_superset_bitset(b), |a, b| a.is_superset(b));
+    }
+}
@@ -0,0 +1,64 @@
+//! Benchmark of `Problem::optimize_internal_layout` on a problem with 300 labels, whose labels are numbered so that
+//! the labels used together are far apart. It compares the time needed to maximize the passive side, and to discard
+//! the non-maximal lines of the active side, with and without optimizing the layout first. Run with `cargo bench --bench layout`.
+
+use std::time::{Duration, Instant};
+
+use round_eliminator_lib::{algorithms::event::EventHandler, problem::Problem};
+
+const CLUSTERS: usize = 30;
+const CLUSTER_SIZE: usize = 10;
+
+/// The label of the i-th element of cluster c. Labels are numbered by first appearance, so the names are
+/// chosen such that the first line mentions one label of each cluster, and labels of the same cluster get far apart indices.
+fn name(c: usize, i: usize) -> String {
+    format!("(l{}_{})", c, i)
+}
+
+fn problem() -> Problem {
+    let mut seed: u64 = 7;
+    let mut random = |n: usize| {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (seed >> 33) as usize % n
+    };
+    // a line that mentions all the labels, ordered by element first, so that consecutive indices belong to different clusters
+    let all = (0..CLUSTER_SIZE).flat_map(|i| (0..CLUSTERS).map(move |c| name(c, i))).collect::<String>();
+    let mut active = vec![format!("{} {} {}", all, all, all)];
+    let mut passive = vec![];
+    for c in 0..CLUSTERS {
+        for _ in 0..3 {
+            let group = |random: &mut dyn FnMut(usize) -> usize| (0..CLUSTER_SIZE).filter(|_| random(2) == 0).map(|i| name(c, i)).collect::<String>();
+            let (a, b, d) = (group(&mut random), group(&mut random), group(&mut random));
+            if !a.is_empty() && !b.is_empty() && !d.is_empty() {
+                active.push(format!("{} {} {}", a, b, d));
+                passive.push(format!("{} {}", a, b));
+            }
+        }
+    }
+    Problem::from_string(format!("{}\n\n{}", active.join("\n"), passive.join("\n"))).unwrap()
+}
+
+fn time(p: &Problem) -> (Duration, Duration) {
+    let eh = &mut EventHandler::null();
+    let mut passive = p.passive.clone();
+    let start = Instant::now();
+    passive.maximize(eh);
+    let maximize = start.elapsed();
+    let mut active = p.active.clone();
+    let start = Instant::now();
+    for _ in 0..20 {
+        active.discard_non_maximal_lines();
+    }
+    (maximize, start.elapsed())
+}
+
+fn main() {
+    let p = problem();
+    let mut optimized = p.clone();
+    optimized.optimize_internal_layout();
+    let (maximize, discard) = time(&p);
+    let (maximize_optimized, discard_optimized) = time(&optimized);
+    println!("{} labels", p.labels().len());
+    println!("maximize passive   original {:>10.2?}   optimized {:>10.2?}", maximize, maximize_optimized);
+    println!("discard non-max    original {:>10.2?}   optimized {:>10.2?}", discard, discard_optimized);
+}
@@ -0,0 +1,77 @@
+//! Benchmark of `Constraint::maximize`, which compares each candidate line only with the lines whose labels are a subset
+//! or a superset of its labels, against `Constraint::maximize_custom` with the same operations on groups, which compares
+//! each candidate with all the lines found so far. The constraints are the passive sides of problems obtained by speedups.
+//! It also runs `Constraint::maximize_custom` with the scalar versions of the group operations, which merge the sorted labels
+//! instead of using bitsets, to measure the gain of the bitsets on a whole maximization. Run with `cargo bench --bench maximize`.
+
+use std::time::{Duration, Instant};
+
+use round_eliminator_lib::{algorithms::{event::EventHandler, limits::LimitGuard}, constraint::Constraint, group::Group, problem::Problem};
+
+fn problems() -> Vec<(&'static str, Problem)> {
+    let speedups = |s: &str, n: usize| {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string(s).unwrap();
+        for _ in 0..n {
+            p = p.speedup(eh);
+        }
+        p
+    };
+    vec![
+        ("3-coloring, degree 3, 2 speedups", speedups("A A A\nB B B\nC C C\n\nA BC\nB AC\nC AB", 2)),
+        ("4-coloring, degree 3, 1 speedup", speedups("A A A\nB B B\nC C C\nD D D\n\nA BCD\nB ACD\nC ABD\nD ABC", 1)),
+        ("3-coloring, degree 4, 1 speedup", speedups("A A A A\nB B B B\nC C C C\n\nA BC\nB AC\nC AB", 1)),
+        ("maximal matching, degree 3, 3 speedups", speedups("M O O\nP P P\n\nM UP\nO UO\nP UO\nU U", 3)),
+        ("maximal matching, degree 3, 4 speedups", speedups("M O O\nP P P\n\nM UP\nO UO\nP UO\nU U", 4)),
+    ]
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Indexed,
+    AllLines,
+    ScalarGroups,
+}
+
+/// The best of 5 runs, and the number of lines of the result.
+fn time(c: &Constraint, mode: Mode) -> (Duration, usize) {
+    let eh = &mut EventHandler::null();
+    let mut best = Duration::MAX;
+    let mut lines = 0;
+    for _ in 0..5 {
+        let mut c = c.clone();
+        let start = Instant::now();
+        match mode {
+            Mode::Indexed => c.maximize(eh),
+            Mode::AllLines => {
+                let f_is_superset = |g1: &Group, g2: &Group| g1.is_superset(g2);
+                let f_union = |g1: &Group, g2: &Group| g1.union(g2);
+                let f_intersection = |g1: &Group, g2: &Group| g1.intersection(g2);
+                c.maximize_custom(eh, false, false, None, &LimitGuard::unlimited(), f_is_superset, f_union, f_intersection).unwrap();
+            }
+            Mode::ScalarGroups => {
+                let f_is_superset = |g1: &Group, g2: &Group| g1.is_superset_scalar(g2);
+                let f_union = |g1: &Group, g2: &Group| g1.union_scalar(g2);
+                let f_intersection = |g1: &Group, g2: &Group| g1.intersection_scalar(g2);
+                c.maximize_custom(eh, false, false, None, &LimitGuard::unlimited(), f_is_superset, f_union, f_intersection).unwrap();
+            }
+        }
+        best = best.min(start.elapsed());
+        lines = c.lines.len();
+    }
+    (best, lines)
+}
+
+fn main() {
+    for (name, p) in problems() {
+        let (scalar, scalar_lines) = time(&p.passive, Mode::ScalarGroups);
+        let (unindexed, lines) = time(&p.passive, Mode::AllLines);
+        let (indexed, indexed_lines) = time(&p.passive, Mode::Indexed);
+        assert_eq!(lines, indexed_lines);
+        assert_eq!(lines, scalar_lines);
+        println!(
+            "{:<45} {:>5} lines   scalar groups {:>10.2?}   all lines {:>10.2?}   indexed {:>10.2?}",
+            name, lines, scalar, unindexed, indexed
+        );
+    }
+}
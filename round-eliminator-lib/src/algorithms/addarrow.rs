@@ -0,0 +1,43 @@
+use crate::{group::Label, line::Degree, problem::Problem};
+
+impl Problem {
+    /// Returns all pairs (a,b) of distinct labels such that b is not reachable from a in the diagram,
+    /// that is, all the arrows that could be added with `relax_addarrow`.
+    pub fn possible_addarrow(&self) -> Result<Vec<(Label, Label)>, &'static str> {
+        Ok(self.reachability()?.unreachable_pairs())
+    }
+
+    /// Returns the pairs of `possible_addarrow` for which the passive side already allows b in every configuration where a is allowed.
+    /// This can happen when the diagram has been computed only partially.
+    /// Adding these arrows gives an equivalent problem, so they can be applied freely before merging labels.
+    pub fn safe_addarrow(&self) -> Result<Vec<(Label, Label)>, &'static str> {
+        if !self.passive.is_maximized && self.passive.degree != Degree::Finite(2) {
+            return Err("Safe arrows can be computed only if the passive side is maximized or has degree 2");
+        }
+        Ok(self
+            .possible_addarrow()?
+            .into_iter()
+            .filter(|&(a, b)| self.passive.is_diagram_predecessor(a, b))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    #[test]
+    fn safe_addarrow() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("A B\nC C\n\nA AC\nB BC").unwrap();
+        p.compute_diagram(eh);
+        assert_eq!(p.possible_addarrow().unwrap(), vec![(0, 1), (0, 2), (1, 0), (1, 2), (2, 0), (2, 1)]);
+        assert_eq!(p.safe_addarrow().unwrap(), vec![]);
+
+        let mut p = Problem::from_string("A B C\n\nA C\nB BC").unwrap();
+        p.compute_partial_diagram(eh);
+        assert!(p.possible_addarrow().unwrap().contains(&(0, 1)));
+        assert_eq!(p.safe_addarrow().unwrap(), vec![(0, 1)]);
+    }
+}
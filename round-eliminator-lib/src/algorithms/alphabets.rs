@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use crate::{group::Label, problem::Problem, store::Side};
+
+const ACTIVE_PREFIX: &str = "# active only:";
+const PASSIVE_PREFIX: &str = "# passive only:";
+
+fn other(side: Side) -> Side {
+    match side {
+        Side::Active => Side::Passive,
+        Side::Passive => Side::Active,
+    }
+}
+
+impl Problem {
+    /// Parses a problem where the labels of `active_only` may appear only on the active side, and the labels of `passive_only`
+    /// may appear only on the passive side, see `set_one_sided_labels`.
+    pub fn from_string_with_alphabets<S: AsRef<str>>(s: S, active_only: &[&str], passive_only: &[&str]) -> Result<Self, &'static str> {
+        let mut p = Problem::from_string(s)?;
+        let mapping: HashMap<&str, Label> = p.mapping_label_text.iter().map(|(l, t)| (t.as_str(), *l)).collect();
+        let mut labels = vec![];
+        for (names, side) in [(active_only, Side::Active), (passive_only, Side::Passive)] {
+            for name in names {
+                let label = *mapping.get(name).ok_or("A label of an alphabet does not appear in the problem")?;
+                labels.push((label, side));
+            }
+        }
+        p.set_one_sided_labels(&labels)?;
+        Ok(p)
+    }
+
+    /// Same as `from_string`, but also reads the labels that may appear only on one side, written by `to_string_with_alphabets`
+    /// after the passive side as lines such as `# active only: X Y` and `# passive only: Z`, with the names separated by spaces.
+    pub fn from_string_with_alphabet_comments(text: &str) -> Result<Self, &'static str> {
+        let names = |prefix: &str| -> Vec<&str> {
+            text.lines().filter_map(|line| line.strip_prefix(prefix)).flat_map(|rest| rest.split_whitespace()).collect()
+        };
+        Problem::from_string_with_alphabets(text, &names(ACTIVE_PREFIX), &names(PASSIVE_PREFIX))
+    }
+
+    /// The text of the problem followed by its one-sided labels, as comments after the passive side, that `from_string` ignores.
+    pub fn to_string_with_alphabets(&self) -> String {
+        let mut s = self.to_string();
+        if self.one_sided_labels.is_some() {
+            s.push('\n');
+            for (side, prefix) in [(Side::Active, ACTIVE_PREFIX), (Side::Passive, PASSIVE_PREFIX)] {
+                let names = self.mapping_label_text.iter().filter(|(l, _)| self.side_of_label(*l) == Some(side)).map(|(_, t)| t).join(" ");
+                if !names.is_empty() {
+                    s.push_str(&format!("{} {}\n", prefix, names));
+                }
+            }
+        }
+        s
+    }
+
+    /// Declares that each of the given labels may appear only on the given side, as required by encodings that use distinct
+    /// alphabets for the two sides. Fails if some label already appears on the other side.
+    /// After a speedup the sides are exchanged, and the new labels that are sets of labels of the passive side are labels of the active side.
+    /// Relaxations and hardenings keep the labels that are still one-sided, see `one_sided_labels_after_change`.
+    pub fn set_one_sided_labels(&mut self, labels: &[(Label, Side)]) -> Result<(), &'static str> {
+        self.check_labels(&labels.iter().map(|(l, _)| *l).collect::<Vec<_>>())?;
+        if labels.iter().map(|(l, _)| l).unique().count() != labels.len() {
+            return Err("A label is given for more than one side");
+        }
+        let mut p = self.clone();
+        p.one_sided_labels = if labels.is_empty() { None } else { Some(labels.iter().cloned().sorted().collect()) };
+        p.check_one_sided_labels()?;
+        *self = p;
+        Ok(())
+    }
+
+    /// Checks that the labels that may appear only on one side do not appear on the other side.
+    pub fn check_one_sided_labels(&self) -> Result<(), &'static str> {
+        let active = self.active.labels_appearing();
+        let passive = self.passive.labels_appearing();
+        for &(l, side) in self.one_sided_labels.iter().flatten() {
+            match side {
+                Side::Active if passive.contains(&l) => return Err("A label of the active alphabet appears on the passive side"),
+                Side::Passive if active.contains(&l) => return Err("A label of the passive alphabet appears on the active side"),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// The side to which the label is restricted, if any.
+    pub fn side_of_label(&self, label: Label) -> Option<Side> {
+        self.one_sided_labels.iter().flatten().find(|(l, _)| *l == label).map(|(_, side)| *side)
+    }
+
+    pub(crate) fn one_sided_labels_swapped(&self) -> Option<Vec<(Label, Side)>> {
+        self.one_sided_labels.as_ref().map(|v| v.iter().map(|&(l, side)| (l, other(side))).collect())
+    }
+
+    /// The one-sided labels of this problem that are still labels of the problem and still appear only on their side, for a problem
+    /// obtained by an operation that keeps the one-sided labels of the original problem. Hardening may remove labels, and relaxing
+    /// may make a label appear on the other side, for example when a label of both sides is merged into it.
+    pub(crate) fn one_sided_labels_after_change(&self) -> Option<Vec<(Label, Side)>> {
+        let active = self.active.labels_appearing();
+        let passive = self.passive.labels_appearing();
+        let labels: Vec<(Label, Side)> = self
+            .one_sided_labels
+            .iter()
+            .flatten()
+            .filter(|&&(l, side)| match side {
+                Side::Active => active.contains(&l) && !passive.contains(&l),
+                Side::Passive => passive.contains(&l) && !active.contains(&l),
+            })
+            .cloned()
+            .collect();
+        if labels.is_empty() {
+            None
+        } else {
+            Some(labels)
+        }
+    }
+
+    /// The one-sided labels of `new`, obtained from this problem with a speedup. The labels that appear only on the active side
+    /// are not used by the maximized passive side, so they disappear, while the new labels that are sets of labels of the passive side
+    /// cannot be used by the old active side, so they appear only on the new active side.
+    pub(crate) fn one_sided_labels_after_speedup(&self, new: &Problem) -> Option<Vec<(Label, Side)>> {
+        self.one_sided_labels.as_ref()?;
+        let labels: Vec<(Label, Side)> = new
+            .mapping_label_oldlabels
+            .iter()
+            .flatten()
+            .filter(|(_, old)| !old.is_empty() && old.iter().all(|&l| self.side_of_label(l) == Some(Side::Passive)))
+            .map(|(l, _)| (*l, Side::Active))
+            .sorted()
+            .collect();
+        if labels.is_empty() {
+            None
+        } else {
+            Some(labels)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem, store::Side};
+
+    #[test]
+    fn alphabets() {
+        let eh = &mut EventHandler::null();
+        let text = "A A X\nB B X\n\nA B\nX Y\nY Y";
+        assert!(Problem::from_string_with_alphabets(text, &["X"], &["A"]).is_err());
+        assert!(Problem::from_string_with_alphabets(text, &["Z"], &[]).is_err());
+        assert!(Problem::from_string_with_alphabets(text, &["X"], &["X"]).is_err());
+        let p = Problem::from_string_with_alphabets(text, &[], &["Y"]).unwrap();
+        assert_eq!(p.one_sided_labels, Some(vec![(3, Side::Passive)]));
+        assert_eq!(p.swap_sides().side_of_label(3), Some(Side::Active));
+
+        let sped = p.speedup(eh);
+        sped.check_one_sided_labels().unwrap();
+        let old = sped.mapping_label_oldlabels.clone().unwrap();
+        for (l, old) in old {
+            let expected = (old == vec![3]).then_some(Side::Active);
+            assert_eq!(sped.side_of_label(l), expected);
+        }
+
+        let mut renamed = sped.clone();
+        let labels = renamed.labels();
+        renamed.relabel(&labels.iter().map(|&l| (l, labels.len() as u32 - 1 - l)).collect());
+        renamed.check_one_sided_labels().unwrap();
+
+        // merging a label of both sides into a one-sided label makes it appear on both sides
+        let relaxed = p.relax_merge(0, 3);
+        relaxed.check_one_sided_labels().unwrap();
+        assert_eq!(relaxed.one_sided_labels, None);
+        let relaxed = p.relax_merge(3, 2);
+        assert_eq!(relaxed.one_sided_labels, None);
+        let hardened = p.harden_remove(3, false);
+        assert_eq!(hardened.one_sided_labels, None);
+
+        let p = Problem::from_string_with_alphabets("A A X\nB B X\n\nA B\nY Y", &["X"], &["Y"]).unwrap();
+        let written = p.to_string_with_alphabets();
+        assert!(written.contains("# active only: X\n# passive only: Y\n"));
+        let read = Problem::from_string_with_alphabet_comments(&written).unwrap();
+        assert_eq!(read.to_string_with_alphabets(), written);
+        assert_eq!(Problem::from_string_with_alphabet_comments(text).unwrap().one_sided_labels, None);
+
+        let mut q = Problem::from_string(text).unwrap();
+        assert!(q.set_one_sided_labels(&[(2, Side::Passive)]).is_err());
+        assert!(q.one_sided_labels.is_none());
+    }
+}
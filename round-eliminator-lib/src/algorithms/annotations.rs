@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{group::Label, line::Line, problem::Problem, store::Side};
+
+/// What a note is about.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+pub enum AnnotationTarget {
+    /// The step that produced the problem, for example why a merge was chosen.
+    /// It is not carried over to problems derived from this one.
+    Step,
+    Label(Label),
+    Line(Side, Line),
+}
+
+impl Problem {
+    /// Attaches a note to the given target, replacing the previous one. An empty note removes it.
+    pub fn annotate(&mut self, target: AnnotationTarget, note: String) {
+        let annotations = self.annotations.get_or_insert_with(Vec::new);
+        annotations.retain(|(t, _)| t != &target);
+        if !note.is_empty() {
+            annotations.push((target, note));
+            annotations.sort();
+        }
+        if annotations.is_empty() {
+            self.annotations = None;
+        }
+    }
+
+    pub fn annotation(&self, target: &AnnotationTarget) -> Option<&str> {
+        self.annotations
+            .as_ref()?
+            .iter()
+            .find(|(t, _)| t == target)
+            .map(|(_, note)| note.as_str())
+    }
+
+    /// The notes that still make sense for a problem obtained from this one without changing the labels.
+    pub(crate) fn annotations_for_derived(&self) -> Option<Vec<(AnnotationTarget, String)>> {
+        let annotations: Vec<_> = self
+            .annotations
+            .as_ref()?
+            .iter()
+            .filter(|(t, _)| t != &AnnotationTarget::Step)
+            .cloned()
+            .collect();
+        if annotations.is_empty() {
+            None
+        } else {
+            Some(annotations)
+        }
+    }
+
+    /// The notes for a problem obtained by merging each `from` label into the corresponding `to` label.
+    /// Notes of merged labels are appended to the ones of the labels they are merged into,
+    /// while notes of lines that contain a merged label are lost.
+    pub(crate) fn annotations_after_merges(&self, merges: &[(Label, Label)]) -> Option<Vec<(AnnotationTarget, String)>> {
+        let mut annotations = self.annotations_for_derived()?;
+        for &(from, to) in merges.iter().filter(|(from, to)| from != to) {
+            annotations.retain(|(t, _)| match t {
+                AnnotationTarget::Line(_, line) => !line.parts.iter().any(|part| part.group.0.contains(&from)),
+                _ => true,
+            });
+            let moved = annotations
+                .iter()
+                .position(|(t, _)| t == &AnnotationTarget::Label(from))
+                .map(|i| annotations.remove(i).1);
+            if let Some(moved) = moved {
+                match annotations.iter_mut().find(|(t, _)| t == &AnnotationTarget::Label(to)) {
+                    Some((_, note)) => {
+                        note.push('\n');
+                        note.push_str(&moved);
+                    }
+                    None => annotations.push((AnnotationTarget::Label(to), moved)),
+                }
+            }
+        }
+        annotations.sort();
+        Some(annotations)
+    }
+
+    /// Forgets the notes about labels and lines that are not part of the problem anymore.
+    pub fn discard_stale_annotations(&mut self) {
+        let labels = self.active.labels_appearing();
+        let labels = labels.union(&self.passive.labels_appearing()).cloned().collect::<Vec<_>>();
+        let (active, passive) = (&self.active, &self.passive);
+        if let Some(annotations) = self.annotations.as_mut() {
+            annotations.retain(|(t, _)| match t {
+                AnnotationTarget::Step => true,
+                AnnotationTarget::Label(l) => labels.contains(l),
+                AnnotationTarget::Line(Side::Active, line) => active.lines.contains(line),
+                AnnotationTarget::Line(Side::Passive, line) => passive.lines.contains(line),
+            });
+            if annotations.is_empty() {
+                self.annotations = None;
+            }
+        }
+    }
+
+    /// Returns the notes in a human readable form, one per paragraph, to be included in exports.
+    pub fn annotations_text(&self) -> String {
+        let mapping: HashMap<_, _> = self.mapping_label_text.iter().cloned().collect();
+        let Some(annotations) = self.annotations.as_ref() else {
+            return String::new();
+        };
+        annotations
+            .iter()
+            .map(|(t, note)| {
+                let target = match t {
+                    AnnotationTarget::Step => "Step".to_string(),
+                    AnnotationTarget::Label(l) => format!("Label {}", mapping.get(l).map_or("?", |s| s.as_str())),
+                    AnnotationTarget::Line(Side::Active, line) => format!("Active line {}", line.to_string(&mapping)),
+                    AnnotationTarget::Line(Side::Passive, line) => format!("Passive line {}", line.to_string(&mapping)),
+                };
+                format!("{}: {}\n", target, note)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{problem::Problem, store::Side};
+
+    use super::AnnotationTarget;
+
+    #[test]
+    fn annotations() {
+        let mut p = Problem::from_string("A B\nC C\n\nA BC\nB C").unwrap();
+        p.annotate(AnnotationTarget::Label(0), "needed for the leaves".into());
+        p.annotate(AnnotationTarget::Label(2), "the color".into());
+        p.annotate(AnnotationTarget::Line(Side::Active, p.active.lines[1].clone()), "pairs".into());
+        p.annotate(AnnotationTarget::Step, "initial problem".into());
+        assert_eq!(p.annotation(&AnnotationTarget::Label(0)), Some("needed for the leaves"));
+
+        let merged = p.relax_merge(0, 2);
+        assert_eq!(merged.annotation(&AnnotationTarget::Step), None);
+        assert_eq!(merged.annotation(&AnnotationTarget::Label(2)), Some("the color\nneeded for the leaves"));
+        assert_eq!(
+            merged.annotations_text(),
+            "Label C: the color\nneeded for the leaves\n\nActive line C^2: pairs\n"
+        );
+
+        let mut hardened = p.harden_remove(2, false);
+        hardened.discard_stale_annotations();
+        assert_eq!(hardened.annotations_text(), "Label A: needed for the leaves\n");
+    }
+}
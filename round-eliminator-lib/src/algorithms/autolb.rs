@@ -1,49 +1,180 @@
 use std::collections::{HashSet, HashMap};
 
-use crate::{problem::Problem, group::Label, serial::AutoOperation, line::Degree};
+use crate::{problem::Problem, group::Label, serial::AutoOperation, line::Degree, corpus::Budget, verdict::Verdict};
 
 use super::event::EventHandler;
+use super::label_schedule::LabelSchedule;
+use super::pruning_rules::PruningRules;
+use super::resume::split_at_speedups;
 use itertools::Itertools;
 use permutator::Combination;
+use serde::{Deserialize, Serialize};
 
+/// How the merges applied after each speedup are chosen.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum LbStrategy {
+    /// Prefer merging labels whose sets of original labels are close to each other.
+    #[default]
+    Labels,
+    /// Prefer the merges that give the narrowest diagram, see `diagram_width`, since a narrow diagram keeps the next speedups small.
+    DiagramWidth,
+    /// Prefer merging the pairs of labels with the largest similarity, given for pairs of names of labels of the problem before each speedup,
+    /// for example painted by the user or taken from a previous run, see `similarities`. Pairs that are not listed have similarity 0, and ties are broken as in `Labels`.
+    Similarity(Vec<(String, String, u32)>),
+}
 
 impl Problem {
-    pub fn autolb<F>(&self, max_labels : usize, branching : usize, min_steps : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, mut handler : F, eh: &mut EventHandler)  -> bool  where F : FnMut(usize, Vec<(AutoOperation,Problem)>){
-        let mut problems = vec![(vec![],self.clone(),self.clone(),self.to_string())];
+    pub fn autolb<F>(&self, max_labels : usize, branching : usize, min_steps : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, strategy : LbStrategy, handler : F, eh: &mut EventHandler)  -> bool  where F : FnMut(usize, Vec<(AutoOperation,Problem)>){
+        self.autolb_scheduled(&LabelSchedule::uniform(max_labels), branching, min_steps, max_steps, coloring, coloring_passive, strategy, &PruningRules::default(), handler, eh)
+    }
+
+    /// Same as `autolb`, but the number of labels left by the merges depends on the number of speedups performed so far.
+    /// Color labels, see `set_color_labels`, are never merged and are not counted. When a coloring is given and no labels are marked,
+    /// the labels of the independent actions of the initial problem are marked. The problems and the merges excluded by the rules are not explored, see `PruningRules`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn autolb_scheduled<F>(&self, schedule : &LabelSchedule, branching : usize, min_steps : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, strategy : LbStrategy, rules : &PruningRules, mut handler : F, eh: &mut EventHandler)  -> bool  where F : FnMut(usize, Vec<(AutoOperation,Problem)>){
+        let mut start = self.clone();
+        if coloring.is_some() {
+            start.set_color_labels_from_coloring(eh);
+        }
+        let mut problems = vec![(vec![],start.clone(),start,self.to_string())];
         let mut best = usize::MAX;
         let mut seen = HashMap::new();
     
-        automatic_lower_bound_rec(&mut seen, &mut problems, &mut best, max_labels, branching, min_steps, max_steps, coloring, coloring_passive, &mut handler, eh);
+        automatic_lower_bound_rec(&mut seen, &mut problems, &mut best, schedule, branching, min_steps, max_steps, coloring, coloring_passive, &strategy, rules, &mut handler, eh);
 
         return best >= max_steps;
     }
 
+    /// Same as `autolb_scheduled`, but the search continues from the last problem of `prefix`, a sequence of speedups and merges
+    /// that starts with the initial problem, for example performed by hand. The reported sequences start with `prefix`, and their length
+    /// includes its speedups. Each problem of `prefix` must be the result of its operation on the previous one, and a lower bound only holds if the problems of `prefix`
+    /// are not zero round solvable, so the search is stopped at the first one that is.
+    #[allow(clippy::too_many_arguments)]
+    pub fn autolb_resume<F>(prefix : &[(AutoOperation,Problem)], schedule : &LabelSchedule, branching : usize, min_steps : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, strategy : LbStrategy, rules : &PruningRules, mut handler : F, eh: &mut EventHandler) -> Result<bool, &'static str> where F : FnMut(usize, Vec<(AutoOperation,Problem)>){
+        let mut problems = vec![];
+        for (ops, after_speedup, after_merge) in split_at_speedups(prefix, eh)? {
+            let mut merges = vec![];
+            for op in ops {
+                let AutoOperation::Merge(m, _) = op else {
+                    return Err("A lower bound search can only be resumed after speedups and merges");
+                };
+                merges.extend(m);
+            }
+            let m_s = after_merge.to_string();
+            problems.push((merges, after_speedup, after_merge, m_s));
+        }
+        let trivial = problems.iter_mut().position(|(_, _, p, _)| {
+            if p.trivial_sets.is_none() {
+                p.compute_triviality(eh);
+            }
+            !p.trivial_sets.as_ref().unwrap().is_empty()
+        });
+        if let Some(i) = trivial {
+            problems.truncate(i + 1);
+        }
+        // the colorings are given for the sides of the initial problem, and each speedup swaps the sides
+        let (coloring, coloring_passive) = if problems.len() % 2 == 0 { (coloring_passive, coloring) } else { (coloring, coloring_passive) };
+        if coloring.is_some() {
+            problems.last_mut().unwrap().2.set_color_labels_from_coloring(eh);
+        }
+        let mut best = usize::MAX;
+        let mut seen = HashMap::new();
+
+        automatic_lower_bound_rec(&mut seen, &mut problems, &mut best, schedule, branching, min_steps, max_steps, coloring, coloring_passive, &strategy, rules, &mut handler, eh);
 
-    pub fn autoautolb<F>(&self, b_max_labels : bool, max_labels : usize, b_branching : bool, branching : usize, b_max_steps : bool, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, mut handler : F, eh: &mut EventHandler) where F : FnMut(usize, Vec<(AutoOperation,Problem)>) {
+        Ok(best >= max_steps)
+    }
+
+
+    /// Runs autolb with increasing limits, for the limits that are not given. Returns the verdict of the best sequence found,
+    /// or, if none is found, the largest limits that have been tried. If a `schedule` is given, it bounds the labels instead of `max_labels`, see `autolb_scheduled`.
+    /// The problems and the merges excluded by the rules are not explored, see `PruningRules`.
+    pub fn autoautolb<F>(&self, b_max_labels : bool, max_labels : usize, schedule : Option<&LabelSchedule>, b_branching : bool, branching : usize, b_max_steps : bool, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, strategy : LbStrategy, both_sides : bool, rules : &PruningRules, mut handler : F, eh: &mut EventHandler) -> Verdict where F : FnMut(usize, Vec<(AutoOperation,Problem)>) {
+        let mut found = None;
+        let mut handler = |len, seq| {
+            found = Some(Verdict::from_autolb(len));
+            handler(len, seq);
+        };
+        let mut min_steps = 1;
+        let b_max_labels = b_max_labels || schedule.is_some();
+        let schedule_for = |max_labels| schedule.cloned().unwrap_or_else(|| LabelSchedule::uniform(max_labels));
         if b_max_labels && b_branching && b_max_steps {
-            self.autolb(max_labels, branching, 1, max_steps, coloring, coloring_passive, handler, eh);
-            return;
+            let schedule = schedule_for(max_labels);
+            let done = self.autolb_scheduled(&schedule, branching, 1, max_steps, coloring, coloring_passive, strategy.clone(), rules, |len,seq|{
+                min_steps = std::cmp::max(min_steps, len+1);
+                handler(len,seq);
+            }, eh);
+            if both_sides && !done {
+                self.autolb_swapped(&schedule, branching, &mut min_steps, max_steps, coloring, coloring_passive, &strategy, rules, &mut handler, eh);
+            }
+            return found.unwrap_or(Verdict::HeuristicNoResult { budget: Budget { max_steps, max_labels: schedule.max() } });
         }
 
-        let mut min_steps = 1;
         for i in 1.. {
             let i_max_labels = if b_max_labels { max_labels } else { self.labels().len() + i };
             let i_branching = if b_branching { branching } else { i };
             let max_steps = if b_max_steps { max_steps } else { 15 };
+            let i_schedule = schedule_for(i_max_labels);
 
-            if self.autolb(i_max_labels, i_branching, min_steps, max_steps, coloring, coloring_passive, |len,seq|{
+            let done = self.autolb_scheduled(&i_schedule, i_branching, min_steps, max_steps, coloring, coloring_passive, strategy.clone(), rules, |len,seq|{
                 if len >= min_steps {
                     min_steps = len+1;
                     handler(len,seq);
                 }
-            },eh) {
-                return;
+            },eh) || (both_sides && self.autolb_swapped(&i_schedule, i_branching, &mut min_steps, max_steps, coloring, coloring_passive, &strategy, rules, &mut handler, eh));
+            if done {
+                return found.unwrap_or(Verdict::HeuristicNoResult { budget: Budget { max_steps, max_labels: i_schedule.max() } });
+            }
+        }
+        unreachable!()
+    }
+
+    /// Runs autolb on the problem with its sides swapped. A lower bound for the swapped problem gives a lower bound
+    /// for this problem that is one round smaller, see `swap_sides`; only the sequences that give a lower bound of at least `min_steps` are reported.
+    #[allow(clippy::too_many_arguments)]
+    fn autolb_swapped<F>(&self, schedule : &LabelSchedule, branching : usize, min_steps : &mut usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, strategy : &LbStrategy, rules : &PruningRules, handler : &mut F, eh: &mut EventHandler) -> bool where F : FnMut(usize, Vec<(AutoOperation,Problem)>) {
+        let swapped = self.swap_sides();
+        swapped.autolb_scheduled(schedule, branching, *min_steps + 1, max_steps + 1, coloring_passive, coloring, strategy.clone(), rules, |len,seq|{
+            let len = if len == 999 { len } else { len.saturating_sub(1) };
+            if len >= *min_steps {
+                *min_steps = len+1;
+                handler(len,self.sequence_from_swapped(seq));
+            }
+        }, eh)
+    }
+}
+
+/// The pairs of labels of the problem, obtained by a speedup, with their similarity, in both orders. The similarities are given for pairs of names
+/// of original labels, that is, of the labels of the problem before the speedup, since these names, differently from the ones of the new labels,
+/// do not depend on the merges performed so far. Two labels get the largest similarity between an original label that generates only the first
+/// and one that generates only the second, see `mapping_label_generators`, and the pairs with no such similarity are omitted.
+fn similarities(np : &Problem, pairs : &[(String, String, u32)]) -> HashMap<(Label,Label),u32> {
+    let oldlabel_of : HashMap<_,_> = np.mapping_oldlabel_text.iter().flatten().map(|(l,t)|(t.as_str(),*l)).collect();
+    let mut given = HashMap::new();
+    for (a,b,similarity) in pairs {
+        if let (Some(&o1), Some(&o2)) = (oldlabel_of.get(a.as_str()), oldlabel_of.get(b.as_str())) {
+            for key in [(o1,o2),(o2,o1)] {
+                let entry = given.entry(key).or_insert(*similarity);
+                *entry = std::cmp::max(*entry, *similarity);
             }
         }
     }
+
+    let generators : HashMap<_,HashSet<_>> = np.mapping_label_generators().into_iter().map(|(l,g)|(l,g.into_iter().collect())).collect();
+    let mut result = HashMap::new();
+    for (&l1,g1) in &generators {
+        for (&l2,g2) in &generators {
+            let best = g1.difference(g2).cartesian_product(g2.difference(g1)).filter_map(|(o1,o2)|given.get(&(*o1,*o2))).max();
+            if let (true, Some(&similarity)) = (l1 != l2, best) {
+                result.insert((l1,l2),similarity);
+            }
+        }
+    }
+    result
 }
 
-fn best_merges(np : &Problem, branching : usize, max_labels : usize, coloring : Option<usize>, eh: &mut EventHandler) -> Vec<Vec<(Label,Label)>> {
+fn best_merges(np : &Problem, branching : usize, max_labels : usize, coloring : Option<usize>, similarity : Option<&HashMap<(Label,Label),u32>>, eh: &mut EventHandler) -> Vec<Vec<(Label,Label)>> {
     if np.mapping_label_oldlabels.is_none() {
         return unimplemented!();
     }
@@ -53,6 +184,12 @@ fn best_merges(np : &Problem, branching : usize, max_labels : usize, coloring :
     let new : HashSet<_> = new.into_iter().collect();
     let labels = np.labels();
     let map : HashMap<_,_> = np.mapping_label_generators().into_iter().collect();
+    // the rank of each similarity, starting from the largest one, so that a larger similarity always wins over the weight computed from the generators
+    let ranks : Option<HashMap<u32,usize>> = similarity.map(|similarity|{
+        similarity.values().cloned().chain(std::iter::once(0)).sorted().dedup().rev().enumerate().map(|(i,s)|(s,i)).collect()
+    });
+    let scale = map.values().map(|g| g.len()).sum::<usize>() + 101;
+    let ranks = &ranks;
 
     let mut pair_weights : Vec<_> = labels.iter().flat_map(|&l1|{
         let new = &new;
@@ -70,9 +207,13 @@ fn best_merges(np : &Problem, branching : usize, max_labels : usize, coloring :
                 (_, true) => { distance + 2 },
                 _ => { distance + 100 }
             };
+            let weight = match (similarity, ranks) {
+                (Some(similarity), Some(ranks)) => ranks[&similarity.get(&(l1,l2)).copied().unwrap_or(0)] * scale + weight,
+                _ => weight
+            };
             ((l1,l2),weight)
         })
-    }).filter(|((l1,l2),w)|l1 < l2).collect();
+    }).filter(|((l1,l2),w)|l1 < l2 && !np.is_color_label(*l1) && !np.is_color_label(*l2)).collect();
     pair_weights.sort_by_key(|(_,w)|*w);
 
     let mut candidates = HashSet::new();
@@ -124,12 +265,25 @@ fn best_merges(np : &Problem, branching : usize, max_labels : usize, coloring :
     candidates.into_iter().take(branching).map(|v|v.into_iter().map(|(p,_)|p).collect()).collect()
 }
 
-fn automatic_lower_bound_rec<F>(seen : &mut HashMap<String,usize>, problems : &mut Vec<(Vec<(Label,Label)>,Problem,Problem,String)>, best : &mut usize, max_labels : usize, branching : usize, min_steps : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, handler : &mut F, eh: &mut EventHandler) where F : FnMut(usize, Vec<(AutoOperation,Problem)>) {
+fn narrowest_merges(np : &Problem, branching : usize, max_labels : usize, coloring : Option<usize>, eh: &mut EventHandler) -> Vec<Vec<(Label,Label)>> {
+    let mut candidates : Vec<_> = best_merges(np, 3*branching, max_labels, coloring, None, eh).into_iter().map(|merges|{
+        let mut merged = np.relax_many_merges(&merges);
+        merged.discard_useless_stuff(false, eh);
+        ((merged.diagram_width(), merged.labels().len()), merges)
+    }).collect();
+    candidates.sort_by_key(|(key,_)|*key);
+    candidates.into_iter().take(branching).map(|(_,merges)|merges).collect()
+}
+
+fn automatic_lower_bound_rec<F>(seen : &mut HashMap<String,usize>, problems : &mut Vec<(Vec<(Label,Label)>,Problem,Problem,String)>, best : &mut usize, schedule : &LabelSchedule, branching : usize, min_steps : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, strategy : &LbStrategy, rules : &PruningRules, handler : &mut F, eh: &mut EventHandler) where F : FnMut(usize, Vec<(AutoOperation,Problem)>) {
 
     let mut send_sequence = |len : usize, problems : &Vec<(Vec<(Label,Label)>,Problem,Problem,String)>|{
         *best = len + 1;
         let mut sequence = vec![];
-        sequence.push((AutoOperation::Initial,problems[0].2.clone()));
+        sequence.push((AutoOperation::Initial,problems[0].1.clone()));
+        if !problems[0].0.is_empty() {
+            sequence.push((AutoOperation::Merge(problems[0].0.clone(),problems[0].1.clone()),problems[0].2.clone()));
+        }
         for (merges,after_speedup, after_merge,_) in problems.iter().skip(1) {
             sequence.push((AutoOperation::Speedup,after_speedup.clone()));
             if !merges.is_empty() {
@@ -155,11 +309,14 @@ fn automatic_lower_bound_rec<F>(seen : &mut HashMap<String,usize>, problems : &m
         if problems.len() < 6 && seen.len() < 100_000 {
             seen.insert(p_s.clone(),problems.len());
         }
+        if rules.discards(&problems.last().unwrap().2, eh) {
+            return;
+        }
 
         let p = &mut problems.last_mut().unwrap().2;   
 
         if p.trivial_sets.is_none() {
-            p.compute_triviality(eh);
+            p.compute_triviality_with_certificate(eh);
         }
         if coloring.is_some() && p.coloring_sets.is_none() {
             p.compute_coloring_solvability(eh);
@@ -183,18 +340,30 @@ fn automatic_lower_bound_rec<F>(seen : &mut HashMap<String,usize>, problems : &m
     let mut np = p.speedup(eh);
     np.discard_useless_stuff(false, eh);
     np.sort_active_by_strength();
+    let np_rules = rules.applied_to(&np, eh);
+    if np_rules.discards() {
+        return;
+    }
     if coloring.is_some() {
         np.compute_coloring_solvability(eh);
     }
 
-    let candidates = best_merges(&np, branching, max_labels, coloring, eh);
+    // color labels are never merged, so they do not count towards the number of labels allowed by the schedule
+    let max_labels = schedule.at(problems.len()) + np.color_labels().len();
+    let candidates = match strategy {
+        LbStrategy::Labels => best_merges(&np, branching, max_labels, coloring, None, eh),
+        LbStrategy::DiagramWidth => narrowest_merges(&np, branching, max_labels, coloring, eh),
+        LbStrategy::Similarity(pairs) => best_merges(&np, branching, max_labels, coloring, Some(&similarities(&np, pairs)), eh),
+    };
+
+    let candidates : Vec<_> = candidates.into_iter().filter(|merges| np_rules.allows_merges(merges)).take(branching).collect();
 
-    for candidate in candidates.into_iter().take(branching) {
+    for candidate in candidates {
         let merges : Vec<(Label,Label)> = candidate;
         let mut merged = np.relax_many_merges(&merges);
         merged.discard_useless_stuff(false, eh);
         merged.sort_active_by_strength();
-        merged.compute_triviality(eh);
+        merged.compute_triviality_with_certificate(eh);
         if coloring.is_some() {
             merged.compute_coloring_solvability(eh);
         }
@@ -204,7 +373,7 @@ fn automatic_lower_bound_rec<F>(seen : &mut HashMap<String,usize>, problems : &m
         let m_s = merged.to_string();
 
         problems.push((merges,np.clone(),merged.clone(),m_s));
-        automatic_lower_bound_rec(seen, problems, best, max_labels, branching, min_steps, max_steps, coloring, coloring_passive, handler, eh);
+        automatic_lower_bound_rec(seen, problems, best, schedule, branching, min_steps, max_steps, coloring, coloring_passive, strategy, rules, handler, eh);
         problems.pop();
         if *best > max_steps {
             return;
@@ -214,3 +383,36 @@ fn automatic_lower_bound_rec<F>(seen : &mut HashMap<String,usize>, problems : &m
     
 }
 
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashMap;
+
+    use crate::{algorithms::event::EventHandler, group::Label, problem::Problem, serial::fix_problem};
+
+    use super::{best_merges, similarities};
+
+    #[test]
+    fn similarity_merges() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap().speedup(eh);
+        fix_problem(&mut p, true, false, eh);
+        let labels = p.labels();
+        let generators : HashMap<_,_> = p.mapping_label_generators().into_iter().collect();
+        let oldlabel = |name : &str| p.mapping_oldlabel_text.as_ref().unwrap().iter().find(|(_, t)| t == name).unwrap().0;
+
+        // the similarities are given for the labels of the problem before the speedup, and the most similar ones are merged first
+        for (a, b) in [("M", "U"), ("U", "P")] {
+            let pairs = vec![(b.into(), a.into(), 10), (a.into(), "nonexisting".into(), 20)];
+            let similarity = similarities(&p, &pairs);
+            assert!(!similarity.is_empty());
+            let merges = best_merges(&p, 1, labels.len() - 1, None, Some(&similarity), eh);
+            assert_eq!(merges.len(), 1);
+            let &(l1, l2) = &merges[0][0];
+            let only = |x : Label, y : Label, o| generators[&x].contains(&o) && !generators[&y].contains(&o);
+            let (a, b) = (oldlabel(a), oldlabel(b));
+            assert!((only(l1, l2, a) && only(l2, l1, b)) || (only(l1, l2, b) && only(l2, l1, a)));
+        }
+    }
+}
@@ -1,44 +1,293 @@
 use std::collections::{HashSet, HashMap};
 
-use crate::{problem::Problem, group::Label, serial::AutoOperation, line::Degree};
+use crate::{problem::Problem, group::Label, serial::AutoOperation, line::Degree, corpus::Budget, verdict::Verdict};
 
 use super::event::EventHandler;
+use super::problem_triviality::TrivialityAnswer;
+use super::distributed::Shard;
+use super::label_schedule::LabelSchedule;
+use super::pruning_rules::PruningRules;
+use super::resume::split_at_speedups;
+use super::search_tree::{NodeOutcome, PruneReason, SearchTree};
 use itertools::Itertools;
 use permutator::Combination;
 use rand::prelude::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+/// When the triviality and coloring checks are performed during the search.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum EvaluationPolicy {
+    /// Every hardened problem is checked as soon as it is created.
+    #[default]
+    Eager,
+    /// A hardened problem is checked only when the search visits it, that is, after checking that it has not been seen already,
+    /// and that it can still give a better upper bound.
+    Lazy,
+    /// As Lazy, but the triviality check of a problem stops after examining this many candidate sets of labels, and if it finds
+    /// no zero round algorithm the search goes on from the problem. Only the problems where the search stops, because of the
+    /// maximum number of steps, are checked completely. The upper bounds found are still correct, but may not be the best ones.
+    Anytime(usize),
+}
+
+impl EvaluationPolicy {
+    /// The number of candidates examined by the triviality check of a problem, or None to examine all of them.
+    fn triviality_budget(self, last_step: bool) -> Option<usize> {
+        match self {
+            EvaluationPolicy::Anytime(limit) if !last_step => Some(limit),
+            _ => None,
+        }
+    }
+}
 
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SearchStats {
+    pub triviality_checks: usize,
+    /// Triviality checks that have been stopped before knowing the answer, with the anytime policy.
+    pub partial_triviality_checks: usize,
+    pub coloring_checks: usize,
+    /// Problems that have been discarded by the search before checking them, thanks to the lazy policy.
+    pub checks_avoided: usize,
+    /// Hardenings that have not been tried because they keep a subset of the labels kept by a hardening that already failed, see `FailedKeepSets`.
+    pub dominance_pruned: usize,
+    /// If present, all the nodes explored by the search are recorded here. It is not serialized, since it can be large:
+    /// it is sent separately, and only when asked, see `Request::AutoUbTree`.
+    #[serde(skip)]
+    pub tree: Option<SearchTree>,
+    /// Problems and hardenings that have not been explored because of the pruning rules, see `PruningRules`.
+    pub rule_pruned: usize,
+    /// Hardenings checked together with their siblings by the eager policy, see `Problem::compute_triviality_batch`, but not explored,
+    /// because meanwhile the bound improved or a sibling failed. They are not counted in `triviality_checks`.
+    pub speculative_checks: usize,
+}
+
+impl SearchStats {
+    fn check(&mut self, p: &mut Problem, coloring: Option<usize>, budget: Option<usize>, eh: &mut EventHandler) {
+        if p.trivial_sets.is_none() {
+            self.triviality_checks += 1;
+            match budget {
+                None => p.compute_triviality(eh),
+                // trivial problems end the search, so they are checked completely
+                Some(limit) => match p.compute_triviality_within(limit, eh) {
+                    TrivialityAnswer::Trivial if p.trivial_sets.is_none() => p.compute_triviality(eh),
+                    TrivialityAnswer::Unknown { .. } => self.partial_triviality_checks += 1,
+                    _ => {}
+                },
+            }
+        }
+        if coloring.is_some() && p.coloring_sets.is_none() {
+            p.compute_coloring_solvability(eh);
+            self.coloring_checks += 1;
+        }
+    }
+
+    fn mark(&mut self, outcome: NodeOutcome) {
+        if let Some(tree) = self.tree.as_mut() {
+            tree.mark(outcome);
+        }
+    }
+
+    fn pruned_child(&mut self, np: &Problem, kept: &[Label], coloring: Option<usize>, reason: PruneReason) {
+        if let Some(tree) = self.tree.as_mut() {
+            tree.pruned_child(kept, hardening_score(np, kept, coloring), reason);
+        }
+    }
+}
+
+
+/// An upper bound found by autoub, with the number of rounds and the largest number of labels of the problems in its sequence.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FrontierPoint {
+    pub steps: usize,
+    pub labels: usize,
+    pub sequence: Vec<(AutoOperation, Problem)>,
+}
+
+/// How the exploration of a branch of autoub ended. The order is the one used to combine the branches of a node:
+/// a node is solved if one of its branches is, and it failed only if all of them failed.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+enum BranchEnd {
+    /// The branch has been explored completely within the remaining steps, without finding a better upper bound.
+    Failed,
+    /// The exploration stopped before completing the branch, because of a cycle, a problem already seen, the best bound,
+    /// the pruning rules, or the shards, so nothing is known about it.
+    Cut,
+    /// The branch gave a better upper bound.
+    Solved,
+}
+
+/// For each number of speedups, and for each problem obtained after that many speedups,
+/// the sets of labels that have been kept by hardenings whose branch failed, see `BranchEnd::Failed`.
+/// Keeping a subset of one of them gives a harder problem, with the same number of remaining steps, so it is not tried.
+/// This is only a heuristic: the search tries a limited number of hardenings after each speedup, so a failed branch has not been
+/// explored exhaustively, and the hardenings of the harder problem, which may differ, could still succeed.
+#[derive(Default)]
+struct FailedKeepSets {
+    by_depth: HashMap<(usize, String), Vec<HashSet<Label>>>,
+    size: usize,
+}
+
+impl FailedKeepSets {
+    fn dominated(&self, depth: usize, problem: &str, keep: &HashSet<Label>) -> bool {
+        self.by_depth
+            .get(&(depth, problem.to_owned()))
+            .is_some_and(|failed| failed.iter().any(|f| f.is_superset(keep)))
+    }
+
+    fn insert(&mut self, depth: usize, problem: &str, keep: HashSet<Label>) {
+        if self.size >= 100_000 {
+            return;
+        }
+        self.size += 1;
+        self.by_depth.entry((depth, problem.to_owned())).or_default().push(keep);
+    }
+}
 
 impl Problem {
-    pub fn autoub<F>(&self, max_labels : usize, branching : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, mut handler : F, eh: &mut EventHandler) where F : FnMut(usize, bool, Vec<(AutoOperation,Problem)>) {
+    pub fn autoub<F>(&self, max_labels : usize, branching : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, policy : EvaluationPolicy, stats : &mut SearchStats, handler : F, eh: &mut EventHandler) where F : FnMut(usize, bool, Vec<(AutoOperation,Problem)>) {
+        self.autoub_with_shard(&LabelSchedule::uniform(max_labels), branching, max_steps, coloring, coloring_passive, policy, &PruningRules::default(), None, stats, handler, eh);
+    }
+
+    /// Same as `autoub`, but the number of labels kept by the hardenings depends on the number of speedups performed so far,
+    /// and the problems and the hardenings excluded by the rules are not explored, see `PruningRules`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn autoub_scheduled<F>(&self, schedule : &LabelSchedule, branching : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, policy : EvaluationPolicy, rules : &PruningRules, stats : &mut SearchStats, handler : F, eh: &mut EventHandler) where F : FnMut(usize, bool, Vec<(AutoOperation,Problem)>) {
+        self.autoub_with_shard(schedule, branching, max_steps, coloring, coloring_passive, policy, rules, None, stats, handler, eh);
+    }
+
+    /// Same as `autoub_scheduled`, but the search continues from the last problem of `prefix`, a sequence of speedups and hardenings
+    /// that starts with the initial problem, for example performed by hand. The reported sequences start with `prefix`, and their length
+    /// includes its speedups. Each problem of `prefix` must be the result of its operation on the previous one.
+    #[allow(clippy::too_many_arguments)]
+    pub fn autoub_resume<F>(prefix : &[(AutoOperation,Problem)], schedule : &LabelSchedule, branching : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, policy : EvaluationPolicy, rules : &PruningRules, stats : &mut SearchStats, mut handler : F, eh: &mut EventHandler) -> Result<(), &'static str> where F : FnMut(usize, bool, Vec<(AutoOperation,Problem)>) {
+        let mut problems = vec![];
+        for (ops, after_speedup, after_harden) in split_at_speedups(prefix, eh)? {
+            if ops.iter().any(|op| !matches!(op, AutoOperation::Harden(_))) {
+                return Err("An upper bound search can only be resumed after speedups and hardenings");
+            }
+            let h_s = after_harden.to_string();
+            problems.push((after_harden.labels(), after_speedup, after_harden, h_s));
+        }
+        // the colorings are given for the sides of the initial problem, and each speedup swaps the sides
+        let (coloring, coloring_passive) = if problems.len() % 2 == 0 { (coloring_passive, coloring) } else { (coloring, coloring_passive) };
+        let mut best = usize::MAX;
+        let mut seen = HashMap::new();
+        let mut failed = FailedKeepSets::default();
+        automatic_upper_bound_rec(&mut seen, &mut failed, &mut problems, &mut best, schedule, branching, max_steps, coloring, coloring_passive, policy, rules, None, stats, &mut handler, eh);
+        Ok(())
+    }
+
+    /// Same as `autoub`, but only the top-level branches assigned to the given shard are explored.
+    /// The branches are the hardenings of the problem if it has too many labels, and the hardenings of the problem obtained after one speedup otherwise.
+    /// The best upper bound and the visited problems are shared with the other shards through `shard`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn autoub_shard<F>(&self, max_labels : usize, branching : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, policy : EvaluationPolicy, shard : &Shard, stats : &mut SearchStats, handler : F, eh: &mut EventHandler) where F : FnMut(usize, bool, Vec<(AutoOperation,Problem)>) {
+        self.autoub_with_shard(&LabelSchedule::uniform(max_labels), branching, max_steps, coloring, coloring_passive, policy, &PruningRules::default(), Some(shard), stats, handler, eh);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn autoub_with_shard<F>(&self, schedule : &LabelSchedule, branching : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, policy : EvaluationPolicy, rules : &PruningRules, shard : Option<&Shard>, stats : &mut SearchStats, mut handler : F, eh: &mut EventHandler) where F : FnMut(usize, bool, Vec<(AutoOperation,Problem)>) {
+        let max_labels = schedule.at(0);
         if self.labels().len() <= max_labels {
             let mut problems = vec![(self.labels(),self.clone(),self.clone(),self.to_string())];
             let mut best = usize::MAX;
             let mut seen = HashMap::new();
+            let mut failed = FailedKeepSets::default();
             //println!("calling rec");
-            automatic_upper_bound_rec(&mut seen, &mut problems, &mut best, max_labels, branching, max_steps, coloring, coloring_passive, &mut handler, eh);
+            automatic_upper_bound_rec(&mut seen, &mut failed, &mut problems, &mut best, schedule, branching, max_steps, coloring, coloring_passive, policy, rules, shard.map(|s| (s, 1)), stats, &mut handler, eh);
         } else {
             //println!("too many labels");
             let mut best = usize::MAX;
             let mut seen = HashMap::new();
-            for candidate in best_hardenings(self, branching, max_labels, coloring, eh).into_iter().take(branching) {        
-                let tokeep = candidate.iter().cloned().collect();
+            let mut failed = FailedKeepSets::default();
+            let self_s = self.to_string();
+            for (branch, candidate) in best_hardenings(self, branching, max_labels, coloring, eh).into_iter().take(branching).enumerate() {        
+                if shard.is_some_and(|s| !s.owns(branch)) {
+                    continue;
+                }
+                let tokeep : HashSet<Label> = candidate.iter().cloned().collect();
+                if failed.dominated(0, &self_s, &tokeep) {
+                    stats.dominance_pruned += 1;
+                    continue;
+                }
                 let mut hardened = self.harden_keep(&tokeep, true);
                 hardened.discard_useless_stuff(false, eh);
                 hardened.sort_active_by_strength();
-                hardened.compute_triviality(eh);
-                if coloring.is_some() {
-                    hardened.compute_coloring_solvability(eh);
+                if policy == EvaluationPolicy::Eager {
+                    stats.check(&mut hardened, coloring, None, eh);
                 }
                 let h_s = hardened.to_string();
                 let mut problems = vec![(candidate,self.clone(),hardened.clone(),h_s)];
-                automatic_upper_bound_rec(&mut seen, &mut problems, &mut best, max_labels, branching, max_steps, coloring, coloring_passive, &mut handler, eh);
+                let end = automatic_upper_bound_rec(&mut seen, &mut failed, &mut problems, &mut best, schedule, branching, max_steps, coloring, coloring_passive, policy, rules, shard.map(|s| (s, usize::MAX)), stats, &mut handler, eh);
+                if end == BranchEnd::Failed {
+                    failed.insert(0, &self_s, tokeep);
+                }
+            }
+        }
+    }
+
+    /// Computes the tradeoff between the number of rounds and the number of labels of the upper bounds found by autoub,
+    /// by running it with each label budget from the number of labels of the problem up to `max_labels`.
+    /// The number of labels of an upper bound is the largest number of labels of the problems kept in its sequence,
+    /// that is, without counting the results of speedups that are then hardened.
+    /// Only the non-dominated outcomes are returned, that is, each one uses fewer rounds than all the ones that use fewer labels.
+    /// The result is sorted by number of labels.
+    #[allow(clippy::too_many_arguments)]
+    pub fn autoub_frontier(&self, max_labels : usize, branching : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, policy : EvaluationPolicy, stats : &mut SearchStats, eh: &mut EventHandler) -> Vec<FrontierPoint> {
+        let mut frontier : Vec<FrontierPoint> = vec![];
+        for budget in self.labels().len().min(max_labels)..=max_labels {
+            if frontier.last().is_some_and(|point| point.steps == 0) {
+                break;
+            }
+            let steps = frontier.last().map_or(max_steps, |point| point.steps - 1);
+            let mut found : Option<(usize,Vec<(AutoOperation,Problem)>)> = None;
+            self.autoub(budget, branching, steps, coloring, coloring_passive, policy, stats, |len,_,sequence|{
+                if found.as_ref().is_none_or(|(best,_)| len < *best) {
+                    found = Some((len,sequence));
+                }
+            }, eh);
+            if let Some((steps, sequence)) = found {
+                let labels = sequence
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| !matches!(sequence.get(i + 1), Some((AutoOperation::Harden(_), _))))
+                    .map(|(_, (_, p))| p.labels().len())
+                    .max()
+                    .unwrap_or(0);
+                if frontier.last().is_none_or(|point| steps < point.steps) {
+                    // a point with as many labels and more rounds is dominated by the new one
+                    while frontier.last().is_some_and(|point| point.labels >= labels) {
+                        frontier.pop();
+                    }
+                    frontier.push(FrontierPoint { steps, labels, sequence });
+                }
             }
         }
+        frontier
     }
 
-    pub fn autoautoub<F>(&self, b_max_labels : bool, max_labels : usize, b_branching : bool, branching : usize, b_max_steps : bool, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, mut handler : F, eh: &mut EventHandler) where F : FnMut(usize, bool, Vec<(AutoOperation,Problem)>) {
+    /// Runs autoub with increasing limits, for the limits that are not given. Returns the verdict of the best sequence found,
+    /// or, if none is found, the largest limits that have been tried. If a `schedule` is given, it bounds the labels instead of `max_labels`, see `autoub_scheduled`.
+    /// The problems and the hardenings excluded by the rules are not explored, see `PruningRules`.
+    pub fn autoautoub<F>(&self, b_max_labels : bool, max_labels : usize, schedule : Option<&LabelSchedule>, b_branching : bool, branching : usize, b_max_steps : bool, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, policy : EvaluationPolicy, both_sides : bool, rules : &PruningRules, stats : &mut SearchStats, mut handler : F, eh: &mut EventHandler) -> Verdict where F : FnMut(usize, bool, Vec<(AutoOperation,Problem)>) {
+        let mut found = None;
+        let b_max_labels = b_max_labels || schedule.is_some();
+        let schedule_for = |max_labels| schedule.cloned().unwrap_or_else(|| LabelSchedule::uniform(max_labels));
+        let mut budget = Budget { max_steps, max_labels: schedule_for(max_labels).max() };
+        let mut handler = |len, trivial, seq| {
+            found = Some(Verdict::from_autoub(len, trivial));
+            handler(len, trivial, seq);
+        };
         if b_max_labels && b_branching && b_max_steps {
-            return self.autoub(max_labels, branching, max_steps, coloring, coloring_passive, handler, eh);
+            let schedule = schedule_for(max_labels);
+            let mut max_steps = max_steps;
+            self.autoub_scheduled(&schedule, branching, max_steps, coloring, coloring_passive, policy, rules, stats, |len,trivial,seq|{
+                max_steps = std::cmp::min(max_steps, len.saturating_sub(1));
+                handler(len,trivial,seq);
+            }, eh);
+            if both_sides {
+                self.autoub_swapped(&schedule, branching, &mut max_steps, coloring, coloring_passive, policy, rules, stats, &mut handler, eh);
+            }
+            return found.unwrap_or(Verdict::HeuristicNoResult { budget });
         }
 
         let mut max_steps = if b_max_steps {max_steps} else {usize::MAX};
@@ -46,21 +295,46 @@ impl Problem {
             let i_max_labels = if b_max_labels { max_labels } else { self.labels().len() + i };
             let i_branching = if b_branching { branching } else { i };
             let i_max_steps = if b_max_steps { max_steps } else { std::cmp::min(3*i,max_steps) };
+            let i_schedule = schedule_for(i_max_labels);
+            budget = Budget { max_steps: i_max_steps, max_labels: i_schedule.max() };
             for j_max_steps in 1..=i_max_steps {
                 if j_max_steps > max_steps {
                     break;
                 }
-                self.autoub(i_max_labels, i_branching, j_max_steps, coloring, coloring_passive, |len,trivial,seq|{
+                self.autoub_scheduled(&i_schedule, i_branching, j_max_steps, coloring, coloring_passive, policy, rules, stats, |len,trivial,seq|{
                     if len <= max_steps {
                         max_steps = len-1;
                         handler(len,trivial,seq);
                     }
                 },eh);
+                if both_sides {
+                    let mut j_max_steps = std::cmp::min(j_max_steps, max_steps);
+                    self.autoub_swapped(&i_schedule, i_branching, &mut j_max_steps, coloring, coloring_passive, policy, rules, stats, &mut handler, eh);
+                    max_steps = std::cmp::min(max_steps, j_max_steps);
+                }
                 if max_steps == 0 {
-                    return;
+                    return found.unwrap_or(Verdict::HeuristicNoResult { budget });
                 }
             }
         }
+        unreachable!()
+    }
+
+    /// Runs autoub on the problem with its sides swapped. An upper bound for the swapped problem gives an upper bound
+    /// for this problem that is one round larger, see `swap_sides`; only the sequences that give an upper bound of at most `max_steps` are reported,
+    /// and `max_steps` is then decreased below the reported bound.
+    #[allow(clippy::too_many_arguments)]
+    fn autoub_swapped<F>(&self, schedule : &LabelSchedule, branching : usize, max_steps : &mut usize, coloring : Option<usize>, coloring_passive : Option<usize>, policy : EvaluationPolicy, rules : &PruningRules, stats : &mut SearchStats, handler : &mut F, eh: &mut EventHandler) where F : FnMut(usize, bool, Vec<(AutoOperation,Problem)>) {
+        if *max_steps < 2 {
+            return;
+        }
+        let swapped = self.swap_sides();
+        swapped.autoub_scheduled(schedule, branching, *max_steps - 1, coloring_passive, coloring, policy, rules, stats, |len,trivial,seq|{
+            if len < *max_steps {
+                *max_steps = len;
+                handler(len + 1,trivial,self.sequence_from_swapped(seq));
+            }
+        }, eh);
     }
 }
 
@@ -74,11 +348,7 @@ fn best_hardenings(np : &Problem, branching : usize, max_labels : usize, colorin
     let labels = np.labels();
     let (old, new) = np.split_labels_original_new();
     let label_weights : HashMap<_,_> = if coloring.is_some() {
-        let colors : Vec<Label> = if np.orientation_coloring_sets.is_some() {
-            np.orientation_coloring_sets.as_ref().unwrap().iter().flat_map(|(a,b)|a.iter().cloned().chain(b.iter().cloned())).collect()
-        } else {
-            np.coloring_sets.as_ref().unwrap().iter().flat_map(|x|x.iter().cloned()).collect()
-        };
+        let colors = hardening_colors(np);
         np.labels().into_iter().map(|l|{
             let weight = map[&l].len() + 10* if !colors.contains(&l){1}else{0};
             (l,weight)
@@ -126,29 +396,59 @@ fn best_hardenings(np : &Problem, branching : usize, max_labels : usize, colorin
         }
     }
 
-    let mut candidates : Vec<_> = candidates.into_iter().collect();
-    if coloring.is_some() {
-        let colors : Vec<Label> = if np.orientation_coloring_sets.is_some() {
-            np.orientation_coloring_sets.as_ref().unwrap().iter().flat_map(|(a,b)|a.iter().cloned().chain(b.iter().cloned())).collect()
-        } else {
-            np.coloring_sets.as_ref().unwrap().iter().flat_map(|x|x.iter().cloned()).collect()
-        };
-        candidates.sort_by_cached_key(|labels|{
-            labels.iter().map(|l|map[l].len()).sum::<usize>() + 10*labels.iter().filter(|x|!colors.contains(x)).count()
-        });
-    } else {
-        candidates.sort_by_cached_key(|labels|{
-            labels.iter().map(|l|map[l].len()).sum::<usize>()
-        });
-    } 
+    // the order must not depend on the one of the hash set, so that the search, and how it is split among shards, is the same in every run
+    let mut candidates : Vec<_> = candidates.into_iter().map(|mut labels| { labels.sort_unstable(); labels }).collect();
+    let colors = coloring.map(|_| hardening_colors(np));
+    candidates.sort_by_cached_key(|labels| (hardening_weight(&map, colors.as_deref(), labels), labels.clone()));
+    candidates.dedup();
 
     candidates.into_iter().take(branching).collect()
 }
 
-fn automatic_upper_bound_rec<F>(seen : &mut HashMap<String,usize>, problems : &mut Vec<(Vec<Label>,Problem,Problem,String)>, best : &mut usize, max_labels : usize, branching : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, handler : &mut F, eh: &mut EventHandler) where F : FnMut(usize, bool, Vec<(AutoOperation,Problem)>) {
-    //println!("{} {} {}", max_labels, branching, max_steps);
+/// The labels that appear in the coloring found for the problem, used to prefer the hardenings that keep them.
+fn hardening_colors(np : &Problem) -> Vec<Label> {
+    if np.orientation_coloring_sets.is_some() {
+        np.orientation_coloring_sets.as_ref().unwrap().iter().flat_map(|(a,b)|a.iter().cloned().chain(b.iter().cloned())).collect()
+    } else {
+        // the coloring solvability is unknown if the passive side has degree *, and then no label is a color
+        np.coloring_sets.iter().flatten().flat_map(|x|x.iter().cloned()).collect()
+    }
+}
+
+/// The number of original labels generated by the kept labels, plus 10 for each kept label that is not a color, if a coloring is searched.
+/// The hardenings with the smallest weight are tried first, see `best_hardenings`.
+fn hardening_weight(map : &HashMap<Label,Vec<Label>>, colors : Option<&[Label]>, labels : &[Label]) -> usize {
+    labels.iter().map(|l|map[l].len()).sum::<usize>() + colors.map_or(0, |colors| 10*labels.iter().filter(|x|!colors.contains(x)).count())
+}
+
+/// The weight of keeping the given labels of `np`, see `hardening_weight`, recorded as the score of the nodes of the search tree.
+/// It is None if the labels of `np` do not come from a speedup.
+fn hardening_score(np : &Problem, kept : &[Label], coloring : Option<usize>) -> Option<usize> {
+    np.mapping_label_oldlabels.as_ref()?;
+    let map : HashMap<_,_> = np.mapping_label_generators().into_iter().collect();
+    let colors = coloring.map(|_| hardening_colors(np));
+    Some(hardening_weight(&map, colors.as_deref(), kept))
+}
+
+fn automatic_upper_bound_rec<F>(seen : &mut HashMap<String,usize>, failed : &mut FailedKeepSets, problems : &mut Vec<(Vec<Label>,Problem,Problem,String)>, best : &mut usize, schedule : &LabelSchedule, branching : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, policy : EvaluationPolicy, rules : &PruningRules, shard : Option<(&Shard, usize)>, stats : &mut SearchStats, handler : &mut F, eh: &mut EventHandler) -> BranchEnd where F : FnMut(usize, bool, Vec<(AutoOperation,Problem)>) {
+    if let Some(tree) = stats.tree.as_mut() {
+        let (kept, np, p, _) = problems.last().unwrap();
+        tree.enter(kept, hardening_score(np, kept, coloring), p, problems.len() - 1);
+    }
+    let end = automatic_upper_bound_rec_node(seen, failed, problems, best, schedule, branching, max_steps, coloring, coloring_passive, policy, rules, shard, stats, handler, eh);
+    if let Some(tree) = stats.tree.as_mut() {
+        tree.leave();
+    }
+    end
+}
+
+#[allow(clippy::too_many_arguments)]
+fn automatic_upper_bound_rec_node<F>(seen : &mut HashMap<String,usize>, failed : &mut FailedKeepSets, problems : &mut Vec<(Vec<Label>,Problem,Problem,String)>, best : &mut usize, schedule : &LabelSchedule, branching : usize, max_steps : usize, coloring : Option<usize>, coloring_passive : Option<usize>, policy : EvaluationPolicy, rules : &PruningRules, shard : Option<(&Shard, usize)>, stats : &mut SearchStats, handler : &mut F, eh: &mut EventHandler) -> BranchEnd where F : FnMut(usize, bool, Vec<(AutoOperation,Problem)>) {
     let mut send_sequence = |problems : &Vec<(Vec<Label>,Problem,Problem,String)>|{
         *best = problems.len();
+        if let Some((shard, _)) = shard {
+            shard.improve_bound(*best);
+        }
         let mut sequence = vec![];
         sequence.push((AutoOperation::Initial,problems[0].1.clone()));
         if problems[0].1 != problems[0].2 {
@@ -158,33 +458,44 @@ fn automatic_upper_bound_rec<F>(seen : &mut HashMap<String,usize>, problems : &m
             sequence.push((AutoOperation::Speedup,after_speedup.clone()));
             sequence.push((AutoOperation::Harden(kept_labels.clone()),after_harden.clone()));
         }
-        handler(problems.len() - 1,!problems.last().as_ref().unwrap().2.trivial_sets.as_ref().unwrap().is_empty(), sequence);
+        handler(problems.len() - 1,problems.last().as_ref().unwrap().2.trivial_sets.as_ref().is_some_and(|t| !t.is_empty()), sequence);
     };
 
     {
         let p_s = &problems.last().unwrap().3;
+        let unchecked = problems.last().unwrap().2.trivial_sets.is_none();
         if problems.len() >=2 {
             for i in (0..problems.len()-2).rev() {
                 if &problems[i].3 == p_s {
-                    return;
+                    stats.checks_avoided += unchecked as usize;
+                    stats.mark(NodeOutcome::Pruned(PruneReason::Cycle));
+                    return BranchEnd::Cut;
                 }
             }
         }
-        if seen.contains_key(p_s) && seen[p_s] <= problems.len() {
-            return;
+        if (seen.contains_key(p_s) && seen[p_s] <= problems.len()) || shard.is_some_and(|(s, _)| s.was_seen(p_s, problems.len())) {
+            stats.checks_avoided += unchecked as usize;
+            stats.mark(NodeOutcome::Pruned(PruneReason::Seen));
+            return BranchEnd::Cut;
         }
         if problems.len() < 6 && seen.len() < 100_000 {
             seen.insert(p_s.clone(),problems.len());
+            if let Some((shard, _)) = shard {
+                shard.insert_seen(p_s, problems.len());
+            }
+        }
+
+        if rules.discards(&problems.last().unwrap().2, eh) {
+            stats.checks_avoided += unchecked as usize;
+            stats.rule_pruned += 1;
+            stats.mark(NodeOutcome::Pruned(PruneReason::Rule));
+            return BranchEnd::Cut;
         }
 
+        let budget = policy.triviality_budget(problems.len() > max_steps);
         let p = &mut problems.last_mut().unwrap().2;   
 
-        if p.trivial_sets.is_none() {
-            p.compute_triviality(eh);
-        }
-        if coloring.is_some() && p.coloring_sets.is_none() {
-            p.compute_coloring_solvability(eh);
-        }
+        stats.check(p, coloring, budget, eh);
 
         if let Some(outdegree) = p.orientation_given {
             if p.passive.finite_degree() == 2 {
@@ -197,19 +508,21 @@ fn automatic_upper_bound_rec<F>(seen : &mut HashMap<String,usize>, problems : &m
             }
         }
 
-        if p.trivial_sets.as_ref().unwrap().len() > 0 ||
+        if p.trivial_sets.as_ref().is_some_and(|t| !t.is_empty()) ||
            (p.orientation_trivial_sets.is_some() && p.orientation_trivial_sets.as_ref().unwrap().len() > 0) ||
            (coloring.is_some() && p.coloring_sets.is_some() && p.coloring_sets.as_ref().unwrap_or(&vec![]).len() >= coloring.unwrap()) ||
            (coloring.is_some() && p.orientation_coloring_sets.is_some() && p.orientation_coloring_sets.as_ref().unwrap_or(&vec![]).len() >= coloring.unwrap())
         {
             send_sequence(problems);
-            return;
+            stats.mark(NodeOutcome::Solved);
+            return BranchEnd::Solved;
         }
     }
     let p = &problems.last().unwrap().2;  
 
     if problems.len() > max_steps {
-        return;
+        stats.mark(NodeOutcome::Pruned(PruneReason::MaxSteps));
+        return BranchEnd::Failed;
     }
 
 
@@ -218,10 +531,13 @@ fn automatic_upper_bound_rec<F>(seen : &mut HashMap<String,usize>, problems : &m
     let mut np = p.speedup(eh);
     np.discard_useless_stuff(false, eh);
     np.sort_active_by_strength();
-    np.compute_triviality(eh);
-    if coloring.is_some() {
-        np.compute_coloring_solvability(eh);
+    let np_rules = rules.applied_to(&np, eh);
+    if np_rules.discards() {
+        stats.rule_pruned += 1;
+        stats.mark(NodeOutcome::Pruned(PruneReason::Rule));
+        return BranchEnd::Cut;
     }
+    stats.check(&mut np, coloring, policy.triviality_budget(problems.len() >= max_steps), eh);
 
     if let Some(outdegree) = np.orientation_given {
         if np.passive.finite_degree() == 2 {
@@ -234,40 +550,97 @@ fn automatic_upper_bound_rec<F>(seen : &mut HashMap<String,usize>, problems : &m
         }
     }
 
-    if np.trivial_sets.as_ref().unwrap().len() > 0 ||
+    if np.trivial_sets.as_ref().is_some_and(|t| !t.is_empty()) ||
     (np.orientation_trivial_sets.is_some() && np.orientation_trivial_sets.as_ref().unwrap().len() > 0) ||
     (coloring.is_some() && np.coloring_sets.is_some() && np.coloring_sets.as_ref().unwrap_or(&vec![]).len() >= coloring.unwrap()) ||
     (coloring.is_some() && np.orientation_coloring_sets.is_some() && np.orientation_coloring_sets.as_ref().unwrap_or(&vec![]).len() >= coloring.unwrap())
  {
         problems.push((np.labels(),np.clone(),np.clone(),np.to_string()));
         send_sequence(problems);
-        return;
+        stats.mark(NodeOutcome::Solved);
+        return BranchEnd::Solved;
     }
 
 
-    let candidates = best_hardenings(&np, branching, max_labels, coloring, eh);
+    let candidates = best_hardenings(&np, branching, schedule.at(problems.len()), coloring, eh);
+    let np_s = np.to_string();
+    let depth = problems.len();
     
-    for candidate in candidates.into_iter().take(branching) {
+    // the hardenings are computed before exploring them, so that with the eager policy they can be checked together, see `check_batch`
+    let mut kept = vec![];
+    let mut hardenings = vec![];
+    // branches dominated by a failed one count as failed
+    let mut end = BranchEnd::Failed;
+    for (branch, candidate) in candidates.into_iter().take(branching).enumerate() {
+        if let Some((shard, split_depth)) = shard {
+            if depth == split_depth && !shard.owns(branch) {
+                end = end.max(BranchEnd::Cut);
+                continue;
+            }
+            shard.adopt_bound(best);
+        }
         if *best <= problems.len() + 1 {
-            return;
+            stats.pruned_child(&np, &candidate, coloring, PruneReason::Bound);
+            return end.max(BranchEnd::Cut);
         } 
 
-        let tokeep = candidate.iter().cloned().collect();
+        if !np_rules.allows_hardening(&candidate) {
+            stats.rule_pruned += 1;
+            stats.pruned_child(&np, &candidate, coloring, PruneReason::Rule);
+            end = end.max(BranchEnd::Cut);
+            continue;
+        }
+        let tokeep : HashSet<Label> = candidate.iter().cloned().collect();
+        if failed.dominated(depth, &np_s, &tokeep) {
+            stats.dominance_pruned += 1;
+            stats.pruned_child(&np, &candidate, coloring, PruneReason::Dominated);
+            continue;
+        }
         let mut hardened = np.harden_keep(&tokeep, true);
         hardened.discard_useless_stuff(false, eh);
         hardened.sort_active_by_strength();
-        hardened.compute_triviality(eh);
-        if coloring.is_some() {
-            hardened.compute_coloring_solvability(eh);
+        kept.push((candidate, tokeep));
+        hardenings.push(hardened);
+    }
+    let eager = policy == EvaluationPolicy::Eager;
+    if eager {
+        Problem::compute_triviality_batch(&np, &mut hardenings, eh);
+    }
+
+    let mut remaining = hardenings.len();
+    for ((candidate, tokeep), mut hardened) in kept.into_iter().zip(hardenings) {
+        if let Some((shard, _)) = shard {
+            shard.adopt_bound(best);
+        }
+        if *best <= problems.len() + 1 {
+            stats.speculative_checks += if eager { remaining } else { 0 };
+            stats.pruned_child(&np, &candidate, coloring, PruneReason::Bound);
+            return end.max(BranchEnd::Cut);
+        } 
+        remaining -= 1;
+        // the siblings explored so far may have failed with a superset of the kept labels
+        if failed.dominated(depth, &np_s, &tokeep) {
+            stats.speculative_checks += eager as usize;
+            stats.dominance_pruned += 1;
+            stats.pruned_child(&np, &candidate, coloring, PruneReason::Dominated);
+            continue;
+        }
+        if eager {
+            // the triviality has been computed by the batch, and the coloring is checked here
+            stats.triviality_checks += 1;
+            stats.check(&mut hardened, coloring, None, eh);
         }
         let h_s = hardened.to_string();
 
-        problems.push((candidate,np.clone(),hardened.clone(),h_s));
-        automatic_upper_bound_rec(seen, problems, best, max_labels, branching, max_steps, coloring, coloring_passive, handler, eh);
+        problems.push((candidate,np.clone(),hardened,h_s));
+        let child = automatic_upper_bound_rec(seen, failed, problems, best, schedule, branching, max_steps, coloring, coloring_passive, policy, rules, shard, stats, handler, eh);
         problems.pop();
+        if child == BranchEnd::Failed {
+            failed.insert(depth, &np_s, tokeep);
+        }
+        end = end.max(child);
     }
-
-    
+    end
 }
 
 
@@ -479,6 +852,76 @@ fn biregular_graph_non_parallel(d1 : usize, d2 : usize, sz : usize) -> (Vec<Vec<
     }
 }
 
+#[cfg(test)]
+mod policy_tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    use super::{EvaluationPolicy, SearchStats};
+
+    #[test]
+    fn lazy_policy() {
+        let eh = &mut EventHandler::null();
+        let p = Problem::from_string("M U^2\nP^3\n\nM UP\nU^2").unwrap();
+        let mut results = vec![];
+        for policy in [EvaluationPolicy::Eager, EvaluationPolicy::Lazy] {
+            let mut stats = SearchStats::default();
+            let mut found = vec![];
+            p.autoub(4, 2, 3, None, None, policy, &mut stats, |len, trivial, sequence| {
+                found.push((len, trivial, sequence.iter().map(|(_, p)| p.to_string()).collect::<Vec<_>>()));
+            }, eh);
+            results.push((found, stats));
+        }
+        // the policies only change which problems are checked, not the sequences that are found
+        assert!(results[0].0.iter().any(|&(len, trivial, _)| trivial && len == 3));
+        assert_eq!(results[0].0, results[1].0);
+        assert!(results[1].1.triviality_checks <= results[0].1.triviality_checks);
+    }
+
+    #[test]
+    fn anytime_policy() {
+        let eh = &mut EventHandler::null();
+        let p = Problem::from_string("M U^2\nP^3\n\nM UP\nU^2").unwrap();
+        let mut stats = SearchStats::default();
+        let mut best = None;
+        p.autoub(4, 2, 3, None, None, EvaluationPolicy::Anytime(1), &mut stats, |len, trivial, sequence| {
+            assert!(sequence.last().unwrap().1.trivial_sets.as_ref().is_some_and(|t| !t.is_empty()));
+            if trivial {
+                best = Some(len);
+            }
+        }, eh);
+        assert_eq!(best, Some(3));
+        assert!(stats.partial_triviality_checks > 0);
+    }
+
+    #[test]
+    fn dominance_pruning() {
+        let eh = &mut EventHandler::null();
+        let p = Problem::from_string("M U^2\nP^3\n\nM UP\nU^2").unwrap();
+        let mut stats = SearchStats::default();
+        let mut best = None;
+        p.autoub(4, 4, 4, None, None, EvaluationPolicy::Lazy, &mut stats, |len, trivial, _| {
+            if trivial {
+                best = Some(len);
+            }
+        }, eh);
+        assert_eq!(best, Some(3));
+        assert!(stats.dominance_pruned > 0);
+    }
+
+    #[test]
+    fn frontier() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("M U^2\nP^3\n\nM UP\nU^2").unwrap();
+        p.compute_diagram(eh);
+        let frontier = p.autoub_frontier(4, 2, 4, None, None, EvaluationPolicy::Lazy, &mut SearchStats::default(), eh);
+        assert!(!frontier.is_empty());
+        assert_eq!(frontier.last().unwrap().steps, 3);
+        for w in frontier.windows(2) {
+            assert!(w[0].labels <= w[1].labels && w[0].steps > w[1].steps);
+        }
+    }
+}
 
 /* 
 #[cfg(test)]
@@ -0,0 +1,82 @@
+use std::collections::HashSet;
+
+use rayon::prelude::*;
+
+use crate::{constraint::Constraint, group::{Group, Label}, line::Degree, problem::Problem};
+
+use super::event::EventHandler;
+
+impl Constraint {
+    /// The lines of this maximized constraint restricted to the given labels, which are the maximal lines of the configurations
+    /// that use only these labels: each such line is included in a maximal line of this constraint, and hence in its restriction.
+    fn restricted_maximized(&self, labels: &HashSet<Label>) -> Constraint {
+        let mut c = self.edited(|g| Group(g.iter().filter(|l| labels.contains(l)).cloned().collect()));
+        c.discard_non_maximal_lines();
+        c.is_maximized = true;
+        c
+    }
+}
+
+impl Problem {
+    /// Computes the triviality of many problems in parallel. The problems must be hardenings of `parent`, as the siblings explored by autoub,
+    /// that is, the passive side of each problem must allow exactly the configurations of the passive side of `parent` that use only its labels.
+    /// If the passive side of `parent` has already been maximized, the maximized passive side of each problem is obtained by restricting it,
+    /// instead of maximizing each problem from scratch. The problems whose triviality is already known are skipped.
+    pub fn compute_triviality_batch(parent: &Problem, problems: &mut [Problem], eh: &mut EventHandler) {
+        let shared = parent.passive.is_maximized && parent.passive.degree != Degree::Finite(2);
+        eh.notify("triviality batch", 0, problems.len());
+        problems.par_iter_mut().filter(|p| p.trivial_sets.is_none()).for_each(|p| {
+            p.discard_stale_computed_stuff();
+            if shared && !p.passive.is_maximized {
+                p.passive = parent.passive.restricted_maximized(&p.passive.labels_appearing());
+                // the passive side is replaced by an equivalent one, so what has been computed is still valid
+                if p.computed_for.is_some() {
+                    p.mark_computed();
+                }
+            }
+            p.compute_triviality(&mut EventHandler::null());
+        });
+        eh.notify("triviality batch", problems.len(), problems.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashSet;
+
+    use crate::{algorithms::event::EventHandler, problem::Problem, serial::fix_problem};
+
+    #[test]
+    fn triviality_batch() {
+        let eh = &mut EventHandler::null();
+        let mut np = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap().speedup(eh);
+        fix_problem(&mut np, true, false, eh);
+        np.passive.maximize(eh);
+        let labels = np.labels();
+
+        let mut siblings: Vec<Problem> = labels
+            .iter()
+            .map(|removed| {
+                let keep: HashSet<_> = labels.iter().filter(|l| *l != removed).cloned().collect();
+                let mut h = np.harden_keep(&keep, true);
+                h.discard_useless_stuff(false, eh);
+                h
+            })
+            .filter(|h| !h.active.lines.is_empty() && !h.passive.lines.is_empty())
+            .collect();
+        assert!(siblings.len() > 1);
+        let mut expected = siblings.clone();
+        Problem::compute_triviality_batch(&np, &mut siblings, eh);
+
+        for (p, q) in siblings.iter().zip(expected.iter_mut()) {
+            q.compute_triviality(eh);
+            assert_eq!(p.trivial_sets, q.trivial_sets);
+            let mut passive = p.passive.canonical_lines();
+            passive.sort();
+            let mut maximized = q.passive.canonical_lines();
+            maximized.sort();
+            assert_eq!(passive, maximized);
+        }
+    }
+}
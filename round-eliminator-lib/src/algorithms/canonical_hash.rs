@@ -0,0 +1,163 @@
+use std::fmt::Write;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{constraint::Constraint, group::GroupType, line::Degree, problem::Problem};
+
+/// The version of the encoding hashed by `Problem::canonical_hash`. It is part of the encoding, and must be increased whenever the encoding changes,
+/// so that hashes computed by different versions never collide by accident.
+const ENCODING_VERSION: u32 = 1;
+
+const FNV_OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+const FNV_PRIME: u128 = 0x0000000001000000000000000000013b;
+
+/// The 128-bit FNV-1a hash of the bytes, which, differently from the hashers of the standard library, is fixed by its specification.
+pub fn fnv1a_128(bytes: &[u8]) -> u128 {
+    let mut h = Fnv1a128::default();
+    h.write(bytes);
+    h.0
+}
+
+/// Computes `fnv1a_128` of the concatenation of all the bytes written to it, without storing them.
+#[derive(Copy, Clone, Debug)]
+pub struct Fnv1a128(u128);
+
+impl Default for Fnv1a128 {
+    fn default() -> Self {
+        Fnv1a128(FNV_OFFSET)
+    }
+}
+
+impl Fnv1a128 {
+    pub fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= b as u128;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub fn finish(&self) -> CanonicalHash {
+        CanonicalHash(self.0)
+    }
+}
+
+/// A hash of a problem that is the same on all platforms, including wasm, and across versions of the crate, see `Problem::canonical_hash`.
+/// It is serialized as 32 hexadecimal digits, since javascript cannot represent it as a number.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct CanonicalHash(pub u128);
+
+impl std::fmt::Display for CanonicalHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:032x}", self.0)
+    }
+}
+
+impl std::str::FromStr for CanonicalHash {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 32 {
+            return Err("A canonical hash must have 32 hexadecimal digits");
+        }
+        u128::from_str_radix(s, 16).map(CanonicalHash).map_err(|_| "A canonical hash must have 32 hexadecimal digits")
+    }
+}
+
+impl From<CanonicalHash> for String {
+    fn from(h: CanonicalHash) -> String {
+        h.to_string()
+    }
+}
+
+impl TryFrom<String> for CanonicalHash {
+    type Error = &'static str;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+fn encode_degree(degree: Degree) -> String {
+    match degree {
+        Degree::Finite(d) => d.to_string(),
+        Degree::Star => "*".into(),
+    }
+}
+
+fn encode_constraint(out: &mut String, c: &Constraint) {
+    for line in c.canonical_lines() {
+        let mut parts = line.parts.iter().map(|part| {
+            let exponent = match part.gtype {
+                GroupType::Many(x) => x.to_string(),
+                GroupType::Star => "*".into(),
+            };
+            format!("{}^{}", part.group.iter().join(","), exponent)
+        });
+        writeln!(out, "{}", parts.join(" ")).unwrap();
+    }
+}
+
+impl Problem {
+    /// The text hashed by `canonical_hash`. It contains exactly what `Problem::eq` compares, written explicitly rather than with
+    /// `Debug` or serde, whose output may change between versions: the degrees, the given orientation, the labels with their names,
+    /// each name prefixed by its length, and the canonical lines of both sides, with labels written as numbers.
+    pub fn canonical_encoding(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "round-eliminator problem v{}", ENCODING_VERSION).unwrap();
+        writeln!(out, "degrees {} {}", encode_degree(self.active.degree), encode_degree(self.passive.degree)).unwrap();
+        writeln!(out, "orientation {}", self.orientation_given.map_or("-".into(), |o| o.to_string())).unwrap();
+        for (label, name) in self.mapping_label_text.iter().sorted() {
+            writeln!(out, "label {} {}:{}", label, name.len(), name).unwrap();
+        }
+        writeln!(out, "active").unwrap();
+        encode_constraint(&mut out, &self.active);
+        writeln!(out, "passive").unwrap();
+        encode_constraint(&mut out, &self.passive);
+        out
+    }
+
+    /// A 128-bit hash of `canonical_encoding`, so that equal problems, in the sense of `Problem::eq`, get the same hash
+    /// on all platforms and with all versions of the crate that use the same encoding.
+    pub fn canonical_hash(&self) -> CanonicalHash {
+        CanonicalHash(fnv1a_128(self.canonical_encoding().as_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::problem::Problem;
+
+    use super::{fnv1a_128, CanonicalHash, Fnv1a128};
+
+    #[test]
+    fn canonical_hash() {
+        // test vectors of the FNV specification
+        assert_eq!(fnv1a_128(b""), 0x6c62272e07bb014262b821756295c58d);
+        assert_eq!(fnv1a_128(b"a"), 0xd228cb696f1a8caf78912b704e4a8964);
+        let mut h = Fnv1a128::default();
+        h.write(b"a");
+        h.write(b"bc");
+        assert_eq!(h.finish(), CanonicalHash(fnv1a_128(b"abc")));
+
+        let p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        assert_eq!(
+            p.canonical_encoding(),
+            "round-eliminator problem v1\ndegrees 3 2\norientation -\nlabel 0 1:M\nlabel 1 1:U\nlabel 2 1:P\nactive\n0^1 1^2\n2^3\npassive\n0^1 1,2^1\n1^2\n"
+        );
+        // golden value, which must never change for the same encoding version
+        assert_eq!(p.canonical_hash().to_string(), "95bc8fc1a2152e4cd4f3478edbc00edd");
+
+        // the same problem with lines and parts in a different order
+        let q = Problem::from_string("M U U\nP^3\n\nU U\nPU M").unwrap();
+        assert_eq!(q.canonical_hash(), p.canonical_hash());
+        assert_ne!(Problem::from_string("M U U\nP P P\n\nM UP").unwrap().canonical_hash(), p.canonical_hash());
+
+        let h = p.canonical_hash();
+        assert_eq!(h.to_string().parse::<CanonicalHash>(), Ok(h));
+        assert_eq!(CanonicalHash::try_from(String::from(h)), Ok(h));
+        assert!("12".parse::<CanonicalHash>().is_err());
+    }
+}
@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use crate::{constraint::Constraint, group::Label, problem::Problem};
+
+impl Problem {
+    /// The problem as text that only depends on the constraints and on the names of the labels,
+    /// and not on the internal numbering of the labels or on the order in which lines and parts have been produced,
+    /// so that storing it in version control gives small diffs when the same problem is computed again.
+    /// Labels are ordered by name, the labels of each part and the parts of each line follow this order,
+    /// and lines are sorted and shown once. Parsing the result gives an equal problem.
+    pub fn to_canonical_text(&self) -> String {
+        let mapping: HashMap<Label, String> = self.mapping_label_text.iter().cloned().collect();
+        let order: HashMap<Label, usize> = self
+            .mapping_label_text
+            .iter()
+            .sorted_by(|(l1, t1), (l2, t2)| t1.cmp(t2).then(l1.cmp(l2)))
+            .enumerate()
+            .map(|(i, (l, _))| (*l, i))
+            .collect();
+        let lines = |constraint: &Constraint| {
+            let normalized = Constraint { lines: constraint.canonical_lines(), is_maximized: constraint.is_maximized, degree: constraint.degree };
+            normalized.lines_ordered(&order).into_iter().map(|line| line.to_string_ordered(&mapping, &order) + "\n").join("")
+        };
+        format!("{}\n{}", lines(&self.active), lines(&self.passive))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::problem::Problem;
+
+    #[test]
+    fn canonical_text() {
+        let p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        // the same problem, with labels numbered differently and lines and parts in a different order
+        let q = Problem::from_string("P^3\nU M U\n\nU U\nPU M").unwrap();
+        assert_ne!(p.mapping_label_text, q.mapping_label_text);
+        assert_eq!(p.to_canonical_text(), "M U^2\nP^3\n\nM PU\nU^2\n");
+        assert_eq!(p.to_canonical_text(), q.to_canonical_text());
+
+        let parsed = Problem::from_string(p.to_canonical_text()).unwrap();
+        assert_eq!(parsed, p);
+        assert_eq!(parsed.to_canonical_text(), p.to_canonical_text());
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use crate::{
+    group::{Group, GroupType, Label},
+    line::{Degree, Line},
+    part::Part,
+    problem::Problem,
+};
+
+use super::{event::EventHandler, max_clique::Graph};
+
+impl Problem {
+    /// Returns a largest family of labels that behave like colors:
+    /// a label of the family cannot be on both endpoints of an edge, two different labels of the family can,
+    /// and any permutation of the family, possibly together with a renaming of the labels outside the family, gives exactly the same problem.
+    /// For example, the labels of a coloring may each come with some auxiliary labels, which need to be swapped together with the colors.
+    /// The size of the family is a natural choice for the number of colors given as input to autolb.
+    /// If no two labels behave in this way, the result is empty.
+    /// It is required that the passive side has degree 2.
+    pub fn color_like_labels(&self, eh: &mut EventHandler) -> Result<Vec<Label>, &'static str> {
+        if self.passive.degree != Degree::Finite(2) {
+            return Err("It is required that the passive degree is 2.");
+        }
+        let mut p = self.clone();
+        p.passive.maximize(eh);
+
+        let used = p.active.labels_appearing();
+        let labels: Vec<Label> = p.labels().into_iter().filter(|l| used.contains(l)).collect();
+        let allowed = |a: Label, b: Label| {
+            let part = |l: Label| Part {
+                gtype: GroupType::ONE,
+                group: Group(vec![l]),
+            };
+            p.passive.includes(&Line {
+                parts: vec![part(a), part(b)],
+            })
+        };
+        // a renaming of the labels that swaps a and b, keeps the labels of `fixed`, and gives the same problem
+        let swappable = |a: Label, b: Label, fixed: &[Label]| {
+            let mut pinned: HashMap<Label, Label> = fixed.iter().map(|&l| (l, l)).collect();
+            pinned.insert(a, b);
+            pinned.insert(b, a);
+            p.find_automorphism(&pinned).is_some()
+        };
+
+        let colors: Vec<Label> = labels.iter().cloned().filter(|&l| !allowed(l, l)).collect();
+        let mut adj = vec![vec![]; colors.len()];
+        for (i, &a) in colors.iter().enumerate() {
+            for (j, &b) in colors.iter().enumerate().skip(i + 1) {
+                eh.notify("color family", i * colors.len() + j, colors.len() * colors.len());
+                if allowed(a, b) && swappable(a, b, &[]) {
+                    adj[i].push(j);
+                    adj[j].push(i);
+                }
+            }
+        }
+        if adj.iter().all(|v| v.is_empty()) {
+            return Ok(vec![]);
+        }
+        let mut family: Vec<Label> = Graph::from_adj(adj).max_clique().into_iter().map(|i| colors[i]).sorted().collect();
+        // pairwise swaps may each need a different renaming of the other labels of the family, so the family is checked again:
+        // the swaps of the first label with each of the others, keeping the rest of the family as it is, give all the permutations
+        while let Some(i) = (1..family.len()).find(|&i| {
+            let rest: Vec<Label> = family.iter().cloned().filter(|&l| l != family[0] && l != family[i]).collect();
+            !swappable(family[0], family[i], &rest)
+        }) {
+            family.remove(i);
+        }
+        if family.len() < 2 {
+            return Ok(vec![]);
+        }
+        Ok(family)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    #[test]
+    fn color_like_labels() {
+        let eh = &mut EventHandler::null();
+        let p = Problem::from_string("A A A\nB B B\nC C C\n\nA BC\nB C").unwrap();
+        assert_eq!(p.color_like_labels(eh).unwrap(), vec![0, 1, 2]);
+
+        let p = Problem::from_string("A A A\nB B B\nC C X\n\nA BCX\nB CX\nC X").unwrap();
+        assert_eq!(p.color_like_labels(eh).unwrap().len(), 2);
+
+        let p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        assert_eq!(p.color_like_labels(eh).unwrap(), vec![]);
+
+        // A and B can be swapped only together with X and Y
+        let p = Problem::from_string("A A X\nB B Y\n\nA B\nX X\nY Y").unwrap();
+        assert_eq!(p.color_like_labels(eh).unwrap(), vec![0, 2]);
+    }
+}
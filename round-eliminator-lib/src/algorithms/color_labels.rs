@@ -0,0 +1,110 @@
+use std::collections::HashSet;
+
+use itertools::Itertools;
+
+use crate::{algorithms::event::EventHandler, group::Label, problem::Problem};
+
+impl Problem {
+    /// Marks the labels that encode a coloring, for example the labels of a gadget that gives a coloring to the nodes in a lower bound.
+    /// The marks are kept by hardening and by relaxations, and after a speedup the new labels that are sets of color labels are color labels.
+    /// The automatic lower bound search never merges color labels, so that the coloring is not lost. Marking no labels removes the marks.
+    pub fn set_color_labels(&mut self, labels: &[Label]) -> Result<(), &'static str> {
+        self.check_labels(labels)?;
+        self.color_labels = if labels.is_empty() { None } else { Some(labels.iter().cloned().sorted().dedup().collect()) };
+        Ok(())
+    }
+
+    /// The color labels that are still labels of the problem.
+    pub fn color_labels(&self) -> Vec<Label> {
+        let labels: HashSet<Label> = self.labels().into_iter().collect();
+        self.color_labels.iter().flatten().cloned().filter(|l| labels.contains(l)).collect()
+    }
+
+    pub fn is_color_label(&self, label: Label) -> bool {
+        self.color_labels.as_ref().is_some_and(|v| v.contains(&label))
+    }
+
+    /// Marks as color labels the labels of the independent actions of the problem, see `compute_coloring_solvability`,
+    /// unless some labels are marked already. These are the labels through which a given coloring is used, so the automatic
+    /// lower bound search marks them when it is given a coloring.
+    pub(crate) fn set_color_labels_from_coloring(&mut self, eh: &mut EventHandler) {
+        if self.color_labels.is_some() {
+            return;
+        }
+        if self.coloring_sets.is_none() {
+            self.compute_coloring_solvability(eh);
+        }
+        let labels: Vec<Label> = self.coloring_sets.iter().flatten().flatten().cloned().sorted().dedup().collect();
+        if !labels.is_empty() {
+            self.color_labels = Some(labels);
+        }
+    }
+
+    /// The color labels of `new`, obtained from this problem with a speedup: the labels whose set of old labels contains only color labels.
+    pub(crate) fn color_labels_after_speedup(&self, new: &Problem) -> Option<Vec<Label>> {
+        self.color_labels.as_ref()?;
+        let labels: Vec<Label> = new
+            .mapping_label_oldlabels
+            .iter()
+            .flatten()
+            .filter(|(_, old)| !old.is_empty() && old.iter().all(|&l| self.is_color_label(l)))
+            .map(|(l, _)| *l)
+            .sorted()
+            .collect();
+        if labels.is_empty() {
+            None
+        } else {
+            Some(labels)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashMap;
+
+    use crate::{algorithms::event::EventHandler, problem::Problem, serial::AutoOperation};
+
+    #[test]
+    fn color_labels() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("A A X\nB B X\nC C X\n\nA BCX\nB CX\nX X").unwrap();
+        assert!(p.set_color_labels(&[7]).is_err());
+        p.set_color_labels(&[2, 1, 0]).unwrap();
+        assert_eq!(p.color_labels(), vec![0, 1, 2]);
+        assert!(p.is_color_label(1) && !p.is_color_label(3));
+
+        let hardened = p.harden_remove(2, false);
+        assert!(hardened.is_color_label(0));
+
+        let sped = p.speedup(eh);
+        let old: HashMap<_, _> = sped.mapping_label_oldlabels.clone().unwrap().into_iter().collect();
+        assert!(!sped.color_labels().is_empty());
+        for l in sped.labels() {
+            assert_eq!(sped.is_color_label(l), old[&l].iter().all(|&o| o != 3));
+        }
+
+        let mut relabeled = sped.clone();
+        let labels = relabeled.labels();
+        relabeled.relabel(&labels.iter().map(|&l| (l, labels.len() as u32 - 1 - l)).collect());
+        assert_eq!(relabeled.color_labels().len(), sped.color_labels().len());
+
+        // the search never merges color labels
+        p.autolb(3, 2, 1, 2, None, None, Default::default(), |_, sequence| {
+            for (op, _) in &sequence {
+                if let AutoOperation::Merge(merges, before) = op {
+                    assert!(merges.iter().all(|&(a, b)| !before.is_color_label(a) && !before.is_color_label(b)));
+                }
+            }
+        }, eh);
+
+        p.set_color_labels(&[]).unwrap();
+        assert!(p.color_labels.is_none());
+
+        // with a coloring, the labels of the independent actions are marked
+        let mut p = Problem::from_string("A A A\nB B B\nC C C\n\nA BC\nB AC\nC AB").unwrap();
+        p.set_color_labels_from_coloring(eh);
+        assert_eq!(p.color_labels(), vec![0, 1, 2]);
+    }
+}
@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::{Budget, Classification},
+    problem::Problem,
+    serial::fix_problem,
+};
+
+use super::event::EventHandler;
+
+/// How the classification of a problem changes when a proper coloring of the active side is given as input, see `coloring_reductions`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ColoringReductions {
+    /// The classification when no coloring is given.
+    pub uncolored: Classification,
+    /// For each number of colors, in increasing order, the classification when a coloring with that many colors is given.
+    pub colored: Vec<(usize, Classification)>,
+    /// The largest number of colors for which the classification differs from `uncolored`.
+    /// A coloring with fewer colors is also a coloring with more colors, so it changes the classification for all the smaller numbers of colors too.
+    pub helpful_up_to: Option<usize>,
+}
+
+/// The largest number of colors such that, given a proper coloring with that many colors, the problem can be solved in 0 rounds.
+fn sufficient_colors(p: &Problem, eh: &mut EventHandler) -> usize {
+    let mut p = p.clone();
+    p.passive.maximize(eh);
+    p.compute_coloring_solvability(eh);
+    p.coloring_sets.map_or(0, |sets| sets.len())
+}
+
+impl Problem {
+    /// Classifies the problem as `corpus::classify` does, both without coloring and given a proper coloring with c colors, for each c from `min_colors` to `max_colors`.
+    /// This tells whether a coloring with O(1) colors makes the problem easier, and from which number of colors it does not help anymore.
+    /// All the classifications are obtained from the same sequence of speedups: the coloring is given to the nodes of the active side,
+    /// so it can only be used after an even number of speedups, when these nodes are on the active side again.
+    pub fn coloring_reductions(&self, min_colors: usize, max_colors: usize, budget: Budget, eh: &mut EventHandler) -> Result<ColoringReductions, &'static str> {
+        if min_colors < 1 || min_colors > max_colors {
+            return Err("The range of the number of colors is not valid");
+        }
+        let mut uncolored = None;
+        let mut colored: Vec<(usize, Option<Classification>)> = (min_colors..=max_colors).map(|c| (c, None)).collect();
+
+        let mut p = self.clone();
+        fix_problem(&mut p, true, false, eh);
+        for step in 0.. {
+            eh.notify("coloring reductions", step, budget.max_steps);
+            let outcome = if p.active.lines.is_empty() || p.passive.lines.is_empty() {
+                Some(Classification::Unsolvable)
+            } else {
+                if p.trivial_sets.is_none() {
+                    p.compute_triviality(eh);
+                }
+                if !p.trivial_sets.as_ref().unwrap().is_empty() {
+                    Some(Classification::Rounds(step))
+                } else if step == budget.max_steps || p.labels().len() > budget.max_labels {
+                    Some(Classification::AtLeast(step + 1))
+                } else {
+                    None
+                }
+            };
+            if step % 2 == 0 && !matches!(outcome, Some(Classification::Rounds(_))) {
+                let sufficient = sufficient_colors(&p, eh);
+                for (c, classification) in colored.iter_mut() {
+                    if classification.is_none() && *c <= sufficient {
+                        *classification = Some(Classification::Rounds(step));
+                    }
+                }
+            }
+            if let Some(outcome) = outcome {
+                uncolored = Some(outcome);
+                for (_, classification) in colored.iter_mut() {
+                    classification.get_or_insert(outcome);
+                }
+                break;
+            }
+            p = p.speedup(eh);
+            fix_problem(&mut p, true, false, eh);
+        }
+
+        let uncolored = uncolored.unwrap();
+        let colored: Vec<(usize, Classification)> = colored.into_iter().map(|(c, classification)| (c, classification.unwrap())).collect();
+        let helpful_up_to = colored.iter().rev().find(|(_, classification)| *classification != uncolored).map(|(c, _)| *c);
+        Ok(ColoringReductions { uncolored, colored, helpful_up_to })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        algorithms::event::EventHandler,
+        corpus::{Budget, Classification},
+        problem::Problem,
+    };
+
+    #[test]
+    fn coloring_reductions() {
+        let eh = &mut EventHandler::null();
+        let budget = Budget { max_steps: 3, max_labels: 20 };
+
+        // 3-coloring is trivial given a 3-coloring, takes one round of the original graph given a 4-coloring, and is not helped by more colors
+        let p = Problem::from_string("A A\nB B\nC C\n\nA BC\nB C").unwrap();
+        let result = p.coloring_reductions(2, 5, budget, eh).unwrap();
+        assert_eq!(result.uncolored, Classification::AtLeast(4));
+        assert_eq!(
+            result.colored,
+            vec![(2, Classification::Rounds(0)), (3, Classification::Rounds(0)), (4, Classification::Rounds(2)), (5, Classification::AtLeast(4))]
+        );
+        assert_eq!(result.helpful_up_to, Some(4));
+
+        // a coloring does not help for a problem that is already trivial
+        let trivial = Problem::from_string("A A A\n\nA A").unwrap();
+        let result = trivial.coloring_reductions(1, 3, budget, eh).unwrap();
+        assert_eq!(result.uncolored, Classification::Rounds(0));
+        assert_eq!(result.helpful_up_to, None);
+
+        assert!(p.coloring_reductions(4, 3, budget, eh).is_err());
+    }
+}
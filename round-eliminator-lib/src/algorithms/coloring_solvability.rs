@@ -4,7 +4,7 @@ use itertools::Itertools;
 
 use crate::{
     algorithms::max_clique::Graph,
-    group::{Group, GroupType},
+    group::{Group, GroupType, Label},
     line::{Degree, Line},
     part::Part,
     problem::Problem,
@@ -15,6 +15,7 @@ use super::{event::EventHandler, max_clique::HyperGraph};
 impl Problem {
     /// Computes the number of independent actions. If that number is x, then given an x coloring it is possible to solve the problem in 0 rounds.
     pub fn compute_coloring_solvability(&mut self, eh: &mut EventHandler) {
+        self.discard_stale_computed_stuff();
         if self.passive.degree != Degree::Finite(2) {
             return self.compute_hypergraph_coloring_solvability(eh);
             //panic!("cannot compute coloring solvability if the passive side has degree different from 2");
@@ -24,69 +25,79 @@ impl Problem {
         }
         //self.passive.maximize(eh);
 
-        let active_sets = self.active.minimal_sets_of_all_choices();
-
-        let mut edges = vec![];
-
-        for (i, set1) in active_sets.iter().enumerate() {
-            for (j, set2) in active_sets.iter().enumerate() {
-                eh.notify(
-                    "coloring graph",
-                    active_sets.len() * i + j,
-                    active_sets.len() * active_sets.len(),
-                );
-                if i < j {
-                    let group1 = Group(set1.iter().cloned().sorted().collect());
-                    let group2 = Group(set2.iter().cloned().sorted().collect());
-                    let part1 = Part {
-                        gtype: GroupType::ONE,
-                        group: group1,
-                    };
-                    let part2 = Part {
-                        gtype: GroupType::ONE,
-                        group: group2,
-                    };
-
-                    let line = Line {
-                        parts: vec![part1, part2],
-                    };
-                    if self.passive.includes(&line) {
-                        edges.push((i, j));
-                    }
-                }
-            }
+        self.coloring_sets = self.coloring_sets_of(eh);
+        self.mark_computed();
+    }
+
+    /// The largest number of colors x such that, given an x coloring, it is possible to solve the problem in `rounds` rounds,
+    /// obtained by checking the coloring solvability of the problem after `rounds` speedups.
+    /// As in autoub, after an odd number of speedups the coloring is of the nodes of the passive side of this problem.
+    /// Returns None if the problem can be solved in `rounds` rounds without any coloring, or if the coloring solvability is unknown,
+    /// that is, if the passive side of the problem obtained after `rounds` speedups has degree *.
+    pub fn colors_for_rounds(&self, rounds: usize, eh: &mut EventHandler) -> Option<usize> {
+        let mut p = self.clone();
+        for _ in 0..rounds {
+            p = p.speedup(eh);
+            p.discard_useless_stuff(false, eh);
+        }
+        if p.trivial_sets.is_none() {
+            p.compute_triviality(eh);
         }
+        if !p.trivial_sets.as_ref().unwrap().is_empty() {
+            return None;
+        }
+        if p.coloring_sets.is_none() {
+            p.compute_coloring_solvability(eh);
+        }
+        p.coloring_sets.map(|sets| sets.len())
+    }
 
-        if edges.is_empty() {
-            self.coloring_sets = Some(vec![]);
+    pub fn compute_hypergraph_coloring_solvability(&mut self, eh: &mut EventHandler) {
+        self.discard_stale_computed_stuff();
+        if self.coloring_sets.is_some() {
+            panic!("coloring solvability has been computed already");
+        }
+        if self.passive.degree == Degree::Star {
+            // the number of nodes sharing a passive node is not bounded, so the coloring solvability is left unknown
             return;
         }
 
-        let n = active_sets.len();
-        let mut adj = vec![vec![]; n];
-        for (a, b) in edges {
-            adj[a].push(b);
-            adj[b].push(a);
+        self.passive.maximize(eh);
+
+        self.coloring_sets = self.coloring_sets_of(eh);
+        self.mark_computed();
+    }
+
+    /// The coloring sets computed by `compute_coloring_solvability`, for a problem whose passive side has already been maximized if its degree is not 2.
+    /// Returns None if the passive side has degree *, since then the coloring solvability is unknown.
+    pub(crate) fn coloring_sets_of(&self, eh: &mut EventHandler) -> Option<Vec<Vec<Label>>> {
+        match self.passive.degree {
+            Degree::Finite(2) => Some(self.graph_coloring_sets(eh)),
+            Degree::Finite(_) => Some(self.hypergraph_coloring_sets(eh)),
+            Degree::Star => None,
+        }
+    }
+
+    fn graph_coloring_sets(&self, eh: &mut EventHandler) -> Vec<Vec<Label>> {
+        let graph = self.neighborhood_graph(eh).unwrap();
+
+        if graph.edges.iter().all(|(a, b)| a == b) {
+            return vec![];
         }
 
-        let g = Graph::from_adj(adj);
+        let active_sets = &graph.vertices;
+        let g = Graph::from_adj(graph.adjacency());
         eh.notify("clique", 1, 1);
         let mut coloring_sets: Vec<_> = g
             .max_clique()
             .into_iter()
-            .map(|x| active_sets[x].iter().cloned().sorted().collect())
+            .map(|x| active_sets[x].clone())
             .collect();
         coloring_sets.sort();
-        self.coloring_sets = Some(coloring_sets);
+        coloring_sets
     }
 
-    pub fn compute_hypergraph_coloring_solvability(&mut self, eh: &mut EventHandler) {
-        if self.coloring_sets.is_some() {
-            panic!("coloring solvability has been computed already");
-        }
-
-        self.passive.maximize(eh);
-
+    fn hypergraph_coloring_sets(&self, eh: &mut EventHandler) -> Vec<Vec<Label>> {
         let active_sets : Vec<_> = self.active.minimal_sets_of_all_choices().into_iter().flat_map(|set|std::iter::repeat(set).take(self.passive.finite_degree()-1)).enumerate().collect();
 
         let mut hyperedges = vec![];
@@ -113,8 +124,7 @@ impl Problem {
         }
 
         if hyperedges.is_empty() {
-            self.coloring_sets = Some(vec![]);
-            return;
+            return vec![];
         }
 
         let active_sets : HashMap<_,_> = active_sets.into_iter().collect();
@@ -126,7 +136,7 @@ impl Problem {
             .map(|x| active_sets[&x].iter().cloned().sorted().collect())
             .collect();
         coloring_sets.sort();
-        self.coloring_sets = Some(coloring_sets);
+        coloring_sets
     }
 }
 
@@ -154,6 +164,21 @@ mod tests {
         assert!(p.coloring_sets.unwrap().len() < 2);
     }
 
+    #[test]
+    fn colors_for_rounds() {
+        let eh = &mut EventHandler::null();
+        let p = Problem::from_string("A A A\nB B B\nC C C\n\nA BC\nB C").unwrap();
+        assert_eq!(p.colors_for_rounds(0, eh), Some(3));
+
+        let mut q = p.speedup(eh);
+        q.discard_useless_stuff(false, eh);
+        q.compute_coloring_solvability(eh);
+        assert_eq!(p.colors_for_rounds(1, eh), Some(q.coloring_sets.unwrap().len()));
+
+        let p = Problem::from_string("A AB AB\n\nA A\nB B").unwrap();
+        assert_eq!(p.colors_for_rounds(0, eh), None);
+    }
+
     /*#[test]
     #[should_panic]
     fn coloring_hypergraph() {
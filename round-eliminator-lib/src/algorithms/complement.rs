@@ -0,0 +1,107 @@
+use itertools::Itertools;
+
+use crate::{
+    constraint::Constraint,
+    group::{Exponent, Group, GroupType, Label},
+    line::{Degree, Line},
+    part::Part,
+    problem::Problem,
+};
+
+/// Configurations are enumerated explicitly, so the complement is computed only if there are at most this many.
+const MAX_CONFIGURATIONS: usize = 100_000;
+
+/// All multisets of the given size of elements of `labels`, each one sorted.
+fn multisets(labels: &[Label], size: usize) -> Vec<Vec<Label>> {
+    if size == 0 {
+        return vec![vec![]];
+    }
+    let mut result = vec![];
+    for (i, &l) in labels.iter().enumerate() {
+        for mut rest in multisets(&labels[i..], size - 1) {
+            rest.insert(0, l);
+            result.push(rest);
+        }
+    }
+    result
+}
+
+fn configuration(multiset: &[Label]) -> Line {
+    let parts = multiset
+        .iter()
+        .dedup_with_count()
+        .map(|(count, &l)| Part {
+            gtype: GroupType::Many(count as Exponent),
+            group: Group(vec![l]),
+        })
+        .collect();
+    Line { parts }
+}
+
+impl Constraint {
+    /// The constraint that allows exactly the configurations of labels in `labels` that this constraint forbids.
+    fn complement(&self, labels: &[Label]) -> Result<Constraint, &'static str> {
+        let Degree::Finite(d) = self.degree else {
+            return Err("The complement can be computed only for finite degrees");
+        };
+        // the number of multisets is binomial(labels + d - 1, d)
+        let mut count = 1usize;
+        for i in 0..d {
+            count = count.saturating_mul(labels.len() + i) / (i + 1);
+            if count > MAX_CONFIGURATIONS {
+                return Err("There are too many configurations to compute the complement");
+            }
+        }
+        let lines = multisets(labels, d)
+            .into_iter()
+            .map(|m| configuration(&m))
+            .filter(|c| !self.lines.iter().any(|line| line.includes(c)))
+            .collect::<Vec<_>>();
+        if lines.is_empty() {
+            return Err("The complement is empty, since the constraint allows all configurations");
+        }
+        Ok(Constraint {
+            lines,
+            is_maximized: false,
+            degree: self.degree,
+        })
+    }
+}
+
+impl Problem {
+    /// Returns the problem whose passive configurations are exactly the ones forbidden by this problem,
+    /// and, if `active` is true, whose active configurations are exactly the forbidden ones as well.
+    /// The labels, and their text, are the same as the ones of this problem.
+    pub fn complement(&self, active: bool) -> Result<Problem, &'static str> {
+        let labels = self.labels();
+        let mut p = self.clone();
+        p.discard_computed_stuff();
+        p.passive = self.passive.complement(&labels)?;
+        if active {
+            p.active = self.active.complement(&labels)?;
+        }
+        p.annotations = self.annotations_for_derived();
+        p.discard_stale_annotations();
+        Ok(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::problem::Problem;
+
+    #[test]
+    fn complement() {
+        let p = Problem::from_string("A A A\nB B B\n\nA B").unwrap();
+        let c = p.complement(false).unwrap();
+        assert_eq!(format!("{}", c), "A^3\nB^3\n\nA^2\nB^2\n");
+        assert_eq!(c.complement(false).unwrap().passive.canonical_lines(), p.passive.canonical_lines());
+
+        let c = p.complement(true).unwrap();
+        assert_eq!(format!("{}", c), "A^2 B\nA B^2\n\nA^2\nB^2\n");
+
+        assert!(Problem::from_string("A *\n\nA A").unwrap().complement(true).is_err());
+        assert!(Problem::from_string("A B B\n\nAB AB").unwrap().complement(false).is_err());
+    }
+}
@@ -0,0 +1,139 @@
+use crate::{
+    group::{GroupType, Label},
+    line::Line,
+};
+
+/// The configurations represented by a line, see `Line::configurations`.
+pub struct Configurations<'a> {
+    line: &'a Line,
+    /// For each part, the positions in its group of the chosen labels, in non-decreasing order.
+    choices: Vec<Vec<usize>>,
+    done: bool,
+}
+
+impl Iterator for Configurations<'_> {
+    type Item = Vec<Label>;
+
+    fn next(&mut self) -> Option<Vec<Label>> {
+        if self.done {
+            return None;
+        }
+        let current = self
+            .line
+            .parts
+            .iter()
+            .zip(self.choices.iter())
+            .flat_map(|(part, choice)| choice.iter().map(|&i| part.group[i]))
+            .collect();
+
+        // advance the last part that can still advance, and restart the ones after it
+        self.done = true;
+        for (part, choice) in self.line.parts.iter().zip(self.choices.iter_mut()).rev() {
+            let k = part.group.len();
+            if let Some(i) = choice.iter().rposition(|&x| x + 1 < k) {
+                let next = choice[i] + 1;
+                choice[i..].iter_mut().for_each(|x| *x = next);
+                self.done = false;
+                break;
+            }
+            choice.iter_mut().for_each(|x| *x = 0);
+        }
+        Some(current)
+    }
+}
+
+impl Line {
+    /// Lazily lists the configurations allowed by the line, each given by its labels, in the order of the parts.
+    /// The labels chosen from the same part are in non-decreasing order, so each configuration is listed once for each way of
+    /// assigning its labels to the parts, that is, exactly once if the groups of the parts are disjoint.
+    /// Panics if the line contains a star.
+    pub fn configurations(&self) -> Configurations<'_> {
+        let mut choices = vec![];
+        let mut empty = false;
+        for part in &self.parts {
+            let GroupType::Many(n) = part.gtype else {
+                panic!("the configurations of a line with a star cannot be listed");
+            };
+            empty |= n > 0 && part.group.is_empty();
+            choices.push(vec![0; n as usize]);
+        }
+        Configurations { line: self, choices, done: empty }
+    }
+
+    /// The number of items returned by `configurations`, computed without listing them, or None if it does not fit in a usize.
+    /// Panics if the line contains a star.
+    pub fn configuration_count(&self) -> Option<usize> {
+        let mut count = Some(1usize);
+        let mut empty = false;
+        for part in &self.parts {
+            let GroupType::Many(n) = part.gtype else {
+                panic!("the configurations of a line with a star cannot be counted");
+            };
+            let multisets = multiset_count(part.group.len(), n as usize);
+            empty |= multisets == Some(0);
+            count = count.zip(multisets).and_then(|(count, multisets)| count.checked_mul(multisets));
+        }
+        // a part without configurations leaves none, even if the others have too many to be counted
+        if empty {
+            Some(0)
+        } else {
+            count
+        }
+    }
+}
+
+/// The number of multisets of size n of a set of size k, that is, C(k - 1 + n, n), or None if it does not fit in a usize.
+fn multiset_count(k: usize, n: usize) -> Option<usize> {
+    if n == 0 {
+        return Some(1);
+    }
+    if k == 0 {
+        return Some(0);
+    }
+    // C(m + r, r) for increasing r never decreases, so no intermediate result is larger than the final one,
+    // and each product fits in a u128 if the previous result fits in a usize
+    let r = n.min(k - 1);
+    let m = (k - 1) as u128 + (n - r) as u128;
+    let mut multisets: u128 = 1;
+    for i in 1..=r as u128 {
+        multisets = multisets * (m + i) / i;
+        if multisets > usize::MAX as u128 {
+            return None;
+        }
+    }
+    Some(multisets as usize)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::{HashMap, HashSet};
+
+    use crate::{group::Group, line::Line};
+
+    #[test]
+    fn configurations() {
+        let mut h = HashMap::new();
+        let line = Line::parse("AB^2 C CD^3", &mut h).unwrap();
+        let all: Vec<_> = line.configurations().collect();
+        assert_eq!(all.len(), 3 * 4);
+        assert_eq!(line.configuration_count(), Some(all.len()));
+        assert_eq!(all.iter().collect::<HashSet<_>>().len(), all.len());
+        assert!(all.iter().all(|c| c.len() == 6));
+
+        let line = Line::parse("AB ABC", &mut h).unwrap();
+        let sorted: HashSet<Vec<_>> = line.configurations().map(|mut c| { c.sort(); c }).collect();
+        assert_eq!(line.configuration_count(), Some(6));
+        assert_eq!(sorted.len(), 5);
+
+        let line = Line::parse("ABCDEFGHIJ^200 KLMNOPQRST^200", &mut h).unwrap();
+        assert_eq!(line.configuration_count(), None);
+        assert_eq!(line.configurations().take(3).count(), 3);
+        let mut empty = line.clone();
+        empty.parts[0].group = Group(vec![]);
+        assert_eq!(empty.configuration_count(), Some(0));
+
+        let line = Line::parse("AB^65535", &mut h).unwrap();
+        assert_eq!(line.configuration_count(), Some(65536));
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use crate::{
+    constraint::Constraint,
+    group::{Group, GroupType, Label},
+    line::Line,
+    part::Part,
+};
+
+impl Constraint {
+    /// The lines of the constraint, each describing the configurations obtained by picking one label from each group.
+    pub fn iter_lines(&self) -> impl Iterator<Item = &'_ Line> {
+        self.lines.iter()
+    }
+
+    /// Whether the configuration, given as one label for each position, is allowed. The constraint does not need to be maximized.
+    pub fn allows(&self, configuration: &[Label]) -> bool {
+        let mut line = Line {
+            parts: configuration
+                .iter()
+                .map(|&label| Part { gtype: GroupType::ONE, group: Group(vec![label]) })
+                .collect(),
+        };
+        line.normalize();
+        line.degree() == self.degree && self.lines.iter().any(|l| l.includes(&line))
+    }
+
+    /// The constraint that allows the configurations allowed by at least one of the two constraints.
+    pub fn union(&self, other: &Constraint) -> Result<Constraint, &'static str> {
+        if self.degree != other.degree {
+            return Err("The constraints have different degrees");
+        }
+        let mut c = self.clone();
+        for line in &other.lines {
+            c.add_line_and_discard_non_maximal(line.clone());
+        }
+        c.is_maximized = false;
+        Ok(c)
+    }
+
+    /// The constraint that allows the configurations allowed by both constraints.
+    /// The result lists every allowed configuration, so it may be large, and lines with stars are not supported.
+    pub fn intersection(&self, other: &Constraint) -> Result<Constraint, &'static str> {
+        if self.degree != other.degree {
+            return Err("The constraints have different degrees");
+        }
+        if self.lines.iter().chain(other.lines.iter()).any(|line| line.has_star()) {
+            return Err("The intersection of constraints containing stars is not supported");
+        }
+        let mut c = Constraint { lines: vec![], is_maximized: false, degree: self.degree };
+        for line in self.all_choices(true) {
+            if other.lines.iter().any(|l| l.includes(&line)) {
+                c.add_line_and_discard_non_maximal(line);
+            }
+        }
+        Ok(c)
+    }
+
+    /// The constraint that allows the configurations that only use the given labels.
+    pub fn restricted_to(&self, labels: &[Label]) -> Constraint {
+        self.edited(|g| Group(g.0.iter().cloned().filter(|l| labels.contains(l)).collect()))
+    }
+
+    /// Replaces each label by its image under `mapping`, labels that are not in the mapping are kept.
+    /// Mapping different labels to the same label gives a relaxation of the constraint, as in `relax`.
+    pub fn renamed(&self, mapping: &HashMap<Label, Label>) -> Constraint {
+        let mut c = self.edited(|g| {
+            let mut v: Vec<Label> = g.0.iter().map(|l| *mapping.get(l).unwrap_or(l)).collect();
+            v.sort_unstable();
+            v.dedup();
+            Group(v)
+        });
+        c.discard_non_maximal_lines();
+        c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::{HashMap, HashSet};
+
+    use crate::problem::Problem;
+
+    #[test]
+    fn constraint_algebra() {
+        // A=0, B=1, C=2
+        let p = Problem::from_string("A B\nC C\n\nAB C\nAB AB").unwrap();
+        let q = Problem::from_string("A B\nC C\n\nA BC\nC C").unwrap();
+        assert!(p.passive.allows(&[1, 2]));
+        assert!(!p.passive.allows(&[2, 2]));
+        assert!(!p.passive.allows(&[0]));
+        assert_eq!(p.passive.iter_lines().count(), 2);
+
+        let union = p.passive.union(&q.passive).unwrap();
+        let intersection = p.passive.intersection(&q.passive).unwrap();
+        for a in 0..3 {
+            for b in 0..3 {
+                let (x, y) = (p.passive.allows(&[a, b]), q.passive.allows(&[a, b]));
+                assert_eq!(union.allows(&[a, b]), x || y);
+                assert_eq!(intersection.allows(&[a, b]), x && y);
+            }
+        }
+
+        let restricted = p.passive.restricted_to(&[0, 2]);
+        assert_eq!(restricted.labels_appearing(), HashSet::from([0, 2]));
+        assert!(restricted.allows(&[0, 0]) && restricted.allows(&[0, 2]) && !restricted.allows(&[1, 2]));
+
+        let renamed = p.passive.renamed(&HashMap::from([(1, 0)]));
+        assert_eq!(renamed.labels_appearing(), HashSet::from([0, 2]));
+        assert!(renamed.allows(&[0, 0]) && renamed.allows(&[0, 2]));
+        let r = Problem::from_string("A B C\n\nABC ABC").unwrap();
+        assert!(p.passive.union(&r.active).is_err());
+    }
+}
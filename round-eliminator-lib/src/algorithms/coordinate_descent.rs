@@ -0,0 +1,172 @@
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    group::Label,
+    problem::Problem,
+    serial::{fix_problem, AutoOperation},
+};
+
+use super::event::EventHandler;
+
+/// The parameters of `coordinate_descent`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DescentParams {
+    /// The largest number of operations, merges and hardenings together.
+    pub max_steps: usize,
+    /// The descent stops as soon as the problem has at most this many labels.
+    pub min_labels: usize,
+    /// The number of active lines of the sub-problem used to score a candidate, or 0 to use all of them.
+    pub sample_lines: usize,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Coordinate {
+    Merge,
+    Harden,
+}
+
+/// A candidate operation of `coordinate_descent`, which is cheap to store, while the problem it gives is computed only when the candidate is evaluated.
+#[derive(Copy, Clone, Debug)]
+enum Move {
+    Merge(Label, Label),
+    Harden(Label),
+}
+
+impl Move {
+    /// The candidates of the given kind for a problem with the given labels.
+    fn candidates(coordinate: Coordinate, labels: &[Label]) -> Box<dyn Iterator<Item = Move> + '_> {
+        match coordinate {
+            Coordinate::Merge => Box::new(
+                labels
+                    .iter()
+                    .flat_map(move |&from| labels.iter().filter(move |&&to| from < to).map(move |&to| Move::Merge(from, to))),
+            ),
+            Coordinate::Harden => Box::new(labels.iter().map(|&removed| Move::Harden(removed))),
+        }
+    }
+
+    fn count(coordinate: Coordinate, labels: usize) -> usize {
+        match coordinate {
+            Coordinate::Merge => labels * labels.saturating_sub(1) / 2,
+            Coordinate::Harden => labels,
+        }
+    }
+
+    fn apply(self, p: &Problem) -> Problem {
+        match self {
+            Move::Merge(from, to) => p.relax_merge(from, to),
+            Move::Harden(removed) => p.harden_keep(&p.labels().into_iter().filter(|&l| l != removed).collect(), false),
+        }
+    }
+
+    fn operation(self, p: &Problem) -> AutoOperation {
+        match self {
+            Move::Merge(from, to) => AutoOperation::Merge(vec![(from, to)], p.clone()),
+            Move::Harden(removed) => AutoOperation::Harden(p.labels().into_iter().filter(|&l| l != removed).sorted().collect()),
+        }
+    }
+}
+
+/// A cheap estimate of how complicated the problem becomes after a speedup: the number of labels, and then the number of lines,
+/// obtained by performing a speedup on the problem restricted to `sample_lines` active lines, evenly spaced.
+fn descent_score(p: &Problem, sample_lines: usize, eh: &mut EventHandler) -> (usize, usize) {
+    let mut sample = p.clone();
+    let lines = &p.active.lines;
+    if sample_lines > 0 && lines.len() > sample_lines {
+        sample.active.lines = (0..sample_lines).map(|i| lines[i * lines.len() / sample_lines].clone()).collect();
+        sample.active.is_maximized = false;
+        sample.discard_computed_stuff();
+        fix_problem(&mut sample, false, false, eh);
+    }
+    let mut next = sample.speedup(eh);
+    fix_problem(&mut next, false, false, eh);
+    (next.labels().len(), next.active.lines.len() + next.passive.lines.len())
+}
+
+impl Problem {
+    /// Simplifies the problem by alternating between the two operations that are usually applied by hand:
+    /// merging two labels, which gives an easier problem, and removing a label, which gives a harder one.
+    /// At each step, all the candidates of the current kind are tried, and the one with the best `descent_score` is applied.
+    /// Merges that make the problem zero round solvable are never applied, and if no candidate of the current kind is left,
+    /// the other kind is tried; the descent stops when neither kind has candidates left.
+    /// Since both kinds of operations are applied, the result is a simpler problem to study, and not a bound for this one.
+    /// The sequence starts with the initial problem.
+    pub fn coordinate_descent(&self, params: &DescentParams, eh: &mut EventHandler) -> Result<Vec<(AutoOperation, Problem)>, &'static str> {
+        let mut current = self.clone();
+        fix_problem(&mut current, true, false, eh);
+        current.compute_triviality(eh);
+        if !current.trivial_sets.as_ref().unwrap().is_empty() {
+            return Err("The problem is zero round solvable");
+        }
+
+        let mut sequence = vec![(AutoOperation::Initial, current.clone())];
+        let mut coordinate = Coordinate::Merge;
+        let mut failed = 0;
+        while sequence.len() <= params.max_steps && current.labels().len() > params.min_labels && failed < 2 {
+            let labels = current.labels();
+            // the candidates are generated and evaluated one at a time, so that only the best one is kept in memory
+            let mut best = None;
+            let total = Move::count(coordinate, labels.len());
+            for (i, m) in Move::candidates(coordinate, &labels).enumerate() {
+                eh.notify("coordinate descent", i, total);
+                let mut candidate = m.apply(&current);
+                fix_problem(&mut candidate, true, false, eh);
+                if candidate.active.lines.is_empty() || candidate.passive.lines.is_empty() {
+                    continue;
+                }
+                candidate.compute_triviality_with_certificate(eh);
+                if !candidate.trivial_sets.as_ref().unwrap().is_empty() {
+                    continue;
+                }
+                let score = descent_score(&candidate, params.sample_lines, eh);
+                if best.as_ref().is_none_or(|(s, _, _)| score < *s) {
+                    best = Some((score, m, candidate));
+                }
+            }
+
+            match best {
+                Some((_, m, candidate)) => {
+                    let op = m.operation(&current);
+                    current = candidate;
+                    sequence.push((op, current.clone()));
+                    failed = 0;
+                }
+                None => failed += 1,
+            }
+            coordinate = match coordinate {
+                Coordinate::Merge => Coordinate::Harden,
+                Coordinate::Harden => Coordinate::Merge,
+            };
+        }
+        Ok(sequence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem, serial::{fix_problem, AutoOperation}};
+
+    use super::DescentParams;
+
+    #[test]
+    fn coordinate_descent() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap().speedup(eh);
+        fix_problem(&mut p, true, true, eh);
+        let params = DescentParams { max_steps: 4, min_labels: 2, sample_lines: 3 };
+        let sequence = p.coordinate_descent(&params, eh).unwrap();
+        assert!(matches!(sequence[0].0, AutoOperation::Initial));
+        assert!(sequence.len() > 1 && sequence.len() <= params.max_steps + 1);
+        for window in sequence.windows(2) {
+            assert!(window[1].1.labels().len() < window[0].1.labels().len());
+        }
+        for (_, q) in &sequence {
+            assert!(q.trivial_sets.as_ref().unwrap().is_empty());
+        }
+
+        let trivial = Problem::from_string("A A A\n\nA A").unwrap();
+        assert!(trivial.coordinate_descent(&params, eh).is_err());
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    group::{Group, GroupType, Label},
+    line::Line,
+    part::Part,
+};
+
+/// A counting atom `[≥k S]`, satisfied by the configurations where at least `k` ports have a label in `S`.
+struct AtLeast {
+    count: usize,
+    labels: Vec<Label>,
+}
+
+impl AtLeast {
+    /// Parses the content of the brackets. Labels that do not appear in the rest of the line, given by `line`, cannot be counted and are dropped.
+    fn parse(atom: &str, mapping: &HashMap<String, Label>, line: &Line) -> Result<Self, &'static str> {
+        let rest = atom
+            .strip_prefix('≥')
+            .or_else(|| atom.strip_prefix(">="))
+            .ok_or("Counting constraints must be of the form [>=k labels]")?;
+        let (count, labels) = rest.trim_start().split_once(char::is_whitespace).ok_or("Missing labels in a counting constraint")?;
+        let count = count.parse().map_err(|_| "Invalid number")?;
+        let mut known = mapping.clone();
+        let part = Part::parse(labels.trim(), &mut known)?;
+        if part.gtype != GroupType::ONE {
+            return Err("Exponents and stars are not allowed in counting constraints");
+        }
+        let labels = part.group.0.into_iter().filter(|l| line.parts.iter().any(|part| part.group.contains(l))).collect();
+        Ok(AtLeast { count, labels })
+    }
+
+    fn satisfied_by(&self, configuration: &[Label]) -> bool {
+        configuration.iter().filter(|l| self.labels.contains(l)).count() >= self.count
+    }
+}
+
+impl Line {
+    /// Parses a line that may contain counting atoms such as `[≥2 A]` (or `[>=2 A]`), that restrict the configurations
+    /// of the rest of the line to the ones where at least 2 ports are labeled `A`.
+    /// For example, `AB^4 [≥3 A]` stands for `A^3 B` and `A^4`. Lines with counting atoms are expanded into
+    /// the configurations they allow, so they cannot contain stars.
+    pub fn parse_with_counting(line: &str, mapping: &mut HashMap<String, Label>) -> Result<Vec<Line>, &'static str> {
+        if !line.contains('[') {
+            return Ok(vec![Line::parse(line, mapping)?]);
+        }
+
+        let mut base = String::new();
+        let mut atoms = vec![];
+        let mut rest = line;
+        while let Some(start) = rest.find('[') {
+            base.push_str(&rest[..start]);
+            base.push(' ');
+            let end = rest[start..].find(']').ok_or("Missing ']'")? + start;
+            atoms.push(&rest[start + 1..end]);
+            rest = &rest[end + 1..];
+        }
+        base.push_str(rest);
+        if base.contains(']') {
+            return Err("Missing '['");
+        }
+
+        let line = Line::parse(&base, mapping)?;
+        if line.has_star() {
+            return Err("Counting constraints cannot be used in lines with a star");
+        }
+        let atoms: Vec<AtLeast> = atoms.into_iter().map(|atom| AtLeast::parse(atom.trim(), mapping, &line)).collect::<Result<_, _>>()?;
+
+        // a configuration is listed more than once only if the groups of some parts overlap
+        let mut seen = HashSet::new();
+        let mut lines = vec![];
+        for mut configuration in line.configurations() {
+            configuration.sort_unstable();
+            if seen.insert(configuration.clone()) && atoms.iter().all(|atom| atom.satisfied_by(&configuration)) {
+                let mut line = Line {
+                    parts: configuration
+                        .into_iter()
+                        .map(|label| Part { gtype: GroupType::ONE, group: Group(vec![label]) })
+                        .collect(),
+                };
+                line.normalize();
+                lines.push(line);
+            }
+        }
+        Ok(lines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::problem::Problem;
+
+    #[test]
+    fn counting_parser() {
+        let p = Problem::from_string("AB^4 [≥3 A]\n\nAB AB").unwrap();
+        let q = Problem::from_string("A^3 B\nA^4\n\nAB AB").unwrap();
+        assert_eq!(p.active.canonical_lines(), q.active.canonical_lines());
+
+        // majority: at least two ports are labeled with the color of the node
+        let p = Problem::from_string("R RB^2 [≥2 R]\nB RB^2 [>=2 B]\n\nRB RB").unwrap();
+        let q = Problem::from_string("R^3\nR^2 B\nB^3\nB^2 R\n\nRB RB").unwrap();
+        assert_eq!(p.active.canonical_lines(), q.active.canonical_lines());
+
+        // C does not appear in the first line, so it cannot be counted there
+        let p = Problem::from_string("C^3\nAB^3 [≥1 AC]\n\nABC ABC").unwrap();
+        let q = Problem::from_string("C^3\nA^3\nA^2 B\nA B^2\n\nABC ABC").unwrap();
+        assert_eq!(p.active.canonical_lines(), q.active.canonical_lines());
+
+        assert!(Problem::from_string("A B* [≥1 A]\n\nAB AB").is_err());
+        assert!(Problem::from_string("AB^2 [≥1 A\n\nAB AB").is_err());
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    group::{Group, GroupType, Label},
+    line::{Degree, Line},
+    part::Part,
+    problem::Problem,
+};
+
+/// The labels that a node of a cycle outputs towards the previous node and towards the next one.
+pub type CycleState = (Label, Label);
+
+/// How a problem behaves on large cycles, obtained by looking at the labelings of cycles as closed walks in an automaton,
+/// whose states are the configurations of the active side read in some direction, and where a state (a,b) can be followed by (c,d)
+/// if the passive side allows b and c on the same edge. Each closed walk of length n is a labeling of the cycle of length n.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum CycleBehavior {
+    /// The problem can be solved in O(log* n) rounds: there are closed walks through `state` of all lengths that are large enough,
+    /// since the lengths of `loops` have no common divisor, and `turn` goes from `state` to the same state read in the other direction.
+    /// Hence, the nodes can split the cycle into long enough segments, orient each of them locally, and pump the loops to fill each segment,
+    /// using `turn` where two segments with different orientations meet.
+    Flexible {
+        state: CycleState,
+        loops: Vec<Vec<CycleState>>,
+        turn: Vec<CycleState>,
+    },
+    /// No labeling can be pumped in both directions, so solving the problem requires Θ(n) rounds on the cycles where it can be solved.
+    /// Large enough cycles can be solved if and only if their length is a multiple of one of the periods, and each period comes with
+    /// a closed walk whose length is a multiple of it, that can be repeated to label such cycles.
+    Periodic { periods: Vec<(usize, Vec<CycleState>)> },
+    /// The automaton has no closed walks, so no cycle can be labeled.
+    Unsolvable,
+}
+
+/// A breadth first search from `from` that only visits the states in `allowed`, following the arrows backwards if `adj` is reversed.
+/// Returns the distance and the previous state in the search of all the visited states.
+fn bfs(adj: &[Vec<usize>], from: usize, allowed: &HashSet<usize>) -> HashMap<usize, (usize, usize)> {
+    let mut visited = HashMap::from([(from, (0, from))]);
+    let mut queue = VecDeque::from([from]);
+    while let Some(u) = queue.pop_front() {
+        let d = visited[&u].0;
+        for &v in &adj[u] {
+            if allowed.contains(&v) && !visited.contains_key(&v) {
+                visited.insert(v, (d + 1, u));
+                queue.push_back(v);
+            }
+        }
+    }
+    visited
+}
+
+/// The states of the path from the start of `search` to `to`, both included.
+fn path(search: &HashMap<usize, (usize, usize)>, to: usize) -> Vec<usize> {
+    let mut result = vec![to];
+    let mut cur = to;
+    while search[&cur].0 > 0 {
+        cur = search[&cur].1;
+        result.push(cur);
+    }
+    result.reverse();
+    result
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl Problem {
+    /// Finds the labelings of cycles that can be pumped, for a problem with active and passive degree 2, see `CycleBehavior`.
+    pub fn cycle_behavior(&self) -> Result<CycleBehavior, &'static str> {
+        if self.active.degree != Degree::Finite(2) || self.passive.degree != Degree::Finite(2) {
+            return Err("It is required that both the active and the passive degree are 2.");
+        }
+        let part = |l: Label| Part {
+            gtype: GroupType::ONE,
+            group: Group(vec![l]),
+        };
+
+        let mut states = vec![];
+        for line in &self.active.lines {
+            let (g1, g2) = match &line.parts[..] {
+                [p] => (&p.group, &p.group),
+                [p1, p2] => (&p1.group, &p2.group),
+                _ => unreachable!(),
+            };
+            for (&a, &b) in g1.iter().cartesian_product(g2.iter()) {
+                states.push((a, b));
+                states.push((b, a));
+            }
+        }
+        let states: Vec<CycleState> = states.into_iter().sorted().dedup().collect();
+        let index: HashMap<CycleState, usize> = states.iter().enumerate().map(|(i, s)| (*s, i)).collect();
+
+        let labels = self.labels();
+        let allowed: HashSet<(Label, Label)> = labels
+            .iter()
+            .cartesian_product(labels.iter())
+            .filter(|(&a, &b)| self.passive.includes(&Line { parts: vec![part(a), part(b)] }))
+            .map(|(&a, &b)| (a, b))
+            .collect();
+        let mut adj = vec![vec![]; states.len()];
+        let mut radj = vec![vec![]; states.len()];
+        let mut edges = vec![];
+        for (i, &(_, b)) in states.iter().enumerate() {
+            for (j, &(c, _)) in states.iter().enumerate() {
+                if allowed.contains(&(b, c)) {
+                    adj[i].push(j);
+                    radj[j].push(i);
+                    edges.push((i, j));
+                }
+            }
+        }
+
+        let g = petgraph::graph::DiGraph::<(), (), usize>::from_edges(edges.iter().cloned());
+        let components = petgraph::algo::kosaraju_scc(&g)
+            .into_iter()
+            .map(|c| c.into_iter().map(|x| x.index()).sorted().collect::<Vec<_>>())
+            .filter(|c| c.len() > 1 || adj[c[0]].contains(&c[0]))
+            .sorted();
+
+        let mut periods = vec![];
+        for component in components {
+            let inside: HashSet<usize> = component.iter().cloned().collect();
+            let reverse = |i: usize| index[&(states[i].1, states[i].0)];
+            // the walks are taken through a state whose reverse is in the same component, if possible
+            let reversible = component.iter().cloned().find(|&i| inside.contains(&reverse(i)));
+            let root = reversible.unwrap_or(component[0]);
+            let forward = bfs(&adj, root, &inside);
+            let backward = bfs(&radj, root, &inside);
+            // each arrow gives a closed walk through the root, and their lengths have the same gcd as all closed walks
+            let walks: Vec<Vec<usize>> = edges
+                .iter()
+                .filter(|(u, v)| inside.contains(u) && inside.contains(v))
+                .map(|&(u, v)| {
+                    let mut walk = path(&forward, u);
+                    let mut back = path(&backward, v);
+                    back.reverse();
+                    back.pop();
+                    walk.extend(back);
+                    walk
+                })
+                .sorted_by_key(|walk| walk.len())
+                .collect();
+            let period = walks.iter().fold(0, |g, walk| gcd(g, walk.len()));
+            let to_states = |walk: &[usize]| walk.iter().map(|&i| states[i]).collect::<Vec<_>>();
+
+            if let (1, Some(s)) = (period, reversible) {
+                let mut loops = vec![];
+                let mut g = 0;
+                for walk in walks.iter() {
+                    if g == 0 || gcd(g, walk.len()) < g {
+                        g = gcd(g, walk.len());
+                        loops.push(to_states(walk));
+                    }
+                    if g == 1 {
+                        break;
+                    }
+                }
+                return Ok(CycleBehavior::Flexible {
+                    state: states[s],
+                    loops,
+                    turn: to_states(&path(&forward, reverse(s))),
+                });
+            }
+            periods.push((period, to_states(&walks[0])));
+        }
+
+        if periods.is_empty() {
+            return Ok(CycleBehavior::Unsolvable);
+        }
+        periods.sort_by_key(|(period, walk)| (*period, walk.len()));
+        periods.dedup_by_key(|(period, _)| *period);
+        Ok(CycleBehavior::Periodic { periods })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::problem::Problem;
+
+    use super::CycleBehavior;
+
+    #[test]
+    fn cycle_behavior() {
+        let coloring = Problem::from_string("A A\nB B\nC C\n\nA BC\nB C").unwrap();
+        let CycleBehavior::Flexible { state, loops, turn } = coloring.cycle_behavior().unwrap() else {
+            panic!("3-coloring can be pumped");
+        };
+        assert_eq!(state, (0, 0));
+        assert_eq!(loops.iter().map(|l| l.len()).collect::<Vec<_>>(), vec![2, 3]);
+        assert!(loops.iter().all(|l| l[0] == state));
+        assert_eq!(turn, vec![(0, 0)]);
+
+        let mis = Problem::from_string("M M\nP O\n\nM PO\nO O").unwrap();
+        assert!(matches!(mis.cycle_behavior().unwrap(), CycleBehavior::Flexible { .. }));
+
+        let two_coloring = Problem::from_string("A A\nB B\n\nA B").unwrap();
+        assert_eq!(
+            two_coloring.cycle_behavior().unwrap(),
+            CycleBehavior::Periodic { periods: vec![(2, vec![(0, 0), (1, 1)])] }
+        );
+
+        // a consistent orientation exists on all cycles, but it cannot be reversed
+        let orientation = Problem::from_string("A B\n\nA B").unwrap();
+        assert_eq!(
+            orientation.cycle_behavior().unwrap(),
+            CycleBehavior::Periodic { periods: vec![(1, vec![(0, 1)])] }
+        );
+
+        let unsolvable = Problem::from_string("A A\n\nB B").unwrap();
+        assert_eq!(unsolvable.cycle_behavior().unwrap(), CycleBehavior::Unsolvable);
+
+        assert!(Problem::from_string("A A A\n\nA A").unwrap().cycle_behavior().is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::{BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{group::Label, problem::Problem, serial::AutoOperation};
+
+/// The algorithm given by an upper bound found by autoub, as a layered diagram.
+/// Level `i` contains the labels of the problem obtained after `i` speedups (and the hardening that follows), that are used by the algorithm.
+/// The last level contains the labels of the zero round solution. A label of level `i` is a set of labels of level `i-1`,
+/// and there is an edge from it to each of them: after one more round of communication, a node that would output the label
+/// can pick one of the successors, that is, it gets closer to an output label of the original problem.
+/// Swapping the sides also costs one round, see `swap_sides`, so it gives a level whose labels point to the same labels in the level before.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DecisionDiagram {
+    pub levels: Vec<Vec<(Label, String)>>,
+    /// Edges, each given by the level of its source, its source, and its target in the level before.
+    pub edges: Vec<(usize, Label, Label)>,
+}
+
+impl DecisionDiagram {
+    /// Builds the diagram of a sequence reported by autoub. The last problem must be zero round solvable, or solvable given a coloring.
+    pub fn from_upper_bound(sequence: &[(AutoOperation, Problem)]) -> Result<Self, &'static str> {
+        // each stage is a problem, and whether it has been obtained by swapping the sides of the one before
+        let mut stages: Vec<(&Problem, bool)> = vec![];
+        for (op, p) in sequence {
+            match op {
+                AutoOperation::Initial | AutoOperation::Speedup => stages.push((p, false)),
+                AutoOperation::SwapSides => stages.push((p, true)),
+                AutoOperation::Harden(_) => match stages.last_mut() {
+                    Some(last) => last.0 = p,
+                    None => return Err("The sequence does not start with a problem"),
+                },
+                AutoOperation::Merge(..) => return Err("Only sequences found by autoub are supported"),
+            }
+        }
+        let (last, _) = stages.last().ok_or("The sequence is empty")?;
+
+        let mut frontier: BTreeSet<Label> = match (&last.trivial_sets, &last.coloring_sets) {
+            (Some(trivial), _) if !trivial.is_empty() => trivial[0].iter().cloned().collect(),
+            (_, Some(coloring)) if !coloring.is_empty() => coloring.iter().flatten().cloned().collect(),
+            _ => return Err("The last problem of the sequence is not known to be solvable in zero rounds"),
+        };
+
+        let mut levels = vec![vec![]; stages.len()];
+        let mut edges = vec![];
+        for i in (0..stages.len()).rev() {
+            let (p, swapped) = stages[i];
+            let text: HashMap<_, _> = p.mapping_label_text.iter().cloned().collect();
+            levels[i] = frontier
+                .iter()
+                .map(|l| Ok((*l, text.get(l).ok_or("A label of the sequence has no name")?.clone())))
+                .collect::<Result<_, &'static str>>()?;
+            if i == 0 {
+                break;
+            }
+            let previous = stages[i - 1].0.labels();
+            let mut next = BTreeSet::new();
+            if swapped {
+                for &l in frontier.iter().filter(|l| previous.contains(l)) {
+                    edges.push((i, l, l));
+                    next.insert(l);
+                }
+            } else {
+                let oldlabels: HashMap<_, _> = p
+                    .mapping_label_oldlabels
+                    .as_ref()
+                    .ok_or("A problem of the sequence has not been obtained with a speedup")?
+                    .iter()
+                    .cloned()
+                    .collect();
+                for l in &frontier {
+                    let old = oldlabels.get(l).ok_or("A label of the sequence has not been obtained with a speedup")?;
+                    for &o in old.iter().filter(|o| previous.contains(o)) {
+                        edges.push((i, *l, o));
+                        next.insert(o);
+                    }
+                }
+            }
+            frontier = next;
+        }
+        Ok(DecisionDiagram { levels, edges })
+    }
+
+    pub fn to_dot(&self) -> String {
+        let rounds = self.levels.len() - 1;
+        let node = |level: usize, label: Label| format!("\"{}_{}\"", level, label);
+        let mut s = String::from("digraph algorithm {\n");
+        for (i, labels) in self.levels.iter().enumerate().rev() {
+            s.push_str(&format!("    subgraph cluster_{} {{\n        label=\"{} rounds\";\n", i, rounds - i));
+            for (l, text) in labels {
+                s.push_str(&format!("        {} [label=\"{}\"];\n", node(i, *l), text.replace('"', "\\\"")));
+            }
+            s.push_str("    }\n");
+        }
+        for (i, from, to) in &self.edges {
+            s.push_str(&format!("    {} -> {};\n", node(*i, *from), node(*i - 1, *to)));
+        }
+        s.push_str("}\n");
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        algorithms::{autoub::{EvaluationPolicy, SearchStats}, event::EventHandler},
+        problem::Problem,
+        serial::AutoOperation,
+    };
+
+    use super::DecisionDiagram;
+
+    #[test]
+    fn decision_diagram() {
+        let eh = &mut EventHandler::null();
+        let p = Problem::from_string("M U^2\nP^3\n\nM UP\nU^2").unwrap();
+        let mut best = None;
+        p.autoub(4, 2, 3, None, None, EvaluationPolicy::Eager, &mut SearchStats::default(), |len, trivial, seq| {
+            if trivial {
+                best = Some((len, seq));
+            }
+        }, eh);
+        let (len, sequence) = best.unwrap();
+        let diagram = DecisionDiagram::from_upper_bound(&sequence).unwrap();
+        assert_eq!(diagram.levels.len(), len + 1);
+        assert!(diagram.levels.iter().all(|level| !level.is_empty()));
+        for (i, from, to) in &diagram.edges {
+            assert!(diagram.levels[*i].iter().any(|(l, _)| l == from));
+            assert!(diagram.levels[*i - 1].iter().any(|(l, _)| l == to));
+        }
+        assert!(diagram.to_dot().starts_with("digraph algorithm {"));
+
+        // an upper bound found on the problem with the sides swapped takes one more round
+        let swapped = p.sequence_from_swapped(sequence.clone());
+        let diagram = DecisionDiagram::from_upper_bound(&swapped).unwrap();
+        assert_eq!(diagram.levels.len(), len + 2);
+        assert_eq!(diagram.levels[0], diagram.levels[1]);
+
+        let mut unnamed = sequence.clone();
+        unnamed.last_mut().unwrap().1.mapping_label_text.clear();
+        assert!(DecisionDiagram::from_upper_bound(&unnamed).is_err());
+
+        let merged = vec![(AutoOperation::Initial, p.clone()), (AutoOperation::Merge(vec![], p.clone()), p)];
+        assert!(DecisionDiagram::from_upper_bound(&merged).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::problem::Problem;
+
+use super::event::EventHandler;
+
+/// One step of the defective coloring ladder.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DefectiveRung {
+    pub defect: usize,
+    pub arbdefective: bool,
+}
+
+impl Problem {
+    /// Returns the d-defective c-coloring problem on graphs of degree `degree`,
+    /// where each node can have at most d neighbors of its own color.
+    /// If `arbdefective` is true, the edges between nodes of the same color must instead be oriented so that each node has outdegree at most d.
+    /// Label (xi) is used on edges that are fine for color i, and (yi) on edges that use the defect of color i.
+    pub fn defective_coloring(colors: usize, degree: usize, defect: usize, arbdefective: bool) -> Result<Problem, &'static str> {
+        if colors == 0 || degree == 0 {
+            return Err("The number of colors and the degree must be positive");
+        }
+        if defect > degree {
+            return Err("The defect cannot be larger than the degree");
+        }
+        let x = |i: usize| format!("(x{})", i);
+        let y = |i: usize| format!("(y{})", i);
+        let exp = |l: String, e: usize| match e {
+            0 => None,
+            1 => Some(l),
+            _ => Some(format!("{}^{}", l, e)),
+        };
+
+        let active = (1..=colors)
+            .map(|i| {
+                [exp(x(i), degree - defect), exp(y(i), defect)]
+                    .into_iter()
+                    .flatten()
+                    .join(" ")
+            })
+            .join("\n");
+
+        let others = |i: usize| (1..=colors).filter(|&j| j != i).map(|j| format!("{}{}", x(j), y(j))).join("");
+        let passive = (1..=colors)
+            .flat_map(|i| {
+                let (x_with, y_with) = if arbdefective {
+                    (format!("{}{}", y(i), others(i)), format!("{}{}{}", x(i), y(i), others(i)))
+                } else {
+                    (others(i), format!("{}{}", y(i), others(i)))
+                };
+                [(x(i), x_with), (y(i), y_with)]
+            })
+            .filter(|(_, with)| !with.is_empty())
+            .map(|(l, with)| format!("{} {}", l, with))
+            .join("\n");
+
+        Problem::from_string(format!("{}\n\n{}", active, passive))
+    }
+
+    /// Returns the defective and arbdefective c-coloring problems for all defects from 0 to `max_defect`, from the hardest to the easiest.
+    /// Each defective rung is a relaxation of the previous defective rung, and the same holds for arbdefective rungs.
+    /// Moreover, each arbdefective rung is a relaxation of the defective rung with the same defect.
+    pub fn defective_coloring_ladder(
+        colors: usize,
+        degree: usize,
+        max_defect: usize,
+    ) -> Result<Vec<(DefectiveRung, Problem)>, &'static str> {
+        let mut ladder = vec![];
+        for defect in 0..=max_defect.min(degree) {
+            for arbdefective in [false, true] {
+                let rung = DefectiveRung { defect, arbdefective };
+                ladder.push((rung, Problem::defective_coloring(colors, degree, defect, arbdefective)?));
+            }
+        }
+        Ok(ladder)
+    }
+}
+
+/// Returns the index of the first rung that can be solved in 0 rounds,
+/// either without any input, or, if `input_colors` is given, when a proper coloring with that many colors is given as input.
+pub fn first_zero_round_rung(
+    ladder: &[(DefectiveRung, Problem)],
+    input_colors: Option<usize>,
+    eh: &mut EventHandler,
+) -> Option<usize> {
+    ladder.iter().position(|(_, p)| {
+        let mut p = p.clone();
+        p.compute_triviality(eh);
+        if !p.trivial_sets.as_ref().unwrap().is_empty() {
+            return true;
+        }
+        match input_colors {
+            Some(c) => {
+                p.compute_coloring_solvability(eh);
+                p.coloring_sets.as_ref().is_some_and(|sets| sets.len() >= c)
+            }
+            None => false,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    use super::first_zero_round_rung;
+
+    #[test]
+    fn defective_ladder() {
+        let p = Problem::defective_coloring(2, 3, 1, false).unwrap();
+        assert_eq!(
+            format!("{}", p),
+            "(y1) (x1)^2\n(y2) (x2)^2\n\n(x1) (x2)(y2)\n(x1)(y1) (x2)\n(x1)(y1)(y2) (y2)\n(y1) (y1)(x2)(y2)\n"
+        );
+
+        let eh = &mut EventHandler::null();
+        let ladder = Problem::defective_coloring_ladder(2, 3, 3).unwrap();
+        assert_eq!(ladder.len(), 8);
+        let i = first_zero_round_rung(&ladder, None, eh).unwrap();
+        assert_eq!(ladder[i].0.defect, 3);
+        let i = first_zero_round_rung(&ladder, Some(2), eh).unwrap();
+        assert_eq!(ladder[i].0.defect, 0);
+    }
+}
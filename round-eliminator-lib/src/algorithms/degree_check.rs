@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constraint::Constraint,
+    group::{Exponent, Group, GroupType},
+    line::{Degree, Line},
+    part::Part,
+    problem::Problem,
+    store::Side,
+};
+
+/// A line of the text of a problem whose degree differs from the one of the other lines of the same side.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DegreeMismatch {
+    pub side: Side,
+    /// The position of the line starting from 1, in the text for `check_text_degrees`, and among the lines of its side for `check_degrees`.
+    pub line_number: usize,
+    pub text: String,
+    pub degree: Degree,
+    /// The degree of most lines of the same side.
+    pub expected: Degree,
+}
+
+/// Splits the text of a problem as `Problem::from_string` does, keeping the line numbers.
+pub(crate) fn sides(text: &str) -> [(Side, Vec<(usize, &str)>); 2] {
+    let mut lines = text.lines().enumerate().map(|(i, l)| (i + 1, l));
+    let active = lines.by_ref().take_while(|(_, l)| !l.is_empty()).collect();
+    let passive = lines.take_while(|(_, l)| !l.is_empty()).collect();
+    [(Side::Active, active), (Side::Passive, passive)]
+}
+
+impl Problem {
+    /// Finds the lines whose degree is different from the one of most lines of the same side.
+    /// Lines that cannot be parsed are ignored, since `from_string` reports them anyway.
+    pub fn check_text_degrees(text: &str) -> Vec<DegreeMismatch> {
+        let mut mapping = HashMap::new();
+        let mut result = vec![];
+        for (side, lines) in sides(text) {
+            let parsed: Vec<_> = lines
+                .into_iter()
+                .filter_map(|(n, l)| Line::parse(l, &mut mapping).ok().map(|line| (n, l, line.degree())))
+                .collect();
+            let counts = parsed.iter().map(|(_, _, d)| *d).counts();
+            // ties are broken in favor of the degree that appears first
+            let Some(expected) = parsed
+                .iter()
+                .map(|(_, _, d)| *d)
+                .max_by_key(|d| (counts[d], std::cmp::Reverse(parsed.iter().position(|x| x.2 == *d))))
+            else {
+                continue;
+            };
+            for (line_number, text, degree) in parsed {
+                if degree != expected {
+                    result.push(DegreeMismatch {
+                        side,
+                        line_number,
+                        text: text.to_string(),
+                        degree,
+                        expected,
+                    });
+                }
+            }
+        }
+        result
+    }
+
+    /// Same as `from_string`, but lines with fewer ports than the others of the same side get additional ports labeled with `pad`.
+    pub fn from_string_padded(text: &str, pad: &str) -> Result<Self, &'static str> {
+        if pad.is_empty() || pad.contains(char::is_whitespace) {
+            return Err("The padding label is not valid");
+        }
+        let mut mapping = HashMap::new();
+        let mut padded = vec![];
+        for (_, lines) in sides(text) {
+            let parsed = lines
+                .iter()
+                .map(|(_, l)| Line::parse(l, &mut mapping).map(|line| line.degree()))
+                .collect::<Result<Vec<_>, _>>()?;
+            let Some(&max) = parsed.iter().max() else {
+                padded.push(String::new());
+                continue;
+            };
+            let Degree::Finite(max) = max else {
+                return Err("Lines containing a star cannot be padded");
+            };
+            let side: Vec<_> = lines
+                .iter()
+                .zip(parsed)
+                .map(|((_, l), degree)| match degree {
+                    Degree::Finite(d) if d < max => format!("{} {}^{}", l, pad, max - d),
+                    _ => l.to_string(),
+                })
+                .collect();
+            padded.push(side.join("\n"));
+        }
+        Self::from_string_active_passive(&padded[0], &padded[1])
+    }
+
+    /// Finds the lines whose degree is different from the degree of their side.
+    /// Problems obtained by parsing never have such lines, but problems that are built or modified in other ways may have them.
+    pub fn check_degrees(&self) -> Vec<DegreeMismatch> {
+        let mut result = vec![];
+        for (side, c) in [(Side::Active, &self.active), (Side::Passive, &self.passive)] {
+            for (i, line) in c.lines.iter().enumerate() {
+                let degree = line.degree();
+                if degree != c.degree {
+                    result.push(DegreeMismatch {
+                        side,
+                        line_number: i + 1,
+                        text: line.to_string(&self.mapping_label_text.iter().cloned().collect()),
+                        degree,
+                        expected: c.degree,
+                    });
+                }
+            }
+        }
+        result
+    }
+
+    /// Makes every active line have degree `delta`, by adding ports labeled `filler` to the lines with fewer ports.
+    /// If `filler` is a label of the problem, the added ports behave exactly as that label.
+    /// Otherwise, it becomes a new label that the passive side accepts together with anything, as in `lift_degree` with free padding,
+    /// so that the added ports do not constrain the neighbors.
+    /// Fails if some active line has more than `delta` ports or contains a star, or if the passive lines do not all have the same degree.
+    pub fn pad_to_degree(&self, delta: usize, filler: &str) -> Result<Problem, &'static str> {
+        if filler.is_empty() || filler.contains(char::is_whitespace) {
+            return Err("The padding label is not valid");
+        }
+        if delta > Exponent::MAX as usize {
+            return Err("The target degree does not fit in an exponent");
+        }
+        if self.check_degrees().iter().any(|m| m.side == Side::Passive) {
+            return Err("The passive lines do not all have the same degree");
+        }
+        let mut p = self.clone();
+        let existing = self.mapping_label_text.iter().find(|(_, t)| t == filler).map(|(l, _)| *l);
+        let label = existing.unwrap_or_else(|| self.labels().into_iter().max().map_or(0, |l| l + 1));
+
+        let mut active = Constraint {
+            lines: vec![],
+            is_maximized: false,
+            degree: Degree::Finite(delta),
+        };
+        for line in &self.active.lines {
+            let Degree::Finite(d) = line.degree() else {
+                return Err("Lines containing a star cannot be padded");
+            };
+            if d > delta {
+                return Err("Some active line has more ports than the target degree");
+            }
+            let mut line = line.clone();
+            if d < delta {
+                line.parts.push(Part {
+                    gtype: GroupType::Many((delta - d) as Exponent),
+                    group: Group(vec![label]),
+                });
+                line.normalize();
+            }
+            active.add_line_and_discard_non_maximal(line);
+        }
+        p.active = active;
+
+        if existing.is_none() {
+            p.mapping_label_text.push((label, filler.to_string()));
+            let mut all = self.labels();
+            all.push(label);
+            let mut parts = vec![Part {
+                gtype: GroupType::ONE,
+                group: Group(vec![label]),
+            }];
+            let rest = match self.passive.degree {
+                Degree::Finite(0) => return Err("The passive side has degree 0"),
+                Degree::Finite(1) => None,
+                Degree::Finite(d) => Some(GroupType::Many((d - 1) as Exponent)),
+                Degree::Star => Some(GroupType::Star),
+            };
+            if let Some(gtype) = rest {
+                parts.push(Part { gtype, group: Group(all) });
+            }
+            let mut line = Line { parts };
+            line.normalize();
+            p.passive.add_line_and_discard_non_maximal(line);
+            p.passive.is_maximized = false;
+        }
+        p.discard_computed_stuff();
+
+        debug_assert!(p.check_degrees().is_empty());
+        Ok(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashMap;
+
+    use crate::{
+        line::{Degree, Line},
+        problem::Problem,
+        store::Side,
+    };
+
+    #[test]
+    fn degree_mismatch() {
+        let text = "A B B\nA A\nB B B\n\nA B\nB B\nA A A";
+        assert!(Problem::from_string(text).is_err());
+        let mismatches = Problem::check_text_degrees(text);
+        assert_eq!(mismatches.len(), 2);
+        assert_eq!(mismatches[0].side, Side::Active);
+        assert_eq!(mismatches[0].line_number, 2);
+        assert_eq!(mismatches[0].expected, Degree::Finite(3));
+        assert_eq!(mismatches[1].line_number, 7);
+        assert_eq!(mismatches[1].text, "A A A");
+        assert!(Problem::check_text_degrees("A B\n\nA B").is_empty());
+
+        let p = Problem::from_string_padded("A B B\nA A\n\nA B\nB B", "X").unwrap();
+        assert_eq!(format!("{}", p), "X A^2\nA B^2\n\nA B\nB^2\n");
+        assert!(Problem::from_string_padded("A B *\nA A\n\nA B", "X").is_err());
+    }
+
+    #[test]
+    fn pad_to_degree() {
+        let mut mapping = HashMap::new();
+        let mut p = Problem::from_string("A B B
+
+A B
+B B").unwrap();
+        for (l, t) in &p.mapping_label_text {
+            mapping.insert(t.clone(), *l);
+        }
+        p.active.lines.push(Line::parse("A A", &mut mapping).unwrap());
+        let mismatches = p.check_degrees();
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!((mismatches[0].side, mismatches[0].line_number), (Side::Active, 2));
+        assert_eq!(mismatches[0].text, "A^2");
+
+        let padded = p.pad_to_degree(3, "B").unwrap();
+        assert!(padded.check_degrees().is_empty());
+        assert_eq!(format!("{}", padded), "B A^2\nA B^2\n\nA B\nB^2\n");
+
+        let padded = p.pad_to_degree(4, "X").unwrap();
+        assert!(padded.check_degrees().is_empty());
+        assert_eq!(padded.labels().len(), 3);
+        assert_eq!(padded.passive.lines.len(), 3);
+        assert!(p.pad_to_degree(2, "X").is_err());
+        assert!(p.pad_to_degree(3, "X Y").is_err());
+    }
+}
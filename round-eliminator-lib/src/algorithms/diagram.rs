@@ -6,19 +6,43 @@ use rayon::iter::ParallelBridge;
 
 use crate::{group::Label, line::Degree, problem::Problem};
 
-use super::event::EventHandler;
+use super::{
+    event::EventHandler,
+    limits::{estimated_mem, LimitExceeded, LimitGuard, Limits},
+};
 
 impl Problem {
     pub fn compute_diagram(&mut self, eh: &mut EventHandler) {
+        self.compute_diagram_guarded(&LimitGuard::unlimited(), eh).expect("the diagram without limits cannot fail");
+    }
+
+    /// Same as `compute_diagram`, but stops when a limit is exceeded, either while maximizing the passive side or while comparing the labels.
+    /// In that case the diagram is not stored.
+    pub fn compute_diagram_with_limits(&mut self, limits: &Limits, eh: &mut EventHandler) -> Result<(), LimitExceeded> {
+        self.compute_diagram_guarded(&LimitGuard::new(limits), eh)
+    }
+
+    fn compute_diagram_guarded(&mut self, guard: &LimitGuard, eh: &mut EventHandler) -> Result<(), LimitExceeded> {
+        self.discard_stale_computed_stuff();
         if self.diagram_indirect.is_some() {
             panic!("diagram has been computed already");
         }
 
         if self.passive.degree != Degree::Finite(2) {
-            self.passive.maximize(eh);
+            self.passive.maximize_guarded(guard, eh)?;
         }
 
+        self.diagram_indirect = Some(self.diagram_indirect_of(guard, eh)?);
+        self.compute_direct_diagram();
+        self.mark_computed();
+        Ok(())
+    }
+
+    /// The diagram computed by `compute_diagram`, which must have maximized the passive side first if its degree is not 2.
+    /// The limits of `guard` are checked after comparing each label with all the others.
+    pub(crate) fn diagram_indirect_of(&self, guard: &LimitGuard, eh: &mut EventHandler) -> Result<Vec<(Label, Label)>, LimitExceeded> {
         let labels: Vec<_> = self.labels();
+        let mem = estimated_mem(&self.passive);
 
         /*{
             use rayon::prelude::*;
@@ -36,6 +60,7 @@ impl Problem {
         let mut diagram = vec![];
 
         for (i, l1) in labels.iter().enumerate() {
+            guard.check("diagram", labels.len(), self.passive.lines.len(), mem, i)?;
             for (j, l2) in labels.iter().enumerate() {
                 eh.notify("diagram", i * labels.len() + j, labels.len() * labels.len());
                 if l1 == l2 || self.passive.is_diagram_predecessor(*l1, *l2) {
@@ -44,11 +69,17 @@ impl Problem {
             }
         }
 
-        self.diagram_indirect = Some(diagram);
-        self.compute_direct_diagram();
+        Ok(diagram)
+    }
+
+    /// Whether the diagram has been computed and contains all the arrows, which is the case if the passive side has degree 2 or is maximized.
+    /// Otherwise, a diagram computed by `compute_partial_diagram` may miss some of them.
+    pub fn has_full_diagram(&self) -> bool {
+        self.diagram_indirect.is_some() && (self.passive.degree == Degree::Finite(2) || self.passive.is_maximized)
     }
 
     pub fn compute_partial_diagram(&mut self, eh: &mut EventHandler) {
+        self.discard_stale_computed_stuff();
         if self.diagram_indirect.is_some() {
             panic!("diagram has been computed already");
         }
@@ -68,9 +99,11 @@ impl Problem {
 
         self.diagram_indirect = Some(diagram);
         self.compute_direct_diagram();
+        self.mark_computed();
     }
 
     pub fn compute_set_inclusion_diagram(&mut self) {
+        self.discard_stale_computed_stuff();
         if self.diagram_indirect.is_some() {
             panic!("diagram has been computed already");
         }
@@ -91,6 +124,7 @@ impl Problem {
 
         self.diagram_indirect = Some(diagram);
         self.compute_direct_diagram();
+        self.mark_computed();
     }
 
     pub fn compute_direct_diagram(&mut self) {
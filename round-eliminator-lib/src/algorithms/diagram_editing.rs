@@ -0,0 +1,96 @@
+use std::collections::HashSet;
+
+use itertools::Itertools;
+
+use crate::{group::Label, problem::Problem};
+
+// The operations of a diagram-first editing mode, where the user acts on the nodes of the diagram and the operations are translated
+// into merges and hardenings. A node of the diagram is identified by any of its labels, and stands for all the labels that are
+// equivalent to it. The diagram must have been computed, and the diagram of the result is not computed.
+impl Problem {
+    /// The labels of the node of the diagram that contains the given label.
+    fn diagram_node(&self, label: Label) -> Result<Vec<Label>, &'static str> {
+        let (nodes, _) = self.diagram_direct.as_ref().ok_or("The diagram has not been computed")?;
+        nodes
+            .iter()
+            .find(|(l, group)| *l == label || group.contains(&label))
+            .map(|(l, group)| std::iter::once(*l).chain(group.iter().cloned()).unique().sorted().collect())
+            .ok_or("The operation refers to a label that is not in the diagram")
+    }
+
+    /// Merges the given nodes of the diagram into a single one, which is a relaxation. The result does not depend on the label that is kept,
+    /// up to renaming, so the strongest label is kept if there is one, that is, a label that is reachable from all the others, and otherwise the first one.
+    pub fn merge_diagram_nodes(&self, nodes: &[Label]) -> Result<Problem, &'static str> {
+        let mut labels = vec![];
+        for &node in nodes {
+            labels.extend(self.diagram_node(node)?);
+        }
+        let labels: Vec<Label> = labels.into_iter().unique().collect();
+        if labels.len() < 2 {
+            return Err("At least two distinct nodes are required");
+        }
+        let reachable = self.diagram_indirect_to_reachability_adj();
+        let to = labels.iter().cloned().find(|to| labels.iter().all(|l| reachable[l].contains(to))).unwrap_or(labels[0]);
+        let merges = labels.iter().filter(|&&l| l != to).map(|&l| (l, to)).collect();
+        Ok(self.relax_many_merges(&merges))
+    }
+
+    /// Merges all the nodes on the paths of the diagram from `from` to `to` into `to`, which is a relaxation.
+    /// Fails if `to` is not reachable from `from`.
+    pub fn contract_chain(&self, from: Label, to: Label) -> Result<Problem, &'static str> {
+        self.diagram_node(from)?;
+        self.diagram_node(to)?;
+        let reachable = self.diagram_indirect_to_reachability_adj();
+        if !reachable[&from].contains(&to) {
+            return Err("There is no path in the diagram between the given nodes");
+        }
+        let chain: Vec<Label> = self.labels().into_iter().filter(|l| reachable[&from].contains(l) && reachable[l].contains(&to)).collect();
+        self.merge_diagram_nodes(&std::iter::once(to).chain(chain).collect::<Vec<_>>())
+    }
+
+    /// Removes a node of the diagram, which is a hardening. On the active side, its labels are replaced by their predecessors,
+    /// see `HardenMode::Predecessors`. Fails if no configuration remains on some side.
+    pub fn remove_diagram_node(&self, node: Label) -> Result<Problem, &'static str> {
+        let removed = self.diagram_node(node)?;
+        let keep: HashSet<Label> = self.labels().into_iter().filter(|l| !removed.contains(l)).collect();
+        let new = self.harden_keep(&keep, true);
+        if new.active.lines.is_empty() || new.passive.lines.is_empty() {
+            return Err("Removing the node leaves no configurations");
+        }
+        Ok(new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem, serial::fix_problem};
+
+    #[test]
+    fn diagram_editing() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        fix_problem(&mut p, true, false, eh);
+        let mut single = Problem::from_string("A A\n\nA A").unwrap();
+        assert!(single.merge_diagram_nodes(&[0]).is_err());
+        fix_problem(&mut single, true, false, eh);
+        assert!(single.merge_diagram_nodes(&[0, 0]).is_err());
+        assert!(single.remove_diagram_node(0).is_err());
+
+        // U is stronger than P, so merging them keeps U, as contracting the arrow from P to U does, and M alone becomes useless
+        let mut merged = p.merge_diagram_nodes(&[1, 2]).unwrap();
+        fix_problem(&mut merged, true, false, eh);
+        assert_eq!(merged.to_string(), "U^3\n\nU^2\n");
+        let mut contracted = p.contract_chain(2, 1).unwrap();
+        fix_problem(&mut contracted, true, false, eh);
+        assert_eq!(contracted.to_string(), merged.to_string());
+        assert!(p.contract_chain(1, 2).is_err());
+        assert!(p.merge_diagram_nodes(&[1, 7]).is_err());
+
+        let mut removed = p.remove_diagram_node(2).unwrap();
+        fix_problem(&mut removed, true, false, eh);
+        assert_eq!(removed.to_string(), "M U^2\n\nM U\nU^2\n");
+        // without M, P cannot be used on the passive side anymore
+        assert!(p.remove_diagram_node(0).is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+
+use crate::{group::Label, problem::Problem};
+
+use super::{
+    diagram::diagram_to_indirect,
+    event::EventHandler,
+    passive_graph::{parse_dot, parse_edge_list, parse_graphml, xml_escape, GraphFormat},
+};
+
+/// The problem obtained by importing a diagram, and the arrows that have been added.
+type ImportedDiagram = (Problem, Vec<(Label, Label)>);
+
+impl Problem {
+    /// The arrows of the diagram that are drawn: the ones of the direct diagram, and for each group of equivalent labels,
+    /// a cycle going through all of them. The diagram must have been computed already.
+    pub fn diagram_arrows(&self) -> Vec<(Label, Label)> {
+        let (groups, direct) = self.diagram_direct.as_ref().expect("diagram required, but still not computed");
+        let mut arrows = direct.clone();
+        for (_, group) in groups.iter().filter(|(_, group)| group.len() > 1) {
+            arrows.extend(group.iter().cloned().zip(group.iter().cloned().cycle().skip(1)));
+        }
+        arrows.into_iter().sorted().collect()
+    }
+
+    /// Exports the diagram alone, as a directed graph whose nodes are the labels.
+    pub fn diagram_to_string(&self, format: GraphFormat) -> Result<String, &'static str> {
+        if self.diagram_direct.is_none() {
+            return Err("The diagram has not been computed");
+        }
+        let arrows = self.diagram_arrows();
+        let mapping: HashMap<_, _> = self.mapping_label_text.iter().cloned().collect();
+        let labels = self.labels();
+        let s = match format {
+            GraphFormat::EdgeList => arrows.iter().map(|(a, b)| format!("{} {}\n", mapping[a], mapping[b])).join(""),
+            GraphFormat::Dot => {
+                let mut s = String::from("digraph diagram {\n");
+                for l in &labels {
+                    s.push_str(&format!("    \"{}\";\n", mapping[l]));
+                }
+                for (a, b) in &arrows {
+                    s.push_str(&format!("    \"{}\" -> \"{}\";\n", mapping[a], mapping[b]));
+                }
+                s.push_str("}\n");
+                s
+            }
+            GraphFormat::GraphML => {
+                let mut s = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+                s.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+                s.push_str("  <graph id=\"diagram\" edgedefault=\"directed\">\n");
+                for l in &labels {
+                    s.push_str(&format!("    <node id=\"{}\"/>\n", xml_escape(&mapping[l])));
+                }
+                for (a, b) in &arrows {
+                    s.push_str(&format!("    <edge source=\"{}\" target=\"{}\"/>\n", xml_escape(&mapping[a]), xml_escape(&mapping[b])));
+                }
+                s.push_str("  </graph>\n</graphml>\n");
+                s
+            }
+        };
+        Ok(s)
+    }
+
+    /// Imports a diagram exported with `diagram_to_string` and then edited by adding arrows, and adds the new arrows to the problem
+    /// one at a time with `relax_addarrow`. Arrows that are already implied by the diagram obtained so far are skipped.
+    /// The imported diagram must still contain all the arrows of the current one, since removing arrows is not a relaxation.
+    /// Returns the new problem, with its diagram computed, and the arrows that have been added.
+    pub fn import_diagram_arrows(&self, format: GraphFormat, text: &str, eh: &mut EventHandler) -> Result<ImportedDiagram, &'static str> {
+        let Some(current) = self.diagram_indirect.as_ref() else {
+            return Err("The diagram has not been computed");
+        };
+        let mapping: HashMap<_, _> = self.mapping_label_text.iter().map(|(l, s)| (s.clone(), *l)).collect();
+        let names = match format {
+            GraphFormat::EdgeList => parse_edge_list(text)?,
+            GraphFormat::Dot => parse_dot(text, "->")?,
+            GraphFormat::GraphML => parse_graphml(text)?,
+        };
+        let mut arrows = vec![];
+        for (a, b) in names {
+            let a = *mapping.get(&a).ok_or("The diagram contains a node that is not a label of the problem")?;
+            let b = *mapping.get(&b).ok_or("The diagram contains a node that is not a label of the problem")?;
+            if a != b {
+                arrows.push((a, b));
+            }
+        }
+
+        let imported: HashSet<_> = diagram_to_indirect(&self.labels(), &arrows).into_iter().collect();
+        if current.iter().any(|arrow| !imported.contains(arrow)) {
+            return Err("The imported diagram does not contain all the arrows of the current one, but arrows can only be added");
+        }
+
+        let mut p = self.clone();
+        let mut reachability = p.reachability()?;
+        let mut added = vec![];
+        for (a, b) in arrows {
+            if reachability.reaches(a, b) {
+                continue;
+            }
+            p = p.relax_addarrow(a, b);
+            p.compute_diagram(eh);
+            reachability = p.reachability()?;
+            added.push((a, b));
+        }
+        Ok((p, added))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        algorithms::{event::EventHandler, passive_graph::GraphFormat},
+        problem::Problem,
+    };
+
+    #[test]
+    fn diagram_export() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        assert!(p.diagram_to_string(GraphFormat::EdgeList).is_err());
+        p.compute_diagram(eh);
+        assert_eq!(p.diagram_to_string(GraphFormat::EdgeList).unwrap(), "P U\n");
+
+        for format in [GraphFormat::EdgeList, GraphFormat::Dot, GraphFormat::GraphML] {
+            let text = p.diagram_to_string(format).unwrap();
+            let (same, added) = p.import_diagram_arrows(format, &text, eh).unwrap();
+            assert!(added.is_empty());
+            assert_eq!(same.diagram_arrows(), p.diagram_arrows());
+        }
+
+        let (relaxed, added) = p.import_diagram_arrows(GraphFormat::EdgeList, "P U\nU M\nP M", eh).unwrap();
+        assert_eq!(added, vec![(1, 0)]);
+        assert_eq!(
+            relaxed.to_canonical_text(),
+            Problem::from_string(p.relax_addarrow(1, 0).to_string()).unwrap().to_canonical_text()
+        );
+
+        assert!(p.import_diagram_arrows(GraphFormat::EdgeList, "U P", eh).is_err());
+
+        // a partial diagram is not taken as the diagram of the problem unless the passive side is maximized
+        let mut q = Problem::from_string("A A A\nB B B\nC C C\n\nA BC BC\nB AC AC\nC AB AB").unwrap();
+        q.compute_partial_diagram(eh);
+        assert!(!q.has_full_diagram());
+        q.discard_computed_stuff();
+        q.compute_diagram(eh);
+        assert!(q.has_full_diagram());
+        assert!(p.import_diagram_arrows(GraphFormat::EdgeList, "P U\nU X", eh).is_err());
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::HashSet;
+
+use crate::problem::Problem;
+
+/// Tries to find an augmenting path starting from the left vertex `u`, see Kuhn's algorithm.
+fn augment(u: usize, succ: &[Vec<usize>], visited: &mut [bool], matched: &mut [Option<usize>]) -> bool {
+    for &v in &succ[u] {
+        if visited[v] {
+            continue;
+        }
+        visited[v] = true;
+        if matched[v].is_none() || augment(matched[v].unwrap(), succ, visited, matched) {
+            matched[v] = Some(u);
+            return true;
+        }
+    }
+    false
+}
+
+impl Problem {
+    /// The size of the largest antichain of the diagram, that is, of the largest set of pairwise incomparable labels.
+    /// Equivalent labels count once. By Dilworth's theorem, this is the number of labels minus a maximum matching
+    /// between the labels and their strictly stronger labels.
+    /// The diagram of the problem must have been computed.
+    pub fn diagram_width(&self) -> usize {
+        let labels = self.labels();
+        let relation: HashSet<_> = self.diagram_indirect.as_ref().expect("diagram required, but still not computed").iter().cloned().collect();
+
+        // equivalent labels are ordered by their number, so that the relation becomes a partial order
+        let succ: Vec<Vec<usize>> = labels
+            .iter()
+            .map(|&a| {
+                (0..labels.len())
+                    .filter(|&j| {
+                        let b = labels[j];
+                        a != b && relation.contains(&(a, b)) && (!relation.contains(&(b, a)) || a < b)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut matched = vec![None; labels.len()];
+        let mut matching = 0;
+        for u in 0..labels.len() {
+            let mut visited = vec![false; labels.len()];
+            if augment(u, &succ, &mut visited, &mut matched) {
+                matching += 1;
+            }
+        }
+        labels.len() - matching
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    #[test]
+    fn diagram_width() {
+        let eh = &mut EventHandler::null();
+        // only P and U are comparable, M is incomparable with both
+        let mut p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        p.compute_diagram(eh);
+        assert_eq!(p.diagram_width(), 2);
+
+        // 3-coloring, where no two labels are comparable
+        let mut p = Problem::from_string("A A\nB B\nC C\n\nA BC\nB C").unwrap();
+        p.compute_diagram(eh);
+        assert_eq!(p.diagram_width(), 3);
+
+        // all labels are equivalent
+        let mut p = Problem::from_string("A B\n\nAB AB").unwrap();
+        p.compute_diagram(eh);
+        assert_eq!(p.diagram_width(), 1);
+    }
+}
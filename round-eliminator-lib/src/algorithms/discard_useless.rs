@@ -34,6 +34,22 @@ impl Problem {
         // but to emphasize that they now may contain garbage, they are set to None
         self.trivial_sets = None;
         self.coloring_sets = None;
+
+        self.discard_stale_annotations();
+    }
+
+    /// Forgets everything that has been computed about the problem, to be used after changing the constraints.
+    pub fn discard_computed_stuff(&mut self) {
+        self.trivial_sets = None;
+        self.coloring_sets = None;
+        self.diagram_indirect = None;
+        self.diagram_direct = None;
+        self.orientation_trivial_sets = None;
+        self.orientation_coloring_sets = None;
+        self.fixpoint_diagram = None;
+        self.fixpoint_procedure_works = None;
+        self.marks_works = None;
+        self.computed_for = None;
     }
 
     pub fn discard_useless_stuff(&mut self, recompute_full_diagram: bool, eh: &mut EventHandler) {
@@ -42,6 +58,7 @@ impl Problem {
         // coloring solvability is preserved
         // diagram may change
 
+        self.discard_stale_computed_stuff();
         self.diagram_indirect = None;
         self.diagram_direct = None;
 
@@ -67,6 +84,8 @@ impl Problem {
             self.discard_labels_used_on_at_most_one_side_from_configurations();
             eh.notify("discard unused internal", 1, 1);
             self.discard_unused_labels_from_internal_stuff();
+            // what has been computed is preserved, except for the diagram, that is recomputed
+            self.mark_computed();
             if self == &p {
                 break;
             }
@@ -0,0 +1,96 @@
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+
+use crate::{constraint::Constraint, group::Label, problem::Problem, store::Side};
+
+/// The name that replaces the name of a label of the second problem that is also used by the first one, such as (2.A) for A.
+/// The prefix is repeated until the name is not used by either problem.
+fn namespaced(name: &str, used: &HashSet<String>) -> String {
+    let mut inner = name.trim_start_matches('(').trim_end_matches(')').to_string();
+    loop {
+        inner = format!("2.{}", inner);
+        let candidate = format!("({})", inner);
+        if !used.contains(&candidate) {
+            return candidate;
+        }
+    }
+}
+
+fn constraint_text(c: &Constraint, names: &HashMap<Label, String>) -> Vec<String> {
+    c.lines.iter().map(|line| line.to_string(names)).collect()
+}
+
+impl Problem {
+    /// The problem whose configurations are the configurations of this problem and the ones of `other`, on both sides.
+    /// The labels of `other` whose names are also used by this problem are renamed, see `namespaced`, so that the two problems share no labels,
+    /// and hence a solution solves one of the two problems on each connected component. The color labels and the one-sided labels of both problems are kept.
+    /// The problems must have the same degrees.
+    pub fn disjoint_union(&self, other: &Problem) -> Result<Problem, &'static str> {
+        if self.active.degree != other.active.degree || self.passive.degree != other.passive.degree {
+            return Err("The problems have different degrees");
+        }
+        let names: HashMap<Label, String> = self.mapping_label_text.iter().cloned().collect();
+        let mut used: HashSet<String> = names.values().chain(other.mapping_label_text.iter().map(|(_, t)| t)).cloned().collect();
+        let own: HashSet<&String> = names.values().collect();
+        let mut other_names: HashMap<Label, String> = HashMap::new();
+        for (l, t) in other.mapping_label_text.iter().sorted() {
+            let name = if own.contains(t) { namespaced(t, &used) } else { t.clone() };
+            used.insert(name.clone());
+            other_names.insert(*l, name);
+        }
+
+        let active = constraint_text(&self.active, &names).into_iter().chain(constraint_text(&other.active, &other_names)).join("\n");
+        let passive = constraint_text(&self.passive, &names).into_iter().chain(constraint_text(&other.passive, &other_names)).join("\n");
+        let mut union = Problem::from_string_active_passive(active, passive)?;
+
+        let label_of: HashMap<String, Label> = union.mapping_label_text.iter().map(|(l, t)| (t.clone(), *l)).collect();
+        let color_labels: Vec<Label> = self
+            .color_labels()
+            .iter()
+            .map(|l| &names[l])
+            .chain(other.color_labels().iter().map(|l| &other_names[l]))
+            .filter_map(|t| label_of.get(t).copied())
+            .collect();
+        union.set_color_labels(&color_labels)?;
+        let one_sided: Vec<(Label, Side)> = self
+            .one_sided_labels
+            .iter()
+            .flatten()
+            .map(|(l, side)| (&names[l], *side))
+            .chain(other.one_sided_labels.iter().flatten().map(|(l, side)| (&other_names[l], *side)))
+            .filter_map(|(t, side)| label_of.get(t).map(|&l| (l, side)))
+            .collect();
+        union.set_one_sided_labels(&one_sided)?;
+        Ok(union)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    #[test]
+    fn disjoint_union() {
+        let eh = &mut EventHandler::null();
+        let p = Problem::from_string("A AB AB\n\nB AB").unwrap();
+        let mut q = Problem::from_string("A A A\n(2.A) (2.A) (2.A)\n\nA A\nA (2.A)").unwrap();
+        q.set_color_labels(&[1]).unwrap();
+
+        let mut u = p.disjoint_union(&q).unwrap();
+        let mut names: Vec<_> = u.mapping_label_text.iter().map(|(_, t)| t.clone()).collect();
+        names.sort();
+        assert_eq!(names, vec!["(2.2.A)", "(2.A)", "A", "B"]);
+        assert_eq!(u.active.lines.len(), 3);
+        assert_eq!(u.passive.lines.len(), 3);
+        let colored: Vec<_> = u.color_labels().iter().map(|l| u.mapping_label_text.iter().find(|(x, _)| x == l).unwrap().1.clone()).collect();
+        assert_eq!(colored, vec!["(2.A)"]);
+
+        // the union is zero round solvable since the second problem is
+        u.compute_triviality(eh);
+        assert!(!u.trivial_sets.unwrap().is_empty());
+
+        assert!(p.disjoint_union(&Problem::from_string("A A\n\nA A").unwrap()).is_err());
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+};
+
+/// The number of bits of the filter of the problems seen by a shard, 8 MiB.
+const FILTER_BITS: usize = 1 << 26;
+const FILTER_HASHES: usize = 4;
+
+/// A set of keys that may report false positives, but never false negatives.
+/// Filters with the same size can be shared by exchanging the inserted keys.
+#[derive(Clone, Debug)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    hashes: usize,
+}
+
+fn mix(mut x: u64) -> u64 {
+    // splitmix64 finalizer
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+impl BloomFilter {
+    pub fn new(bits: usize, hashes: usize) -> Self {
+        Self {
+            bits: vec![0; bits.div_ceil(64).max(1)],
+            hashes,
+        }
+    }
+
+    fn positions(&self, key: u64) -> impl Iterator<Item = usize> {
+        let len = self.bits.len() as u64 * 64;
+        let (h1, h2) = (mix(key), mix(key ^ 0x9e3779b97f4a7c15) | 1);
+        (0..self.hashes as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % len) as usize)
+    }
+
+    pub fn insert(&mut self, key: u64) {
+        for pos in self.positions(key).collect::<Vec<_>>() {
+            self.bits[pos / 64] |= 1 << (pos % 64);
+        }
+    }
+
+    pub fn contains(&self, key: u64) -> bool {
+        self.positions(key).all(|pos| self.bits[pos / 64] & (1 << (pos % 64)) != 0)
+    }
+}
+
+/// The key of a problem visited after the given number of speedups.
+/// It is the FNV-1a hash of the canonical text of the problem, so that it is the same on all machines.
+pub fn seen_key(problem: &str, depth: usize) -> u64 {
+    let mut h: u64 = 0xcbf29ce484222325;
+    for b in problem.bytes().chain(depth.to_le_bytes()) {
+        h ^= b as u64;
+        h = h.wrapping_mul(0x100000001b3);
+    }
+    h
+}
+
+/// One of the parts in which an autoub search is split.
+/// The shard explores only the top-level branches whose index modulo `count` is `index`,
+/// and shares with the other shards the best upper bound found so far and the problems that have already been visited.
+#[derive(Debug)]
+pub struct Shard {
+    pub index: usize,
+    pub count: usize,
+    /// One more than the number of speedups of the best upper bound known, as used internally by autoub.
+    bound: AtomicUsize,
+    seen: Mutex<BloomFilter>,
+    /// The keys inserted by this shard that have not been taken yet.
+    new_keys: Mutex<Vec<u64>>,
+}
+
+impl Shard {
+    pub fn new(index: usize, count: usize) -> Result<Self, &'static str> {
+        if index >= count {
+            return Err("The index of the shard must be smaller than the number of shards");
+        }
+        Ok(Self {
+            index,
+            count,
+            bound: AtomicUsize::new(usize::MAX),
+            seen: Mutex::new(BloomFilter::new(FILTER_BITS, FILTER_HASHES)),
+            new_keys: Mutex::new(vec![]),
+        })
+    }
+
+    /// The number of speedups of the best upper bound known to this shard.
+    pub fn best_steps(&self) -> Option<usize> {
+        match self.bound.load(Ordering::Acquire) {
+            usize::MAX => None,
+            b => Some(b - 1),
+        }
+    }
+
+    /// Records that an upper bound with the given number of speedups is known.
+    pub fn share_steps(&self, steps: usize) {
+        self.bound.fetch_min(steps + 1, Ordering::AcqRel);
+    }
+
+    pub(crate) fn adopt_bound(&self, best: &mut usize) {
+        *best = (*best).min(self.bound.load(Ordering::Acquire));
+    }
+
+    pub(crate) fn improve_bound(&self, best: usize) {
+        self.bound.fetch_min(best, Ordering::AcqRel);
+    }
+
+    /// Whether the shard explores the branch with the given index. The hardenings are sorted by weight and then by their labels,
+    /// see `best_hardenings`, so all the shards agree on the index of each branch.
+    pub(crate) fn owns(&self, branch: usize) -> bool {
+        branch % self.count == self.index
+    }
+
+    /// Whether some shard visited the problem after at most `depth` speedups.
+    pub(crate) fn was_seen(&self, problem: &str, depth: usize) -> bool {
+        let seen = self.seen.lock().unwrap();
+        (1..=depth).any(|d| seen.contains(seen_key(problem, d)))
+    }
+
+    pub(crate) fn insert_seen(&self, problem: &str, depth: usize) {
+        let key = seen_key(problem, depth);
+        self.seen.lock().unwrap().insert(key);
+        self.new_keys.lock().unwrap().push(key);
+    }
+
+    /// Adds the keys of the problems visited by other shards.
+    pub fn add_keys(&self, keys: &[u64]) {
+        let mut seen = self.seen.lock().unwrap();
+        for &key in keys {
+            seen.insert(key);
+        }
+    }
+
+    /// The keys of the problems visited by this shard since the last call.
+    pub fn take_new_keys(&self) -> Vec<u64> {
+        std::mem::take(&mut *self.new_keys.lock().unwrap())
+    }
+}
+
+/// The shards that are currently running in this process, by search id, so that requests can update them while they run.
+pub fn global_shards() -> &'static Mutex<HashMap<u64, Arc<Shard>>> {
+    static SHARDS: OnceLock<Mutex<HashMap<u64, Arc<Shard>>>> = OnceLock::new();
+    SHARDS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        algorithms::{
+            autoub::{EvaluationPolicy, SearchStats},
+            event::EventHandler,
+        },
+        problem::Problem,
+    };
+
+    use super::{seen_key, BloomFilter, Shard};
+
+    #[test]
+    fn distributed() {
+        let mut filter = BloomFilter::new(1024, 3);
+        filter.insert(seen_key("A B", 1));
+        assert!(filter.contains(seen_key("A B", 1)));
+        assert!(!filter.contains(seen_key("A B", 2)));
+        assert!(Shard::new(2, 2).is_err());
+
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("M U^2\nP^3\n\nM UP\nU^2").unwrap();
+        p.compute_diagram(eh);
+        let best = |shard: Option<&Shard>| {
+            let mut best = None;
+            let mut stats = SearchStats::default();
+            let handler = |len, _, _| best = Some(best.map_or(len, |b: usize| b.min(len)));
+            match shard {
+                Some(shard) => p.autoub_shard(4, 2, 3, None, None, EvaluationPolicy::Eager, shard, &mut stats, handler, &mut EventHandler::null()),
+                None => p.autoub(4, 2, 3, None, None, EvaluationPolicy::Eager, &mut stats, handler, &mut EventHandler::null()),
+            }
+            best
+        };
+        let shards = [Shard::new(0, 2).unwrap(), Shard::new(1, 2).unwrap()];
+        let found = shards.iter().filter_map(|s| best(Some(s))).min();
+        assert_eq!(found, best(None));
+        assert!(!shards[0].take_new_keys().is_empty());
+        assert!(shards[0].take_new_keys().is_empty());
+    }
+}
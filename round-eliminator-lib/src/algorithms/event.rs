@@ -1,12 +1,16 @@
+use super::watchdog::BlowupWarning;
+
 pub struct EventHandler<'a> {
     tx: Option<EventFunc<'a>>,
+    warnings: Option<WarningFunc<'a>>,
 }
 
 type EventFunc<'a> = Box<dyn FnMut((String, usize, usize)) + 'a>;
+type WarningFunc<'a> = Box<dyn FnMut(BlowupWarning) + 'a>;
 
 impl<'a> EventHandler<'a> {
     pub fn null() -> Self {
-        Self { tx: None }
+        Self { tx: None, warnings: None }
     }
 
     pub fn with<T>(f: T) -> Self
@@ -15,13 +19,96 @@ impl<'a> EventHandler<'a> {
     {
         Self {
             tx: Some(Box::new(f)),
+            warnings: None,
         }
     }
 
+    /// Also sends the warnings of the watchdog to `f`, see `Watchdog`.
+    pub fn with_warnings<T>(mut self, f: T) -> Self
+    where
+        T: FnMut(BlowupWarning) + 'a,
+    {
+        self.warnings = Some(Box::new(f));
+        self
+    }
+
     pub fn notify<S: AsRef<str>>(&mut self, s: S, x: usize, t: usize) {
         let s = s.as_ref();
         if let Some(tx) = self.tx.as_mut() {
             tx((s.to_string(), x, t));
         }
     }
+
+    /// Sends a warning of the watchdog. If no one listens for warnings, it is sent as a progress event with the predicted number of lines.
+    pub fn warn(&mut self, warning: BlowupWarning) {
+        match self.warnings.as_mut() {
+            Some(f) => f(warning),
+            None => self.notify("blowup predicted", warning.lines, warning.predicted_lines),
+        }
+    }
+}
+
+/// Rate-limits progress events before they are sent somewhere where each message is expensive, such as across the wasm boundary.
+/// Events received too early are not dropped immediately: for each kind of event the most recent one is kept, and all of them are
+/// delivered together in the next batch, in the order in which they have been last updated.
+/// Time is given by the caller, in milliseconds, since `std::time::Instant` is not available in wasm.
+pub struct EventThrottle {
+    interval: Option<f64>,
+    last: Option<f64>,
+    pending: Vec<(String, usize, usize)>,
+}
+
+impl EventThrottle {
+    /// Allows at most `max_batches_per_second` batches per second. If it is zero, no event is ever delivered.
+    pub fn new(max_batches_per_second: u32) -> Self {
+        Self {
+            interval: (max_batches_per_second > 0).then(|| 1000. / max_batches_per_second as f64),
+            last: None,
+            pending: vec![],
+        }
+    }
+
+    /// Records an event, and returns the batch of events that should be delivered now, if any.
+    pub fn push(&mut self, event: (String, usize, usize), now: f64) -> Option<Vec<(String, usize, usize)>> {
+        let interval = self.interval?;
+        // the events of the batch are in the order of their last update
+        self.pending.retain(|(s, _, _)| *s != event.0);
+        self.pending.push(event);
+        if self.last.is_some_and(|last| now - last < interval) {
+            return None;
+        }
+        self.last = Some(now);
+        Some(std::mem::take(&mut self.pending))
+    }
+
+    /// Returns the events that are still waiting to be delivered, for example before sending a result.
+    pub fn flush(&mut self) -> Vec<(String, usize, usize)> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::EventThrottle;
+
+    #[test]
+    fn event_throttle() {
+        let mut throttle = EventThrottle::new(10);
+        let event = |s: &str, x| (s.to_string(), x, 100);
+        assert_eq!(throttle.push(event("a", 0), 0.), Some(vec![event("a", 0)]));
+        let mut delivered = 0;
+        for i in 1..1000 {
+            if throttle.push(event(if i % 2 == 0 { "a" } else { "b" }, i), i as f64).is_some() {
+                delivered += 1;
+            }
+        }
+        assert_eq!(delivered, 9);
+        assert_eq!(throttle.flush(), vec![event("a", 998), event("b", 999)]);
+        assert!(throttle.flush().is_empty());
+
+        let mut silent = EventThrottle::new(0);
+        assert_eq!(silent.push(event("a", 0), 0.), None);
+        assert!(silent.flush().is_empty());
+    }
 }
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    group::{Exponent, Group, GroupType, Label},
+    line::{Degree, Line},
+    part::Part,
+    problem::Problem,
+};
+
+/// A label together with its text and, if the problem has been obtained with a speedup, the text of the old labels it represents.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ExplainedLabel {
+    pub label: Label,
+    pub text: String,
+    pub origin: Option<Vec<String>>,
+}
+
+/// A set of labels of a trivial or coloring solution, with the passive lines that allow it.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ExplainedSet {
+    pub labels: Vec<ExplainedLabel>,
+    /// The passive lines, in text form, that contain the configurations used by the solution.
+    pub witnesses: Vec<String>,
+}
+
+impl Problem {
+    pub fn explained_label(&self, label: Label) -> ExplainedLabel {
+        let text = self
+            .mapping_label_text
+            .iter()
+            .find(|(l, _)| *l == label)
+            .map_or_else(|| format!("({})", label), |(_, t)| t.clone());
+        let origin = match (&self.mapping_label_oldlabels, &self.mapping_oldlabel_text) {
+            (Some(oldlabels), Some(oldtext)) => {
+                let oldtext: HashMap<_, _> = oldtext.iter().cloned().collect();
+                oldlabels
+                    .iter()
+                    .find(|(l, _)| *l == label)
+                    .map(|(_, old)| old.iter().map(|o| oldtext[o].clone()).collect())
+            }
+            _ => None,
+        };
+        ExplainedLabel { label, text, origin }
+    }
+
+    fn witnesses(&self, line: &Line) -> Vec<String> {
+        let mapping: HashMap<_, _> = self.mapping_label_text.iter().cloned().collect();
+        self.passive
+            .lines
+            .iter()
+            .filter(|l| l.includes(line))
+            .map(|l| l.to_string(&mapping))
+            .collect()
+    }
+
+    /// The trivial sets, each with the passive lines that allow a node to output any label of the set on all its ports.
+    /// It is None if triviality has not been computed.
+    pub fn explained_trivial_sets(&self) -> Option<Vec<ExplainedSet>> {
+        let gtype = match self.passive.degree {
+            Degree::Finite(d) => GroupType::Many(d as Exponent),
+            Degree::Star => GroupType::Star,
+        };
+        let sets = self.trivial_sets.as_ref()?;
+        Some(
+            sets.iter()
+                .map(|set| ExplainedSet {
+                    labels: set.iter().map(|&l| self.explained_label(l)).collect(),
+                    witnesses: self.witnesses(&Line {
+                        parts: vec![Part {
+                            gtype,
+                            group: Group(set.clone()),
+                        }],
+                    }),
+                })
+                .collect(),
+        )
+    }
+
+    /// The coloring sets, each with the passive lines that allow it to be used together with the other sets.
+    /// Witnesses are given only when the passive degree is 2, since otherwise they would be too many.
+    /// It is None if coloring solvability has not been computed.
+    pub fn explained_coloring_sets(&self) -> Option<Vec<ExplainedSet>> {
+        let sets = self.coloring_sets.as_ref()?;
+        let part = |set: &Vec<Label>| Part {
+            gtype: GroupType::ONE,
+            group: Group(set.clone()),
+        };
+        Some(
+            sets.iter()
+                .enumerate()
+                .map(|(i, set)| {
+                    let mut witnesses = vec![];
+                    if self.passive.degree == Degree::Finite(2) {
+                        for (_, other) in sets.iter().enumerate().filter(|&(j, _)| j != i) {
+                            for w in self.witnesses(&Line {
+                                parts: vec![part(set), part(other)],
+                            }) {
+                                if !witnesses.contains(&w) {
+                                    witnesses.push(w);
+                                }
+                            }
+                        }
+                    }
+                    ExplainedSet {
+                        labels: set.iter().map(|&l| self.explained_label(l)).collect(),
+                        witnesses,
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    #[test]
+    fn explained_sets() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("A AB AB\n\nA A\nB B").unwrap();
+        p.compute_triviality(eh);
+        let trivial = p.explained_trivial_sets().unwrap();
+        assert_eq!(trivial[0].labels[0].text, "A");
+        assert_eq!(trivial[0].witnesses, vec!["A^2".to_string()]);
+
+        let mut p = Problem::from_string("A A A\nB B B\nC C C\n\nA BC\nB C").unwrap();
+        p.compute_coloring_solvability(eh);
+        let coloring = p.explained_coloring_sets().unwrap();
+        assert_eq!(coloring.len(), 3);
+        assert_eq!(coloring[0].witnesses, vec!["A BC".to_string()]);
+
+        let mut q = p.speedup(eh);
+        q.compute_triviality(eh);
+        assert!(q.explained_label(0).origin.is_some());
+        assert!(q.explained_trivial_sets().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{group::Exponent, problem::Problem, store::Side};
+
+/// A line whose degree does not fit in an `Exponent`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ExponentOverflow {
+    pub side: Side,
+    pub line: String,
+    pub degree: usize,
+}
+
+impl std::fmt::Display for ExponentOverflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The {} line {} has degree {}, but at most {} is supported",
+            match self.side {
+                Side::Active => "active",
+                Side::Passive => "passive",
+            },
+            self.line,
+            self.degree,
+            Exponent::MAX
+        )
+    }
+}
+
+impl Problem {
+    /// Checks that the degree of every line fits in an `Exponent`.
+    /// Operations only join parts of the same line, or of lines with the same degree, so if this holds, the counts of the parts
+    /// of the problems obtained from this one cannot overflow. Problems obtained by parsing always pass this check,
+    /// while problems built or deserialized by other means should be checked before operating on them.
+    pub fn check_exponents(&self) -> Result<(), ExponentOverflow> {
+        let mapping = self.mapping_label_text.iter().cloned().collect();
+        for (side, constraint) in [(Side::Active, &self.active), (Side::Passive, &self.passive)] {
+            for line in &constraint.lines {
+                let degree = line.degree_without_star();
+                if degree > Exponent::MAX as usize {
+                    return Err(ExponentOverflow { side, line: line.to_string(&mapping), degree });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        group::{Exponent, GroupType},
+        problem::Problem,
+        store::Side,
+    };
+
+    #[test]
+    fn exponents() {
+        let p = Problem::from_string("A^300 B^300\n\nA B").unwrap();
+        assert!(p.check_exponents().is_ok());
+        assert!(Problem::from_string("A^70000\n\nA A").is_err());
+        assert!(Problem::from_string("A^40000 B^40000\n\nA B").is_err());
+
+        let mut q = p.clone();
+        q.passive.lines[0].parts[0].gtype = GroupType::Many(Exponent::MAX);
+        q.passive.lines[0].parts[1].gtype = GroupType::Many(Exponent::MAX);
+        let overflow = q.check_exponents().unwrap_err();
+        assert_eq!(overflow.side, Side::Passive);
+        assert_eq!(overflow.degree, 2 * Exponent::MAX as usize);
+        assert!(overflow.to_string().contains(&format!("A^{} B^{}", Exponent::MAX, Exponent::MAX)));
+
+        assert_eq!(GroupType::Many(Exponent::MAX).checked_add(GroupType::ONE), None);
+        assert_eq!(GroupType::Many(300).checked_add(GroupType::Many(300)), Some(GroupType::Many(600)));
+    }
+}
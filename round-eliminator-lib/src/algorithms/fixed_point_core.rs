@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{problem::Problem, serial::fix_problem, store::Side};
+
+use super::event::EventHandler;
+
+/// Something that has been removed from a problem to obtain its core.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum CoreRemoval {
+    Label(String),
+    Line(Side, String),
+}
+
+/// A minimal nontrivial fixed point contained in a problem, with what has been removed to obtain it, in order.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FixedPointCore {
+    pub core: Problem,
+    pub removed: Vec<CoreRemoval>,
+}
+
+impl Problem {
+    /// The problem without useless labels and with equivalent labels merged, so that problems that differ only in this can be compared.
+    fn fixed_point_form(&self, eh: &mut EventHandler) -> Problem {
+        let mut p = self.clone();
+        fix_problem(&mut p, false, false, eh);
+        p.repeat_merge_equivalent_labels(eh)
+    }
+
+    /// Checks whether speedups give back the same problem, up to renaming labels.
+    /// Each speedup exchanges the degrees of the two sides, so in general the problem is compared with the one obtained after two speedups,
+    /// and when the two degrees are equal it is also compared with the one obtained after a single speedup.
+    pub fn is_fixed_point(&self, eh: &mut EventHandler) -> bool {
+        let p = self.fixed_point_form(eh);
+        let mut current = p.clone();
+        for _ in 0..2 {
+            if current.labels().is_empty() {
+                return false;
+            }
+            current = current.speedup(eh).fixed_point_form(eh);
+            if current.find_matching_relabeling(&p).is_ok() {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn is_hard_fixed_point(&self, eh: &mut EventHandler) -> bool {
+        if self.labels().is_empty() || self.active.lines.is_empty() || self.passive.lines.is_empty() {
+            return false;
+        }
+        let mut p = self.clone();
+        p.compute_triviality(eh);
+        p.trivial_sets.as_ref().unwrap().is_empty() && p.is_fixed_point(eh)
+    }
+
+    /// Greedily removes labels, and then lines, as long as the problem stays a nontrivial fixed point, and returns the result.
+    /// Removing something gives a harder problem, so the core is not a relaxation of the problem,
+    /// but it is a smaller fixed point that can be presented instead of the whole problem.
+    pub fn fixed_point_core(&self, eh: &mut EventHandler) -> Result<FixedPointCore, &'static str> {
+        let mut core = self.fixed_point_form(eh);
+        if !core.is_hard_fixed_point(eh) {
+            return Err("The problem is not a nontrivial fixed point");
+        }
+        let mut removed = vec![];
+        'outer: loop {
+            let text: HashMap<_, _> = core.mapping_label_text.iter().cloned().collect();
+            let labels = core.labels();
+            for (i, &label) in labels.iter().enumerate() {
+                eh.notify("core labels", i, labels.len());
+                let candidate = core.harden_remove(label, false);
+                if candidate.is_hard_fixed_point(eh) {
+                    removed.push(CoreRemoval::Label(text[&label].clone()));
+                    core = candidate.fixed_point_form(eh);
+                    continue 'outer;
+                }
+            }
+            for side in [Side::Active, Side::Passive] {
+                let lines = match side {
+                    Side::Active => &core.active.lines,
+                    Side::Passive => &core.passive.lines,
+                };
+                for i in 0..lines.len() {
+                    eh.notify("core lines", i, lines.len());
+                    let mut candidate = core.clone();
+                    let constraint = match side {
+                        Side::Active => &mut candidate.active,
+                        Side::Passive => &mut candidate.passive,
+                    };
+                    let line = constraint.lines.remove(i);
+                    constraint.is_maximized = false;
+                    candidate.trivial_sets = None;
+                    candidate.coloring_sets = None;
+                    candidate.diagram_indirect = None;
+                    candidate.diagram_direct = None;
+                    if candidate.is_hard_fixed_point(eh) {
+                        removed.push(CoreRemoval::Line(side, line.to_string(&text)));
+                        core = candidate.fixed_point_form(eh);
+                        continue 'outer;
+                    }
+                }
+            }
+            break;
+        }
+        Ok(FixedPointCore { core, removed })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    use super::CoreRemoval;
+
+    #[test]
+    fn fixed_point_core() {
+        let eh = &mut EventHandler::null();
+        let so = Problem::from_string("O I I\n\nI OI").unwrap();
+        assert!(so.is_fixed_point(eh));
+        let core = so.fixed_point_core(eh).unwrap();
+        assert!(core.removed.is_empty());
+
+        // sinkless orientation, together with a variant of it that uses other labels
+        let p = Problem::from_string("A B B\nO I I\n\nI OI\nA B").unwrap();
+        let core = p.fixed_point_core(eh).unwrap();
+        assert_eq!(core.removed, vec![CoreRemoval::Label("A".into())]);
+        assert!(core.core.find_matching_relabeling(&so.fixed_point_form(eh)).is_ok());
+
+        let mm = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        assert!(!mm.is_fixed_point(eh));
+        assert!(mm.fixed_point_core(eh).is_err());
+    }
+}
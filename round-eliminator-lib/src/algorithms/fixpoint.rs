@@ -6,7 +6,7 @@ use itertools::Itertools;
 
 use crate::{algorithms::diagram::compute_direct_diagram, constraint::Constraint, group::{Exponent, Group, GroupType, Label}, line::{Degree, Line}, part::Part, problem::{DiagramDirect, Problem}};
 use serde::{Deserialize, Serialize};
-use super::{event::EventHandler, maximize::{Operation}, diagram::{diagram_indirect_to_reachability_adj, diagram_to_indirect}};
+use super::{event::EventHandler, maximize::{Operation, Tracking}, limits::LimitGuard, diagram::{diagram_indirect_to_reachability_adj, diagram_to_indirect}};
 
 
 #[derive(Clone,Debug,Serialize,Deserialize,Eq,PartialEq)]
@@ -242,8 +242,6 @@ impl FixpointDiagram {
     }
 }
 
-type Tracking = (Line, Line, Line, Vec<Vec<usize>>, Vec<(usize, usize, Operation)>);
-
 pub enum FixpointType{
     Basic,
     Dup(Vec<Vec<Label>>),
@@ -254,6 +252,7 @@ pub enum FixpointType{
 impl Problem {
 
     pub fn compute_default_fixpoint_diagram(&mut self, labels : Option<Vec<Label>>, eh: &mut EventHandler) {
+        self.discard_stale_computed_stuff();
         if let Some(sublabels) = &labels {
             let mut subproblem = self.harden_keep(&sublabels.iter().cloned().collect(), false);
             subproblem.discard_useless_stuff(false, eh);
@@ -261,6 +260,7 @@ impl Problem {
         } else {
             self.fixpoint_diagram = Some((None,FixpointDiagram::new(self)));
         }
+        self.mark_computed();
     }
 
 
@@ -367,7 +367,16 @@ impl Problem {
                 orientation_given: None,
                 fixpoint_diagram : None,
                 fixpoint_procedure_works : None,
-                marks_works : None
+                marks_works : None,
+                label_priorities : None,
+                degree_lifts : None,
+                annotations : None,
+                provenance : self.provenance.clone(),
+                line_origins : None,
+                computed_for : None,
+                label_folds : None,
+                color_labels : None,
+                one_sided_labels : None
             };
             p.compute_diagram(eh);
             p.discard_useless_stuff(true, eh);
@@ -611,7 +620,9 @@ impl Problem {
                 //println!("candidate {}",target_line.to_string(&tostr));
                 if Problem::fp_is_obtainable(&mut active, &mut not_obtainable,&target_line, &passive_successors,&tostr,&tree_for_labels) {
                     let mut p = self.clone();
+                    p.discard_stale_computed_stuff();
                     p.fixpoint_procedure_works = Some(false);
+                    p.mark_computed();
                     //println!("obtainable lines:");
                     //for line in &active.lines {
                     //    println!("{}",line.to_string(&tostr));
@@ -624,7 +635,9 @@ impl Problem {
         }
 
         let mut p = self.clone();
+        p.discard_stale_computed_stuff();
         p.fixpoint_procedure_works = Some(true);
+        p.mark_computed();
         return Ok((p,Constraint{lines:vec![],is_maximized:false,degree:passive.degree}));
     }
 
@@ -835,7 +848,16 @@ impl Problem {
             orientation_given: self.orientation_given,
             fixpoint_diagram : None,
             fixpoint_procedure_works : None,
-            marks_works : None
+            marks_works : None,
+            label_priorities : None,
+            degree_lifts : None,
+            annotations : None,
+            provenance : self.provenance.clone(),
+            line_origins : None,
+            computed_for : None,
+            label_folds : None,
+            color_labels : None,
+            one_sided_labels : None
         };
         p.mapping_label_text = mapping_newlabel_text.clone();
         Ok((p,passive_before_edit))
@@ -1172,7 +1194,8 @@ fn procedure(constraint : &Constraint, labels : &[Label], diagram_indirect : &Ve
     newconstraint.is_maximized = false;
 
 
-    newconstraint.maximize_custom(eh,true,false,tracking,f_is_superset, f_union, f_intersection);
+    newconstraint.maximize_custom(eh,true,false,tracking,&LimitGuard::unlimited(),f_is_superset, f_union, f_intersection)
+        .expect("maximization without limits cannot fail");
     /*println!("obtained constraint");
     for line in &newconstraint.lines {
         println!("{}",line.to_string(&mapping));
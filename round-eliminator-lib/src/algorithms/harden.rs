@@ -1,6 +1,7 @@
 use std::collections::HashSet;
 
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     constraint::Constraint,
@@ -8,6 +9,23 @@ use crate::{
     problem::Problem,
 };
 
+/// How the labels that are removed by a hardening are handled on the active side.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum HardenMode {
+    /// Removed labels are deleted, and lines that remain without labels in some group are deleted.
+    /// The result is a restriction: every solution of it is a solution of the original problem.
+    Remove,
+    /// Removed labels are replaced by their predecessors in the diagram, that is, by weaker labels.
+    /// The result is still a restriction, since a node can output the original label instead of the weaker one,
+    /// but fewer lines get deleted than with `Remove`.
+    Predecessors,
+    /// Removed labels are replaced by their successors in the diagram, that is, by stronger labels.
+    /// If every removed label that is used by a solution has a kept successor, the result is a relaxation:
+    /// any solution of the original problem can be converted by replacing each removed label with such a successor.
+    /// Otherwise, the result is neither a relaxation nor a restriction in general.
+    Successors,
+}
+
 impl Problem {
     pub fn harden_remove(&self, label: Label, add_predecessors: bool) -> Self {
         let mut h: HashSet<_> = self.labels().into_iter().collect();
@@ -16,21 +34,34 @@ impl Problem {
     }
 
     pub fn harden_keep(&self, keep: &HashSet<Label>, add_predecessors: bool) -> Self {
+        let mode = if add_predecessors { HardenMode::Predecessors } else { HardenMode::Remove };
+        self.harden_keep_with_mode(keep, mode)
+    }
+
+    /// Keeps only the given labels, handling the removed labels of the active side as specified by `mode`.
+    /// The diagram is required by all modes except `HardenMode::Remove`.
+    pub fn harden_keep_with_mode(&self, keep: &HashSet<Label>, mode: HardenMode) -> Self {
         let mut keep = keep.clone();
 
         let mut newpassive = self.passive.clone();
-        let mut newactive = if add_predecessors {
-            let predecessors = self.diagram_indirect_to_inverse_reachability_adj();
-
-            self.active.edited(|g| {
-                let mut h = HashSet::new();
-                for label in &g.0 {
-                    h.extend(predecessors[label].iter().cloned());
-                }
-                Group::from_set(&h)
-            })
-        } else {
-            self.active.clone()
+        let mut newactive = match mode {
+            HardenMode::Remove => self.active.clone(),
+            HardenMode::Predecessors | HardenMode::Successors => {
+                let closure = if mode == HardenMode::Predecessors {
+                    self.diagram_indirect_to_inverse_reachability_adj()
+                } else {
+                    self.diagram_indirect_to_reachability_adj()
+                };
+
+                self.active.edited(|g| {
+                    let mut h = HashSet::new();
+                    for label in &g.0 {
+                        h.insert(*label);
+                        h.extend(closure[label].iter().cloned());
+                    }
+                    Group::from_set(&h)
+                })
+            }
         };
 
         loop {
@@ -51,7 +82,7 @@ impl Problem {
             keep = newkeep;
         }
 
-        Problem {
+        let mut p = Problem {
             active: newactive,
             passive: newpassive,
             mapping_label_text: self.mapping_label_text.clone(),
@@ -68,12 +99,50 @@ impl Problem {
             orientation_given: self.orientation_given,
             fixpoint_diagram : None,
             fixpoint_procedure_works : None,
-            marks_works : None
-        }
+            marks_works : None,
+            label_priorities : self.label_priorities.clone(),
+            degree_lifts : self.degree_lifts.clone(),
+            annotations : self.annotations_for_derived(),
+            provenance : self.provenance.clone(),
+            line_origins : self.line_origins.clone(),
+            computed_for : None,
+            label_folds : self.label_folds.clone(),
+            color_labels : self.color_labels.clone(),
+            one_sided_labels : self.one_sided_labels.clone()
+        };
+        p.one_sided_labels = p.one_sided_labels_after_change();
+        p
+    }
+}
+
+impl Problem {
+    /// Keeps only the given labels, and the lines whose groups contain only these labels.
+    /// Differently from `harden_keep`, a line that contains some other label is removed entirely,
+    /// instead of being restricted to the kept labels, and labels that are not used anymore are not removed.
+    /// As for hardening, every solution of the result is a solution of this problem,
+    /// so upper bounds for the result are upper bounds for this problem, while lower bounds do not transfer.
+    pub fn induced(&self, labels: &HashSet<Label>) -> Self {
+        let mut p = self.harden_keep(labels, false);
+        p.active = self.active.induced(labels);
+        p.passive = self.passive.induced(labels);
+        p
     }
 }
 
 impl Constraint {
+    fn induced(&self, labels: &HashSet<Label>) -> Self {
+        Constraint {
+            lines: self
+                .lines
+                .iter()
+                .filter(|line| line.parts.iter().all(|part| part.group.iter().all(|l| labels.contains(l))))
+                .cloned()
+                .collect(),
+            is_maximized: false,
+            degree: self.degree,
+        }
+    }
+
     fn harden(&self, keep: &HashSet<Label>) -> Self {
         self.edited(|g| Group(g.as_set().intersection(keep).cloned().sorted().collect()))
     }
@@ -86,6 +155,8 @@ mod tests {
 
     use crate::{algorithms::event::EventHandler, problem::Problem};
 
+    use super::HardenMode;
+
     #[test]
     fn harden_with_predecessors() {
         let mut p = Problem::from_string("0	1	1	1\n2	1	1	3\n4	4	4	5\n\n053 4513 4513 4513\n13 13 13 204513\n53 4513 4513 04513\n513 513 0513 4513\n513 513 513 04513").unwrap();
@@ -96,4 +167,36 @@ mod tests {
         p.discard_useless_stuff(true, &mut EventHandler::null());
         assert_eq!(format!("{}", p), "0 1^3\n\n01 1^3\n");
     }
+
+    #[test]
+    fn harden_modes() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        p.compute_diagram(eh);
+        let (m, u, pp) = (0, 1, 2);
+        assert_eq!(format!("{}", p), "M U^2\nP^3\n\nM UP\nU^2\n");
+
+        // removing P: with successors, P^3 becomes U^3, which is a relaxation
+        let keep = HashSet::from([m, u]);
+        let removed = p.harden_keep_with_mode(&keep, HardenMode::Remove);
+        assert_eq!(format!("{}", removed), "M U^2\n\nM U\nU^2\n");
+        let relaxed = p.harden_keep_with_mode(&keep, HardenMode::Successors);
+        assert_eq!(format!("{}", relaxed), "M U^2\nU^3\n\nM U\nU^2\n");
+
+        // removing U: with predecessors, U is replaced by the weaker P, which is a restriction
+        let keep = HashSet::from([m, pp]);
+        let restricted = p.harden_keep_with_mode(&keep, HardenMode::Predecessors);
+        assert_eq!(format!("{}", restricted), "M P^2\nP^3\n\nM P\n");
+        assert_eq!(restricted, p.harden_keep(&keep, true));
+    }
+
+    #[test]
+    fn induced() {
+        let p = Problem::from_string("A AB AB\nC C C\n\nA BC\nB B").unwrap();
+        let keep = HashSet::from([0, 1]);
+        let induced = p.induced(&keep);
+        assert_eq!(format!("{}", induced), "A AB^2\n\nB^2\n");
+        let hardened = p.harden_keep(&keep, false);
+        assert_eq!(format!("{}", hardened), "A AB^2\n\nA B\nB^2\n");
+    }
 }
@@ -0,0 +1,142 @@
+use crate::{
+    algorithms::max_clique::Graph,
+    group::{Group, GroupType, Label},
+    line::{Degree, Line},
+    part::Part,
+    problem::Problem,
+};
+
+use super::event::EventHandler;
+
+impl Problem {
+    /// Checks whether there is a homomorphism from the compatibility graph of the passive side to the given template, that is,
+    /// a map from the labels to the vertices of the template such that any two labels allowed together by the passive side
+    /// are mapped to adjacent vertices, and a label allowed twice is mapped to a vertex with a loop.
+    /// With a clique as template, this is a proper coloring of the labels in which labels that can share an edge get different colors.
+    /// Returns, for each label, the vertex of the template it is mapped to.
+    pub fn homomorphism_to_template(
+        &self,
+        template_size: usize,
+        template_edges: &[(usize, usize)],
+        eh: &mut EventHandler,
+    ) -> Result<Option<Vec<(Label, usize)>>, &'static str> {
+        if self.passive.degree != Degree::Finite(2) {
+            return Err("The homomorphism check requires that the passive degree is 2");
+        }
+        if template_edges.iter().any(|&(a, b)| a >= template_size || b >= template_size) {
+            return Err("The template contains an edge between nonexisting vertices");
+        }
+
+        let labels = self.labels();
+        let n = labels.len();
+        let mut compatible = vec![vec![false; n]; n];
+        for i in 0..n {
+            for j in i..n {
+                eh.notify("compatibility graph", i * n + j, n * n);
+                let parts = if i == j {
+                    vec![Part { gtype: GroupType::Many(2), group: Group(vec![labels[i]]) }]
+                } else {
+                    vec![
+                        Part { gtype: GroupType::ONE, group: Group(vec![labels[i]]) },
+                        Part { gtype: GroupType::ONE, group: Group(vec![labels[j]]) },
+                    ]
+                };
+                let c = self.passive.includes(&Line { parts });
+                compatible[i][j] = c;
+                compatible[j][i] = c;
+            }
+        }
+
+        let mut template = vec![vec![false; template_size]; template_size];
+        for &(a, b) in template_edges {
+            template[a][b] = true;
+            template[b][a] = true;
+        }
+
+        // without loops in the template, a clique of labels must be mapped to a clique of the same size
+        if (0..template_size).all(|v| !template[v][v]) {
+            if (0..n).any(|i| compatible[i][i]) {
+                return Ok(None);
+            }
+            let clique = |m: &Vec<Vec<bool>>| Graph::from_adj(m.iter().map(|row| (0..row.len()).filter(|&j| row[j]).collect()).collect()).max_clique().len();
+            eh.notify("clique", 0, 1);
+            if clique(&compatible) > clique(&template) {
+                return Ok(None);
+            }
+        }
+
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(compatible[i].iter().filter(|&&c| c).count()));
+        let mut assignment = vec![None; n];
+        if !homomorphism_rec(&order, &compatible, &template, &mut assignment, eh) {
+            return Ok(None);
+        }
+        Ok(Some(labels.into_iter().zip(assignment).map(|(l, v)| (l, v.unwrap())).collect()))
+    }
+}
+
+/// Assigns a vertex of the template to each label of `order`, consistently with the labels assigned so far.
+fn homomorphism_rec(
+    order: &[usize],
+    compatible: &[Vec<bool>],
+    template: &[Vec<bool>],
+    assignment: &mut [Option<usize>],
+    eh: &mut EventHandler,
+) -> bool {
+    let (i, rest) = match order.split_first() {
+        Some((&i, rest)) => (i, rest),
+        None => return true,
+    };
+    for v in 0..template.len() {
+        if order.len() == assignment.len() {
+            eh.notify("homomorphism", v, template.len());
+        }
+        let ok = (0..compatible.len()).all(|j| {
+            if !compatible[i][j] {
+                true
+            } else if i == j {
+                template[v][v]
+            } else {
+                assignment[j].is_none_or(|w| template[v][w])
+            }
+        });
+        if ok {
+            assignment[i] = Some(v);
+            if homomorphism_rec(rest, compatible, template, assignment, eh) {
+                return true;
+            }
+            assignment[i] = None;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+
+    use itertools::Itertools;
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    #[test]
+    fn homomorphism() {
+        let eh = &mut EventHandler::null();
+        // the compatibility graph of 3-coloring is a triangle
+        let p = Problem::from_string("A A A\nB B B\nC C C\n\nA BC\nB C").unwrap();
+        let triangle = [(0, 1), (1, 2), (0, 2)];
+        let h = p.homomorphism_to_template(3, &triangle, eh).unwrap().unwrap();
+        assert_eq!(h.iter().map(|&(_, v)| v).unique().count(), 3);
+        assert_eq!(p.homomorphism_to_template(2, &[(0, 1)], eh).unwrap(), None);
+        let k4 = [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)];
+        assert!(p.homomorphism_to_template(4, &k4, eh).unwrap().is_some());
+
+        // the compatibility graph of sinkless orientation is the path I - O together with a loop on I,
+        // hence every label can be mapped to a single vertex with a loop, but not to an edge without loops
+        let p = Problem::from_string("O I I\n\nI OI").unwrap();
+        assert_eq!(p.homomorphism_to_template(1, &[(0, 0)], eh).unwrap(), Some(vec![(0, 0), (1, 0)]));
+        assert_eq!(p.homomorphism_to_template(2, &[(0, 1)], eh).unwrap(), None);
+        assert_eq!(p.homomorphism_to_template(2, &[(0, 1), (1, 1)], eh).unwrap().unwrap().len(), 2);
+
+        assert!(p.homomorphism_to_template(2, &[(0, 2)], eh).is_err());
+    }
+}
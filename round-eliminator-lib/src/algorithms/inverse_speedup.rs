@@ -69,7 +69,16 @@ impl Problem {
             orientation_given: self.orientation_given,
             fixpoint_diagram : None,
             fixpoint_procedure_works : None,
-            marks_works : None
+            marks_works : None,
+            label_priorities : None,
+            degree_lifts : None,
+            annotations : None,
+            provenance : self.provenance.clone(),
+            line_origins : None,
+            computed_for : None,
+            label_folds : None,
+            color_labels : None,
+            one_sided_labels : None
         };
         p.assign_chars();
         p
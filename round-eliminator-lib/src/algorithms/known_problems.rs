@@ -0,0 +1,195 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock, PoisonError},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{line::Degree, problem::Problem, serial::fix_problem};
+
+use super::{
+    event::EventHandler,
+    limits::{Limit, Limits},
+};
+
+/// The largest degree of the active side for which the known problems are generated.
+const MAX_DEGREE: usize = 5;
+
+/// The largest number of colors of the colorings that are generated.
+const MAX_COLORS: usize = 5;
+
+/// The speedups of the known problems are not computed further once they have more labels or lines than this, or take longer.
+const MAX_LABELS: usize = 20;
+const MAX_LINES: usize = 10_000;
+const MAX_TIME: Duration = Duration::from_secs(2);
+
+/// The problems of the literature on graphs whose nodes have the given degree, written as usual, with the passive side of degree 2. The name includes the parameters other than the degree, as in "3-coloring".
+pub(crate) fn known_problems(degree: usize) -> Vec<(String, String)> {
+    let power = |label: &str, exponent: usize| if exponent == 1 { label.to_string() } else { format!("{}^{}", label, exponent) };
+    let mut problems = vec![
+        ("maximal matching".to_string(), format!("M {}\n{}\n\nM UP\nU U", power("U", degree - 1), power("P", degree))),
+        ("maximal independent set".to_string(), format!("{}\nP {}\n\nM PO\nO O", power("M", degree), power("O", degree - 1))),
+        ("sinkless orientation".to_string(), format!("O {}\n\nI OI", power("I", degree - 1))),
+        ("perfect matching".to_string(), format!("M {}\n\nM M\nU U", power("U", degree - 1))),
+    ];
+    let colors = ["A", "B", "C", "D", "E"];
+    for k in 2..=MAX_COLORS {
+        let colors = &colors[..k];
+        let active: Vec<String> = colors.iter().map(|c| power(c, degree)).collect();
+        let passive: Vec<String> = colors.iter().map(|c| format!("{} {}", c, colors.iter().filter(|o| *o != c).cloned().collect::<String>())).collect();
+        problems.push((format!("{}-coloring", k), format!("{}\n\n{}", active.join("\n"), passive.join("\n"))));
+    }
+    problems
+}
+
+/// The degree of the nodes of a problem that may be a known problem, or one of its speedups, if it is in the range of the known problems.
+/// A speedup swaps the degrees of the two sides, so the degree of the nodes is the one that is not 2, if any.
+pub(crate) fn node_degree(p: &Problem) -> Option<usize> {
+    match (p.active.degree, p.passive.degree) {
+        (Degree::Finite(d), Degree::Finite(2)) | (Degree::Finite(2), Degree::Finite(d)) if (2..=MAX_DEGREE).contains(&d) => Some(d),
+        _ => None,
+    }
+}
+
+/// The speedups of a known problem computed so far, and whether a limit, other than time, stopped the next one.
+struct KnownSpeedups {
+    problems: Vec<Problem>,
+    exceeded: bool,
+}
+
+/// The speedups of the known problems, by name and degree, computed once for the whole process.
+fn speedup_cache() -> &'static Mutex<HashMap<(String, usize), KnownSpeedups>> {
+    static CACHE: OnceLock<Mutex<HashMap<(String, usize), KnownSpeedups>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// The known problem with the given name and text, of the given degree, after the given number of speedups, each followed by `fix_problem`.
+/// Returns None if a speedup exceeds `MAX_LABELS`, `MAX_LINES` or `MAX_TIME`. The results are cached, except when the time runs out,
+/// since another attempt may be faster.
+pub(crate) fn known_speedup(name: &str, text: &str, degree: usize, speedups: usize, eh: &mut EventHandler) -> Option<Problem> {
+    let limits = Limits { max_labels: Some(MAX_LABELS), max_lines: Some(MAX_LINES), max_time: Some(MAX_TIME), ..Default::default() };
+    let key = (name.to_string(), degree);
+    loop {
+        // the lock is held only to read and update the cache, not during the speedups, which may take a while
+        let (computed, last) = {
+            let mut cache = speedup_cache().lock().unwrap_or_else(PoisonError::into_inner);
+            let known = cache
+                .entry(key.clone())
+                .or_insert_with(|| KnownSpeedups { problems: vec![Problem::from_string(text).unwrap()], exceeded: false });
+            if let Some(p) = known.problems.get(speedups) {
+                return Some(p.clone());
+            }
+            if known.exceeded {
+                return None;
+            }
+            (known.problems.len(), known.problems.last().unwrap().clone())
+        };
+        let result = last.speedup_with_limits(&limits, eh).map(|mut new| {
+            fix_problem(&mut new, true, false, eh);
+            new
+        });
+        let mut cache = speedup_cache().lock().unwrap_or_else(PoisonError::into_inner);
+        let known = cache.get_mut(&key).unwrap();
+        match result {
+            // another thread may have computed the same speedup in the meantime
+            Ok(new) if known.problems.len() == computed => known.problems.push(new),
+            Ok(_) => {}
+            Err(e) => {
+                known.exceeded |= !matches!(e.limit, Limit::Time(_));
+                return None;
+            }
+        }
+    }
+}
+
+/// A known problem that, up to renaming labels, is the same as a given problem.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct KnownProblemMatch {
+    /// The name of the known problem, as in "3-coloring".
+    pub problem: String,
+    pub degree: usize,
+    /// How many times the known problem has been sped up, with `speedup` followed by the usual cleanup.
+    pub speedups: usize,
+    /// Whether the given problem matches only after merging its equivalent labels, see `repeat_merge_equivalent_labels`.
+    pub after_cleanup: bool,
+}
+
+impl std::fmt::Display for KnownProblemMatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} of the {}-regular tree", self.problem, self.degree)?;
+        match self.speedups {
+            0 => {}
+            1 => write!(f, " after one speedup")?,
+            n => write!(f, " after {} speedups", n)?,
+        }
+        if self.after_cleanup {
+            write!(f, ", once equivalent labels are merged")?;
+        }
+        Ok(())
+    }
+}
+
+impl Problem {
+    /// Compares the problem, up to renaming labels, with the known problems of the same degree, and with the results of applying
+    /// at most `max_speedups` speedups to them. The problem is compared both as it is and after merging its equivalent labels.
+    /// Speedups that exceed the limits of `known_speedup` are not computed.
+    pub fn known_problems(&self, max_speedups: usize, eh: &mut EventHandler) -> Vec<KnownProblemMatch> {
+        let Some(degree) = node_degree(self) else {
+            return vec![];
+        };
+        let cleaned = Some(self.repeat_merge_equivalent_labels(eh)).filter(|cleaned| cleaned != self);
+
+        let known = known_problems(degree);
+        let mut matches = vec![];
+        for (i, (name, text)) in known.iter().enumerate() {
+            eh.notify("known problems", i, known.len());
+            for speedups in 0..=max_speedups {
+                let Some(candidate) = known_speedup(name, text, degree, speedups, eh) else {
+                    break;
+                };
+                for (after_cleanup, p) in [(false, Some(self)), (true, cleaned.as_ref())] {
+                    if p.is_some_and(|p| p.find_matching_relabeling(&candidate).is_ok()) {
+                        matches.push(KnownProblemMatch { problem: name.clone(), degree, speedups, after_cleanup });
+                        break;
+                    }
+                }
+            }
+        }
+        eh.notify("known problems", known.len(), known.len());
+        matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem, serial::fix_problem};
+
+    use super::KnownProblemMatch;
+
+    #[test]
+    fn known_problems() {
+        let eh = &mut EventHandler::null();
+        let p = Problem::from_string("X Y Y\nZ Z Z\n\nX YZ\nY Y").unwrap();
+        assert_eq!(
+            p.known_problems(1, eh),
+            vec![KnownProblemMatch { problem: "maximal matching".into(), degree: 3, speedups: 0, after_cleanup: false }]
+        );
+
+        let mut q = Problem::from_string("X X X\nY Y Y\nZ Z Z\n\nX YZ\nY XZ\nZ XY").unwrap().speedup(eh);
+        fix_problem(&mut q, true, true, eh);
+        let matches = q.known_problems(1, eh);
+        assert!(matches.iter().any(|m| m.problem == "3-coloring" && m.speedups == 1));
+        let m = matches.iter().find(|m| m.problem == "3-coloring").unwrap();
+        assert_eq!(m.to_string(), "3-coloring of the 3-regular tree after one speedup");
+
+        // sinkless orientation with the incoming edges split into two equivalent labels
+        let r = Problem::from_string("O I J J\n\nI OIJ\nJ OIJ").unwrap();
+        let matches = r.known_problems(0, eh);
+        assert_eq!(matches.len(), 1);
+        assert_eq!((matches[0].problem.as_str(), matches[0].degree, matches[0].after_cleanup), ("sinkless orientation", 4, true));
+
+        assert!(Problem::from_string("A B B\n\nA B").unwrap().known_problems(1, eh).is_empty());
+    }
+}
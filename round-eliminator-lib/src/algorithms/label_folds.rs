@@ -0,0 +1,111 @@
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+
+use crate::{
+    constraint::Constraint,
+    group::{GroupType, Label},
+    line::Line,
+    problem::Problem,
+};
+
+impl Problem {
+    /// Shows the labels of each group with the name of the group in `to_string_folded`, so that problems with many symmetric labels are easier to read.
+    /// Folding only changes how the problem is shown: the constraints are not changed, operations keep working on the original labels,
+    /// and `Display` still shows all the labels, since its text is also used to save and compare problems. Setting no groups removes folding.
+    pub fn set_label_folds(&mut self, groups: &[(Vec<Label>, String)]) -> Result<(), &'static str> {
+        if groups.iter().any(|(labels, _)| labels.is_empty()) {
+            return Err("A folded group of labels is empty");
+        }
+        self.check_labels(&groups.iter().flat_map(|(labels, _)| labels.iter().cloned()).collect::<Vec<_>>())?;
+        let mut seen = HashSet::new();
+        if !groups.iter().flat_map(|(labels, _)| labels.iter()).all(|l| seen.insert(*l)) {
+            return Err("Some label is in more than one folded group");
+        }
+        for (labels, name) in groups {
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                return Err("The name of a folded group is not valid");
+            }
+            if self.mapping_label_text.iter().any(|(l, t)| t == name && !labels.contains(l)) {
+                return Err("The name of a folded group is the name of a label that is not in the group");
+            }
+        }
+        self.label_folds = if groups.is_empty() { None } else { Some(groups.to_vec()) };
+        Ok(())
+    }
+
+    /// The problem as text, with the labels of each group shown with the name of the group.
+    /// Parts that end up with the same text are joined, and lines that end up with the same text are shown once,
+    /// so the result is meant to be read, and parsing it may give a different problem.
+    pub fn to_string_folded(&self) -> String {
+        let mut names: HashMap<Label, String> = self.mapping_label_text.iter().cloned().collect();
+        for (labels, name) in self.label_folds.iter().flatten() {
+            for label in labels {
+                if let Some(old) = names.get_mut(label) {
+                    *old = name.clone();
+                }
+            }
+        }
+        let order = self
+            .label_display_order()
+            .unwrap_or_else(|| self.labels().into_iter().enumerate().map(|(i, l)| (l, i)).collect());
+
+        let lines = |constraint: &Constraint| {
+            constraint.lines_ordered(&order).into_iter().map(|line| line.to_string_folded(&names, &order)).unique().map(|line| line + "\n").join("")
+        };
+        format!("{}\n{}", lines(&self.active), lines(&self.passive))
+    }
+}
+
+impl Line {
+    fn to_string_folded(&self, names: &HashMap<Label, String>, order: &HashMap<Label, usize>) -> String {
+        // the text of each part, with the number of ports, or None for a star
+        let mut parts: Vec<(String, Option<usize>)> = vec![];
+        for part in &self.parts {
+            let mut text: String = part.group.iter().sorted_by_key(|l| order[l]).map(|l| names[l].as_str()).unique().collect();
+            if text.is_empty() {
+                text.push('∅');
+            }
+            let count = match part.gtype {
+                GroupType::Many(n) => Some(n as usize),
+                GroupType::Star => None,
+            };
+            match parts.iter_mut().find(|(t, _)| *t == text) {
+                Some((_, c)) => *c = c.zip(count).map(|(a, b)| a + b),
+                None => parts.push((text, count)),
+            }
+        }
+        parts
+            .into_iter()
+            .map(|(text, count)| match count {
+                None => format!("{}*", text),
+                Some(1) => text,
+                Some(n) => format!("{}^{}", text, n),
+            })
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::problem::Problem;
+
+    #[test]
+    fn label_folds() {
+        let mut p = Problem::from_string("A B B\nB A A\nC C C\n\nA BC\nB C").unwrap();
+        let original = format!("{}", p);
+        p.set_label_folds(&[(vec![0, 1], "S".into())]).unwrap();
+        assert_eq!(p.to_string_folded(), "S^3\nC^3\n\nS SC\nS C\n");
+        assert_eq!(format!("{}", p), original);
+
+        let hardened = p.harden_remove(2, false);
+        assert_eq!(hardened.to_string_folded(), "S^3\n\nS^2\n");
+
+        assert!(p.set_label_folds(&[(vec![0], "C".into())]).is_err());
+        assert!(p.set_label_folds(&[(vec![0], "X".into()), (vec![0, 1], "Y".into())]).is_err());
+        assert!(p.set_label_folds(&[(vec![7], "X".into())]).is_err());
+        p.set_label_folds(&[]).unwrap();
+        assert_eq!(p.to_string_folded(), original);
+    }
+}
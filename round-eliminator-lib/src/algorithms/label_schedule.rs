@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+
+/// The largest number of labels allowed by autoub and autolb at each step of a search.
+/// Entry `i` bounds the problems obtained after `i` speedups, entry 0 being the initial problem, and the last entry is used for all the later steps.
+/// For example, `[15, 25, 40]` keeps the first steps small, while still allowing the later ones to grow.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LabelSchedule(pub Vec<usize>);
+
+impl LabelSchedule {
+    /// The same bound at every step.
+    pub fn uniform(max_labels: usize) -> Self {
+        LabelSchedule(vec![max_labels])
+    }
+
+    /// The bound for the problems obtained after `step` speedups. An empty schedule does not bound anything.
+    pub fn at(&self, step: usize) -> usize {
+        self.0.get(step).or(self.0.last()).copied().unwrap_or(usize::MAX)
+    }
+
+    /// The largest bound of the schedule.
+    pub fn max(&self) -> usize {
+        self.0.iter().copied().max().unwrap_or(usize::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        algorithms::{autolb::LbStrategy, autoub::{EvaluationPolicy, SearchStats}, event::EventHandler, pruning_rules::PruningRules},
+        problem::Problem,
+        serial::AutoOperation,
+    };
+
+    use super::LabelSchedule;
+
+    #[test]
+    fn label_schedule() {
+        let schedule = LabelSchedule(vec![15, 25, 40]);
+        assert_eq!((schedule.at(0), schedule.at(2), schedule.at(7), schedule.max()), (15, 40, 40, 40));
+        assert_eq!(LabelSchedule(vec![]).at(3), usize::MAX);
+
+        let eh = &mut EventHandler::null();
+        let p = Problem::from_string("M U^2\nP^3\n\nM UP\nU^2").unwrap();
+        let schedule = LabelSchedule(vec![3, 4]);
+        let mut found = false;
+        p.autoub_scheduled(&schedule, 2, 3, None, None, EvaluationPolicy::Eager, &PruningRules::default(), &mut SearchStats::default(), |_, _, sequence| {
+            found = true;
+            let mut speedups = 0;
+            for (op, p) in &sequence {
+                match op {
+                    AutoOperation::Speedup => speedups += 1,
+                    AutoOperation::Harden(_) => assert!(p.labels().len() <= schedule.at(speedups)),
+                    _ => {}
+                }
+            }
+        }, eh);
+        assert!(found);
+
+        let mut lower = None;
+        p.autolb_scheduled(&LabelSchedule(vec![3, 4]), 2, 1, 2, None, None, LbStrategy::Labels, &PruningRules::default(), |len, _| lower = Some(len), eh);
+        assert!(lower.is_some());
+    }
+}
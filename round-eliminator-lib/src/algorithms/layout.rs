@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use crate::{
+    constraint::Constraint,
+    group::{Group, Label},
+    line::Line,
+    problem::Problem,
+};
+
+use super::annotations::AnnotationTarget;
+
+/// Problems with at most this many labels are left untouched by `optimize_internal_layout`.
+pub const LAYOUT_BLOCK_SIZE: usize = 64;
+
+impl Problem {
+    /// Renames the internal label indices according to the given bijection.
+    /// Everything that refers to the current labels is updated accordingly, except for the fixpoint diagram, that is discarded.
+    /// Notes, folds, line origins and degree lifts that refer to labels that are not in the problem anymore are discarded.
+    pub fn relabel(&mut self, map: &HashMap<Label, Label>) {
+        let f = |l: &Label| map[l];
+        let fset = |v: &Vec<Label>| v.iter().map(f).sorted().collect::<Vec<_>>();
+        let fline = |line: &Line| {
+            line.groups()
+                .all(|g| g.iter().all(|l| map.contains_key(l)))
+                .then(|| line.edited(|g| Group(g.iter().map(f).sorted().collect())))
+        };
+
+        self.active = self.active.relabeled(map);
+        self.passive = self.passive.relabeled(map);
+        self.mapping_label_text = self
+            .mapping_label_text
+            .iter()
+            .map(|(l, s)| (f(l), s.clone()))
+            .sorted()
+            .collect();
+        if let Some(v) = self.mapping_label_oldlabels.as_mut() {
+            *v = v.iter().map(|(l, o)| (f(l), o.clone())).sorted().collect();
+        }
+        if let Some(v) = self.mapping_oldlabel_labels.as_mut() {
+            *v = v.iter().map(|(o, ls)| (*o, fset(ls))).collect();
+        }
+        if let Some(v) = self.trivial_sets.as_mut() {
+            *v = v.iter().map(fset).sorted().collect();
+        }
+        if let Some(v) = self.coloring_sets.as_mut() {
+            *v = v.iter().map(fset).sorted().collect();
+        }
+        if let Some(v) = self.color_labels.as_mut() {
+            *v = v.iter().filter_map(|l| map.get(l)).cloned().sorted().collect();
+        }
+        if let Some(v) = self.one_sided_labels.as_mut() {
+            *v = v.iter().filter_map(|(l, side)| map.get(l).map(|l| (*l, *side))).sorted().collect();
+        }
+        if let Some(v) = self.diagram_indirect.as_mut() {
+            *v = v.iter().map(|(a, b)| (f(a), f(b))).sorted().collect();
+        }
+        if let Some((merged, edges)) = self.diagram_direct.as_mut() {
+            *merged = merged.iter().map(|(l, g)| (f(l), fset(g))).sorted().collect();
+            *edges = edges.iter().map(|(a, b)| (f(a), f(b))).sorted().collect();
+        }
+        if let Some(v) = self.orientation_trivial_sets.as_mut() {
+            *v = v.iter().map(|(a, b)| (fset(a), fset(b))).collect();
+        }
+        if let Some(v) = self.orientation_coloring_sets.as_mut() {
+            *v = v.iter().map(|(a, b)| (fset(a), fset(b))).collect();
+        }
+        if let Some(v) = self.label_priorities.as_mut() {
+            *v = v.iter().map(|(l, p)| (f(l), p.clone())).collect();
+        }
+        if let Some(v) = self.annotations.as_mut() {
+            *v = v
+                .iter()
+                .filter_map(|(target, note)| {
+                    let target = match target {
+                        AnnotationTarget::Step => AnnotationTarget::Step,
+                        AnnotationTarget::Label(l) => AnnotationTarget::Label(*map.get(l)?),
+                        AnnotationTarget::Line(side, line) => AnnotationTarget::Line(*side, fline(line)?),
+                    };
+                    Some((target, note.clone()))
+                })
+                .sorted()
+                .collect();
+        }
+        if let Some(v) = self.label_folds.as_mut() {
+            *v = v
+                .iter()
+                .filter(|(labels, _)| labels.iter().all(|l| map.contains_key(l)))
+                .map(|(labels, name)| (fset(labels), name.clone()))
+                .collect();
+        }
+        if let Some(origins) = self.line_origins.as_mut() {
+            origins.relaxed = origins.relaxed.iter().filter_map(|(side, line)| Some((*side, fline(line)?))).collect();
+        }
+        if let Some(v) = self.degree_lifts.as_mut() {
+            for lift in v.iter_mut() {
+                lift.free_label = lift.free_label.and_then(|l| map.get(&l).cloned());
+            }
+        }
+        self.fixpoint_diagram = None;
+        // the computed fields have been renamed together with the constraints, so they are still valid
+        if self.computed_for.is_some() {
+            self.mark_computed();
+        }
+    }
+
+    /// Reorders the internal label indices so that labels that often appear together in the same group get close indices,
+    /// and hence likely end up in the same block of `LAYOUT_BLOCK_SIZE` labels.
+    /// This is done only for problems that have more than `LAYOUT_BLOCK_SIZE` labels.
+    pub fn optimize_internal_layout(&mut self) {
+        let labels = self.labels();
+        if labels.len() <= LAYOUT_BLOCK_SIZE {
+            return;
+        }
+
+        let index: HashMap<_, _> = labels.iter().enumerate().map(|(i, &l)| (l, i)).collect();
+        let n = labels.len();
+        let mut cooccurrences = vec![vec![0usize; n]; n];
+        for group in self.active.groups().chain(self.passive.groups()) {
+            for &a in group.iter() {
+                for &b in group.iter() {
+                    if a != b {
+                        cooccurrences[index[&a]][index[&b]] += 1;
+                    }
+                }
+            }
+        }
+
+        let mut placed = vec![false; n];
+        let mut order: Vec<usize> = Vec::with_capacity(n);
+        let mut score = vec![0usize; n];
+        while order.len() < n {
+            if order.len().is_multiple_of(LAYOUT_BLOCK_SIZE) {
+                // a new block starts, pick the label that co-occurs the most with the remaining ones
+                score = (0..n)
+                    .map(|i| (0..n).filter(|&j| !placed[j]).map(|j| cooccurrences[i][j]).sum())
+                    .collect();
+            }
+            let next = (0..n)
+                .filter(|&i| !placed[i])
+                .max_by_key(|&i| (score[i], std::cmp::Reverse(i)))
+                .unwrap();
+            placed[next] = true;
+            order.push(next);
+            if !order.len().is_multiple_of(LAYOUT_BLOCK_SIZE) {
+                // inside a block, the score of a label is the number of co-occurrences with the labels of the current block
+                if order.len() % LAYOUT_BLOCK_SIZE == 1 {
+                    score = vec![0; n];
+                }
+                for (s, c) in score.iter_mut().zip(cooccurrences[next].iter()) {
+                    *s += c;
+                }
+            }
+        }
+
+        let mut sorted_labels = labels.clone();
+        sorted_labels.sort_unstable();
+        let map: HashMap<Label, Label> = order
+            .into_iter()
+            .zip(sorted_labels)
+            .map(|(i, l)| (labels[i], l))
+            .collect();
+        self.relabel(&map);
+    }
+}
+
+impl Constraint {
+    pub fn relabeled(&self, map: &HashMap<Label, Label>) -> Self {
+        let mut c = self.edited(|g| Group(g.iter().map(|l| map[l]).sorted().collect()));
+        c.is_maximized = self.is_maximized;
+        c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashMap;
+
+    use itertools::Itertools;
+
+    use crate::{
+        algorithms::{
+            annotations::AnnotationTarget,
+            event::EventHandler,
+            lift::LiftPadding,
+            line_origins::{LineOrigin, LineOrigins},
+        },
+        problem::Problem,
+        store::Side,
+    };
+
+    #[test]
+    fn relabel() {
+        let mut p = Problem::from_string("A AB AB\n\nB AB").unwrap();
+        p.compute_diagram(&mut EventHandler::null());
+        p.relabel(&HashMap::from([(0, 1), (1, 0)]));
+        assert_eq!(format!("{}", p), "A BA^2\n\nB BA\n");
+        assert_eq!(p.diagram_indirect, Some(vec![(0, 0), (1, 0), (1, 1)]));
+    }
+
+    #[test]
+    fn relabel_metadata() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("A B\nC C\n\nA B\nC C").unwrap().lift_degree(LiftPadding::Free).unwrap();
+        let relaxed = vec![(Side::Passive, p.passive.lines[0].clone())];
+        p.line_origins = Some(LineOrigins { speedups: 1, relaxed });
+        p.annotate(AnnotationTarget::Label(0), "first".into());
+        p.annotate(AnnotationTarget::Line(Side::Passive, p.passive.lines[0].clone()), "line".into());
+        p.set_label_folds(&[(vec![0, 1], "AB".into())]).unwrap();
+        p.compute_triviality(eh);
+        let text = p.annotations_text();
+
+        // a cyclic shift of the four labels
+        let map: HashMap<_, _> = (0..4).map(|l| (l, (l + 1) % 4)).collect();
+        p.relabel(&map);
+        assert_eq!(p.annotation(&AnnotationTarget::Label(1)), Some("first"));
+        assert_eq!(p.annotations_text(), text);
+        assert_eq!(p.label_folds, Some(vec![(vec![1, 2], "AB".into())]));
+        assert_eq!(p.degree_lifts.as_ref().unwrap()[0].free_label, Some(0));
+        assert_eq!(p.line_origin(Side::Passive, &p.passive.lines[0]), LineOrigin::Relax);
+        for line in &p.passive.lines[1..] {
+            assert_eq!(p.line_origin(Side::Passive, line), LineOrigin::Speedup(1));
+        }
+        assert!(!p.is_stale());
+    }
+
+    #[test]
+    fn layout() {
+        let active = (0..100).map(|i| format!("(a{}) (b{})", i, i)).join("\n");
+        let passive = (0..100).map(|i| format!("(a{})(b{}) (a{})(b{})", i, i, i, i)).join("\n");
+        let mut p = Problem::from_string(format!("{}\n\n{}", active, passive)).unwrap();
+        let before = format!("{}", p);
+        p.optimize_internal_layout();
+        assert_eq!(p.labels(), (0..200).collect::<Vec<_>>());
+        for line in &p.active.lines {
+            let (a, b) = (line.parts[0].group[0], line.parts[1].group[0]);
+            assert_eq!(a / 64, b / 64);
+        }
+        let normalize = |s: String| {
+            s.lines()
+                .map(|line| line.split_whitespace().sorted().join(" "))
+                .sorted()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(normalize(before), normalize(format!("{}", p)));
+    }
+}
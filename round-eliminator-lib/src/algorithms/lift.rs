@@ -0,0 +1,140 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constraint::Constraint,
+    group::{Exponent, Group, GroupType, Label},
+    line::{Degree, Line},
+    part::Part,
+    problem::Problem,
+};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum LiftPadding {
+    /// The new port gets a fresh label that the passive side accepts together with anything.
+    Free,
+    /// The new port repeats the label of one of the other ports.
+    Repeat,
+}
+
+/// Records that the active degree of a problem has been increased from `from` to `from + 1`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct DegreeLift {
+    pub from: usize,
+    pub padding: LiftPadding,
+    pub free_label: Option<Label>,
+}
+
+impl Problem {
+    /// Increases the active degree by one, padding each configuration according to `padding`.
+    /// Any algorithm for the original problem can be turned into one for the lifted problem, and vice versa, in the same number of rounds.
+    /// The transformation is appended to `degree_lifts`.
+    pub fn lift_degree(&self, padding: LiftPadding) -> Result<Problem, &'static str> {
+        let from = match self.active.degree {
+            Degree::Finite(d) => d,
+            Degree::Star => return Err("Cannot lift a problem whose active side has arbitrary degree"),
+        };
+        if from >= Exponent::MAX as usize {
+            return Err("The lifted degree does not fit in an exponent");
+        }
+
+        let mut p = self.clone();
+        p.discard_computed_stuff();
+
+        let free_label = match padding {
+            LiftPadding::Repeat => {
+                let mut active = Constraint {
+                    lines: vec![],
+                    is_maximized: false,
+                    degree: Degree::Finite(from + 1),
+                };
+                for line in &self.active.lines {
+                    for i in 0..line.parts.len() {
+                        active.add_line_and_discard_non_maximal(line.with_repeated_port(i));
+                    }
+                }
+                p.active = active;
+                None
+            }
+            LiftPadding::Free => {
+                let free = self.labels().into_iter().max().map_or(0, |l| l + 1);
+                let used: HashSet<&str> = self.mapping_label_text.iter().map(|(_, s)| s.as_str()).collect();
+                let name = std::iter::once("(free)".to_string())
+                    .chain((1..).map(|i| format!("(free_{})", i)))
+                    .find(|name| !used.contains(name.as_str()))
+                    .unwrap();
+                p.mapping_label_text.push((free, name));
+
+                let free_part = Part {
+                    gtype: GroupType::ONE,
+                    group: Group(vec![free]),
+                };
+                for line in p.active.lines.iter_mut() {
+                    line.parts.push(free_part.clone());
+                    line.normalize();
+                }
+                p.active.degree = Degree::Finite(from + 1);
+                p.active.is_maximized = false;
+
+                let mut all = self.labels();
+                all.push(free);
+                all.sort_unstable();
+                let mut parts = vec![free_part];
+                let rest = match self.passive.degree {
+                    Degree::Finite(1) => None,
+                    Degree::Finite(d) => Some(GroupType::Many((d - 1) as Exponent)),
+                    Degree::Star => Some(GroupType::Star),
+                };
+                if let Some(gtype) = rest {
+                    parts.push(Part { gtype, group: Group(all) });
+                }
+                let mut line = Line { parts };
+                line.normalize();
+                p.passive.add_line_and_discard_non_maximal(line);
+                p.passive.is_maximized = false;
+                Some(free)
+            }
+        };
+
+        p.degree_lifts.get_or_insert_with(Vec::new).push(DegreeLift {
+            from,
+            padding,
+            free_label,
+        });
+        Ok(p)
+    }
+}
+
+impl Line {
+    fn with_repeated_port(&self, i: usize) -> Line {
+        let mut line = self.clone();
+        if let GroupType::Many(n) = line.parts[i].gtype {
+            line.parts[i].gtype = GroupType::Many(n + 1);
+        }
+        line.normalize();
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::problem::Problem;
+
+    use super::LiftPadding;
+
+    #[test]
+    fn lift() {
+        let p = Problem::from_string("A B\nC C\n\nA B\nC C").unwrap();
+
+        let free = p.lift_degree(LiftPadding::Free).unwrap();
+        assert_eq!(format!("{}", free), "A B (free)\n(free) C^2\n\nA B\nABC(free) (free)\nC^2\n");
+        let lift = &free.degree_lifts.as_ref().unwrap()[0];
+        assert_eq!((lift.from, lift.free_label), (2, Some(3)));
+
+        let repeat = p.lift_degree(LiftPadding::Repeat).unwrap();
+        assert_eq!(format!("{}", repeat), "B A^2\nA B^2\nC^3\n\nA B\nC^2\n");
+        assert_eq!(repeat.degree_lifts.as_ref().unwrap()[0].free_label, None);
+    }
+}
@@ -0,0 +1,218 @@
+use std::{cell::Cell, time::Duration};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{constraint::Constraint, group::Label, line::Line};
+
+use super::{
+    event::EventHandler,
+    watchdog::{mitigations, predicted_lines, BlowupWarning, Watchdog},
+};
+
+/// Bounds on the resources that an operation can use, `None` meaning unbounded.
+/// The browser needs to give up much earlier than a server, so the limits are given by the caller.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Limits {
+    pub max_labels: Option<usize>,
+    pub max_lines: Option<usize>,
+    pub max_time: Option<Duration>,
+    /// An estimate of the memory used by the lines of the constraints, in bytes.
+    pub max_mem: Option<usize>,
+    #[serde(default)]
+    pub watchdog: Option<Watchdog>,
+}
+
+/// The limit that has been exceeded, with its value.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Limit {
+    Labels(usize),
+    Lines(usize),
+    Time(Duration),
+    Mem(usize),
+    /// The number of lines predicted by the watchdog.
+    Blowup(usize),
+    /// The candidate lines could not be spilled to disk, see `spill_threshold`. The phase tells what went wrong.
+    Disk,
+}
+
+/// Returned by operations that stopped because a limit has been exceeded, with how far the computation got.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LimitExceeded {
+    pub limit: Limit,
+    /// The part of the operation that was running.
+    pub phase: String,
+    /// The number of labels and lines when the computation stopped.
+    pub labels: usize,
+    pub lines: usize,
+    /// The number of completed iterations of the phase, for example the rounds of combining lines during maximization.
+    pub iterations: usize,
+}
+
+static UNLIMITED: Limits = Limits { max_labels: None, max_lines: None, max_time: None, max_mem: None, watchdog: None };
+
+/// Checks the limits during an operation, remembering when it started.
+/// The clock of chrono is used since, unlike `Instant`, it is available on WASM as well.
+pub struct LimitGuard<'a> {
+    limits: &'a Limits,
+    start: Option<DateTime<Utc>>,
+    /// The watchdog warns only once for each operation.
+    warned: Cell<bool>,
+}
+
+impl<'a> LimitGuard<'a> {
+    pub fn new(limits: &'a Limits) -> Self {
+        LimitGuard {
+            limits,
+            start: limits.max_time.map(|_| Utc::now()),
+            warned: Cell::new(false),
+        }
+    }
+
+    /// A guard that never stops the operation, used by the operations that are called without limits.
+    pub fn unlimited() -> LimitGuard<'static> {
+        LimitGuard::new(&UNLIMITED)
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        *self.limits == UNLIMITED
+    }
+
+    pub fn check(&self, phase: &str, labels: usize, lines: usize, mem: usize, iterations: usize) -> Result<(), LimitExceeded> {
+        let exceeded = |limit| Err(LimitExceeded { limit, phase: phase.to_string(), labels, lines, iterations });
+        if let Some(max) = self.limits.max_labels.filter(|&max| labels > max) {
+            return exceeded(Limit::Labels(max));
+        }
+        if let Some(max) = self.limits.max_lines.filter(|&max| lines > max) {
+            return exceeded(Limit::Lines(max));
+        }
+        if let Some(max) = self.limits.max_mem.filter(|&max| mem > max) {
+            return exceeded(Limit::Mem(max));
+        }
+        if let (Some(max), Some(start)) = (self.limits.max_time, self.start) {
+            if (Utc::now() - start).to_std().is_ok_and(|elapsed| elapsed > max) {
+                return exceeded(Limit::Time(max));
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_constraint(&self, phase: &str, c: &Constraint, iterations: usize) -> Result<(), LimitExceeded> {
+        self.check(phase, c.labels_appearing().len(), c.lines.len(), estimated_mem(c), iterations)
+    }
+
+    /// Same as `check_constraint`, for a problem whose labels are already known.
+    pub(crate) fn check_sides(&self, phase: &str, labels: usize, active: &Constraint, passive: &Constraint, iterations: usize) -> Result<(), LimitExceeded> {
+        let mem = estimated_mem(active) + estimated_mem(passive);
+        self.check(phase, labels, active.lines.len().max(passive.lines.len()), mem, iterations)
+    }
+
+    /// Whether a watchdog is set, so that the callers can avoid collecting the lines obtained so far when it is not.
+    pub(crate) fn is_watching(&self) -> bool {
+        self.limits.watchdog.is_some()
+    }
+
+    /// Runs the watchdog, if any, on the lines obtained so far in a round of combinations that started with `start` lines,
+    /// after combining `processed` of its `total` pairs of lines. When a blowup is predicted, a `BlowupWarning` is sent through `eh`,
+    /// and the operation fails if the watchdog is set to abort.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn watch(&self, phase: &str, c: &Constraint, start: usize, processed: usize, total: usize, iterations: usize, eh: &mut EventHandler) -> Result<(), LimitExceeded> {
+        self.watch_count(phase, c, c.lines.len(), start, processed, total, iterations, eh)
+    }
+
+    /// Same as `watch`, when only the number `lines` of lines obtained so far is known, for example because they are stored on disk.
+    /// The mitigations are then suggested from the labels of `c`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn watch_count(&self, phase: &str, c: &Constraint, lines: usize, start: usize, processed: usize, total: usize, iterations: usize, eh: &mut EventHandler) -> Result<(), LimitExceeded> {
+        let Some(watchdog) = self.limits.watchdog else {
+            return Ok(());
+        };
+        let Some(predicted) = predicted_lines(start, lines, processed, total).filter(|&n| n > watchdog.max_predicted_lines) else {
+            return Ok(());
+        };
+        if !self.warned.replace(true) {
+            let (rarely_used, equivalent) = mitigations(c);
+            eh.warn(BlowupWarning {
+                phase: phase.to_string(),
+                lines,
+                processed_pairs: processed,
+                total_pairs: total,
+                predicted_lines: predicted,
+                rarely_used,
+                equivalent,
+            });
+        }
+        if watchdog.abort {
+            return Err(LimitExceeded {
+                limit: Limit::Blowup(predicted),
+                phase: phase.to_string(),
+                labels: c.labels_appearing().len(),
+                lines,
+                iterations,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// The error returned when spilling the lines of `c` to disk fails.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn disk_failure(phase: &str, error: impl std::fmt::Display, c: &Constraint, iterations: usize) -> LimitExceeded {
+    LimitExceeded {
+        limit: Limit::Disk,
+        phase: format!("{}: {}", phase, error),
+        labels: c.labels_appearing().len(),
+        lines: c.lines.len(),
+        iterations,
+    }
+}
+
+/// A rough estimate of the memory used by the lines of the constraint.
+pub(crate) fn estimated_mem(c: &Constraint) -> usize {
+    c.lines.iter().map(estimated_line_mem).sum()
+}
+
+pub(crate) fn estimated_line_mem(line: &Line) -> usize {
+    line.parts.iter().map(|part| 32 + part.group.0.len() * std::mem::size_of::<Label>()).sum::<usize>() + 24
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    use super::{Limit, Limits};
+
+    #[test]
+    fn limits() {
+        let eh = &mut EventHandler::null();
+        let p = Problem::from_string("A AB AB\n\nB AB\nA A").unwrap();
+        let unlimited = p.speedup_with_limits(&Limits::default(), eh).unwrap();
+        assert_eq!(unlimited.to_string(), p.speedup(eh).to_string());
+
+        let limits = Limits { max_labels: Some(1), ..Default::default() };
+        let e = p.speedup_with_limits(&limits, eh).unwrap_err();
+        assert_eq!(e.limit, Limit::Labels(1));
+        assert_eq!(e.phase, "speedup");
+
+        let mut q = Problem::from_string("A B C\n\nA B C\nB C C\nA A C").unwrap();
+        let limits = Limits { max_lines: Some(2), ..Default::default() };
+        let e = q.passive.maximize_with_limits(&limits, eh).unwrap_err();
+        assert_eq!((e.limit, e.phase.as_str(), e.lines), (Limit::Lines(2), "maximize", 3));
+        assert!(!q.passive.is_maximized);
+        let limits = Limits { max_time: Some(Duration::ZERO), ..Default::default() };
+        assert_eq!(q.passive.maximize_with_limits(&limits, eh).unwrap_err().limit, Limit::Time(Duration::ZERO));
+
+        let mut q = Problem::from_string("A B C\n\nA B C\nB C C").unwrap();
+        q.compute_diagram_with_limits(&Limits::default(), eh).unwrap();
+        assert!(q.diagram_indirect.is_some());
+
+        // the labels are compared only while there is time left
+        let mut q = Problem::from_string("A B\n\nA B\nB B").unwrap();
+        let e = q.compute_diagram_with_limits(&Limits { max_time: Some(Duration::ZERO), ..Default::default() }, eh).unwrap_err();
+        assert_eq!((e.limit, e.phase.as_str(), e.iterations), (Limit::Time(Duration::ZERO), "diagram", 0));
+        assert!(q.diagram_indirect.is_none());
+    }
+}
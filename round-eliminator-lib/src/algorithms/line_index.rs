@@ -0,0 +1,254 @@
+use crate::{
+    group::{Group, GroupType, Label},
+    line::Line,
+};
+
+#[derive(Default)]
+struct Node {
+    /// Sorted by label.
+    children: Vec<(Label, usize)>,
+    items: Vec<usize>,
+}
+
+/// A set-trie storing items under sets of labels, that finds the stored sets that are supersets or subsets of a given set
+/// by visiting only the parts of the trie that can contain them, instead of comparing the given set with all the stored ones.
+/// Sets are given as sorted vectors without repetitions.
+pub struct LineIndex {
+    nodes: Vec<Node>,
+}
+
+impl Default for LineIndex {
+    fn default() -> Self {
+        Self { nodes: vec![Node::default()] }
+    }
+}
+
+impl LineIndex {
+    pub fn insert(&mut self, set: &[Label], item: usize) {
+        let mut node = 0;
+        for &label in set {
+            node = match self.nodes[node].children.binary_search_by_key(&label, |&(l, _)| l) {
+                Ok(i) => self.nodes[node].children[i].1,
+                Err(i) => {
+                    let new = self.nodes.len();
+                    self.nodes.push(Node::default());
+                    self.nodes[node].children.insert(i, (label, new));
+                    new
+                }
+            };
+        }
+        self.nodes[node].items.push(item);
+    }
+
+    /// Removes an item, that must have been inserted with the same set.
+    pub fn remove(&mut self, set: &[Label], item: usize) {
+        let mut node = 0;
+        for &label in set {
+            let Ok(i) = self.nodes[node].children.binary_search_by_key(&label, |&(l, _)| l) else {
+                return;
+            };
+            node = self.nodes[node].children[i].1;
+        }
+        self.nodes[node].items.retain(|&x| x != item);
+    }
+
+    /// The items stored under a superset of `set`.
+    pub fn supersets(&self, set: &[Label]) -> Vec<usize> {
+        let mut result = vec![];
+        self.supersets_rec(0, set, &mut result);
+        result
+    }
+
+    fn supersets_rec(&self, node: usize, set: &[Label], result: &mut Vec<usize>) {
+        let Some((&first, rest)) = set.split_first() else {
+            self.all_rec(node, result);
+            return;
+        };
+        for &(label, child) in &self.nodes[node].children {
+            if label < first {
+                self.supersets_rec(child, set, result);
+            } else {
+                if label == first {
+                    self.supersets_rec(child, rest, result);
+                }
+                // labels are sorted along each path, so first cannot appear below larger labels
+                break;
+            }
+        }
+    }
+
+    fn all_rec(&self, node: usize, result: &mut Vec<usize>) {
+        result.extend(self.nodes[node].items.iter().cloned());
+        for &(_, child) in &self.nodes[node].children {
+            self.all_rec(child, result);
+        }
+    }
+
+    /// The items stored under a subset of `set`.
+    pub fn subsets(&self, set: &[Label]) -> Vec<usize> {
+        let mut result = vec![];
+        self.subsets_rec(0, set, &mut result);
+        result
+    }
+
+    fn subsets_rec(&self, node: usize, set: &[Label], result: &mut Vec<usize>) {
+        result.extend(self.nodes[node].items.iter().cloned());
+        for (i, &label) in set.iter().enumerate() {
+            if let Ok(j) = self.nodes[node].children.binary_search_by_key(&label, |&(l, _)| l) {
+                self.subsets_rec(self.nodes[node].children[j].1, &set[i + 1..], result);
+            }
+        }
+    }
+}
+
+impl Line {
+    /// The labels appearing in the line, if `includes` can only hold when the labels of the included line are a subset of the labels
+    /// of the including one, that is, if the line has no star and no part repeated 0 times.
+    pub(crate) fn inclusion_key(&self) -> Option<Vec<Label>> {
+        if self.parts.iter().any(|part| matches!(part.gtype, GroupType::Star | GroupType::Many(0))) {
+            return None;
+        }
+        let mut labels: Vec<Label> = self.parts.iter().flat_map(|part| part.group.iter().cloned()).collect();
+        labels.sort_unstable();
+        labels.dedup();
+        Some(labels)
+    }
+}
+
+/// The maximal lines among the ones added so far, in the order in which they have been added, as in repeated calls to
+/// `Constraint::add_line_and_discard_non_maximal_with_custom_supersets`. With the usual inclusion, that is, without `is_superset`,
+/// a new line is compared only with the lines whose labels are a subset or a superset of its labels, found with a `LineIndex`,
+/// and with the lines that have no `inclusion_key`. With a custom `is_superset`, it is compared with all of them.
+pub(crate) struct MaximalLines<T> {
+    is_superset: Option<T>,
+    index: LineIndex,
+    /// Removed lines leave an empty slot, so that the items of the index stay valid.
+    slots: Vec<Option<(Line, Option<Vec<Label>>)>>,
+    /// The slots of the lines that are not in the index.
+    unkeyed: Vec<usize>,
+    len: usize,
+}
+
+impl<T> MaximalLines<T>
+where
+    T: Fn(&Group, &Group) -> bool + Copy,
+{
+    pub fn new(is_superset: Option<T>) -> Self {
+        Self { is_superset, index: LineIndex::default(), slots: vec![], unkeyed: vec![], len: 0 }
+    }
+
+    /// The slots of the lines that may include the line with the given key, or that may be included in it.
+    fn candidates(&self, key: Option<&[Label]>, including: bool) -> Vec<usize> {
+        match key {
+            Some(key) => {
+                let mut candidates = if including { self.index.supersets(key) } else { self.index.subsets(key) };
+                candidates.extend(self.unkeyed.iter().cloned());
+                candidates
+            }
+            None => (0..self.slots.len()).collect(),
+        }
+    }
+
+    /// Adds the line, unless it is included in one of the lines, and then removes the lines included in it. Returns whether the line has been added.
+    pub fn add(&mut self, line: Line) -> bool {
+        let is_superset = self.is_superset;
+        let key = if is_superset.is_none() { line.inclusion_key() } else { None };
+        let included = self
+            .candidates(key.as_deref(), true)
+            .into_iter()
+            .any(|i| self.slots[i].as_ref().is_some_and(|(old, _)| old.includes_with_custom_supersets(&line, is_superset)));
+        if included {
+            return false;
+        }
+        for i in self.candidates(key.as_deref(), false) {
+            if self.slots[i].as_ref().is_some_and(|(old, _)| line.includes_with_custom_supersets(old, is_superset)) {
+                match self.slots[i].take().unwrap().1 {
+                    Some(old_key) => self.index.remove(&old_key, i),
+                    None => self.unkeyed.retain(|&j| j != i),
+                }
+                self.len -= 1;
+            }
+        }
+        match &key {
+            Some(key) => self.index.insert(key, self.slots.len()),
+            None => self.unkeyed.push(self.slots.len()),
+        }
+        self.slots.push(Some((line, key)));
+        self.len += 1;
+        // with a custom inclusion all the slots are visited, so the empty ones are dropped once they are the majority
+        if self.is_superset.is_some() && self.slots.len() > 2 * self.len + 64 {
+            self.slots.retain(|slot| slot.is_some());
+            self.unkeyed = (0..self.slots.len()).collect();
+        }
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &Line> {
+        self.slots.iter().flatten().map(|(line, _)| line)
+    }
+
+    pub fn into_lines(self) -> Vec<Line> {
+        self.slots.into_iter().flatten().map(|(line, _)| line).collect()
+    }
+}
+
+/// Removes the lines, all of the same degree, that are included in other lines, keeping the order of the remaining ones, as repeated calls to
+/// `Constraint::add_line_and_discard_non_maximal` would do.
+pub(crate) fn maximal_lines(lines: Vec<Line>) -> Vec<Line> {
+    let mut maximal = MaximalLines::new(None::<fn(&'_ _, &'_ _) -> _>);
+    for line in lines {
+        maximal.add(line);
+    }
+    maximal.into_lines()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashMap;
+
+    use crate::{constraint::Constraint, group::Group, line::Line};
+
+    use super::{LineIndex, MaximalLines};
+
+    #[test]
+    fn line_index() {
+        let mut index = LineIndex::default();
+        let sets: Vec<Vec<u32>> = vec![vec![1, 2, 3], vec![1, 3], vec![2], vec![], vec![2, 4], vec![1, 2, 3, 4]];
+        for (i, s) in sets.iter().enumerate() {
+            index.insert(s, i);
+        }
+        let sorted = |mut v: Vec<usize>| {
+            v.sort();
+            v
+        };
+        assert_eq!(sorted(index.supersets(&[1, 3])), vec![0, 1, 5]);
+        assert_eq!(sorted(index.supersets(&[2])), vec![0, 2, 4, 5]);
+        assert_eq!(sorted(index.subsets(&[1, 2, 3])), vec![0, 1, 2, 3]);
+        index.remove(&[2], 2);
+        assert_eq!(sorted(index.subsets(&[2, 4])), vec![3, 4]);
+
+        // the index gives the same lines as comparing all the pairs of lines
+        let mut mapping = HashMap::new();
+        let lines: Vec<Line> = ["A B C", "AB C C", "A B B", "ABC ABC D", "A BC C", "D D D", "AD BD C", "ABCD ABCD ABCD", "C C D"]
+            .iter()
+            .map(|l| Line::parse(l, &mut mapping).unwrap())
+            .collect();
+        let mut slow = Constraint { lines: vec![], is_maximized: false, degree: lines[0].degree() };
+        for line in lines.iter().take(7) {
+            slow.add_line_and_discard_non_maximal(line.clone());
+        }
+        let fast = super::maximal_lines(lines.iter().take(7).cloned().collect());
+        assert_eq!(fast, slow.lines);
+        let mut custom = MaximalLines::new(Some(|g1: &Group, g2: &Group| g1.is_superset(g2)));
+        for line in lines.iter().take(7) {
+            custom.add(line.clone());
+        }
+        assert_eq!(custom.into_lines(), slow.lines);
+        assert_eq!(super::maximal_lines(lines).len(), 1);
+    }
+}
@@ -3,6 +3,8 @@ use crate::group::{Group, GroupType, Label};
 use crate::line::Line;
 use crate::part::Part;
 use crate::problem::Problem;
+use itertools::Itertools;
+use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 
 impl Line {
@@ -52,11 +54,10 @@ impl Line {
                 lastgroup = part.group;
                 lastcount = part.gtype;
             } else {
-                use GroupType::*;
-                lastcount = match (lastcount, part.gtype) {
-                    (Many(a), Many(b)) => Many(a + b),
-                    (Star, _) | (_, Star) => Star,
-                };
+                // cannot fail for lines of problems that pass `check_exponents`
+                lastcount = lastcount
+                    .checked_add(part.gtype)
+                    .expect("the degree of a line does not fit in an exponent, see Problem::check_exponents");
             }
         }
         if lastcount != GroupType::Many(0) {
@@ -75,6 +76,31 @@ impl Line {
         }
     }
 
+    /// The order in which lines are kept and printed: lines are compared as the sorted lists of the groups of their ports,
+    /// so that, for example, `A^3` comes before `A^2 B`, which comes before `A B^2`. The groups of a star come after the other ones.
+    /// The lines should be normalized.
+    pub fn canonical_cmp(&self, other: &Line) -> Ordering {
+        self.ports_cmp(other, |g| g.0.clone()).then_with(|| self.cmp(other))
+    }
+
+    /// Compares the lines as in `canonical_cmp`, with groups compared by `key`.
+    pub(crate) fn ports_cmp<K: Ord>(&self, other: &Line, key: impl Fn(&Group) -> K) -> Ordering {
+        let groups = |line: &Line| -> Vec<(bool, K, usize)> {
+            line.parts
+                .iter()
+                .map(|part| match part.gtype {
+                    GroupType::Many(n) => (false, key(&part.group), n as usize),
+                    GroupType::Star => (true, key(&part.group), 1),
+                })
+                .sorted_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)))
+                .collect()
+        };
+        let (a, b) = (groups(self), groups(other));
+        let ports_a = a.iter().flat_map(|(star, key, n)| std::iter::repeat_n((star, key), *n));
+        let ports_b = b.iter().flat_map(|(star, key, n)| std::iter::repeat_n((star, key), *n));
+        ports_a.cmp(ports_b)
+    }
+
     pub fn sort_by_strength(&mut self, reachability: &HashMap<Label, HashSet<Label>>) {
         self.parts.sort_by(|a, b| {
             if a.group.len() != 1 || b.group.len() != 1 {
@@ -101,6 +127,51 @@ impl Line {
 }
 
 impl Constraint {
+    /// Returns the lines in a canonical form, that does not depend on the order of the lines, or of the parts of each line.
+    /// Two constraints that are printed differently only because of the order in which lines and parts appear
+    /// have the same canonical lines.
+    pub fn canonical_lines(&self) -> Vec<Line> {
+        let mut lines: Vec<Line> = self
+            .lines
+            .iter()
+            .map(|line| {
+                let mut line = line.clone();
+                line.normalize();
+                line
+            })
+            .collect();
+        lines.sort_unstable_by(|a, b| a.canonical_cmp(b));
+        lines.dedup();
+        lines
+    }
+
+    /// Replaces the lines with their canonical form, see `canonical_lines`.
+    /// This is done when a constraint is parsed and after a speedup, so that equal constraints are also written in the same way.
+    pub fn canonicalize(&mut self) {
+        for line in self.lines.iter_mut() {
+            line.normalize();
+        }
+        self.lines.sort_unstable_by(|a, b| a.canonical_cmp(b));
+        self.lines.dedup();
+    }
+
+    /// The lines in the order of their canonical form, see `canonical_lines`, each one written as it is.
+    /// This is the order used to print a constraint, so that the parts of each line can be sorted for display, as in `sort_lines_by_strength`.
+    pub fn lines_canonical_order(&self) -> Vec<&Line> {
+        let mut lines: Vec<(Line, &Line)> = self
+            .lines
+            .iter()
+            .map(|line| {
+                let mut normalized = line.clone();
+                normalized.normalize();
+                (normalized, line)
+            })
+            .collect();
+        lines.sort_by(|a, b| a.0.canonical_cmp(&b.0));
+        lines.dedup_by(|a, b| a.0 == b.0);
+        lines.into_iter().map(|(_, line)| line).collect()
+    }
+
     pub fn sort_lines_by_strength(&mut self, reachability: &HashMap<Label, HashSet<Label>>) {
         for line in self.lines.iter_mut() {
             line.sort_by_strength(reachability);
@@ -0,0 +1,152 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{line::Line, problem::Problem, store::Side};
+
+/// Where a line of a constraint comes from.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum LineOrigin {
+    /// The line was in the problem given as input.
+    Input,
+    /// The line has been created by the speedup with this number, starting from 1.
+    Speedup(usize),
+    /// The line has been changed by a relaxation, such as merging labels or adding an arrow.
+    Relax,
+}
+
+/// The origins of the lines of a problem, when they were computed. Every line comes from the last speedup, or from the input
+/// if there has been none, except for the ones changed by relaxations since then, so only the latter are stored.
+/// Lines that are later restricted, for example by hardening or when useless labels are discarded, keep the origin
+/// of the line they come from, see `line_origin`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LineOrigins {
+    /// The number of speedups that have been performed to obtain the problem.
+    pub speedups: usize,
+    /// The lines changed by relaxations after the last speedup.
+    pub relaxed: Vec<(Side, Line)>,
+}
+
+impl LineOrigins {
+    fn unchanged(&self) -> LineOrigin {
+        if self.speedups == 0 {
+            LineOrigin::Input
+        } else {
+            LineOrigin::Speedup(self.speedups)
+        }
+    }
+}
+
+impl Problem {
+    fn lines_of(&self, side: Side) -> &[Line] {
+        match side {
+            Side::Active => &self.active.lines,
+            Side::Passive => &self.passive.lines,
+        }
+    }
+
+    /// The number of speedups that have been performed to obtain the problem, as far as it is known.
+    pub fn speedups_performed(&self) -> usize {
+        self.line_origins.as_ref().map_or(0, |o| o.speedups)
+    }
+
+    /// Where the line comes from. A line that is contained in a line changed by a relaxation comes from the relaxation,
+    /// and lines of problems that have not been derived with operations that track origins come from the input.
+    pub fn line_origin(&self, side: Side, line: &Line) -> LineOrigin {
+        let Some(origins) = self.line_origins.as_ref() else {
+            return LineOrigin::Input;
+        };
+        let relaxed = origins.relaxed.iter().any(|(s, l)| *s == side && (l == line || l.includes(line)));
+        if relaxed {
+            LineOrigin::Relax
+        } else {
+            origins.unchanged()
+        }
+    }
+
+    /// The lines of the given side that have the given origin, for example the ones created by the last speedup.
+    pub fn lines_with_origin(&self, side: Side, origin: LineOrigin) -> Vec<&Line> {
+        self.lines_of(side).iter().filter(|line| self.line_origin(side, line) == origin).collect()
+    }
+
+    /// The origins of the lines of a problem obtained from this one with a speedup, where all the lines are new.
+    pub(crate) fn line_origins_after_speedup(&self) -> Option<LineOrigins> {
+        Some(LineOrigins { speedups: self.speedups_performed() + 1, relaxed: vec![] })
+    }
+
+    /// The origins of the lines of `new`, obtained from this problem with a relaxation that does not change the meaning of the labels:
+    /// the lines that are still there keep their origin, and the other ones come from the relaxation.
+    pub(crate) fn line_origins_after_relax(&self, new: &Problem) -> Option<LineOrigins> {
+        let mut relaxed = vec![];
+        for side in [Side::Active, Side::Passive] {
+            let old: HashSet<&Line> = self.lines_of(side).iter().collect();
+            for line in new.lines_of(side) {
+                if !old.contains(line) || self.line_origin(side, line) == LineOrigin::Relax {
+                    relaxed.push((side, line.clone()));
+                }
+            }
+        }
+        Some(LineOrigins { speedups: self.speedups_performed(), relaxed })
+    }
+
+    /// The origins of the lines after exchanging the two sides.
+    pub(crate) fn line_origins_swapped(&self) -> Option<LineOrigins> {
+        let mut origins = self.line_origins.clone()?;
+        for (side, _) in origins.relaxed.iter_mut() {
+            *side = match side {
+                Side::Active => Side::Passive,
+                Side::Passive => Side::Active,
+            };
+        }
+        Some(origins)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem, serial::fix_problem, store::Side};
+
+    use super::LineOrigin;
+
+    #[test]
+    fn line_origins() {
+        let eh = &mut EventHandler::null();
+        let p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        assert_eq!(p.line_origin(Side::Active, &p.active.lines[0]), LineOrigin::Input);
+
+        let mut q = p.speedup(eh);
+        fix_problem(&mut q, true, true, eh);
+        assert!(q.line_origins.as_ref().unwrap().relaxed.is_empty());
+        assert_eq!(q.lines_with_origin(Side::Passive, LineOrigin::Speedup(1)).len(), q.passive.lines.len());
+
+        let mut r = q.speedup(eh);
+        fix_problem(&mut r, true, true, eh);
+        assert_eq!(r.speedups_performed(), 2);
+        assert!(r.lines_with_origin(Side::Active, LineOrigin::Speedup(1)).is_empty());
+
+        // merging two labels changes only the lines that contain the merged label
+        let labels = r.labels();
+        let (from, to) = (labels[0], labels[1]);
+        let merged = r.relax_merge(from, to);
+        for side in [Side::Active, Side::Passive] {
+            let lines = match side {
+                Side::Active => &merged.active.lines,
+                Side::Passive => &merged.passive.lines,
+            };
+            for line in lines {
+                let expected = if r.lines_of(side).contains(line) { LineOrigin::Speedup(2) } else { LineOrigin::Relax };
+                assert_eq!(merged.line_origin(side, line), expected);
+            }
+        }
+
+        // the relaxed lines keep their origin when they are restricted by the cleanup
+        let mut cleaned = merged.clone();
+        fix_problem(&mut cleaned, true, true, eh);
+        assert!(!merged.lines_with_origin(Side::Active, LineOrigin::Relax).is_empty());
+        assert!(!cleaned.lines_with_origin(Side::Active, LineOrigin::Relax).is_empty());
+
+        let swapped = r.swap_sides();
+        assert_eq!(swapped.lines_with_origin(Side::Active, LineOrigin::Speedup(2)).len(), r.passive.lines.len());
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    group::{Exponent, Group, GroupType, Label},
+    line::Line,
+    part::Part,
+    problem::Problem,
+    store::Side,
+};
+
+use super::degree_check::sides;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum LintKind {
+    /// The same line, possibly written in a different way, appears more than once on the same side.
+    DuplicateLine,
+    /// Two lines of the same side differ only in one port, and can be written as a single line.
+    CombinableLines,
+    /// A label appears only on one side, so it can never be used.
+    OneSidedLabel,
+    /// A label appears on both sides, but each line containing it on some side requires labels that can never be used.
+    UnreachableLabel,
+}
+
+/// A change of the text of a problem that fixes a `LintWarning` without changing the problem that the text describes.
+/// Lines are numbered from 1 in the whole text, as in `LintWarning`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum LintFix {
+    RemoveLines(Vec<usize>),
+    /// Replaces the first line with the given text, and removes the other ones.
+    ReplaceLines(Vec<usize>, String),
+    /// Removes the label from all the lines, and drops the lines that are left with a port that has no labels.
+    RemoveLabel(String),
+}
+
+/// A suspicious pattern in the text of a problem, that usually comes from a transcription error.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LintWarning {
+    pub kind: LintKind,
+    /// The lines involved, numbered from 1 in the whole text.
+    pub line_numbers: Vec<usize>,
+    pub message: String,
+    pub fix: LintFix,
+}
+
+fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::Active => "active",
+        Side::Passive => "passive",
+    }
+}
+
+/// The single line allowing the configurations of both lines, if they have no star and differ only in one port.
+fn combined(line1: &Line, line2: &Line) -> Option<Line> {
+    if line1.has_star() || line2.has_star() {
+        return None;
+    }
+    let mut ports: HashMap<&Group, isize> = HashMap::new();
+    for (line, sign) in [(line1, 1), (line2, -1)] {
+        for part in &line.parts {
+            *ports.entry(&part.group).or_default() += sign * part.gtype.value() as isize;
+        }
+    }
+    let differences: Vec<_> = ports.into_iter().filter(|&(_, n)| n != 0).collect();
+    let [(g1, n1), (g2, n2)] = differences[..] else {
+        return None;
+    };
+    if n1.abs() != 1 || n1 + n2 != 0 {
+        return None;
+    }
+    let union = g1.union(g2);
+    let mut line = line1.clone();
+    let removed = if n1 > 0 { g1 } else { g2 };
+    let part = line.parts.iter_mut().find(|part| &part.group == removed).unwrap();
+    part.gtype = GroupType::Many(part.gtype.value() as Exponent - 1);
+    line.parts.push(Part { gtype: GroupType::ONE, group: union });
+    line.parts.retain(|part| part.gtype != GroupType::Many(0));
+    line.normalize();
+    Some(line)
+}
+
+/// Whether the line can still be used if the given labels are never used.
+fn usable(line: &Line, removed: &HashSet<Label>) -> bool {
+    line.parts
+        .iter()
+        .all(|part| part.gtype == GroupType::Star || part.group.iter().any(|l| !removed.contains(l)))
+}
+
+impl Problem {
+    /// Finds suspicious patterns in the text of a problem: lines that appear twice, pairs of lines that can be combined,
+    /// labels that appear only on one side, and labels that can never be used. Each warning comes with a fix that does not change
+    /// the problem; since fixes can change the line numbers, the text should be checked again after applying one of them.
+    /// Lines that cannot be parsed are ignored, since `from_string` reports them anyway.
+    pub fn lint_text(text: &str) -> Vec<LintWarning> {
+        let mut mapping = HashMap::new();
+        let parsed: Vec<(Side, Vec<(usize, Line)>)> = sides(text)
+            .into_iter()
+            .map(|(side, lines)| {
+                let lines = lines.into_iter().filter_map(|(n, l)| Line::parse(l, &mut mapping).ok().map(|line| (n, line))).collect();
+                (side, lines)
+            })
+            .collect();
+        let names: HashMap<Label, String> = mapping.iter().map(|(t, l)| (*l, t.clone())).collect();
+
+        let mut result = vec![];
+        for (_, lines) in &parsed {
+            let first = |i: usize| lines.iter().position(|(_, other)| *other == lines[i].1) == Some(i);
+            for i in (0..lines.len()).filter(|&i| first(i)) {
+                let duplicates: Vec<usize> = lines[i + 1..].iter().filter(|(_, other)| *other == lines[i].1).map(|(n, _)| *n).collect();
+                if !duplicates.is_empty() {
+                    result.push(LintWarning {
+                        kind: LintKind::DuplicateLine,
+                        line_numbers: std::iter::once(lines[i].0).chain(duplicates.iter().cloned()).collect(),
+                        message: format!("Line {} appears again at {}", lines[i].0, duplicates.iter().join(", ")),
+                        fix: LintFix::RemoveLines(duplicates),
+                    });
+                }
+            }
+            for i in (0..lines.len()).filter(|&i| first(i)) {
+                for j in (i + 1..lines.len()).filter(|&j| first(j)) {
+                    let Some(line) = combined(&lines[i].1, &lines[j].1) else {
+                        continue;
+                    };
+                    let text = line.to_string(&names);
+                    result.push(LintWarning {
+                        kind: LintKind::CombinableLines,
+                        line_numbers: vec![lines[i].0, lines[j].0],
+                        message: format!("Lines {} and {} differ only in one port, and can be written as {}", lines[i].0, lines[j].0, text),
+                        fix: LintFix::ReplaceLines(vec![lines[i].0, lines[j].0], text),
+                    });
+                }
+            }
+        }
+
+        let labels_of = |lines: &[(usize, Line)]| -> HashSet<Label> {
+            lines.iter().flat_map(|(_, line)| line.parts.iter().flat_map(|part| part.group.iter().cloned())).collect()
+        };
+        let containing = |label: Label| -> Vec<usize> {
+            parsed
+                .iter()
+                .flat_map(|(_, lines)| lines.iter())
+                .filter(|(_, line)| line.parts.iter().any(|part| part.group.contains(&label)))
+                .map(|(n, _)| *n)
+                .collect()
+        };
+        let active = labels_of(&parsed[0].1);
+        let passive = labels_of(&parsed[1].1);
+        let one_sided: HashSet<Label> = active.symmetric_difference(&passive).cloned().collect();
+
+        // removing labels can make lines unusable, and then more labels may appear only on one side
+        let mut removed = one_sided.clone();
+        loop {
+            let used: Vec<HashSet<Label>> = parsed
+                .iter()
+                .map(|(_, lines)| {
+                    lines
+                        .iter()
+                        .filter(|(_, line)| usable(line, &removed))
+                        .flat_map(|(_, line)| line.parts.iter().flat_map(|part| part.group.iter().cloned()))
+                        .filter(|l| !removed.contains(l))
+                        .collect()
+                })
+                .collect();
+            let more: Vec<Label> = active.union(&passive).filter(|l| !removed.contains(l) && !used.iter().all(|u| u.contains(l))).cloned().collect();
+            if more.is_empty() {
+                break;
+            }
+            removed.extend(more);
+        }
+
+        for label in removed.into_iter().sorted() {
+            let name = names[&label].clone();
+            let warning = if one_sided.contains(&label) {
+                let side = if active.contains(&label) { Side::Active } else { Side::Passive };
+                LintWarning {
+                    kind: LintKind::OneSidedLabel,
+                    line_numbers: containing(label),
+                    message: format!("Label {} appears only on the {} side, so it can never be used", name, side_name(side)),
+                    fix: LintFix::RemoveLabel(name),
+                }
+            } else {
+                LintWarning {
+                    kind: LintKind::UnreachableLabel,
+                    line_numbers: containing(label),
+                    message: format!("Label {} can never be used, since the lines containing it on some side require labels that can never be used", name),
+                    fix: LintFix::RemoveLabel(name),
+                }
+            };
+            result.push(warning);
+        }
+        result
+    }
+
+    /// Applies the fix of a warning given by `lint_text` to the same text.
+    pub fn apply_lint_fix(text: &str, fix: &LintFix) -> String {
+        let mut lines: Vec<Option<String>> = text.lines().map(|l| Some(l.to_string())).collect();
+        match fix {
+            LintFix::RemoveLines(numbers) => {
+                for &n in numbers {
+                    if let Some(l) = lines.get_mut(n.wrapping_sub(1)) {
+                        *l = None;
+                    }
+                }
+            }
+            LintFix::ReplaceLines(numbers, replacement) => {
+                for (i, &n) in numbers.iter().enumerate() {
+                    if let Some(l) = lines.get_mut(n.wrapping_sub(1)) {
+                        *l = if i == 0 { Some(replacement.clone()) } else { None };
+                    }
+                }
+            }
+            LintFix::RemoveLabel(name) => {
+                let mut mapping = HashMap::new();
+                let parsed: Vec<Option<Line>> = text.lines().map(|l| Line::parse(l, &mut mapping).ok()).collect();
+                let names: HashMap<Label, String> = mapping.iter().map(|(t, l)| (*l, t.clone())).collect();
+                if let Some(&label) = mapping.get(name) {
+                    for (l, line) in lines.iter_mut().zip(parsed) {
+                        let Some(mut line) = line else {
+                            continue;
+                        };
+                        if !line.parts.iter().any(|part| part.group.contains(&label)) {
+                            continue;
+                        }
+                        for part in line.parts.iter_mut() {
+                            part.group.0.retain(|&x| x != label);
+                        }
+                        if line.parts.iter().any(|part| part.group.is_empty() && part.gtype != GroupType::Star) {
+                            *l = None;
+                            continue;
+                        }
+                        line.parts.retain(|part| !part.group.is_empty());
+                        line.normalize();
+                        *l = Some(line.to_string(&names));
+                    }
+                }
+            }
+        }
+        lines.into_iter().flatten().join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::problem::Problem;
+
+    use super::{LintFix, LintKind};
+
+    #[test]
+    fn lint() {
+        let text = "A B B\nA B^2\nA A C\nA A D\nF F F\n\nA B\nC D\nF G";
+        let warnings = Problem::lint_text(text);
+        let kinds: Vec<_> = warnings.iter().map(|w| (w.kind, w.line_numbers.clone())).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (LintKind::DuplicateLine, vec![1, 2]),
+                (LintKind::CombinableLines, vec![3, 4]),
+                (LintKind::UnreachableLabel, vec![5, 9]),
+                (LintKind::OneSidedLabel, vec![9]),
+            ]
+        );
+        assert_eq!(warnings[0].fix, LintFix::RemoveLines(vec![2]));
+        assert_eq!(warnings[1].fix, LintFix::ReplaceLines(vec![3, 4], "CD A^2".into()));
+        assert_eq!(warnings[3].fix, LintFix::RemoveLabel("G".into()));
+
+        assert_eq!(Problem::apply_lint_fix(text, &warnings[1].fix), "A B B\nA B^2\nCD A^2\nF F F\n\nA B\nC D\nF G");
+        let fixed = Problem::apply_lint_fix(text, &warnings[2].fix);
+        assert_eq!(fixed, "A B B\nA B^2\nA A C\nA A D\n\nA B\nC D");
+        let mut fixed = text.to_string();
+        while let Some(w) = Problem::lint_text(&fixed).first() {
+            fixed = Problem::apply_lint_fix(&fixed, &w.fix);
+        }
+        assert_eq!(fixed, "A B B\nCD A^2\n\nA B\nC D");
+
+        assert!(Problem::lint_text("M U U\nP P P\n\nM UP\nU U").is_empty());
+    }
+}
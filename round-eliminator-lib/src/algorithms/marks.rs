@@ -88,8 +88,10 @@ fn handle_choice_nodes<T,F>(active : &Constraint, choice : &Vec<TableIndex>, pre
 impl Problem {
 
     pub fn apply_marks_technique(&mut self, eh: &mut EventHandler) {
+        self.discard_stale_computed_stuff();
         let r = self.marks(eh);
         self.marks_works = Some(r);
+        self.mark_computed();
     }
 
     pub fn marks(&self, eh: &mut EventHandler) -> bool {
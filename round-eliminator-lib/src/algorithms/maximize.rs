@@ -1,11 +1,13 @@
 use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicBool, Ordering, AtomicUsize};
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Mutex;
 use dashmap::DashMap as CHashMap;
 use streaming_iterator::StreamingIterator;
 use std::time::Instant;
 
 use crate::{
-    algorithms::multisets_pairing::Pairings,
+    algorithms::{line_index::MaximalLines, multisets_pairing::Pairings},
     constraint::Constraint,
     group::{Group, GroupType},
     line::Line,
@@ -13,24 +15,85 @@ use crate::{
 };
 
 use super::event::EventHandler;
+use super::limits::{estimated_mem, LimitExceeded, LimitGuard, Limits};
+#[cfg(not(target_arch = "wasm32"))]
+use super::{limits::{disk_failure, estimated_line_mem}, spill::{spill_threshold, LineSpill}};
+
+/// How a line obtained while maximizing comes from the lines that have been combined, see `maximize_custom`.
+pub type Tracking = (Line, Line, Line, Vec<Vec<usize>>, Vec<(usize, usize, Operation)>);
 
 impl Constraint {
 
     
+    /// Maximizes the constraint, combining lines with the given operations on groups.
+    /// The limits of `guard` are checked while the pairs of lines are combined; when one is exceeded,
+    /// the constraint contains the lines found so far, and it is not marked as maximized.
+    #[allow(clippy::too_many_arguments)]
     pub fn maximize_custom<FS,FU,FI>(
         &mut self,
         eh: &mut EventHandler,
         allow_empty : bool,
         track_unions : bool,
-        tracking : Option<&CHashMap<Line, (Line, Line, Line, Vec<Vec<usize>>, Vec<(usize, usize, Operation)>)>>,
+        tracking : Option<&CHashMap<Line,Tracking>>,
+        guard : &LimitGuard,
+        f_is_superset : FS,
+        f_union : FU,
+        f_intersection : FI
+    ) -> Result<(), LimitExceeded> where FS : Fn(&Group,&Group) -> bool + Copy + Send + Sync, FU : Fn(&Group,&Group) -> Group + Copy + Send + Sync, FI : Fn(&Group,&Group) -> Group + Copy + Send + Sync {
+        self.maximize_spilling(eh, false, allow_empty, track_unions, tracking, guard, f_is_superset, f_union, f_intersection)
+    }
+
+    /// `maximize_custom` with the usual operations on groups. Then the candidate lines are compared only with
+    /// the lines whose labels are a subset or a superset of their labels, see `MaximalLines`.
+    pub(crate) fn maximize_guarded(&mut self, guard: &LimitGuard, eh: &mut EventHandler) -> Result<(), LimitExceeded> {
+        let f_is_superset = |g1 : &Group ,g2 : &Group |{ g1.is_superset(g2) };
+        let f_union = |g1 : &Group ,g2 : &Group |{ g1.union(g2) };
+        let f_intersection = |g1 : &Group ,g2 : &Group |{ g1.intersection(g2) };
+        self.maximize_spilling(eh, true, false, false, None, guard, f_is_superset, f_union, f_intersection)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn maximize_spilling<FS,FU,FI>(
+        &mut self,
+        eh: &mut EventHandler,
+        indexed : bool,
+        allow_empty : bool,
+        track_unions : bool,
+        tracking : Option<&CHashMap<Line,Tracking>>,
+        guard : &LimitGuard,
+        f_is_superset : FS,
+        f_union : FU,
+        f_intersection : FI
+    ) -> Result<(), LimitExceeded> where FS : Fn(&Group,&Group) -> bool + Copy + Send + Sync, FU : Fn(&Group,&Group) -> Group + Copy + Send + Sync, FI : Fn(&Group,&Group) -> Group + Copy + Send + Sync {
+        let result = self.maximize_rounds(eh, indexed, allow_empty, track_unions, tracking, guard, f_is_superset, f_union, f_intersection);
+        // without limits, only spilling to disk can fail, and then the lines obtained so far are combined in memory
+        #[cfg(not(target_arch = "wasm32"))]
+        if result.is_err() && guard.is_unlimited() {
+            return super::spill::with_spill_threshold(None, || {
+                self.maximize_rounds(eh, indexed, allow_empty, track_unions, tracking, guard, f_is_superset, f_union, f_intersection)
+            });
+        }
+        result
+    }
+
+    /// When `indexed` is true, `f_is_superset` must be the usual inclusion of groups.
+    #[allow(clippy::too_many_arguments)]
+    fn maximize_rounds<FS,FU,FI>(
+        &mut self,
+        eh: &mut EventHandler,
+        indexed : bool,
+        allow_empty : bool,
+        track_unions : bool,
+        tracking : Option<&CHashMap<Line,Tracking>>,
+        guard : &LimitGuard,
         f_is_superset : FS,
         f_union : FU,
         f_intersection : FI
-    ) where FS : Fn(&Group,&Group) -> bool + Copy + Send + Sync, FU : Fn(&Group,&Group) -> Group + Copy + Send + Sync, FI : Fn(&Group,&Group) -> Group + Copy + Send + Sync {
+    ) -> Result<(), LimitExceeded> where FS : Fn(&Group,&Group) -> bool + Copy + Send + Sync, FU : Fn(&Group,&Group) -> Group + Copy + Send + Sync, FI : Fn(&Group,&Group) -> Group + Copy + Send + Sync {
  
         if self.is_maximized || self.lines.is_empty() {
             self.is_maximized = true;
-            return;
+            return Ok(());
         }
 
         let becomes_star = 100;
@@ -39,30 +102,123 @@ impl Constraint {
         let mut seen_pairs = CHashMap::<(usize,usize),()>::new();
         let next_id = AtomicUsize::new(1);
 
+        let custom_superset = if indexed { None } else { Some(f_is_superset) };
         let lines = std::mem::take(&mut self.lines);
-        let empty = self.clone();
+        let mut maximal = MaximalLines::new(custom_superset);
         for mut line in lines {
             line.normalize();
             seen.insert(line.clone(),next_id.fetch_add(1,Ordering::SeqCst));
-            self.add_line_and_discard_non_maximal_with_custom_supersets(line, Some(f_is_superset));
+            maximal.add(line);
         }
+        self.lines = maximal.into_lines();
+        let maximal_of = |c : &Constraint| {
+            let mut maximal = MaximalLines::new(custom_superset);
+            for line in &c.lines {
+                maximal.add(line.clone());
+            }
+            maximal
+        };
+        let degree = self.degree;
+        let constraint_of = |maximal : MaximalLines<FS>| Constraint{ lines: maximal.into_lines(), is_maximized: false, degree };
+        // combining lines never introduces new labels
+        let labels = self.labels_appearing().len();
+        guard.check_constraint("maximize", self, 0)?;
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let spill_threshold = spill_threshold().map_err(|e| disk_failure("maximize", e, self, 0))?;
 
+        let mut iterations = 0;
         loop {
             let lines = &self.lines;
+            let start = lines.len();
+            let total_pairs = start * (start + 1) / 2;
             let useful_ids : HashSet<usize> = lines.iter().map(|line|*seen.get(line).unwrap()).collect();
             seen_pairs = seen_pairs.into_iter().filter(|((p1,p2),_)| useful_ids.contains(p1) && useful_ids.contains(p2)).collect();
 
             let without_one = without_one(lines);
 
             #[cfg(not(target_arch = "wasm32"))]
-            let newconstraint = {
+            let newconstraint = if let Some(threshold) = spill_threshold {
+                // the candidates may not fit in memory, they are combined sequentially and stored on disk
+                let mut spill = LineSpill::new(threshold, custom_superset);
+                let len = lines.len();
+                for i in 0..len {
+                    for j in 0..=i {
+                        eh.notify("combining line pairs", (2. * (i * (i+1)/2 + j) as f64).sqrt() as usize, len);
+
+                        let id1 = *seen.get(&lines[i]).unwrap();
+                        let id2 = *seen.get(&lines[j]).unwrap();
+                        let pair = (id1,id2);
+                        if seen_pairs.contains_key(&pair)
+                            || seen_pairs.contains_key(&(pair.1, pair.0))
+                        {
+                            continue;
+                        }
+                        seen_pairs.insert(pair,());
+
+                        let (candidates,_,how) = combine_lines_custom(
+                            &lines[i],
+                            &lines[j],
+                            &without_one[i],
+                            &without_one[j],
+                            &seen,
+                            Some(&next_id),
+                            becomes_star,
+                            allow_empty,
+                            track_unions,
+                            tracking.is_some(),
+                            f_is_superset, f_union, f_intersection
+                        );
+                        if let Some(tracking) = tracking {
+                            for (a,b) in how.into_iter() {
+                                tracking.insert(a,b);
+                            }
+                        }
+                        for newline in candidates {
+                            spill.push(newline).map_err(|e| disk_failure("maximize, writing the candidate lines", e, self, iterations))?;
+                        }
+                    }
+                    guard.check("maximize", labels, self.lines.len(), estimated_mem(self), iterations)?;
+                    guard.watch_count("maximize", self, spill.stored(), start, (i + 1) * (i + 2) / 2, total_pairs, iterations, eh)?;
+                }
+                let mut newconstraint = maximal_of(self);
+                spill.merge_into(&mut newconstraint).map_err(|e| disk_failure("maximize, reading the candidate lines", e, self, iterations))?;
+                constraint_of(newconstraint)
+            } else if indexed {
+                self.combine_pairs_indexed(eh, guard, maximal_of(self), labels, iterations, &|i, j| {
+                    let id1 = *seen.get(&lines[i]).unwrap();
+                    let id2 = *seen.get(&lines[j]).unwrap();
+                    let pair = (id1,id2);
+                    if seen_pairs.contains_key(&pair)
+                        || seen_pairs.contains_key(&(pair.1, pair.0))
+                    {
+                        return vec![];
+                    }
+                    seen_pairs.insert(pair,());
+                    combine_lines_custom(
+                        &lines[i],
+                        &lines[j],
+                        &without_one[i],
+                        &without_one[j],
+                        &seen,
+                        Some(&next_id),
+                        becomes_star,
+                        allow_empty,
+                        track_unions,
+                        false,
+                        f_is_superset, f_union, f_intersection
+                    ).0
+                }).map(constraint_of)?
+            } else {
                 let v = append_only_vec::AppendOnlyVec::<_>::new();
                 for line in self.lines.iter() {
                     v.push((AtomicBool::new(false),line.clone()));
                 }
                 let newconstraint = std::sync::Arc::new(v);
+                // set when a limit is exceeded, the remaining pairs are then skipped
+                let stop = AtomicBool::new(false);
 
-                crossbeam::scope(|s| {
+                let exceeded = crossbeam::scope(|s| {
                     let (in_tx, in_rx) =  crossbeam_channel::unbounded();
                     let (out_tx, out_rx) =  crossbeam_channel::unbounded();
                     let (progress_tx, progress_rx) : (crossbeam_channel::Sender<()>,crossbeam_channel::Receiver<()>)  =  crossbeam_channel::unbounded();
@@ -84,8 +240,13 @@ impl Constraint {
                         let (in_tx, in_rx) : (crossbeam_channel::Sender<(usize,usize)>,crossbeam_channel::Receiver<(usize,usize)>) = (in_tx.clone(), in_rx.clone());
                         let (out_tx, out_rx) = (out_tx.clone(), out_rx.clone());
                         let next_id = &next_id;
+                        let stop = &stop;
                         s.spawn(move |_|{
                             while let Ok((i,j)) = in_rx.recv() {
+                                if stop.load(Ordering::Relaxed) {
+                                    out_tx.send(vec![]).unwrap();
+                                    continue;
+                                }
                                 let id1 = *seen.get(&lines[i]).unwrap();
                                 let id2 = *seen.get(&lines[j]).unwrap();
                                 let pair = (id1,id2);
@@ -175,11 +336,28 @@ impl Constraint {
                         if last_notify.elapsed().as_millis() > 100 {
                             eh.notify("combining line pairs", (2. *received as f64).sqrt() as usize, len);
                             last_notify = Instant::now();
+                            let live = newconstraint.iter().filter(|(removed,_)|!removed.load(Ordering::Relaxed));
+                            let (lines, mem) = live.fold((0, 0), |(lines, mem), (_,line)| (lines + 1, mem + estimated_line_mem(line)));
+                            if let Err(e) = guard.check("maximize", labels, lines, mem, iterations) {
+                                stop.store(true, Ordering::Relaxed);
+                                return Some(e);
+                            }
+                            if guard.is_watching() {
+                                let live = newconstraint.iter().filter(|(removed,_)|!removed.load(Ordering::Relaxed));
+                                let snapshot = Constraint{ lines: live.map(|(_,line)|line.clone()).collect(), is_maximized: false, degree };
+                                if let Err(e) = guard.watch("maximize", &snapshot, len, received, total, iterations, eh) {
+                                    stop.store(true, Ordering::Relaxed);
+                                    return Some(e);
+                                }
+                            }
                         }
                     }
                     //println!("It took {}s",now.elapsed().as_secs());
-
+                    None
                 }).unwrap();
+                if let Some(e) = exceeded {
+                    return Err(e);
+                }
 
                 //let c1 = newconstraint.iter().filter(|(removed,_)|!removed.load(Ordering::SeqCst)).count();
                 //let c2 = newconstraint.iter().filter(|(removed,_)|removed.load(Ordering::SeqCst)).count();
@@ -191,9 +369,9 @@ impl Constraint {
 
             #[cfg(target_arch = "wasm32")]
             let newconstraint = {
-                let mut newconstraint = self.clone();
+                let mut newconstraint = maximal_of(self);
                 for i in 0..lines.len() {
-                    let mut candidates2 = empty.clone();
+                    let mut candidates2 = MaximalLines::new(custom_superset);
                     let len = lines.len();
                     for j in 0..=i {
                         eh.notify("combining line pairs", (2. * (i * (i+1)/2 + j) as f64).sqrt() as usize, len);
@@ -227,15 +405,18 @@ impl Constraint {
                             }
                         }
                         for newline in candidates {
-                            candidates2.add_line_and_discard_non_maximal_with_custom_supersets(newline,Some(f_is_superset));
+                            candidates2.add(newline);
                         }
                     }
 
-                    for newline in candidates2.lines {
-                        newconstraint.add_line_and_discard_non_maximal_with_custom_supersets(newline,Some(f_is_superset));
+                    for newline in candidates2.into_lines() {
+                        newconstraint.add(newline);
                     }
+                    let snapshot = Constraint{ lines: newconstraint.lines().cloned().collect(), is_maximized: false, degree: self.degree };
+                    guard.check("maximize", labels, snapshot.lines.len(), estimated_mem(&snapshot), iterations)?;
+                    guard.watch("maximize", &snapshot, start, (i + 1) * (i + 2) / 2, total_pairs, iterations, eh)?;
                 }
-                newconstraint
+                constraint_of(newconstraint)
             };
 
             //println!("seen elements: {}, seen_pairs elements: {}",seen.len(),seen_pairs.len());
@@ -244,16 +425,107 @@ impl Constraint {
                 break;
             }
             *self = newconstraint;
+            iterations += 1;
+            guard.check_constraint("maximize", self, iterations)?;
+            guard.watch("maximize", self, start, total_pairs, total_pairs, iterations, eh)?;
         }
 
         self.is_maximized = true;
+        Ok(())
     }
 
     pub fn maximize(&mut self, eh: &mut EventHandler) {
-        let f_is_superset = |g1 : &Group ,g2 : &Group |{ g1.is_superset(g2) };
-        let f_union = |g1 : &Group ,g2 : &Group |{ g1.union(g2) };
-        let f_intersection = |g1 : &Group ,g2 : &Group |{ g1.intersection(g2) };
-        self.maximize_custom(eh,false,false,None,f_is_superset,f_union,f_intersection);
+        self.maximize_guarded(&LimitGuard::unlimited(), eh)
+            .expect("maximization without limits cannot fail");
+    }
+
+    /// Same as `maximize`, but stops when a limit is exceeded. In that case the constraint contains the lines found so far, and it is not marked as maximized.
+    pub fn maximize_with_limits(&mut self, limits: &Limits, eh: &mut EventHandler) -> Result<(), LimitExceeded> {
+        self.maximize_guarded(&LimitGuard::new(limits), eh)
+    }
+
+    /// Combines all the pairs of lines of the constraint with `combine`, in parallel, and adds the results to `maximal`.
+    /// The limits and the watchdog of `guard` are checked every 100ms; when one is exceeded, the remaining pairs are skipped.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn combine_pairs_indexed<FS>(
+        &self,
+        eh: &mut EventHandler,
+        guard : &LimitGuard,
+        maximal : MaximalLines<FS>,
+        labels : usize,
+        iterations : usize,
+        combine : &(dyn Fn(usize,usize) -> Vec<Line> + Sync)
+    ) -> Result<MaximalLines<FS>, LimitExceeded> where FS : Fn(&Group,&Group) -> bool + Copy + Send + Sync {
+        let len = self.lines.len();
+        let total = len * (len+1)/2;
+        let maximal = Mutex::new(maximal);
+        let stop = AtomicBool::new(false);
+        let next_pair = AtomicUsize::new(0);
+
+        let n_workers = std::env::var("RE_NUM_THREADS").ok().and_then(|val| val.parse::<usize>().ok()).unwrap_or_else(num_cpus::get);
+
+        let exceeded = crossbeam::scope(|s| {
+            let (progress_tx, progress_rx) = crossbeam_channel::unbounded::<()>();
+            for _ in 0..n_workers {
+                let progress_tx = progress_tx.clone();
+                let (maximal, stop, next_pair) = (&maximal, &stop, &next_pair);
+                s.spawn(move |_|{
+                    loop {
+                        let k = next_pair.fetch_add(1, Ordering::Relaxed);
+                        if k >= total {
+                            break;
+                        }
+                        if !stop.load(Ordering::Relaxed) {
+                            // the k-th pair (i,j) with j <= i
+                            let i = ((((8 * k + 1) as f64).sqrt() as usize) - 1) / 2;
+                            let i = if (i + 1) * (i + 2) / 2 <= k { i + 1 } else { i };
+                            let j = k - i * (i + 1) / 2;
+                            let candidates = combine(i, j);
+                            let mut maximal = maximal.lock().unwrap();
+                            for newline in candidates {
+                                maximal.add(newline);
+                            }
+                        }
+                        progress_tx.send(()).unwrap();
+                    }
+                });
+            }
+            drop(progress_tx);
+
+            // once a limit is exceeded, the workers skip the remaining pairs, but they are still received until the end,
+            // since the workers send a message for each pair
+            let mut exceeded = None;
+            let mut last_notify = Instant::now();
+            for received in 0..total {
+                progress_rx.recv().unwrap();
+                if exceeded.is_none() && last_notify.elapsed().as_millis() > 100 {
+                    eh.notify("combining line pairs", (2. *received as f64).sqrt() as usize, len);
+                    last_notify = Instant::now();
+                    let (lines, mem) = {
+                        let maximal = maximal.lock().unwrap();
+                        (maximal.len(), maximal.lines().map(estimated_line_mem).sum())
+                    };
+                    if let Err(e) = guard.check("maximize", labels, lines, mem, iterations) {
+                        stop.store(true, Ordering::Relaxed);
+                        exceeded = Some(e);
+                        continue;
+                    }
+                    if guard.is_watching() {
+                        let lines = maximal.lock().unwrap().lines().cloned().collect();
+                        let snapshot = Constraint{ lines, is_maximized: false, degree: self.degree };
+                        if let Err(e) = guard.watch("maximize", &snapshot, len, received, total, iterations, eh) {
+                            stop.store(true, Ordering::Relaxed);
+                            exceeded = Some(e);
+                        }
+                    }
+                }
+            }
+            exceeded
+        }).unwrap();
+        match exceeded {
+            Some(e) => Err(e),
+            None => Ok(maximal.into_inner().unwrap()),
+        }
     }
 }
 
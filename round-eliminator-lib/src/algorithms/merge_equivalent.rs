@@ -16,12 +16,11 @@ impl Problem {
 
     pub fn repeat_merge_equivalent_labels(&self, eh : &mut EventHandler) -> Problem {
         let mut p = self.clone();
-        if p.diagram_indirect.is_none() {
-            p.compute_diagram(eh);
-        }        
-        p = p.merge_equivalent_labels();
-        p.discard_useless_stuff(true, eh);
         loop {
+            // a partial diagram, or no diagram at all if the last cleanup did not compute it, does not tell which labels are equivalent
+            if p.diagram_direct.is_none() {
+                p.compute_diagram(eh);
+            }
             let l = p.labels().len();
             p = p.merge_equivalent_labels();
             p.discard_useless_stuff(true, eh);
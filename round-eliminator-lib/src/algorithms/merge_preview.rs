@@ -0,0 +1,136 @@
+use std::borrow::Cow;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{group::Label, line::Degree, problem::Problem};
+
+use super::{event::EventHandler, limits::Limits};
+
+/// What merging a label into another one would give, computed without simplifying the resulting problem.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct MergePreview {
+    pub from: Label,
+    pub to: Label,
+    /// The number of labels after the merge, before discarding the ones that become useless.
+    pub labels: usize,
+    /// The number of distinct lines of each side, before removing the ones that become non-maximal.
+    pub active_lines: usize,
+    pub passive_lines: usize,
+    /// Whether the result is zero round solvable, or None if the quick check cannot tell, because maximizing its passive side exceeds the limits.
+    pub trivial: Option<bool>,
+    /// The edges of the diagram that involve `from`, and the ones they become by replacing `from` with `to`.
+    /// The diagram of the result may have further changes.
+    pub edges_removed: Vec<(Label, Label)>,
+    pub edges_added: Vec<(Label, Label)>,
+}
+
+/// The problem itself if its diagram has been computed, and otherwise a copy with the diagram computed.
+fn with_direct_diagram<'a>(p: &'a Problem, eh: &mut EventHandler) -> Cow<'a, Problem> {
+    if p.diagram_direct.is_some() {
+        return Cow::Borrowed(p);
+    }
+    let mut p = p.clone();
+    if p.diagram_indirect.is_none() {
+        p.compute_partial_diagram(eh);
+    } else {
+        p.compute_direct_diagram();
+    }
+    Cow::Owned(p)
+}
+
+impl Problem {
+    /// Previews the relaxation obtained by replacing `from` with `to`, see `relax_merge`.
+    /// The diagram is computed if it is missing. The triviality of the result is checked only if maximizing its passive side,
+    /// when its degree is not 2, stays within `limits`.
+    pub fn preview_merge(&self, from: Label, to: Label, limits: &Limits, eh: &mut EventHandler) -> MergePreview {
+        let p = with_direct_diagram(self, eh);
+        p.preview_merge_with_diagram(from, to, limits, eh)
+    }
+
+    fn preview_merge_with_diagram(&self, from: Label, to: Label, limits: &Limits, eh: &mut EventHandler) -> MergePreview {
+        let edges = &self.diagram_direct.as_ref().unwrap().1;
+        let mut relaxed = self.relax_merge(from, to);
+
+        let trivial = if self.trivial_sets.as_ref().is_some_and(|t| !t.is_empty()) {
+            // a relaxation of a trivial problem is trivial
+            Some(true)
+        } else {
+            // the triviality check needs the passive side maximized, unless its degree is 2
+            if relaxed.passive.degree == Degree::Finite(2) || relaxed.passive.maximize_with_limits(limits, eh).is_ok() {
+                relaxed.compute_triviality(eh);
+                relaxed.trivial_sets.as_ref().map(|t| !t.is_empty())
+            } else {
+                None
+            }
+        };
+
+        let edges_removed: Vec<_> = edges.iter().cloned().filter(|&(a, b)| a == from || b == from).collect();
+        let replace = |l: Label| if l == from { to } else { l };
+        let edges_added = edges_removed
+            .iter()
+            .map(|&(a, b)| (replace(a), replace(b)))
+            .filter(|&(a, b)| a != b && !edges.contains(&(a, b)))
+            .unique()
+            .collect();
+
+        MergePreview {
+            from,
+            to,
+            labels: self.labels().len() - (from != to) as usize,
+            active_lines: relaxed.active.canonical_lines().into_iter().dedup().count(),
+            passive_lines: relaxed.passive.canonical_lines().into_iter().dedup().count(),
+            trivial,
+            edges_removed,
+            edges_added,
+        }
+    }
+
+    /// Previews all the merges along the edges of the diagram, merging each label into a stronger one.
+    /// The diagram is computed if it is missing, and `limits` apply to each triviality check, see `preview_merge`.
+    pub fn preview_diagram_merges(&self, limits: &Limits, eh: &mut EventHandler) -> Vec<MergePreview> {
+        let p = with_direct_diagram(self, eh);
+        let edges = p.diagram_direct.as_ref().unwrap().1.clone();
+        edges.into_iter().map(|(a, b)| p.preview_merge_with_diagram(a, b, limits, eh)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        algorithms::{event::EventHandler, limits::Limits},
+        problem::Problem,
+    };
+
+    #[test]
+    fn merge_preview() {
+        let eh = &mut EventHandler::null();
+        let limits = &Limits::default();
+        let mut p = Problem::from_string("M U^2\nP^3\n\nM UP\nU^2").unwrap();
+        p.compute_triviality(eh);
+        // the diagram is computed when it is missing
+        let previews = p.preview_diagram_merges(limits, eh);
+        p.compute_diagram(eh);
+        assert_eq!(previews.len(), p.diagram_direct.as_ref().unwrap().1.len());
+        assert_eq!(previews, p.preview_diagram_merges(limits, eh));
+        // sinkless orientation with incoming edges split into two labels stays nontrivial when they are merged
+        let q = Problem::from_string("O I^2\nO J^2\n\nO IJ\nIJ IJ").unwrap();
+        assert_eq!(q.preview_merge(2, 1, limits, eh).trivial, Some(false));
+
+        // merging P into U gives U^3 on the active side, which is trivial
+        let preview = p.preview_merge(2, 1, limits, eh);
+        assert_eq!(preview.labels, 2);
+        assert_eq!(preview.active_lines, 2);
+        assert_eq!(preview.trivial, Some(true));
+        let mut merged = p.relax_merge(2, 1);
+        merged.passive.maximize(eh);
+        merged.compute_triviality(eh);
+        assert!(!merged.trivial_sets.unwrap().is_empty());
+
+        // the passive side of the result has degree 3, and maximizing it needs more than one line
+        let r = Problem::from_string("A B C\n\nA B C\nB C C\nA A C").unwrap();
+        assert_eq!(r.preview_merge(0, 1, &Limits { max_lines: Some(1), ..Default::default() }, eh).trivial, None);
+        assert!(r.preview_merge(0, 1, limits, eh).trivial.is_some());
+    }
+}
@@ -23,4 +23,71 @@ pub mod autolb;
 pub mod fixpoint;
 pub mod multigraph;
 pub mod marks;
-pub mod merge_subdiagram;
\ No newline at end of file
+pub mod merge_subdiagram;
+pub mod priorities;
+pub mod passive_graph;
+pub mod homomorphism;
+pub mod layout;
+pub mod addarrow;
+pub mod split;
+pub mod projection;
+pub mod lift;
+pub mod relabeling;
+pub mod defective;
+pub mod step_diff;
+pub mod reachability;
+pub mod annotations;
+pub mod color_family;
+pub mod order_invariant;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod spill;
+pub mod speedup_estimate;
+pub mod explain;
+pub mod degree_check;
+pub mod complement;
+pub mod search_tree;
+pub mod distributed;
+pub mod paper_notation;
+pub mod quotient;
+pub mod merge_preview;
+pub mod provenance;
+pub mod structured_passive;
+pub mod diagram_width;
+pub mod zero_round;
+pub mod counting_parser;
+pub mod nontriviality;
+pub mod constraint_algebra;
+pub mod swap_sides;
+pub mod precompute;
+pub mod decision_diagram;
+pub mod renaming_presets;
+pub mod limits;
+pub mod line_origins;
+pub mod label_schedule;
+pub mod fixed_point_core;
+pub mod configurations;
+pub mod staleness;
+pub mod coordinate_descent;
+pub mod label_folds;
+pub mod line_index;
+pub mod exponents;
+pub mod resume;
+pub mod coloring_reductions;
+pub mod canonical_text;
+pub mod lint;
+pub mod diagram_export;
+pub mod cycle_pumping;
+pub mod color_labels;
+pub mod neighborhood_graph;
+pub mod watchdog;
+pub mod alphabets;
+pub mod power;
+pub mod self_relaxation;
+pub mod diagram_editing;
+pub mod upper_bound_summary;
+pub mod disjoint_union;
+pub mod pruning_rules;
+pub mod canonical_hash;
+pub mod batch_triviality;
+pub mod side_maximization;
+pub mod known_problems;
\ No newline at end of file
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    group::{Group, GroupType, Label},
+    line::{Degree, Line},
+    part::Part,
+    problem::Problem,
+};
+
+use super::{
+    event::EventHandler,
+    passive_graph::{xml_escape, GraphFormat},
+};
+
+/// The graph used by the 0-round checks of a problem with passive degree 2.
+/// There is a vertex for each minimal set of labels of the choices of the active side, that is, for each way in which
+/// a node can fix its output in 0 rounds, and there is an edge between two vertices if the passive side allows any pair of labels
+/// taken from the two sets. A vertex has a self loop if its set of labels is a trivial solution, and if there is no self loop,
+/// a clique of size x gives an algorithm that solves the problem in 0 rounds given an x coloring.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct NeighborhoodGraph {
+    pub vertices: Vec<Vec<Label>>,
+    /// The edges (i,j) with i <= j, where i == j is a self loop.
+    pub edges: Vec<(usize, usize)>,
+}
+
+impl NeighborhoodGraph {
+    /// The adjacency lists of the graph without the self loops.
+    pub fn adjacency(&self) -> Vec<Vec<usize>> {
+        let mut adj = vec![vec![]; self.vertices.len()];
+        for &(a, b) in self.edges.iter().filter(|(a, b)| a != b) {
+            adj[a].push(b);
+            adj[b].push(a);
+        }
+        adj
+    }
+
+    /// The vertices that have a self loop.
+    pub fn loops(&self) -> Vec<usize> {
+        self.edges.iter().filter(|(a, b)| a == b).map(|(a, _)| *a).collect()
+    }
+}
+
+impl Problem {
+    /// Computes the neighborhood graph of the problem, see `NeighborhoodGraph`.
+    /// As in `compute_coloring_solvability`, the passive side is used as it is, so it should be maximized.
+    pub fn neighborhood_graph(&self, eh: &mut EventHandler) -> Result<NeighborhoodGraph, &'static str> {
+        if self.passive.degree != Degree::Finite(2) {
+            return Err("The neighborhood graph is defined only if the passive degree is 2");
+        }
+        let vertices: Vec<Vec<Label>> = self
+            .active
+            .minimal_sets_of_all_choices()
+            .into_iter()
+            .map(|set| set.into_iter().sorted().collect())
+            .collect();
+        let part = |set: &Vec<Label>| Part {
+            gtype: GroupType::ONE,
+            group: Group(set.clone()),
+        };
+
+        let mut edges = vec![];
+        for (i, set1) in vertices.iter().enumerate() {
+            for (j, set2) in vertices.iter().enumerate().skip(i) {
+                eh.notify("coloring graph", vertices.len() * i + j, vertices.len() * vertices.len());
+                let line = Line {
+                    parts: vec![part(set1), part(set2)],
+                };
+                if self.passive.includes(&line) {
+                    edges.push((i, j));
+                }
+            }
+        }
+        Ok(NeighborhoodGraph { vertices, edges })
+    }
+
+    /// Exports the neighborhood graph, where each vertex is named by its set of labels.
+    pub fn neighborhood_graph_to_string(&self, format: GraphFormat, eh: &mut EventHandler) -> Result<String, &'static str> {
+        let graph = self.neighborhood_graph(eh)?;
+        let mapping: HashMap<_, _> = self.mapping_label_text.iter().cloned().collect();
+        let names: Vec<String> = graph.vertices.iter().map(|set| set.iter().map(|l| mapping[l].as_str()).join("")).collect();
+        let s = match format {
+            GraphFormat::EdgeList => graph.edges.iter().map(|&(a, b)| format!("{} {}\n", names[a], names[b])).join(""),
+            GraphFormat::Dot => {
+                let mut s = String::from("graph neighborhood {\n");
+                for name in &names {
+                    s.push_str(&format!("    \"{}\";\n", name));
+                }
+                for &(a, b) in &graph.edges {
+                    s.push_str(&format!("    \"{}\" -- \"{}\";\n", names[a], names[b]));
+                }
+                s.push_str("}\n");
+                s
+            }
+            GraphFormat::GraphML => {
+                let mut s = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+                s.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+                s.push_str("  <graph id=\"neighborhood\" edgedefault=\"undirected\">\n");
+                for name in &names {
+                    s.push_str(&format!("    <node id=\"{}\"/>\n", xml_escape(name)));
+                }
+                for &(a, b) in &graph.edges {
+                    s.push_str(&format!("    <edge source=\"{}\" target=\"{}\"/>\n", xml_escape(&names[a]), xml_escape(&names[b])));
+                }
+                s.push_str("  </graph>\n</graphml>\n");
+                s
+            }
+        };
+        Ok(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        algorithms::{event::EventHandler, passive_graph::GraphFormat},
+        problem::Problem,
+    };
+
+    #[test]
+    fn neighborhood_graph() {
+        let eh = &mut EventHandler::null();
+        let p = Problem::from_string("A A A\nB B B\nC C C\n\nA BC\nB C").unwrap();
+        let g = p.neighborhood_graph(eh).unwrap();
+        assert_eq!(g.vertices, vec![vec![0], vec![1], vec![2]]);
+        assert_eq!(g.edges, vec![(0, 1), (0, 2), (1, 2)]);
+        assert!(g.loops().is_empty());
+        assert_eq!(p.neighborhood_graph_to_string(GraphFormat::EdgeList, eh).unwrap(), "A B\nA C\nB C\n");
+        assert!(p.neighborhood_graph_to_string(GraphFormat::Dot, eh).unwrap().contains("\"A\" -- \"B\";"));
+
+        let mut q = Problem::from_string("A AB AB\n\nA A\nB B").unwrap();
+        q.passive.maximize(eh);
+        let g = q.neighborhood_graph(eh).unwrap();
+        assert_eq!(g.loops().len(), g.vertices.len());
+
+        assert!(Problem::from_string("A A A\n\nA A A").unwrap().neighborhood_graph(eh).is_err());
+    }
+}
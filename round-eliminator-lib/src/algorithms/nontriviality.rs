@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use crate::{group::Label, line::Degree, problem::Problem};
+
+use super::event::EventHandler;
+
+/// A proof that a problem is not zero round solvable: a zero round algorithm outputs the same active configuration on every node,
+/// so the passive side must allow every combination of the labels it uses. For every minimal set of labels of an active configuration,
+/// the certificate gives two of its labels (possibly the same label twice) that are never used together on the passive side.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NonTrivialityCertificate {
+    pub witnesses: Vec<(Vec<Label>, Label, Label)>,
+}
+
+impl NonTrivialityCertificate {
+    pub fn to_text(&self, mapping: &HashMap<Label, String>) -> Vec<String> {
+        self.witnesses
+            .iter()
+            .map(|(set, a, b)| {
+                format!(
+                    "{} contains {} and {}, which are never together on the passive side",
+                    set.iter().map(|l| &mapping[l]).join(""),
+                    mapping[a],
+                    mapping[b]
+                )
+            })
+            .collect()
+    }
+}
+
+impl Problem {
+    /// Whether some passive configuration contains both `a` and `b`, in different positions. This does not require the passive side to be maximized.
+    fn passive_allows_together(&self, a: Label, b: Label) -> bool {
+        self.passive.lines.iter().any(|line| {
+            let count = |f: &dyn Fn(&[Label]) -> bool| line.parts.iter().filter(|part| f(&part.group.0)).map(|part| part.gtype.value()).sum::<usize>();
+            let ca = count(&|g| g.contains(&a));
+            let cb = count(&|g| g.contains(&b));
+            if a == b {
+                ca >= 2
+            } else {
+                let cab = count(&|g| g.contains(&a) && g.contains(&b));
+                ca >= 1 && cb >= 1 && !(ca == 1 && cb == 1 && cab == 1)
+            }
+        })
+    }
+
+    /// Searches for a certificate that the problem is not zero round solvable, see `NonTrivialityCertificate`.
+    /// This is much cheaper than `compute_triviality`, since the passive side does not need to be maximized,
+    /// but it may fail to find a certificate for problems that are not trivial.
+    pub fn nontriviality_certificate(&self) -> Option<NonTrivialityCertificate> {
+        match self.passive.degree {
+            Degree::Finite(d) if d >= 2 => {}
+            _ => return None,
+        }
+        let witnesses = self
+            .active
+            .minimal_sets_of_all_choices()
+            .into_iter()
+            .map(|set| {
+                let set: Vec<Label> = set.into_iter().sorted().collect();
+                let (a, b) = set
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(i, &a)| set[i..].iter().map(move |&b| (a, b)))
+                    .find(|&(a, b)| !self.passive_allows_together(a, b))?;
+                Some((set, a, b))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(NonTrivialityCertificate { witnesses })
+    }
+
+    /// Same as `compute_triviality`, but if a `nontriviality_certificate` exists, the problem is marked as not trivial without the full check.
+    pub fn compute_triviality_with_certificate(&mut self, eh: &mut EventHandler) {
+        if self.nontriviality_certificate().is_some() {
+            self.trivial_sets = Some(vec![]);
+            self.mark_computed();
+        } else {
+            self.compute_triviality(eh);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    #[test]
+    fn nontriviality() {
+        let mut p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        let certificate = p.nontriviality_certificate().unwrap();
+        let mapping = p.mapping_label_text.iter().cloned().collect();
+        let mut text = certificate.to_text(&mapping);
+        text.sort();
+        assert_eq!(
+            text,
+            vec![
+                "MU contains M and M, which are never together on the passive side",
+                "P contains P and P, which are never together on the passive side"
+            ]
+        );
+        p.compute_triviality_with_certificate(&mut EventHandler::null());
+        assert_eq!(p.trivial_sets, Some(vec![]));
+
+        let mut p = Problem::from_string("A AB AB\n\nA A\nB B").unwrap();
+        assert!(p.nontriviality_certificate().is_none());
+        p.compute_triviality_with_certificate(&mut EventHandler::null());
+        assert!(!p.trivial_sets.unwrap().is_empty());
+    }
+}
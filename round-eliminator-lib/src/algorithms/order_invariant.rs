@@ -0,0 +1,79 @@
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    group::{Group, GroupType, Label},
+    line::{Degree, Line},
+    part::Part,
+    problem::Problem,
+};
+
+/// A certificate that a problem on paths and cycles requires Ω(log* n) rounds, given by the Ramsey argument.
+/// An algorithm running in o(log* n) rounds can be made order-invariant, and then all nodes of a path with increasing identifiers
+/// see the same view, and hence output the same label a towards the smaller identifiers and the same label b towards the larger ones.
+/// Every edge would then get the labels a and b, but for each such pair the passive side forbids it.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LogStarLowerBound {
+    /// All pairs (a,b) that a node can output, none of which is allowed on an edge.
+    pub forbidden_pairs: Vec<(Label, Label)>,
+}
+
+impl Problem {
+    /// Tries to prove an Ω(log* n) lower bound for a problem on paths and cycles, that is, with active and passive degree 2.
+    /// Returns None if the argument does not apply, which does not imply that the problem can be solved faster.
+    pub fn log_star_lower_bound(&self) -> Result<Option<LogStarLowerBound>, &'static str> {
+        if self.active.degree != Degree::Finite(2) || self.passive.degree != Degree::Finite(2) {
+            return Err("It is required that both the active and the passive degree are 2.");
+        }
+        let part = |l: Label| Part {
+            gtype: GroupType::ONE,
+            group: Group(vec![l]),
+        };
+
+        let mut pairs = vec![];
+        for line in &self.active.lines {
+            let (g1, g2) = match &line.parts[..] {
+                [p] => (&p.group, &p.group),
+                [p1, p2] => (&p1.group, &p2.group),
+                _ => unreachable!(),
+            };
+            for (&a, &b) in g1.iter().cartesian_product(g2.iter()) {
+                pairs.push((a, b));
+                pairs.push((b, a));
+            }
+        }
+        let pairs: Vec<_> = pairs.into_iter().sorted().dedup().collect();
+
+        let allowed = pairs.iter().any(|&(a, b)| {
+            self.passive.includes(&Line {
+                parts: vec![part(a), part(b)],
+            })
+        });
+        if allowed {
+            Ok(None)
+        } else {
+            Ok(Some(LogStarLowerBound { forbidden_pairs: pairs }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::problem::Problem;
+
+    #[test]
+    fn log_star_lower_bound() {
+        let coloring = Problem::from_string("A A\nB B\nC C\n\nA BC\nB C").unwrap();
+        let certificate = coloring.log_star_lower_bound().unwrap().unwrap();
+        assert_eq!(certificate.forbidden_pairs, vec![(0, 0), (1, 1), (2, 2)]);
+
+        let mis = Problem::from_string("M M\nP O\n\nM PO\nO O").unwrap();
+        assert!(mis.log_star_lower_bound().unwrap().is_some());
+
+        let orientation = Problem::from_string("A B\n\nA B").unwrap();
+        assert!(orientation.log_star_lower_bound().unwrap().is_none());
+
+        assert!(Problem::from_string("A A A\n\nA A").unwrap().log_star_lower_bound().is_err());
+    }
+}
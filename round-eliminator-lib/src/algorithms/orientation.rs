@@ -83,6 +83,7 @@ impl Problem {
         outdegree: usize,
         eh: &mut EventHandler,
     ) {
+        self.discard_stale_computed_stuff();
         if self.orientation_trivial_sets.is_some() {
             panic!("triviality has been computed already");
         }
@@ -92,6 +93,31 @@ impl Problem {
 
         //self.passive.maximize(eh);
 
+        self.orientation_trivial_sets = Some(self.orientation_trivial_sets_of(outdegree, eh));
+        self.mark_computed();
+    }
+
+    pub fn compute_coloring_solvability_given_orientation(
+        &mut self,
+        outdegree: usize,
+        eh: &mut EventHandler,
+    ) {
+        self.discard_stale_computed_stuff();
+        if self.passive.degree != Degree::Finite(2) {
+            panic!("cannot compute coloring solvability given orientation if the passive side has degree different from 2");
+        }
+        if self.orientation_coloring_sets.is_some() {
+            panic!("coloring solvability has been computed already");
+        }
+
+        //self.passive.maximize(eh);
+
+        self.orientation_coloring_sets = Some(self.orientation_coloring_sets_of(outdegree, eh));
+        self.mark_computed();
+    }
+
+    /// The sets computed by `compute_triviality_given_orientation`, for a problem whose passive side has degree 2.
+    pub(crate) fn orientation_trivial_sets_of(&self, outdegree: usize, eh: &mut EventHandler) -> Vec<(Vec<Label>, Vec<Label>)> {
         let splits = self.active.minimal_splits(outdegree);
 
         let mut trivial_sets = vec![];
@@ -118,23 +144,11 @@ impl Problem {
             }
         }
 
-        self.orientation_trivial_sets = Some(trivial_sets);
+        trivial_sets
     }
 
-    pub fn compute_coloring_solvability_given_orientation(
-        &mut self,
-        outdegree: usize,
-        eh: &mut EventHandler,
-    ) {
-        if self.passive.degree != Degree::Finite(2) {
-            panic!("cannot compute coloring solvability given orientation if the passive side has degree different from 2");
-        }
-        if self.orientation_coloring_sets.is_some() {
-            panic!("coloring solvability has been computed already");
-        }
-
-        //self.passive.maximize(eh);
-
+    /// The sets computed by `compute_coloring_solvability_given_orientation`, for a problem whose passive side has degree 2.
+    pub(crate) fn orientation_coloring_sets_of(&self, outdegree: usize, eh: &mut EventHandler) -> Vec<(Vec<Label>, Vec<Label>)> {
         let splits = self.active.minimal_splits(outdegree);
 
         let mut edges = vec![];
@@ -178,8 +192,7 @@ impl Problem {
         }
 
         if edges.is_empty() {
-            self.orientation_coloring_sets = Some(vec![]);
-            return;
+            return vec![];
         }
 
         let n = splits.len();
@@ -198,7 +211,7 @@ impl Problem {
             .collect();
         coloring_sets.sort();
 
-        self.orientation_coloring_sets = Some(coloring_sets);
+        coloring_sets
     }
 }
 
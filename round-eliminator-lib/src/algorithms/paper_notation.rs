@@ -0,0 +1,212 @@
+use serde::{Deserialize, Serialize};
+
+use crate::problem::Problem;
+
+/// A line of a problem written in the notation of papers, and how it has been rewritten in the internal format.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Interpretation {
+    /// The position of the line in the text, starting from 1.
+    pub line_number: usize,
+    pub original: String,
+    pub normalized: String,
+    pub notes: Vec<String>,
+}
+
+const SUPERSCRIPTS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+
+fn superscript_digit(c: char) -> Option<char> {
+    SUPERSCRIPTS
+        .iter()
+        .position(|&s| s == c)
+        .map(|d| (b'0' + d as u8) as char)
+}
+
+/// A label name in the internal format, where names longer than one character are written in parentheses.
+fn label_text(name: &str) -> String {
+    if name.chars().count() == 1 || (name.starts_with('(') && name.ends_with(')')) {
+        name.to_string()
+    } else {
+        format!("({})", name)
+    }
+}
+
+struct Port {
+    /// The labels, if the port is a set written with braces, otherwise the text of the port.
+    set: Option<Vec<String>>,
+    bare: String,
+    exponent: String,
+}
+
+impl Port {
+    fn new() -> Self {
+        Port {
+            set: None,
+            bare: String::new(),
+            exponent: String::new(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.set.is_none() && self.bare.is_empty() && self.exponent.is_empty()
+    }
+}
+
+fn note(notes: &mut Vec<String>, s: &str) {
+    if !notes.iter().any(|n| n == s) {
+        notes.push(s.to_string());
+    }
+}
+
+/// Splits a line into ports, understanding set braces, port separators, and superscript exponents.
+fn ports(line: &str, notes: &mut Vec<String>) -> Result<Vec<Port>, &'static str> {
+    let mut result = vec![];
+    let mut current = Port::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '[' => {
+                let close = if c == '{' { '}' } else { ']' };
+                let mut content = String::new();
+                loop {
+                    match chars.next() {
+                        Some(x) if x == close => break,
+                        Some(x) => content.push(x),
+                        None => return Err("A set is not closed"),
+                    }
+                }
+                if !current.is_empty() {
+                    result.push(std::mem::replace(&mut current, Port::new()));
+                }
+                let separated = content.contains(',') || content.contains(char::is_whitespace);
+                let labels = if separated {
+                    content.split(|x: char| x == ',' || x.is_whitespace()).filter(|l| !l.is_empty()).map(|l| l.to_string()).collect()
+                } else {
+                    content.chars().map(|l| l.to_string()).collect()
+                };
+                current.set = Some(labels);
+                note(notes, "braces are read as a set of labels");
+            }
+            '|' | ',' => {
+                if !current.is_empty() {
+                    result.push(std::mem::replace(&mut current, Port::new()));
+                }
+                note(notes, "'|' and ',' are read as port separators");
+            }
+            '^' => {
+                if chars.peek() == Some(&'{') {
+                    chars.next();
+                    for x in chars.by_ref() {
+                        if x == '}' {
+                            break;
+                        }
+                        current.exponent.push(x);
+                    }
+                    note(notes, "^{n} is read as an exponent");
+                } else {
+                    while let Some(&x) = chars.peek().filter(|x| x.is_ascii_digit()) {
+                        current.exponent.push(x);
+                        chars.next();
+                    }
+                }
+            }
+            '*' => current.exponent = "*".into(),
+            c if c.is_whitespace() => {
+                if !current.is_empty() {
+                    result.push(std::mem::replace(&mut current, Port::new()));
+                }
+            }
+            c => {
+                if let Some(d) = superscript_digit(c) {
+                    current.exponent.push(d);
+                    note(notes, "superscript numbers are read as exponents");
+                } else if current.set.is_some() || !current.exponent.is_empty() {
+                    result.push(std::mem::replace(&mut current, Port::new()));
+                    current.bare.push(c);
+                } else {
+                    current.bare.push(c);
+                }
+            }
+        }
+    }
+    if !current.is_empty() {
+        result.push(current);
+    }
+    Ok(result)
+}
+
+/// Rewrites a problem written with the notations that appear in papers in the internal format:
+/// ports separated by `|` or `,`, sets of labels written as `{A,B}` or `[AB]`, and exponents written as superscripts or as `^{n}`.
+/// If braces or separators are used, labels written without braces are single labels even if their name is longer than one character.
+/// Returns the normalized text and, for each line that has been changed, what has been done.
+pub fn normalize_paper_notation(text: &str) -> Result<(String, Vec<Interpretation>), &'static str> {
+    let paper_style = text.contains(['{', '[', '|', ',']);
+    let mut lines = vec![];
+    let mut interpretations = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let mut notes = vec![];
+        let mut normalized = vec![];
+        for port in ports(line.trim(), &mut notes)? {
+            let mut s = match port.set {
+                Some(labels) => labels.iter().map(|l| label_text(l)).collect(),
+                None if paper_style && port.bare.chars().count() > 1 && !port.bare.contains('(') => {
+                    note(&mut notes, "labels with more than one character are read as a single label");
+                    label_text(&port.bare)
+                }
+                None => port.bare,
+            };
+            if s.is_empty() {
+                return Err("A port has no labels");
+            }
+            match port.exponent.as_str() {
+                "" => {}
+                "*" => s.push('*'),
+                e => {
+                    e.parse::<usize>().map_err(|_| "Invalid exponent")?;
+                    s.push('^');
+                    s.push_str(e);
+                }
+            }
+            normalized.push(s);
+        }
+        let normalized = normalized.join(" ");
+        if !notes.is_empty() || normalized != line.trim() {
+            interpretations.push(Interpretation {
+                line_number: i + 1,
+                original: line.to_string(),
+                normalized: normalized.clone(),
+                notes,
+            });
+        }
+        lines.push(normalized);
+    }
+    Ok((lines.join("\n"), interpretations))
+}
+
+impl Problem {
+    /// Same as `from_string`, but accepts the notations described in `normalize_paper_notation`.
+    pub fn from_paper_notation(text: &str) -> Result<(Problem, Vec<Interpretation>), &'static str> {
+        let (normalized, interpretations) = normalize_paper_notation(text)?;
+        Ok((Problem::from_string(normalized)?, interpretations))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::problem::Problem;
+
+    use super::normalize_paper_notation;
+
+    #[test]
+    fn paper_notation() {
+        let (text, interpretations) = normalize_paper_notation("M | {P,O}²\nA B³\n\nM | X1\n[AB]^{2}").unwrap();
+        assert_eq!(text, "M PO^2\nA B^3\n\nM (X1)\nAB^2");
+        assert_eq!(interpretations.len(), 4);
+        assert_eq!(interpretations[1].line_number, 2);
+        assert_eq!(interpretations[1].notes, vec!["superscript numbers are read as exponents"]);
+
+        let (p, _) = Problem::from_paper_notation("A, B, B\n\n{A, B}, B").unwrap();
+        assert_eq!(format!("{}", p), "A B^2\n\nAB B\n");
+        assert!(normalize_paper_notation("{A B\n\nA").is_err());
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constraint::Constraint,
+    group::{Group, GroupType, Label},
+    line::{Degree, Line},
+    part::Part,
+    problem::Problem,
+};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum GraphFormat {
+    Dot,
+    GraphML,
+    EdgeList,
+}
+
+impl Problem {
+    /// Returns the passive constraint seen as a graph: there is an edge {a,b} (possibly a self loop) if the configuration `a b` is allowed.
+    pub fn passive_graph(&self) -> Result<Vec<(Label, Label)>, &'static str> {
+        if self.passive.degree != Degree::Finite(2) {
+            return Err("The passive graph is defined only if the passive degree is 2");
+        }
+        let mut edges = HashSet::new();
+        for line in &self.passive.lines {
+            let (g1, g2) = match line.parts.as_slice() {
+                [p] => (&p.group, &p.group),
+                [p1, p2] => (&p1.group, &p2.group),
+                _ => unreachable!(),
+            };
+            for &a in g1.iter() {
+                for &b in g2.iter() {
+                    edges.insert((a.min(b), a.max(b)));
+                }
+            }
+        }
+        Ok(edges.into_iter().sorted().collect())
+    }
+
+    pub fn passive_graph_to_string(&self, format: GraphFormat) -> Result<String, &'static str> {
+        let edges = self.passive_graph()?;
+        let mapping: HashMap<_, _> = self.mapping_label_text.iter().cloned().collect();
+        let labels = self.labels();
+        let s = match format {
+            GraphFormat::EdgeList => edges
+                .iter()
+                .map(|(a, b)| format!("{} {}\n", mapping[a], mapping[b]))
+                .join(""),
+            GraphFormat::Dot => {
+                let mut s = String::from("graph passive {\n");
+                for l in &labels {
+                    s.push_str(&format!("    \"{}\";\n", mapping[l]));
+                }
+                for (a, b) in &edges {
+                    s.push_str(&format!("    \"{}\" -- \"{}\";\n", mapping[a], mapping[b]));
+                }
+                s.push_str("}\n");
+                s
+            }
+            GraphFormat::GraphML => {
+                let mut s = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+                s.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+                s.push_str("  <graph id=\"passive\" edgedefault=\"undirected\">\n");
+                for l in &labels {
+                    s.push_str(&format!("    <node id=\"{}\"/>\n", xml_escape(&mapping[l])));
+                }
+                for (a, b) in &edges {
+                    s.push_str(&format!(
+                        "    <edge source=\"{}\" target=\"{}\"/>\n",
+                        xml_escape(&mapping[a]),
+                        xml_escape(&mapping[b])
+                    ));
+                }
+                s.push_str("  </graph>\n</graphml>\n");
+                s
+            }
+        };
+        Ok(s)
+    }
+
+    /// Replaces the passive constraint with the one described by the given graph.
+    /// Nodes are identified by the text of the labels of the current problem.
+    pub fn set_passive_from_graph(&mut self, format: GraphFormat, text: &str) -> Result<(), &'static str> {
+        let mapping: HashMap<_, _> = self
+            .mapping_label_text
+            .iter()
+            .map(|(l, s)| (s.clone(), *l))
+            .collect();
+        let names = match format {
+            GraphFormat::EdgeList => parse_edge_list(text)?,
+            GraphFormat::Dot => parse_dot(text, "--")?,
+            GraphFormat::GraphML => parse_graphml(text)?,
+        };
+        let mut lines = vec![];
+        for (a, b) in names {
+            let a = *mapping.get(&a).ok_or("The graph contains a node that is not a label of the problem")?;
+            let b = *mapping.get(&b).ok_or("The graph contains a node that is not a label of the problem")?;
+            let mut line = Line {
+                parts: vec![
+                    Part { gtype: GroupType::ONE, group: Group(vec![a]) },
+                    Part { gtype: GroupType::ONE, group: Group(vec![b]) },
+                ],
+            };
+            line.normalize();
+            lines.push(line);
+        }
+        if lines.is_empty() {
+            return Err("The graph contains no edges");
+        }
+        let mut passive = Constraint {
+            lines,
+            is_maximized: false,
+            degree: Degree::Finite(2),
+        };
+        passive.discard_non_maximal_lines();
+
+        self.passive = passive;
+        self.discard_computed_stuff();
+        Ok(())
+    }
+}
+
+pub(crate) fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn xml_unescape(s: &str) -> String {
+    s.replace("&quot;", "\"").replace("&gt;", ">").replace("&lt;", "<").replace("&amp;", "&")
+}
+
+pub(crate) fn parse_edge_list(text: &str) -> Result<Vec<(String, String)>, &'static str> {
+    let mut edges = vec![];
+    for line in text.lines().map(|l| l.trim()).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+        let v: Vec<_> = line.split_whitespace().collect();
+        if v.len() != 2 {
+            return Err("Each line of an edge list must contain exactly two nodes");
+        }
+        edges.push((v[0].to_string(), v[1].to_string()));
+    }
+    Ok(edges)
+}
+
+/// Parses the edges of a DOT graph, written with `edgeop`, that is `--` for undirected graphs and `->` for directed ones.
+pub(crate) fn parse_dot(text: &str, edgeop: &str) -> Result<Vec<(String, String)>, &'static str> {
+    let start = text.find('{').ok_or("Invalid DOT graph")?;
+    let end = text.rfind('}').ok_or("Invalid DOT graph")?;
+    let mut edges = vec![];
+    for statement in text[start + 1..end].split([';', '\n']) {
+        if !statement.contains(edgeop) {
+            continue;
+        }
+        let nodes: Vec<_> = statement
+            .split(edgeop)
+            .map(|x| x.trim().trim_matches('"').to_string())
+            .collect();
+        if nodes.iter().any(|x| x.is_empty()) {
+            return Err("Invalid DOT graph");
+        }
+        for w in nodes.windows(2) {
+            edges.push((w[0].clone(), w[1].clone()));
+        }
+    }
+    Ok(edges)
+}
+
+pub(crate) fn parse_graphml(text: &str) -> Result<Vec<(String, String)>, &'static str> {
+    fn attribute(tag: &str, name: &str) -> Option<String> {
+        let key = format!("{}=\"", name);
+        let start = tag.find(&key)? + key.len();
+        let len = tag[start..].find('"')?;
+        Some(xml_unescape(&tag[start..start + len]))
+    }
+    let mut edges = vec![];
+    for tag in text.split('<').skip(1).filter(|t| t.starts_with("edge ") || t.starts_with("edge\t")) {
+        let source = attribute(tag, "source").ok_or("Invalid GraphML edge")?;
+        let target = attribute(tag, "target").ok_or("Invalid GraphML edge")?;
+        edges.push((source, target));
+    }
+    Ok(edges)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::problem::Problem;
+
+    use super::GraphFormat;
+
+    #[test]
+    fn passive_graph() {
+        let p = Problem::from_string("A A\nB B\nC C\n\nA BC\nB C").unwrap();
+        assert_eq!(p.passive_graph().unwrap(), vec![(0, 1), (0, 2), (1, 2)]);
+        assert_eq!(p.passive_graph_to_string(GraphFormat::EdgeList).unwrap(), "A B\nA C\nB C\n");
+
+        for format in [GraphFormat::EdgeList, GraphFormat::Dot, GraphFormat::GraphML] {
+            let text = p.passive_graph_to_string(format).unwrap();
+            let mut q = Problem::from_string("A A\nB B\nC C\n\nA A").unwrap();
+            q.set_passive_from_graph(format, &text).unwrap();
+            assert_eq!(q.passive_graph().unwrap(), p.passive_graph().unwrap());
+        }
+
+        let mut q = Problem::from_string("A A\nB B\nC C\n\nA A").unwrap();
+        assert!(q.set_passive_from_graph(GraphFormat::EdgeList, "A D").is_err());
+    }
+}
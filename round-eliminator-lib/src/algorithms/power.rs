@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use crate::{constraint::Constraint, group::Label, problem::Problem};
+
+/// The distinct orderings of a sequence, obtained by repeatedly taking the next permutation in lexicographic order.
+fn distinct_permutations<T: Ord + Clone>(v: &[T]) -> Vec<Vec<T>> {
+    let mut current: Vec<T> = v.iter().cloned().sorted().collect();
+    let mut result = vec![current.clone()];
+    loop {
+        let Some(i) = (1..current.len()).rev().find(|&i| current[i - 1] < current[i]) else {
+            return result;
+        };
+        let j = (i..current.len()).rev().find(|&j| current[i - 1] < current[j]).unwrap();
+        current.swap(i - 1, j);
+        current[i..].reverse();
+        result.push(current.clone());
+    }
+}
+
+/// The powers with more lines or more labels than these are not computed.
+const MAX_LINES: usize = 100_000;
+const MAX_LABELS: usize = 10_000;
+
+/// The name of a label inside the name of a tuple. Parenthesized names are written in braces, and the characters that
+/// separate the labels of a tuple are escaped with a backslash, so that different tuples always get different names.
+fn component_name(name: &str) -> String {
+    let escape = |s: &str| s.chars().flat_map(|c| if matches!(c, '\\' | ',' | '{' | '}') { vec!['\\', c] } else { vec![c] }).collect::<String>();
+    match name.strip_prefix('(').and_then(|inner| inner.strip_suffix(')')) {
+        Some(inner) => format!("{{{}}}", escape(inner)),
+        None => escape(name),
+    }
+}
+
+/// The name of a tuple of labels, such as (A,B) for A and B, or ({A,B},C) for (A,B) and C. The name of a 1-tuple is the name of its label.
+fn tuple_name(tuple: &[&Label], name: &HashMap<Label, String>) -> String {
+    if tuple.len() == 1 {
+        return name[tuple[0]].clone();
+    }
+    format!("({})", tuple.iter().map(|l| component_name(&name[l])).join(","))
+}
+
+/// The lines of the side of the power, as text. For each choice of one line for each copy, and for each way of matching the ports
+/// of the lines of the other copies with the ports of the first one, port j gets the tuples whose labels come from the groups at port j.
+fn power_lines(c: &Constraint, k: usize, name: &HashMap<Label, String>) -> Result<Vec<String>, &'static str> {
+    if c.lines.iter().any(|line| line.has_star()) {
+        return Err("Lines containing a star are not supported");
+    }
+    let ports: Vec<Vec<Vec<Label>>> = c
+        .lines
+        .iter()
+        .map(|line| {
+            line.parts
+                .iter()
+                .flat_map(|part| std::iter::repeat_n(part.group.0.clone(), part.gtype.value()))
+                .collect()
+        })
+        .collect();
+    let orderings: Vec<Vec<Vec<Vec<Label>>>> = ports.iter().map(|groups| distinct_permutations(groups)).collect();
+    let per_copy: usize = orderings.iter().map(|o| o.len()).sum();
+    if (1..k).try_fold(ports.len(), |n, _| n.checked_mul(per_copy).filter(|&n| n <= MAX_LINES)).is_none() {
+        return Err("The power has too many lines");
+    }
+
+    let mut lines = vec![];
+    for first in 0..ports.len() {
+        let others = (1..k).map(|_| (0..ports.len()).flat_map(|i| orderings[i].iter())).multi_cartesian_product();
+        let others: Box<dyn Iterator<Item = Vec<&Vec<Vec<Label>>>>> = if k == 1 { Box::new(std::iter::once(vec![])) } else { Box::new(others) };
+        for others in others {
+            let line = (0..ports[first].len())
+                .map(|j| {
+                    std::iter::once(&ports[first][j])
+                        .chain(others.iter().map(|groups| &groups[j]))
+                        .map(|group| group.iter())
+                        .multi_cartesian_product()
+                        .map(|tuple| tuple_name(&tuple, name))
+                        .join("")
+                })
+                .join(" ");
+            lines.push(line);
+        }
+    }
+    Ok(lines)
+}
+
+impl Problem {
+    /// The problem of solving k independent instances of this problem at the same time. The labels are the k-tuples of labels,
+    /// where the label (A,B) stands for A in the first instance and B in the second one, and a configuration is allowed if
+    /// the labels of each instance form an allowed configuration. Only the tuples that appear in some line are kept,
+    /// and the lines that are not maximal are discarded. The size of the result grows exponentially with k, so it fails if there
+    /// could be more than 10000 labels or if there would be more than 100000 lines before discarding the non maximal ones.
+    pub fn power(&self, k: usize) -> Result<Problem, &'static str> {
+        if k == 0 {
+            return Err("The number of copies must be at least 1");
+        }
+        let labels = self.labels().len();
+        if u32::try_from(k).ok().and_then(|k| labels.checked_pow(k)).is_none_or(|n| n > MAX_LABELS) {
+            return Err("The power has too many labels");
+        }
+        let name: HashMap<Label, String> = self.mapping_label_text.iter().cloned().collect();
+        let active = power_lines(&self.active, k, &name)?;
+        let passive = power_lines(&self.passive, k, &name)?;
+        Problem::from_string_active_passive(active.join("\n"), passive.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    use super::distinct_permutations;
+
+    #[test]
+    fn power() {
+        assert_eq!(distinct_permutations(&[1, 0, 1]), vec![vec![0, 1, 1], vec![1, 0, 1], vec![1, 1, 0]]);
+
+        let p = Problem::from_string("A A\nB B\n\nA B").unwrap();
+        assert_eq!(p.power(1).unwrap().to_canonical_text(), p.to_canonical_text());
+
+        // two independent 2-colorings, where the labels are pairs of colors
+        let q = p.power(2).unwrap();
+        assert_eq!(q.labels().len(), 4);
+        assert_eq!(q.active.lines.len(), 4);
+        let mut names: Vec<_> = q.mapping_label_text.iter().map(|(_, t)| t.clone()).collect();
+        names.sort();
+        assert_eq!(names, vec!["(A,A)", "(A,B)", "(B,A)", "(B,B)"]);
+        assert_eq!(q.passive.lines.len(), 2);
+
+        let eh = &mut EventHandler::null();
+        let mut mm = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap().power(2).unwrap();
+        mm.compute_triviality(eh);
+        assert!(mm.trivial_sets.unwrap().is_empty());
+
+        assert!(p.power(0).is_err());
+        assert_eq!(p.power(20).unwrap_err(), "The power has too many labels");
+        assert_eq!(Problem::from_string("A B C D E F\n\nA B").unwrap().power(3).unwrap_err(), "The power has too many lines");
+
+        // the names of the tuples are distinct even when the names of the labels contain parentheses or commas
+        let r = Problem::from_string("x (x) , (a,b)\n\nx (x)\n, (a,b)").unwrap().power(2).unwrap();
+        let names: Vec<_> = r.mapping_label_text.iter().map(|(_, t)| t.clone()).collect();
+        assert_eq!(names.len(), 16);
+        assert!(names.contains(&"({x},x)".to_string()) && names.contains(&"(\\,,{a\\,b})".to_string()));
+        assert!(Problem::from_string("A A*\n\nA A").unwrap().power(2).is_err());
+    }
+}
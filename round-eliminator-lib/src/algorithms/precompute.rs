@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{group::Label, line::Degree, problem::Problem};
+
+use super::{event::EventHandler, limits::LimitGuard};
+
+/// Sizes of a problem, as shown next to it by the GUI.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ProblemStats {
+    pub labels: usize,
+    pub active_lines: usize,
+    pub passive_lines: usize,
+    /// The size of the largest antichain of the diagram.
+    pub diagram_width: usize,
+}
+
+/// What `precompute_all` computes, for the fields that are missing.
+#[derive(Default)]
+struct Missing {
+    diagram: Option<Vec<(Label, Label)>>,
+    trivial_sets: Option<Vec<Vec<Label>>>,
+    coloring_sets: Option<Option<Vec<Vec<Label>>>>,
+    orientation_trivial_sets: Option<Vec<(Vec<Label>, Vec<Label>)>>,
+    orientation_coloring_sets: Option<Vec<(Vec<Label>, Vec<Label>)>>,
+}
+
+impl Problem {
+    /// Computes the diagram, the triviality, the coloring solvability, and (if an orientation is given) the solvability given the orientation,
+    /// skipping what has already been computed, so calling this again is a no-op.
+    /// These computations are independent, so they run in parallel, and their progress events are all sent to `eh`.
+    /// The passive side is maximized first if its degree is not 2, since all of them would do it anyway.
+    pub fn precompute_all(&mut self, eh: &mut EventHandler) -> ProblemStats {
+        self.discard_stale_computed_stuff();
+        if self.passive.degree != Degree::Finite(2) && !self.passive.is_maximized {
+            self.passive.maximize(eh);
+            // maximizing gives an equivalent problem, so what has been computed is still valid
+            if self.computed_for.is_some() {
+                self.mark_computed();
+            }
+        }
+
+        let missing = self.compute_missing(eh);
+        if let Some(diagram) = missing.diagram {
+            self.diagram_indirect = Some(diagram);
+            self.compute_direct_diagram();
+        }
+        if let Some(trivial_sets) = missing.trivial_sets {
+            self.trivial_sets = Some(trivial_sets);
+        }
+        if let Some(coloring_sets) = missing.coloring_sets {
+            self.coloring_sets = coloring_sets;
+        }
+        if let Some(sets) = missing.orientation_trivial_sets {
+            self.orientation_trivial_sets = Some(sets);
+        }
+        if let Some(sets) = missing.orientation_coloring_sets {
+            self.orientation_coloring_sets = Some(sets);
+        }
+        self.mark_computed();
+
+        ProblemStats {
+            labels: self.labels().len(),
+            active_lines: self.active.lines.len(),
+            passive_lines: self.passive.lines.len(),
+            diagram_width: self.diagram_width(),
+        }
+    }
+
+    /// The diagram of the problem, computed without limits.
+    fn unlimited_diagram(&self, eh: &mut EventHandler) -> Vec<(Label, Label)> {
+        self.diagram_indirect_of(&LimitGuard::unlimited(), eh).expect("the diagram without limits cannot fail")
+    }
+
+    /// The outdegree of the given orientation, if the solvability given the orientation can be computed.
+    fn orientation_outdegree(&self) -> Option<usize> {
+        self.orientation_given.filter(|_| self.passive.degree == Degree::Finite(2))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn compute_missing(&self, eh: &mut EventHandler) -> Missing {
+        std::thread::scope(|s| {
+            let (tx, rx) = std::sync::mpsc::channel();
+            let outdegree = self.orientation_outdegree();
+            let diagram = spawn_with_events(s, &tx, self.diagram_indirect.is_none(), |eh| self.unlimited_diagram(eh));
+            let trivial_sets = spawn_with_events(s, &tx, self.trivial_sets.is_none(), |eh| self.trivial_sets_of(eh));
+            let coloring_sets = spawn_with_events(s, &tx, self.coloring_sets.is_none(), |eh| self.coloring_sets_of(eh));
+            let orientation_trivial_sets = spawn_with_events(s, &tx, outdegree.is_some() && self.orientation_trivial_sets.is_none(), move |eh| {
+                self.orientation_trivial_sets_of(outdegree.unwrap(), eh)
+            });
+            let orientation_coloring_sets = spawn_with_events(s, &tx, outdegree.is_some() && self.orientation_coloring_sets.is_none(), move |eh| {
+                self.orientation_coloring_sets_of(outdegree.unwrap(), eh)
+            });
+            // the loop ends when all the computations are done, since each of them owns a sender
+            drop(tx);
+            for (s, x, t) in rx {
+                eh.notify(s, x, t);
+            }
+            Missing {
+                diagram: diagram.map(|h| h.join().unwrap()),
+                trivial_sets: trivial_sets.map(|h| h.join().unwrap()),
+                coloring_sets: coloring_sets.map(|h| h.join().unwrap()),
+                orientation_trivial_sets: orientation_trivial_sets.map(|h| h.join().unwrap()),
+                orientation_coloring_sets: orientation_coloring_sets.map(|h| h.join().unwrap()),
+            }
+        })
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn compute_missing(&self, eh: &mut EventHandler) -> Missing {
+        let outdegree = self.orientation_outdegree();
+        Missing {
+            diagram: self.diagram_indirect.is_none().then(|| self.unlimited_diagram(eh)),
+            trivial_sets: self.trivial_sets.is_none().then(|| self.trivial_sets_of(eh)),
+            coloring_sets: self.coloring_sets.is_none().then(|| self.coloring_sets_of(eh)),
+            orientation_trivial_sets: outdegree.filter(|_| self.orientation_trivial_sets.is_none()).map(|o| self.orientation_trivial_sets_of(o, eh)),
+            orientation_coloring_sets: outdegree.filter(|_| self.orientation_coloring_sets.is_none()).map(|o| self.orientation_coloring_sets_of(o, eh)),
+        }
+    }
+}
+
+/// Runs `f` on a new thread if `needed`, with an event handler that sends the progress events to `tx`.
+#[cfg(not(target_arch = "wasm32"))]
+fn spawn_with_events<'scope, T, F>(
+    s: &'scope std::thread::Scope<'scope, '_>,
+    tx: &std::sync::mpsc::Sender<(String, usize, usize)>,
+    needed: bool,
+    f: F,
+) -> Option<std::thread::ScopedJoinHandle<'scope, T>>
+where
+    T: Send + 'scope,
+    F: FnOnce(&mut EventHandler) -> T + Send + 'scope,
+{
+    needed.then(|| {
+        let tx = tx.clone();
+        s.spawn(move || f(&mut EventHandler::with(move |e| tx.send(e).unwrap())))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, group::Label, problem::Problem};
+
+    #[test]
+    fn precompute_all() {
+        let mut events = vec![];
+        let mut p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        let stats = p.precompute_all(&mut EventHandler::with(|(s, _, _)| events.push(s)));
+        assert_eq!((stats.labels, stats.active_lines, stats.passive_lines), (3, 2, 2));
+        for kind in ["diagram", "triviality", "coloring graph"] {
+            assert!(events.iter().any(|s| s == kind));
+        }
+
+        let eh = &mut EventHandler::null();
+
+        let mut q = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        q.compute_diagram(eh);
+        q.compute_triviality(eh);
+        q.compute_coloring_solvability(eh);
+        assert_eq!(p.diagram_indirect, q.diagram_indirect);
+        assert_eq!(p.trivial_sets, q.trivial_sets);
+        assert_eq!(p.coloring_sets, q.coloring_sets);
+        assert_eq!(stats.diagram_width, q.diagram_width());
+
+        // everything has been computed, so this must not recompute (and panic)
+        assert_eq!(p.precompute_all(eh), stats);
+
+        let mut p = Problem::from_string("A B B\nC C C\n\nA B\nB B\nC C").unwrap();
+        p.orientation_given = Some(1);
+        p.precompute_all(eh);
+        let mut q = Problem::from_string("A B B\nC C C\n\nA B\nB B\nC C").unwrap();
+        q.compute_triviality_given_orientation(1, eh);
+        q.compute_coloring_solvability_given_orientation(1, eh);
+        // the order of the sets depends on the iteration order of hash maps
+        let sorted = |sets: &Option<Vec<(Vec<Label>, Vec<Label>)>>| sets.clone().map(|mut sets| {
+            sets.sort();
+            sets
+        });
+        assert_eq!(sorted(&p.orientation_trivial_sets), sorted(&q.orientation_trivial_sets));
+        assert_eq!(p.orientation_coloring_sets, q.orientation_coloring_sets);
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+
+use crate::{constraint::Constraint, group::Label, line::Line, part::Part, problem::Problem};
+
+impl Problem {
+    /// Priorities are compared lexicographically, so a priority like `[group, rank]` can be used to
+    /// keep related labels together. Labels without a priority are shown after all the others.
+    pub fn set_label_priorities(&mut self, v: &[(Label, Vec<usize>)]) -> Result<(), &'static str> {
+        let labels: HashSet<Label> = self.labels().into_iter().collect();
+        if v.iter().any(|(l, _)| !labels.contains(l)) {
+            return Err("Priorities given for labels that are not in the problem");
+        }
+        if v.iter().map(|(l, _)| l).unique().count() != v.len() {
+            return Err("Some label has more than one priority");
+        }
+        self.label_priorities = if v.is_empty() { None } else { Some(v.to_vec()) };
+        Ok(())
+    }
+
+    /// Returns, for each label, its position in the display order, or None if no priorities have been set.
+    pub fn label_display_order(&self) -> Option<HashMap<Label, usize>> {
+        let priorities: HashMap<_, _> = self.label_priorities.as_ref()?.iter().cloned().collect();
+        let order = self
+            .labels()
+            .into_iter()
+            .sorted_by_key(|l| (!priorities.contains_key(l), priorities.get(l).cloned(), *l))
+            .enumerate()
+            .map(|(i, l)| (l, i))
+            .collect();
+        Some(order)
+    }
+}
+
+impl Line {
+    pub fn to_string_ordered(&self, mapping: &HashMap<Label, String>, order: &HashMap<Label, usize>) -> String {
+        self.parts
+            .iter()
+            .sorted_by_cached_key(|part| part.group.iter().map(|l| order[l]).sorted().collect::<Vec<_>>())
+            .map(|part| part.to_string_ordered(mapping, order))
+            .join(" ")
+    }
+}
+
+impl Part {
+    pub fn to_string_ordered(&self, mapping: &HashMap<Label, String>, order: &HashMap<Label, usize>) -> String {
+        let mut s: String = self.group.iter().sorted_by_key(|l| order[l]).map(|l| mapping[l].as_str()).collect();
+        if s.is_empty() {
+            s.push('∅');
+        }
+        s.push_str(&self.gtype.to_string());
+        s
+    }
+}
+
+impl Constraint {
+    pub fn lines_ordered(&self, order: &HashMap<Label, usize>) -> Vec<&Line> {
+        self.lines
+            .iter()
+            .sorted_by(|a, b| a.ports_cmp(b, |g| g.iter().map(|l| order[l]).sorted().collect::<Vec<_>>()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::problem::Problem;
+
+    #[test]
+    fn priorities() {
+        let mut p = Problem::from_string("A AB AB\nC C C\n\nB AB\nC AC").unwrap();
+        assert_eq!(format!("{}", p), "A AB^2\nC^3\n\nAB B\nAC C\n");
+
+        p.set_label_priorities(&[(2, vec![0]), (1, vec![1, 0]), (0, vec![1, 1])]).unwrap();
+        assert_eq!(format!("{}", p), "C^3\nBA^2 A\n\nC CA\nB BA\n");
+
+        assert!(p.set_label_priorities(&[(5, vec![0])]).is_err());
+        assert!(p.set_label_priorities(&[(0, vec![0]), (0, vec![1])]).is_err());
+    }
+}
@@ -1,7 +1,11 @@
+use std::collections::HashSet;
+
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    group::{Group, GroupType, Exponent},
+    constraint::Constraint,
+    group::{Group, GroupType, Exponent, Label},
     line::{Degree, Line},
     part::Part,
     problem::Problem,
@@ -9,46 +13,196 @@ use crate::{
 
 use super::event::EventHandler;
 
-impl Problem {
-    pub fn compute_triviality(&mut self, eh: &mut EventHandler) {
-        if self.trivial_sets.is_some() {
-            panic!("triviality has been computed already");
-        }
+/// The answer of a triviality check that may have been stopped before examining all the candidates.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum TrivialityAnswer {
+    /// Some examined set of labels gives a zero round algorithm.
+    Trivial,
+    /// All the candidates have been examined, and none of them gives a zero round algorithm.
+    NotTrivial,
+    /// None of the `examined` candidates out of `total` gives a zero round algorithm, and the other ones have not been examined yet.
+    Unknown { examined: usize, total: usize },
+}
+
+/// The largest number of parts of a line of the passive side with a star for which `trivial_supersets` enumerates the subsets of its parts.
+const MAX_PARTS_FAST_PATH: usize = 12;
 
-        if self.passive.degree != Degree::Finite(2) {
-            self.passive.maximize(eh);
+/// The sets of labels that contain all the trivial sets, one for each line of the maximized passive side and each set of its parts
+/// that can cover the `degree` ports. A set S, repeated on all the `degree` ports, is included in a line if and only if the ports
+/// can be matched with the parts whose groups contain S. Without a star, the exponents of a line sum to `degree`, so all its parts are needed,
+/// and S is trivial if and only if it is a subset of the intersection of the groups of all the parts of some line.
+/// A line with a star is covered by any set of parts that contains a star, or whose exponents sum to at least `degree`, so only then its subsets are enumerated.
+/// Returns None if some line with a star has too many parts.
+fn trivial_supersets(passive: &Constraint, degree: usize) -> Option<Vec<Group>> {
+    let mut result = vec![];
+    let push = |result: &mut Vec<Group>, group: Option<Group>| {
+        if let Some(group) = group.filter(|group| !group.is_empty()) {
+            result.push(group);
         }
+    };
+    for line in &passive.lines {
+        let parts = &line.parts;
+        if !parts.iter().any(|part| part.gtype == GroupType::Star) {
+            let ports: usize = parts.iter().map(|part| part.gtype.value()).sum();
+            if ports >= degree {
+                push(&mut result, parts.iter().map(|part| part.group.clone()).reduce(|g1, g2| g1.intersection(&g2)));
+            }
+            continue;
+        }
+        if parts.len() > MAX_PARTS_FAST_PATH {
+            return None;
+        }
+        for mask in 1usize..(1 << parts.len()) {
+            let chosen = (0..parts.len()).filter(|i| mask & (1 << i) != 0);
+            let ports: usize = chosen
+                .clone()
+                .map(|i| match parts[i].gtype {
+                    GroupType::Star => degree,
+                    GroupType::Many(x) => x as usize,
+                })
+                .sum();
+            if ports < degree {
+                continue;
+            }
+            push(&mut result, chosen.map(|i| parts[i].group.clone()).reduce(|g1, g2| g1.intersection(&g2)));
+        }
+    }
+    Some(result)
+}
+
+/// Whether the problem is certainly not zero round solvable, because no line of the active side can be labeled using only the labels
+/// of one of the `trivial_supersets`, which happens if some part of the line has no label in it. This avoids enumerating the
+/// choices of the active side, whose number grows exponentially with the degree, for the problems whose passive side
+/// is a matching or orientation-like constraint. The passive side must be maximized.
+fn certainly_not_trivial(p: &Problem, degree: usize) -> bool {
+    let Some(supersets) = trivial_supersets(&p.passive, degree) else {
+        return false;
+    };
+    p.active.lines.iter().all(|line| {
+        supersets.iter().all(|set| line.parts.iter().any(|part| part.group.intersection(set).is_empty()))
+    })
+}
+
+/// A triviality check that can be stopped after examining some of the candidate sets of labels, that are the minimal sets of labels
+/// of the configurations of the active side, and resumed later.
+pub struct TrivialityCheck {
+    candidates: Vec<HashSet<Label>>,
+    passive_degree: GroupType,
+    examined: usize,
+    trivial_sets: Vec<Vec<Label>>,
+}
 
-        if self.passive.lines.is_empty() {
-            self.trivial_sets = Some(vec![]);
-            return;
+impl TrivialityCheck {
+    /// Prepares the check, maximizing the passive side of the problem if its degree is not 2.
+    /// If the maximized passive side shows that the problem is not trivial, see `certainly_not_trivial`, there are no candidates to examine.
+    pub fn new(p: &mut Problem, eh: &mut EventHandler) -> Self {
+        if p.passive.degree != Degree::Finite(2) {
+            p.passive.maximize(eh);
         }
+        Self::of_maximized(p)
+    }
 
-        let passive_degree = match self.passive.lines[0].degree() {
+    /// Same as `new`, for a problem whose passive side has already been maximized if its degree is not 2.
+    pub(crate) fn of_maximized(p: &Problem) -> Self {
+        let Some(first) = p.passive.lines.first() else {
+            return TrivialityCheck { candidates: vec![], passive_degree: GroupType::ONE, examined: 0, trivial_sets: vec![] };
+        };
+        let passive_degree = match first.degree() {
             Degree::Finite(x) => GroupType::Many(x as Exponent),
             Degree::Star => GroupType::Star,
         };
+        if let GroupType::Many(degree) = passive_degree {
+            if p.passive.is_maximized && certainly_not_trivial(p, degree as usize) {
+                return TrivialityCheck { candidates: vec![], passive_degree, examined: 0, trivial_sets: vec![] };
+            }
+        }
 
-        let active_sets = self.active.minimal_sets_of_all_choices();
-
-        let mut trivial_sets = vec![];
-        let num_active_sets = active_sets.len();
+        TrivialityCheck {
+            candidates: p.active.minimal_sets_of_all_choices(),
+            passive_degree,
+            examined: 0,
+            trivial_sets: vec![],
+        }
+    }
 
-        for (i, set) in active_sets.into_iter().enumerate() {
-            eh.notify("triviality", i, num_active_sets);
+    /// Examines at most `limit` more candidates, or all the remaining ones if `limit` is None.
+    /// The problem must be the one given to `new`.
+    pub fn run(&mut self, p: &Problem, limit: Option<usize>, eh: &mut EventHandler) -> TrivialityAnswer {
+        let total = self.candidates.len();
+        let end = limit.map_or(total, |limit| total.min(self.examined + limit));
+        for i in self.examined..end {
+            eh.notify("triviality", i, total);
 
-            let group = Group(set.into_iter().sorted().collect());
-            let part = Part {
-                gtype: passive_degree,
-                group,
-            };
-            let mut line = Line { parts: vec![part] };
-            if self.passive.includes(&line) {
-                trivial_sets.push(std::mem::take(&mut line.parts[0].group.0));
+            let group = Group(self.candidates[i].iter().cloned().sorted().collect());
+            let line = Line { parts: vec![Part { gtype: self.passive_degree, group }] };
+            if p.passive.includes(&line) {
+                self.trivial_sets.push(line.parts.into_iter().next().unwrap().group.0);
             }
         }
+        self.examined = end;
+        self.answer()
+    }
+
+    pub fn answer(&self) -> TrivialityAnswer {
+        if !self.trivial_sets.is_empty() {
+            TrivialityAnswer::Trivial
+        } else if self.examined == self.candidates.len() {
+            TrivialityAnswer::NotTrivial
+        } else {
+            TrivialityAnswer::Unknown { examined: self.examined, total: self.candidates.len() }
+        }
+    }
 
-        self.trivial_sets = Some(trivial_sets);
+    /// The fraction of the candidates that have been examined.
+    pub fn fraction_examined(&self) -> f64 {
+        if self.candidates.is_empty() {
+            1.0
+        } else {
+            self.examined as f64 / self.candidates.len() as f64
+        }
+    }
+
+    /// All the trivial sets, as in `Problem::trivial_sets`, if all the candidates have been examined.
+    pub fn trivial_sets(self) -> Option<Vec<Vec<Label>>> {
+        (self.examined == self.candidates.len()).then_some(self.trivial_sets)
+    }
+}
+
+impl Problem {
+    pub fn compute_triviality(&mut self, eh: &mut EventHandler) {
+        self.discard_stale_computed_stuff();
+        if self.trivial_sets.is_some() {
+            panic!("triviality has been computed already");
+        }
+
+        let mut check = TrivialityCheck::new(self, eh);
+        check.run(self, None, eh);
+        self.trivial_sets = check.trivial_sets();
+        self.mark_computed();
+    }
+
+    /// The trivial sets computed by `compute_triviality`, for a problem whose passive side has already been maximized if its degree is not 2.
+    pub(crate) fn trivial_sets_of(&self, eh: &mut EventHandler) -> Vec<Vec<Label>> {
+        let mut check = TrivialityCheck::of_maximized(self);
+        check.run(self, None, eh);
+        check.trivial_sets().unwrap()
+    }
+
+    /// Same as `compute_triviality`, but stops after examining `limit` candidate sets of labels.
+    /// The trivial sets are stored only if all the candidates have been examined.
+    pub fn compute_triviality_within(&mut self, limit: usize, eh: &mut EventHandler) -> TrivialityAnswer {
+        self.discard_stale_computed_stuff();
+        if self.trivial_sets.is_some() {
+            panic!("triviality has been computed already");
+        }
+
+        let mut check = TrivialityCheck::new(self, eh);
+        let answer = check.run(self, Some(limit), eh);
+        if let Some(trivial_sets) = check.trivial_sets() {
+            self.trivial_sets = Some(trivial_sets);
+            self.mark_computed();
+        }
+        answer
     }
 }
 
@@ -57,6 +211,8 @@ mod tests {
 
     use crate::{algorithms::event::EventHandler, problem::Problem};
 
+    use super::{trivial_supersets, TrivialityAnswer, TrivialityCheck};
+
     #[test]
     fn triviality() {
         let mut p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
@@ -75,4 +231,50 @@ mod tests {
         p.compute_triviality(&mut EventHandler::null());
         assert!(!p.trivial_sets.unwrap().is_empty());
     }
+
+    #[test]
+    fn anytime_triviality() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("A A\nB B\n\nB B").unwrap();
+        let mut check = TrivialityCheck::new(&mut p, eh);
+        assert_eq!(check.fraction_examined(), 0.0);
+        assert_eq!(check.run(&p, Some(1), eh), TrivialityAnswer::Unknown { examined: 1, total: 2 });
+        assert_eq!(check.fraction_examined(), 0.5);
+        assert_eq!(check.run(&p, Some(5), eh), TrivialityAnswer::Trivial);
+        assert_eq!(check.run(&p, None, eh), TrivialityAnswer::Trivial);
+        assert_eq!(check.trivial_sets(), Some(vec![vec![1]]));
+
+        let mut p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        assert!(matches!(p.compute_triviality_within(1, eh), TrivialityAnswer::Unknown { examined: 1, .. }));
+        assert!(p.trivial_sets.is_none());
+        assert_eq!(p.compute_triviality_within(100, eh), TrivialityAnswer::NotTrivial);
+        assert_eq!(p.trivial_sets, Some(vec![]));
+    }
+
+    #[test]
+    fn triviality_fast_path() {
+        let eh = &mut EventHandler::null();
+
+        // the passive side forbids three equal labels, so the choices of the active side are never examined
+        let mut p = Problem::from_string("A A\nB B\n\nA B B\nA A B").unwrap();
+        let check = TrivialityCheck::new(&mut p, eh);
+        assert_eq!(check.fraction_examined(), 1.0);
+        assert_eq!(check.answer(), TrivialityAnswer::NotTrivial);
+        assert!(trivial_supersets(&p.passive, 3).unwrap().is_empty());
+        // without a star, only the intersection of all the parts of a line is kept, whatever the number of parts
+        let many = Problem::from_string("N N\n\nAN BN CN DN EN FN GN HN IN JN KN LN MN").unwrap();
+        assert_eq!(many.passive.lines[0].parts.len(), 13);
+        assert_eq!(trivial_supersets(&many.passive, 13).unwrap().len(), 1);
+        p.compute_triviality(eh);
+        assert_eq!(p.trivial_sets, Some(vec![]));
+
+        // an orientation of 3-uniform hyperedges with both an outgoing and an incoming port
+        let mut p = Problem::from_string("O I I I\n\nO I I\nO O I").unwrap();
+        p.compute_triviality(eh);
+        assert_eq!(p.trivial_sets, Some(vec![]));
+
+        let mut p = Problem::from_string("A A\nB B\n\nA A A\nA B B").unwrap();
+        p.compute_triviality(eh);
+        assert_eq!(p.trivial_sets.unwrap().len(), 1);
+    }
 }
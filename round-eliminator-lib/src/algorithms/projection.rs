@@ -0,0 +1,122 @@
+use crate::{
+    constraint::Constraint,
+    group::{GroupType, Label},
+    line::{Degree, Line},
+    problem::Problem,
+};
+
+use super::event::EventHandler;
+
+impl Problem {
+    /// Removes one port from the active side, reducing its degree by one.
+    /// A configuration is allowed in the new active constraint if it can be extended, with some label, to a configuration of the old one.
+    pub fn project_active_port(&self) -> Result<Problem, &'static str> {
+        let active = self.active.existential_projection()?;
+        let mut p = self.clone();
+        p.discard_computed_stuff();
+        p.active = active;
+        Ok(p)
+    }
+
+    /// Removes one port from the passive side, reducing its degree by one.
+    /// A configuration is allowed in the new passive constraint if it can be extended, with any label, to a configuration of the old one.
+    pub fn project_passive_port(&self, eh: &mut EventHandler) -> Result<Problem, &'static str> {
+        let passive = self.passive.universal_projection(&self.labels(), eh)?;
+        let mut p = self.clone();
+        p.discard_computed_stuff();
+        p.passive = passive;
+        Ok(p)
+    }
+}
+
+impl Constraint {
+    fn projected_degree(&self) -> Result<Degree, &'static str> {
+        match self.degree {
+            Degree::Finite(d) if d >= 2 => Ok(Degree::Finite(d - 1)),
+            Degree::Finite(_) => Err("Cannot project a port of a constraint of degree less than 2"),
+            Degree::Star => Err("Cannot project a port of a constraint with arbitrary degree"),
+        }
+    }
+
+    pub fn existential_projection(&self) -> Result<Constraint, &'static str> {
+        let degree = self.projected_degree()?;
+        let mut c = Constraint {
+            lines: vec![],
+            is_maximized: false,
+            degree,
+        };
+        for line in &self.lines {
+            for i in 0..line.parts.len() {
+                c.add_line_and_discard_non_maximal(line.without_one_port(i));
+            }
+        }
+        Ok(c)
+    }
+
+    /// The result is maximized, since every maximal configuration of the projection,
+    /// extended with a group containing all labels, is included in a maximal configuration of the original constraint.
+    pub fn universal_projection(&self, labels: &[Label], eh: &mut EventHandler) -> Result<Constraint, &'static str> {
+        let degree = self.projected_degree()?;
+        let mut maximized = self.clone();
+        if !maximized.is_maximized {
+            maximized.maximize(eh);
+        }
+        let mut c = Constraint {
+            lines: vec![],
+            is_maximized: false,
+            degree,
+        };
+        for line in &maximized.lines {
+            for (i, part) in line.parts.iter().enumerate() {
+                if labels.iter().all(|l| part.group.contains(l)) {
+                    c.add_line_and_discard_non_maximal(line.without_one_port(i));
+                }
+            }
+        }
+        if c.lines.is_empty() {
+            return Err("No configuration can be extended with all labels");
+        }
+        c.is_maximized = true;
+        Ok(c)
+    }
+}
+
+impl Line {
+    fn without_one_port(&self, i: usize) -> Line {
+        let mut line = self.clone();
+        match line.parts[i].gtype {
+            GroupType::Many(1) => {
+                line.parts.remove(i);
+            }
+            GroupType::Many(n) => {
+                line.parts[i].gtype = GroupType::Many(n - 1);
+            }
+            GroupType::Star => {}
+        }
+        line.normalize();
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    #[test]
+    fn projection() {
+        let eh = &mut EventHandler::null();
+        let p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+
+        let active = p.project_active_port().unwrap();
+        assert_eq!(format!("{}", active), "M U\nU^2\nP^2\n\nM UP\nU^2\n");
+
+        assert!(p.project_passive_port(eh).is_err());
+
+        let p = Problem::from_string("A B\n\nA ABC\nB C").unwrap();
+        let passive = p.project_passive_port(eh).unwrap();
+        assert_eq!(format!("{}", passive), "A B\n\nA\n");
+        assert!(passive.passive.is_maximized);
+        assert!(passive.project_passive_port(eh).is_err());
+    }
+}
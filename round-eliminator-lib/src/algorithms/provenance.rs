@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    problem::Problem,
+    store::{problem_id, ProblemId},
+};
+
+/// An operation that produced a problem from another one.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Derivation {
+    /// The canonical hash of the problem the operation has been applied to, see `store::problem_id`.
+    pub parent: ProblemId,
+    pub operation: String,
+    /// The version of the library that performed the operation.
+    pub version: String,
+    /// Seconds since the Unix epoch, if the clock is available.
+    pub timestamp: Option<u64>,
+}
+
+const PREFIX: &str = "# derived from ";
+
+fn now() -> Option<u64> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()
+            .map(|d| d.as_secs())
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        None
+    }
+}
+
+impl Derivation {
+    fn to_text(&self) -> String {
+        let timestamp = self.timestamp.map_or("-".to_string(), |t| t.to_string());
+        format!("{}{} by {} version {} at {}", PREFIX, self.parent, self.operation, self.version, timestamp)
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let rest = line.strip_prefix(PREFIX)?;
+        let (parent, rest) = rest.split_once(" by ")?;
+        let (rest, timestamp) = rest.rsplit_once(" at ")?;
+        let (operation, version) = rest.rsplit_once(" version ")?;
+        Some(Derivation {
+            parent: parent.parse().ok()?,
+            operation: operation.to_string(),
+            version: version.to_string(),
+            timestamp: if timestamp == "-" { None } else { Some(timestamp.parse().ok()?) },
+        })
+    }
+}
+
+impl Problem {
+    /// The operations that produced this problem, starting from a problem that has been given as text.
+    pub fn provenance(&self) -> &[Derivation] {
+        self.provenance.as_deref().unwrap_or(&[])
+    }
+
+    /// Records that this problem has been obtained by applying `operation` to `parent`.
+    pub fn derived_from(&mut self, parent: &Problem, operation: &str) {
+        let mut provenance = parent.provenance().to_vec();
+        provenance.push(Derivation {
+            parent: problem_id(parent),
+            operation: operation.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            timestamp: now(),
+        });
+        self.provenance = Some(provenance);
+    }
+
+    /// The text of the problem followed by its provenance, as comments after the passive side, that `from_string` ignores.
+    pub fn to_string_with_provenance(&self) -> String {
+        let mut s = self.to_string();
+        if !self.provenance().is_empty() {
+            s.push('\n');
+            for derivation in self.provenance() {
+                s.push_str(&derivation.to_text());
+                s.push('\n');
+            }
+        }
+        s
+    }
+
+    /// Same as `from_string`, but also reads the provenance written by `to_string_with_provenance`.
+    pub fn from_string_with_provenance(text: &str) -> Result<Self, &'static str> {
+        let mut p = Problem::from_string(text)?;
+        let provenance: Vec<_> = text.lines().filter_map(Derivation::parse).collect();
+        if !provenance.is_empty() {
+            p.provenance = Some(provenance);
+        }
+        Ok(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, pipeline::Op, problem::Problem, store::problem_id};
+
+    #[test]
+    fn provenance() {
+        let eh = &mut EventHandler::null();
+        let p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        assert!(p.provenance().is_empty());
+        let q = p.apply_op(&Op::Speedup, eh).unwrap().apply_op(&Op::Cleanup, eh).unwrap();
+        let r = q.apply_op(&Op::MergeEquivalentLabels, eh).unwrap();
+        assert_eq!(r.provenance().len(), 2);
+        assert_eq!(r.provenance()[0].parent, problem_id(&p));
+        assert_eq!(r.provenance()[1].parent, problem_id(&q));
+        assert_eq!(r.provenance()[1].operation, "MergeEquivalentLabels");
+
+        // the operations keep the provenance of the problem they are applied to
+        assert_eq!(r.speedup(eh).relax_merge(0, 1).provenance(), r.provenance());
+
+        let text = r.to_string_with_provenance();
+        let loaded = Problem::from_string_with_provenance(&text).unwrap();
+        assert_eq!(loaded, Problem::from_string(r.to_string()).unwrap());
+        assert_eq!(loaded.provenance(), r.provenance());
+    }
+}
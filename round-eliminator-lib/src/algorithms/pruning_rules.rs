@@ -0,0 +1,280 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{group::Label, problem::Problem};
+
+use super::event::EventHandler;
+
+/// A number that describes a problem, bounded by `PruningRule::Exceeds`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Quantity {
+    Labels,
+    ActiveLines,
+    PassiveLines,
+    /// The largest number of arrows of the diagram that enter the same label.
+    InDegree,
+    /// The largest number of arrows of the diagram that leave the same label.
+    OutDegree,
+    /// The number of labels that the speedup of the problem may have, see `estimate_speedup_size`.
+    /// Since it is computed without performing the speedup, hopeless problems are discarded before paying for it.
+    SpeedupLabels,
+    /// The number of active lines of the speedup of the problem, as estimated by `estimate_speedup_size`.
+    SpeedupActiveLines,
+}
+
+/// A set of labels defined by their position in the diagram.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum LabelClass {
+    /// The labels from which only equivalent labels are reachable, that is, the strongest ones.
+    Maximal,
+    /// The labels that are reachable only from equivalent labels, that is, the weakest ones.
+    Minimal,
+}
+
+/// A rule that excludes part of the search of autoub and autolb, to encode knowledge about the problem at hand.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum PruningRule {
+    /// The problems for which the quantity is larger than the value are not explored, and neither is anything obtained from them.
+    Exceeds(Quantity, usize),
+    /// autolb does not try the merges that replace a label of the class with another label.
+    NeverMerge(LabelClass),
+    /// autoub does not try the hardenings that remove a label of the class.
+    AlwaysKeep(LabelClass),
+}
+
+/// The pruning rules of a search, written one per line, as in
+/// ```text
+/// # comments and empty lines are ignored
+/// labels > 12
+/// indegree > 5
+/// never merge maximal
+/// always keep minimal
+/// ```
+/// The quantities are `labels`, `active lines`, `passive lines`, `indegree`, `outdegree`, `speedup labels` and `speedup active lines`,
+/// and the classes of labels are `maximal` and `minimal`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PruningRules(pub Vec<PruningRule>);
+
+fn parse_class(s: &str) -> Result<LabelClass, &'static str> {
+    match s {
+        "maximal" => Ok(LabelClass::Maximal),
+        "minimal" => Ok(LabelClass::Minimal),
+        _ => Err("Unknown class of labels in a pruning rule, expected maximal or minimal"),
+    }
+}
+
+fn parse_rule(line: &str) -> Result<PruningRule, &'static str> {
+    if let Some(class) = line.strip_prefix("never merge ") {
+        return Ok(PruningRule::NeverMerge(parse_class(class.trim())?));
+    }
+    if let Some(class) = line.strip_prefix("always keep ") {
+        return Ok(PruningRule::AlwaysKeep(parse_class(class.trim())?));
+    }
+    let (quantity, value) = line.split_once('>').ok_or("A pruning rule must be of the form `quantity > value`, `never merge class` or `always keep class`")?;
+    let quantity = match quantity.split_whitespace().collect::<Vec<_>>().join(" ").as_str() {
+        "labels" => Quantity::Labels,
+        "active lines" => Quantity::ActiveLines,
+        "passive lines" => Quantity::PassiveLines,
+        "indegree" => Quantity::InDegree,
+        "outdegree" => Quantity::OutDegree,
+        "speedup labels" => Quantity::SpeedupLabels,
+        "speedup active lines" => Quantity::SpeedupActiveLines,
+        _ => return Err("Unknown quantity in a pruning rule"),
+    };
+    let value = value.trim().parse().map_err(|_| "The value of a pruning rule must be a non-negative integer")?;
+    Ok(PruningRule::Exceeds(quantity, value))
+}
+
+impl PruningRules {
+    pub fn parse(text: &str) -> Result<Self, &'static str> {
+        let rules = text
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(parse_rule)
+            .collect::<Result<_, _>>()?;
+        Ok(PruningRules(rules))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Evaluates the rules on the problem. The diagram, needed by most rules, is computed once on a copy of the problem
+    /// if the problem does not have it, and the result can be asked about all the candidate merges or hardenings of the problem.
+    pub fn applied_to(&self, p: &Problem, eh: &mut EventHandler) -> AppliedRules<'_> {
+        if self.is_empty() {
+            return AppliedRules { rules: self, discards: false, labels: vec![], reachable: HashMap::new() };
+        }
+        let p = with_diagram(p, eh);
+        let discards = self.0.iter().any(|rule| match *rule {
+            PruningRule::Exceeds(quantity, value) => quantity.of(&p, value) > value,
+            _ => false,
+        });
+        let reachable = if self.0.iter().any(|rule| !matches!(rule, PruningRule::Exceeds(..))) {
+            p.diagram_indirect_to_reachability_adj()
+        } else {
+            HashMap::new()
+        };
+        AppliedRules { rules: self, discards, labels: p.labels(), reachable }
+    }
+
+    /// Whether the problem is excluded by some `PruningRule::Exceeds`.
+    pub fn discards(&self, p: &Problem, eh: &mut EventHandler) -> bool {
+        self.applied_to(p, eh).discards()
+    }
+
+    /// Whether autolb can apply the given merges to the problem.
+    pub fn allows_merges(&self, p: &Problem, merges: &[(Label, Label)], eh: &mut EventHandler) -> bool {
+        self.applied_to(p, eh).allows_merges(merges)
+    }
+
+    /// Whether autoub can harden the problem by keeping only the given labels.
+    pub fn allows_hardening(&self, p: &Problem, kept: &[Label], eh: &mut EventHandler) -> bool {
+        self.applied_to(p, eh).allows_hardening(kept)
+    }
+}
+
+/// The outcome of the pruning rules on a problem, see `PruningRules::applied_to`.
+pub struct AppliedRules<'a> {
+    rules: &'a PruningRules,
+    discards: bool,
+    labels: Vec<Label>,
+    /// The labels reachable from each label in the diagram, only if some rule is about classes of labels.
+    reachable: HashMap<Label, HashSet<Label>>,
+}
+
+impl AppliedRules<'_> {
+    /// Whether the problem is excluded by some `PruningRule::Exceeds`.
+    pub fn discards(&self) -> bool {
+        self.discards
+    }
+
+    /// Whether autolb can apply the given merges to the problem.
+    pub fn allows_merges(&self, merges: &[(Label, Label)]) -> bool {
+        let classes = self.classes(|rule| if let PruningRule::NeverMerge(c) = rule { Some(*c) } else { None });
+        merges.iter().all(|&(from, _)| classes.iter().all(|class| !class.contains(&self.reachable, from)))
+    }
+
+    /// Whether autoub can harden the problem by keeping only the given labels.
+    pub fn allows_hardening(&self, kept: &[Label]) -> bool {
+        let classes = self.classes(|rule| if let PruningRule::AlwaysKeep(c) = rule { Some(*c) } else { None });
+        self.labels.iter().filter(|l| !kept.contains(l)).all(|&l| classes.iter().all(|class| !class.contains(&self.reachable, l)))
+    }
+
+    fn classes(&self, class_of: impl Fn(&PruningRule) -> Option<LabelClass>) -> Vec<LabelClass> {
+        self.rules.0.iter().filter_map(class_of).collect()
+    }
+}
+
+/// The problem with its diagram, direct and indirect, computed on a copy if the problem does not have both.
+fn with_diagram<'a>(p: &'a Problem, eh: &mut EventHandler) -> Cow<'a, Problem> {
+    if p.diagram_indirect.is_some() && p.diagram_direct.is_some() {
+        return Cow::Borrowed(p);
+    }
+    let mut p = p.clone();
+    if p.diagram_indirect.is_none() {
+        p.compute_partial_diagram(eh);
+    } else {
+        p.compute_direct_diagram();
+    }
+    Cow::Owned(p)
+}
+
+impl Quantity {
+    /// The quantity for the problem, which may be computed only up to `bound`, returning something larger if it exceeds it.
+    fn of(self, p: &Problem, bound: usize) -> usize {
+        let max_degree = |endpoint: fn(&(Label, Label)) -> Label| {
+            let mut degree: HashMap<Label, usize> = HashMap::new();
+            for edge in &p.diagram_direct.as_ref().unwrap().1 {
+                *degree.entry(endpoint(edge)).or_default() += 1;
+            }
+            degree.into_values().max().unwrap_or(0)
+        };
+        match self {
+            Quantity::Labels => p.labels().len(),
+            Quantity::ActiveLines => p.active.lines.len(),
+            Quantity::PassiveLines => p.passive.lines.len(),
+            Quantity::InDegree => max_degree(|&(_, b)| b),
+            Quantity::OutDegree => max_degree(|&(a, _)| a),
+            Quantity::SpeedupLabels => p.estimate_speedup_size(bound).max_labels.unwrap_or(bound.saturating_add(1)),
+            Quantity::SpeedupActiveLines => p.estimate_speedup_size(bound).active_lines.unwrap_or(bound.saturating_add(1)),
+        }
+    }
+}
+
+impl LabelClass {
+    /// Whether the label is in the class, given the labels reachable from each label in the diagram.
+    fn contains(self, reachable: &HashMap<Label, HashSet<Label>>, label: Label) -> bool {
+        match self {
+            LabelClass::Maximal => reachable[&label].iter().all(|other| reachable[other].contains(&label)),
+            LabelClass::Minimal => reachable.iter().filter(|(_, r)| r.contains(&label)).all(|(other, _)| reachable[&label].contains(other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        algorithms::{autoub::SearchStats, event::EventHandler, label_schedule::LabelSchedule},
+        problem::Problem,
+        serial::fix_problem,
+    };
+
+    use super::{LabelClass, PruningRule, PruningRules, Quantity};
+
+    #[test]
+    fn pruning_rules() {
+        let eh = &mut EventHandler::null();
+        let rules = PruningRules::parse("# bounds\nlabels > 2\n  active  lines > 9\n\nnever merge maximal\nalways keep minimal").unwrap();
+        assert_eq!(
+            rules.0,
+            vec![
+                PruningRule::Exceeds(Quantity::Labels, 2),
+                PruningRule::Exceeds(Quantity::ActiveLines, 9),
+                PruningRule::NeverMerge(LabelClass::Maximal),
+                PruningRule::AlwaysKeep(LabelClass::Minimal),
+            ]
+        );
+        assert!(PruningRules::parse("labels >= 2").is_err());
+        assert!(PruningRules::parse("never merge strongest").is_err());
+
+        // in the diagram of maximal independent set, P points to U, and M is not related to them
+        let mut p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        fix_problem(&mut p, true, false, eh);
+        assert!(rules.discards(&p, eh));
+        assert!(!PruningRules::parse("labels > 3\nindegree > 1").unwrap().discards(&p, eh));
+        assert!(PruningRules::parse("indegree > 0").unwrap().discards(&p, eh));
+        let mut indirect_only = p.clone();
+        indirect_only.diagram_direct = None;
+        assert!(PruningRules::parse("indegree > 0").unwrap().discards(&indirect_only, eh));
+        // P -> U, so the right-closed sets are M, U, MU, PU, MPU
+        assert!(PruningRules::parse("speedup labels > 4").unwrap().discards(&p, eh));
+        assert!(!PruningRules::parse("speedup labels > 5").unwrap().discards(&p, eh));
+        assert!(PruningRules::parse("speedup active lines > 0").unwrap().discards(&p, eh));
+
+        let (m, u, pp) = (0, 1, 2);
+        assert!(!rules.allows_merges(&p, &[(u, pp)], eh));
+        assert!(rules.allows_merges(&p, &[(pp, u)], eh));
+        assert!(!rules.allows_hardening(&p, &[m, u], eh));
+        assert!(rules.allows_hardening(&p, &[m, pp], eh));
+        assert!(PruningRules::default().allows_hardening(&p, &[], eh));
+
+        // the searches explore nothing beyond the problems excluded by the rules
+        let mut stats = SearchStats::default();
+        let mut found = false;
+        let schedule = LabelSchedule::uniform(5);
+        p.autoub_scheduled(&schedule, 3, 4, None, None, Default::default(), &PruningRules::parse("labels > 3").unwrap(), &mut stats, |_, _, _| found = true, eh);
+        assert!(!found);
+        assert!(stats.rule_pruned > 0);
+        let mut stats = SearchStats::default();
+        p.autoub(5, 3, 4, None, None, Default::default(), &mut stats, |_, _, _| found = true, eh);
+        assert!(found);
+        assert_eq!(stats.rule_pruned, 0);
+    }
+}
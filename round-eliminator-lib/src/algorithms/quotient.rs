@@ -0,0 +1,234 @@
+use crate::{
+    group::{GroupType, Label},
+    line::Degree,
+    problem::Problem,
+};
+
+use super::{event::EventHandler, max_clique::Graph};
+
+/// The search gives up after checking the triviality of this many images.
+const MAX_CHECKS: usize = 100_000;
+
+/// A relaxation of a problem obtained by mapping its labels onto a smaller set of labels.
+pub struct Quotient {
+    /// The labels mapped to the same label, each class is represented by its first label.
+    pub classes: Vec<Vec<Label>>,
+    pub problem: Problem,
+}
+
+/// The problem with the labels replaced by their indices in `Problem::labels`, when the passive side has degree 2 and the active side
+/// has no stars. Then the triviality of an image can be decided on the classes, without computing it, see `Search::image_is_trivial`.
+struct Compatibility {
+    /// Whether the passive side allows the two labels on the same edge.
+    allowed: Vec<Vec<bool>>,
+    /// For each line of the active side, the labels of each of its parts.
+    active: Vec<Vec<Vec<usize>>>,
+}
+
+impl Compatibility {
+    fn new(p: &Problem, labels: &[Label]) -> Option<Self> {
+        if p.passive.degree != Degree::Finite(2) || p.active.degree == Degree::Star {
+            return None;
+        }
+        let index = |l: &Label| labels.iter().position(|m| m == l).unwrap();
+        let mut allowed = vec![vec![false; labels.len()]; labels.len()];
+        for line in &p.passive.lines {
+            let groups: Vec<Vec<usize>> = line
+                .parts
+                .iter()
+                .flat_map(|part| {
+                    let count = match part.gtype {
+                        GroupType::Many(x) => x as usize,
+                        GroupType::Star => 0,
+                    };
+                    std::iter::repeat_n(part.group.iter().map(index).collect(), count)
+                })
+                .collect();
+            let [g1, g2] = &groups[..] else {
+                return None;
+            };
+            for &a in g1 {
+                for &b in g2 {
+                    allowed[a][b] = true;
+                    allowed[b][a] = true;
+                }
+            }
+        }
+        let active = p
+            .active
+            .lines
+            .iter()
+            .map(|line| line.parts.iter().map(|part| part.group.iter().map(index).collect()).collect())
+            .collect();
+        Some(Self { allowed, active })
+    }
+}
+
+struct Search<'a> {
+    problem: &'a Problem,
+    labels: Vec<Label>,
+    target: usize,
+    checks: usize,
+    compatibility: Option<Compatibility>,
+}
+
+impl<'a> Search<'a> {
+    /// The class of each label, where the first `assignment.len()` labels are in the classes given by the assignment, and each other label is alone in its class.
+    fn classes(&self, assignment: &[usize]) -> Vec<usize> {
+        let assigned = assignment.iter().max().map_or(0, |&c| c + 1);
+        (0..self.labels.len()).map(|i| if i < assignment.len() { assignment[i] } else { assigned + i - assignment.len() }).collect()
+    }
+
+    /// Whether the image given by the assignment, as in `image`, is zero round solvable, decided with a clique search.
+    /// In zero rounds all the nodes output the same configuration, so each class it uses may meet any class it uses, itself included.
+    /// Hence the image is trivial if and only if, for some line of the active side, there is a class for each part such that all the chosen
+    /// classes are compatible with each other and with themselves, which is a clique with one vertex for each part in the graph whose vertices
+    /// are the pairs of a part and a class of its labels.
+    fn image_is_trivial(&self, compatibility: &Compatibility, assignment: &[usize]) -> bool {
+        let classes = self.classes(assignment);
+        let count = classes.iter().max().map_or(0, |&c| c + 1);
+        let mut allowed = vec![vec![false; count]; count];
+        for (a, row) in compatibility.allowed.iter().enumerate() {
+            for (b, &ok) in row.iter().enumerate() {
+                allowed[classes[a]][classes[b]] |= ok;
+            }
+        }
+        compatibility.active.iter().any(|line| {
+            let mut vertices = vec![];
+            for (part, labels) in line.iter().enumerate() {
+                let mut part_classes: Vec<usize> = labels.iter().map(|&l| classes[l]).filter(|&c| allowed[c][c]).collect();
+                part_classes.sort_unstable();
+                part_classes.dedup();
+                vertices.extend(part_classes.into_iter().map(|c| (part, c)));
+            }
+            let adj = (0..vertices.len())
+                .map(|i| {
+                    let (p, c) = vertices[i];
+                    (0..vertices.len()).filter(|&j| vertices[j].0 != p && allowed[c][vertices[j].1]).collect()
+                })
+                .collect();
+            Graph::from_adj(adj).max_clique().len() == line.len()
+        })
+    }
+
+    /// The problem where the first `assignment.len()` labels are mapped to the first label of their class, and the others are unchanged.
+    fn image(&self, assignment: &[usize], eh: &mut EventHandler) -> Problem {
+        let merges: Vec<(Label, Label)> = assignment
+            .iter()
+            .enumerate()
+            .map(|(i, &class)| (self.labels[i], self.labels[assignment.iter().position(|&c| c == class).unwrap()]))
+            .collect();
+        let mut p = self.problem.relax_many_merges(&merges);
+        p.passive.maximize(eh);
+        p.compute_triviality(eh);
+        p
+    }
+
+    /// Extends the assignment, where labels are mapped to classes numbered in order of first appearance.
+    /// Mapping more labels together gives a relaxation, so a partial assignment whose image is trivial cannot be extended.
+    fn rec(&mut self, assignment: &mut Vec<usize>, eh: &mut EventHandler) -> Result<Option<(Vec<usize>, Problem)>, &'static str> {
+        let classes = assignment.iter().max().map_or(0, |&c| c + 1);
+        let remaining = self.labels.len() - assignment.len();
+        if classes > self.target || classes + remaining < self.target {
+            return Ok(None);
+        }
+        let merged = assignment.split_last().is_some_and(|(last, rest)| rest.contains(last));
+        if merged || remaining == 0 {
+            self.checks += 1;
+            if self.checks > MAX_CHECKS {
+                return Err("There are too many mappings to check");
+            }
+            eh.notify("quotient", self.checks, MAX_CHECKS);
+            if let Some(compatibility) = &self.compatibility {
+                if self.image_is_trivial(compatibility, assignment) {
+                    return Ok(None);
+                }
+                if remaining == 0 {
+                    return Ok(Some((assignment.clone(), self.image(assignment, eh))));
+                }
+            } else {
+                let image = self.image(assignment, eh);
+                if !image.trivial_sets.as_ref().unwrap().is_empty() {
+                    return Ok(None);
+                }
+                if remaining == 0 {
+                    return Ok(Some((assignment.clone(), image)));
+                }
+            }
+        }
+        for class in 0..=classes {
+            assignment.push(class);
+            let result = self.rec(assignment, eh)?;
+            assignment.pop();
+            if result.is_some() {
+                return Ok(result);
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Problem {
+    /// Searches for a surjective mapping of the labels onto `target` labels such that the image of the problem,
+    /// obtained by mapping all its configurations, is not zero round solvable.
+    /// The image is a relaxation of the problem, so it gives a lower bound for it if it is not trivial.
+    /// If the passive side has degree 2, the triviality of the images is decided with a clique search on the classes, without computing them.
+    pub fn nontrivial_quotient(&self, target: usize, eh: &mut EventHandler) -> Result<Option<Quotient>, &'static str> {
+        let labels = self.labels();
+        if target == 0 || target >= labels.len() {
+            return Err("The number of labels of the quotient must be positive and smaller than the number of labels of the problem");
+        }
+        let mut search = Search {
+            problem: self,
+            labels: labels.clone(),
+            target,
+            checks: 0,
+            compatibility: Compatibility::new(self, &labels),
+        };
+        let Some((assignment, mut problem)) = search.rec(&mut vec![], eh)? else {
+            return Ok(None);
+        };
+        problem.discard_useless_stuff(false, eh);
+        let classes = (0..target)
+            .map(|c| (0..labels.len()).filter(|&i| assignment[i] == c).map(|i| labels[i]).collect())
+            .collect();
+        Ok(Some(Quotient { classes, problem }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use itertools::Itertools;
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    use super::{Compatibility, Search};
+
+    #[test]
+    fn quotient() {
+        let eh = &mut EventHandler::null();
+        // 3-coloring, whose quotients on 2 labels are trivial
+        let p = Problem::from_string("A A\nB B\nC C\n\nA BC\nB C").unwrap();
+        assert!(p.nontrivial_quotient(2, eh).unwrap().is_none());
+        assert!(p.nontrivial_quotient(3, eh).is_err());
+
+        // sinkless orientation where incoming edges have two labels
+        let p = Problem::from_string("O I^2\nO J^2\n\nO IJ\nIJ IJ").unwrap();
+        let q = p.nontrivial_quotient(2, eh).unwrap().unwrap();
+        assert_eq!(q.classes, vec![vec![0], vec![1, 2]]);
+        assert_eq!(format!("{}", q.problem), "O I^2\n\nOI I\n");
+
+        // the clique search agrees with the triviality of the images
+        for text in ["M U^2\nP^3\n\nM UP\nU^2", "A A\nB B\nC C\nD D\n\nA BCD\nB CD\nC D", "O I^2\nO J^2\n\nO IJ\nIJ IJ"] {
+            let p = Problem::from_string(text).unwrap();
+            let labels = p.labels();
+            let search = Search { problem: &p, labels: labels.clone(), target: 2, checks: 0, compatibility: Compatibility::new(&p, &labels) };
+            let compatibility = search.compatibility.as_ref().unwrap();
+            for assignment in (0..labels.len()).map(|_| 0..2).multi_cartesian_product() {
+                let trivial = !search.image(&assignment, eh).trivial_sets.unwrap().is_empty();
+                assert_eq!(search.image_is_trivial(compatibility, &assignment), trivial);
+            }
+        }
+    }
+}
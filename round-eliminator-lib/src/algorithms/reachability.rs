@@ -0,0 +1,92 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{group::Label, problem::Problem};
+
+/// Reachability information of the diagram of a problem, computed once so that it can be queried many times.
+/// It does not get updated if the problem changes.
+#[derive(Clone, Debug)]
+pub struct Reachability {
+    labels: Vec<Label>,
+    successors: HashMap<Label, HashSet<Label>>,
+    predecessors: HashMap<Label, HashSet<Label>>,
+    direct: HashSet<(Label, Label)>,
+}
+
+impl Reachability {
+    /// Whether b is reachable from a in the diagram, that is, b is at least as strong as a. Every label reaches itself.
+    pub fn reaches(&self, a: Label, b: Label) -> bool {
+        a == b || self.successors.get(&a).is_some_and(|s| s.contains(&b))
+    }
+
+    /// Whether (a,b) is an edge of the direct diagram, that is, of the transitive reduction of the diagram.
+    pub fn is_direct_edge(&self, a: Label, b: Label) -> bool {
+        self.direct.contains(&(a, b))
+    }
+
+    /// Labels reachable from `label`, including itself.
+    pub fn successors(&self, label: Label) -> HashSet<Label> {
+        let mut result = self.successors.get(&label).cloned().unwrap_or_default();
+        result.insert(label);
+        result
+    }
+
+    /// Labels from which `label` is reachable, including itself.
+    pub fn predecessors(&self, label: Label) -> HashSet<Label> {
+        let mut result = self.predecessors.get(&label).cloned().unwrap_or_default();
+        result.insert(label);
+        result
+    }
+
+    /// Whether a and b are equivalent, that is, each is reachable from the other.
+    pub fn equivalent(&self, a: Label, b: Label) -> bool {
+        self.reaches(a, b) && self.reaches(b, a)
+    }
+
+    /// Returns all pairs (a,b) of distinct labels such that b is not reachable from a, ordered by a and then by b.
+    pub fn unreachable_pairs(&self) -> Vec<(Label, Label)> {
+        let mut result = vec![];
+        for &a in &self.labels {
+            for &b in &self.labels {
+                if !self.reaches(a, b) {
+                    result.push((a, b));
+                }
+            }
+        }
+        result
+    }
+}
+
+impl Problem {
+    /// Precomputes the reachability information of the diagram, that must have been computed already.
+    /// The result should be computed once and then queried, rather than computed again for each query.
+    pub fn reachability(&self) -> Result<Reachability, &'static str> {
+        let (Some(_), Some((_, direct))) = (self.diagram_indirect.as_ref(), self.diagram_direct.as_ref()) else {
+            return Err("The diagram has not been computed");
+        };
+        Ok(Reachability {
+            labels: self.labels(),
+            successors: self.diagram_indirect_to_reachability_adj(),
+            predecessors: self.diagram_indirect_to_inverse_reachability_adj(),
+            direct: direct.iter().cloned().collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    #[test]
+    fn reachability() {
+        let mut p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        assert!(p.reachability().is_err());
+        p.compute_diagram(&mut EventHandler::null());
+        let r = p.reachability().unwrap();
+        assert!(r.reaches(2, 1));
+        assert!(!r.reaches(1, 2));
+        assert!(r.is_direct_edge(2, 1));
+        assert_eq!(r.unreachable_pairs(), vec![(0, 1), (0, 2), (1, 0), (1, 2), (2, 0)]);
+        assert_eq!(r.predecessors(1), [1, 2].into_iter().collect());
+    }
+}
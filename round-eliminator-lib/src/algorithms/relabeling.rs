@@ -0,0 +1,244 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constraint::Constraint,
+    group::{GroupType, Label},
+    line::Degree,
+    problem::Problem,
+    store::Side,
+};
+
+use super::event::EventHandler;
+
+/// The reason why no renaming makes two problems syntactically equal, which certifies that no renaming exists.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum RelabelingMismatch {
+    /// The problems have different degrees on this side: the degree of the first problem, and the one of the second problem.
+    Degrees(Side, Degree, Degree),
+    /// The problems have a different number of labels.
+    LabelCount(usize, usize),
+    /// The problems have a different number of lines on this side.
+    LineCount(Side, usize, usize),
+    /// These labels of the first problem and these labels of the second problem are exactly the ones with the same invariants,
+    /// in terms of the configurations they appear in and of their neighborhood in the diagram, but they are not as many.
+    /// Any renaming would have to map the former to the latter.
+    Invariants(Vec<Label>, Vec<Label>),
+    /// All the renamings that preserve the invariants and the diagram have been tried, and none of them works.
+    /// This is the number of complete renamings that have been checked against the constraints.
+    Exhausted(usize),
+}
+
+type LabelSignature = (Vec<(GroupType, usize, usize)>, Vec<(GroupType, usize, usize)>, usize, usize);
+
+impl Problem {
+    /// Searches for a bijection from the labels of this problem to the labels of `other`,
+    /// such that renaming the labels gives exactly the same constraints.
+    /// Labels are first partitioned according to invariants that do not depend on names
+    /// (the kind of lines and groups they appear in, and their degrees in the diagram),
+    /// and then the search tries only bijections that preserve these invariants and the diagram.
+    pub fn find_matching_relabeling(&self, other: &Problem) -> Result<HashMap<Label, Label>, RelabelingMismatch> {
+        if self.active.degree != other.active.degree {
+            return Err(RelabelingMismatch::Degrees(Side::Active, self.active.degree, other.active.degree));
+        }
+        if self.passive.degree != other.passive.degree {
+            return Err(RelabelingMismatch::Degrees(Side::Passive, self.passive.degree, other.passive.degree));
+        }
+        let labels = self.labels();
+        let other_labels = other.labels();
+        if labels.len() != other_labels.len() {
+            return Err(RelabelingMismatch::LabelCount(labels.len(), other_labels.len()));
+        }
+        if self.active.lines.len() != other.active.lines.len() {
+            return Err(RelabelingMismatch::LineCount(Side::Active, self.active.lines.len(), other.active.lines.len()));
+        }
+        if self.passive.lines.len() != other.passive.lines.len() {
+            return Err(RelabelingMismatch::LineCount(Side::Passive, self.passive.lines.len(), other.passive.lines.len()));
+        }
+
+        let diagram = self.diagram_for_matching();
+        let other_diagram = other.diagram_for_matching();
+
+        let signatures = self.label_signatures(&diagram);
+        let other_signatures = other.label_signatures(&other_diagram);
+
+        let mut candidates = vec![];
+        for &l in &labels {
+            let c: Vec<Label> = other_labels
+                .iter()
+                .cloned()
+                .filter(|o| signatures[&l] == other_signatures[o])
+                .collect();
+            let same: Vec<Label> = labels.iter().cloned().filter(|x| signatures[x] == signatures[&l]).collect();
+            if c.len() != same.len() {
+                return Err(RelabelingMismatch::Invariants(same, c));
+            }
+            candidates.push((l, c));
+        }
+        candidates.sort_by_key(|(_, c)| c.len());
+
+        let mut checked = 0;
+        match self.run_relabeling_search(other, candidates, diagram, other_diagram, &mut checked) {
+            Some(map) => Ok(map),
+            None => Err(RelabelingMismatch::Exhausted(checked)),
+        }
+    }
+
+    /// Searches for a renaming of the labels of this problem that gives exactly the same constraints, and that maps each label of
+    /// `pinned` to the given one. Since the search uses the same invariants as `find_matching_relabeling`, it is much faster than trying
+    /// all renamings. Returns `None` if no such renaming exists.
+    pub(crate) fn find_automorphism(&self, pinned: &HashMap<Label, Label>) -> Option<HashMap<Label, Label>> {
+        let diagram = self.diagram_for_matching();
+        let signatures = self.label_signatures(&diagram);
+        let labels = self.labels();
+        let mut candidates = vec![];
+        for &l in &labels {
+            let c: Vec<Label> = labels
+                .iter()
+                .cloned()
+                .filter(|o| signatures[&l] == signatures[o])
+                .filter(|o| pinned.get(&l).is_none_or(|p| p == o))
+                .collect();
+            candidates.push((l, c));
+        }
+        candidates.sort_by_key(|(_, c)| c.len());
+        self.run_relabeling_search(self, candidates, diagram.clone(), diagram, &mut 0)
+    }
+
+    fn run_relabeling_search(
+        &self,
+        other: &Problem,
+        candidates: Vec<(Label, Vec<Label>)>,
+        diagram: Vec<(Label, Label)>,
+        other_diagram: Vec<(Label, Label)>,
+        checked: &mut usize,
+    ) -> Option<HashMap<Label, Label>> {
+        let target_active = other.active.canonical_lines();
+        let target_passive = other.passive.canonical_lines();
+
+        let mut search = RelabelingSearch {
+            candidates,
+            diagram: diagram.into_iter().collect(),
+            other_diagram: other_diagram.into_iter().collect(),
+            map: HashMap::new(),
+            used: HashSet::new(),
+        };
+        let found = search.run(0, &mut |map| {
+            *checked += 1;
+            let active = self.active.relabeled(map).canonical_lines();
+            let passive = self.passive.relabeled(map).canonical_lines();
+            active == target_active && passive == target_passive
+        });
+        found.then_some(search.map)
+    }
+
+    /// The partial diagram of the problem. The diagram stored in the problem is not used, since it may be either the full diagram
+    /// or a partial one, and the diagrams of the two problems can be compared only if they are of the same kind.
+    fn diagram_for_matching(&self) -> Vec<(Label, Label)> {
+        let mut p = self.clone();
+        p.diagram_indirect = None;
+        p.diagram_direct = None;
+        p.compute_partial_diagram(&mut EventHandler::null());
+        p.diagram_indirect.unwrap()
+    }
+
+    fn label_signatures(&self, diagram: &[(Label, Label)]) -> HashMap<Label, LabelSignature> {
+        let occurrences = |c: &Constraint, l: Label| {
+            let mut v = vec![];
+            for line in &c.lines {
+                for part in &line.parts {
+                    if part.group.contains(&l) {
+                        v.push((part.gtype, part.group.len(), line.parts.len()));
+                    }
+                }
+            }
+            v.sort();
+            v
+        };
+        self.labels()
+            .into_iter()
+            .map(|l| {
+                let successors = diagram.iter().filter(|&&(a, b)| a == l && b != l).count();
+                let predecessors = diagram.iter().filter(|&&(a, b)| b == l && a != l).count();
+                (
+                    l,
+                    (occurrences(&self.active, l), occurrences(&self.passive, l), successors, predecessors),
+                )
+            })
+            .collect()
+    }
+}
+
+struct RelabelingSearch {
+    candidates: Vec<(Label, Vec<Label>)>,
+    diagram: HashSet<(Label, Label)>,
+    other_diagram: HashSet<(Label, Label)>,
+    map: HashMap<Label, Label>,
+    used: HashSet<Label>,
+}
+
+impl RelabelingSearch {
+    fn run<F: FnMut(&HashMap<Label, Label>) -> bool>(&mut self, i: usize, check: &mut F) -> bool {
+        if i == self.candidates.len() {
+            return check(&self.map);
+        }
+        let (l, candidates) = self.candidates[i].clone();
+        for o in candidates {
+            if self.used.contains(&o) {
+                continue;
+            }
+            let consistent = self.map.iter().all(|(&a, &b)| {
+                self.diagram.contains(&(a, l)) == self.other_diagram.contains(&(b, o))
+                    && self.diagram.contains(&(l, a)) == self.other_diagram.contains(&(o, b))
+            });
+            if !consistent {
+                continue;
+            }
+            self.map.insert(l, o);
+            self.used.insert(o);
+            if self.run(i + 1, check) {
+                return true;
+            }
+            self.map.remove(&l);
+            self.used.remove(&o);
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, line::Degree, problem::Problem, store::Side};
+
+    use super::RelabelingMismatch;
+
+    #[test]
+    fn matching_relabeling() {
+        let p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        let q = Problem::from_string("X Y Y\nZ Z Z\n\nX YZ\nY Y").unwrap();
+        let map = p.find_matching_relabeling(&q).unwrap();
+        let text = |p: &Problem, l| p.mapping_label_text.iter().find(|(x, _)| *x == l).unwrap().1.clone();
+        for (a, b) in [("M", "X"), ("U", "Y"), ("P", "Z")] {
+            let l = p.mapping_label_text.iter().find(|(_, s)| s == a).unwrap().0;
+            assert_eq!(text(&q, map[&l]), b);
+        }
+
+        // U is the only label of p that appears in U U, while Y does not appear in Z Z
+        let q = Problem::from_string("X Y Y\nZ Z Z\n\nX YZ\nZ Z").unwrap();
+        let u = p.mapping_label_text.iter().find(|(_, s)| s == "U").unwrap().0;
+        assert_eq!(p.find_matching_relabeling(&q), Err(RelabelingMismatch::Invariants(vec![u], vec![])));
+
+        let q = Problem::from_string("X Y\n\nX Y").unwrap();
+        assert_eq!(
+            p.find_matching_relabeling(&q),
+            Err(RelabelingMismatch::Degrees(Side::Active, Degree::Finite(3), Degree::Finite(2)))
+        );
+
+        // the diagrams of the two problems are compared even if only one of them has the full diagram
+        let mut q = Problem::from_string("X Y Y\nZ Z Z\n\nX YZ\nY Y").unwrap();
+        q.compute_diagram(&mut EventHandler::null());
+        assert!(p.find_matching_relabeling(&q).is_ok());
+    }
+}
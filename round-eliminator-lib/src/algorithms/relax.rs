@@ -11,7 +11,7 @@ impl Problem {
         let active = self.active.relax(from, to, true);
         let passive = self.passive.relax(from, to, true);
 
-        Problem {
+        let mut p = Problem {
             active,
             passive,
             mapping_label_text: self.mapping_label_text.clone(),
@@ -28,8 +28,20 @@ impl Problem {
             orientation_given: self.orientation_given,
             fixpoint_diagram : None,
             fixpoint_procedure_works : None,
-            marks_works : None
-        }
+            marks_works : None,
+            label_priorities : self.label_priorities.clone(),
+            degree_lifts : self.degree_lifts.clone(),
+            annotations : self.annotations_after_merges(&[(from, to)]),
+            provenance : self.provenance.clone(),
+            line_origins : None,
+            computed_for : None,
+            label_folds : self.label_folds.clone(),
+            color_labels : self.color_labels.clone(),
+            one_sided_labels : self.one_sided_labels.clone()
+        };
+        p.line_origins = self.line_origins_after_relax(&p);
+        p.one_sided_labels = p.one_sided_labels_after_change();
+        p
     }
 
     pub fn relax_many_merges(&self, merges : &Vec<(Label,Label)>) -> Self {
@@ -52,7 +64,7 @@ impl Problem {
             }
         }
 
-        Problem {
+        let mut p = Problem {
             active,
             passive,
             mapping_label_text: self.mapping_label_text.clone(),
@@ -69,14 +81,26 @@ impl Problem {
             orientation_given: self.orientation_given,
             fixpoint_diagram : None,
             fixpoint_procedure_works : None,
-            marks_works : None
-        }
+            marks_works : None,
+            label_priorities : self.label_priorities.clone(),
+            degree_lifts : self.degree_lifts.clone(),
+            annotations : self.annotations_after_merges(&merges),
+            provenance : self.provenance.clone(),
+            line_origins : None,
+            computed_for : None,
+            label_folds : self.label_folds.clone(),
+            color_labels : self.color_labels.clone(),
+            one_sided_labels : self.one_sided_labels.clone()
+        };
+        p.line_origins = self.line_origins_after_relax(&p);
+        p.one_sided_labels = p.one_sided_labels_after_change();
+        p
     }
 
     pub fn relax_addarrow(&self, from: Label, to: Label) -> Self {
         let passive = self.passive.relax(from, to, false);
 
-        Problem {
+        let mut p = Problem {
             active: self.active.clone(),
             passive,
             mapping_label_text: self.mapping_label_text.clone(),
@@ -93,8 +117,20 @@ impl Problem {
             orientation_given: self.orientation_given,
             fixpoint_diagram : None,
             fixpoint_procedure_works : None,
-            marks_works : None
-        }
+            marks_works : None,
+            label_priorities : self.label_priorities.clone(),
+            degree_lifts : self.degree_lifts.clone(),
+            annotations : self.annotations_for_derived(),
+            provenance : self.provenance.clone(),
+            line_origins : None,
+            computed_for : None,
+            label_folds : self.label_folds.clone(),
+            color_labels : self.color_labels.clone(),
+            one_sided_labels : self.one_sided_labels.clone()
+        };
+        p.line_origins = self.line_origins_after_relax(&p);
+        p.one_sided_labels = p.one_sided_labels_after_change();
+        p
     }
 }
 
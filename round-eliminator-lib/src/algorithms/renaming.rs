@@ -41,6 +41,8 @@ impl Problem {
         }
 
         let mut renaming = vec![];
+        // the computed fields refer to labels and not to their names, so they are still valid after renaming
+        let was_stale = self.is_stale();
 
         for (l, s) in v {
             if s.chars().any(|c| "()*^ ".contains(c)) {
@@ -54,6 +56,9 @@ impl Problem {
         }
 
         self.mapping_label_text = renaming;
+        if !was_stale && self.has_computed_stuff() {
+            self.mark_computed();
+        }
 
         Ok(())
     }
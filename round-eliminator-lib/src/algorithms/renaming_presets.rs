@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{group::Label, problem::Problem};
+
+use super::{
+    event::EventHandler,
+    known_problems::{known_problems, known_speedup, node_degree},
+};
+
+/// Label names suggested for a problem that is a known problem, or the result of a speedup of a known problem.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RenamingPreset {
+    pub problem: String,
+    /// 0 if the problem is the known problem, 1 if it is its speedup, in which case the names are the generators, see `rename_by_generators`.
+    pub speedups: usize,
+    /// A renaming that can be given to `rename`.
+    pub renaming: Vec<(Label, String)>,
+}
+
+/// The name of a label as it would be given to `rename`, that is, without the parentheses that are added around long names.
+fn plain_name(text: &str) -> String {
+    match text.strip_prefix('(').and_then(|t| t.strip_suffix(')')) {
+        Some(t) => t.to_string(),
+        None => text.to_string(),
+    }
+}
+
+impl Problem {
+    /// Compares the problem, up to renaming labels, with the known problems of the same degree and with their speedups, see `known_problems`,
+    /// and for each match returns the label names used in the literature.
+    pub fn renaming_presets(&self, eh: &mut EventHandler) -> Vec<RenamingPreset> {
+        let Some(degree) = node_degree(self) else {
+            return vec![];
+        };
+        let mut presets = vec![];
+        for (name, text) in known_problems(degree) {
+            let Some(known) = known_speedup(&name, &text, degree, 0, eh) else {
+                continue;
+            };
+            let mut candidates = vec![known];
+            if let Some(mut speedup) = known_speedup(&name, &text, degree, 1, eh) {
+                if speedup.rename_by_generators().is_ok() {
+                    candidates.push(speedup);
+                }
+            }
+            for (speedups, candidate) in candidates.into_iter().enumerate() {
+                if let Ok(map) = self.find_matching_relabeling(&candidate) {
+                    let text: HashMap<_, _> = candidate.mapping_label_text.iter().cloned().collect();
+                    let mut renaming: Vec<_> = map.into_iter().map(|(l, o)| (l, plain_name(&text[&o]))).collect();
+                    renaming.sort();
+                    presets.push(RenamingPreset { problem: name.clone(), speedups, renaming });
+                }
+            }
+        }
+        presets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem, serial::fix_problem};
+
+    #[test]
+    fn renaming_presets() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("X Y Y\nZ Z Z\n\nX YZ\nY Y").unwrap();
+        let presets = p.renaming_presets(eh);
+        assert_eq!(presets.len(), 1);
+        assert_eq!((presets[0].problem.as_str(), presets[0].speedups), ("maximal matching", 0));
+        p.rename(&presets[0].renaming).unwrap();
+        assert_eq!(p, Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap());
+
+        let mut q = Problem::from_string("A A A\nB C C\n\nA BC\nC C").unwrap().speedup(eh);
+        fix_problem(&mut q, false, false, eh);
+        let presets = q.renaming_presets(eh);
+        assert!(presets.iter().any(|preset| preset.problem == "maximal independent set" && preset.speedups == 1));
+        q.rename(&presets[0].renaming).unwrap();
+
+        // the known problems of the degree of the problem are used
+        let presets = Problem::from_string("X Y Y Y\nZ Z Z Z\n\nX YZ\nY Y").unwrap().renaming_presets(eh);
+        assert_eq!(presets.iter().map(|preset| preset.problem.as_str()).collect::<Vec<_>>(), vec!["maximal matching"]);
+
+        assert!(Problem::from_string("A B B\n\nA B").unwrap().renaming_presets(eh).is_empty());
+    }
+}
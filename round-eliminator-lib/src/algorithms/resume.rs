@@ -0,0 +1,128 @@
+use std::collections::HashSet;
+
+use crate::{problem::Problem, serial::AutoOperation};
+
+use super::event::EventHandler;
+
+/// A step of a sequence between two speedups: the operations applied after the speedup,
+/// the problem obtained by the speedup, and the problem obtained after the operations.
+pub(crate) type SequenceStep = (Vec<AutoOperation>, Problem, Problem);
+
+/// Splits a sequence, as returned by autolb and autoub or built by hand in the same format, at its speedups.
+/// The first step starts from the initial problem instead of a speedup.
+/// Each operation is replayed on the problem before it, and the result must be equal to the problem after it, once both are cleaned up
+/// with `discard_useless_stuff` and their passive sides are maximized, so that a search is never resumed from a problem that the sequence does not lead to.
+pub(crate) fn split_at_speedups(sequence: &[(AutoOperation, Problem)], eh: &mut EventHandler) -> Result<Vec<SequenceStep>, &'static str> {
+    let Some(((AutoOperation::Initial, initial), rest)) = sequence.split_first() else {
+        return Err("The sequence must start with the initial problem");
+    };
+    let mut steps = vec![(vec![], initial.clone(), initial.clone())];
+    let mut previous = initial;
+    for (op, p) in rest {
+        if matches!(op, AutoOperation::Initial | AutoOperation::SwapSides) {
+            return Err("The sequence can only contain speedups, merges, and hardenings after the initial problem");
+        }
+        check_replay(previous, op, p, eh)?;
+        match op {
+            AutoOperation::Speedup => steps.push((vec![], p.clone(), p.clone())),
+            op => {
+                let last = steps.last_mut().unwrap();
+                last.0.push(op.clone());
+                last.2 = p.clone();
+            }
+        }
+        previous = p;
+    }
+    Ok(steps)
+}
+
+/// Checks that `p` is the result of `op` on `previous`, once both are cleaned up, see `cleaned`.
+pub(crate) fn check_replay(previous: &Problem, op: &AutoOperation, p: &Problem, eh: &mut EventHandler) -> Result<(), &'static str> {
+    let replayed = match op {
+        AutoOperation::Speedup => previous.speedup(eh),
+        AutoOperation::SwapSides => previous.swap_sides(),
+        AutoOperation::Harden(keep) => previous.harden_keep(&keep.iter().cloned().collect::<HashSet<_>>(), true),
+        AutoOperation::Merge(merges, _) => previous.relax_many_merges(merges),
+        AutoOperation::Initial => return Err("Only the first problem of a sequence can be the initial problem"),
+    };
+    if cleaned(replayed, eh) != cleaned(p.clone(), eh) {
+        return Err("A problem of the sequence is not the result of its operation on the previous problem");
+    }
+    Ok(())
+}
+
+/// The problem after `discard_useless_stuff`, with the passive side maximized, since the searches may maximize it
+/// after an operation, for example when computing the zero round solvability.
+fn cleaned(mut p: Problem, eh: &mut EventHandler) -> Problem {
+    p.discard_useless_stuff(false, eh);
+    p.passive.maximize(eh);
+    p
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        algorithms::{autoub::{EvaluationPolicy, SearchStats}, event::EventHandler, label_schedule::LabelSchedule, pruning_rules::PruningRules},
+        problem::Problem,
+        serial::{fix_problem, AutoOperation},
+    };
+
+    #[test]
+    fn resume() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        fix_problem(&mut p, true, true, eh);
+        let mut sped = p.speedup(eh);
+        fix_problem(&mut sped, true, true, eh);
+        let prefix = vec![(AutoOperation::Initial, p.clone()), (AutoOperation::Speedup, sped.clone())];
+
+        let mut lengths = vec![];
+        let done = Problem::autolb_resume(&prefix, &LabelSchedule::uniform(5), 2, 1, 3, None, None, Default::default(), &PruningRules::default(), |len, sequence| {
+            assert!(matches!(sequence[0].0, AutoOperation::Initial));
+            assert!(matches!(sequence[1].0, AutoOperation::Speedup));
+            assert_eq!(sequence[1].1.to_string(), sped.to_string());
+            lengths.push(len);
+        }, eh).unwrap();
+        assert!(done);
+        assert!(lengths.iter().all(|&len| len >= 1));
+
+        // the prefix of an upper bound can contain hardenings, but not merges
+        let mut q = Problem::from_string("A A A\nB B C\n\nA A\nB C").unwrap();
+        fix_problem(&mut q, true, true, eh);
+        let mut q_sped = q.speedup(eh);
+        fix_problem(&mut q_sped, true, true, eh);
+        // keeping only A gives a zero round solvable problem, after one speedup
+        let kept = q_sped.mapping_label_text.iter().filter(|(_, t)| t == "A").map(|(l, _)| *l).collect();
+        let hardened = q_sped.harden_keep(&kept, true);
+        let ub_prefix = vec![(AutoOperation::Initial, q), (AutoOperation::Speedup, q_sped), (AutoOperation::Harden(kept.into_iter().collect()), hardened)];
+        let mut stats = SearchStats::default();
+        let mut lengths = vec![];
+        Problem::autoub_resume(&ub_prefix, &LabelSchedule::uniform(5), 2, 5, None, None, EvaluationPolicy::Lazy, &PruningRules::default(), &mut stats, |len, _, sequence| {
+            assert!(matches!(sequence[2].0, AutoOperation::Harden(_)));
+            lengths.push(len);
+        }, eh).unwrap();
+        assert_eq!(lengths, vec![1]);
+        // the pruning rules apply to the resumed search
+        let mut lengths = vec![];
+        let rules = PruningRules::parse("labels > 0").unwrap();
+        Problem::autoub_resume(&ub_prefix, &LabelSchedule::uniform(5), 2, 5, None, None, EvaluationPolicy::Lazy, &rules, &mut SearchStats::default(), |len, _, _| lengths.push(len), eh).unwrap();
+        assert!(lengths.is_empty());
+        assert!(Problem::autolb_resume(&ub_prefix, &LabelSchedule::uniform(5), 2, 1, 3, None, None, Default::default(), &PruningRules::default(), |_, _| {}, eh).is_err());
+        assert!(Problem::autolb_resume(&prefix[1..], &LabelSchedule::uniform(5), 2, 1, 3, None, None, Default::default(), &PruningRules::default(), |_, _| {}, eh).is_err());
+
+        // a sequence found by autolb can be resumed, even if the search maximized the passive sides of its problems
+        let mut found = vec![];
+        Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap().autolb(3, 2, 2, 2, None, None, Default::default(), |_, s| found = s, eh);
+        assert!(found.iter().any(|(op, _)| matches!(op, AutoOperation::Merge(..))));
+        assert!(Problem::autolb_resume(&found, &LabelSchedule::uniform(3), 2, 1, 3, None, None, Default::default(), &PruningRules::default(), |_, _| {}, eh).is_ok());
+
+        // the problems of the prefix must be the results of its operations
+        let other = Problem::from_string("A A A\nB B B\n\nA B").unwrap();
+        let tampered = vec![prefix[0].clone(), (AutoOperation::Speedup, other)];
+        assert!(Problem::autolb_resume(&tampered, &LabelSchedule::uniform(5), 2, 1, 3, None, None, Default::default(), &PruningRules::default(), |_, _| {}, eh).is_err());
+        let mut wrong_harden = ub_prefix.clone();
+        wrong_harden[2].1 = wrong_harden[1].1.clone();
+        assert!(Problem::autoub_resume(&wrong_harden, &LabelSchedule::uniform(5), 2, 5, None, None, EvaluationPolicy::Lazy, &PruningRules::default(), &mut SearchStats::default(), |_, _, _| {}, eh).is_err());
+    }
+}
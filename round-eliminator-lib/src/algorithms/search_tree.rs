@@ -0,0 +1,296 @@
+use std::io::{self, BufRead, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    algorithms::canonical_hash::{CanonicalHash, Fnv1a128},
+    group::Label,
+    problem::Problem,
+    store::{problem_id, ProblemId},
+};
+
+use super::event::EventHandler;
+
+/// Why the search did not continue from a node.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum PruneReason {
+    /// The same problem appears earlier in the sequence.
+    Cycle,
+    /// The same problem has already been visited after at most as many speedups.
+    Seen,
+    /// The kept labels are a subset of the ones of a hardening that already failed, see `SearchStats::dominance_pruned`.
+    Dominated,
+    /// The node could not give a better upper bound than the best one found so far.
+    Bound,
+    /// The maximum number of speedups has been reached.
+    MaxSteps,
+    /// The problem, or the hardening that gives it, is excluded by the pruning rules of the search, see `SearchStats::rules`.
+    Rule,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum NodeOutcome {
+    Expanded,
+    /// The problem, or the one obtained with one more speedup, is zero round solvable.
+    Solved,
+    Pruned(PruneReason),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SearchNode {
+    /// An id that depends only on the path from the root to the node, that is, on the labels kept by each hardening,
+    /// so that the same node gets the same id in every run of the search, independently of the order of exploration and of the shards.
+    pub id: CanonicalHash,
+    pub parent: Option<usize>,
+    /// The number of speedups performed to reach this node.
+    pub depth: usize,
+    /// The labels kept by the hardening that produced this node.
+    pub kept: Vec<Label>,
+    /// The canonical hash of the problem, as computed by the store. It is None for nodes pruned before computing the problem.
+    pub hash: Option<ProblemId>,
+    pub labels: usize,
+    /// The weight used by autoub to order the hardenings of the parent, the ones with the smallest weight being tried first.
+    /// It is None if the labels of the parent do not come from a speedup.
+    pub score: Option<usize>,
+    pub outcome: NodeOutcome,
+}
+
+/// The tree explored by autoub, where each node is a problem obtained by speedups and hardenings.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SearchTree {
+    pub nodes: Vec<SearchNode>,
+    /// The path from the root to the node currently visited by the search.
+    #[serde(skip)]
+    stack: Vec<usize>,
+}
+
+impl SearchTree {
+    fn node_id(&self, parent: Option<usize>, depth: usize, kept: &[Label]) -> CanonicalHash {
+        let mut h = Fnv1a128::default();
+        if let Some(parent) = parent {
+            h.write(&self.nodes[parent].id.0.to_le_bytes());
+        }
+        h.write(&(depth as u64).to_le_bytes());
+        let mut kept = kept.to_vec();
+        kept.sort_unstable();
+        for l in kept {
+            h.write(&(l as u64).to_le_bytes());
+        }
+        h.finish()
+    }
+
+    pub(crate) fn enter(&mut self, kept: &[Label], score: Option<usize>, problem: &Problem, depth: usize) {
+        let parent = self.stack.last().cloned();
+        self.nodes.push(SearchNode {
+            id: self.node_id(parent, depth, kept),
+            parent,
+            depth,
+            kept: kept.to_vec(),
+            hash: Some(problem_id(problem)),
+            labels: problem.labels().len(),
+            score,
+            outcome: NodeOutcome::Expanded,
+        });
+        self.stack.push(self.nodes.len() - 1);
+    }
+
+    pub(crate) fn leave(&mut self) {
+        self.stack.pop();
+    }
+
+    /// Sets the outcome of the node currently visited.
+    pub(crate) fn mark(&mut self, outcome: NodeOutcome) {
+        if let Some(&current) = self.stack.last() {
+            self.nodes[current].outcome = outcome;
+        }
+    }
+
+    /// Records a child of the current node that has been discarded without computing it.
+    pub(crate) fn pruned_child(&mut self, kept: &[Label], score: Option<usize>, reason: PruneReason) {
+        let current = self.stack.last().cloned();
+        let depth = current.map_or(0, |c| self.nodes[c].depth + 1);
+        self.nodes.push(SearchNode {
+            id: self.node_id(current, depth, kept),
+            parent: current,
+            depth,
+            kept: kept.to_vec(),
+            hash: None,
+            labels: kept.len(),
+            score,
+            outcome: NodeOutcome::Pruned(reason),
+        });
+    }
+
+    /// The node with the given id, see `SearchNode::id`.
+    pub fn node(&self, id: CanonicalHash) -> Option<usize> {
+        self.nodes.iter().position(|n| n.id == id)
+    }
+
+    pub fn why_pruned(&self, node: usize) -> Option<PruneReason> {
+        match self.nodes.get(node)?.outcome {
+            NodeOutcome::Pruned(reason) => Some(reason),
+            _ => None,
+        }
+    }
+
+    /// The nodes from a root to the given one.
+    pub fn path(&self, node: usize) -> Vec<usize> {
+        let mut path = vec![node];
+        while let Some(parent) = self.nodes[*path.last().unwrap()].parent {
+            path.push(parent);
+        }
+        path.reverse();
+        path
+    }
+
+    /// The nodes whose problem has the given hash, for example the one of a problem of a known good sequence.
+    pub fn find(&self, hash: ProblemId) -> Vec<usize> {
+        (0..self.nodes.len()).filter(|&i| self.nodes[i].hash == Some(hash)).collect()
+    }
+
+    /// Recomputes the problems on the path to the given node, starting from the problem given to autoub,
+    /// so that the node can be explored again. The diagram of `initial` must have been computed.
+    pub fn reexpand(&self, initial: &Problem, node: usize, eh: &mut EventHandler) -> Vec<Problem> {
+        let mut problems = vec![];
+        for (i, n) in self.path(node).into_iter().enumerate() {
+            let kept = self.nodes[n].kept.iter().cloned().collect();
+            let mut p = if i == 0 {
+                if self.nodes[n].kept == initial.labels() {
+                    problems.push(initial.clone());
+                    continue;
+                }
+                initial.harden_keep(&kept, true)
+            } else {
+                let mut np = problems.last().unwrap().speedup(eh);
+                np.discard_useless_stuff(false, eh);
+                np.sort_active_by_strength();
+                np.harden_keep(&kept, true)
+            };
+            p.discard_useless_stuff(false, eh);
+            p.sort_active_by_strength();
+            problems.push(p);
+        }
+        problems
+    }
+
+    /// Writes the tree, one node per line, with tab separated fields.
+    pub fn save<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for node in &self.nodes {
+            let outcome = match node.outcome {
+                NodeOutcome::Expanded => "expanded",
+                NodeOutcome::Solved => "solved",
+                NodeOutcome::Pruned(PruneReason::Cycle) => "cycle",
+                NodeOutcome::Pruned(PruneReason::Seen) => "seen",
+                NodeOutcome::Pruned(PruneReason::Dominated) => "dominated",
+                NodeOutcome::Pruned(PruneReason::Bound) => "bound",
+                NodeOutcome::Pruned(PruneReason::MaxSteps) => "maxsteps",
+                NodeOutcome::Pruned(PruneReason::Rule) => "rule",
+            };
+            let opt = |x: Option<String>| x.unwrap_or_else(|| "-".into());
+            let kept: Vec<_> = node.kept.iter().map(|l| l.to_string()).collect();
+            writeln!(
+                writer,
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                node.id,
+                opt(node.parent.map(|p| p.to_string())),
+                node.depth,
+                opt(node.hash.map(|h| h.to_string())),
+                node.labels,
+                opt(node.score.map(|s| s.to_string())),
+                kept.join(","),
+                outcome
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn load<R: BufRead>(reader: R) -> io::Result<Self> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid search tree");
+        let mut nodes = vec![];
+        for line in reader.lines() {
+            let line = line?;
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [id, parent, depth, hash, labels, score, kept, outcome] = fields[..] else {
+                return Err(invalid());
+            };
+            let number = |s: &str| s.parse::<u64>().map_err(|_| invalid());
+            let optional = |s: &str| if s == "-" { Ok(None) } else { number(s).map(Some) };
+            let outcome = match outcome {
+                "expanded" => NodeOutcome::Expanded,
+                "solved" => NodeOutcome::Solved,
+                "cycle" => NodeOutcome::Pruned(PruneReason::Cycle),
+                "seen" => NodeOutcome::Pruned(PruneReason::Seen),
+                "dominated" => NodeOutcome::Pruned(PruneReason::Dominated),
+                "bound" => NodeOutcome::Pruned(PruneReason::Bound),
+                "maxsteps" => NodeOutcome::Pruned(PruneReason::MaxSteps),
+                "rule" => NodeOutcome::Pruned(PruneReason::Rule),
+                _ => return Err(invalid()),
+            };
+            let kept = if kept.is_empty() {
+                vec![]
+            } else {
+                kept.split(',').map(|l| number(l).map(|l| l as Label)).collect::<Result<_, _>>()?
+            };
+            nodes.push(SearchNode {
+                id: id.parse().map_err(|_| invalid())?,
+                parent: optional(parent)?.map(|p| p as usize),
+                depth: number(depth)? as usize,
+                kept,
+                hash: if hash == "-" { None } else { Some(hash.parse().map_err(|_| invalid())?) },
+                labels: number(labels)? as usize,
+                score: optional(score)?.map(|s| s as usize),
+                outcome,
+            });
+        }
+        if nodes.iter().enumerate().any(|(i, n)| n.parent.is_some_and(|p| p >= i)) {
+            return Err(invalid());
+        }
+        Ok(SearchTree { nodes, stack: vec![] })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        algorithms::{
+            autoub::{EvaluationPolicy, SearchStats},
+            event::EventHandler,
+        },
+        problem::Problem,
+        store::problem_id,
+    };
+
+    use super::{NodeOutcome, SearchTree};
+
+    #[test]
+    fn search_tree() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("M U^2\nP^3\n\nM UP\nU^2").unwrap();
+        p.compute_diagram(eh);
+        let mut stats = SearchStats { tree: Some(SearchTree::default()), ..Default::default() };
+        let mut sequences = vec![];
+        p.autoub(4, 2, 3, None, None, EvaluationPolicy::Lazy, &mut stats, |_, _, seq| sequences.push(seq), eh);
+        let tree = stats.tree.unwrap();
+        assert_eq!(tree.nodes[0].parent, None);
+        let solved: Vec<_> = (0..tree.nodes.len()).filter(|&i| tree.nodes[i].outcome == NodeOutcome::Solved).collect();
+        assert!(!solved.is_empty());
+        assert!(tree.nodes.iter().any(|n| matches!(n.outcome, NodeOutcome::Pruned(_))));
+
+        let path = tree.reexpand(&p, solved[0], eh);
+        assert_eq!(problem_id(path.last().unwrap()), tree.nodes[solved[0]].hash.unwrap());
+        assert!(!tree.find(problem_id(&p)).is_empty());
+
+        // another run of the search visits the same nodes, with the same ids
+        let mut again = SearchStats { tree: Some(SearchTree::default()), ..Default::default() };
+        p.autoub(4, 2, 3, None, None, EvaluationPolicy::Lazy, &mut again, |_, _, _| {}, eh);
+        assert_eq!(again.tree.unwrap().nodes, tree.nodes);
+        assert!(tree.nodes.iter().enumerate().all(|(i, n)| tree.node(n.id) == Some(i)));
+        assert!(tree.nodes.iter().filter(|n| n.depth > 0).all(|n| n.score.is_some()));
+
+        let mut file = vec![];
+        tree.save(&mut file).unwrap();
+        let loaded = SearchTree::load(&file[..]).unwrap();
+        assert_eq!(loaded.nodes, tree.nodes);
+    }
+}
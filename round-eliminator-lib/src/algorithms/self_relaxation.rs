@@ -0,0 +1,259 @@
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    group::{Group, Label},
+    line::Line,
+    part::Part,
+    problem::Problem,
+};
+
+use super::{event::EventHandler, limits::Limits};
+
+/// A relaxation applied to obtain the candidates of `self_relaxation`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum RelaxationStep {
+    /// See `Problem::relax_merge`.
+    Merge(Label, Label),
+    /// See `Problem::relax_addarrow`.
+    Addarrow(Label, Label),
+}
+
+/// The parameters of `self_relaxation`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SelfRelaxationParams {
+    /// The largest number of merges and added arrows applied to the problem to obtain the relaxation.
+    pub max_relaxations: usize,
+    /// The relaxations whose speedups have more labels than this are skipped.
+    pub max_labels: usize,
+    /// The largest number of assignments tried while searching for the self-map of each relaxation.
+    pub max_assignments: usize,
+}
+
+impl Default for SelfRelaxationParams {
+    fn default() -> Self {
+        SelfRelaxationParams { max_relaxations: 2, max_labels: 12, max_assignments: 100_000 }
+    }
+}
+
+/// A relaxation of a problem that is also a relaxation of its own speedup, which certifies that the problem requires Ω(log n) rounds
+/// for deterministic algorithms: if the relaxation could be solved in T rounds, mapping the labels of its speedup would solve it
+/// in fewer rounds, and eventually in 0 rounds, but it is not zero round solvable.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SelfRelaxation {
+    /// The relaxations that give `relaxation` from the problem, in order.
+    pub steps: Vec<RelaxationStep>,
+    pub relaxation: Problem,
+    /// The number of speedups applied to the relaxation: 1 if the speedup already has the same degrees, and otherwise 2.
+    pub speedups: usize,
+    pub sped_up: Problem,
+    /// The self-map, that sends each label of `sped_up` to a label of `relaxation`, such that every configuration of `sped_up`
+    /// becomes a configuration of `relaxation`.
+    pub map: Vec<(Label, Label)>,
+}
+
+impl SelfRelaxation {
+    /// The self-map as text, one label of the speedup per line.
+    pub fn map_text(&self) -> String {
+        let from: HashMap<_, _> = self.sped_up.mapping_label_text.iter().cloned().collect();
+        let to: HashMap<_, _> = self.relaxation.mapping_label_text.iter().cloned().collect();
+        self.map.iter().map(|(a, b)| format!("{} -> {}\n", from[a], to[b])).join("")
+    }
+}
+
+/// The number of speedups, the sped up problem, and the map from its labels to the labels of the relaxation.
+type SelfMap = (usize, Problem, Vec<(Label, Label)>);
+
+/// Searches for an assignment of a label of `target` to each label of `from`, such that the lines of `from` become lines of `target`.
+/// Labels are assigned in order of how many lines contain them, and after each assignment only the lines whose labels
+/// have all been assigned are checked. Each label first tries the labels it is built from.
+struct MapSearch<'a> {
+    order: Vec<Label>,
+    candidates: Vec<Vec<Label>>,
+    /// The lines of each side whose last label in `order` is at the given position, with whether they are active.
+    completed: Vec<Vec<(bool, &'a Line)>>,
+    target: &'a Problem,
+    map: HashMap<Label, Label>,
+    budget: usize,
+}
+
+impl MapSearch<'_> {
+    fn mapped(&self, line: &Line) -> Line {
+        let parts = line
+            .parts
+            .iter()
+            .map(|part| Part { gtype: part.gtype, group: Group(part.group.iter().map(|l| self.map[l]).sorted().dedup().collect()) })
+            .collect();
+        let mut line = Line { parts };
+        line.normalize();
+        line
+    }
+
+    fn run(&mut self, i: usize, eh: &mut EventHandler) -> bool {
+        if i == self.order.len() {
+            return true;
+        }
+        for j in 0..self.candidates[i].len() {
+            if self.budget == 0 {
+                return false;
+            }
+            self.budget -= 1;
+            if i == 0 {
+                eh.notify("self-map", j, self.candidates[i].len());
+            }
+            self.map.insert(self.order[i], self.candidates[i][j]);
+            let ok = self.completed[i].iter().all(|&(active, line)| {
+                let c = if active { &self.target.active } else { &self.target.passive };
+                c.includes(&self.mapped(line))
+            });
+            if ok && self.run(i + 1, eh) {
+                return true;
+            }
+        }
+        self.map.remove(&self.order[i]);
+        false
+    }
+}
+
+fn find_map(from: &Problem, target: &Problem, built_from: &HashMap<Label, Vec<Label>>, budget: usize, eh: &mut EventHandler) -> Option<Vec<(Label, Label)>> {
+    let lines: Vec<(bool, &Line)> = from.active.lines.iter().map(|line| (true, line)).chain(from.passive.lines.iter().map(|line| (false, line))).collect();
+    let order: Vec<Label> = from
+        .labels()
+        .into_iter()
+        .sorted_by_key(|l| std::cmp::Reverse(lines.iter().filter(|(_, line)| line.parts.iter().any(|part| part.group.contains(l))).count()))
+        .collect();
+    let position: HashMap<Label, usize> = order.iter().enumerate().map(|(i, l)| (*l, i)).collect();
+    let mut completed = vec![vec![]; order.len()];
+    for &(active, line) in &lines {
+        let last = line.parts.iter().flat_map(|part| part.group.iter()).map(|l| position[l]).max().unwrap();
+        completed[last].push((active, line));
+    }
+    let targets = target.labels();
+    let candidates = order
+        .iter()
+        .map(|l| {
+            let preferred = built_from.get(l).cloned().unwrap_or_default();
+            targets.iter().cloned().sorted_by_key(|t| !preferred.contains(t)).collect()
+        })
+        .collect();
+
+    let mut search = MapSearch { order, candidates, completed, target, map: HashMap::new(), budget };
+    if search.run(0, eh) {
+        Some(search.map.into_iter().sorted().collect())
+    } else {
+        None
+    }
+}
+
+impl Problem {
+    /// Looks for a self-relaxation, see `SelfRelaxation`, which is the most common way of proving an Ω(log n) lower bound.
+    /// The candidate relaxations are obtained by applying up to `max_relaxations` merges and added arrows to the problem, fewer first,
+    /// and the relaxations that are zero round solvable are discarded together with all their further relaxations.
+    /// For each remaining relaxation, one speedup (if it gives the same degrees) and two speedups are tried,
+    /// and a map from the labels of the result to the labels of the relaxation is searched. Returns None if nothing is found, which does not imply that the problem can be solved faster.
+    pub fn self_relaxation(&self, params: &SelfRelaxationParams, eh: &mut EventHandler) -> Option<SelfRelaxation> {
+        let mut seen = HashSet::new();
+        let mut start = self.clone();
+        start.discard_useless_stuff(false, eh);
+        seen.insert(start.to_canonical_text());
+        let mut level = vec![(vec![], start)];
+
+        for depth in 0..=params.max_relaxations {
+            let mut next = vec![];
+            for (steps, mut p) in level {
+                eh.notify("self-relaxation", depth, params.max_relaxations + 1);
+                p.compute_triviality(eh);
+                if !p.trivial_sets.as_ref().unwrap().is_empty() {
+                    continue;
+                }
+                if let Some((speedups, sped_up, map)) = p.find_self_map(params, eh) {
+                    return Some(SelfRelaxation { steps, relaxation: p, speedups, sped_up, map });
+                }
+                if depth == params.max_relaxations {
+                    continue;
+                }
+                let labels = p.labels();
+                let pairs = labels.iter().cartesian_product(labels.iter()).filter(|(a, b)| a != b);
+                let candidates = pairs.clone().map(|(&a, &b)| RelaxationStep::Addarrow(a, b)).chain(pairs.map(|(&a, &b)| RelaxationStep::Merge(a, b)));
+                for step in candidates {
+                    let mut relaxed = match step {
+                        RelaxationStep::Merge(from, to) => p.relax_merge(from, to),
+                        RelaxationStep::Addarrow(from, to) => p.relax_addarrow(from, to),
+                    };
+                    relaxed.discard_useless_stuff(false, eh);
+                    if seen.insert(relaxed.to_canonical_text()) {
+                        let mut steps = steps.clone();
+                        steps.push(step);
+                        next.push((steps, relaxed));
+                    }
+                }
+            }
+            level = next;
+        }
+        None
+    }
+
+    /// The self-map from one or two speedups of this problem, if some of them can be relaxed to it.
+    fn find_self_map(&self, params: &SelfRelaxationParams, eh: &mut EventHandler) -> Option<SelfMap> {
+        let limits = Limits { max_labels: Some(params.max_labels), ..Default::default() };
+        let mut target = self.clone();
+        target.active.maximize(eh);
+        target.passive.maximize(eh);
+
+        let mut current = self.clone();
+        // the labels of this problem from which each label of `current` is built
+        let mut built_from: HashMap<Label, Vec<Label>> = self.labels().into_iter().map(|l| (l, vec![l])).collect();
+        for speedups in 1..=2 {
+            let mut next = current.speedup_with_limits(&limits, eh).ok()?;
+            next.discard_useless_stuff(false, eh);
+            built_from = next
+                .mapping_label_oldlabels
+                .iter()
+                .flatten()
+                .map(|(l, old)| (*l, old.iter().flat_map(|o| built_from.get(o).cloned().unwrap_or_default()).sorted().dedup().collect()))
+                .collect();
+            if next.active.degree == self.active.degree && next.passive.degree == self.passive.degree {
+                if let Some(map) = find_map(&next, &target, &built_from, params.max_assignments, eh) {
+                    return Some((speedups, next, map));
+                }
+            }
+            current = next;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    use super::{RelaxationStep, SelfRelaxationParams};
+
+    #[test]
+    fn self_relaxation() {
+        let eh = &mut EventHandler::null();
+        let params = SelfRelaxationParams::default();
+
+        // sinkless orientation becomes a fixed point once edges are allowed to point towards both endpoints
+        let p = Problem::from_string("O OI OI\n\nI O").unwrap();
+        let found = p.self_relaxation(&params, eh).unwrap();
+        assert_eq!(found.steps, vec![RelaxationStep::Addarrow(0, 1)]);
+        assert_eq!(found.speedups, 2);
+        assert_eq!(found.map.len(), found.sped_up.labels().len());
+        assert_eq!(found.map_text().lines().count(), found.map.len());
+
+        // 3-coloring of cycles can be solved in O(log* n) rounds, so no self-relaxation exists
+        let p = Problem::from_string("A A\nB B\nC C\n\nA BC\nB C").unwrap();
+        assert!(p.self_relaxation(&params, eh).is_none());
+
+        // maximal independent set can be solved in O(log* n) rounds on bounded degree graphs
+        let p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        assert!(p.self_relaxation(&SelfRelaxationParams { max_relaxations: 3, ..params.clone() }, eh).is_none());
+
+        let p = Problem::from_string("A A A\n\nA A").unwrap();
+        assert!(p.self_relaxation(&params, eh).is_none());
+    }
+}
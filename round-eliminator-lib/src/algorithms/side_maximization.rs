@@ -0,0 +1,84 @@
+use crate::{constraint::Constraint, problem::Problem};
+
+use super::{
+    event::EventHandler,
+    limits::{LimitExceeded, Limits},
+};
+
+/// The constraint maximized, directly if it has a structure for which the result is known, and otherwise stopping when a limit is exceeded.
+fn maximized(c: &Constraint, limits: &Limits, eh: &mut EventHandler) -> Result<Constraint, LimitExceeded> {
+    if c.is_maximized {
+        return Ok(c.clone());
+    }
+    if let Some(maximized) = c.maximize_structured() {
+        return Ok(maximized);
+    }
+    let mut c = c.clone();
+    c.maximize_with_limits(limits, eh)?;
+    Ok(c)
+}
+
+impl Problem {
+    /// The same problem, with the passive side replaced by its maximized form, that is, by all the maximal configurations it allows,
+    /// written with as few lines as possible. This is the first step of `speedup`, without the rest of it.
+    /// Since the constraints are equivalent, everything that has been computed about the problem is kept.
+    pub fn maximize_passive(&self, limits: &Limits, eh: &mut EventHandler) -> Result<Problem, LimitExceeded> {
+        let mut p = self.clone();
+        p.discard_stale_computed_stuff();
+        p.passive = maximized(&p.passive, limits, eh)?;
+        if p.computed_for.is_some() {
+            p.mark_computed();
+        }
+        Ok(p)
+    }
+
+    /// Same as `maximize_passive`, but for the active side.
+    pub fn maximize_active(&self, limits: &Limits, eh: &mut EventHandler) -> Result<Problem, LimitExceeded> {
+        let mut p = self.clone();
+        p.discard_stale_computed_stuff();
+        p.active = maximized(&p.active, limits, eh)?;
+        if p.computed_for.is_some() {
+            p.mark_computed();
+        }
+        Ok(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        algorithms::{
+            event::EventHandler,
+            limits::{Limit, Limits},
+        },
+        problem::Problem,
+    };
+
+    #[test]
+    fn side_maximization() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("A AB AB\n\nA B\nB B").unwrap();
+        p.compute_triviality(eh);
+
+        let active = p.maximize_active(&Limits::default(), eh).unwrap();
+        assert!(active.active.is_maximized);
+        assert!(!active.passive.is_maximized);
+        assert_eq!(active.passive, p.passive);
+        assert_eq!(active.trivial_sets, p.trivial_sets);
+        assert!(!active.is_stale());
+
+        // A B and B B are combined into AB B
+        let passive = p.maximize_passive(&Limits::default(), eh).unwrap();
+        assert!(passive.passive.is_maximized);
+        assert_eq!(passive.active, p.active);
+        assert_eq!(passive.to_string(), "A AB^2\n\nAB B\n");
+        assert_eq!(passive.trivial_sets, p.trivial_sets);
+
+        let p = Problem::from_string("A B B\nC C C\n\nA B C\nB B B\nC C A").unwrap();
+        let limits = Limits { max_lines: Some(1), ..Default::default() };
+        let e = p.maximize_passive(&limits, eh).unwrap_err();
+        assert_eq!(e.limit, Limit::Lines(1));
+        assert!(p.maximize_active(&Limits::default(), eh).unwrap().active.is_maximized);
+    }
+}
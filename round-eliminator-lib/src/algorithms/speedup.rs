@@ -3,16 +3,29 @@ use std::collections::HashMap;
 use itertools::Itertools;
 
 use crate::{
+    constraint::Constraint,
     group::{Group, Label},
     problem::Problem,
 };
 
-use super::event::EventHandler;
+use super::{
+    event::EventHandler,
+    limits::{LimitExceeded, LimitGuard, Limits},
+};
 
 impl Problem {
     pub fn speedup(&self, eh: &mut EventHandler) -> Self {
-        let mut newactive_before_renaming = self.passive.clone();
-        newactive_before_renaming.maximize(eh);
+        self.speedup_guarded(&LimitGuard::unlimited(), eh).expect("a speedup without limits cannot fail")
+    }
+
+    /// Same as `speedup`, but stops when a limit is exceeded, either while maximizing the passive side or because the result is too large.
+    pub fn speedup_with_limits(&self, limits: &Limits, eh: &mut EventHandler) -> Result<Self, LimitExceeded> {
+        self.speedup_guarded(&LimitGuard::new(limits), eh)
+    }
+
+    fn speedup_guarded(&self, guard: &LimitGuard, eh: &mut EventHandler) -> Result<Self, LimitExceeded> {
+        guard.check_constraint("speedup", &self.passive, 0)?;
+        let newactive_before_renaming = self.maximized_passive(guard, eh)?;
 
         let mapping_label_oldlabels: Vec<_> = newactive_before_renaming
             .groups()
@@ -28,8 +41,65 @@ impl Problem {
             .collect();
 
         let active = newactive_before_renaming.edited(|g| Group(vec![h_oldlabels_label[&g.0]]));
+        // the new passive side has as many lines as the old active side, so the size of the result is known before building it
+        guard.check_sides("speedup", mapping_label_oldlabels.len(), &active, &self.active, 1)?;
+
+        Ok(self.speedup_with_new_labels(active, mapping_label_oldlabels))
+    }
+
+    /// Same as `speedup`, but only the given sets of old labels can be used as new labels.
+    /// Each set of the maximized passive side is replaced by all the given sets that contain it,
+    /// so the result is a relaxation of the speedup, as long as every such set has a superset among the given ones.
+    pub fn speedup_restricted(&self, candidates: &[Vec<Label>], eh: &mut EventHandler) -> Result<Self, &'static str> {
+        let labels = self.labels();
+        if candidates.is_empty() || candidates.iter().any(|c| c.is_empty()) {
+            return Err("The new labels must be nonempty sets of old labels");
+        }
+        if candidates.iter().flatten().any(|l| !labels.contains(l)) {
+            return Err("The new labels contain labels that are not in the problem");
+        }
+
+        let newactive_before_renaming = self
+            .maximized_passive(&LimitGuard::unlimited(), eh)
+            .expect("maximization without limits cannot fail");
 
-        let passive = self.active.edited(|g| {
+        let mapping_label_oldlabels: Vec<_> = candidates
+            .iter()
+            .map(|c| c.iter().cloned().sorted().collect::<Vec<_>>())
+            .unique()
+            .enumerate()
+            .map(|(a, b)| (a as Label, b))
+            .collect();
+
+        let mut missing = false;
+        let active = newactive_before_renaming.edited(|g| {
+            let ng: Vec<Label> = mapping_label_oldlabels
+                .iter()
+                .filter(|(_, o)| g.iter().all(|l| o.contains(l)))
+                .map(|p| p.0)
+                .collect();
+            missing |= ng.is_empty();
+            Group(ng)
+        });
+        if missing {
+            return Err("Some set of the speedup is not contained in any of the given new labels, the result would not be a relaxation");
+        }
+
+        Ok(self.speedup_with_new_labels(active, mapping_label_oldlabels))
+    }
+
+    /// The passive side maximized, directly if it has a structure for which the result is known.
+    fn maximized_passive(&self, guard: &LimitGuard, eh: &mut EventHandler) -> Result<Constraint, LimitExceeded> {
+        if let Some(maximized) = self.passive.maximize_structured() {
+            return Ok(maximized);
+        }
+        let mut passive = self.passive.clone();
+        passive.maximize_guarded(guard, eh)?;
+        Ok(passive)
+    }
+
+    fn speedup_with_new_labels(&self, mut active: Constraint, mapping_label_oldlabels: Vec<(Label, Vec<Label>)>) -> Self {
+        let mut passive = self.active.edited(|g| {
             let h = g.as_set();
             let ng = mapping_label_oldlabels
                 .iter()
@@ -39,6 +109,8 @@ impl Problem {
                 .collect();
             Group(ng)
         });
+        active.canonicalize();
+        passive.canonicalize();
 
         let mut p = Problem {
             active,
@@ -57,8 +129,20 @@ impl Problem {
             orientation_given: self.orientation_given,
             fixpoint_diagram : None,
             fixpoint_procedure_works : None,
-            marks_works : None
+            marks_works : None,
+            label_priorities : None,
+            degree_lifts : None,
+            annotations : None,
+            provenance : self.provenance.clone(),
+            line_origins : None,
+            computed_for : None,
+            label_folds : None,
+            color_labels : None,
+            one_sided_labels : None
         };
+        p.line_origins = self.line_origins_after_speedup();
+        p.color_labels = self.color_labels_after_speedup(&p);
+        p.one_sided_labels = self.one_sided_labels_after_speedup(&p);
         p.assign_chars();
         p
     }
@@ -71,23 +155,7 @@ impl Problem {
                 .iter()
                 .map(|(l, _)| *l)
                 .collect();
-            self.mapping_label_text = labels
-                .iter()
-                .map(|&i| {
-                    if labels.len() <= 62 {
-                        let i8 = i as u8;
-                        let c = match i {
-                            0..=25 => (b'A' + i8) as char,
-                            26..=51 => (b'a' + i8 - 26) as char,
-                            52..=61 => (b'0' + i8 - 52) as char,
-                            _ => (b'z' + 1 + i8 - 62) as char,
-                        };
-                        (i, format!("{}", c))
-                    } else {
-                        (i, format!("({})", i))
-                    }
-                })
-                .collect();
+            self.mapping_label_text = label_chars(&labels);
         } else {
             let old_to_text = self.mapping_oldlabel_text.as_ref().unwrap(). iter().cloned().collect::<HashMap<_,_>>();
             self.mapping_label_text = self.mapping_oldlabel_labels
@@ -111,6 +179,27 @@ impl Problem {
     }
 }
 
+/// The names of the labels obtained by a speedup: one character each if there are at most 62 labels, and their numbers otherwise.
+pub(crate) fn label_chars(labels: &[Label]) -> Vec<(Label, String)> {
+    labels
+        .iter()
+        .map(|&i| {
+            if labels.len() <= 62 {
+                let i8 = i as u8;
+                let c = match i {
+                    0..=25 => (b'A' + i8) as char,
+                    26..=51 => (b'a' + i8 - 26) as char,
+                    52..=61 => (b'0' + i8 - 52) as char,
+                    _ => (b'z' + 1 + i8 - 62) as char,
+                };
+                (i, format!("{}", c))
+            } else {
+                (i, format!("({})", i))
+            }
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -125,6 +214,25 @@ mod tests {
         assert_eq!(format!("{}", p), "A^2\nB C\n\nA BC^3\nAC C^3\n");
     }
 
+    #[test]
+    fn speedup_restricted() {
+        let eh = &mut EventHandler::null();
+        let p = Problem::from_string("M U U U\nP P P P\n\nM M\nU PU").unwrap();
+        let full = p.speedup(eh);
+        let candidates: Vec<_> = full.mapping_label_oldlabels.as_ref().unwrap().iter().map(|(_, o)| o.clone()).collect();
+        let restricted = p.speedup_restricted(&candidates, eh).unwrap();
+        assert_eq!(format!("{}", full), "A^2\nB C\n\nA BC^3\nC^4\n");
+        // B is contained in C, so it can be relaxed to C
+        assert_eq!(format!("{}", restricted), "A^2\nBC C\n\nA BC^3\nC^4\n");
+
+        assert!(p.speedup_restricted(&candidates[1..], eh).is_err());
+
+        let mut coarse = p.speedup_restricted(&[vec![0, 1], vec![0, 1, 2]], eh).unwrap();
+        coarse.compute_diagram(eh);
+        coarse.sort_active_by_strength();
+        assert_eq!(format!("{}", coarse), "AB^2\nAB B\n\nAB^4\n");
+    }
+
     #[test]
     fn matching() {
         //let mut eh = EventHandler::with(|(x,a,b)|println!("{} {} {}",x,a,b));
@@ -0,0 +1,113 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{group::Label, problem::Problem};
+
+use super::{event::EventHandler, limits::Limits, watchdog::Watchdog};
+
+/// Bounds on the size of the result of a speedup, obtained without computing it.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SpeedupEstimate {
+    /// Upper bound on the number of new labels, that is, the number of nonempty right-closed sets of labels.
+    /// It is None if there are more than the limit given to the estimator.
+    pub max_labels: Option<usize>,
+    /// The number of passive lines of the result, before it gets simplified.
+    pub passive_lines: usize,
+    /// The number of active lines of the result, that is, of lines of the maximized passive side.
+    /// It is None if the maximization reaches more than the limit given to the estimator, or if the watchdog predicts so
+    /// from the pairs of lines combined so far, see `Watchdog`.
+    #[serde(default)]
+    pub active_lines: Option<usize>,
+}
+
+impl Problem {
+    /// Estimates the size of the speedup of this problem. The diagram must have been computed already.
+    /// Every new label is a set of labels that appears in the maximized passive side, and these sets are right-closed,
+    /// while every active line gives exactly one new passive line.
+    /// Right-closed sets are counted only up to `limit`, since there may be exponentially many.
+    /// The active lines are counted by maximizing the passive side, which is stopped as soon as it has more than `limit` lines,
+    /// or as soon as the lines obtained from a sample of the pairs of lines predict so.
+    pub fn estimate_speedup_size(&self, limit: usize) -> SpeedupEstimate {
+        let labels = self.labels();
+        let successors = self.diagram_indirect_to_reachability_adj();
+        let predecessors = self.diagram_indirect_to_inverse_reachability_adj();
+        let mut state = HashMap::new();
+        let sets = count_right_closed(&labels, &successors, &predecessors, &mut state, limit.saturating_add(2));
+        // the empty set is always right-closed, but it is not a label
+        let max_labels = sets - 1;
+        let limits = Limits {
+            max_lines: Some(limit),
+            watchdog: Some(Watchdog { max_predicted_lines: limit, abort: true }),
+            ..Default::default()
+        };
+        let mut passive = self.passive.clone();
+        let active_lines = passive.maximize_with_limits(&limits, &mut EventHandler::null()).ok().map(|()| passive.lines.len());
+        SpeedupEstimate {
+            max_labels: if max_labels > limit { None } else { Some(max_labels) },
+            passive_lines: self.active.lines.len(),
+            active_lines,
+        }
+    }
+}
+
+/// Counts the right-closed sets compatible with the choices in `state`, stopping as soon as `limit` is reached.
+fn count_right_closed(
+    labels: &[Label],
+    successors: &HashMap<Label, HashSet<Label>>,
+    predecessors: &HashMap<Label, HashSet<Label>>,
+    state: &mut HashMap<Label, bool>,
+    limit: usize,
+) -> usize {
+    let Some(&l) = labels.iter().find(|l| !state.contains_key(l)) else {
+        return 1;
+    };
+    let mut count = 0;
+    for include in [true, false] {
+        // including a label forces its successors, excluding it forces out its predecessors
+        let forced = if include { &successors[&l] } else { &predecessors[&l] };
+        if forced.iter().chain(std::iter::once(&l)).any(|x| state.get(x) == Some(&!include)) {
+            continue;
+        }
+        let mut changed = vec![];
+        for &x in forced.iter().chain(std::iter::once(&l)) {
+            if state.insert(x, include).is_none() {
+                changed.push(x);
+            }
+        }
+        count += count_right_closed(labels, successors, predecessors, state, limit - count);
+        for x in changed {
+            state.remove(&x);
+        }
+        if count >= limit {
+            return count;
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    #[test]
+    fn estimate_speedup_size() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("M U U U\nP P P P\n\nM UP\nU U").unwrap();
+        p.compute_diagram(eh);
+        let estimate = p.estimate_speedup_size(100);
+        let speedup = p.speedup(eh);
+        assert_eq!(estimate.passive_lines, speedup.passive.lines.len());
+        assert!(estimate.max_labels.unwrap() >= speedup.labels().len());
+        assert_eq!(estimate.active_lines, Some(speedup.active.lines.len()));
+        // P -> U, so the right-closed sets are M, U, MU, PU, MPU
+        assert_eq!(estimate.max_labels, Some(5));
+
+        let mut p = Problem::from_string("A A A\nB B B\nC C C\n\nA BC\nB C").unwrap();
+        p.compute_diagram(eh);
+        assert_eq!(p.estimate_speedup_size(100).max_labels, Some(7));
+        assert_eq!(p.estimate_speedup_size(5).max_labels, None);
+        assert_eq!(p.estimate_speedup_size(1).active_lines, None);
+    }
+}
@@ -0,0 +1,232 @@
+//! Disk-backed storage for the candidate lines produced while maximizing, available only in native builds.
+//! Candidates are kept in memory until a threshold is reached, then the maximal ones are sorted and written to a temporary file.
+//! At the end, all the runs are merged, and only the lines that are not dominated by other lines are kept.
+
+use std::{
+    cell::Cell,
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Lines, Write},
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    algorithms::line_index::MaximalLines,
+    group::{Group, GroupType, Label},
+    line::Line,
+    part::Part,
+};
+
+static NEXT_RUN: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// The threshold set by `with_spill_threshold`, which takes precedence over the environment.
+    static THRESHOLD: Cell<Option<Option<usize>>> = const { Cell::new(None) };
+}
+
+/// Returns the number of candidate lines after which maximize starts spilling to disk, if set with `RE_MAXIMIZE_SPILL_LINES`,
+/// or with `with_spill_threshold`.
+pub fn spill_threshold() -> Result<Option<usize>, &'static str> {
+    if let Some(threshold) = THRESHOLD.with(|t| t.get()) {
+        return Ok(threshold);
+    }
+    match std::env::var("RE_MAXIMIZE_SPILL_LINES") {
+        Ok(val) => val.parse::<usize>().map(Some).map_err(|_| "RE_MAXIMIZE_SPILL_LINES is not a number"),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Runs `f` in the current thread with the given spill threshold instead of the one of the environment.
+/// With `None`, all the candidate lines are kept in memory, which is the fallback of operations that cannot report that spilling failed.
+pub(crate) fn with_spill_threshold<T>(threshold: Option<usize>, f: impl FnOnce() -> T) -> T {
+    let old = THRESHOLD.with(|t| t.replace(Some(threshold)));
+    let result = f();
+    THRESHOLD.with(|t| t.set(old));
+    result
+}
+
+pub(crate) struct LineSpill<FS> {
+    threshold: usize,
+    buffer: MaximalLines<FS>,
+    runs: Vec<PathBuf>,
+    written: usize,
+    is_superset: Option<FS>,
+}
+
+impl<FS> LineSpill<FS>
+where
+    FS: Fn(&Group, &Group) -> bool + Copy + Sync,
+{
+    /// Lines are compared with `is_superset`, or with the usual inclusion if it is `None`, see `MaximalLines`.
+    pub fn new(threshold: usize, is_superset: Option<FS>) -> Self {
+        LineSpill {
+            threshold: threshold.max(1),
+            buffer: MaximalLines::new(is_superset),
+            runs: vec![],
+            written: 0,
+            is_superset,
+        }
+    }
+
+    pub fn push(&mut self, line: Line) -> io::Result<()> {
+        self.buffer.add(line);
+        if self.buffer.len() >= self.threshold {
+            self.write_run()?;
+        }
+        Ok(())
+    }
+
+    /// The number of lines stored so far, on disk or in memory. Lines stored in different runs are counted once for each run.
+    pub fn stored(&self) -> usize {
+        self.written + self.buffer.len()
+    }
+
+    fn write_run(&mut self) -> io::Result<()> {
+        let mut lines = std::mem::replace(&mut self.buffer, MaximalLines::new(self.is_superset)).into_lines();
+        self.written += lines.len();
+        lines.sort();
+        let path = std::env::temp_dir().join(format!(
+            "round-eliminator-{}-{}.run",
+            std::process::id(),
+            NEXT_RUN.fetch_add(1, Ordering::SeqCst)
+        ));
+        self.runs.push(path.clone());
+        let mut file = BufWriter::new(File::create(&path)?);
+        for line in lines {
+            writeln!(file, "{}", encode(&line))?;
+        }
+        file.flush()
+    }
+
+    /// Adds all the stored lines to `into`, discarding the non maximal ones.
+    /// Runs are merged in sorted order, so that lines that appear in more than one run are checked only once.
+    pub fn merge_into(mut self, into: &mut MaximalLines<FS>) -> io::Result<()> {
+        for line in std::mem::replace(&mut self.buffer, MaximalLines::new(self.is_superset)).into_lines() {
+            into.add(line);
+        }
+
+        let mut readers: Vec<Lines<BufReader<File>>> = vec![];
+        for path in &self.runs {
+            readers.push(BufReader::new(File::open(path)?).lines());
+        }
+        let mut heap = BinaryHeap::new();
+        for (i, reader) in readers.iter_mut().enumerate() {
+            if let Some(line) = reader.next() {
+                heap.push(Reverse((decode(&line?)?, i)));
+            }
+        }
+        let mut last: Option<Line> = None;
+        while let Some(Reverse((line, i))) = heap.pop() {
+            if let Some(next) = readers[i].next() {
+                heap.push(Reverse((decode(&next?)?, i)));
+            }
+            if last.as_ref() == Some(&line) {
+                continue;
+            }
+            into.add(line.clone());
+            last = Some(line);
+        }
+        Ok(())
+    }
+}
+
+impl<FS> Drop for LineSpill<FS> {
+    fn drop(&mut self) {
+        for path in &self.runs {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Writes a line as space separated parts, each one given by its exponent (or `*`) followed by its labels.
+fn encode(line: &Line) -> String {
+    line.parts
+        .iter()
+        .map(|part| {
+            let exp = match part.gtype {
+                GroupType::Many(x) => x.to_string(),
+                GroupType::Star => "*".to_string(),
+            };
+            let labels: Vec<_> = part.group.0.iter().map(|l| l.to_string()).collect();
+            format!("{}:{}", exp, labels.join(","))
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn decode(s: &str) -> io::Result<Line> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid line in spill file");
+    let mut parts = vec![];
+    for part in s.split(' ') {
+        let (exp, labels) = part.split_once(':').ok_or_else(invalid)?;
+        let gtype = if exp == "*" {
+            GroupType::Star
+        } else {
+            GroupType::Many(exp.parse().map_err(|_| invalid())?)
+        };
+        let group = if labels.is_empty() {
+            vec![]
+        } else {
+            labels
+                .split(',')
+                .map(|l| l.parse::<Label>())
+                .collect::<Result<_, _>>()
+                .map_err(|_| invalid())?
+        };
+        parts.push(Part {
+            gtype,
+            group: Group(group),
+        });
+    }
+    Ok(Line { parts })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        algorithms::{event::EventHandler, limits::Limits, line_index::MaximalLines},
+        group::Group,
+        problem::Problem,
+    };
+
+    use super::{with_spill_threshold, LineSpill};
+
+    #[test]
+    fn spill() {
+        let eh = &mut EventHandler::null();
+        let p = Problem::from_string("A B B B\nC D D D\n\nAB CD\nA A\nC C\nBD BD").unwrap();
+        let mut expected = p.passive.clone();
+        expected.maximize(eh);
+
+        let f_is_superset = |g1: &Group, g2: &Group| g1.is_superset(g2);
+        let mut spill = LineSpill::new(2, Some(f_is_superset));
+        for line in &expected.lines {
+            spill.push(line.clone()).unwrap();
+            spill.push(line.clone()).unwrap();
+        }
+        assert!(spill.runs.len() > 1);
+        let paths = spill.runs.clone();
+        let mut merged = MaximalLines::new(Some(f_is_superset));
+        spill.merge_into(&mut merged).unwrap();
+        let mut merged_constraint = p.passive.clone();
+        merged_constraint.lines = merged.into_lines();
+        assert_eq!(merged_constraint.canonical_lines(), expected.canonical_lines());
+        assert!(paths.iter().all(|path| !path.exists()));
+    }
+
+    #[test]
+    fn maximize_spilling() {
+        let eh = &mut EventHandler::null();
+        let p = Problem::from_string("A B B B\nC D D D\n\nAB CD\nA A\nC C\nBD BD\nA D").unwrap();
+        let mut expected = p.passive.clone();
+        with_spill_threshold(None, || expected.maximize(eh));
+
+        let mut spilled = p.passive.clone();
+        with_spill_threshold(Some(2), || spilled.maximize_with_limits(&Limits::default(), eh)).unwrap();
+        assert!(spilled.is_maximized);
+        assert_eq!(spilled.canonical_lines(), expected.canonical_lines());
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::HashSet;
+
+use crate::{
+    constraint::Constraint,
+    group::{Group, Label},
+    problem::Problem,
+};
+
+impl Problem {
+    /// Creates k copies of the given label (the label itself is the first copy).
+    /// Each copy can be used wherever the original label can be used, so all the copies are equivalent in the diagram.
+    /// Returns the new problem, and the list of copies.
+    pub fn split_label(&self, label: Label, k: usize) -> Result<(Problem, Vec<Label>), &'static str> {
+        let labels = self.labels();
+        if !labels.contains(&label) {
+            return Err("The label to split is not in the problem");
+        }
+        if k == 0 {
+            return Err("At least one copy is required");
+        }
+
+        let first_new = labels.iter().max().unwrap() + 1;
+        let copies: Vec<Label> = std::iter::once(label)
+            .chain((0..k as Label - 1).map(|i| first_new + i))
+            .collect();
+        let new_labels = &copies[1..];
+
+        let mut p = self.clone();
+        p.discard_computed_stuff();
+        p.active = self.active.split_label(label, new_labels);
+        p.passive = self.passive.split_label(label, new_labels);
+
+        let text = self
+            .mapping_label_text
+            .iter()
+            .find(|(l, _)| *l == label)
+            .map(|(_, s)| s.trim_start_matches('(').trim_end_matches(')').to_string())
+            .unwrap();
+        let mut used: HashSet<String> = self.mapping_label_text.iter().map(|(_, s)| s.clone()).collect();
+        let mut suffix = 1;
+        for &new in new_labels {
+            let name = loop {
+                let candidate = format!("({}_{})", text, suffix);
+                suffix += 1;
+                if !used.contains(&candidate) {
+                    break candidate;
+                }
+            };
+            used.insert(name.clone());
+            p.mapping_label_text.push((new, name));
+        }
+
+        if let Some(v) = p.mapping_label_oldlabels.as_mut() {
+            if let Some((_, old)) = self.mapping_label_oldlabels.as_ref().unwrap().iter().find(|(l, _)| *l == label) {
+                for &new in new_labels {
+                    v.push((new, old.clone()));
+                }
+            }
+        }
+        if let Some(v) = p.mapping_oldlabel_labels.as_mut() {
+            for (_, ls) in v.iter_mut() {
+                if ls.contains(&label) {
+                    ls.extend(new_labels.iter().cloned());
+                }
+            }
+        }
+        if let Some(v) = p.label_priorities.as_mut() {
+            if let Some((_, priority)) = self.label_priorities.as_ref().unwrap().iter().find(|(l, _)| *l == label) {
+                for &new in new_labels {
+                    v.push((new, priority.clone()));
+                }
+            }
+        }
+
+        if let Some(diagram) = self.diagram_indirect.as_ref().filter(|_| !self.is_stale()) {
+            let mut new_diagram = diagram.clone();
+            for &(a, b) in diagram {
+                for &new in new_labels {
+                    if a == label && b != label {
+                        new_diagram.push((new, b));
+                    }
+                    if b == label && a != label {
+                        new_diagram.push((a, new));
+                    }
+                }
+            }
+            for &a in &copies {
+                for &b in &copies {
+                    if a != label || b != label {
+                        new_diagram.push((a, b));
+                    }
+                }
+            }
+            new_diagram.sort_unstable();
+            new_diagram.dedup();
+            p.diagram_indirect = Some(new_diagram);
+            p.compute_direct_diagram();
+            p.mark_computed();
+        }
+
+        Ok((p, copies))
+    }
+}
+
+impl Constraint {
+    pub fn split_label(&self, label: Label, new_labels: &[Label]) -> Self {
+        let mut c = self.edited(|g| {
+            if g.contains(&label) {
+                let mut v = g.0.clone();
+                v.extend(new_labels.iter().cloned());
+                v.sort_unstable();
+                Group(v)
+            } else {
+                g.clone()
+            }
+        });
+        c.is_maximized = self.is_maximized;
+        c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    #[test]
+    fn split() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("A AB AB\n\nB AB").unwrap();
+        p.compute_diagram(eh);
+        let (mut p, copies) = p.split_label(1, 3).unwrap();
+        assert_eq!(copies, vec![1, 2, 3]);
+        assert_eq!(
+            format!("{}", p),
+            "A AB(B_1)(B_2)^2\n\nAB(B_1)(B_2) B(B_1)(B_2)\n"
+        );
+        let diagram = p.diagram_indirect.clone();
+        p.diagram_indirect = None;
+        p.compute_diagram(eh);
+        assert_eq!(p.diagram_indirect, diagram);
+
+        let p = p.merge_equivalent_labels();
+        assert_eq!(format!("{}", p), "A AB^2\n\nAB B\n");
+    }
+}
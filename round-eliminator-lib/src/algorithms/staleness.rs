@@ -0,0 +1,83 @@
+use crate::problem::Problem;
+
+impl Problem {
+    /// Whether something has been computed about the problem, that is, whether any of the fields cleared by `discard_computed_stuff` is set.
+    pub fn has_computed_stuff(&self) -> bool {
+        self.trivial_sets.is_some()
+            || self.coloring_sets.is_some()
+            || self.diagram_indirect.is_some()
+            || self.diagram_direct.is_some()
+            || self.orientation_trivial_sets.is_some()
+            || self.orientation_coloring_sets.is_some()
+            || self.fixpoint_diagram.is_some()
+            || self.fixpoint_procedure_works.is_some()
+            || self.marks_works.is_some()
+    }
+
+    /// Whether the triviality, the coloring solvability, or the diagram may not be valid for the current problem,
+    /// either because the problem has been changed after computing them, or because it is not known for which problem they have been computed.
+    /// The problem is compared through its `canonical_hash`, so reordering lines or parts does not make it stale, while renaming labels does.
+    pub fn is_stale(&self) -> bool {
+        match self.computed_for {
+            Some(h) => h != self.canonical_hash(),
+            None => self.has_computed_stuff(),
+        }
+    }
+
+    /// Records that the computed fields are valid for the current problem.
+    pub(crate) fn mark_computed(&mut self) {
+        self.computed_for = Some(self.canonical_hash());
+    }
+
+    /// Forgets everything that has been computed about the problem if it may not be valid anymore, see `is_stale`.
+    /// Returns whether something has been discarded.
+    pub fn discard_stale_computed_stuff(&mut self) -> bool {
+        if !self.is_stale() {
+            return false;
+        }
+        self.discard_computed_stuff();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, line::Line, problem::Problem};
+
+    #[test]
+    fn staleness() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        assert!(!p.is_stale());
+        p.compute_diagram(eh);
+        p.compute_triviality(eh);
+        let mut reordered = p.clone();
+        reordered.active.lines.reverse();
+        reordered.passive.lines[0].parts.reverse();
+        assert!(!reordered.is_stale());
+
+        // allowing two P on the same edge makes the problem trivial, so the old result must not be trusted
+        let mut mapping = p.mapping_label_text.iter().map(|(l, t)| (t.clone(), *l)).collect();
+        p.passive.lines.push(Line::parse("P P", &mut mapping).unwrap());
+        assert!(p.is_stale());
+        p.compute_triviality(eh);
+        assert!(!p.trivial_sets.as_ref().unwrap().is_empty());
+        assert!(!p.is_stale());
+        assert!(p.diagram_indirect.is_none());
+        assert!(!p.discard_stale_computed_stuff());
+
+        // renaming keeps what has been computed, since it refers to labels and not to their names
+        let names: Vec<_> = p.mapping_label_text.iter().map(|(l, t)| (*l, format!("{}{}", t, t))).collect();
+        p.rename(&names).unwrap();
+        assert!(!p.is_stale());
+        assert!(p.trivial_sets.is_some());
+
+        // results that are not known to be computed for this problem are not trusted
+        let mut q = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        q.trivial_sets = Some(vec![]);
+        assert!(q.is_stale());
+        assert!(q.discard_stale_computed_stuff());
+        assert!(q.trivial_sets.is_none() && !q.is_stale());
+    }
+}
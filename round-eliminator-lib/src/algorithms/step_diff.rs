@@ -0,0 +1,120 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{group::Label, problem::Problem, serial::AutoOperation};
+
+/// What changed between two consecutive problems of a sequence produced by autoub or autolb.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct StepDiff {
+    /// Whether the labels of the new problem are unrelated to the previous ones, as it happens after a speedup.
+    pub new_labels: bool,
+    pub labels_before: usize,
+    pub labels_after: usize,
+    /// The set of labels that has been kept by a hardening.
+    pub kept: Option<Vec<Label>>,
+    /// Labels of the previous problem that do not appear in the new one.
+    pub removed: Vec<Label>,
+    pub merged: Vec<(Label, Label)>,
+    /// Arrows of the new diagram, between labels that appear in both problems, that were not in the previous diagram.
+    pub arrows_added: Vec<(Label, Label)>,
+}
+
+impl StepDiff {
+    pub fn new(prev: Option<&Problem>, operation: &AutoOperation, next: &Problem) -> Self {
+        let next_labels = used_labels(next);
+        let prev = match (prev, operation) {
+            (Some(prev), AutoOperation::Harden(_)) | (Some(prev), AutoOperation::Merge(..)) => prev,
+            (prev, _) => {
+                return StepDiff {
+                    new_labels: true,
+                    labels_before: prev.map_or(0, |p| used_labels(p).len()),
+                    labels_after: next_labels.len(),
+                    ..Default::default()
+                }
+            }
+        };
+
+        let prev_labels = used_labels(prev);
+        let next_set: HashSet<Label> = next_labels.iter().cloned().collect();
+        let removed = prev_labels.iter().cloned().filter(|l| !next_set.contains(l)).collect();
+
+        let arrows_added = match (&prev.diagram_indirect, &next.diagram_indirect) {
+            (Some(before), Some(after)) => {
+                let before: HashSet<_> = before.iter().cloned().collect();
+                let prev_set: HashSet<Label> = prev_labels.iter().cloned().collect();
+                after
+                    .iter()
+                    .cloned()
+                    .filter(|&(a, b)| a != b && prev_set.contains(&a) && prev_set.contains(&b) && !before.contains(&(a, b)))
+                    .collect()
+            }
+            _ => vec![],
+        };
+
+        StepDiff {
+            new_labels: false,
+            labels_before: prev_labels.len(),
+            labels_after: next_labels.len(),
+            kept: match operation {
+                AutoOperation::Harden(kept) => Some(kept.clone()),
+                _ => None,
+            },
+            removed,
+            merged: match operation {
+                AutoOperation::Merge(merged, _) => merged.clone(),
+                _ => vec![],
+            },
+            arrows_added,
+        }
+    }
+
+    /// Returns the diff of each step of the sequence with respect to the previous one.
+    pub fn of_sequence(sequence: &[(AutoOperation, Problem)]) -> Vec<StepDiff> {
+        sequence
+            .iter()
+            .enumerate()
+            .map(|(i, (operation, p))| {
+                let prev = if i == 0 { None } else { Some(&sequence[i - 1].1) };
+                StepDiff::new(prev, operation, p)
+            })
+            .collect()
+    }
+}
+
+fn used_labels(p: &Problem) -> Vec<Label> {
+    let mut labels: Vec<Label> = p.active.labels_appearing().union(&p.passive.labels_appearing()).cloned().collect();
+    labels.sort_unstable();
+    labels
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem, serial::AutoOperation};
+
+    use super::StepDiff;
+
+    #[test]
+    fn step_diff() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("A B C\n\nA BC\nB C").unwrap();
+        p.compute_diagram(eh);
+        let mut merged = p.relax_merge(0, 1);
+        merged.compute_diagram(eh);
+        let mut hardened = merged.harden_keep(&[1, 2].into_iter().collect(), false);
+        hardened.compute_diagram(eh);
+
+        let sequence = vec![
+            (AutoOperation::Initial, p.clone()),
+            (AutoOperation::Merge(vec![(0, 1)], p), merged),
+            (AutoOperation::Harden(vec![1, 2]), hardened),
+        ];
+        let diffs = StepDiff::of_sequence(&sequence);
+        assert!(diffs[0].new_labels);
+        assert_eq!(diffs[1].merged, vec![(0, 1)]);
+        assert_eq!(diffs[1].removed, vec![0]);
+        assert_eq!(diffs[2].kept, Some(vec![1, 2]));
+        assert_eq!((diffs[2].labels_before, diffs[2].labels_after), (2, 2));
+    }
+}
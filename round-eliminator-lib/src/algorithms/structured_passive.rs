@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use crate::{
+    constraint::Constraint,
+    group::{Group, GroupType, Label},
+    line::{Degree, Line},
+    part::Part,
+};
+
+/// Above this many labels with a forbidden partner, the closed form is not used, since the result would be too large anyway.
+const MAX_PARTNERED: usize = 20;
+
+fn pair(a: &[Label], b: &[Label]) -> Line {
+    let mut line = Line {
+        parts: vec![
+            Part { gtype: GroupType::ONE, group: Group(a.to_vec()) },
+            Part { gtype: GroupType::ONE, group: Group(b.to_vec()) },
+        ],
+    };
+    line.normalize();
+    if line.parts.len() == 2 && line.parts[0].group == line.parts[1].group {
+        line = Line { parts: vec![Part { gtype: GroupType::Many(2), group: line.parts[0].group.clone() }] };
+    }
+    line
+}
+
+/// The line whose parts are the given groups, repeated the given number of times.
+fn line_of(parts: Vec<(&[Label], usize)>) -> Line {
+    let mut line = Line {
+        parts: parts
+            .into_iter()
+            .filter(|&(group, count)| count > 0 && !group.is_empty())
+            .map(|(group, count)| Part { gtype: GroupType::Many(count as _), group: Group(group.to_vec()) })
+            .collect(),
+    };
+    line.normalize();
+    line
+}
+
+impl Constraint {
+    /// If the constraint allows exactly the configurations with at most t labels from a set H, returns its maximized form, which is the single line
+    /// with t ports allowing all the labels and the other ports allowing the labels that are not in H.
+    /// Allowing at least t labels from a set is the same as allowing at most d-t labels from the other labels.
+    /// The labels not in H are the ones that can appear on all the ports, and t is the largest number of labels from H that a line allows.
+    /// Unless the degree is 2 or the constraint is maximized, the configurations with k labels from H, for each k up to t,
+    /// must all be allowed by a single line, otherwise the constraint is not recognized.
+    fn maximize_threshold(&self, d: usize, labels: &[Label]) -> Option<Line> {
+        let free: Vec<Label> = labels
+            .iter()
+            .cloned()
+            .filter(|&l| self.lines.iter().any(|line| line.includes(&line_of(vec![(&[l], d)]))))
+            .collect();
+        let heavy: Vec<Label> = labels.iter().cloned().filter(|l| !free.contains(l)).collect();
+        if free.is_empty() || heavy.is_empty() {
+            return None;
+        }
+        let threshold = self
+            .lines
+            .iter()
+            .map(|line| {
+                line.parts
+                    .iter()
+                    .filter(|part| part.group.iter().any(|l| heavy.contains(l)))
+                    .map(|part| match part.gtype {
+                        GroupType::Many(x) => x as usize,
+                        GroupType::Star => d,
+                    })
+                    .sum::<usize>()
+            })
+            .max()?
+            .min(d);
+        let covered = (0..=threshold).all(|k| {
+            let configurations = line_of(vec![(&heavy, k), (&free, d - k)]);
+            if d == 2 || self.is_maximized {
+                self.includes(&configurations)
+            } else {
+                self.lines.iter().any(|line| line.includes(&configurations))
+            }
+        });
+        covered.then(|| line_of(vec![(labels, threshold), (&free, d - threshold)]))
+    }
+
+    /// If the constraint allows all configurations, or at most some number of labels from a set, see `maximize_threshold`,
+    /// or if its degree is 2 and every label is forbidden together with at most one label
+    /// (possibly itself, as in coloring-like constraints), returns the maximized constraint, computed directly.
+    pub(crate) fn maximize_structured(&self) -> Option<Constraint> {
+        let Degree::Finite(d) = self.degree else {
+            return None;
+        };
+        let labels: Vec<Label> = self.labels_appearing().into_iter().sorted().collect();
+        let maximized = |lines| Constraint { lines, is_maximized: true, degree: self.degree };
+
+        let complete = self
+            .lines
+            .iter()
+            .any(|line| line.parts.len() == 1 && line.parts[0].group.0 == labels && line.parts[0].gtype == GroupType::Many(d as _));
+        if complete {
+            return Some(maximized(vec![Line { parts: vec![Part { gtype: GroupType::Many(d as _), group: Group(labels) }] }]));
+        }
+        if let Some(line) = self.maximize_threshold(d, &labels) {
+            return Some(maximized(vec![line]));
+        }
+        if d != 2 {
+            return None;
+        }
+
+        let mut partner: HashMap<Label, Label> = HashMap::new();
+        for (i, &a) in labels.iter().enumerate() {
+            for &b in &labels[i..] {
+                if !self.lines.iter().any(|line| line.includes(&pair(&[a], &[b]))) {
+                    if partner.contains_key(&a) || partner.contains_key(&b) {
+                        return None;
+                    }
+                    partner.insert(a, b);
+                    partner.insert(b, a);
+                }
+            }
+        }
+        if partner.len() > MAX_PARTNERED {
+            return None;
+        }
+
+        // the maximal lines are the pairs (S,T) where, independently for each forbidden pair {a,b}, either both are in S,
+        // or both are in T, or one of them is in both sets and the other in none; a label forbidden with itself is in exactly one set
+        let free: Vec<Label> = labels.iter().cloned().filter(|l| !partner.contains_key(l)).collect();
+        let components: Vec<Vec<(Vec<Label>, Vec<Label>)>> = labels
+            .iter()
+            .filter(|l| partner.get(l).is_some_and(|p| p >= l))
+            .map(|&a| {
+                let b = partner[&a];
+                if a == b {
+                    vec![(vec![a], vec![]), (vec![], vec![a])]
+                } else {
+                    vec![(vec![a, b], vec![]), (vec![], vec![a, b]), (vec![a], vec![a]), (vec![b], vec![b])]
+                }
+            })
+            .collect();
+
+        let mut lines = vec![];
+        for choice in components.into_iter().multi_cartesian_product() {
+            let mut s = free.clone();
+            let mut t = free.clone();
+            for (cs, ct) in choice {
+                s.extend(cs);
+                t.extend(ct);
+            }
+            if s.is_empty() || t.is_empty() {
+                continue;
+            }
+            s.sort_unstable();
+            t.sort_unstable();
+            lines.push(pair(&s, &t));
+        }
+        if lines.is_empty() && !free.is_empty() {
+            // no label has a forbidden partner
+            lines.push(pair(&free, &free));
+        }
+        Some(maximized(lines.into_iter().unique().collect()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    #[test]
+    fn structured_passive() {
+        let eh = &mut EventHandler::null();
+        for text in [
+            "A A\nB B\nC C\nD D\n\nA B\nA C\nA D\nB C\nB D\nC D",
+            "A A\n\nA A\nA C\nA D\nB B\nB C\nB D\nC D\nD D",
+            "A A A\n\nABC ABC ABC",
+            // at most one A
+            "A A A\n\nB B B\nA B B",
+            // at least two H
+            "A A A\n\nH H H\nH H A",
+            // at most one of A, B, C, where every one of them has more than one forbidden partner
+            "A A\n\nX X\nX A\nX B\nX C",
+            "A A A A\n\nX X X X\nAB X X X\nAB AB X X",
+        ] {
+            let p = Problem::from_string(text).unwrap();
+            let fast = p.passive.maximize_structured().unwrap();
+            let mut slow = p.passive.clone();
+            slow.maximize(eh);
+            assert_eq!(fast.canonical_lines(), slow.canonical_lines());
+        }
+        let p = Problem::from_string("A A\n\nA B\nC D").unwrap();
+        assert!(p.passive.maximize_structured().is_none());
+        // at most one A, but the configurations with one A are split among two lines
+        let p = Problem::from_string("A A A\n\nB B B\nC C C\nBC BC BC\nA B BC\nA C BC").unwrap();
+        assert!(p.passive.maximize_structured().is_none());
+    }
+}
@@ -0,0 +1,66 @@
+use crate::{problem::Problem, serial::AutoOperation};
+
+impl Problem {
+    /// The problem where the active and passive sides are exchanged.
+    /// A node of one side can compute the output of a neighbor of the other side with one additional round,
+    /// so the complexities of the two problems differ by at most one round.
+    pub fn swap_sides(&self) -> Self {
+        Problem {
+            active: self.passive.clone(),
+            passive: self.active.clone(),
+            mapping_label_text: self.mapping_label_text.clone(),
+            mapping_label_oldlabels: None,
+            mapping_oldlabel_labels: None,
+            mapping_oldlabel_text: None,
+            trivial_sets: None,
+            coloring_sets: None,
+            diagram_indirect: None,
+            diagram_direct: None,
+            diagram_indirect_old: None,
+            orientation_coloring_sets: None,
+            orientation_trivial_sets: None,
+            orientation_given: None,
+            fixpoint_diagram : None,
+            fixpoint_procedure_works : None,
+            marks_works : None,
+            label_priorities : None,
+            degree_lifts : None,
+            annotations : None,
+            provenance : self.provenance.clone(),
+            line_origins : self.line_origins_swapped(),
+            computed_for : None,
+            label_folds : None,
+            color_labels : self.color_labels.clone(),
+            one_sided_labels : self.one_sided_labels_swapped()
+        }
+    }
+
+    /// Turns a sequence of operations that starts from the side-swapped version of this problem into one that starts from this problem.
+    pub(crate) fn sequence_from_swapped(&self, mut sequence: Vec<(AutoOperation, Problem)>) -> Vec<(AutoOperation, Problem)> {
+        if let Some(first) = sequence.first_mut() {
+            first.0 = AutoOperation::SwapSides;
+        }
+        sequence.insert(0, (AutoOperation::Initial, self.clone()));
+        sequence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, line::Degree, problem::Problem};
+
+    #[test]
+    fn swap_sides() {
+        let p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        let q = p.swap_sides();
+        assert_eq!(q.active.degree, Degree::Finite(2));
+        assert_eq!(q.passive.degree, Degree::Finite(3));
+        assert_eq!(q.swap_sides(), p);
+
+        // maximal matching is not trivial from either side
+        let mut q = q;
+        q.compute_triviality(&mut EventHandler::null());
+        assert!(q.trivial_sets.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{problem::Problem, serial::AutoOperation};
+
+use super::decision_diagram::DecisionDiagram;
+
+/// The numbers that describe an upper bound found by autoub, so that upper bounds can be compared without reading the sequence.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct UpperBoundSummary {
+    /// The number of rounds of the algorithm, as reported by autoub.
+    pub rounds: usize,
+    /// Whether the algorithm requires a proper coloring given as input.
+    pub given_coloring: bool,
+    /// The number of labels of the initial problem and of the problem obtained after each speedup and the hardening that follows it.
+    pub labels_per_step: Vec<usize>,
+    /// The number of labels and edges of the algorithm, described as a `DecisionDiagram`, or None if it cannot be extracted.
+    pub algorithm_labels: Option<usize>,
+    pub algorithm_edges: Option<usize>,
+}
+
+impl UpperBoundSummary {
+    /// The summary of a sequence of `len` rounds reported by autoub, where `is_trivial` tells whether the last problem
+    /// is zero round solvable without a coloring.
+    pub fn of_sequence(len: usize, is_trivial: bool, sequence: &[(AutoOperation, Problem)]) -> Self {
+        let mut labels_per_step = vec![];
+        for (op, p) in sequence {
+            match op {
+                AutoOperation::Initial | AutoOperation::Speedup => labels_per_step.push(p.labels().len()),
+                _ => {
+                    if let Some(last) = labels_per_step.last_mut() {
+                        *last = p.labels().len();
+                    }
+                }
+            }
+        }
+        let diagram = DecisionDiagram::from_upper_bound(sequence).ok();
+        UpperBoundSummary {
+            rounds: len,
+            given_coloring: !is_trivial,
+            labels_per_step,
+            algorithm_labels: diagram.as_ref().map(|d| d.levels.iter().map(|level| level.len()).sum()),
+            algorithm_edges: diagram.as_ref().map(|d| d.edges.len()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::{autoub::SearchStats, event::EventHandler}, problem::Problem};
+
+    use super::UpperBoundSummary;
+
+    #[test]
+    fn upper_bound_summary() {
+        let eh = &mut EventHandler::null();
+        let p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        let mut found = None;
+        p.autoub(5, 3, 4, None, None, Default::default(), &mut SearchStats::default(), |len, is_trivial, sequence| {
+            found = Some(UpperBoundSummary::of_sequence(len, is_trivial, &sequence));
+        }, eh);
+        let summary = found.unwrap();
+        assert!(!summary.given_coloring);
+        assert_eq!(summary.labels_per_step.len(), summary.rounds + 1);
+        assert!(summary.labels_per_step.iter().all(|&n| n <= 5));
+        assert!(summary.algorithm_labels.unwrap() > summary.rounds);
+        assert!(summary.algorithm_edges.unwrap() >= summary.rounds);
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{constraint::Constraint, group::Label};
+
+/// Predicts whether maximizing a constraint, for example during a speedup, is going to produce too many lines, by looking at how many
+/// lines have been obtained so far from the pairs of lines combined so far. It is given as part of `Limits`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Watchdog {
+    /// A `BlowupWarning` is sent when the predicted number of lines exceeds this value.
+    pub max_predicted_lines: usize,
+    /// Whether to stop with `Limit::Blowup` after sending the warning.
+    pub abort: bool,
+}
+
+/// Sent through the `EventHandler` when the `Watchdog` predicts a blowup, with some ways to avoid it computed from the lines obtained so far.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BlowupWarning {
+    pub phase: String,
+    pub lines: usize,
+    /// How many pairs of lines of the current round of combinations have been combined, out of `total_pairs`.
+    pub processed_pairs: usize,
+    pub total_pairs: usize,
+    pub predicted_lines: usize,
+    /// The labels that appear in the fewest lines, that can be removed by hardening without losing much.
+    pub rarely_used: Vec<Label>,
+    /// Groups of labels that appear in exactly the same parts of the lines, that can be merged.
+    pub equivalent: Vec<Vec<Label>>,
+}
+
+/// How many rarely used labels are suggested for hardening.
+const SUGGESTED_HARDENINGS: usize = 3;
+
+/// The number of lines expected at the end of the current round, if `lines` have been obtained from a round that started with
+/// `start` lines, after combining `processed` of its `total` pairs. If the round is complete, the number of lines of the next round
+/// is predicted, assuming that it grows by the same factor. Returns None if too few pairs have been combined to tell.
+pub(crate) fn predicted_lines(start: usize, lines: usize, processed: usize, total: usize) -> Option<usize> {
+    if processed == 0 || processed * 20 < total {
+        return None;
+    }
+    let grown = lines.saturating_sub(start);
+    if processed < total {
+        Some(start + grown * total / processed)
+    } else {
+        Some(lines + grown * lines / start.max(1))
+    }
+}
+
+/// The mitigations reported in a `BlowupWarning`: the labels used in the fewest lines, and the groups of labels with the same occurrences.
+pub(crate) fn mitigations(c: &Constraint) -> (Vec<Label>, Vec<Vec<Label>>) {
+    let mut occurrences: HashMap<Label, Vec<(usize, usize)>> = HashMap::new();
+    for (i, line) in c.lines.iter().enumerate() {
+        for (j, part) in line.parts.iter().enumerate() {
+            for &l in part.group.iter() {
+                occurrences.entry(l).or_default().push((i, j));
+            }
+        }
+    }
+    let rarely_used = occurrences
+        .iter()
+        .map(|(&l, v)| (v.iter().map(|(i, _)| i).dedup().count(), l))
+        .sorted()
+        .take(SUGGESTED_HARDENINGS)
+        .map(|(_, l)| l)
+        .collect();
+    let mut groups: HashMap<Vec<(usize, usize)>, Vec<Label>> = HashMap::new();
+    for (l, v) in occurrences {
+        groups.entry(v).or_default().push(l);
+    }
+    let equivalent = groups
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(|group| group.into_iter().sorted().collect())
+        .sorted()
+        .collect();
+    (rarely_used, equivalent)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::cell::RefCell;
+
+    use crate::{
+        algorithms::{
+            event::EventHandler,
+            limits::{Limit, Limits},
+            spill::with_spill_threshold,
+        },
+        problem::Problem,
+    };
+
+    use super::{mitigations, predicted_lines, Watchdog};
+
+    #[test]
+    fn watchdog() {
+        assert_eq!(predicted_lines(10, 30, 1, 55), None);
+        assert_eq!(predicted_lines(10, 30, 11, 55), Some(110));
+        assert_eq!(predicted_lines(10, 30, 55, 55), Some(90));
+
+        let p = Problem::from_string("A BC BC\nA A A\n\nA B\nA C").unwrap();
+        let (rarely_used, equivalent) = mitigations(&p.active);
+        assert_eq!(rarely_used, vec![1, 2, 0]);
+        assert_eq!(equivalent, vec![vec![1, 2]]);
+
+        let p = Problem::from_string("A B C\n\nA B C\nB C C\nA A C").unwrap();
+        let warnings = RefCell::new(vec![]);
+        let mut eh = EventHandler::null().with_warnings(|w| warnings.borrow_mut().push(w));
+        let watchdog = Watchdog { max_predicted_lines: 1, abort: false };
+        let limits = Limits { watchdog: Some(watchdog), ..Default::default() };
+        let sped = p.speedup_with_limits(&limits, &mut eh).unwrap();
+        drop(eh);
+        assert_eq!(sped.to_string(), p.speedup(&mut EventHandler::null()).to_string());
+        let warnings = warnings.into_inner();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].predicted_lines > 1);
+
+        let limits = Limits { watchdog: Some(Watchdog { abort: true, ..watchdog }), ..Default::default() };
+        let e = p.speedup_with_limits(&limits, &mut EventHandler::null()).unwrap_err();
+        assert!(matches!(e.limit, Limit::Blowup(n) if n > 1));
+
+        // when the candidates are spilled to disk, the watchdog stops the first round of combinations
+        let e = with_spill_threshold(Some(1), || p.speedup_with_limits(&limits, &mut EventHandler::null())).unwrap_err();
+        assert!(matches!(e.limit, Limit::Blowup(n) if n > 1));
+        assert_eq!((e.phase.as_str(), e.iterations), ("maximize", 0));
+    }
+}
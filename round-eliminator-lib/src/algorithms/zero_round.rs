@@ -0,0 +1,85 @@
+use itertools::Itertools;
+
+use crate::{
+    group::{Group, GroupType, Exponent},
+    line::{Degree, Line},
+    part::Part,
+    problem::Problem,
+};
+
+impl Problem {
+    fn allows_on_passive_side(&self, parts: Vec<Part>) -> bool {
+        self.passive.includes(&Line { parts })
+    }
+
+    /// All the configurations of the active side that give a zero round algorithm, where every node outputs the same configuration
+    /// in an arbitrary order. These are the configurations whose labels can be combined arbitrarily on the passive side,
+    /// and each trivial set computed by `compute_triviality` is contained in the set of labels of one of them.
+    /// The passive side must be maximized if its degree is not 2, and the active side must not contain stars.
+    pub fn zero_round_algorithms(&self) -> impl Iterator<Item = Line> + '_ {
+        let passive_degree = match self.passive.degree {
+            Degree::Finite(x) => GroupType::Many(x as Exponent),
+            Degree::Star => GroupType::Star,
+        };
+        self.active.all_choices(true).into_iter().filter(move |line| {
+            self.allows_on_passive_side(vec![Part {
+                gtype: passive_degree,
+                group: line.line_set(),
+            }])
+        })
+    }
+
+    /// All the ways of solving the problem in zero rounds given a proper `colors`-coloring of the nodes, as in `compute_coloring_solvability`:
+    /// each item contains the configuration output by the nodes of each color, and the configurations of different colors
+    /// must be compatible on the passive side. Assignments that only differ by a permutation of the colors are returned once.
+    pub fn zero_round_colorings(&self, colors: usize) -> Result<impl Iterator<Item = Vec<Line>> + '_, &'static str> {
+        if self.passive.degree != Degree::Finite(2) {
+            return Err("Enumerating zero round algorithms given a coloring requires the passive side to have degree 2");
+        }
+        let configurations = self.active.all_choices(true);
+        let sets: Vec<Group> = configurations.iter().map(|line| line.line_set()).collect();
+        let compatible: Vec<Vec<bool>> = sets
+            .iter()
+            .map(|s1| {
+                sets.iter()
+                    .map(|s2| {
+                        self.allows_on_passive_side(vec![
+                            Part { gtype: GroupType::ONE, group: s1.clone() },
+                            Part { gtype: GroupType::ONE, group: s2.clone() },
+                        ])
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Ok((0..configurations.len())
+            .combinations(colors)
+            .filter(move |choice| choice.iter().enumerate().all(|(a, &i)| choice[a + 1..].iter().all(|&j| compatible[i][j])))
+            .map(move |choice| choice.into_iter().map(|i| configurations[i].clone()).collect()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    #[test]
+    fn zero_round() {
+        let eh = &mut EventHandler::null();
+        let mut p = Problem::from_string("A AB AB\n\nA A\nB B").unwrap();
+        p.passive.maximize(eh);
+        let algorithms: Vec<_> = p.zero_round_algorithms().map(|line| line.to_string(&p.mapping_label_text.iter().cloned().collect())).collect();
+        assert_eq!(algorithms, vec!["A^3".to_string()]);
+
+        let mut p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        p.passive.maximize(eh);
+        assert_eq!(p.zero_round_algorithms().count(), 0);
+
+        // given a 3-coloring, the nodes of each color output a different color
+        let p = Problem::from_string("A A\nB B\nC C\n\nA BC\nB C").unwrap();
+        assert_eq!(p.zero_round_colorings(3).unwrap().count(), 1);
+        assert_eq!(p.zero_round_colorings(2).unwrap().count(), 3);
+        assert_eq!(p.zero_round_colorings(4).unwrap().count(), 0);
+    }
+}
@@ -3,6 +3,7 @@ use std::collections::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    algorithms::line_index::maximal_lines,
     group::{Group, GroupType, Label},
     line::{Degree, Line},
     part::Part,
@@ -34,8 +35,11 @@ impl Constraint {
         );
     }
 
+    /// Same as `discard_non_maximal_lines_with_custom_supersets` with the usual inclusion, but lines are compared only with the lines
+    /// whose labels are a subset or a superset of their labels, found with a `LineIndex`, see `MaximalLines`.
     pub fn discard_non_maximal_lines(&mut self) {
-        self.discard_non_maximal_lines_with_custom_supersets(None::<fn(&'_ _, &'_ _) -> _>)
+        self.is_maximized = false;
+        self.lines = maximal_lines(std::mem::take(&mut self.lines));
     }
 
 
@@ -78,10 +82,10 @@ impl Constraint {
         mapping: &mut HashMap<String, Label>,
     ) -> Result<Constraint, &'static str> {
         let text = text.as_ref();
-        let lines: Vec<_> = text
-            .lines()
-            .map(|l| Line::parse(l, mapping))
-            .collect::<Result<_, _>>()?;
+        let mut lines = vec![];
+        for l in text.lines() {
+            lines.extend(Line::parse_with_counting(l, mapping)?);
+        }
         if lines.is_empty() {
             return Err("Empty constraint");
         }
@@ -95,9 +99,11 @@ impl Constraint {
             degree,
         };
         constraint.discard_non_maximal_lines();
+        constraint.canonicalize();
         Ok(constraint)
     }
 
+    /// Whether all the configurations of `other` are allowed. The constraint must be maximized, unless its degree is 2.
     pub fn includes(&self, other: &Line) -> bool {
         if !self.is_maximized && self.degree != Degree::Finite(2) {
             panic!("this should not happen");
@@ -200,6 +206,7 @@ impl Constraint {
         true
     }
 
+    /// The labels used in the constraint.
     pub fn labels_appearing(&self) -> HashSet<Label> {
         let mut h = HashSet::new();
         for group in self.groups() {
@@ -0,0 +1,219 @@
+//! Systematic enumerations of small problems, for surveys that classify all problems with a given number of labels and given degrees.
+//! A problem is encoded by the number of labels, the two degrees, and two bitmasks,
+//! where bit i of a mask tells whether the i-th multiset of labels, in lexicographic order, is allowed on that side.
+
+use std::io::BufRead;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{algorithms::event::EventHandler, problem::Problem, serial::fix_problem};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct CorpusEntry {
+    pub labels: usize,
+    pub active_degree: usize,
+    pub passive_degree: usize,
+    pub active_mask: u64,
+    pub passive_mask: u64,
+}
+
+/// All multisets of the given size of the first `labels` labels, each one sorted, in lexicographic order.
+fn multisets(labels: usize, size: usize) -> Vec<Vec<usize>> {
+    if size == 0 {
+        return vec![vec![]];
+    }
+    let mut result = vec![];
+    for first in 0..labels {
+        for rest in multisets(labels - first, size - 1) {
+            result.push(std::iter::once(first).chain(rest.into_iter().map(|l| l + first)).collect());
+        }
+    }
+    result
+}
+
+fn label_name(l: usize) -> String {
+    ((b'A' + l as u8) as char).to_string()
+}
+
+fn side_text(labels: usize, degree: usize, mask: u64) -> String {
+    multisets(labels, degree)
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| mask & (1 << i) != 0)
+        .map(|(_, m)| m.into_iter().map(label_name).collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The number of bits needed to encode a side, or an error if they do not fit in a mask.
+fn mask_bits(labels: usize, degree: usize) -> Result<usize, &'static str> {
+    if labels == 0 || labels > 26 || degree == 0 {
+        return Err("The corpus supports from 1 to 26 labels, and positive degrees");
+    }
+    // the number of multisets is C(labels + degree - 1, labels - 1), computed as C(degree + i, i) for increasing i,
+    // which never decreases, so that it can be rejected as soon as it exceeds 64, without enumerating the multisets
+    let too_many = "There are too many configurations to be encoded in a mask";
+    let mut bits = 1usize;
+    for i in 1..labels {
+        bits = degree.checked_add(i).and_then(|n| bits.checked_mul(n)).ok_or(too_many)? / i;
+        if bits > 64 {
+            return Err(too_many);
+        }
+    }
+    Ok(bits)
+}
+
+impl CorpusEntry {
+    /// Parses a line containing the number of labels, the active degree, the passive degree, the active mask, and the passive mask.
+    pub fn parse(line: &str) -> Result<Self, &'static str> {
+        let numbers: Vec<u64> = line
+            .split_whitespace()
+            .map(|x| x.parse::<u64>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| "The corpus contains a line that is not a sequence of numbers")?;
+        let [labels, active_degree, passive_degree, active_mask, passive_mask] = numbers[..] else {
+            return Err("Each line of the corpus must contain exactly five numbers");
+        };
+        let entry = CorpusEntry {
+            labels: labels as usize,
+            active_degree: active_degree as usize,
+            passive_degree: passive_degree as usize,
+            active_mask,
+            passive_mask,
+        };
+        let active_bits = mask_bits(entry.labels, entry.active_degree)?;
+        let passive_bits = mask_bits(entry.labels, entry.passive_degree)?;
+        let fits = |mask: u64, bits: usize| bits == 64 || mask >> bits == 0;
+        if !fits(active_mask, active_bits) || !fits(passive_mask, passive_bits) {
+            return Err("A mask has more bits than the number of configurations");
+        }
+        Ok(entry)
+    }
+
+    pub fn problem(&self) -> Result<Problem, &'static str> {
+        Problem::from_string_active_passive(
+            side_text(self.labels, self.active_degree, self.active_mask),
+            side_text(self.labels, self.passive_degree, self.passive_mask),
+        )
+    }
+}
+
+impl std::fmt::Display for CorpusEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} {} {} {}",
+            self.labels, self.active_degree, self.passive_degree, self.active_mask, self.passive_mask
+        )
+    }
+}
+
+/// All the problems with the given number of labels and degrees, where both sides contain at least one configuration.
+pub fn all_entries(labels: usize, active_degree: usize, passive_degree: usize) -> Result<impl Iterator<Item = CorpusEntry>, &'static str> {
+    let active_bits = mask_bits(labels, active_degree)?;
+    let passive_bits = mask_bits(labels, passive_degree)?;
+    if active_bits + passive_bits > 40 {
+        return Err("There are too many problems to enumerate");
+    }
+    Ok((1u64..1 << active_bits).flat_map(move |active_mask| {
+        (1u64..1 << passive_bits).map(move |passive_mask| CorpusEntry {
+            labels,
+            active_degree,
+            passive_degree,
+            active_mask,
+            passive_mask,
+        })
+    }))
+}
+
+/// Reads a corpus, one entry per line, skipping empty lines and lines starting with `#`.
+pub fn read_corpus<R: BufRead>(reader: R) -> impl Iterator<Item = Result<CorpusEntry, &'static str>> {
+    reader.lines().filter_map(|line| match line {
+        Ok(line) if line.trim().is_empty() || line.starts_with('#') => None,
+        Ok(line) => Some(CorpusEntry::parse(&line)),
+        Err(_) => Some(Err("Cannot read the corpus")),
+    })
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Budget {
+    pub max_steps: usize,
+    pub max_labels: usize,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Classification {
+    /// The problem can be solved in exactly this many rounds.
+    Rounds(usize),
+    /// The problem requires at least this many rounds, and the budget did not allow to say more.
+    AtLeast(usize),
+    /// After some speedups a side has no configurations left, so the problem cannot be solved in any number of rounds.
+    Unsolvable,
+}
+
+/// Classifies a problem by applying speedups until it becomes zero round solvable, or until the budget is exhausted.
+pub fn classify(p: &Problem, budget: Budget, eh: &mut EventHandler) -> Classification {
+    let mut p = p.clone();
+    fix_problem(&mut p, true, false, eh);
+    for step in 0.. {
+        if p.active.lines.is_empty() || p.passive.lines.is_empty() {
+            return Classification::Unsolvable;
+        }
+        if p.trivial_sets.is_none() {
+            p.compute_triviality(eh);
+        }
+        if !p.trivial_sets.as_ref().unwrap().is_empty() {
+            return Classification::Rounds(step);
+        }
+        if step == budget.max_steps || p.labels().len() > budget.max_labels {
+            return Classification::AtLeast(step + 1);
+        }
+        p = p.speedup(eh);
+        fix_problem(&mut p, true, false, eh);
+    }
+    unreachable!()
+}
+
+/// Classifies all the entries, calling `handler` as soon as each result is available.
+pub fn classify_corpus<I, F>(entries: I, budget: Budget, mut handler: F, eh: &mut EventHandler)
+where
+    I: Iterator<Item = Result<CorpusEntry, &'static str>>,
+    F: FnMut(Result<CorpusEntry, &'static str>, Result<Classification, &'static str>),
+{
+    for entry in entries {
+        let result = entry.and_then(|e| e.problem()).map(|p| classify(&p, budget, eh));
+        handler(entry, result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::algorithms::event::EventHandler;
+
+    use super::{all_entries, classify_corpus, mask_bits, multisets, read_corpus, Budget, Classification, CorpusEntry};
+
+    #[test]
+    fn corpus() {
+        let eh = &mut EventHandler::null();
+        // the configurations of degree 2 are A A, A B, B B
+        let entry = CorpusEntry::parse("2 2 2 1 2").unwrap();
+        assert_eq!(format!("{}", entry.problem().unwrap()), "A^2\n\nA B\n");
+        assert!(CorpusEntry::parse("2 2 2 8 1").is_err());
+        assert_eq!(all_entries(2, 2, 2).unwrap().count(), 49);
+        for (labels, degree) in [(1, 5), (2, 63), (3, 9), (4, 5), (8, 2)] {
+            assert_eq!(mask_bits(labels, degree), Ok(multisets(labels, degree).len()));
+        }
+        assert!(mask_bits(2, 64).is_err());
+        assert!(mask_bits(26, usize::MAX).is_err());
+
+        let text = "# trivial, and not solvable\n2 2 2 1 1\n\n2 2 2 1 2\n1 2 x\n";
+        let budget = Budget { max_steps: 3, max_labels: 10 };
+        let mut results = vec![];
+        classify_corpus(read_corpus(text.as_bytes()), budget, |_, c| results.push(c), eh);
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0], Ok(Classification::Rounds(0)));
+        assert_eq!(results[1], Ok(Classification::Unsolvable));
+        assert!(results[2].is_err());
+    }
+}
@@ -0,0 +1,164 @@
+//! The records stored in a database of classified problems, and the queries that select them.
+//! The storage itself is provided by the cli.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corpus::Classification,
+    problem::Problem,
+    store::{problem_id, ProblemId},
+};
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ProblemRecord {
+    /// The canonical hash of the problem, see `store::problem_id`.
+    pub hash: ProblemId,
+    pub text: String,
+    pub labels: usize,
+    pub active_degree: String,
+    pub passive_degree: String,
+    /// Whether the problem is zero round solvable, if known.
+    pub trivial: Option<bool>,
+    pub unsolvable: bool,
+    /// The best known lower bound on the number of rounds.
+    pub lower_bound: Option<usize>,
+    /// The best known upper bound on the number of rounds.
+    pub upper_bound: Option<usize>,
+    /// Where the problem comes from, for example a file, a corpus entry, or the hash of the problem it has been derived from.
+    pub derivation: Option<String>,
+}
+
+impl ProblemRecord {
+    pub fn new(p: &Problem, classification: Option<Classification>, derivation: Option<String>) -> Self {
+        let mut record = ProblemRecord {
+            hash: problem_id(p),
+            text: p.to_string(),
+            labels: p.labels().len(),
+            active_degree: p.active.degree.to_string(),
+            passive_degree: p.passive.degree.to_string(),
+            trivial: p.trivial_sets.as_ref().map(|t| !t.is_empty()),
+            unsolvable: false,
+            lower_bound: None,
+            upper_bound: None,
+            derivation,
+        };
+        match classification {
+            Some(Classification::Rounds(r)) => {
+                record.trivial = Some(r == 0);
+                record.lower_bound = Some(r);
+                record.upper_bound = Some(r);
+            }
+            Some(Classification::AtLeast(r)) => {
+                record.trivial = Some(false);
+                record.lower_bound = Some(r);
+            }
+            Some(Classification::Unsolvable) => {
+                record.trivial = Some(false);
+                record.unsolvable = true;
+            }
+            None => {}
+        }
+        record
+    }
+
+    /// Combines what is known about the same problem, keeping the best bounds, and the first known derivation.
+    /// Fails if the other record is about a different problem with the same hash.
+    pub fn merge(&mut self, other: &ProblemRecord) -> Result<(), &'static str> {
+        if self.hash != other.hash || self.text != other.text {
+            return Err("Two different problems have the same hash");
+        }
+        self.trivial = self.trivial.or(other.trivial);
+        self.unsolvable |= other.unsolvable;
+        self.lower_bound = self.lower_bound.max(other.lower_bound);
+        self.upper_bound = match (self.upper_bound, other.upper_bound) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        if self.derivation.is_none() {
+            self.derivation = other.derivation.clone();
+        }
+        Ok(())
+    }
+}
+
+/// A conjunction of conditions on records, such as `--labels<=4 --nontrivial`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Query {
+    pub min_labels: Option<usize>,
+    pub max_labels: Option<usize>,
+    pub active_degree: Option<String>,
+    pub passive_degree: Option<String>,
+    pub trivial: Option<bool>,
+    /// If true, only the problems whose bounds do not match.
+    pub open: bool,
+}
+
+impl Query {
+    /// Parses conditions of the form `--labels<=N`, `--labels>=N`, `--labels=N`, `--active-degree=D`,
+    /// `--passive-degree=D`, `--trivial`, `--nontrivial`, and `--open`.
+    pub fn parse<S: AsRef<str>>(conditions: &[S]) -> Result<Self, &'static str> {
+        let mut query = Query::default();
+        for condition in conditions {
+            let condition = condition.as_ref();
+            let number = |s: &str| s.parse::<usize>().map_err(|_| "The number of labels is not valid");
+            if let Some(n) = condition.strip_prefix("--labels<=") {
+                query.max_labels = Some(number(n)?);
+            } else if let Some(n) = condition.strip_prefix("--labels>=") {
+                query.min_labels = Some(number(n)?);
+            } else if let Some(n) = condition.strip_prefix("--labels=") {
+                query.min_labels = Some(number(n)?);
+                query.max_labels = Some(number(n)?);
+            } else if let Some(d) = condition.strip_prefix("--active-degree=") {
+                query.active_degree = Some(d.to_string());
+            } else if let Some(d) = condition.strip_prefix("--passive-degree=") {
+                query.passive_degree = Some(d.to_string());
+            } else if condition == "--trivial" {
+                query.trivial = Some(true);
+            } else if condition == "--nontrivial" {
+                query.trivial = Some(false);
+            } else if condition == "--open" {
+                query.open = true;
+            } else {
+                return Err("Unknown condition");
+            }
+        }
+        Ok(query)
+    }
+
+    pub fn matches(&self, record: &ProblemRecord) -> bool {
+        self.min_labels.is_none_or(|m| record.labels >= m)
+            && self.max_labels.is_none_or(|m| record.labels <= m)
+            && self.active_degree.as_ref().is_none_or(|d| &record.active_degree == d)
+            && self.passive_degree.as_ref().is_none_or(|d| &record.passive_degree == d)
+            && self.trivial.is_none_or(|t| record.trivial == Some(t))
+            && (!self.open || (!record.unsolvable && (record.upper_bound.is_none() || record.lower_bound != record.upper_bound)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{corpus::Classification, problem::Problem};
+
+    use super::{ProblemRecord, Query};
+
+    #[test]
+    fn database() {
+        let p = Problem::from_string("A B B\n\nA B").unwrap();
+        let mut record = ProblemRecord::new(&p, Some(Classification::AtLeast(2)), Some("test".into()));
+        assert_eq!(record.labels, 2);
+        assert_eq!(record.active_degree, "3");
+        record.merge(&ProblemRecord::new(&p, Some(Classification::Rounds(3)), None)).unwrap();
+        assert_eq!((record.lower_bound, record.upper_bound), (Some(3), Some(3)));
+        assert_eq!(record.derivation.as_deref(), Some("test"));
+        let mut other = ProblemRecord::new(&Problem::from_string("A A A\n\nA A").unwrap(), None, None);
+        other.hash = record.hash;
+        assert!(record.merge(&other).is_err());
+
+        let query = Query::parse(&["--labels<=4", "--nontrivial"]).unwrap();
+        assert!(query.matches(&record));
+        assert!(!Query::parse(&["--labels>=3"]).unwrap().matches(&record));
+        assert!(!Query::parse(&["--open"]).unwrap().matches(&record));
+        assert!(Query::parse(&["--labels<4"]).is_err());
+    }
+}
@@ -0,0 +1,192 @@
+//! Cross-checks the speedup against a naive implementation that follows the definition directly,
+//! by explicitly enumerating all sets of labels and all configurations.
+//! The fixed problems are always checked, while the random ones, and the ones of the corpus of small problems (see `corpus`),
+//! are checked only with the `slow-tests` feature.
+
+use std::collections::HashSet;
+
+use itertools::Itertools;
+
+use crate::{
+    algorithms::event::EventHandler,
+    constraint::Constraint,
+    group::{GroupType, Label},
+    problem::Problem,
+};
+
+type Configuration<T> = Vec<T>;
+
+/// The configurations of a constraint whose labels are sets of old labels.
+type SetConstraint = HashSet<Configuration<Vec<Label>>>;
+
+/// All multisets of the given size of elements of `items`, each one sorted.
+fn multisets<T: Clone + Ord>(items: &[T], size: usize) -> Vec<Vec<T>> {
+    if size == 0 {
+        return vec![vec![]];
+    }
+    let mut result = vec![];
+    for (i, x) in items.iter().enumerate() {
+        for mut rest in multisets(&items[i..], size - 1) {
+            rest.insert(0, x.clone());
+            result.push(rest);
+        }
+    }
+    result
+}
+
+/// All the configurations allowed by the constraint, as sorted vectors of labels.
+fn configurations(c: &Constraint) -> HashSet<Configuration<Label>> {
+    let mut result = HashSet::new();
+    for line in &c.lines {
+        let mut partial: Vec<Vec<Label>> = vec![vec![]];
+        for part in &line.parts {
+            let n = match part.gtype {
+                GroupType::Many(n) => n as usize,
+                GroupType::Star => panic!("the reference implementation does not support stars"),
+            };
+            let choices = multisets(&part.group.0, n);
+            partial = partial
+                .into_iter()
+                .cartesian_product(choices)
+                .map(|(a, b)| a.into_iter().chain(b).collect())
+                .collect();
+        }
+        result.extend(partial.into_iter().map(|v| v.into_iter().sorted().collect()));
+    }
+    result
+}
+
+fn nonempty_subsets(labels: &[Label]) -> Vec<Vec<Label>> {
+    (1..1usize << labels.len())
+        .map(|mask| (0..labels.len()).filter(|i| mask & (1 << i) != 0).map(|i| labels[i]).collect())
+        .collect()
+}
+
+/// All the ways of picking one label from each set, as sorted vectors.
+fn choices(sets: &[Vec<Label>]) -> Vec<Configuration<Label>> {
+    sets.iter()
+        .map(|s| s.iter().cloned())
+        .multi_cartesian_product()
+        .map(|v| v.into_iter().sorted().collect())
+        .collect()
+}
+
+/// Whether the sets of `a` can be matched with the sets of `b` so that each set of `a` is a subset of its match.
+fn dominated(a: &[Vec<Label>], b: &[Vec<Label>], used: &mut Vec<bool>) -> bool {
+    let Some((first, rest)) = a.split_first() else {
+        return true;
+    };
+    for i in 0..b.len() {
+        if !used[i] && first.iter().all(|l| b[i].contains(l)) {
+            used[i] = true;
+            let found = dominated(rest, b, used);
+            used[i] = false;
+            if found {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// The speedup by definition: the new active side contains the maximal configurations of sets where every choice is allowed by the passive side,
+/// and the new passive side contains the configurations of sets where some choice is allowed by the active side.
+fn reference_speedup(p: &Problem) -> (SetConstraint, SetConstraint) {
+    let labels = p.labels();
+    let sets: Vec<_> = nonempty_subsets(&labels).into_iter().sorted().collect();
+    let active = configurations(&p.active);
+    let passive = configurations(&p.passive);
+
+    let forall: Vec<_> = multisets(&sets, p.passive.finite_degree())
+        .into_iter()
+        .filter(|c| choices(c).iter().all(|x| passive.contains(x)))
+        .collect();
+    let new_active = forall
+        .iter()
+        .filter(|&c| {
+            !forall
+                .iter()
+                .any(|d| d != c && dominated(c, d, &mut vec![false; d.len()]))
+        })
+        .cloned()
+        .collect();
+
+    let new_passive = multisets(&sets, p.active.finite_degree())
+        .into_iter()
+        .filter(|c| choices(c).iter().any(|x| active.contains(x)))
+        .collect();
+
+    (new_active, new_passive)
+}
+
+/// Panics if the speedup of `p` differs from the reference one.
+fn check_speedup(p: &Problem) {
+    let q = p.speedup(&mut EventHandler::null());
+    let mapping: std::collections::HashMap<_, _> = q.mapping_label_oldlabels.clone().unwrap().into_iter().collect();
+    let as_sets = |c: &Constraint| -> SetConstraint {
+        configurations(c)
+            .into_iter()
+            .map(|v| v.iter().map(|l| mapping[l].clone()).sorted().collect())
+            .collect()
+    };
+    let new_labels: HashSet<_> = mapping.values().cloned().collect();
+
+    let (reference_active, reference_passive) = reference_speedup(p);
+    let reference_passive: HashSet<_> = reference_passive
+        .into_iter()
+        .filter(|c| c.iter().all(|s| new_labels.contains(s)))
+        .collect();
+
+    assert_eq!(as_sets(&q.active), reference_active, "active side of the speedup of\n{}", p);
+    assert_eq!(as_sets(&q.passive), reference_passive, "passive side of the speedup of\n{}", p);
+}
+
+#[test]
+fn speedup_matches_reference() {
+    for text in [
+        "M U U\nP P P\n\nM UP\nU U",
+        "A B B\n\nB AB",
+        "A A A\nB B B\nC C C\n\nA BC\nB C",
+        "A B C\nA A C\n\nA BC\nB C\nC C",
+    ] {
+        check_speedup(&Problem::from_string(text).unwrap());
+    }
+}
+
+#[cfg(feature = "slow-tests")]
+#[test]
+fn speedup_matches_reference_random() {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(1);
+    let names = ["A", "B", "C"];
+    let random_side = |rng: &mut StdRng, labels: usize, degree: usize| loop {
+        let lines: Vec<_> = multisets(&names[..labels], degree)
+            .into_iter()
+            .filter(|_| rng.gen_bool(0.4))
+            .map(|c| c.join(" "))
+            .collect();
+        if !lines.is_empty() {
+            return lines.join("\n");
+        }
+    };
+    for _ in 0..300 {
+        let labels = rng.gen_range(2..4usize);
+        let (active_degree, passive_degree) = (rng.gen_range(2..4usize), rng.gen_range(2..4usize));
+        let active = random_side(&mut rng, labels, active_degree);
+        let passive = random_side(&mut rng, labels, passive_degree);
+        check_speedup(&Problem::from_string(format!("{}\n\n{}", active, passive)).unwrap());
+    }
+}
+
+#[cfg(feature = "slow-tests")]
+#[test]
+fn speedup_matches_reference_corpus() {
+    use crate::corpus::all_entries;
+
+    for (labels, active_degree, passive_degree) in [(2, 2, 2), (2, 3, 2), (3, 2, 2)] {
+        for entry in all_entries(labels, active_degree, passive_degree).unwrap() {
+            check_speedup(&entry.problem().unwrap());
+        }
+    }
+}
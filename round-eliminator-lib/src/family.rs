@@ -0,0 +1,93 @@
+use crate::{
+    algorithms::event::EventHandler,
+    pipeline::{Op, StepSummary},
+    problem::Problem,
+};
+
+/// What is expected from the last problem obtained by running the script of a family.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Outcome {
+    /// Whether the problem is zero round solvable.
+    Trivial(bool),
+    Labels(usize),
+    /// The problem is equal to the one given in text form.
+    SameAs(String),
+}
+
+/// A parameterized sequence of problems, together with the operations that give a proof for each of them and the expected results.
+/// Validating a family checks all the claims made about it, for example in the appendix of a paper.
+pub struct Family {
+    pub name: String,
+    /// Returns the problem with the given parameter, in text form.
+    pub generator: Box<dyn Fn(usize) -> String>,
+    pub script: Vec<Op>,
+    pub expected: Vec<(usize, Vec<Outcome>)>,
+}
+
+impl Family {
+    pub fn problem(&self, parameter: usize) -> Result<Problem, &'static str> {
+        Problem::from_string((self.generator)(parameter))
+    }
+
+    /// Runs the script on the problem with the given parameter, and returns the summary of each step together with the last problem.
+    pub fn run(&self, parameter: usize, eh: &mut EventHandler) -> Result<(Vec<StepSummary>, Problem), &'static str> {
+        self.problem(parameter)?.run_pipeline(&self.script, eh).map_err(|(_, e)| e)
+    }
+
+    /// Checks all the expected outcomes, and returns the first parameter for which something does not hold.
+    pub fn validate(&self, eh: &mut EventHandler) -> Result<(), (usize, &'static str)> {
+        for (i, (parameter, outcomes)) in self.expected.iter().enumerate() {
+            eh.notify("family", i, self.expected.len());
+            let (_, mut last) = self.run(*parameter, eh).map_err(|e| (*parameter, e))?;
+            for outcome in outcomes {
+                let holds = match outcome {
+                    Outcome::Trivial(trivial) => {
+                        if last.trivial_sets.is_none() {
+                            last.compute_triviality(eh);
+                        }
+                        last.trivial_sets.as_ref().unwrap().is_empty() != *trivial
+                    }
+                    Outcome::Labels(n) => last.labels().len() == *n,
+                    Outcome::SameAs(text) => {
+                        let other = Problem::from_string(text).map_err(|e| (*parameter, e))?;
+                        last == other
+                    }
+                };
+                if !holds {
+                    return Err((*parameter, "The result does not match the expected outcome"));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, pipeline::Op};
+
+    use super::{Family, Outcome};
+
+    #[test]
+    fn family() {
+        let eh = &mut EventHandler::null();
+        let coloring = |k: usize| {
+            let names: Vec<_> = (0..k).map(|i| ((b'A' + i as u8) as char).to_string()).collect();
+            let active: Vec<_> = names.iter().map(|c| format!("{} {}", c, c)).collect();
+            let passive: Vec<_> = (0..k).map(|i| format!("{} {}", names[i], names[i + 1..].concat())).filter(|l| !l.ends_with(' ')).collect();
+            format!("{}\n\n{}", active.join("\n"), passive.join("\n"))
+        };
+        let mut family = Family {
+            name: "coloring on paths".into(),
+            generator: Box::new(coloring),
+            script: vec![Op::Speedup, Op::Cleanup],
+            expected: vec![(3, vec![Outcome::Trivial(false)]), (4, vec![Outcome::Trivial(false)])],
+        };
+        assert_eq!(family.problem(3).unwrap().labels().len(), 3);
+        assert!(family.validate(eh).is_ok());
+
+        family.expected.push((5, vec![Outcome::Trivial(true)]));
+        assert_eq!(family.validate(eh).unwrap_err().0, 5);
+    }
+}
@@ -8,7 +8,9 @@ use serde::Deserialize;
 use serde::Serialize;
 
 pub type Label = u32;
-pub type Exponent = u8;
+/// The number of times a group is repeated in a line. Every line of a problem has degree at most `Exponent::MAX`,
+/// see `Problem::check_exponents`, so joining parts of the same line never overflows.
+pub type Exponent = u16;
 
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 pub struct Group(pub Vec<Label>);
@@ -21,6 +23,15 @@ pub enum GroupType {
 
 impl GroupType {
     pub const ONE: GroupType = GroupType::Many(1);
+
+    /// The type of the part obtained by joining two parts with the same group, or None if the count does not fit in an `Exponent`.
+    pub fn checked_add(self, other: GroupType) -> Option<GroupType> {
+        use GroupType::*;
+        match (self, other) {
+            (Many(a), Many(b)) => a.checked_add(b).map(Many),
+            (Star, _) | (_, Star) => Some(Star),
+        }
+    }
 }
 
 impl Deref for Group {
@@ -37,6 +48,118 @@ impl DerefMut for Group {
     }
 }
 
+/// Groups whose labels are all smaller than this are handled as bitsets by the group operations, see `Bitset`.
+const BITSET_LABELS: Label = 256;
+const BITSET_WORDS: usize = (BITSET_LABELS / 64) as usize;
+/// The bitset path is used only if both groups have at least this many labels, since for smaller groups building the bitsets
+/// costs more than merging the sorted labels. With the group_ops benchmark, intersection and difference are 1.4x faster
+/// with bitsets on groups of 8 labels and slower on groups of 4, while union, whose scalar version just sorts the labels,
+/// breaks even at 8 labels and is 1.2x faster at 12.
+const BITSET_MIN_LEN: usize = 8;
+const UNION_BITSET_MIN_LEN: usize = 12;
+
+/// A group as a bitset of packed words. The operations are written word by word, so that they compile to a few vector instructions
+/// instead of the data-dependent branches of merging sorted labels, which dominate maximization and the computation of the diagram.
+/// Each bitset is built from scratch, so only the operations that visit all the labels anyway benefit from it.
+#[derive(Copy, Clone)]
+struct Bitset([u64; BITSET_WORDS]);
+
+impl Bitset {
+    #[inline(always)]
+    fn of(group: &[Label]) -> Option<Self> {
+        let mut words = [0u64; BITSET_WORDS];
+        for &l in group {
+            if l >= BITSET_LABELS {
+                return None;
+            }
+            words[(l / 64) as usize] |= 1 << (l % 64);
+        }
+        Some(Bitset(words))
+    }
+
+    /// The group obtained by applying `op` to the bitsets of both groups, or None if some label does not fit in a bitset.
+    #[inline(always)]
+    fn apply(g1: &Group, g2: &Group, op: impl Fn([u64; BITSET_WORDS], [u64; BITSET_WORDS]) -> [u64; BITSET_WORDS]) -> Option<Group> {
+        Some(Bitset(op(Self::of(g1)?.0, Self::of(g2)?.0)).to_group())
+    }
+
+    /// Same as `apply`, but the groups are merged as sorted labels if they have fewer than `min_len` labels or do not fit in a bitset.
+    #[inline(always)]
+    fn apply_or(g1: &Group, g2: &Group, min_len: usize, op: impl Fn([u64; BITSET_WORDS], [u64; BITSET_WORDS]) -> [u64; BITSET_WORDS] + Copy, scalar: impl FnOnce() -> Group) -> Group {
+        if g1.len() < min_len || g2.len() < min_len {
+            return scalar();
+        }
+        with_cpu_features(|| Self::apply(g1, g2, op)).unwrap_or_else(scalar)
+    }
+
+    /// Whether the bitset contains all the labels of `group`, which must be sorted. The labels are collected one word at a time,
+    /// and each word is compared as soon as it is complete, so that the check stops at the first word with a missing label.
+    #[inline(always)]
+    fn contains_all(&self, group: &[Label]) -> bool {
+        let mut current = 0;
+        let mut bits = 0u64;
+        for &l in group {
+            let word = (l / 64) as usize;
+            if word != current {
+                if bits & !self.0[current] != 0 {
+                    return false;
+                }
+                current = word;
+                bits = 0;
+            }
+            bits |= 1 << (l % 64);
+        }
+        bits & !self.0[current] == 0
+    }
+
+    #[inline(always)]
+    fn to_group(self) -> Group {
+        let mut v = Vec::with_capacity(self.0.iter().map(|w| w.count_ones() as usize).sum());
+        for (i, &word) in self.0.iter().enumerate() {
+            let mut w = word;
+            while w != 0 {
+                v.push(i as Label * 64 + w.trailing_zeros());
+                w &= w - 1;
+            }
+        }
+        Group(v)
+    }
+}
+
+#[inline(always)]
+fn and(a: [u64; BITSET_WORDS], b: [u64; BITSET_WORDS]) -> [u64; BITSET_WORDS] {
+    std::array::from_fn(|i| a[i] & b[i])
+}
+
+#[inline(always)]
+fn or(a: [u64; BITSET_WORDS], b: [u64; BITSET_WORDS]) -> [u64; BITSET_WORDS] {
+    std::array::from_fn(|i| a[i] | b[i])
+}
+
+#[inline(always)]
+fn and_not(a: [u64; BITSET_WORDS], b: [u64; BITSET_WORDS]) -> [u64; BITSET_WORDS] {
+    std::array::from_fn(|i| a[i] & !b[i])
+}
+
+/// Runs `f`, compiled for the CPU features available at runtime. The baseline x86-64 target has no instruction to count
+/// the bits of a word and uses two SSE2 registers for the four words of a bitset, so on CPUs with POPCNT, BMI1 and AVX2
+/// a version of `f` compiled with them is used. Everything that `f` calls must be inlined into it to benefit.
+#[inline(always)]
+fn with_cpu_features<T>(f: impl FnOnce() -> T) -> T {
+    #[cfg(target_arch = "x86_64")]
+    if std::is_x86_feature_detected!("avx2") && std::is_x86_feature_detected!("popcnt") && std::is_x86_feature_detected!("bmi1") {
+        // SAFETY: the CPU supports the features enabled by `with_x86_v3`
+        return unsafe { with_x86_v3(f) };
+    }
+    f()
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2,popcnt,bmi1")]
+unsafe fn with_x86_v3<T>(f: impl FnOnce() -> T) -> T {
+    f()
+}
+
 impl Group {
     pub fn as_set(&self) -> HashSet<Label> {
         self.iter().cloned().collect()
@@ -46,10 +169,32 @@ impl Group {
         Group(h.iter().cloned().sorted().collect())
     }
 
+    /// Whether the group contains all the labels of `other`. Both groups must be sorted and without duplicates.
+    /// Differently from the other operations, this does not use bitsets by default: the merge stops at the first missing label,
+    /// and with the group_ops benchmark it is about 2x faster than `is_superset_bitset` on groups of any size.
     #[inline(never)]
     pub fn is_superset(&self, other: &Group) -> bool {
+        self.is_superset_scalar(other)
+    }
+
+    /// Same as `is_superset`, by building the bitset of this group and comparing the labels of `other` with it one word at a time.
+    pub fn is_superset_bitset(&self, other: &Group) -> bool {
+        if other.len() > self.len() {
+            return false;
+        }
+        if other.last().is_some_and(|&l| l >= BITSET_LABELS) {
+            return self.is_superset_scalar(other);
+        }
+        with_cpu_features(|| Bitset::of(self).map(|a| a.contains_all(other))).unwrap_or_else(|| self.is_superset_scalar(other))
+    }
+
+    /// Same as `is_superset`, by merging the sorted labels. The merge stops at the first missing label.
+    pub fn is_superset_scalar(&self, other: &Group) -> bool {
         //assert!(self.is_sorted());
         //assert!(other.is_sorted());
+        if other.len() > self.len() {
+            return false;
+        }
         let mut it1 = self.iter();
 
         for &elem in other.iter() {
@@ -61,6 +206,11 @@ impl Group {
     }
 
     pub fn difference(&self, other: &Group) -> Self {
+        Bitset::apply_or(self, other, BITSET_MIN_LEN, and_not, || self.difference_scalar(other))
+    }
+
+    /// Same as `difference`, by merging the sorted labels.
+    pub fn difference_scalar(&self, other: &Group) -> Self {
         let mut i = 0;
         let mut j = 0;
         let mut v = Vec::with_capacity(std::cmp::min(self.len(), other.len()));
@@ -84,36 +234,13 @@ impl Group {
     }
 
     pub fn intersection(&self, other: &Group) -> Self {
+        Bitset::apply_or(self, other, BITSET_MIN_LEN, and, || self.intersection_scalar(other))
+    }
+
+    /// Same as `intersection`, by merging the sorted labels.
+    pub fn intersection_scalar(&self, other: &Group) -> Self {
         //assert!(self.is_sorted());
         //assert!(other.is_sorted());
-        /*
-        let mut it1 = self.iter();
-        let mut it2 = other.iter();
-        let mut v = Vec::with_capacity(std::cmp::min(self.len(),other.len()));
-
-
-        let mut last1 = if let Some(&x) = it1.next() { x } else { return Self(v); };
-        let mut last2 = if let Some(&x) = it2.next() { x } else { return Self(v); };
-
-        loop {
-            while last1 < last2 {
-                last1 = if let Some(&x) = it1.next() { x } else { return Self(v); };
-            }
-            while last2 < last1 {
-                last2 = if let Some(&x) = it2.next() { x } else { return Self(v); };
-            }
-            if last1 == last2 {
-                v.push(last1);
-                last2 = if let Some(&x) = it2.next() { x } else { return Self(v); };
-            }
-        }*/
-        /*Group(
-            self.as_set()
-                .intersection(&other.as_set())
-                .cloned()
-                .sorted()
-                .collect(),
-        )*/
         let mut i = 0;
         let mut j = 0;
         let mut v = Vec::with_capacity(std::cmp::min(self.len(), other.len()));
@@ -135,14 +262,19 @@ impl Group {
         Group(v)
     }
 
+    /// The sorted labels that are in at least one of the groups. Differently from the other operations, the groups do not need to be sorted.
     pub fn union(&self, other: &Group) -> Self {
-        Group(
-            self.as_set()
-                .union(&other.as_set())
-                .cloned()
-                .sorted()
-                .collect(),
-        )
+        Bitset::apply_or(self, other, UNION_BITSET_MIN_LEN, or, || self.union_scalar(other))
+    }
+
+    /// Same as `union`, by sorting the labels of both groups.
+    pub fn union_scalar(&self, other: &Group) -> Self {
+        let mut v = Vec::with_capacity(self.len() + other.len());
+        v.extend_from_slice(self);
+        v.extend_from_slice(other);
+        v.sort_unstable();
+        v.dedup();
+        Group(v)
     }
 }
 
@@ -172,7 +304,7 @@ impl GroupType {
 
 #[cfg(test)]
 mod tests {
-    use super::GroupType;
+    use super::{Group, GroupType};
 
     #[test]
     #[should_panic]
@@ -185,4 +317,34 @@ mod tests {
         assert_eq!(GroupType::ONE.value(), 1);
         assert_eq!(GroupType::Many(100).value(), 100);
     }
+
+    #[test]
+    fn bitset_operations() {
+        // small pseudorandom groups on 300 labels, so that both paths are used
+        let mut seed: u64 = 1;
+        let mut groups = vec![];
+        for i in 0..60 {
+            let range = if i % 3 == 0 { 300 } else { 256 };
+            let v: Vec<u32> = (0..range)
+                .filter(|_| {
+                    seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                    (seed >> 33).is_multiple_of(4)
+                })
+                .collect();
+            groups.push(Group(v));
+        }
+        groups.push(Group(vec![3, 5]));
+        groups.push(Group(vec![]));
+        for g1 in &groups {
+            for g2 in &groups {
+                assert_eq!(g1.is_superset(g2), g2.iter().all(|l| g1.contains(l)));
+                assert_eq!(g1.is_superset(g2), g1.is_superset_bitset(g2));
+                assert_eq!(g1.union(g2), g1.union_scalar(g2));
+                assert_eq!(g1.intersection(g2), g1.intersection_scalar(g2));
+                assert_eq!(g1.difference(g2), g1.difference_scalar(g2));
+            }
+            let sub = Group(g1.iter().cloned().step_by(2).collect());
+            assert!(g1.is_superset(&sub));
+        }
+    }
 }
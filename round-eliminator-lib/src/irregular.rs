@@ -0,0 +1,296 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Display,
+};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    algorithms::{event::EventHandler, speedup::label_chars},
+    verdict::AUTOLB_FIXED_POINT,
+    constraint::Constraint,
+    group::{Exponent, Group, GroupType, Label},
+    line::{Degree, Line},
+    part::Part,
+    problem::Problem,
+};
+
+/// A problem where the nodes of each side can have several degrees, for example a problem on graphs of maximum degree Δ,
+/// where the active side contains the nodes, of degree from 1 to Δ, and the passive side contains the edges.
+/// Each side has one constraint for each allowed degree, and a node of a given degree must satisfy the constraint of that degree.
+/// The labels are shared by all the degrees: a node does not know the degrees of its neighbors.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct IrregularProblem {
+    /// The constraints of the active side, sorted by degree, with at most one constraint for each degree.
+    pub active: Vec<Constraint>,
+    /// The constraints of the passive side, sorted by degree, with at most one constraint for each degree.
+    pub passive: Vec<Constraint>,
+    pub mapping_label_text: Vec<(Label, String)>,
+    pub mapping_label_oldlabels: Option<Vec<(Label, Vec<Label>)>>,
+    pub mapping_oldlabel_text: Option<Vec<(Label, String)>>,
+}
+
+/// Groups the lines by degree, in increasing order of degree.
+fn constraints_by_degree(lines: Vec<Line>) -> Result<Vec<Constraint>, &'static str> {
+    let mut by_degree: BTreeMap<usize, Vec<Line>> = BTreeMap::new();
+    for line in lines {
+        let Degree::Finite(d) = line.degree() else {
+            return Err("Lines containing a star are not supported when several degrees are allowed");
+        };
+        by_degree.entry(d).or_default().push(line);
+    }
+    Ok(by_degree
+        .into_iter()
+        .map(|(d, lines)| {
+            let mut constraint = Constraint { lines, is_maximized: false, degree: Degree::Finite(d) };
+            constraint.discard_non_maximal_lines();
+            constraint
+        })
+        .collect())
+}
+
+impl IrregularProblem {
+    /// Parses a problem written as a `Problem`, where the lines of each side can have different degrees.
+    pub fn from_string<S: AsRef<str>>(s: S) -> Result<Self, &'static str> {
+        let s = s.as_ref();
+        let mut mapping = HashMap::new();
+        let mut sides = vec![];
+        for text in s.trim().split("\n\n") {
+            let mut lines = vec![];
+            for l in text.lines() {
+                lines.extend(Line::parse_with_counting(l, &mut mapping)?);
+            }
+            if lines.is_empty() {
+                return Err("Empty constraint");
+            }
+            sides.push(constraints_by_degree(lines)?);
+        }
+        if sides.len() != 2 {
+            return Err("Expected two constraints separated by an empty line");
+        }
+        let passive = sides.pop().unwrap();
+        let active = sides.pop().unwrap();
+        Ok(IrregularProblem {
+            active,
+            passive,
+            mapping_label_text: mapping.into_iter().map(|(a, b)| (b, a)).sorted().collect(),
+            mapping_label_oldlabels: None,
+            mapping_oldlabel_text: None,
+        })
+    }
+
+    /// The same problem, where only one degree is allowed on each side.
+    pub fn from_problem(p: &Problem) -> Result<Self, &'static str> {
+        if p.active.degree == Degree::Star || p.passive.degree == Degree::Star {
+            return Err("Lines containing a star are not supported when several degrees are allowed");
+        }
+        Ok(IrregularProblem {
+            active: vec![p.active.clone()],
+            passive: vec![p.passive.clone()],
+            mapping_label_text: p.mapping_label_text.clone(),
+            mapping_label_oldlabels: None,
+            mapping_oldlabel_text: None,
+        })
+    }
+
+    /// The same problem as a `Problem`, if each side allows only one degree.
+    pub fn to_problem(&self) -> Result<Problem, &'static str> {
+        if self.active.len() != 1 || self.passive.len() != 1 {
+            return Err("Only problems with one degree on each side can be converted");
+        }
+        let mapping: HashMap<_, _> = self.mapping_label_text.iter().cloned().collect();
+        let text = |c: &Constraint| c.lines.iter().map(|line| line.to_string(&mapping)).join("\n");
+        Problem::from_string_active_passive(text(&self.active[0]), text(&self.passive[0]))
+    }
+
+    pub fn labels(&self) -> Vec<Label> {
+        let mut labels: Vec<_> = self.mapping_label_text.iter().map(|(l, _)| *l).collect();
+        labels.sort_unstable();
+        labels
+    }
+
+    /// The allowed degrees of the active side.
+    pub fn active_degrees(&self) -> Vec<usize> {
+        self.active.iter().map(|c| c.finite_degree()).collect()
+    }
+
+    /// The allowed degrees of the passive side.
+    pub fn passive_degrees(&self) -> Vec<usize> {
+        self.passive.iter().map(|c| c.finite_degree()).collect()
+    }
+
+    pub fn maximize(&mut self, eh: &mut EventHandler) {
+        for c in self.active.iter_mut().chain(self.passive.iter_mut()) {
+            c.maximize(eh);
+        }
+    }
+
+    fn assign_chars(&mut self) {
+        self.mapping_label_text = label_chars(&self.labels_from_oldlabels());
+    }
+
+    fn labels_from_oldlabels(&self) -> Vec<Label> {
+        self.mapping_label_oldlabels.iter().flatten().map(|(l, _)| *l).collect()
+    }
+
+    /// Performs a round elimination step, as `Problem::speedup`, separately for each degree.
+    /// Each constraint of the passive side is maximized on its own, and the sets of labels appearing in any of them become the new labels,
+    /// so that the new labels are the same for all the degrees. Then, each constraint of the active side allows the sets of new labels
+    /// where some choice is allowed, and the two sides are swapped.
+    pub fn speedup(&self, eh: &mut EventHandler) -> Self {
+        let maximized: Vec<Constraint> = self
+            .passive
+            .iter()
+            .map(|c| {
+                let mut c = c.clone();
+                c.maximize(eh);
+                c
+            })
+            .collect();
+
+        let mapping_label_oldlabels: Vec<_> = maximized
+            .iter()
+            .flat_map(|c| c.groups())
+            .unique()
+            .map(|g| g.0.clone())
+            .sorted_by_key(|v| v.iter().cloned().rev().collect::<Vec<Label>>())
+            .enumerate()
+            .map(|(a, b)| (a as Label, b))
+            .collect();
+        let h_oldlabels_label: HashMap<_, _> = mapping_label_oldlabels.iter().map(|(a, b)| (b.clone(), *a)).collect();
+
+        let active = maximized.iter().map(|c| c.edited(|g| Group(vec![h_oldlabels_label[&g.0]]))).collect();
+        let passive = self
+            .active
+            .iter()
+            .map(|c| {
+                c.edited(|g| {
+                    let h = g.as_set();
+                    Group(mapping_label_oldlabels.iter().filter(|(_, o)| o.iter().any(|l| h.contains(l))).map(|p| p.0).sorted().collect())
+                })
+            })
+            .collect();
+
+        let mut p = IrregularProblem {
+            active,
+            passive,
+            mapping_label_text: vec![],
+            mapping_label_oldlabels: Some(mapping_label_oldlabels),
+            mapping_oldlabel_text: Some(self.mapping_label_text.clone()),
+        };
+        p.assign_chars();
+        p
+    }
+
+    /// The sets of labels that give a zero round algorithm: for each degree of the active side, the nodes of that degree output
+    /// a fixed configuration, and the passive nodes must accept any combination of the labels used by all these configurations,
+    /// since they do not know the degrees of their neighbors. Only the minimal such sets are returned.
+    pub fn trivial_sets(&self, eh: &mut EventHandler) -> Vec<Vec<Label>> {
+        let mut passive = self.passive.clone();
+        for c in passive.iter_mut() {
+            c.maximize(eh);
+        }
+        if self.active.iter().any(|c| c.lines.is_empty()) {
+            return vec![];
+        }
+        let accepted = |set: &HashSet<Label>| {
+            let group = Group(set.iter().cloned().sorted().collect());
+            passive.iter().all(|c| {
+                c.includes(&Line { parts: vec![Part { gtype: GroupType::Many(c.finite_degree() as Exponent), group: group.clone() }] })
+            })
+        };
+
+        let choices: Vec<Vec<HashSet<Label>>> = self.active.iter().map(|c| c.minimal_sets_of_all_choices()).collect();
+        let mut trivial: Vec<HashSet<Label>> = vec![];
+        for combination in choices.iter().map(|sets| sets.iter()).multi_cartesian_product() {
+            let set: HashSet<Label> = combination.into_iter().flatten().cloned().collect();
+            if accepted(&set) && !trivial.iter().any(|t| t.is_subset(&set)) {
+                trivial.retain(|t| !t.is_superset(&set));
+                trivial.push(set);
+            }
+        }
+        trivial.into_iter().map(|set| set.into_iter().sorted().collect()).sorted().collect()
+    }
+
+    /// Applies speedups until a problem is zero round solvable, has more than `max_labels` labels, or `max_steps` speedups have been done.
+    /// Returns the lower bound given by the problems that are not zero round solvable, which is `AUTOLB_FIXED_POINT` if a problem is the same
+    /// as an earlier one, as in `Problem::autolb`, together with the sequence of problems.
+    pub fn autolb(&self, max_labels: usize, max_steps: usize, eh: &mut EventHandler) -> (usize, Vec<IrregularProblem>) {
+        let mut sequence = vec![self.clone()];
+        let mut seen = HashSet::new();
+        loop {
+            let p = sequence.last().unwrap();
+            if !p.trivial_sets(eh).is_empty() {
+                return (sequence.len() - 1, sequence);
+            }
+            if !seen.insert(p.to_string()) {
+                return (AUTOLB_FIXED_POINT, sequence);
+            }
+            if sequence.len() > max_steps || p.labels().len() > max_labels {
+                return (sequence.len(), sequence);
+            }
+            let sped = p.speedup(eh);
+            sequence.push(sped);
+        }
+    }
+}
+
+impl Display for IrregularProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mapping: HashMap<Label, String> = self.mapping_label_text.iter().cloned().collect();
+        for line in self.active.iter().flat_map(|c| c.lines.iter()) {
+            writeln!(f, "{}", line.to_string(&mapping))?;
+        }
+        writeln!(f)?;
+        for line in self.passive.iter().flat_map(|c| c.lines.iter()) {
+            writeln!(f, "{}", line.to_string(&mapping))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    use super::IrregularProblem;
+
+    #[test]
+    fn irregular() {
+        let eh = &mut EventHandler::null();
+
+        // with one degree per side, the speedup is the same as for regular problems
+        let p = Problem::from_string("M U U U\nP P P P\n\nM M\nU PU").unwrap();
+        let irregular = IrregularProblem::from_problem(&p).unwrap();
+        let sped = irregular.speedup(eh).to_problem().unwrap();
+        assert_eq!(sped.to_canonical_text(), Problem::from_string(p.speedup(eh).to_string()).unwrap().to_canonical_text());
+
+        // maximal matching on graphs of maximum degree 3
+        let mm = IrregularProblem::from_string("M\nP\nM U\nP P\nM U U\nP P P\n\nM UP\nU U").unwrap();
+        assert_eq!(mm.active_degrees(), vec![1, 2, 3]);
+        assert_eq!(mm.passive_degrees(), vec![2]);
+        assert!(mm.to_problem().is_err());
+        assert!(mm.trivial_sets(eh).is_empty());
+        let sped = mm.speedup(eh);
+        assert_eq!(sped.active_degrees(), vec![2]);
+        assert_eq!(sped.passive_degrees(), vec![1, 2, 3]);
+        assert!(sped.speedup(eh).trivial_sets(eh).is_empty());
+
+        // the nodes of degree 2 can only use B, so the edges must accept both labels together
+        let p = IrregularProblem::from_string("A A A\nB B\n\nA B").unwrap();
+        assert!(p.trivial_sets(eh).is_empty());
+        let p = IrregularProblem::from_string("A A A\nB B\n\nAB AB").unwrap();
+        assert_eq!(p.trivial_sets(eh), vec![vec![0, 1]]);
+        assert_eq!(p.autolb(10, 3, eh).0, 0);
+
+        // matching is not zero round solvable, neither is its speedup
+        let (len, sequence) = mm.autolb(20, 1, eh);
+        assert_eq!(len, 2);
+        assert_eq!(sequence[0], mm);
+        assert_eq!(sequence[1].to_string(), sped.to_string());
+
+        assert!(IrregularProblem::from_string("A A*\n\nA A").is_err());
+    }
+}
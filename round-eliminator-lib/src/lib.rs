@@ -11,9 +11,21 @@ pub mod line;
 pub mod part;
 pub mod problem;
 pub mod serial;
+pub mod trace;
+pub mod store;
 pub mod directed;
 pub mod kpartite;
+pub mod irregular;
+pub mod pipeline;
+pub mod family;
+pub mod corpus;
+pub mod database;
+pub mod verdict;
+pub mod session;
+pub mod prelude;
 //#[cfg(test)]
 //pub mod moretests;
 
 
+#[cfg(test)]
+mod differential;
@@ -9,12 +9,21 @@ pub struct Line {
     pub parts: Vec<Part>,
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 pub enum Degree {
     Finite(usize),
     Star,
 }
 
+impl std::fmt::Display for Degree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Degree::Finite(d) => write!(f, "{}", d),
+            Degree::Star => write!(f, "*"),
+        }
+    }
+}
+
 impl Line {
     pub fn parse(line: &str, mapping: &mut HashMap<String, Label>) -> Result<Line, &'static str> {
         let parts = line
@@ -22,6 +31,9 @@ impl Line {
             .map(|part| Part::parse(part, mapping))
             .collect::<Result<_, _>>()?;
         let mut line = Line { parts };
+        if line.degree_without_star() > Exponent::MAX as usize {
+            return Err("The degree of a line is too large");
+        }
         line.normalize();
         Ok(line)
     }
@@ -39,12 +51,12 @@ impl Line {
                 //    s += 1;
                 //}
                 Many(n) => {
-                    s += n;
+                    s += n as usize;
                 }
                 Star => {}
             }
         }
-        s as usize
+        s
     }
 
     pub fn has_star(&self) -> bool {
@@ -64,14 +76,14 @@ impl Line {
                 //    s += 1;
                 //}
                 Many(n) => {
-                    s += n;
+                    s += n as usize;
                 }
                 Star => {
                     return Degree::Star;
                 }
             }
         }
-        Degree::Finite(s as usize)
+        Degree::Finite(s)
     }
 }
 
@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{algorithms::event::EventHandler, group::Label, problem::Problem, serial::fix_problem};
+
+/// An operation of a pipeline. Labels are referred to by their text,
+/// since the numeric labels of a problem are not known in advance after a speedup.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Op {
+    Speedup,
+    Maximize,
+    MergeEquivalentLabels,
+    /// Merges each label of the list into the other label, all at the same time.
+    MergeGroup(Vec<String>, String),
+    /// Merges the first label of each pair into the second one, all at the same time, as done by autolb.
+    Merges(Vec<(String, String)>),
+    /// Keeps only the given labels, possibly replacing the removed ones with their predecessors.
+    HardenKeep(Vec<String>, bool),
+    HardenRemove(String, bool),
+    /// Keeps only the given labels and the lines that use only them.
+    Induced(Vec<String>),
+    RenameGenerators,
+    SwapSides,
+    /// Recomputes the diagram, removes useless labels and lines, and sorts the lines, as done after each operation of the GUI.
+    Cleanup,
+    ComputeDiagram,
+    ComputeTriviality,
+}
+
+/// A short description of the problem obtained after an operation of a pipeline.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct StepSummary {
+    pub op: Op,
+    pub labels: usize,
+    pub active_lines: usize,
+    pub passive_lines: usize,
+    /// Whether the problem is zero round solvable, if it has been computed.
+    pub trivial: Option<bool>,
+    pub coloring: Option<usize>,
+    /// The size of the largest antichain of the diagram, if the diagram has been computed.
+    pub diagram_width: Option<usize>,
+}
+
+impl StepSummary {
+    fn new(op: &Op, p: &Problem) -> Self {
+        // what has been computed for a different problem is not reported
+        let fresh = !p.is_stale();
+        StepSummary {
+            op: op.clone(),
+            labels: p.labels().len(),
+            active_lines: p.active.lines.len(),
+            passive_lines: p.passive.lines.len(),
+            trivial: p.trivial_sets.as_ref().filter(|_| fresh).map(|t| !t.is_empty()),
+            coloring: p.coloring_sets.as_ref().filter(|_| fresh).map(|c| c.len()),
+            diagram_width: p.diagram_indirect.as_ref().filter(|_| fresh).map(|_| p.diagram_width()),
+        }
+    }
+}
+
+impl Problem {
+    fn label_by_text(&self, text: &str) -> Result<Label, &'static str> {
+        self.mapping_label_text
+            .iter()
+            .find(|(_, t)| t == text)
+            .map(|(l, _)| *l)
+            .ok_or("The pipeline refers to a label that is not in the problem")
+    }
+
+    pub fn apply_op(&self, op: &Op, eh: &mut EventHandler) -> Result<Problem, &'static str> {
+        let mut p = self.clone();
+        p.discard_stale_computed_stuff();
+        let mut p = match op {
+            Op::Speedup => {
+                if p.diagram_indirect.is_none() {
+                    p.compute_partial_diagram(eh);
+                }
+                p.speedup(eh)
+            }
+            Op::Maximize => {
+                p.discard_computed_stuff();
+                p.passive.maximize(eh);
+                p
+            }
+            Op::MergeEquivalentLabels => {
+                if p.diagram_indirect.is_none() {
+                    p.compute_partial_diagram(eh);
+                }
+                p.merge_equivalent_labels()
+            }
+            Op::MergeGroup(labels, to) => {
+                let to = p.label_by_text(to)?;
+                let labels = labels.iter().map(|l| p.label_by_text(l)).collect::<Result<Vec<_>, _>>()?;
+                let merges: Vec<_> = labels.into_iter().map(|l| (l, to)).collect();
+                p.relax_many_merges(&merges)
+            }
+            Op::Merges(pairs) => {
+                let merges = pairs.iter().map(|(a, b)| Ok((p.label_by_text(a)?, p.label_by_text(b)?))).collect::<Result<Vec<_>, _>>()?;
+                p.relax_many_merges(&merges)
+            }
+            Op::HardenKeep(labels, add_predecessors) => {
+                let keep = labels.iter().map(|l| p.label_by_text(l)).collect::<Result<_, _>>()?;
+                if *add_predecessors && p.diagram_indirect.is_none() {
+                    p.compute_partial_diagram(eh);
+                }
+                p.harden_keep(&keep, *add_predecessors)
+            }
+            Op::HardenRemove(label, add_predecessors) => {
+                let label = p.label_by_text(label)?;
+                if *add_predecessors && p.diagram_indirect.is_none() {
+                    p.compute_partial_diagram(eh);
+                }
+                p.harden_remove(label, *add_predecessors)
+            }
+            Op::Induced(labels) => {
+                let keep = labels.iter().map(|l| p.label_by_text(l)).collect::<Result<_, _>>()?;
+                p.induced(&keep)
+            }
+            Op::RenameGenerators => {
+                p.rename_by_generators()?;
+                p
+            }
+            Op::SwapSides => p.swap_sides(),
+            Op::Cleanup => {
+                fix_problem(&mut p, true, false, eh);
+                p
+            }
+            Op::ComputeDiagram => {
+                p.diagram_indirect = None;
+                p.compute_diagram(eh);
+                p
+            }
+            Op::ComputeTriviality => {
+                if p.trivial_sets.is_none() {
+                    p.compute_triviality(eh);
+                }
+                if p.coloring_sets.is_none() {
+                    p.compute_coloring_solvability(eh);
+                }
+                p
+            }
+        };
+        // the operations that only compute something about the problem are not recorded
+        if !matches!(op, Op::Cleanup | Op::ComputeDiagram | Op::ComputeTriviality) {
+            p.derived_from(self, &format!("{:?}", op));
+        }
+        Ok(p)
+    }
+
+    /// Applies the operations in order, and returns a summary of each intermediate problem, together with the last one.
+    /// On failure, returns the index of the operation that failed.
+    pub fn run_pipeline(&self, ops: &[Op], eh: &mut EventHandler) -> Result<(Vec<StepSummary>, Problem), (usize, &'static str)> {
+        let mut p = self.clone();
+        let mut summaries = vec![];
+        for (i, op) in ops.iter().enumerate() {
+            eh.notify("pipeline", i, ops.len());
+            p = p.apply_op(op, eh).map_err(|e| (i, e))?;
+            summaries.push(StepSummary::new(op, &p));
+        }
+        Ok((summaries, p))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{algorithms::event::EventHandler, problem::Problem};
+
+    use super::Op;
+
+    #[test]
+    fn pipeline() {
+        let eh = &mut EventHandler::null();
+        let p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        let ops = [Op::Speedup, Op::Cleanup, Op::RenameGenerators, Op::ComputeTriviality];
+        let (summaries, last) = p.run_pipeline(&ops, eh).unwrap();
+        assert_eq!(summaries.len(), 4);
+        assert_eq!(summaries[3].trivial, Some(false));
+        assert_eq!(summaries[3].labels, last.labels().len());
+
+        let ops = [Op::Speedup, Op::HardenRemove("X".into(), false)];
+        assert_eq!(p.run_pipeline(&ops, eh).unwrap_err().0, 1);
+    }
+}
@@ -0,0 +1,144 @@
+//! The API of the library meant for crates that depend on it: problems, their operations, and the searches. It does not
+//! follow semantic versioning yet, since some of the types that it exposes, such as `Problem`, have public fields that the
+//! internal modules change, and some enums and parameters can still get new variants and fields. The parameters of the
+//! searches are `#[non_exhaustive]`, so that new parameters can be added with default values that keep the old behavior.
+//!
+//! The other modules are public because the tools of this repository (the CLI, the server, the WASM bindings) use them,
+//! but they are internal: their types change often, even in patch releases, and they should not be used by other crates.
+
+pub use crate::{
+    algorithms::{
+        autolb::LbStrategy,
+        autoub::EvaluationPolicy,
+        canonical_hash::CanonicalHash,
+        cycle_pumping::CycleBehavior,
+        event::EventHandler,
+        limits::{Limit, LimitExceeded, Limits},
+        lint::{LintFix, LintKind, LintWarning},
+        neighborhood_graph::NeighborhoodGraph,
+        passive_graph::GraphFormat,
+        problem_triviality::TrivialityAnswer,
+        upper_bound_summary::UpperBoundSummary,
+        watchdog::{BlowupWarning, Watchdog},
+    },
+    group::Label,
+    line::Degree,
+    problem::Problem,
+    serial::AutoOperation,
+    verdict::Verdict,
+};
+
+use crate::{algorithms::autoub::SearchStats, corpus::Budget};
+
+/// The errors of the operations, that are messages meant to be shown to the user.
+pub type Error = &'static str;
+
+/// The parameters of `upper_bound`, see `Problem::autoub`.
+#[non_exhaustive]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UpperBoundSearch {
+    pub max_labels: usize,
+    pub branching: usize,
+    pub max_steps: usize,
+    /// The number of colors of a coloring of the active nodes given as input, if any.
+    pub coloring: Option<usize>,
+    /// The number of colors of a coloring of the passive nodes given as input, if any.
+    pub coloring_passive: Option<usize>,
+    pub policy: EvaluationPolicy,
+}
+
+impl Default for UpperBoundSearch {
+    fn default() -> Self {
+        UpperBoundSearch { max_labels: 5, branching: 3, max_steps: 10, coloring: None, coloring_passive: None, policy: EvaluationPolicy::default() }
+    }
+}
+
+/// The parameters of `lower_bound`, see `Problem::autolb`.
+#[non_exhaustive]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LowerBoundSearch {
+    pub max_labels: usize,
+    pub branching: usize,
+    pub max_steps: usize,
+    /// The number of colors of a coloring of the active nodes given as input, if any.
+    pub coloring: Option<usize>,
+    /// The number of colors of a coloring of the passive nodes given as input, if any.
+    pub coloring_passive: Option<usize>,
+    pub strategy: LbStrategy,
+}
+
+impl Default for LowerBoundSearch {
+    fn default() -> Self {
+        LowerBoundSearch { max_labels: 5, branching: 3, max_steps: 10, coloring: None, coloring_passive: None, strategy: LbStrategy::default() }
+    }
+}
+
+/// The best sequence of operations found by a search, and what it proves.
+#[non_exhaustive]
+#[derive(Clone)]
+pub struct SearchResult {
+    pub verdict: Verdict,
+    /// Empty if the search found nothing.
+    pub sequence: Vec<(AutoOperation, Problem)>,
+    /// The numbers that describe the sequence, for upper bounds.
+    pub summary: Option<UpperBoundSummary>,
+}
+
+/// Searches for an upper bound, by hardening the problem and performing speedups until it becomes zero round solvable.
+pub fn upper_bound(p: &Problem, search: &UpperBoundSearch, eh: &mut EventHandler) -> SearchResult {
+    let mut result = SearchResult {
+        verdict: Verdict::HeuristicNoResult { budget: Budget { max_steps: search.max_steps, max_labels: search.max_labels } },
+        sequence: vec![],
+        summary: None,
+    };
+    let mut stats = SearchStats::default();
+    p.autoub(search.max_labels, search.branching, search.max_steps, search.coloring, search.coloring_passive, search.policy, &mut stats, |len, is_trivial, sequence| {
+        let summary = Some(UpperBoundSummary::of_sequence(len, is_trivial, &sequence));
+        result = SearchResult { verdict: Verdict::from_autoub(len, is_trivial), sequence, summary };
+    }, eh);
+    result
+}
+
+/// Searches for a lower bound, by performing speedups and merging labels to keep the problems small, until a problem
+/// is zero round solvable or a problem seen before is obtained.
+pub fn lower_bound(p: &Problem, search: &LowerBoundSearch, eh: &mut EventHandler) -> SearchResult {
+    let mut result = SearchResult {
+        verdict: Verdict::HeuristicNoResult { budget: Budget { max_steps: search.max_steps, max_labels: search.max_labels } },
+        sequence: vec![],
+        summary: None,
+    };
+    p.autolb(search.max_labels, search.branching, 1, search.max_steps, search.coloring, search.coloring_passive, search.strategy.clone(), |len, sequence| {
+        result = SearchResult { verdict: Verdict::from_autolb(len), sequence, summary: None };
+    }, eh);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    // written as in other crates, where the parameters cannot be given with struct literals
+    #[allow(clippy::field_reassign_with_default)]
+    #[test]
+    fn prelude() {
+        let eh = &mut EventHandler::null();
+        let p: Result<Problem, Error> = Problem::from_string("M U U\nP P P\n\nM UP\nU U");
+        let p = p.unwrap();
+        let labels: Vec<Label> = p.labels();
+        assert_eq!(labels.len(), 3);
+        assert_eq!(p.passive.degree, Degree::Finite(2));
+
+        let mut search = UpperBoundSearch::default();
+        search.max_steps = 4;
+        let result = upper_bound(&p, &search, eh);
+        assert!(result.verdict.is_proof());
+        assert!(!result.sequence.is_empty());
+        assert_eq!(result.summary.unwrap().labels_per_step.len(), result.sequence.iter().filter(|(op, _)| !matches!(op, AutoOperation::Harden(_))).count());
+
+        let mut search = LowerBoundSearch::default();
+        search.max_steps = 2;
+        let result = lower_bound(&p, &search, eh);
+        assert!(result.verdict.is_proof());
+    }
+}
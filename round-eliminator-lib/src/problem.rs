@@ -7,8 +7,14 @@ use crate::{constraint::Constraint, group::Label};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use crate::algorithms::fixpoint::FixpointDiagram;
+use crate::algorithms::lift::DegreeLift;
+use crate::algorithms::annotations::AnnotationTarget;
+use crate::algorithms::provenance::Derivation;
+use crate::algorithms::line_origins::LineOrigins;
+use crate::store::Side;
+use crate::algorithms::canonical_hash::CanonicalHash;
 
-#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Problem {
     pub active: Constraint,
     pub passive: Constraint,
@@ -26,9 +32,46 @@ pub struct Problem {
     pub orientation_coloring_sets: Option<Vec<(Vec<Label>, Vec<Label>)>>,
     pub fixpoint_diagram : Option<(Option<Vec<Label>>,FixpointDiagram)>,
     pub fixpoint_procedure_works : Option<bool>,
-    pub marks_works : Option<bool>
+    pub marks_works : Option<bool>,
+    pub label_priorities : Option<Vec<(Label, Vec<usize>)>>,
+    pub degree_lifts : Option<Vec<DegreeLift>>,
+    pub annotations : Option<Vec<(AnnotationTarget, String)>>,
+    /// The operations that produced this problem, starting from the first one, see `provenance`.
+    /// The operations keep the provenance of the problem they are applied to, and `apply_op` and the requests add the operation to it.
+    #[serde(default)]
+    pub provenance : Option<Vec<Derivation>>,
+    /// Where each line comes from, see `line_origin`.
+    #[serde(default)]
+    pub line_origins : Option<LineOrigins>,
+    /// The canonical hash of the problem for which the computed fields are valid, see `is_stale`.
+    #[serde(default)]
+    pub computed_for : Option<CanonicalHash>,
+    /// Groups of labels shown with a single name, see `set_label_folds`.
+    #[serde(default)]
+    pub label_folds : Option<Vec<(Vec<Label>, String)>>,
+    /// The labels that encode a coloring, see `set_color_labels`.
+    #[serde(default)]
+    pub color_labels : Option<Vec<Label>>,
+    /// The labels that may appear only on one side, see `set_one_sided_labels`.
+    #[serde(default)]
+    pub one_sided_labels : Option<Vec<(Label, Side)>>
 }
 
+/// Two problems are equal if they have the same constraints, up to the order of lines and parts, and the same label names.
+/// Whatever has been computed about the problems (diagram, triviality, ...) is not taken into account.
+impl PartialEq for Problem {
+    fn eq(&self, other: &Self) -> bool {
+        self.active.degree == other.active.degree
+            && self.passive.degree == other.passive.degree
+            && self.orientation_given == other.orientation_given
+            && self.mapping_label_text.iter().sorted().eq(other.mapping_label_text.iter().sorted())
+            && self.active.canonical_lines() == other.active.canonical_lines()
+            && self.passive.canonical_lines() == other.passive.canonical_lines()
+    }
+}
+
+impl Eq for Problem {}
+
 pub type DiagramDirect = (Vec<(Label, Vec<Label>)>, Vec<(Label, Label)>);
 
 impl Problem {
@@ -63,7 +106,16 @@ impl Problem {
             orientation_given: None,
             fixpoint_diagram : None,
             fixpoint_procedure_works : None,
-            marks_works : None
+            marks_works : None,
+            label_priorities : None,
+            degree_lifts : None,
+            annotations : None,
+            provenance : None,
+            line_origins : None,
+            computed_for : None,
+            label_folds : None,
+            color_labels : None,
+            one_sided_labels : None
         };
         Ok(p)
     }
@@ -84,17 +136,72 @@ impl Problem {
         labels
     }
 
-    
+    /// Returns an error if some of the given labels is not a label of the problem, for operations that would otherwise create labels without a name.
+    pub fn check_labels(&self, labels: &[Label]) -> Result<(), &'static str> {
+        if labels.iter().all(|l| self.mapping_label_text.iter().any(|(m, _)| m == l)) {
+            Ok(())
+        } else {
+            Err("The operation refers to a label that is not in the problem")
+        }
+    }
+
+    /// Fills the fields of this problem that have not been computed with the ones of `other`, which must be equal to this problem.
+    /// Since equal problems have the same labels and the same constraints, what has been computed for one of them is valid for the other.
+    pub fn keep_computed_from(&mut self, other: Problem) {
+        fn fill<T>(field: &mut Option<T>, other: Option<T>) {
+            if field.is_none() {
+                *field = other;
+            }
+        }
+        debug_assert!(*self == other);
+        let mut other = other;
+        self.discard_stale_computed_stuff();
+        other.discard_stale_computed_stuff();
+        fill(&mut self.mapping_label_oldlabels, other.mapping_label_oldlabels);
+        fill(&mut self.mapping_oldlabel_labels, other.mapping_oldlabel_labels);
+        fill(&mut self.mapping_oldlabel_text, other.mapping_oldlabel_text);
+        fill(&mut self.trivial_sets, other.trivial_sets);
+        fill(&mut self.coloring_sets, other.coloring_sets);
+        fill(&mut self.diagram_indirect, other.diagram_indirect);
+        fill(&mut self.diagram_indirect_old, other.diagram_indirect_old);
+        fill(&mut self.diagram_direct, other.diagram_direct);
+        fill(&mut self.orientation_trivial_sets, other.orientation_trivial_sets);
+        fill(&mut self.orientation_coloring_sets, other.orientation_coloring_sets);
+        fill(&mut self.fixpoint_diagram, other.fixpoint_diagram);
+        fill(&mut self.fixpoint_procedure_works, other.fixpoint_procedure_works);
+        fill(&mut self.marks_works, other.marks_works);
+        fill(&mut self.label_priorities, other.label_priorities);
+        fill(&mut self.degree_lifts, other.degree_lifts);
+        fill(&mut self.annotations, other.annotations);
+        fill(&mut self.provenance, other.provenance);
+        fill(&mut self.line_origins, other.line_origins);
+        fill(&mut self.label_folds, other.label_folds);
+        fill(&mut self.color_labels, other.color_labels);
+        fill(&mut self.one_sided_labels, other.one_sided_labels);
+        if self.has_computed_stuff() {
+            self.mark_computed();
+        }
+    }
 }
 
 impl Display for Problem {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mapping = self.mapping_label_text.iter().cloned().collect();
-        for line in &self.active.lines {
+        if let Some(order) = self.label_display_order() {
+            for line in self.active.lines_ordered(&order) {
+                writeln!(f, "{}", line.to_string_ordered(&mapping, &order))?;
+            }
+            writeln!(f)?;
+            for line in self.passive.lines_ordered(&order) {
+                writeln!(f, "{}", line.to_string_ordered(&mapping, &order))?;
+            }
+            return Ok(());
+        }
+        for line in self.active.lines_canonical_order() {
             writeln!(f, "{}", line.to_string(&mapping))?;
         }
         writeln!(f)?;
-        for line in &self.passive.lines {
+        for line in self.passive.lines_canonical_order() {
             writeln!(f, "{}", line.to_string(&mapping))?;
         }
         Ok(())
@@ -106,6 +213,43 @@ mod tests {
 
     use crate::{algorithms::event::EventHandler, problem::Problem};
 
+    #[test]
+    fn degenerate() {
+        let eh = &mut EventHandler::null();
+        let mut empty_active = Problem::from_string("A A\n\nA B").unwrap();
+        empty_active.active.lines.clear();
+        let mut empty_passive = Problem::from_string("A A\n\nA B").unwrap();
+        empty_passive.passive.lines.clear();
+        let single = Problem::from_string("A A\n\nA A").unwrap();
+        let passive_star = Problem::from_string("A A\n\nA*").unwrap();
+        for p in [&empty_active, &empty_passive, &single, &passive_star] {
+            let mut q = p.speedup(eh);
+            crate::serial::fix_problem(&mut q, true, true, eh);
+            assert!(q.trivial_sets.is_some());
+            let q = p.repeat_merge_equivalent_labels(eh);
+            assert!(q.labels().len() <= p.labels().len());
+            assert!(p.check_labels(&[0]).is_ok());
+            assert!(p.check_labels(&[0, 7]).is_err());
+        }
+
+        // nothing can be solved if a side has no configurations
+        let mut q = empty_active.repeat_merge_equivalent_labels(eh);
+        q.compute_triviality(eh);
+        assert!(q.trivial_sets.unwrap().is_empty());
+        let mut q = single.clone();
+        q.compute_triviality(eh);
+        assert_eq!(q.trivial_sets, Some(vec![vec![0]]));
+
+        // the coloring solvability of a passive side of degree * is unknown, and it is not needed by the searches
+        let mut q = passive_star.clone();
+        q.compute_coloring_solvability(eh);
+        assert_eq!(q.coloring_sets, None);
+        assert_eq!(q.explained_coloring_sets(), None);
+        let mut found = None;
+        q.autoub(2, 2, 2, Some(3), None, Default::default(), &mut Default::default(), |len, _, _| found = Some(len), eh);
+        assert_eq!(found, Some(0));
+    }
+
     #[test]
     #[should_panic]
     fn parsing_err() {
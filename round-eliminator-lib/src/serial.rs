@@ -1,8 +1,11 @@
+use std::{cell::RefCell, collections::HashMap, sync::Mutex};
+
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
-use crate::{algorithms::{event::EventHandler, fixpoint::FixpointType}, group::Label, line::Degree, problem::Problem};
+use crate::{algorithms::{event::{EventHandler, EventThrottle}, fixpoint::FixpointType, passive_graph::GraphFormat, lift::LiftPadding, relabeling::RelabelingMismatch, defective::{DefectiveRung, first_zero_round_rung}, step_diff::StepDiff, autoub::{EvaluationPolicy, FrontierPoint, SearchStats}, search_tree::SearchTree, autolb::LbStrategy, distributed::{global_shards, Shard}, annotations::AnnotationTarget, order_invariant::LogStarLowerBound, problem_triviality::TrivialityAnswer, cycle_pumping::CycleBehavior, speedup_estimate::SpeedupEstimate, explain::ExplainedSet, harden::HardenMode, paper_notation::Interpretation, merge_preview::MergePreview, decision_diagram::DecisionDiagram, renaming_presets::RenamingPreset, known_problems::KnownProblemMatch, limits::{LimitExceeded, Limits}, watchdog::BlowupWarning, line_origins::LineOrigin, label_schedule::LabelSchedule, fixed_point_core::CoreRemoval, degree_check::DegreeMismatch, lint::{LintFix, LintWarning}, coloring_reductions::ColoringReductions, coordinate_descent::DescentParams, self_relaxation::{SelfRelaxation, SelfRelaxationParams}, upper_bound_summary::UpperBoundSummary, pruning_rules::PruningRules}, group::Label, line::Degree, pipeline::{Op, StepSummary}, problem::Problem, irregular::IrregularProblem, store::{global_store, resolve_problem_references, ProblemId, ProblemStore, Side}, corpus::Budget, verdict::Verdict};
 
-fn fix_problem(new: &mut Problem, sort_by_strength: bool, compute_triviality_and_coloring : bool, eh: &mut EventHandler) {
+pub(crate) fn fix_problem(new: &mut Problem, sort_by_strength: bool, compute_triviality_and_coloring : bool, eh: &mut EventHandler) {
     if new.passive.degree == Degree::Finite(2) {
         new.diagram_indirect = None;
         new.compute_diagram(eh);
@@ -11,13 +14,8 @@ fn fix_problem(new: &mut Problem, sort_by_strength: bool, compute_triviality_and
             new.sort_active_by_strength();
         }
         if compute_triviality_and_coloring {
-            new.compute_triviality(eh);
-            new.compute_coloring_solvability(eh);
-            if let Some(outdegree) = new.orientation_given {
-                new.orientation_trivial_sets = None;
-                new.compute_triviality_given_orientation(outdegree, eh);
-                new.compute_coloring_solvability_given_orientation(outdegree, eh);
-            }
+            new.orientation_trivial_sets = None;
+            new.precompute_all(eh);
         }
     } else {
         new.discard_useless_stuff(false, eh);
@@ -27,24 +25,218 @@ fn fix_problem(new: &mut Problem, sort_by_strength: bool, compute_triviality_and
     }
 }
 
+/// Handles a request, calling `f` for each response. Problems uploaded with `Request::Upload` are kept in `global_store`,
+/// which is shared by all the callers in the process, so this is meant for processes that serve a single client, as the wasm module.
+/// Servers should give each client its own store, with `request_json_in`.
 pub fn request_json<F>(req: &str, f: F)
 where
     F: Fn(String, bool),
 {
-    let req: Request = serde_json::from_str(req).unwrap();
+    request_json_in(req, global_store(), f);
+}
+
+/// Same as `request_json`, but problems are uploaded to, and referenced from, the given store.
+pub fn request_json_in<F>(req: &str, store: &Mutex<ProblemStore>, f: F)
+where
+    F: Fn(String, bool),
+{
+    request_json_with(req, store, |resp, send_to_client| f(serde_json::to_string(resp).unwrap(), send_to_client));
+}
+
+/// Same as `request_json`, but progress events are rate-limited by `throttle` and sent in batches, as `Response::Events`.
+/// `now` gives the current time in milliseconds.
+pub fn request_json_throttled<F, C>(req: &str, f: F, throttle: EventThrottle, now: C)
+where
+    F: Fn(String, bool),
+    C: Fn() -> f64,
+{
+    request_json_with(req, global_store(), throttled(|resp, send_to_client| f(serde_json::to_string(resp).unwrap(), send_to_client), throttle, now));
+}
+
+fn request_json_with<F>(req: &str, store: &Mutex<ProblemStore>, f: F)
+where
+    F: Fn(&Response, bool),
+{
+    let Ok(req) = serde_json::from_str::<serde_json::Value>(req) else {
+        f(&Response::E("The request is not valid JSON".into()), true);
+        f(&Response::Done, true);
+        return;
+    };
+    request_value(req, store, f);
+}
+
+/// Same as `request_json`, but the request and the responses are encoded in CBOR.
+/// Large problems are much faster to encode and decode this way, and take less memory.
+pub fn request_cbor<F>(req: &[u8], f: F)
+where
+    F: Fn(Vec<u8>, bool),
+{
+    request_cbor_in(req, global_store(), f);
+}
+
+/// Same as `request_cbor`, but problems are uploaded to, and referenced from, the given store, see `request_json_in`.
+pub fn request_cbor_in<F>(req: &[u8], store: &Mutex<ProblemStore>, f: F)
+where
+    F: Fn(Vec<u8>, bool),
+{
+    request_cbor_with(req, store, |resp, send_to_client| f(encode_cbor(resp), send_to_client));
+}
+
+/// Same as `request_cbor`, with progress events handled as in `request_json_throttled`.
+pub fn request_cbor_throttled<F, C>(req: &[u8], f: F, throttle: EventThrottle, now: C)
+where
+    F: Fn(Vec<u8>, bool),
+    C: Fn() -> f64,
+{
+    request_cbor_with(req, global_store(), throttled(|resp, send_to_client| f(encode_cbor(resp), send_to_client), throttle, now));
+}
+
+fn encode_cbor(resp: &Response) -> Vec<u8> {
+    let mut v = vec![];
+    ciborium::ser::into_writer(resp, &mut v).unwrap();
+    v
+}
+
+fn request_cbor_with<F>(req: &[u8], store: &Mutex<ProblemStore>, f: F)
+where
+    F: Fn(&Response, bool),
+{
+    if let Ok(decoded) = ciborium::de::from_reader::<Request, _>(req) {
+        let operation = cbor_variant_name(req).unwrap_or_default().to_string();
+        request_decoded(decoded, operation, store, f);
+        return;
+    }
+    // requests that reference stored problems cannot be decoded directly, the references are resolved on the generic value first
+    let Ok(req) = ciborium::de::from_reader::<serde_json::Value, _>(req) else {
+        f(&Response::E("The request is not valid CBOR".into()), true);
+        f(&Response::Done, true);
+        return;
+    };
+    request_value(req, store, f);
+}
+
+/// The name of the request, read from the beginning of its CBOR encoding without decoding the rest:
+/// requests without parameters are encoded as text, and the other ones as maps with a single entry, whose key is the name.
+fn cbor_variant_name(bytes: &[u8]) -> Option<&str> {
+    let (major, len, rest) = cbor_header(bytes)?;
+    let (len, rest) = match major {
+        3 => (len, rest),
+        5 if len == 1 => match cbor_header(rest)? {
+            (3, len, rest) => (len, rest),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    std::str::from_utf8(rest.get(..len)?).ok()
+}
+
+/// The major type and the argument of the CBOR item at the beginning of `bytes`, and the bytes that follow its header.
+fn cbor_header(bytes: &[u8]) -> Option<(u8, usize, &[u8])> {
+    let (&first, rest) = bytes.split_first()?;
+    let size = match first & 0x1f {
+        info @ 0..=23 => return Some((first >> 5, info as usize, rest)),
+        24 => 1,
+        25 => 2,
+        26 => 4,
+        27 => 8,
+        _ => return None,
+    };
+    let argument = rest.get(..size)?.iter().fold(0usize, |a, &b| (a << 8) | b as usize);
+    Some((first >> 5, argument, &rest[size..]))
+}
+
+/// Holds back the progress events sent to the client until `throttle` allows them, and sends the ones that are still waiting
+/// before any other response, so that the client sees them in order. Events that are not sent to the client are left untouched.
+fn throttled<F, C>(f: F, throttle: EventThrottle, now: C) -> impl Fn(&Response, bool)
+where
+    F: Fn(&Response, bool),
+    C: Fn() -> f64,
+{
+    let throttle = RefCell::new(throttle);
+    move |resp, send_to_client| {
+        if !send_to_client {
+            f(resp, send_to_client);
+            return;
+        }
+        if let Response::Event(s, x, t) = resp {
+            let batch = throttle.borrow_mut().push((s.clone(), *x, *t), now());
+            if let Some(batch) = batch {
+                f(&Response::Events(batch), true);
+            }
+            return;
+        }
+        let pending = throttle.borrow_mut().flush();
+        if !pending.is_empty() && !matches!(resp, Response::Done) {
+            f(&Response::Events(pending), true);
+        }
+        f(resp, send_to_client);
+    }
+}
+
+fn request_value<F>(mut req: serde_json::Value, store: &Mutex<ProblemStore>, f: F)
+where
+    F: Fn(&Response, bool),
+{
+    let handler = |resp: Response| {
+        f(&resp, true);
+    };
+
+    if let Err(s) = resolve_problem_references(&mut req, &store.lock().unwrap()) {
+        handler(Response::E(s.into()));
+        handler(Response::Done);
+        return;
+    }
+    let operation = match &req {
+        serde_json::Value::Object(o) => o.keys().next().cloned().unwrap_or_default(),
+        v => v.as_str().unwrap_or_default().to_string(),
+    };
+    match serde_json::from_value::<Request>(req) {
+        Ok(req) => request_decoded(req, operation, store, f),
+        Err(e) => {
+            handler(Response::E(format!("The request is not supported: {}", e)));
+            handler(Response::Done);
+        }
+    }
+}
+
+/// Handles a request whose problem references have been resolved, `operation` being the name of the request.
+fn request_decoded<F>(mut req: Request, operation: String, store: &Mutex<ProblemStore>, f: F)
+where
+    F: Fn(&Response, bool),
+{
     let handler = |resp: Response| {
-        let s = serde_json::to_string(&resp).unwrap();
-        f(s, true);
+        f(&resp, true);
+    };
+
+    // what has been computed for the problem is trusted only if it has been computed for the problem as it is now
+    if let Some(p) = req.first_problem_mut() {
+        p.discard_stale_computed_stuff();
+    }
+    // every problem sent back is recorded as derived from the first problem of the request, if it is different from it
+    let parent = req.first_problem().cloned();
+    // problems that are not obtained by parsing may have lines whose parts overflow when joined
+    if let Some(Err(overflow)) = parent.as_ref().map(|p| p.check_exponents()) {
+        handler(Response::E(overflow.to_string()));
+        handler(Response::Done);
+        return;
+    }
+    let handler = |mut resp: Response| {
+        if let (Response::P(p), Some(parent)) = (&mut resp, &parent) {
+            if p != parent {
+                p.derived_from(parent, &operation);
+            }
+        }
+        handler(resp);
     };
 
     let mut eh = EventHandler::with(|x: (String, usize, usize)| {
         let resp = Response::Event(x.0, x.1, x.2);
         handler(resp);
-    });
+    })
+    .with_warnings(|w| handler(Response::BlowupWarning(w)));
 
     let handler_ignore = |resp: Response| {
-        let s = serde_json::to_string(&resp).unwrap();
-        f(s, false);
+        f(&resp, false);
     };
 
     let mut eh_ignore = EventHandler::with(|x: (String, usize, usize)| {
@@ -57,8 +249,64 @@ where
             handler(Response::Pong);
             return;
         }
+        Request::Upload(problem) => {
+            match store.lock().unwrap().insert(problem) {
+                Ok(id) => handler(Response::Stored(id)),
+                Err(s) => handler(Response::E(s.into())),
+            }
+        }
+        Request::GetLines(id, side, offset, count) => {
+            let page = store.lock().unwrap().get(id).map(|p| p.lines_text(side, offset, count));
+            match page {
+                Some((total, lines)) => handler(Response::Lines(total, lines)),
+                None => handler(Response::E("The problem is not in the store anymore".into())),
+            }
+        }
+        Request::SampleLines(id, side, count, seed) => {
+            let sample = store.lock().unwrap().get(id).map(|p| p.sample_lines(side, count, seed));
+            match sample {
+                Some((total, lines)) => handler(Response::Lines(total, lines)),
+                None => handler(Response::E("The problem is not in the store anymore".into())),
+            }
+        }
+        Request::CheckConfiguration(id, side, configuration) => {
+            let allowed = store.lock().unwrap().get(id).map(|p| p.allows_configuration(side, &configuration));
+            match allowed {
+                Some(Ok(allowed)) => handler(Response::ConfigurationAllowed(allowed)),
+                Some(Err(s)) => handler(Response::E(s.into())),
+                None => handler(Response::E("The problem is not in the store anymore".into())),
+            }
+        }
+        Request::ForgetProblem(id) => {
+            store.lock().unwrap().remove(id);
+        }
         Request::NewProblem(active, passive) => {
-            match Problem::from_string_active_passive(active, passive) {
+            match Problem::from_string_active_passive(&active, &passive) {
+                Ok(mut new) => {
+                    fix_problem(&mut new, true, true,&mut eh);
+                    handler(Response::P(new))
+                }
+                Err(s) => {
+                    let active_lines = active.lines().count();
+                    let mismatches = Problem::check_text_degrees(&format!("{}\n\n{}", active, passive));
+                    let details : Vec<String> = mismatches.iter().map(|m|{
+                        // the passive lines come after the active ones and an empty line, unless the active text itself contains empty lines
+                        let (side, n) = match m.side {
+                            Side::Active => ("active", Some(m.line_number)),
+                            Side::Passive => ("passive", m.line_number.checked_sub(active_lines + 1).filter(|&n| n > 0)),
+                        };
+                        let position = match n {
+                            Some(n) => format!("Line {} of the {} side", n, side),
+                            None => format!("Line {} of the problem, on the {} side,", m.line_number, side),
+                        };
+                        format!("\n{} ({}) has degree {}, while most lines have degree {}.", position, m.text, m.degree, m.expected)
+                    }).collect();
+                    handler(Response::E(format!("{}{}", s, details.concat())))
+                }
+            }
+        }
+        Request::NewProblemPadded(active, passive, pad) => {
+            match Problem::from_string_padded(&format!("{}\n\n{}", active, passive), &pad) {
                 Ok(mut new) => {
                     fix_problem(&mut new, true, true,&mut eh);
                     handler(Response::P(new))
@@ -66,7 +314,18 @@ where
                 Err(s) => handler(Response::E(s.into())),
             }
         }
+        Request::NewProblemFromPaper(text) => {
+            match Problem::from_paper_notation(&text) {
+                Ok((mut new, interpretations)) => {
+                    fix_problem(&mut new, true, true,&mut eh);
+                    handler(Response::Interpretations(interpretations));
+                    handler(Response::P(new))
+                }
+                Err(s) => handler(Response::E(s.into())),
+            }
+        }
         Request::Speedup(mut problem) => {
+            problem.optimize_internal_layout();
             if problem.diagram_indirect.is_none() {
                 problem.compute_partial_diagram(&mut eh);
             }
@@ -74,6 +333,39 @@ where
             fix_problem(&mut new, true, true, &mut eh);
             handler(Response::P(new));
         }
+        Request::SpeedupWithLimits(mut problem, limits) => {
+            problem.optimize_internal_layout();
+            if problem.diagram_indirect.is_none() {
+                problem.compute_partial_diagram(&mut eh);
+            }
+            match problem.speedup_with_limits(&limits, &mut eh) {
+                Ok(mut new) => {
+                    fix_problem(&mut new, true, true, &mut eh);
+                    handler(Response::P(new));
+                }
+                Err(e) => handler(Response::LimitExceeded(e)),
+            }
+        }
+        Request::MaximizeActive(problem, limits) => match problem.maximize_active(&limits, &mut eh) {
+            Ok(new) => handler(Response::P(new)),
+            Err(e) => handler(Response::LimitExceeded(e)),
+        },
+        Request::Pipeline(problem, ops) => match problem.run_pipeline(&ops, &mut eh) {
+            Ok((summaries, last)) => handler(Response::Pipeline(summaries, last)),
+            Err((i, s)) => handler(Response::E(format!("Operation {} of the pipeline failed: {}", i + 1, s))),
+        },
+        Request::SpeedupRestricted(mut problem, candidates) => {
+            if problem.diagram_indirect.is_none() {
+                problem.compute_partial_diagram(&mut eh);
+            }
+            match problem.speedup_restricted(&candidates, &mut eh) {
+                Ok(mut new) => {
+                    fix_problem(&mut new, true, true, &mut eh);
+                    handler(Response::P(new));
+                }
+                Err(s) => handler(Response::E(s.into())),
+            }
+        }
         Request::FixpointBasic(mut problem, partial, triviality_only, sublabels) => {
             if problem.diagram_indirect.is_none() {
                 problem.compute_partial_diagram(&mut eh);
@@ -131,12 +423,14 @@ where
                 let mut new = problem.inverse_speedup();
                 if new.active.degree != Degree::Finite(1) {
                     new.trivial_sets = Some(vec![]);
+                    new.mark_computed();
                 }
                 fix_problem(&mut new, false, false, &mut eh);
                 handler(Response::P(new));
             }
         }
         Request::SpeedupMaximize(mut problem) => {
+            problem.optimize_internal_layout();
             if problem.diagram_indirect.is_none() {
                 problem.compute_partial_diagram(&mut eh);
             }
@@ -156,6 +450,7 @@ where
             handler(Response::P(new));
         }
         Request::SpeedupMaximizeRenamegen(mut problem) => {
+            problem.optimize_internal_layout();
             if problem.diagram_indirect.is_none() {
                 problem.compute_partial_diagram(&mut eh);
             }
@@ -179,23 +474,143 @@ where
                 Err(s) => handler(Response::E(s.into())),
             }
         }
-        Request::SimplifyMerge(problem, a, b) => {
-            let mut new = problem.relax_merge(a, b);
-            fix_problem(&mut new, true, true, &mut eh);
-            handler(Response::P(new));
+        Request::SimplifyMerge(problem, a, b) => match problem.check_labels(&[a, b]) {
+            Ok(()) => {
+                let mut new = problem.relax_merge(a, b);
+                fix_problem(&mut new, true, true, &mut eh);
+                handler(Response::P(new));
+            }
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::SimplifyMergeGroup(problem, labels, to) => match problem.check_labels(&[&labels[..], &[to]].concat()) {
+            Ok(()) => {
+                let mut new = problem;
+                for label in labels {
+                    new = new.relax_merge(label, to);
+                }
+                fix_problem(&mut new, true, true, &mut eh);
+                handler(Response::P(new));
+            }
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::MergeDiagramNodes(mut problem, nodes) => {
+            if problem.diagram_indirect.is_none() {
+                problem.compute_partial_diagram(&mut eh);
+            }
+            match problem.merge_diagram_nodes(&nodes) {
+                Ok(mut new) => {
+                    fix_problem(&mut new, true, true, &mut eh);
+                    handler(Response::P(new));
+                }
+                Err(s) => handler(Response::E(s.into())),
+            }
         }
-        Request::SimplifyMergeGroup(problem, labels, to) => {
-            let mut new = problem;
-            for label in labels {
-                new = new.relax_merge(label, to);
+        Request::ContractChain(mut problem, from, to) => {
+            if problem.diagram_indirect.is_none() {
+                problem.compute_partial_diagram(&mut eh);
+            }
+            match problem.contract_chain(from, to) {
+                Ok(mut new) => {
+                    fix_problem(&mut new, true, true, &mut eh);
+                    handler(Response::P(new));
+                }
+                Err(s) => handler(Response::E(s.into())),
             }
-            fix_problem(&mut new, true, true, &mut eh);
-            handler(Response::P(new));
         }
-        Request::SimplifyAddarrow(problem, a, b) => {
-            let mut new = problem.relax_addarrow(a, b);
-            fix_problem(&mut new, true, true, &mut eh);
-            handler(Response::P(new));
+        Request::RemoveDiagramNode(mut problem, node) => {
+            if problem.diagram_indirect.is_none() {
+                problem.compute_partial_diagram(&mut eh);
+            }
+            match problem.remove_diagram_node(node) {
+                Ok(mut new) => {
+                    fix_problem(&mut new, true, true, &mut eh);
+                    handler(Response::P(new));
+                }
+                Err(s) => handler(Response::E(s.into())),
+            }
+        }
+        Request::SimplifyAddarrow(problem, a, b) => match problem.check_labels(&[a, b]) {
+            Ok(()) => {
+                let mut new = problem.relax_addarrow(a, b);
+                fix_problem(&mut new, true, true, &mut eh);
+                handler(Response::P(new));
+            }
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::PossibleAddarrow(mut problem) => {
+            if problem.diagram_indirect.is_none() {
+                problem.compute_partial_diagram(&mut eh);
+            }
+            match problem.possible_addarrow().and_then(|possible| Ok((possible, problem.safe_addarrow()?))) {
+                Ok((possible, safe)) => handler(Response::Addarrow(possible, safe)),
+                Err(s) => handler(Response::E(s.into())),
+            }
+        }
+        Request::SplitLabel(problem, label, copies) => match problem.split_label(label, copies) {
+            Ok((mut new, _)) => {
+                fix_problem(&mut new, true, true, &mut eh);
+                handler(Response::P(new));
+            }
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::ProjectActivePort(problem) => match problem.project_active_port() {
+            Ok(mut new) => {
+                fix_problem(&mut new, true, true, &mut eh);
+                handler(Response::P(new));
+            }
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::ProjectPassivePort(problem) => match problem.project_passive_port(&mut eh) {
+            Ok(mut new) => {
+                fix_problem(&mut new, true, true, &mut eh);
+                handler(Response::P(new));
+            }
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::LiftDegree(problem, padding) => match problem.lift_degree(padding) {
+            Ok(mut new) => {
+                fix_problem(&mut new, true, true, &mut eh);
+                handler(Response::P(new));
+            }
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::ColoringReductions(problem, min_colors, max_colors, budget) => match problem.coloring_reductions(min_colors, max_colors, budget, &mut eh) {
+            Ok(reductions) => handler(Response::ColoringReductions(reductions)),
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::CheckDegrees(problem) => {
+            handler(Response::DegreeMismatches(problem.check_degrees()));
+        }
+        Request::Lint(text) => {
+            handler(Response::LintWarnings(Problem::lint_text(&text)));
+        }
+        Request::ApplyLintFix(text, fix) => {
+            handler(Response::S(Problem::apply_lint_fix(&text, &fix)));
+        }
+        Request::PadToDegree(problem, delta, filler) => match problem.pad_to_degree(delta, &filler) {
+            Ok(mut new) => {
+                fix_problem(&mut new, true, true, &mut eh);
+                handler(Response::P(new));
+            }
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::MatchingRelabeling(problem, other) => {
+            let result = problem
+                .find_matching_relabeling(&other)
+                .map(|map| map.into_iter().sorted().collect());
+            handler(Response::Relabeling(result));
+        }
+        Request::DefectiveLadder(colors, degree, max_defect, input_colors) => {
+            match Problem::defective_coloring_ladder(colors, degree, max_defect) {
+                Ok(mut ladder) => {
+                    let first = first_zero_round_rung(&ladder, input_colors, &mut eh);
+                    for (_, p) in ladder.iter_mut() {
+                        fix_problem(p, true, true, &mut eh);
+                    }
+                    handler(Response::DefectiveLadder(ladder, first));
+                }
+                Err(s) => handler(Response::E(s.into())),
+            }
         }
         Request::HardenRemove(mut problem, label, keep_predecessors) => {
             if keep_predecessors && problem.diagram_indirect.is_none() {
@@ -213,26 +628,65 @@ where
             fix_problem(&mut new, true, true, &mut eh);
             handler(Response::P(new));
         }
+        Request::HardenKeepWithMode(mut problem, labels, mode) => {
+            if mode != HardenMode::Remove && problem.diagram_indirect.is_none() {
+                problem.compute_partial_diagram(&mut eh);
+            }
+            let mut new = problem.harden_keep_with_mode(&labels.into_iter().collect(), mode);
+            fix_problem(&mut new, true, true, &mut eh);
+            handler(Response::P(new));
+        }
+        Request::Complement(problem, active) => match problem.complement(active) {
+            Ok(mut new) => {
+                fix_problem(&mut new, true, true, &mut eh);
+                handler(Response::P(new));
+            }
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::Power(problem, k) => match problem.power(k) {
+            Ok(mut new) => {
+                fix_problem(&mut new, true, true, &mut eh);
+                handler(Response::P(new));
+            }
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::DisjointUnion(problem, other) => match problem.disjoint_union(&other) {
+            Ok(mut new) => {
+                fix_problem(&mut new, true, true, &mut eh);
+                handler(Response::P(new));
+            }
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::Induced(problem, labels) => {
+            let mut new = problem.induced(&labels.into_iter().collect());
+            fix_problem(&mut new, true, true, &mut eh);
+            handler(Response::P(new));
+        }
         Request::MergeEquivalentLabels(problem) => {
             let mut new = problem.merge_equivalent_labels();
             fix_problem(&mut new, true, true, &mut eh);
             handler(Response::P(new));
         }
-        Request::Maximize(mut problem) => {
+        Request::Maximize(mut problem, limits) => {
+            problem.optimize_internal_layout();
             problem.diagram_indirect = None;
-            problem.passive.maximize(&mut eh);
-            problem.compute_diagram(&mut eh);
-            problem.discard_useless_stuff(true, &mut eh);
-            problem.sort_active_by_strength();
-            problem.compute_triviality(&mut eh);
-            if problem.passive.degree == Degree::Finite(2) {
-                problem.compute_coloring_solvability(&mut eh);
-                if let Some(outdegree) = problem.orientation_given {
-                    problem.compute_triviality_given_orientation(outdegree, &mut eh);
-                    problem.compute_coloring_solvability_given_orientation(outdegree, &mut eh);
+            match problem.passive.maximize_with_limits(&limits, &mut eh) {
+                Ok(()) => {
+                    problem.compute_diagram(&mut eh);
+                    problem.discard_useless_stuff(true, &mut eh);
+                    problem.sort_active_by_strength();
+                    problem.compute_triviality(&mut eh);
+                    if problem.passive.degree == Degree::Finite(2) {
+                        problem.compute_coloring_solvability(&mut eh);
+                        if let Some(outdegree) = problem.orientation_given {
+                            problem.compute_triviality_given_orientation(outdegree, &mut eh);
+                            problem.compute_coloring_solvability_given_orientation(outdegree, &mut eh);
+                        }
+                    }
+                    handler(Response::P(problem));
                 }
+                Err(e) => handler(Response::LimitExceeded(e)),
             }
-            handler(Response::P(problem));
         }
         Request::RenameGenerators(mut problem) => match problem.rename_by_generators() {
             Ok(()) => {
@@ -244,6 +698,95 @@ where
             Ok(()) => handler(Response::P(problem)),
             Err(s) => handler(Response::E(s.into())),
         },
+        Request::RenamingPresets(problem) => {
+            handler(Response::RenamingPresets(problem.renaming_presets(&mut eh)));
+        }
+        Request::KnownProblems(problem, max_speedups) => {
+            handler(Response::KnownProblems(problem.known_problems(max_speedups, &mut eh)));
+        }
+        Request::LineOrigins(problem) => {
+            let text: HashMap<_, _> = problem.mapping_label_text.iter().cloned().collect();
+            let origins = [(Side::Active, &problem.active), (Side::Passive, &problem.passive)]
+                .into_iter()
+                .flat_map(|(side, c)| c.lines.iter().map(move |line| (side, line)))
+                .map(|(side, line)| (side, line.to_string(&text), problem.line_origin(side, line)))
+                .collect();
+            handler(Response::LineOrigins(origins));
+        }
+        Request::Annotate(mut problem, target, note) => {
+            problem.annotate(target, note);
+            handler(Response::P(problem));
+        }
+        Request::AnnotationsText(problem) => {
+            handler(Response::S(problem.annotations_text()));
+        }
+        Request::FoldLabels(mut problem, groups) => match problem.set_label_folds(&groups) {
+            Ok(()) => handler(Response::P(problem)),
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::ColorLabels(mut problem, labels) => match problem.set_color_labels(&labels) {
+            Ok(()) => handler(Response::P(problem)),
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::OneSidedLabels(mut problem, labels) => match problem.set_one_sided_labels(&labels) {
+            Ok(()) => handler(Response::P(problem)),
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::FoldedText(problem) => {
+            handler(Response::S(problem.to_string_folded()));
+        }
+        Request::CanonicalText(problem) => {
+            handler(Response::S(problem.to_canonical_text()));
+        }
+        Request::LabelPriorities(mut problem, priorities) => match problem.set_label_priorities(&priorities) {
+            Ok(()) => handler(Response::P(problem)),
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::PassiveGraph(problem, format) => match problem.passive_graph_to_string(format) {
+            Ok(s) => handler(Response::S(s)),
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::NeighborhoodGraph(problem, format) => match problem.neighborhood_graph_to_string(format, &mut eh) {
+            Ok(s) => handler(Response::S(s)),
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::ImportPassiveGraph(mut problem, format, text) => match problem.set_passive_from_graph(format, &text) {
+            Ok(()) => {
+                fix_problem(&mut problem, true, true, &mut eh);
+                handler(Response::P(problem));
+            }
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::DiagramGraph(mut problem, format) => {
+            // a partial diagram may miss some arrows, and the exported diagram is taken as the diagram of the problem
+            if !problem.has_full_diagram() {
+                problem.diagram_indirect = None;
+                problem.diagram_direct = None;
+                problem.compute_diagram(&mut eh);
+            }
+            match problem.diagram_to_string(format) {
+                Ok(s) => handler(Response::S(s)),
+                Err(s) => handler(Response::E(s.into())),
+            }
+        }
+        Request::ImportDiagramGraph(mut problem, format, text) => {
+            if problem.diagram_indirect.is_none() {
+                problem.compute_partial_diagram(&mut eh);
+            }
+            match problem.import_diagram_arrows(format, &text, &mut eh) {
+                Ok((mut new, _)) => {
+                    fix_problem(&mut new, true, true, &mut eh);
+                    handler(Response::P(new));
+                }
+                Err(s) => handler(Response::E(s.into())),
+            }
+        }
+        Request::Homomorphism(problem, template_size, template_edges) => {
+            match problem.homomorphism_to_template(template_size, &template_edges, &mut eh) {
+                Ok(h) => handler(Response::Homomorphism(h)),
+                Err(s) => handler(Response::E(s.into())),
+            }
+        }
         Request::Orientation(mut problem, outdegree) => {
             problem.orientation_given = Some(outdegree);
             problem.orientation_coloring_sets = None;
@@ -254,27 +797,260 @@ where
             }
             handler(Response::P(problem));
         },
-        Request::AutoUb(problem, b_max_labels, max_labels, b_branching, branching, b_max_steps, max_steps, coloring_given, coloring, coloring_given_passive, coloring_passive) => {
+        Request::AutoUb(problem, b_max_labels, max_labels, b_branching, branching, b_max_steps, max_steps, coloring_given, coloring, coloring_given_passive, coloring_passive, policy, both_sides, schedule, rules) => {
+            let rules = match PruningRules::parse(rules.as_deref().unwrap_or_default()) {
+                Ok(rules) => rules,
+                Err(s) => {
+                    handler(Response::E(s.into()));
+                    handler(Response::Done);
+                    return;
+                }
+            };
             eh.notify("autoub",0,0);
-            problem.autoautoub( b_max_labels, max_labels, b_branching, branching, b_max_steps, max_steps, if coloring_given {Some(coloring)} else {None}, if coloring_given_passive {Some(coloring_passive)} else {None}, |len,is_trivial,mut sequence|{
+            let mut stats = SearchStats::default();
+            let verdict = problem.autoautoub( b_max_labels, max_labels, schedule.as_ref(), b_branching, branching, b_max_steps, max_steps, if coloring_given {Some(coloring)} else {None}, if coloring_given_passive {Some(coloring_passive)} else {None}, policy, both_sides, &rules, &mut stats, |len,is_trivial,sequence|{
                 //for p in sequence.iter_mut() {
                 //    fix_problem(&mut p.1, true, true, &mut eh);
                 //}
-                handler(Response::AutoUb(len,sequence));
+                let diffs = StepDiff::of_sequence(&sequence);
+                let summary = UpperBoundSummary::of_sequence(len, is_trivial, &sequence);
+                handler(Response::AutoUb(len,sequence,diffs,summary));
+                eh.notify("autoub",0,0);
+            }, &mut eh_ignore);
+            handler(Response::SearchStats(stats));
+            handler(Response::Verdict(verdict));
+        },
+        Request::AutoUbTree(problem, b_max_labels, max_labels, b_branching, branching, b_max_steps, max_steps, coloring_given, coloring, coloring_given_passive, coloring_passive, policy, both_sides, schedule, rules) => {
+            let rules = match PruningRules::parse(rules.as_deref().unwrap_or_default()) {
+                Ok(rules) => rules,
+                Err(s) => {
+                    handler(Response::E(s.into()));
+                    handler(Response::Done);
+                    return;
+                }
+            };
+            eh.notify("autoub",0,0);
+            let mut stats = SearchStats { tree: Some(SearchTree::default()), ..Default::default() };
+            let verdict = problem.autoautoub( b_max_labels, max_labels, schedule.as_ref(), b_branching, branching, b_max_steps, max_steps, if coloring_given {Some(coloring)} else {None}, if coloring_given_passive {Some(coloring_passive)} else {None}, policy, both_sides, &rules, &mut stats, |len,is_trivial,sequence|{
+                let diffs = StepDiff::of_sequence(&sequence);
+                let summary = UpperBoundSummary::of_sequence(len, is_trivial, &sequence);
+                handler(Response::AutoUb(len,sequence,diffs,summary));
                 eh.notify("autoub",0,0);
             }, &mut eh_ignore);
+            handler(Response::SearchTree(stats.tree.take().unwrap_or_default()));
+            handler(Response::SearchStats(stats));
+            handler(Response::Verdict(verdict));
+        },
+        Request::AutoUbResume(prefix, schedule, branching, max_steps, coloring_given, coloring, coloring_given_passive, coloring_passive, policy, rules) => {
+            let rules = match PruningRules::parse(rules.as_deref().unwrap_or_default()) {
+                Ok(rules) => rules,
+                Err(s) => {
+                    handler(Response::E(s.into()));
+                    handler(Response::Done);
+                    return;
+                }
+            };
+            eh.notify("autoub",0,0);
+            let mut stats = SearchStats::default();
+            let mut verdict = Verdict::HeuristicNoResult { budget: Budget { max_steps, max_labels: schedule.max() } };
+            let mut max_steps = max_steps;
+            let result = Problem::autoub_resume(&prefix, &schedule, branching, max_steps, if coloring_given {Some(coloring)} else {None}, if coloring_given_passive {Some(coloring_passive)} else {None}, policy, &rules, &mut stats, |len,is_trivial,sequence|{
+                if len <= max_steps {
+                    max_steps = len.saturating_sub(1);
+                    verdict = Verdict::from_autoub(len, is_trivial);
+                    let diffs = StepDiff::of_sequence(&sequence);
+                    let summary = UpperBoundSummary::of_sequence(len, is_trivial, &sequence);
+                    handler(Response::AutoUb(len,sequence,diffs,summary));
+                    eh.notify("autoub",0,0);
+                }
+            }, &mut eh_ignore);
+            match result {
+                Ok(()) => {
+                    handler(Response::SearchStats(stats));
+                    handler(Response::Verdict(verdict));
+                }
+                Err(s) => handler(Response::E(s.into())),
+            }
         },
-        Request::AutoLb(problem, b_max_labels, max_labels, b_branching, branching, b_max_steps, max_steps, coloring_given, coloring, coloring_given_passive, coloring_passive) => {
+        Request::AutoUbFrontier(problem, max_labels, branching, max_steps, coloring_given, coloring, coloring_given_passive, coloring_passive, policy) => {
+            eh.notify("autoub",0,0);
+            let mut stats = SearchStats::default();
+            let frontier = problem.autoub_frontier(max_labels, branching, max_steps, if coloring_given {Some(coloring)} else {None}, if coloring_given_passive {Some(coloring_passive)} else {None}, policy, &mut stats, &mut eh_ignore);
+            handler(Response::AutoUbFrontier(frontier));
+            handler(Response::SearchStats(stats));
+        },
+        Request::AutoUbShard(search, problem, max_labels, branching, max_steps, coloring_given, coloring, coloring_given_passive, coloring_passive, policy, index, count) => {
+            let shard = match Shard::new(index, count) {
+                Ok(shard) => std::sync::Arc::new(shard),
+                Err(s) => {
+                    handler(Response::E(s.into()));
+                    handler(Response::Done);
+                    return;
+                }
+            };
+            global_shards().lock().unwrap().insert(search, shard.clone());
+            eh.notify("autoub",0,0);
+            let mut stats = SearchStats::default();
+            problem.autoub_shard(max_labels, branching, max_steps, if coloring_given {Some(coloring)} else {None}, if coloring_given_passive {Some(coloring_passive)} else {None}, policy, &shard, &mut stats, |len,is_trivial,sequence|{
+                let diffs = StepDiff::of_sequence(&sequence);
+                let summary = UpperBoundSummary::of_sequence(len, is_trivial, &sequence);
+                handler(Response::AutoUb(len,sequence,diffs,summary));
+                eh.notify("autoub",0,0);
+            }, &mut eh_ignore);
+            global_shards().lock().unwrap().remove(&search);
+            handler(Response::SearchStats(stats));
+        },
+        Request::SyncShard(search, steps, keys) => {
+            let shard = global_shards().lock().unwrap().get(&search).cloned();
+            match shard {
+                Some(shard) => {
+                    if let Some(steps) = steps {
+                        shard.share_steps(steps);
+                    }
+                    shard.add_keys(&keys);
+                    handler(Response::ShardState(shard.best_steps(), shard.take_new_keys()));
+                }
+                None => handler(Response::E("The search is not running".into())),
+            }
+        },
+        Request::NontrivialQuotient(problem, target) => match problem.nontrivial_quotient(target, &mut eh) {
+            Ok(Some(mut quotient)) => {
+                fix_problem(&mut quotient.problem, true, true, &mut eh);
+                handler(Response::Quotient(quotient.classes, quotient.problem));
+            }
+            Ok(None) => handler(Response::E("Every mapping onto this many labels gives a zero round solvable problem".into())),
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::PreviewMerges(problem, limits) => {
+            handler(Response::MergePreviews(problem.preview_diagram_merges(&limits, &mut eh)));
+        }
+        Request::AutoLb(problem, b_max_labels, max_labels, b_branching, branching, b_max_steps, max_steps, coloring_given, coloring, coloring_given_passive, coloring_passive, strategy, both_sides, schedule, rules) => {
+            let rules = match PruningRules::parse(rules.as_deref().unwrap_or_default()) {
+                Ok(rules) => rules,
+                Err(s) => {
+                    handler(Response::E(s.into()));
+                    handler(Response::Done);
+                    return;
+                }
+            };
             eh.notify("autolb",0,0);
-            problem.autoautolb( b_max_labels, max_labels, b_branching, branching, b_max_steps, max_steps, if coloring_given {Some(coloring)} else {None}, if coloring_given_passive {Some(coloring_passive)} else {None}, |len,mut sequence|{
-                handler(Response::AutoLb(len,sequence));
+            let verdict = problem.autoautolb( b_max_labels, max_labels, schedule.as_ref(), b_branching, branching, b_max_steps, max_steps, if coloring_given {Some(coloring)} else {None}, if coloring_given_passive {Some(coloring_passive)} else {None}, strategy, both_sides, &rules, |len,sequence|{
+                let diffs = StepDiff::of_sequence(&sequence);
+                handler(Response::AutoLb(len,sequence,diffs));
                 eh.notify("autolb",0,0);
             }, &mut eh_ignore);
+            handler(Response::Verdict(verdict));
+        },
+        Request::AutoLbResume(prefix, schedule, branching, max_steps, coloring_given, coloring, coloring_given_passive, coloring_passive, strategy, rules) => {
+            let rules = match PruningRules::parse(rules.as_deref().unwrap_or_default()) {
+                Ok(rules) => rules,
+                Err(s) => {
+                    handler(Response::E(s.into()));
+                    handler(Response::Done);
+                    return;
+                }
+            };
+            eh.notify("autolb",0,0);
+            let mut verdict = Verdict::HeuristicNoResult { budget: Budget { max_steps, max_labels: schedule.max() } };
+            let mut min_steps = 1;
+            let result = Problem::autolb_resume(&prefix, &schedule, branching, 1, max_steps, if coloring_given {Some(coloring)} else {None}, if coloring_given_passive {Some(coloring_passive)} else {None}, strategy, &rules, |len,sequence|{
+                if len >= min_steps {
+                    min_steps = len+1;
+                    verdict = Verdict::from_autolb(len);
+                    let diffs = StepDiff::of_sequence(&sequence);
+                    handler(Response::AutoLb(len,sequence,diffs));
+                    eh.notify("autolb",0,0);
+                }
+            }, &mut eh_ignore);
+            match result {
+                Ok(_) => handler(Response::Verdict(verdict)),
+                Err(s) => handler(Response::E(s.into())),
+            }
+        },
+        Request::CoordinateDescent(problem, params) => match problem.coordinate_descent(&params, &mut eh) {
+            Ok(sequence) => handler(Response::Simplification(sequence)),
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::FixedPointCore(problem) => match problem.fixed_point_core(&mut eh) {
+            Ok(core) => {
+                handler(Response::CoreRemovals(core.removed));
+                let mut new = core.core;
+                fix_problem(&mut new, true, true, &mut eh);
+                handler(Response::P(new));
+            }
+            Err(s) => handler(Response::E(s.into())),
         },
         Request::ColoringSolvability(mut problem) => {
             problem.compute_coloring_solvability(&mut eh);
             handler(Response::P(problem));
         }
+        Request::UpperBoundAlgorithm(sequence) => match DecisionDiagram::from_upper_bound(&sequence) {
+            Ok(diagram) => handler(Response::S(diagram.to_dot())),
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::ColorsForRounds(problem, rounds) => {
+            handler(Response::ColorsForRounds(rounds, problem.colors_for_rounds(rounds, &mut eh)));
+        }
+        Request::LogStarLowerBound(problem) => match problem.log_star_lower_bound() {
+            Ok(certificate) => handler(Response::LogStarLowerBound(certificate)),
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::SelfRelaxation(problem, params) => {
+            handler(Response::SelfRelaxation(problem.self_relaxation(&params, &mut eh)));
+        }
+        Request::TrivialityWithin(mut problem, limit) => {
+            let answer = problem.compute_triviality_within(limit, &mut eh);
+            handler(Response::TrivialityAnswer(answer));
+            if problem.trivial_sets.is_some() {
+                handler(Response::P(problem));
+            }
+        }
+        Request::CycleBehavior(problem) => match problem.cycle_behavior() {
+            Ok(behavior) => handler(Response::CycleBehavior(behavior)),
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::EstimateSpeedupSize(mut problem, limit) => {
+            if problem.diagram_indirect.is_none() {
+                problem.compute_partial_diagram(&mut eh);
+            }
+            handler(Response::SpeedupEstimate(problem.estimate_speedup_size(limit)));
+        }
+        Request::ExplainSets(mut problem) => {
+            if problem.trivial_sets.is_none() {
+                problem.compute_triviality(&mut eh);
+            }
+            if problem.coloring_sets.is_none() {
+                problem.compute_coloring_solvability(&mut eh);
+            }
+            // the coloring solvability is unknown if the passive side has degree *, and then no set is explained
+            handler(Response::ExplainedSets(
+                problem.explained_trivial_sets().unwrap(),
+                problem.explained_coloring_sets().unwrap_or_default(),
+            ));
+        }
+        Request::ColorLikeLabels(problem) => match problem.color_like_labels(&mut eh) {
+            Ok(labels) => handler(Response::ColorLikeLabels(labels)),
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::NewIrregularProblem(active, passive) => match IrregularProblem::from_string(format!("{}\n\n{}", active, passive)) {
+            Ok(problem) => {
+                let trivial = problem.trivial_sets(&mut eh);
+                handler(Response::Irregular(problem.clone(), problem.to_string(), trivial));
+            }
+            Err(s) => handler(Response::E(s.into())),
+        },
+        Request::IrregularSpeedup(problem) => {
+            let new = problem.speedup(&mut eh);
+            let trivial = new.trivial_sets(&mut eh);
+            handler(Response::Irregular(new.clone(), new.to_string(), trivial));
+        }
+        Request::IrregularAutoLb(problem, max_labels, max_steps) => {
+            eh.notify("autolb",0,0);
+            let (len, sequence) = problem.autolb(max_labels, max_steps, &mut eh_ignore);
+            handler(Response::IrregularAutoLb(len, sequence.into_iter().map(|p| { let text = p.to_string(); (p, text) }).collect()));
+            let verdict = if len > 0 { Verdict::from_autolb(len) } else { Verdict::HeuristicNoResult { budget: Budget { max_steps, max_labels } } };
+            handler(Response::Verdict(verdict));
+        }
         Request::Marks(mut problem) => {
             if problem.passive.degree  != Degree::Finite(2) {
                 handler(Response::E(
@@ -302,16 +1078,160 @@ where
     handler(Response::Done);
 }
 
+/// The first argument of a request that is a problem, borrowed as the request is.
+macro_rules! first_problem_of {
+    ($req:expr) => {
+        match $req {
+            Request::SimplifyMerge(p, ..)
+            | Request::SimplifyMergeGroup(p, ..)
+            | Request::SimplifyAddarrow(p, ..)
+            | Request::MergeDiagramNodes(p, ..)
+            | Request::ContractChain(p, ..)
+            | Request::RemoveDiagramNode(p, ..)
+            | Request::PossibleAddarrow(p, ..)
+            | Request::SplitLabel(p, ..)
+            | Request::ProjectActivePort(p, ..)
+            | Request::ProjectPassivePort(p, ..)
+            | Request::LiftDegree(p, ..)
+            | Request::ColoringReductions(p, ..)
+            | Request::CheckDegrees(p, ..)
+            | Request::PadToDegree(p, ..)
+            | Request::MatchingRelabeling(p, ..)
+            | Request::Upload(p, ..)
+            | Request::SimplifySD(p, ..)
+            | Request::HardenRemove(p, ..)
+            | Request::HardenKeep(p, ..)
+            | Request::HardenKeepWithMode(p, ..)
+            | Request::Induced(p, ..)
+            | Request::Complement(p, ..)
+            | Request::Power(p, ..)
+            | Request::DisjointUnion(p, ..)
+            | Request::Speedup(p, ..)
+            | Request::SpeedupWithLimits(p, ..)
+            | Request::MaximizeActive(p, ..)
+            | Request::SpeedupRestricted(p, ..)
+            | Request::Pipeline(p, ..)
+            | Request::FixpointBasic(p, ..)
+            | Request::FixpointLoop(p, ..)
+            | Request::FixpointCustom(p, ..)
+            | Request::FixpointDup(p, ..)
+            | Request::InverseSpeedup(p, ..)
+            | Request::SpeedupMaximize(p, ..)
+            | Request::SpeedupMaximizeRenamegen(p, ..)
+            | Request::Maximize(p, ..)
+            | Request::MergeEquivalentLabels(p, ..)
+            | Request::RenameGenerators(p, ..)
+            | Request::Rename(p, ..)
+            | Request::RenamingPresets(p, ..)
+            | Request::KnownProblems(p, ..)
+            | Request::LineOrigins(p, ..)
+            | Request::LabelPriorities(p, ..)
+            | Request::FoldLabels(p, ..)
+            | Request::FoldedText(p, ..)
+            | Request::ColorLabels(p, ..)
+            | Request::OneSidedLabels(p, ..)
+            | Request::CanonicalText(p, ..)
+            | Request::Annotate(p, ..)
+            | Request::AnnotationsText(p, ..)
+            | Request::PassiveGraph(p, ..)
+            | Request::ImportPassiveGraph(p, ..)
+            | Request::NeighborhoodGraph(p, ..)
+            | Request::DiagramGraph(p, ..)
+            | Request::ImportDiagramGraph(p, ..)
+            | Request::Homomorphism(p, ..)
+            | Request::Orientation(p, ..)
+            | Request::DefaultDiagram(p, ..)
+            | Request::AutoUb(p, ..)
+            | Request::AutoUbTree(p, ..)
+            | Request::AutoUbFrontier(p, ..)
+            | Request::NontrivialQuotient(p, ..)
+            | Request::PreviewMerges(p, ..)
+            | Request::AutoLb(p, ..)
+            | Request::ColoringSolvability(p, ..)
+            | Request::FixedPointCore(p, ..)
+            | Request::ColorsForRounds(p, ..)
+            | Request::CoordinateDescent(p, ..)
+            | Request::ColorLikeLabels(p, ..)
+            | Request::LogStarLowerBound(p, ..)
+            | Request::SelfRelaxation(p, ..)
+            | Request::CycleBehavior(p, ..)
+            | Request::TrivialityWithin(p, ..)
+            | Request::EstimateSpeedupSize(p, ..)
+            | Request::ExplainSets(p, ..)
+            | Request::Marks(p, ..) => Some(p),
+            Request::AutoUbShard(_, p, ..) => Some(p),
+            _ => None,
+        }
+    };
+}
+
+impl Request {
+    /// The first argument of the request that is a problem, from which the problems sent back are derived.
+    fn first_problem(&self) -> Option<&Problem> {
+        first_problem_of!(self)
+    }
+
+    fn first_problem_mut(&mut self) -> Option<&mut Problem> {
+        first_problem_of!(self)
+    }
+}
+
 #[derive(Deserialize, Serialize)]
 pub enum Request {
     NewProblem(String, String),
+    /// Same as NewProblem, but lines with fewer ports are padded with the given label.
+    NewProblemPadded(String, String, String),
+    /// A problem written with the notation of papers, see `paper_notation::normalize_paper_notation`.
+    NewProblemFromPaper(String),
     SimplifyMerge(Problem, Label, Label),
     SimplifyMergeGroup(Problem, Vec<Label>, Label),
     SimplifyAddarrow(Problem, Label, Label),
+    /// Merges the given nodes of the diagram, see `Problem::merge_diagram_nodes`.
+    MergeDiagramNodes(Problem, Vec<Label>),
+    /// Merges the nodes on the paths of the diagram from the first label to the second one, see `Problem::contract_chain`.
+    ContractChain(Problem, Label, Label),
+    /// Removes a node of the diagram by hardening, see `Problem::remove_diagram_node`.
+    RemoveDiagramNode(Problem, Label),
+    PossibleAddarrow(Problem),
+    SplitLabel(Problem, Label, usize),
+    ProjectActivePort(Problem),
+    ProjectPassivePort(Problem),
+    LiftDegree(Problem, LiftPadding),
+    /// Classifies the problem without coloring and given a coloring with each number of colors in the range, see `Problem::coloring_reductions`.
+    ColoringReductions(Problem, usize, usize, Budget),
+    /// The lines whose degree differs from the one of their side.
+    CheckDegrees(Problem),
+    /// Suspicious patterns in the text of a problem, with the fixes that can be applied with ApplyLintFix.
+    Lint(String),
+    ApplyLintFix(String, LintFix),
+    /// Pads the active lines to the given degree with the given label, see `Problem::pad_to_degree`.
+    PadToDegree(Problem, usize, String),
+    MatchingRelabeling(Problem, Problem),
+    DefectiveLadder(usize, usize, usize, Option<usize>),
+    Upload(Problem),
+    GetLines(ProblemId, Side, usize, usize),
+    /// Random lines of a stored problem: the number of lines and the seed of the random generator.
+    SampleLines(ProblemId, Side, usize, u64),
+    /// Checks whether a side of a stored problem allows a configuration, written with one label per port.
+    CheckConfiguration(ProblemId, Side, String),
+    ForgetProblem(ProblemId),
     SimplifySD(Problem,String),
     HardenRemove(Problem, Label, bool),
     HardenKeep(Problem, Vec<Label>, bool),
+    HardenKeepWithMode(Problem, Vec<Label>, HardenMode),
+    Induced(Problem, Vec<Label>),
+    Complement(Problem, bool),
+    /// The problem of solving the given number of independent instances at the same time, see `Problem::power`.
+    Power(Problem, usize),
+    /// The union of the constraints of the two problems, with the clashing labels of the second one renamed, see `Problem::disjoint_union`.
+    DisjointUnion(Problem, Problem),
     Speedup(Problem),
+    /// Same as Speedup, but gives up if the limits are exceeded.
+    SpeedupWithLimits(Problem, Limits),
+    /// The problem with only the active side maximized, giving up if the limits are exceeded, see `Problem::maximize_active`.
+    MaximizeActive(Problem, Limits),
+    SpeedupRestricted(Problem, Vec<Vec<Label>>),
+    Pipeline(Problem, Vec<Op>),
     FixpointBasic(Problem, bool, bool, Vec<Label>),
     FixpointLoop(Problem, bool, bool, Vec<Label>),
     FixpointCustom(Problem,String, bool, bool, Vec<Label>),
@@ -319,16 +1239,89 @@ pub enum Request {
     InverseSpeedup(Problem),
     SpeedupMaximize(Problem),
     SpeedupMaximizeRenamegen(Problem),
-    Maximize(Problem),
+    /// The problem with the passive side maximized, giving up if the limits are exceeded, and with its diagram and triviality computed again.
+    Maximize(Problem, Limits),
     MergeEquivalentLabels(Problem),
     RenameGenerators(Problem),
     Rename(Problem, Vec<(Label, String)>),
+    /// Label names from the literature, if the problem is a known problem or its speedup.
+    RenamingPresets(Problem),
+    /// The known problems that, up to renaming labels, are the same as the problem, after at most the given number of speedups.
+    KnownProblems(Problem, usize),
+    /// Where each line comes from: the input, a speedup, or a relaxation.
+    LineOrigins(Problem),
+    LabelPriorities(Problem, Vec<(Label, Vec<usize>)>),
+    /// Shows each group of labels with the given name, without changing the problem, see `Problem::set_label_folds`.
+    FoldLabels(Problem, Vec<(Vec<Label>, String)>),
+    /// The text of the problem with the folded labels shown with the name of their group.
+    FoldedText(Problem),
+    /// Marks the labels that encode a coloring, that are kept through speedups and never merged by the automatic lower bound search, see `Problem::set_color_labels`.
+    ColorLabels(Problem, Vec<Label>),
+    /// Declares the labels that may appear only on one side, see `Problem::set_one_sided_labels`.
+    OneSidedLabels(Problem, Vec<(Label, Side)>),
+    /// The text of the problem that does not depend on the internal order of labels and lines, see `Problem::to_canonical_text`.
+    CanonicalText(Problem),
+    Annotate(Problem, AnnotationTarget, String),
+    AnnotationsText(Problem),
+    PassiveGraph(Problem, GraphFormat),
+    ImportPassiveGraph(Problem, GraphFormat, String),
+    /// The graph used by the triviality and coloring checks, see `NeighborhoodGraph`.
+    NeighborhoodGraph(Problem, GraphFormat),
+    /// The diagram alone, as a directed graph. The full diagram is computed if the problem only has a partial one.
+    DiagramGraph(Problem, GraphFormat),
+    /// Adds the arrows of an edited copy of the diagram obtained with DiagramGraph, as a sequence of SimplifyAddarrow.
+    ImportDiagramGraph(Problem, GraphFormat, String),
+    /// A homomorphism from the compatibility graph of the passive side to the template with the given number of vertices and edges, see `Problem::homomorphism_to_template`.
+    Homomorphism(Problem, usize, Vec<(usize, usize)>),
     Orientation(Problem, usize),
     DefaultDiagram(Problem, bool, bool, Vec<Label>),
-    AutoUb(Problem, bool, usize, bool, usize, bool, usize, bool, usize, bool, usize),
-    AutoLb(Problem, bool, usize, bool, usize, bool, usize, bool, usize, bool, usize),
+    /// The schedule, if given, bounds the number of labels at each step instead of the maximum number of labels, see `LabelSchedule`.
+    /// The last field, if given, contains pruning rules as text, see `PruningRules`.
+    AutoUb(Problem, bool, usize, bool, usize, bool, usize, bool, usize, bool, usize, EvaluationPolicy, bool, Option<LabelSchedule>, Option<String>),
+    /// Same as AutoUb, but all the nodes explored by the search are recorded, and sent at the end as a SearchTree.
+    AutoUbTree(Problem, bool, usize, bool, usize, bool, usize, bool, usize, bool, usize, EvaluationPolicy, bool, Option<LabelSchedule>, Option<String>),
+    AutoUbFrontier(Problem, usize, usize, usize, bool, usize, bool, usize, EvaluationPolicy),
+    /// Same as AutoUb with fixed parameters and a bound on the number of labels for each step, but continuing from the last problem of a sequence of speedups and hardenings, see `autoub_resume`.
+    /// The last field, if given, contains pruning rules as text, see `PruningRules`.
+    AutoUbResume(Vec<(AutoOperation,Problem)>, LabelSchedule, usize, usize, bool, usize, bool, usize, EvaluationPolicy, Option<String>),
+    /// A part of an autoub search split among several processes: the search id, the parameters of the search, the index of the shard, and the number of shards.
+    AutoUbShard(u64, Problem, usize, usize, usize, bool, usize, bool, usize, EvaluationPolicy, usize, usize),
+    /// Sends to a running shard the best number of speedups and the visited problems known by the other shards.
+    SyncShard(u64, Option<usize>, Vec<u64>),
+    NontrivialQuotient(Problem, usize),
+    /// The previews of the merges along the edges of the diagram, with the limits of each triviality check, see `Problem::preview_diagram_merges`.
+    PreviewMerges(Problem, Limits),
+    /// The schedule, if given, bounds the number of labels at each step instead of the maximum number of labels, see `LabelSchedule`.
+    /// The last field, if given, contains pruning rules as text, see `PruningRules`.
+    AutoLb(Problem, bool, usize, bool, usize, bool, usize, bool, usize, bool, usize, LbStrategy, bool, Option<LabelSchedule>, Option<String>),
+    /// Same as AutoLb with fixed parameters and a bound on the number of labels for each step, but continuing from the last problem of a sequence of speedups and merges, see `autolb_resume`.
+    /// The last field, if given, contains pruning rules as text, see `PruningRules`.
+    AutoLbResume(Vec<(AutoOperation,Problem)>, LabelSchedule, usize, usize, bool, usize, bool, usize, LbStrategy, Option<String>),
     ColoringSolvability(Problem),
+    /// A minimal nontrivial fixed point obtained by removing labels and lines, if the problem is a nontrivial fixed point.
+    FixedPointCore(Problem),
+    ColorsForRounds(Problem, usize),
+    /// Simplifies the problem by alternating merges and hardenings, see `Problem::coordinate_descent`.
+    CoordinateDescent(Problem, DescentParams),
+    /// The algorithm given by a sequence found by autoub, as a decision diagram in the DOT format.
+    UpperBoundAlgorithm(Vec<(AutoOperation, Problem)>),
+    ColorLikeLabels(Problem),
+    LogStarLowerBound(Problem),
+    /// Searches for a relaxation of the problem that is also a relaxation of its speedup, which proves an Ω(log n) lower bound, see `Problem::self_relaxation`.
+    SelfRelaxation(Problem, SelfRelaxationParams),
+    /// The labelings of cycles that can be pumped, for problems with active and passive degree 2.
+    CycleBehavior(Problem),
+    /// Checks whether the problem is zero round solvable by examining at most the given number of candidates, see `Problem::compute_triviality_within`.
+    /// The problem, with its trivial sets, is sent back if all the candidates have been examined.
+    TrivialityWithin(Problem, usize),
+    EstimateSpeedupSize(Problem, usize),
+    ExplainSets(Problem),
     Marks(Problem),
+    /// A problem where each side can have several degrees, given by the texts of its two sides, see `IrregularProblem`.
+    NewIrregularProblem(String, String),
+    IrregularSpeedup(IrregularProblem),
+    /// Applies speedups until the problem is zero round solvable, with the maximum number of labels and of speedups, see `IrregularProblem::autolb`.
+    IrregularAutoLb(IrregularProblem, usize, usize),
     Ping,
 }
 
@@ -338,10 +1331,62 @@ pub enum Response {
     Done,
     Pong,
     Event(String, usize, usize),
+    /// Several progress events, sent together by `request_json_throttled`, at most one for each kind of event.
+    Events(Vec<(String, usize, usize)>),
     P(Problem),
     E(String),
-    AutoUb(usize,Vec<(AutoOperation,Problem)>),
-    AutoLb(usize,Vec<(AutoOperation,Problem)>),
+    S(String),
+    Homomorphism(Option<Vec<(Label, usize)>>),
+    Addarrow(Vec<(Label, Label)>, Vec<(Label, Label)>),
+    Relabeling(Result<Vec<(Label, Label)>, RelabelingMismatch>),
+    DefectiveLadder(Vec<(DefectiveRung, Problem)>, Option<usize>),
+    Stored(ProblemId),
+    Lines(usize, Vec<String>),
+    ConfigurationAllowed(bool),
+    MergePreviews(Vec<MergePreview>),
+    /// The number of rounds, the sequence, the differences between its steps, and the numbers that describe the upper bound.
+    AutoUb(usize,Vec<(AutoOperation,Problem)>,Vec<StepDiff>,UpperBoundSummary),
+    AutoLb(usize,Vec<(AutoOperation,Problem)>,Vec<StepDiff>),
+    /// The number of rounds, and the largest number of colors of an input coloring that allows solving the problem in that many rounds,
+    /// or None if no coloring is needed.
+    ColorsForRounds(usize, Option<usize>),
+    /// Sent when an automatic search ends, with the best outcome it found.
+    Verdict(Verdict),
+    AutoUbFrontier(Vec<FrontierPoint>),
+    /// The best number of speedups known by a shard, and the keys of the problems it visited since the last synchronization.
+    ShardState(Option<usize>, Vec<u64>),
+    /// The labels mapped to each label of the quotient, and the quotient.
+    Quotient(Vec<Vec<Label>>, Problem),
+    /// How the lines of a problem written with the notation of papers have been read.
+    Interpretations(Vec<Interpretation>),
+    SearchStats(SearchStats),
+    /// The nodes explored by autoub, sent only for AutoUbTree.
+    SearchTree(SearchTree),
+    ColorLikeLabels(Vec<Label>),
+    Pipeline(Vec<StepSummary>, Problem),
+    LogStarLowerBound(Option<LogStarLowerBound>),
+    SelfRelaxation(Option<SelfRelaxation>),
+    CycleBehavior(CycleBehavior),
+    TrivialityAnswer(TrivialityAnswer),
+    SpeedupEstimate(SpeedupEstimate),
+    /// The trivial sets and the coloring sets.
+    ExplainedSets(Vec<ExplainedSet>, Vec<ExplainedSet>),
+    RenamingPresets(Vec<RenamingPreset>),
+    KnownProblems(Vec<KnownProblemMatch>),
+    LimitExceeded(LimitExceeded),
+    /// Sent while an operation is still running, when the watchdog given in its limits predicts a blowup.
+    BlowupWarning(BlowupWarning),
+    LineOrigins(Vec<(Side, String, LineOrigin)>),
+    CoreRemovals(Vec<CoreRemoval>),
+    DegreeMismatches(Vec<DegreeMismatch>),
+    LintWarnings(Vec<LintWarning>),
+    ColoringReductions(ColoringReductions),
+    /// The operations applied by `CoordinateDescent`, each with the problem obtained after it.
+    Simplification(Vec<(AutoOperation,Problem)>),
+    /// A problem where each side can have several degrees, its text, and its trivial sets.
+    Irregular(IrregularProblem, String, Vec<Vec<Label>>),
+    /// The lower bound found by `IrregularAutoLb`, and the problems obtained by the speedups, each with its text.
+    IrregularAutoLb(usize, Vec<(IrregularProblem, String)>),
 }
 
 #[derive(Serialize,Deserialize,Clone)]
@@ -349,5 +1394,42 @@ pub enum AutoOperation{
     Initial,
     Harden(Vec<Label>),
     Merge(Vec<(Label,Label)>,Problem),
-    Speedup
-}
\ No newline at end of file
+    Speedup,
+    SwapSides
+}
+#[cfg(test)]
+mod tests {
+
+    use std::{cell::RefCell, sync::Mutex};
+
+    use crate::{
+        algorithms::{event::EventHandler, limits::Limits},
+        problem::Problem,
+        store::ProblemStore,
+    };
+
+    use super::{cbor_variant_name, fix_problem, request_cbor_in, Request, Response};
+
+    #[test]
+    fn cbor_round_trip() {
+        let p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        let mut req = vec![];
+        ciborium::ser::into_writer(&Request::SpeedupWithLimits(p.clone(), Limits::default()), &mut req).unwrap();
+        assert_eq!(cbor_variant_name(&req), Some("SpeedupWithLimits"));
+
+        let responses = RefCell::new(vec![]);
+        request_cbor_in(&req, &Mutex::new(ProblemStore::default()), |v, send_to_client| {
+            if send_to_client {
+                responses.borrow_mut().push(ciborium::de::from_reader::<Response, _>(&v[..]).unwrap());
+            }
+        });
+        let responses = responses.into_inner();
+        assert!(matches!(responses.last(), Some(Response::Done)));
+        let sped = responses.iter().find_map(|r| if let Response::P(p) = r { Some(p) } else { None }).unwrap();
+
+        let eh = &mut EventHandler::null();
+        let mut expected = p.speedup(eh);
+        fix_problem(&mut expected, true, true, eh);
+        assert_eq!(sped, &expected);
+    }
+}
@@ -0,0 +1,316 @@
+//! The problems obtained by applying operations one after the other, as in the REPL, with named branches to explore alternatives side by side.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    algorithms::{annotations::AnnotationTarget, event::EventHandler, resume::check_replay},
+    group::Label,
+    pipeline::Op,
+    problem::Problem,
+    serial::AutoOperation,
+    store::Side,
+    verdict::Verdict,
+};
+
+/// A problem of a branch, with the operation that produced it from the previous one, or None if it has been loaded.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Step {
+    pub op: Option<Op>,
+    pub problem: Problem,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Branch {
+    pub name: String,
+    /// The branch and the number of its steps at the time of the fork.
+    pub forked_from: Option<(String, usize)>,
+    /// Never empty.
+    pub steps: Vec<Step>,
+}
+
+/// What a branch obtained, to compare branches.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BranchOutcome {
+    pub name: String,
+    pub steps: usize,
+    pub speedups: usize,
+    pub labels: usize,
+    pub active_lines: usize,
+    pub passive_lines: usize,
+    pub trivial: bool,
+    /// What the operations since the last loaded problem prove, if anything.
+    pub verdict: Option<Verdict>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Session {
+    branches: Vec<Branch>,
+    current: usize,
+}
+
+impl Branch {
+    fn outcome(&self, eh: &mut EventHandler) -> BranchOutcome {
+        let mut p = self.steps.last().unwrap().problem.clone();
+        if p.trivial_sets.is_none() {
+            p.compute_triviality(eh);
+        }
+        let trivial = !p.trivial_sets.as_ref().unwrap().is_empty();
+
+        let start = self.steps.iter().rposition(|step| step.op.is_none()).unwrap_or(0);
+        let ops: Vec<&Op> = self.steps[start..].iter().filter_map(|step| step.op.as_ref()).collect();
+        let speedups = ops.iter().filter(|op| matches!(op, Op::Speedup)).count();
+        let relaxed = ops.iter().any(|op| matches!(op, Op::MergeGroup(..) | Op::Merges(..)));
+        let hardened = ops.iter().any(|op| matches!(op, Op::HardenKeep(..) | Op::HardenRemove(..) | Op::Induced(..)));
+        let swapped = ops.iter().any(|op| matches!(op, Op::SwapSides));
+        let verdict = match (trivial, relaxed, hardened) {
+            // swapping the sides changes the complexity by one round, in a direction that is not known
+            _ if swapped => None,
+            (true, false, _) if speedups == 0 => Some(Verdict::ProvedTrivial),
+            (true, false, _) => Some(Verdict::ProvedUpperBound(speedups)),
+            (false, _, false) => Some(Verdict::ProvedLowerBound(speedups + 1)),
+            _ => None,
+        };
+
+        BranchOutcome {
+            name: self.name.clone(),
+            steps: self.steps.len() - 1,
+            speedups,
+            labels: p.labels().len(),
+            active_lines: p.active.lines.len(),
+            passive_lines: p.passive.lines.len(),
+            trivial,
+            verdict,
+        }
+    }
+}
+
+impl Session {
+    /// A session with a single branch called "main", starting from the given problem.
+    pub fn new(problem: Problem) -> Self {
+        Session {
+            branches: vec![Branch { name: "main".into(), forked_from: None, steps: vec![Step { op: None, problem }] }],
+            current: 0,
+        }
+    }
+
+    /// A session whose main branch contains the problems of a sequence found by autoub or autolb, so that the work can go on
+    /// manually from any of them after undoing the steps that follow it. Each step records the operation that gives the same problem
+    /// as the search, and the problems are the ones of the sequence. Fails if some problem is not the result of its operation
+    /// on the previous one, see `check_replay`.
+    pub fn from_sequence(sequence: &[(AutoOperation, Problem)], eh: &mut EventHandler) -> Result<Self, &'static str> {
+        let Some(((_, first), rest)) = sequence.split_first() else {
+            return Err("The sequence is empty");
+        };
+        let mut session = Session::new(first.clone());
+        for (op, problem) in rest {
+            let previous = session.current();
+            check_replay(previous, op, problem, eh)?;
+            let name = |p: &Problem, l: &Label| p.mapping_label_text.iter().find(|(x, _)| x == l).map(|(_, t)| t.clone()).ok_or("A label of the sequence does not exist");
+            let op = match op {
+                AutoOperation::Initial => None,
+                AutoOperation::Speedup => Some(Op::Speedup),
+                AutoOperation::SwapSides => Some(Op::SwapSides),
+                AutoOperation::Harden(keep) => {
+                    let keep = keep.iter().map(|l| name(previous, l)).collect::<Result<_, _>>()?;
+                    Some(Op::HardenKeep(keep, true))
+                }
+                AutoOperation::Merge(merges, before) => {
+                    let merges = merges.iter().map(|(a, b)| Ok((name(before, a)?, name(before, b)?))).collect::<Result<_, &'static str>>()?;
+                    Some(Op::Merges(merges))
+                }
+            };
+            session.branches[0].steps.push(Step { op, problem: problem.clone() });
+        }
+        Ok(session)
+    }
+
+    pub fn branch(&self) -> &Branch {
+        &self.branches[self.current]
+    }
+
+    pub fn branches(&self) -> &[Branch] {
+        &self.branches
+    }
+
+    pub fn current(&self) -> &Problem {
+        &self.branch().steps.last().unwrap().problem
+    }
+
+    fn find(&self, name: &str) -> Result<usize, &'static str> {
+        self.branches.iter().position(|b| b.name == name).ok_or("There is no branch with this name")
+    }
+
+    /// Replaces the current problem of the current branch with a problem that has not been obtained with an operation.
+    pub fn load(&mut self, problem: Problem) {
+        self.branches[self.current].steps.push(Step { op: None, problem });
+    }
+
+    /// Applies the operation to the current problem, followed by the same cleanup done by the GUI.
+    pub fn apply(&mut self, op: Op, eh: &mut EventHandler) -> Result<&Problem, &'static str> {
+        let new = self.current().apply_op(&op, eh)?.apply_op(&Op::Cleanup, eh)?;
+        self.branches[self.current].steps.push(Step { op: Some(op), problem: new });
+        Ok(self.current())
+    }
+
+    /// Goes back to the previous problem of the current branch.
+    pub fn undo(&mut self) -> Result<&Problem, &'static str> {
+        let steps = &mut self.branches[self.current].steps;
+        if steps.len() == 1 {
+            return Err("There is nothing to undo");
+        }
+        steps.pop();
+        Ok(self.current())
+    }
+
+    /// Copies the current branch into a new branch with the given name, and switches to it.
+    pub fn fork(&mut self, name: &str) -> Result<(), &'static str> {
+        if self.find(name).is_ok() {
+            return Err("There is already a branch with this name");
+        }
+        let branch = self.branch();
+        let forked = Branch {
+            name: name.to_string(),
+            forked_from: Some((branch.name.clone(), branch.steps.len())),
+            steps: branch.steps.clone(),
+        };
+        self.branches.push(forked);
+        self.current = self.branches.len() - 1;
+        Ok(())
+    }
+
+    pub fn switch(&mut self, name: &str) -> Result<(), &'static str> {
+        self.current = self.find(name)?;
+        Ok(())
+    }
+
+    /// The outcome of each branch, in the order in which the branches have been created.
+    pub fn compare(&self, eh: &mut EventHandler) -> Vec<BranchOutcome> {
+        self.branches.iter().map(|b| b.outcome(eh)).collect()
+    }
+
+    /// Copies the notes about labels and lines of the last problem of the given branch to the current problem,
+    /// for the labels and lines that have the same text in both problems. Notes about the step are not copied.
+    /// Returns the number of copied notes.
+    pub fn merge_annotations(&mut self, from: &str) -> Result<usize, &'static str> {
+        let source = self.branches[self.find(from)?].steps.last().unwrap().problem.clone();
+        let target = &mut self.branches[self.current].steps.last_mut().unwrap().problem;
+
+        let source_text: HashMap<_, _> = source.mapping_label_text.iter().cloned().collect();
+        let target_labels: HashMap<_, _> = target.mapping_label_text.iter().map(|(l, t)| (t.clone(), *l)).collect();
+        let target_text: HashMap<_, _> = target.mapping_label_text.iter().cloned().collect();
+
+        let mut copied = 0;
+        for (t, note) in source.annotations.iter().flatten() {
+            let translated = match t {
+                AnnotationTarget::Step => None,
+                AnnotationTarget::Label(l) => target_labels.get(&source_text[l]).map(|&l| AnnotationTarget::Label(l)),
+                AnnotationTarget::Line(side, line) => {
+                    let text = line.to_string(&source_text);
+                    let constraint = match side {
+                        Side::Active => &target.active,
+                        Side::Passive => &target.passive,
+                    };
+                    constraint
+                        .lines
+                        .iter()
+                        .find(|l| l.to_string(&target_text) == text)
+                        .map(|l| AnnotationTarget::Line(*side, l.clone()))
+                }
+            };
+            let Some(translated) = translated else {
+                continue;
+            };
+            let merged = match target.annotation(&translated) {
+                Some(existing) if existing == note => continue,
+                Some(existing) => format!("{}\n{}", existing, note),
+                None => note.clone(),
+            };
+            target.annotate(translated, merged);
+            copied += 1;
+        }
+        Ok(copied)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        algorithms::{annotations::AnnotationTarget, event::EventHandler},
+        pipeline::Op,
+        problem::Problem,
+        verdict::Verdict,
+    };
+
+    use super::Session;
+
+    #[test]
+    fn session() {
+        let eh = &mut EventHandler::null();
+        let p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        let mut session = Session::new(p.apply_op(&Op::Cleanup, eh).unwrap());
+        session.apply(Op::Speedup, eh).unwrap();
+
+        session.fork("relaxed").unwrap();
+        assert!(session.fork("relaxed").is_err());
+        let labels: Vec<String> = session.current().mapping_label_text.iter().map(|(_, t)| t.clone()).collect();
+        session.apply(Op::MergeGroup(vec![labels[0].clone()], labels[1].clone()), eh).unwrap();
+        session.undo().unwrap();
+        session.undo().unwrap();
+        assert!(session.undo().is_err());
+        let mut current = session.current().clone();
+        let m = current.mapping_label_text.iter().find(|(_, t)| t == "M").unwrap().0;
+        current.annotate(AnnotationTarget::Label(m), "matched".into());
+        session.load(current);
+
+        session.switch("main").unwrap();
+        let outcomes = session.compare(eh);
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!((outcomes[0].name.as_str(), outcomes[0].speedups), ("main", 1));
+        assert_eq!(outcomes[0].verdict, Some(Verdict::ProvedLowerBound(2)));
+        assert_eq!(outcomes[1].verdict, Some(Verdict::ProvedLowerBound(1)));
+
+        // the label M does not exist after the speedup
+        assert_eq!(session.merge_annotations("relaxed"), Ok(0));
+        session.undo().unwrap();
+        assert_eq!(session.merge_annotations("relaxed"), Ok(1));
+        assert_eq!(session.current().annotation(&AnnotationTarget::Label(m)), Some("matched"));
+        assert!(session.switch("other").is_err());
+    }
+
+    #[test]
+    fn from_sequence() {
+        let eh = &mut EventHandler::null();
+        let p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        let mut sequence = vec![];
+        p.autolb(3, 2, 2, 2, None, None, Default::default(), |_, s| sequence = s, eh);
+        assert!(sequence.len() > 2);
+
+        let mut session = Session::from_sequence(&sequence, eh).unwrap();
+        assert_eq!(session.branch().steps.len(), sequence.len());
+        for (step, (_, problem)) in session.branch().steps.iter().zip(sequence.iter()) {
+            assert_eq!(step.problem.to_string(), problem.to_string());
+        }
+        // each operation gives back the problem of the next step
+        let steps = session.branch().steps.clone();
+        for pair in steps.windows(2) {
+            let op = pair[1].op.as_ref().unwrap();
+            let replayed = pair[0].problem.apply_op(op, eh).unwrap().apply_op(&Op::Cleanup, eh).unwrap();
+            assert_eq!(replayed.labels().len(), pair[1].problem.labels().len());
+        }
+
+        // continue manually from the first speedup
+        while session.branch().steps.len() > 2 {
+            session.undo().unwrap();
+        }
+        session.apply(Op::Speedup, eh).unwrap();
+        assert!(Session::from_sequence(&[], eh).is_err());
+
+        // a sequence whose problems do not follow from the operations is rejected
+        let mut tampered = sequence.clone();
+        tampered[1].1 = p.clone();
+        assert!(Session::from_sequence(&tampered, eh).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Mutex, OnceLock},
+};
+
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{algorithms::canonical_hash::CanonicalHash, constraint::Constraint, group::Label, line::Line, problem::Problem};
+
+/// Problems are identified by their whole `CanonicalHash`, which is sent to javascript as a string of hexadecimal digits.
+pub type ProblemId = CanonicalHash;
+
+/// At most this many problems are kept in the store, older ones are forgotten first.
+pub const STORE_CAPACITY: usize = 64;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+pub enum Side {
+    Active,
+    Passive,
+}
+
+/// Problems kept on the side of the library, so that clients can fetch them a piece at a time,
+/// or refer to them in requests without sending them again.
+/// Problems are identified by a hash of their content, so storing the same problem twice gives the same id.
+/// Each client should have its own store, see `request_json_in`, so that clients cannot see, or evict, the problems of each other.
+#[derive(Default)]
+pub struct ProblemStore {
+    problems: HashMap<ProblemId, Problem>,
+    order: VecDeque<ProblemId>,
+}
+
+/// Problems that are equal, in the sense of `Problem::eq`, get the same id.
+/// The id is `Problem::canonical_hash`, so it is the same on all platforms and across versions.
+pub fn problem_id(problem: &Problem) -> ProblemId {
+    problem.canonical_hash()
+}
+
+impl ProblemStore {
+    /// Stores the problem. If an equal problem is stored already, it is replaced, but whatever has been computed about it,
+    /// and is missing in the new one, is kept, since `Problem::eq` does not take it into account. Fails if a different problem has the same id.
+    pub fn insert(&mut self, mut problem: Problem) -> Result<ProblemId, &'static str> {
+        let id = problem_id(&problem);
+        if let Some(old) = self.problems.remove(&id) {
+            if old != problem {
+                self.problems.insert(id, old);
+                return Err("A different problem with the same id is in the store");
+            }
+            problem.keep_computed_from(old);
+        }
+        self.order.retain(|&x| x != id);
+        self.problems.insert(id, problem);
+        self.order.push_back(id);
+        while self.order.len() > STORE_CAPACITY {
+            let old = self.order.pop_front().unwrap();
+            self.problems.remove(&old);
+        }
+        Ok(id)
+    }
+
+    pub fn get(&self, id: ProblemId) -> Option<&Problem> {
+        self.problems.get(&id)
+    }
+
+    pub fn remove(&mut self, id: ProblemId) -> Option<Problem> {
+        self.order.retain(|&x| x != id);
+        self.problems.remove(&id)
+    }
+}
+
+/// Replaces, inside a JSON request, every object of the form `{"ProblemRef": id}` with the stored problem having that id.
+pub fn resolve_problem_references(value: &mut Value, store: &ProblemStore) -> Result<(), &'static str> {
+    let reference = match value {
+        Value::Object(map) if map.len() == 1 => map.get("ProblemRef").and_then(|id| id.as_str()),
+        _ => None,
+    };
+    if let Some(id) = reference {
+        let problem = store.get(id.parse()?).ok_or("The referenced problem is not in the store anymore")?;
+        *value = serde_json::to_value(problem).unwrap();
+        return Ok(());
+    }
+    match value {
+        Value::Object(map) => map.values_mut().try_for_each(|v| resolve_problem_references(v, store)),
+        Value::Array(v) => v.iter_mut().try_for_each(|v| resolve_problem_references(v, store)),
+        _ => Ok(()),
+    }
+}
+
+/// The store shared by all the requests handled by `request_json`, in the whole process.
+pub fn global_store() -> &'static Mutex<ProblemStore> {
+    static STORE: OnceLock<Mutex<ProblemStore>> = OnceLock::new();
+    STORE.get_or_init(|| Mutex::new(ProblemStore::default()))
+}
+
+impl Problem {
+    /// Returns the total number of lines of the given side, and the text of `count` of them starting from `offset`,
+    /// in the same order used when printing the problem.
+    pub fn lines_text(&self, side: Side, offset: usize, count: usize) -> (usize, Vec<String>) {
+        let constraint = self.constraint(side);
+        let mapping = self.mapping_label_text.iter().cloned().collect();
+        let total = constraint.lines.len();
+        let lines = match self.label_display_order() {
+            Some(order) => constraint
+                .lines_ordered(&order)
+                .into_iter()
+                .skip(offset)
+                .take(count)
+                .map(|line| line.to_string_ordered(&mapping, &order))
+                .collect(),
+            None => constraint
+                .lines
+                .iter()
+                .skip(offset)
+                .take(count)
+                .map(|line| line.to_string(&mapping))
+                .collect(),
+        };
+        (total, lines)
+    }
+
+    /// Returns the total number of lines of the given side, and the text of `count` of them chosen at random, in printing order.
+    /// The same seed gives the same lines.
+    pub fn sample_lines(&self, side: Side, count: usize, seed: u64) -> (usize, Vec<String>) {
+        let constraint = self.constraint(side);
+        let mapping = self.mapping_label_text.iter().cloned().collect();
+        let total = constraint.lines.len();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut chosen = rand::seq::index::sample(&mut rng, total, count.min(total)).into_vec();
+        chosen.sort_unstable();
+        let lines = match self.label_display_order() {
+            Some(order) => {
+                let ordered = constraint.lines_ordered(&order);
+                chosen.into_iter().map(|i| ordered[i].to_string_ordered(&mapping, &order)).collect()
+            }
+            None => chosen.into_iter().map(|i| constraint.lines[i].to_string(&mapping)).collect(),
+        };
+        (total, lines)
+    }
+
+    /// Checks whether a configuration, given as text with one label per port, is allowed by the given side.
+    pub fn allows_configuration(&self, side: Side, configuration: &str) -> Result<bool, &'static str> {
+        let constraint = self.constraint(side);
+        let mut mapping: HashMap<String, Label> = self.mapping_label_text.iter().map(|(l, s)| (s.clone(), *l)).collect();
+        let known = mapping.len();
+        let line = Line::parse(configuration, &mut mapping)?;
+        if mapping.len() != known {
+            return Err("The configuration contains labels that are not in the problem");
+        }
+        if line.parts.iter().any(|part| part.group.len() != 1) {
+            return Err("A configuration must contain exactly one label in each port");
+        }
+        if line.degree() != constraint.degree {
+            return Err("The configuration does not have the same degree as the constraint");
+        }
+        Ok(constraint.includes(&line))
+    }
+
+    fn constraint(&self, side: Side) -> &Constraint {
+        match side {
+            Side::Active => &self.active,
+            Side::Passive => &self.passive,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::problem::Problem;
+
+    use super::{ProblemStore, Side, STORE_CAPACITY};
+
+    #[test]
+    fn store_and_pages() {
+        let p = Problem::from_string("A B\nC C\nD D\n\nA B\nC D").unwrap();
+        assert_eq!(p.lines_text(Side::Active, 1, 5), (3, vec!["C^2".to_string(), "D^2".to_string()]));
+        assert_eq!(p.lines_text(Side::Passive, 2, 1), (2, vec![]));
+
+        let mut store = ProblemStore::default();
+        let first = store.insert(p.clone()).unwrap();
+        assert_eq!(store.insert(p.clone()), Ok(first));
+        let ids: Vec<_> = (0..STORE_CAPACITY)
+            .map(|i| store.insert(Problem::from_string(format!("A^{}\n\nA", i + 1)).unwrap()).unwrap())
+            .collect();
+        assert!(store.get(first).is_none());
+        assert!(store.get(ids[0]).is_some());
+        assert!(store.remove(ids[0]).is_some());
+        // a different problem stored with the id of p, as it would happen if their hashes collided
+        store.problems.insert(super::problem_id(&p), ids.iter().find_map(|&id| store.get(id)).unwrap().clone());
+        assert!(store.insert(p.clone()).is_err());
+        assert!(store.get(ids[0]).is_none());
+
+        let reordered = Problem::from_string("A B\nC C\nD D\n\nD C\nA B").unwrap();
+        assert_eq!(reordered, p);
+        assert_eq!(reordered.to_string(), p.to_string());
+        assert_eq!(super::problem_id(&reordered), super::problem_id(&p));
+        assert_ne!(reordered, Problem::from_string("A B\nC C\nD D\n\nD C\nA B\nA C").unwrap());
+
+        // storing an equal problem keeps what has been computed for the stored one
+        let mut computed = p.clone();
+        computed.compute_partial_diagram(&mut crate::algorithms::event::EventHandler::null());
+        let mut store = ProblemStore::default();
+        let id = store.insert(computed.clone()).unwrap();
+        assert_eq!(store.insert(reordered), Ok(id));
+        assert_eq!(store.get(id).unwrap().diagram_indirect, computed.diagram_indirect);
+        // references are written with the id as a string
+        let reference = |id: String| serde_json::Value::Object(serde_json::Map::from_iter([("ProblemRef".to_string(), serde_json::Value::String(id))]));
+        let mut request = reference(id.to_string());
+        super::resolve_problem_references(&mut request, &store).unwrap();
+        assert!(request.get("ProblemRef").is_none());
+        assert!(super::resolve_problem_references(&mut reference("12".into()), &store).is_err());
+
+        let (total, sample) = p.sample_lines(Side::Active, 2, 7);
+        assert_eq!((total, sample.len()), (3, 2));
+        assert_eq!(p.sample_lines(Side::Active, 2, 7).1, sample);
+        assert_eq!(p.sample_lines(Side::Passive, 5, 0).1.len(), 2);
+        assert_eq!(p.allows_configuration(Side::Passive, "B A"), Ok(true));
+        assert_eq!(p.allows_configuration(Side::Passive, "A A"), Ok(false));
+        assert!(p.allows_configuration(Side::Passive, "A X").is_err());
+        assert!(p.allows_configuration(Side::Passive, "A B C").is_err());
+    }
+}
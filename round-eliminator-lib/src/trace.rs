@@ -0,0 +1,148 @@
+use std::{
+    io::{BufRead, Write},
+    sync::Mutex,
+    time::Instant,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    algorithms::canonical_hash::{CanonicalHash, Fnv1a128},
+    serial::{request_json_in, Request, Response},
+    store::{global_store, ProblemStore},
+};
+
+/// One line of a trace file: the request as it was received, how long it took, and a hash of the responses it produced.
+/// Events are not part of the hash, since they depend on timing. The hash is `fnv1a_128`, so that traces recorded by
+/// different builds, or on different platforms, can be compared.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TraceEntry {
+    pub request: String,
+    pub elapsed_ms: u64,
+    pub result_hash: CanonicalHash,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReplayOutcome {
+    pub index: usize,
+    pub entry: TraceEntry,
+    pub result_hash: CanonicalHash,
+}
+
+impl ReplayOutcome {
+    pub fn matches(&self) -> bool {
+        self.entry.result_hash == self.result_hash
+    }
+}
+
+fn is_event(response: &str) -> bool {
+    response.starts_with("{\"Event\"")
+}
+
+/// Same as `request_json`, but additionally returns a trace entry describing the request.
+pub fn request_json_traced<F>(req: &str, f: F) -> TraceEntry
+where
+    F: Fn(String, bool),
+{
+    request_json_traced_in(req, global_store(), f)
+}
+
+/// Same as `request_json_traced`, but with the given store, see `request_json_in`.
+pub fn request_json_traced_in<F>(req: &str, store: &Mutex<ProblemStore>, f: F) -> TraceEntry
+where
+    F: Fn(String, bool),
+{
+    let hasher = Mutex::new(Fnv1a128::default());
+    let start = Instant::now();
+    request_json_in(req, store, |s, send_to_client| {
+        if send_to_client && !is_event(&s) {
+            // each response is prefixed by its length, so that the boundaries between responses are part of the hash
+            let mut hasher = hasher.lock().unwrap();
+            hasher.write(format!("{}:", s.len()).as_bytes());
+            hasher.write(s.as_bytes());
+        }
+        f(s, send_to_client);
+    });
+    TraceEntry {
+        request: req.to_string(),
+        elapsed_ms: start.elapsed().as_millis() as u64,
+        result_hash: hasher.into_inner().unwrap().finish(),
+    }
+}
+
+fn invalid_data(e: serde_json::Error) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string())
+}
+
+/// Same as `request_json_traced`, but for a request that has not been serialized yet.
+pub fn request_traced<F>(req: &Request, f: F) -> std::io::Result<TraceEntry>
+where
+    F: Fn(String, bool),
+{
+    Ok(request_json_traced(&serde_json::to_string(req).map_err(invalid_data)?, f))
+}
+
+/// Same as `request_traced`, but passes to `f` the responses sent to the client, decoded, instead of their serialization.
+/// Events are not passed to `f`.
+pub fn request_traced_responses<F>(req: &Request, f: F) -> std::io::Result<TraceEntry>
+where
+    F: Fn(Response),
+{
+    request_traced(req, |s, send_to_client| {
+        if send_to_client && !is_event(&s) {
+            if let Ok(response) = serde_json::from_str(&s) {
+                f(response);
+            }
+        }
+    })
+}
+
+/// Runs a request and appends its trace entry, as a single JSON line, to the given writer.
+pub fn request_json_to_trace<F, W>(req: &str, f: F, trace: &mut W) -> std::io::Result<()>
+where
+    F: Fn(String, bool),
+    W: Write,
+{
+    let entry = request_json_traced(req, f);
+    write_entry(&entry, trace)
+}
+
+pub fn write_entry<W: Write>(entry: &TraceEntry, trace: &mut W) -> std::io::Result<()> {
+    writeln!(trace, "{}", serde_json::to_string(entry).map_err(invalid_data)?)?;
+    trace.flush()
+}
+
+pub fn request_to_trace<F, W>(req: &Request, f: F, trace: &mut W) -> std::io::Result<()>
+where
+    F: Fn(String, bool),
+    W: Write,
+{
+    let entry = request_traced(req, f)?;
+    write_entry(&entry, trace)
+}
+
+pub fn read_trace<R: BufRead>(trace: R) -> Result<Vec<TraceEntry>, &'static str> {
+    trace
+        .lines()
+        .map(|line| line.map_err(|_| "Cannot read the trace"))
+        .filter(|line| line.as_ref().map_or(true, |l| !l.trim().is_empty()))
+        .map(|line| serde_json::from_str(&line?).map_err(|_| "Invalid trace entry"))
+        .collect()
+}
+
+/// Runs again all the requests contained in a trace, and reports, for each of them, whether the results are the same.
+pub fn replay<R: BufRead, F: FnMut(&ReplayOutcome)>(trace: R, mut f: F) -> Result<Vec<ReplayOutcome>, &'static str> {
+    let entries = read_trace(trace)?;
+    let mut outcomes = vec![];
+    for (index, entry) in entries.into_iter().enumerate() {
+        let new = request_json_traced(&entry.request, |_, _| {});
+        let outcome = ReplayOutcome {
+            index,
+            entry,
+            result_hash: new.result_hash,
+        };
+        f(&outcome);
+        outcomes.push(outcome);
+    }
+    Ok(outcomes)
+}
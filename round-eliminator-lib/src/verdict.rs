@@ -0,0 +1,99 @@
+//! Machine-readable outcomes of classifications and automatic searches, that tell proofs apart from searches that gave up.
+
+use serde::{Deserialize, Serialize};
+
+use crate::corpus::{Budget, Classification};
+
+/// The value reported by autolb for sequences that reach a problem seen before.
+pub(crate) const AUTOLB_FIXED_POINT: usize = 999;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Verdict {
+    /// The problem is zero round solvable.
+    ProvedTrivial,
+    /// The problem can be solved in exactly this many rounds.
+    ProvedRounds(usize),
+    /// The problem requires at least this many rounds.
+    ProvedLowerBound(usize),
+    /// The problem can be solved in this many rounds.
+    ProvedUpperBound(usize),
+    /// The problem can be solved in this many rounds if a proper coloring is given as input.
+    ProvedUpperBoundGivenColoring(usize),
+    /// Relaxing the problem after speedups gives back a problem seen before, which implies a lower bound that grows with the size of the graph.
+    ProvedFixedPoint,
+    /// After some speedups a side has no configurations left, so the problem cannot be solved in any number of rounds.
+    ProvedUnsolvable,
+    /// The search did not find anything within the given limits, which proves nothing.
+    HeuristicNoResult { budget: Budget },
+}
+
+impl Verdict {
+    /// Whether the verdict is backed by a proof.
+    pub fn is_proof(&self) -> bool {
+        !matches!(self, Verdict::HeuristicNoResult { .. })
+    }
+
+    /// The verdict of a sequence found by autoub, see `Problem::autoub`.
+    pub fn from_autoub(len: usize, is_trivial: bool) -> Self {
+        match (len, is_trivial) {
+            (0, true) => Verdict::ProvedTrivial,
+            (_, true) => Verdict::ProvedUpperBound(len),
+            (_, false) => Verdict::ProvedUpperBoundGivenColoring(len),
+        }
+    }
+
+    /// The verdict of a sequence found by autolb, see `Problem::autolb`.
+    pub fn from_autolb(len: usize) -> Self {
+        if len == AUTOLB_FIXED_POINT {
+            Verdict::ProvedFixedPoint
+        } else {
+            Verdict::ProvedLowerBound(len)
+        }
+    }
+}
+
+impl From<Classification> for Verdict {
+    fn from(classification: Classification) -> Self {
+        match classification {
+            Classification::Rounds(0) => Verdict::ProvedTrivial,
+            Classification::Rounds(r) => Verdict::ProvedRounds(r),
+            Classification::AtLeast(r) => Verdict::ProvedLowerBound(r),
+            Classification::Unsolvable => Verdict::ProvedUnsolvable,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::{
+        algorithms::{autolb::LbStrategy, autoub::{EvaluationPolicy, SearchStats}, event::EventHandler, pruning_rules::PruningRules},
+        corpus::{classify, Budget},
+        problem::Problem,
+    };
+
+    use super::Verdict;
+
+    #[test]
+    fn verdict() {
+        let eh = &mut EventHandler::null();
+        let budget = Budget { max_steps: 2, max_labels: 10 };
+        let p = Problem::from_string("M U U\nP P P\n\nM UP\nU U").unwrap();
+        let verdict = Verdict::from(classify(&p, budget, eh));
+        assert_eq!(verdict, Verdict::ProvedLowerBound(3));
+        assert!(verdict.is_proof());
+
+        assert_eq!(Verdict::from_autoub(0, true), Verdict::ProvedTrivial);
+        assert_eq!(Verdict::from_autolb(999), Verdict::ProvedFixedPoint);
+        assert!(!Verdict::HeuristicNoResult { budget }.is_proof());
+
+        // maximal matching needs 3 rounds on this graph, so a search with 1 step finds nothing
+        let mut stats = SearchStats::default();
+        let verdict = p.autoautoub(true, 4, None, true, 2, true, 1, None, None, EvaluationPolicy::Lazy, false, &PruningRules::default(), &mut stats, |_, _, _| {}, eh);
+        assert_eq!(verdict, Verdict::HeuristicNoResult { budget: Budget { max_steps: 1, max_labels: 4 } });
+        // a zero round solvable problem has no lower bound, and the number of labels tried is reported instead of the one given
+        let q = Problem::from_string("A A A\n\nA A").unwrap();
+        let verdict = q.autoautolb(false, 0, None, true, 2, true, 1, None, None, LbStrategy::Labels, false, &PruningRules::default(), |_, _| {}, eh);
+        assert_eq!(verdict, Verdict::HeuristicNoResult { budget: Budget { max_steps: 1, max_labels: 2 } });
+    }
+}
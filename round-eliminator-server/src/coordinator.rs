@@ -0,0 +1,148 @@
+use futures_util::{SinkExt, StreamExt};
+use round_eliminator_lib::{
+    algorithms::{autoub::EvaluationPolicy, event::EventHandler},
+    pipeline::Op,
+    problem::Problem,
+    serial::{AutoOperation, Request, Response},
+};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tokio::net::TcpStream;
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// How often the best bound and the visited problems are exchanged between the workers.
+const SYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+async fn connect(url: &str) -> Result<Socket, String> {
+    let (socket, _) = connect_async(url)
+        .await
+        .map_err(|e| format!("cannot connect to {}: {}", url, e))?;
+    Ok(socket)
+}
+
+async fn send(socket: &mut Socket, request: &Request) -> Result<(), String> {
+    let text = serde_json::to_string(request).map_err(|e| e.to_string())?;
+    socket.send(Message::Text(text)).await.map_err(|e| e.to_string())
+}
+
+/// Reads the responses to a request, up to the final Done.
+async fn responses(socket: &mut Socket) -> Result<Vec<Response>, String> {
+    let mut result = vec![];
+    while let Some(message) = socket.next().await {
+        let Message::Text(text) = message.map_err(|e| e.to_string())? else {
+            continue;
+        };
+        match serde_json::from_str(&text).map_err(|e| e.to_string())? {
+            Response::Done => return Ok(result),
+            response => result.push(response),
+        }
+    }
+    Err("the worker closed the connection".into())
+}
+
+/// Splits an autoub search among the servers listening at the given websocket urls, for example ws://host:8080/api.
+/// Each server explores a part of the top-level branches, and every second the coordinator forwards to each of them
+/// the best upper bound found so far and the problems visited by the others.
+pub async fn coordinate(file: &str, max_labels: usize, branching: usize, max_steps: usize, workers: &[String]) -> Result<(), String> {
+    let text = std::fs::read_to_string(file).map_err(|e| e.to_string())?;
+    let problem = Problem::from_string(text)?.apply_op(&Op::Cleanup, &mut EventHandler::null())?;
+    let search = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64;
+
+    let best: Arc<Mutex<Option<(usize, Vec<(AutoOperation, Problem)>)>>> = Arc::new(Mutex::new(None));
+    let running = Arc::new(AtomicUsize::new(workers.len()));
+    let mut sync_sockets = vec![];
+    for (index, url) in workers.iter().enumerate() {
+        let mut socket = connect(url).await?;
+        let request = Request::AutoUbShard(
+            search,
+            problem.clone(),
+            max_labels,
+            branching,
+            max_steps,
+            false,
+            0,
+            false,
+            0,
+            EvaluationPolicy::Eager,
+            index,
+            workers.len(),
+        );
+        send(&mut socket, &request).await?;
+        sync_sockets.push(connect(url).await?);
+
+        let best = best.clone();
+        let running = running.clone();
+        tokio::spawn(async move {
+            match responses(&mut socket).await {
+                Ok(responses) => {
+                    for response in responses {
+                        if let Response::AutoUb(len, sequence, _, _) = response {
+                            let mut best = best.lock().unwrap();
+                            if best.as_ref().map_or(true, |(b, _)| len < *b) {
+                                println!("worker {} found an upper bound of {} rounds", index, len);
+                                *best = Some((len, sequence));
+                            }
+                        } else if let Response::E(e) = response {
+                            eprintln!("worker {}: {}", index, e);
+                        }
+                    }
+                }
+                Err(e) => eprintln!("worker {}: {}", index, e),
+            }
+            running.fetch_sub(1, Ordering::AcqRel);
+        });
+    }
+
+    // keys visited by some worker that still have to be sent to each worker
+    let mut pending: Vec<Vec<u64>> = vec![vec![]; workers.len()];
+    while running.load(Ordering::Acquire) > 0 {
+        tokio::time::sleep(SYNC_INTERVAL).await;
+        let steps = best.lock().unwrap().as_ref().map(|(len, _)| *len);
+        for (index, socket) in sync_sockets.iter_mut().enumerate() {
+            let keys = std::mem::take(&mut pending[index]);
+            send(socket, &Request::SyncShard(search, steps, keys.clone())).await?;
+            let state = responses(socket).await?.into_iter().find_map(|r| match r {
+                Response::ShardState(_, new_keys) => Some(new_keys),
+                _ => None,
+            });
+            match state {
+                Some(new_keys) => {
+                    for (other, p) in pending.iter_mut().enumerate() {
+                        if other != index {
+                            p.extend_from_slice(&new_keys);
+                        }
+                    }
+                }
+                // the shard has not started yet, or it has already finished
+                None => pending[index] = keys,
+            }
+        }
+    }
+
+    match best.lock().unwrap().take() {
+        Some((len, sequence)) => {
+            println!("upper bound of {} rounds", len);
+            for (op, p) in sequence {
+                match op {
+                    AutoOperation::Initial => println!("initial problem"),
+                    AutoOperation::Harden(_) => println!("harden"),
+                    AutoOperation::Merge(_, _) => println!("merge"),
+                    AutoOperation::Speedup => println!("speedup"),
+                }
+                println!("{}", p);
+            }
+        }
+        None => println!("no upper bound found"),
+    }
+    Ok(())
+}
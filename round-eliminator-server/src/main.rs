@@ -1,15 +1,34 @@
 use futures_util::StreamExt;
+use round_eliminator_lib::store::ProblemStore;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
-    Arc,
+    Arc, Mutex,
 };
 use warp::{
     ws::{Message, WebSocket, Ws},
     Filter,
 };
 
+mod coordinator;
+
 #[tokio::main]
 async fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    // round-eliminator-server coordinate FILE MAX_LABELS BRANCHING MAX_STEPS WORKER_URL...
+    if args.len() >= 7 && args[1] == "coordinate" {
+        let number = |name: &str, s: &str| s.parse::<usize>().map_err(|_| format!("{} must be a number, got {}", name, s));
+        let params = number("MAX_LABELS", &args[3]).and_then(|max_labels| {
+            Ok((max_labels, number("BRANCHING", &args[4])?, number("MAX_STEPS", &args[5])?))
+        });
+        let result = match params {
+            Ok((max_labels, branching, max_steps)) => coordinator::coordinate(&args[2], max_labels, branching, max_steps, &args[6..]).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            eprintln!("{}", e);
+        }
+        return;
+    }
     server("127.0.0.1:8080").await; 
 }
 
@@ -45,6 +64,21 @@ async fn serve_client(ws: WebSocket) {
 
     let stop = Arc::new(AtomicBool::new(false));
 
+    // the problems uploaded by this client, that other clients cannot see
+    let store = Arc::new(Mutex::new(ProblemStore::default()));
+
+    // if RE_TRACE is set, all requests are appended to that file, so that they can be replayed later with the cli
+    // if the file cannot be opened, the requests are served anyway, without tracing them
+    let trace = std::env::var("RE_TRACE").ok().and_then(|path| {
+        match std::fs::OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => Some(Arc::new(Mutex::new(file))),
+            Err(e) => {
+                eprintln!("cannot open the trace file {}: {}", path, e);
+                None
+            }
+        }
+    });
+
     while let Some(m) = ws_rx.next().await {
         match m {
             Ok(m) => {
@@ -56,8 +90,10 @@ async fn serve_client(ws: WebSocket) {
                     } else {
                         let tx = tx.clone();
                         let stop = stop.clone();
+                        let trace = trace.clone();
+                        let store = store.clone();
                         let fun = move || {
-                            round_eliminator_lib::serial::request_json(&request, |s, send_to_client| {
+                            let f = |s: String, send_to_client: bool| {
                                 if stop.load(Ordering::Acquire) {
                                     panic!("stopping thread");
                                 }
@@ -65,10 +101,38 @@ async fn serve_client(ws: WebSocket) {
                                     tx.unbounded_send(Message::text(s))
                                         .expect("unbounded_send failed!");
                                 }
-                            });
+                            };
+                            if let Some(trace) = trace {
+                                let entry = round_eliminator_lib::trace::request_json_traced_in(&request, &store, f);
+                                let mut trace = trace.lock().unwrap();
+                                if let Err(e) = round_eliminator_lib::trace::write_entry(&entry, &mut *trace) {
+                                    eprintln!("cannot write to the trace file: {}", e);
+                                }
+                            } else {
+                                round_eliminator_lib::serial::request_json_in(&request, &store, f);
+                            }
                         };
                         tokio::task::spawn_blocking(fun);
                     }
+                } else if m.is_binary() {
+                    // binary requests are encoded in CBOR, and so are their responses; they are not traced, since traces are JSON
+                    let request = m.into_bytes();
+                    let tx = tx.clone();
+                    let stop = stop.clone();
+                    let store = store.clone();
+                    let fun = move || {
+                        let f = |v: Vec<u8>, send_to_client: bool| {
+                            if stop.load(Ordering::Acquire) {
+                                panic!("stopping thread");
+                            }
+                            if send_to_client {
+                                tx.unbounded_send(Message::binary(v))
+                                    .expect("unbounded_send failed!");
+                            }
+                        };
+                        round_eliminator_lib::serial::request_cbor_in(&request, &store, f);
+                    };
+                    tokio::task::spawn_blocking(fun);
                 }
             }
             Err(e) => {
@@ -1,5 +1,8 @@
 use log::Level;
+use round_eliminator_lib::algorithms::event::EventThrottle;
 use wasm_bindgen::prelude::*;
+
+pub mod problem;
 //pub use wasm_bindgen_rayon::init_thread_pool;
 
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
@@ -19,6 +22,53 @@ pub fn request_json(req: &str, f: &js_sys::Function) {
     });
 }
 
+/// Same as `request_json`, but the request and the responses are CBOR bytes, passed as `Uint8Array`s.
+#[wasm_bindgen]
+pub fn request_cbor(req: &[u8], f: &js_sys::Function) {
+    round_eliminator_lib::serial::request_cbor(req, |v, send_to_client| {
+        if send_to_client {
+            let this = JsValue::NULL;
+            let v = JsValue::from(js_sys::Uint8Array::from(&v[..]));
+            let _ = f.call1(&this, &v);
+        }
+    });
+}
+
+/// Same as `request_json`, but progress events are sent in batches, at most `max_events_per_second` times per second,
+/// so that big computations do not flood the page with messages.
+#[wasm_bindgen]
+pub fn request_json_throttled(req: &str, f: &js_sys::Function, max_events_per_second: u32) {
+    round_eliminator_lib::serial::request_json_throttled(
+        req,
+        |s, send_to_client| {
+            if send_to_client {
+                let this = JsValue::NULL;
+                let s = JsValue::from(s);
+                let _ = f.call1(&this, &s);
+            }
+        },
+        EventThrottle::new(max_events_per_second),
+        js_sys::Date::now,
+    );
+}
+
+/// Same as `request_cbor`, with progress events handled as in `request_json_throttled`.
+#[wasm_bindgen]
+pub fn request_cbor_throttled(req: &[u8], f: &js_sys::Function, max_events_per_second: u32) {
+    round_eliminator_lib::serial::request_cbor_throttled(
+        req,
+        |v, send_to_client| {
+            if send_to_client {
+                let this = JsValue::NULL;
+                let v = JsValue::from(js_sys::Uint8Array::from(&v[..]));
+                let _ = f.call1(&this, &v);
+            }
+        },
+        EventThrottle::new(max_events_per_second),
+        js_sys::Date::now,
+    );
+}
+
 #[wasm_bindgen(start)]
 pub fn main() -> Result<(), JsValue> {
     #[cfg(feature = "console_error_panic_hook")]
@@ -0,0 +1,182 @@
+use round_eliminator_lib::{algorithms::{autolb::LbStrategy, autoub::{EvaluationPolicy, SearchStats}, event::EventHandler}, group::Label, line::Degree, problem::Problem};
+use serde::Serialize;
+use wasm_bindgen::{prelude::*, JsCast};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_TYPES: &'static str = r#"
+export type Label = number;
+export type Diagram = [Label, Label][];
+export type LabelSets = Label[][];
+export type AutoOperation = "Initial" | "Speedup" | { Harden: Label[] } | { Merge: [[Label, Label][], any] };
+export type AutoSequence = [AutoOperation, any][];
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "Diagram")]
+    pub type JsDiagram;
+    #[wasm_bindgen(typescript_type = "LabelSets")]
+    pub type JsLabelSets;
+    #[wasm_bindgen(typescript_type = "(len: number, sequence: AutoSequence) => void")]
+    pub type JsAutoCallback;
+}
+
+fn to_js<T: Serialize>(value: &T) -> JsValue {
+    js_sys::JSON::parse(&serde_json::to_string(value).unwrap()).unwrap()
+}
+
+/// A problem of the new library, exposed directly to javascript, without going through `request_json`.
+#[wasm_bindgen(js_name = Problem)]
+pub struct WasmProblem {
+    inner: Problem,
+}
+
+#[wasm_bindgen(js_class = Problem)]
+impl WasmProblem {
+    pub fn parse(text: &str) -> Result<WasmProblem, JsValue> {
+        Problem::from_string(text)
+            .map(|inner| WasmProblem { inner })
+            .map_err(JsValue::from_str)
+    }
+
+    /// Accepts a problem in the same format used by `request_json`.
+    #[wasm_bindgen(js_name = fromJSON)]
+    pub fn from_json(json: &str) -> Result<WasmProblem, JsValue> {
+        serde_json::from_str(json)
+            .map(|inner| WasmProblem { inner })
+            .map_err(|_| JsValue::from_str("Invalid problem"))
+    }
+
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.inner).unwrap()
+    }
+
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_string(&self) -> String {
+        format!("{}", self.inner)
+    }
+
+    pub fn labels(&self) -> Vec<Label> {
+        self.inner.labels()
+    }
+
+    #[wasm_bindgen(js_name = labelText)]
+    pub fn label_text(&self, label: Label) -> Option<String> {
+        self.inner
+            .mapping_label_text
+            .iter()
+            .find(|(l, _)| *l == label)
+            .map(|(_, s)| s.clone())
+    }
+
+    pub fn speedup(&self) -> WasmProblem {
+        let eh = &mut EventHandler::null();
+        let mut p = self.inner.clone();
+        if p.diagram_indirect.is_none() {
+            p.compute_partial_diagram(eh);
+        }
+        WasmProblem { inner: p.speedup(eh) }
+    }
+
+    pub fn maximize(&mut self) {
+        self.inner.passive.maximize(&mut EventHandler::null());
+    }
+
+    #[wasm_bindgen(js_name = mergeEquivalentLabels)]
+    pub fn merge_equivalent_labels(&self) -> WasmProblem {
+        WasmProblem {
+            inner: self.inner.merge_equivalent_labels(),
+        }
+    }
+
+    /// Computes the diagram, removes the useless labels and configurations, and sorts the active lines, as done after each operation of the GUI.
+    pub fn simplify(&mut self) {
+        let eh = &mut EventHandler::null();
+        self.inner.diagram_indirect = None;
+        if self.inner.passive.degree == Degree::Finite(2) {
+            self.inner.compute_diagram(eh);
+            self.inner.discard_useless_stuff(true, eh);
+        } else {
+            self.inner.discard_useless_stuff(false, eh);
+        }
+        self.inner.sort_active_by_strength();
+    }
+
+    #[wasm_bindgen(js_name = computeDiagram)]
+    pub fn compute_diagram(&mut self) {
+        self.inner.diagram_indirect = None;
+        self.inner.compute_diagram(&mut EventHandler::null());
+    }
+
+    /// The transitive closure of the diagram, as a list of pairs (a,b) meaning that b is at least as strong as a.
+    pub fn diagram(&self) -> Option<JsDiagram> {
+        self.inner
+            .diagram_indirect
+            .as_ref()
+            .map(|d| to_js(d).unchecked_into())
+    }
+
+    #[wasm_bindgen(js_name = computeTriviality)]
+    pub fn compute_triviality(&mut self) {
+        self.inner.trivial_sets = None;
+        self.inner.compute_triviality(&mut EventHandler::null());
+    }
+
+    #[wasm_bindgen(js_name = isTrivial)]
+    pub fn is_trivial(&self) -> Option<bool> {
+        self.inner.trivial_sets.as_ref().map(|t| !t.is_empty())
+    }
+
+    #[wasm_bindgen(js_name = trivialSets)]
+    pub fn trivial_sets(&self) -> Option<JsLabelSets> {
+        self.inner.trivial_sets.as_ref().map(|t| to_js(t).unchecked_into())
+    }
+
+    #[wasm_bindgen(js_name = computeColoringSolvability)]
+    pub fn compute_coloring_solvability(&mut self) {
+        self.inner.coloring_sets = None;
+        self.inner.compute_coloring_solvability(&mut EventHandler::null());
+    }
+
+    #[wasm_bindgen(js_name = coloringSets)]
+    pub fn coloring_sets(&self) -> Option<JsLabelSets> {
+        self.inner.coloring_sets.as_ref().map(|t| to_js(t).unchecked_into())
+    }
+
+    /// Searches for upper bounds, calling `f` every time a better sequence of operations is found.
+    pub fn autoub(&self, max_labels: usize, branching: usize, max_steps: usize, f: JsAutoCallback) {
+        let f: js_sys::Function = f.unchecked_into();
+        self.inner.autoub(
+            max_labels,
+            branching,
+            max_steps,
+            None,
+            None,
+            EvaluationPolicy::Lazy,
+            &mut SearchStats::default(),
+            |len, _, sequence| {
+                let _ = f.call2(&JsValue::NULL, &JsValue::from(len as u32), &to_js(&sequence));
+            },
+            &mut EventHandler::null(),
+        );
+    }
+
+    /// Searches for lower bounds, calling `f` every time a better sequence of operations is found.
+    pub fn autolb(&self, max_labels: usize, branching: usize, max_steps: usize, f: JsAutoCallback) {
+        let f: js_sys::Function = f.unchecked_into();
+        self.inner.autolb(
+            max_labels,
+            branching,
+            1,
+            max_steps,
+            None,
+            None,
+            LbStrategy::Labels,
+            |len, sequence| {
+                let _ = f.call2(&JsValue::NULL, &JsValue::from(len as u32), &to_js(&sequence));
+            },
+            &mut EventHandler::null(),
+        );
+    }
+}
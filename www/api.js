@@ -1,7 +1,21 @@
     
 
+import * as cbor from "./cbor.js";
+
 let dontgc = [];
 
+// requests are sent encoded in CBOR, which is much faster than JSON for large problems, unless the url asks for json
+let use_cbor = !window.location.href.includes("json");
+
+function encode_request(req) {
+    return use_cbor ? cbor.encode(req) : JSON.stringify(req);
+}
+
+// the responses use the same encoding of the request, while messages sent as text are always JSON
+function decode_response(m) {
+    return typeof m === "string" ? JSON.parse(m) : cbor.decode(m);
+}
+
 function request_wasm(req, onresult, oncomplete) {
     let t0 = performance.now();
 
@@ -13,11 +27,10 @@ function request_wasm(req, onresult, oncomplete) {
     }
 
     w.onmessage = function (s){
-        let r = s.data;
-        let o = JSON.parse(r);
+        let o = decode_response(s.data);
 
         if( o == "WASM_READY"){
-            let r = JSON.stringify(req);
+            let r = encode_request(req);
             w.postMessage(r);
         } else if( o == "Pong" ){
 
@@ -46,7 +59,8 @@ function request_wasm(req, onresult, oncomplete) {
 
 function request_server(req, onresult, oncomplete) {
     let a = new WebSocket("ws://" + location.host + "/api");
-    let r = JSON.stringify(req);
+    a.binaryType = "arraybuffer";
+    let r = encode_request(req);
     let terminated = false;
     let opened = false;
 
@@ -79,8 +93,7 @@ function request_server(req, onresult, oncomplete) {
     }
     a.onmessage = function(s){
         if( terminated )return;
-        let m = s.data;
-        let o = JSON.parse(m);
+        let o = decode_response(s.data);
         //console.log(o);
         if( o == "Pong" ){
         }else if( o != "Done" ){
@@ -0,0 +1,130 @@
+// A minimal CBOR encoder and decoder, covering what serde produces and accepts for the requests and the responses:
+// integers, floats, strings, arrays, maps, booleans and null.
+
+function write_header(out, major, n) {
+    if (n < 24) {
+        out.push((major << 5) | n);
+    } else if (n < 0x100) {
+        out.push((major << 5) | 24, n);
+    } else if (n < 0x10000) {
+        out.push((major << 5) | 25, n >> 8, n & 0xff);
+    } else if (n < 0x100000000) {
+        out.push((major << 5) | 26, (n >>> 24) & 0xff, (n >> 16) & 0xff, (n >> 8) & 0xff, n & 0xff);
+    } else {
+        let high = Math.floor(n / 0x100000000);
+        let low = n % 0x100000000;
+        out.push((major << 5) | 27);
+        for (let x of [high, low]) {
+            out.push((x >>> 24) & 0xff, (x >> 16) & 0xff, (x >> 8) & 0xff, x & 0xff);
+        }
+    }
+}
+
+function write_item(out, x) {
+    if (x === null || x === undefined) {
+        out.push(0xf6);
+    } else if (x === false) {
+        out.push(0xf4);
+    } else if (x === true) {
+        out.push(0xf5);
+    } else if (typeof x === "number") {
+        if (Number.isSafeInteger(x)) {
+            if (x >= 0) write_header(out, 0, x); else write_header(out, 1, -1 - x);
+        } else {
+            let b = new Uint8Array(8);
+            new DataView(b.buffer).setFloat64(0, x);
+            out.push(0xfb, ...b);
+        }
+    } else if (typeof x === "string") {
+        let b = new TextEncoder().encode(x);
+        write_header(out, 3, b.length);
+        for (let c of b) out.push(c);
+    } else if (Array.isArray(x)) {
+        write_header(out, 4, x.length);
+        for (let y of x) write_item(out, y);
+    } else {
+        let keys = Object.keys(x);
+        write_header(out, 5, keys.length);
+        for (let k of keys) {
+            write_item(out, k);
+            write_item(out, x[k]);
+        }
+    }
+}
+
+export function encode(x) {
+    let out = [];
+    write_item(out, x);
+    return new Uint8Array(out);
+}
+
+function read_item(view, state) {
+    let first = view.getUint8(state.pos++);
+    let major = first >> 5;
+    let info = first & 0x1f;
+    if (major == 7) {
+        switch (info) {
+            case 20: return false;
+            case 21: return true;
+            case 22: case 23: return null;
+            case 25: {
+                // half precision floats are not supported by DataView
+                let h = view.getUint16(state.pos); state.pos += 2;
+                let exp = (h >> 10) & 0x1f, mant = h & 0x3ff;
+                let v = exp == 0 ? mant * Math.pow(2, -24) : exp == 31 ? (mant ? NaN : Infinity) : (mant + 1024) * Math.pow(2, exp - 25);
+                return (h & 0x8000) ? -v : v;
+            }
+            case 26: { let v = view.getFloat32(state.pos); state.pos += 4; return v; }
+            case 27: { let v = view.getFloat64(state.pos); state.pos += 8; return v; }
+            default: throw new Error("unsupported CBOR simple value " + info);
+        }
+    }
+    let n;
+    if (info < 24) {
+        n = info;
+    } else if (info == 24) {
+        n = view.getUint8(state.pos); state.pos += 1;
+    } else if (info == 25) {
+        n = view.getUint16(state.pos); state.pos += 2;
+    } else if (info == 26) {
+        n = view.getUint32(state.pos); state.pos += 4;
+    } else if (info == 27) {
+        n = view.getUint32(state.pos) * 0x100000000 + view.getUint32(state.pos + 4); state.pos += 8;
+    } else {
+        throw new Error("unsupported CBOR length " + info);
+    }
+    switch (major) {
+        case 0: return n;
+        case 1: return -1 - n;
+        case 2: {
+            let b = new Uint8Array(view.buffer, view.byteOffset + state.pos, n);
+            state.pos += n;
+            return Array.from(b);
+        }
+        case 3: {
+            let b = new Uint8Array(view.buffer, view.byteOffset + state.pos, n);
+            state.pos += n;
+            return new TextDecoder().decode(b);
+        }
+        case 4: {
+            let v = [];
+            for (let i = 0; i < n; i++) v.push(read_item(view, state));
+            return v;
+        }
+        case 5: {
+            let o = {};
+            for (let i = 0; i < n; i++) {
+                let k = read_item(view, state);
+                o[k] = read_item(view, state);
+            }
+            return o;
+        }
+        // tags, such as the ones of large integers, are skipped
+        case 6: return read_item(view, state);
+    }
+}
+
+export function decode(bytes) {
+    let b = bytes instanceof Uint8Array ? bytes : new Uint8Array(bytes);
+    return read_item(new DataView(b.buffer, b.byteOffset, b.byteLength), { pos : 0 });
+}
@@ -24,6 +24,12 @@ function handle_result(x, onresult, onerror, progress) {
         }
         onresult(x.AutoLb)
     }
+    if( x.Irregular != null ){
+        onresult(x.Irregular);
+    }
+    if( x.IrregularAutoLb != null ){
+        onresult(x.IrregularAutoLb);
+    }
 
     if( x.Event != null ){
         progress.type = x.Event[0];
@@ -32,6 +38,13 @@ function handle_result(x, onresult, onerror, progress) {
             progress.max = x.Event[2];
         }
     }
+    // a batch of throttled events, only the most recent one is shown
+    if( x.Events != null && x.Events.length > 0 ){
+        let e = x.Events[x.Events.length - 1];
+        progress.type = e[0];
+        progress.cur = e[1];
+        progress.max = e[2];
+    }
 };
 
 function new_problem(left, right, onresult, onerror, progress) {
@@ -39,9 +52,45 @@ function new_problem(left, right, onresult, onerror, progress) {
     api.request({ NewProblem : [left,right] }, ondata , function(){});
 }
 
-function speedup(problem, onresult, onerror, progress){
+// problems where each side can have several degrees
+function new_irregular_problem(left, right, onresult, onerror, progress) {
+    let ondata = x => handle_result(x, onresult, onerror, progress);
+    return api.request({ NewIrregularProblem : [left,right] }, ondata , function(){});
+}
+
+function irregular_speedup(problem, onresult, onerror, progress) {
+    let ondata = x => handle_result(x, onresult, onerror, progress);
+    return api.request({ IrregularSpeedup : problem }, ondata , function(){});
+}
+
+function irregular_autolb(problem, max_labels, max_steps, onresult, onerror, progress) {
     let ondata = x => handle_result(x, onresult, onerror, progress);
-    return api.request({ Speedup : problem }, ondata , function(){});
+    return api.request({ IrregularAutoLb : [problem, parseInt(max_labels), parseInt(max_steps)] }, ondata , function(){});
+}
+
+// speedups are watched, so that a blowup is reported with some ways to avoid it before the tab runs out of memory
+const speedup_limits = { max_labels : null, max_lines : null, max_time : null, max_mem : null, watchdog : { max_predicted_lines : 200000, abort : false } };
+
+function blowup_message(w, problem) {
+    let names = vec_to_map(problem.mapping_label_text);
+    let msg = "The speedup is likely to blow up: about " + w.predicted_lines + " lines are expected (" + w.lines + " so far).";
+    if( w.rarely_used.length > 0 ){
+        msg += " Consider hardening the rarely used labels " + w.rarely_used.map(l => names[l]).join(", ") + ".";
+    }
+    if( w.equivalent.length > 0 ){
+        msg += " Consider merging " + w.equivalent.map(g => g.map(l => names[l]).join("")).join(", ") + ".";
+    }
+    return msg;
+}
+
+function speedup(problem, onresult, onerror, progress){
+    let ondata = x => {
+        if( x.BlowupWarning != null ){
+            progress.warning = blowup_message(x.BlowupWarning, problem);
+        }
+        handle_result(x, onresult, onerror, progress);
+    };
+    return api.request({ SpeedupWithLimits : [problem, speedup_limits] }, ondata , function(){});
 }
 
 function fixpoint_gendefault(problem, partial, triviality_only, sublabels, onresult, onerror, progress){
@@ -136,7 +185,7 @@ function merge_equivalent_labels(problem, onresult, onerror, progress){
 
 function maximize(problem, onresult, onerror, progress){
     let ondata = x => handle_result(x, onresult, onerror, progress);
-    return api.request({ Maximize : problem }, ondata , function(){});
+    return api.request({ Maximize : [problem, speedup_limits] }, ondata , function(){});
 }
 
 function renamegenerators(problem, onresult, onerror, progress){
@@ -149,17 +198,70 @@ function rename(problem, renaming, onresult, onerror, progress){
     return api.request({ Rename : [problem,renaming] }, ondata , function(){});
 }
 
-function autoub(problem, b_max_labels, max_labels, b_branching, branching, b_max_steps, max_steps, coloring_given, coloring, coloring_given_passive, coloring_passive, onresult, onerror, progress, oncomplete){
+function foldlabels(problem, groups, onresult, onerror, progress){
+    let ondata = x => handle_result(x, onresult, onerror, progress);
+    return api.request({ FoldLabels : [problem,groups] }, ondata , function(){});
+}
+
+function colorlabels(problem, labels, onresult, onerror, progress){
+    let ondata = x => handle_result(x, onresult, onerror, progress);
+    return api.request({ ColorLabels : [problem,labels] }, ondata , function(){});
+}
+
+function onesidedlabels(problem, labels, onresult, onerror, progress){
+    let ondata = x => handle_result(x, onresult, onerror, progress);
+    return api.request({ OneSidedLabels : [problem,labels] }, ondata , function(){});
+}
+
+function autoub(problem, b_max_labels, max_labels, b_branching, branching, b_max_steps, max_steps, coloring_given, coloring, coloring_given_passive, coloring_passive, both_sides, onresult, onerror, progress, oncomplete){
+    let ondata = x => handle_result(x, onresult, onerror, progress);
+    return api.request({ AutoUb : [problem, b_max_labels, parseInt(max_labels), b_branching, parseInt(branching), b_max_steps, parseInt(max_steps), coloring_given, parseInt(coloring), coloring_given_passive, parseInt(coloring_passive), "Lazy", both_sides, null, null] }, ondata, oncomplete);
+}
+
+function autolb(problem, b_max_labels, max_labels, b_branching, branching, b_max_steps, max_steps, coloring_given, coloring, coloring_given_passive, coloring_passive, strategy, both_sides, onresult, onerror, progress, oncomplete){
     let ondata = x => handle_result(x, onresult, onerror, progress);
-    return api.request({ AutoUb : [problem, b_max_labels, parseInt(max_labels), b_branching, parseInt(branching), b_max_steps, parseInt(max_steps), coloring_given, parseInt(coloring), coloring_given_passive, parseInt(coloring_passive)] }, ondata, oncomplete);
+    return api.request({ AutoLb : [problem, b_max_labels, parseInt(max_labels), b_branching, parseInt(branching),  b_max_steps, parseInt(max_steps), coloring_given, parseInt(coloring), coloring_given_passive, parseInt(coloring_passive), strategy, both_sides, null, null] }, ondata, oncomplete);
 }
 
-function autolb(problem, b_max_labels, max_labels, b_branching, branching, b_max_steps, max_steps, coloring_given, coloring, coloring_given_passive, coloring_passive, onresult, onerror, progress, oncomplete){
+
+
+function autoub_resume(prefix, max_labels, branching, max_steps, coloring_given, coloring, coloring_given_passive, coloring_passive, onresult, onerror, progress, oncomplete){
     let ondata = x => handle_result(x, onresult, onerror, progress);
-    return api.request({ AutoLb : [problem, b_max_labels, parseInt(max_labels), b_branching, parseInt(branching),  b_max_steps, parseInt(max_steps), coloring_given, parseInt(coloring), coloring_given_passive, parseInt(coloring_passive)] }, ondata, oncomplete);
+    return api.request({ AutoUbResume : [prefix, [parseInt(max_labels)], parseInt(branching), parseInt(max_steps), coloring_given, parseInt(coloring), coloring_given_passive, parseInt(coloring_passive), "Lazy", null] }, ondata, oncomplete);
 }
 
+function autolb_resume(prefix, max_labels, branching, max_steps, coloring_given, coloring, coloring_given_passive, coloring_passive, strategy, onresult, onerror, progress, oncomplete){
+    let ondata = x => handle_result(x, onresult, onerror, progress);
+    return api.request({ AutoLbResume : [prefix, [parseInt(max_labels)], parseInt(branching), parseInt(max_steps), coloring_given, parseInt(coloring), coloring_given_passive, parseInt(coloring_passive), strategy, null] }, ondata, oncomplete);
+}
 
+// the operations performed by hand that produced the problem, as a sequence that autolb and autoub can continue,
+// going back from the problem as long as the operations are of the given types
+function manual_sequence(stuff, problem, types) {
+    let find = p => stuff.find(x => x.type == "problem" && x.data === p);
+    let label_of = (p, text) => p.labels.find(l => p.map_label_text[l] == text);
+    let sequence = [];
+    let entry = find(problem);
+    while( entry != null && entry.origin != null && types.includes(entry.origin.action.type) ){
+        let action = entry.origin.action;
+        let parent = entry.origin.parent;
+        let operation = null;
+        if( action.type == "speedup" ){
+            operation = "Speedup";
+        } else if( action.type == "simplificationmerge" ){
+            operation = { Merge : [[[label_of(parent, action.from), label_of(parent, action.to)]], parent] };
+        } else if( action.type == "simplifymergegroup" ){
+            operation = { Merge : [action.labels.map(x => [label_of(parent, x), label_of(parent, action.to)]), parent] };
+        } else if( action.type == "hardenkeep" || action.type == "hardenremove" ){
+            operation = { Harden : entry.data.labels };
+        }
+        sequence.unshift([operation, entry.data]);
+        problem = parent;
+        entry = find(parent);
+    }
+    sequence.unshift(["Initial", problem]);
+    return sequence;
+}
 
 function fix_problem(p) {
     p.map_label_text = vec_to_map(p.mapping_label_text);
@@ -197,14 +299,30 @@ function fix_problem(p) {
 
 
 
-function on_new_what(stuff, action, progress, p, what, removeprogress = true){
+function on_new_what(stuff, action, progress, p, what, removeprogress = true, parent = null){
     let idx = stuff.indexOf(progress);
     if( removeprogress ){
         stuff.splice(idx,1);
     }
-    if( what == "problem" ){
+    if( what == "irregular" ){
+        stuff.push({ type : "performed", data : action });
+        stuff.push({ type : "irregular", data : { problem : p[0], text : p[1], trivial : p[2] } });
+    }else if( what == "irregularsequence" ){
+        let action_copy = JSON.parse(JSON.stringify(action));
+        action_copy.len = p[0];
+        let substuff = [{ type : "performed", data : action_copy }];
+        p[1].forEach((step, i) => {
+            substuff.push({ type : "performed", data : {type: i == 0 ? "irregularinitial" : "irregularspeedup"} });
+            substuff.push({ type : "irregular", data : { problem : step[0], text : step[1], trivial : null } });
+        });
+        stuff.splice(idx+1,0,{ type : "sub", data : substuff });
+    }else if( what == "problem" ){
         stuff.push({ type : "performed", data : action });
-        stuff.push({ type : "problem", data : p });
+        let entry = { type : "problem", data : p };
+        // the problem and the operation it has been obtained from, used by manual_sequence;
+        // it is not enumerable, so that it is not included in exported data, that would otherwise contain each problem twice
+        Object.defineProperty(entry, "origin", { value : { action : action, parent : parent }, enumerable : false });
+        stuff.push(entry);
     }else if( what == "sequence" ){
         let action_copy = JSON.parse(JSON.stringify(action));
         let len = p[0];
@@ -218,6 +336,8 @@ function on_new_what(stuff, action, progress, p, what, removeprogress = true){
                 substuff.push({ type : "performed", data : {type:"initial"} });
             } else if( operation == "Speedup" ){
                 substuff.push({ type : "performed", data : {type:"speedup"} });
+            } else if( operation == "SwapSides" ){
+                substuff.push({ type : "performed", data : {type:"swapsides"} });
             } else if( operation.Harden != null) {
                 substuff.push({ type : "performed", data : {type:"hardenkeep", labels:operation.Harden.map(x => step[1].map_label_text[x])} });
             } else if( operation.Merge != null) {
@@ -244,7 +364,7 @@ function call_api_generating_sequence(stuff, action, f, params, removeprogress =
 
 
 function call_api_generating_what(stuff, action, f, params, what, removeprogress = true) {
-    let progress = { type : "computing", data : {type : "empty", cur : 1, max : 1, onstop : function(){}} };
+    let progress = { type : "computing", data : {type : "empty", cur : 1, max : 1, warning : null, onstop : function(){}} };
     stuff.push(progress);
     let remove_progress_bar = function() {
         //console.log("removing progress bar");
@@ -252,8 +372,8 @@ function call_api_generating_what(stuff, action, f, params, what, removeprogress
         if(idx != -1)stuff.splice(idx,1);
     }
     let termination_handle = removeprogress?
-        f(...params, p => on_new_what(stuff, action, progress, p, what, removeprogress),e =>  { remove_progress_bar() ; stuff.push({ type : "error", data : e });} ,progress.data) :
-        f(...params, p => on_new_what(stuff, action, progress, p, what, removeprogress),e =>  { remove_progress_bar() ; stuff.push({ type : "error", data : e });} ,progress.data, function(){
+        f(...params, p => on_new_what(stuff, action, progress, p, what, removeprogress, params[0]),e =>  { remove_progress_bar() ; stuff.push({ type : "error", data : e });} ,progress.data) :
+        f(...params, p => on_new_what(stuff, action, progress, p, what, removeprogress, params[0]),e =>  { remove_progress_bar() ; stuff.push({ type : "error", data : e });} ,progress.data, function(){
             remove_progress_bar();
         });
 
@@ -271,6 +391,92 @@ function vec_to_map(v){
     return Object.assign({}, ...v.map((x) => ({[x[0]]: x[1]})));
 }
 
+// compares two vectors of numbers lexicographically
+function compare_lexicographically(a, b) {
+    for( let i = 0; i < Math.min(a.length, b.length); i++ ){
+        if( a[i] != b[i] ){
+            return a[i] - b[i];
+        }
+    }
+    return a.length - b.length;
+}
+
+// the position of each label in the order given by the label priorities, as in Problem::label_display_order, or null if no priorities have been set
+function label_display_order(problem) {
+    if( problem.label_priorities == null ){
+        return null;
+    }
+    let priorities = vec_to_map(problem.label_priorities);
+    let labels = problem.mapping_label_text.map(x => x[0]).sort((a, b) => {
+        let pa = priorities[a];
+        let pb = priorities[b];
+        if( (pa == null) != (pb == null) ){
+            return pa == null ? 1 : -1;
+        }
+        let c = pa == null ? 0 : compare_lexicographically(pa, pb);
+        return c != 0 ? c : a - b;
+    });
+    let order = {};
+    labels.forEach((l, i) => order[l] = i);
+    return order;
+}
+
+// the lines of a constraint with the labels of each group, the parts of each line, and the lines sorted by the display order,
+// so that the steps of a derivation are shown consistently
+function ordered_lines(constraint, order) {
+    if( order == null ){
+        return constraint.lines;
+    }
+    let key = part => part.group.map(x => order[x]);
+    let lines = constraint.lines.map(row => {
+        let parts = row.parts.map(elem => Object.assign({}, elem, { group : elem.group.slice().sort((a, b) => order[a] - order[b]) }));
+        parts.sort((a, b) => compare_lexicographically(key(a), key(b)));
+        return { parts : parts };
+    });
+    let line_key = row => [].concat(...row.parts.map(part => key(part).concat([-1])));
+    lines.sort((a, b) => compare_lexicographically(line_key(a), line_key(b)));
+    return lines;
+}
+
+// the lines of a constraint, with the labels of each folded group shown with the name of the group,
+// parts with the same text joined, and lines with the same text shown once
+function folded_table(problem, constraint) {
+    let names = Object.assign({}, problem.map_label_text);
+    for( let group of problem.label_folds ){
+        for( let label of group[0] ){
+            names[label] = group[1];
+        }
+    }
+    let seen = new Set();
+    let table = [];
+    for( let row of constraint.lines ){
+        let parts = [];
+        for( let elem of row.parts ){
+            let renamed = [...new Set(elem.group.map(x => names[x]))].join("");
+            let star = elem.gtype == "Star";
+            let rep = star ? 0 : (elem.gtype == "One" ? 1 : elem.gtype.Many);
+            let same = parts.find(x => x.renamed == renamed);
+            if( same != null ){
+                same.star = same.star || star;
+                same.rep += rep;
+            } else {
+                parts.push({ renamed : renamed, rep : rep, star : star, original : null });
+            }
+        }
+        for( let part of parts ){
+            if( part.star || part.rep == 1 ){
+                part.rep = null;
+            }
+        }
+        let key = parts.map(x => x.renamed + "^" + x.rep + (x.star ? "*" : "")).join(" ");
+        if( !seen.has(key) ){
+            seen.add(key);
+            table.push(parts);
+        }
+    }
+    return table;
+}
+
 function labelset_to_string(v, mapping, sep = "") {
     return v.map(x => mapping[x]).join(sep);
 }
@@ -318,6 +524,8 @@ Vue.component('re-performed-action', {
                     return "Gave input orientation. Outdegree = " + this.action.outdegree;
                 case "speedup":
                     return "Performed speedup";
+                case "swapsides":
+                    return "Swapped active and passive sides (bounds change by at most one round)";
                 case "fixpoint-basic":
                     return "Generated Fixed Point with Default Diagram" + (this.action.sub !== null ? " for labels " + this.action.sub : "");
                 case "fixpoint-gendefault":
@@ -344,8 +552,24 @@ Vue.component('re-performed-action', {
                     return "Renamed by generators";
                 case "rename":
                     return "Renamed";
+                case "foldlabels":
+                    return "Folded labels (only the presentation changes)";
+                case "colorlabels":
+                    return "Marked color labels";
+                case "onesidedlabels":
+                    return "Declared one-sided labels";
                 case "autoub":
                     return "Automatic Upper Bound. Obtained Upper Bound of " + this.action.len + " Rounds.";
+                case "irregularinitial":
+                    return "Initial problem, with several degrees per side";
+                case "irregularspeedup":
+                    return "Performed speedup, separately for each degree";
+                case "irregularautolb":
+                    if(this.action.len == 999 ){
+                        return "Speedups until zero round solvable. Obtained a Fixed Point."
+                    }else {
+                        return "Speedups until zero round solvable. Obtained Lower Bound of " + this.action.len + " Rounds.";
+                    }
                 case "autolb":
                     if(this.action.len == 999 ){
                         return "Automatic Lower Bound. Obtained a Fixed Point."
@@ -453,6 +677,7 @@ Vue.component('re-computing', {
             <div v-if="state.bar" class="progress">
                 <div class="progress-bar" role="progressbar" :style="'width : ' + Math.floor(state.cur *100 / state.max) + '%'"></div>
             </div>
+            <div v-if="action.warning != null" class="alert alert-warning mt-2 mb-0">{{ action.warning }}</div>
             <button type="button" class="close position-absolute top-0 end-0 p-2" aria-label="Close" v-on:click="on_close">
                 <span aria-hidden="true">&times;</span>
             </button>
@@ -561,16 +786,21 @@ Vue.component('re-problem-info', {
 
 
 Vue.component('re-constraint', {
-    props: ['problem','side','mode'],
+    props: ['problem','side','mode','folded'],
     computed: {
         table : function() {
             let problem = this.problem;
             let constraint = this.side == "active" ? problem.active : problem.passive;
-            return constraint.lines.map(row => row.parts.map(elem => {
+            if( this.folded && this.mode == "renamed" && problem.label_folds != null ){
+                return folded_table(problem, constraint);
+            }
+            return ordered_lines(constraint, label_display_order(problem)).map(row => row.parts.map(elem => {
                 let renamed = labelset_to_string(elem.group,this.problem.map_label_text);
                 let original = problem.mapping_label_oldlabels == null ? null : elem.group.map(x => labelset_to_string(this.problem.map_label_oldlabels[x],this.problem.map_oldlabel_text));
 
-                let r = {  renamed : renamed, original : original};
+                let color_labels = problem.color_labels ?? [];
+                let colored = elem.group.length > 0 && elem.group.every(x => color_labels.includes(x));
+                let r = {  renamed : renamed, original : original, colored : colored};
                 if( elem.gtype == "One" || elem.gtype.Many == 1 ){
                 } else if( elem.gtype == "Star" ){
                     r.star = true;
@@ -591,13 +821,13 @@ Vue.component('re-constraint', {
                         <sup v-if="elem.rep">{{ elem.rep }}</sup>
                         <span v-if="elem.star">*</span>
                     </div>
-                    <div v-if="mode == 'renamed'">
+                    <div v-if="mode == 'renamed'" :class="{ colorlabel : elem.colored }">
                         {{ elem.renamed }}
                         <sup v-if="elem.rep">{{ elem.rep }}</sup>
                         <span v-if="elem.star">*</span>
                     </div>
                     <div v-if="mode == 'both'">
-                        {{ elem.renamed }}
+                        <span :class="{ colorlabel : elem.colored }">{{ elem.renamed }}</span>
                         <sup v-if="elem.rep">{{ elem.rep }}</sup>
                         <span v-if="elem.star">*</span>
                         <hr/>
@@ -874,8 +1104,11 @@ Vue.component('re-rename-generators',{
 
 Vue.component('re-rename',{
     props: ['problem','stuff'],
-    data: function(){ return {
-        table: this.problem.mapping_label_text.map(x => {
+    data: function(){
+        let order = label_display_order(this.problem);
+        let mapping = order == null ? this.problem.mapping_label_text : this.problem.mapping_label_text.slice().sort((a, b) => order[a[0]] - order[b[0]]);
+        return {
+            table: mapping.map(x => {
                 let label = x[0];
                 let text = x[1];
                 let oldtext = this.problem.map_label_oldlabels == null ? null : labelset_to_string(this.problem.map_label_oldlabels[label],this.problem.map_oldlabel_text);
@@ -885,8 +1118,9 @@ Vue.component('re-rename',{
                 } else {
                     return [label,text,oldtext,without_parenthesis];
                 }
-        })
-    }},
+            })
+        };
+    },
     watch: { 
         // for some unknown reason, vue updates the template values when the prop "problem" changes, but it does not update the values of the variables contained in "data"
         // this is a workaround
@@ -914,6 +1148,122 @@ Vue.component('re-rename',{
 })
 
 
+Vue.component('re-fold',{
+    props: ['problem','stuff'],
+    data: function(){ 
+        let folds = this.problem.label_folds ?? [];
+        return {
+            table: this.problem.mapping_label_text.map(x => {
+                let group = folds.find(g => g[0].includes(x[0]));
+                return [x[0], x[1], group == null ? "" : group[1]];
+            })
+        }
+    },
+    watch: { 
+        // for some unknown reason, vue updates the template values when the prop "problem" changes, but it does not update the values of the variables contained in "data"
+        // this is a workaround
+        problem: function(newVal, oldVal) { 
+            Object.assign(this.$data, this.$options.data.apply(this))
+        }
+    },
+    methods: {
+        on_fold() {
+            // labels with the same (nonempty) name are folded together
+            let groups = [];
+            for( let row of this.table ){
+                let name = row[2].trim();
+                if( name == "" ) continue;
+                let group = groups.find(g => g[1] == name);
+                if( group == null ){
+                    groups.push([[row[0]], name]);
+                } else {
+                    group[0].push(row[0]);
+                }
+            }
+            call_api_generating_problem(this.stuff,{type:"foldlabels"},foldlabels,[this.problem,groups]);
+        }
+    },
+    template: `
+    <re-card title="Fold labels" subtitle="(show groups of labels with one name, without changing the problem)">
+        <table class="table">
+            <tr v-for="(row,index) in this.table">
+                <td class="align-middle">{{ row[1] }}</td>
+                <td class="align-middle"><input class="form-control" v-model="table[index][2]"></input></td>
+            </tr>
+        </table>
+        <button type="button" class="btn btn-primary m-1" v-on:click="on_fold">Fold</button>
+    </re-card>
+    `
+})
+
+Vue.component('re-color-labels',{
+    props: ['problem','stuff'],
+    data: function(){ 
+        let color_labels = this.problem.color_labels ?? [];
+        return {
+            table: this.problem.mapping_label_text.map(x => [x[0], x[1], color_labels.includes(x[0])])
+        }
+    },
+    watch: { 
+        problem: function(newVal, oldVal) { 
+            Object.assign(this.$data, this.$options.data.apply(this))
+        }
+    },
+    methods: {
+        on_mark() {
+            let labels = this.table.filter(row => row[2]).map(row => row[0]);
+            call_api_generating_problem(this.stuff,{type:"colorlabels"},colorlabels,[this.problem,labels]);
+        }
+    },
+    template: `
+    <re-card title="Color labels" subtitle="(labels that encode a coloring: they are kept through speedups and never merged by the automatic lower bound)">
+        <div class="m-2">
+            <label v-for="(row,index) in this.table" class="m-1" :class="{ colorlabel : row[2] }"><input type="checkbox" v-model="table[index][2]"> {{ row[1] }}</label>
+        </div>
+        <button type="button" class="btn btn-primary m-1" v-on:click="on_mark">Mark</button>
+    </re-card>
+    `
+})
+
+Vue.component('re-one-sided-labels',{
+    props: ['problem','stuff'],
+    data: function(){ 
+        let one_sided = this.problem.one_sided_labels ?? [];
+        let side_of = l => (one_sided.find(x => x[0] == l) ?? [l, "Both"])[1];
+        return {
+            table: this.problem.mapping_label_text.map(x => [x[0], x[1], side_of(x[0])])
+        }
+    },
+    watch: { 
+        problem: function(newVal, oldVal) { 
+            Object.assign(this.$data, this.$options.data.apply(this))
+        }
+    },
+    methods: {
+        on_declare() {
+            let labels = this.table.filter(row => row[2] != "Both").map(row => [row[0], row[2]]);
+            call_api_generating_problem(this.stuff,{type:"onesidedlabels"},onesidedlabels,[this.problem,labels]);
+        }
+    },
+    template: `
+    <re-card title="One-sided labels" subtitle="(labels that may appear only on the active side or only on the passive side)">
+        <table class="table">
+            <tr v-for="(row,index) in this.table">
+                <td class="align-middle">{{ row[1] }}</td>
+                <td class="align-middle">
+                    <select class="form-control" v-model="table[index][2]">
+                        <option value="Both">both sides</option>
+                        <option value="Active">active only</option>
+                        <option value="Passive">passive only</option>
+                    </select>
+                </td>
+            </tr>
+        </table>
+        <button type="button" class="btn btn-primary m-1" v-on:click="on_declare">Declare</button>
+    </re-card>
+    `
+})
+
 Vue.component('re-maximize',{
     props: ['problem','stuff'],
     methods: {
@@ -1207,6 +1557,11 @@ Vue.component('re-auto-lb',{
             coloring : (this.problem.active.degree.Finite != null && this.problem.passive.degree.Finite != null) ? (this.problem.active.degree.Finite*(this.problem.passive.degree.Finite - 1) +1) : 4,
             coloring_given_passive : false,
             coloring_passive : (this.problem.active.degree.Finite != null && this.problem.passive.degree.Finite != null) ? (this.problem.passive.degree.Finite*(this.problem.active.degree.Finite - 1) +1) : 4,
+            b_similarity : false,
+            similarity : "",
+            b_diagram_width : false,
+            both_sides : false,
+            b_resume : false,
         }
     },
     watch: { 
@@ -1218,7 +1573,15 @@ Vue.component('re-auto-lb',{
     },
     methods: {
         on_autolb() {
-            call_api_generating_sequence(this.stuff,{type:"autolb"},autolb,[this.problem, this.b_max_labels, this.max_labels, this.b_branching, this.branching, this.b_max_steps, this.max_steps, this.coloring_given, this.coloring, this.coloring_given_passive, this.coloring_passive], false);
+            // each line is "A B s", meaning that merging A and B has similarity s
+            let similarity = !this.b_similarity ? [] : this.similarity.split("\n").map(x => x.trim().split(/\s+/)).filter(x => x.length == 3).map(x => [x[0], x[1], parseInt(x[2])]);
+            let strategy = similarity.length > 0 ? { Similarity : similarity } : this.b_diagram_width ? "DiagramWidth" : "Labels";
+            if( this.b_resume ){
+                let prefix = manual_sequence(this.stuff, this.problem, ["speedup", "simplificationmerge", "simplifymergegroup"]);
+                call_api_generating_sequence(this.stuff,{type:"autolb"},autolb_resume,[prefix, this.max_labels, this.branching, this.max_steps, this.coloring_given, this.coloring, this.coloring_given_passive, this.coloring_passive, strategy], false);
+                return;
+            }
+            call_api_generating_sequence(this.stuff,{type:"autolb"},autolb,[this.problem, this.b_max_labels, this.max_labels, this.b_branching, this.branching, this.b_max_steps, this.max_steps, this.coloring_given, this.coloring, this.coloring_given_passive, this.coloring_passive, strategy, this.both_sides], false);
         },
     },
     template: `
@@ -1249,6 +1612,24 @@ Vue.component('re-auto-lb',{
             </div>
             <div v-if="this.b_max_steps">Max Steps: <input class="form-control m-2" type="number" v-model="max_steps"></div>
 
+            <div class="custom-control custom-switch m-2">
+                <label><input type="checkbox" class="custom-control-input" v-model="b_similarity"><p class="form-control-static custom-control-label">Merge the most similar labels first</p></label>
+            </div>
+            <div v-if="this.b_similarity">One pair of labels of this problem per line, as "A B similarity": <textarea class="form-control m-2" rows="4" v-model="similarity"></textarea></div>
+
+            <div class="custom-control custom-switch m-2" v-if="!this.b_similarity">
+                <label><input type="checkbox" class="custom-control-input" v-model="b_diagram_width"><p class="form-control-static custom-control-label">Prefer the merges that give the narrowest diagram</p></label>
+            </div>
+
+            <div class="custom-control custom-switch m-2" v-if="!this.b_resume">
+                <label><input type="checkbox" class="custom-control-input" v-model="both_sides"><p class="form-control-static custom-control-label">Also search on the problem with the sides swapped</p></label>
+            </div>
+
+            <div class="custom-control custom-switch m-2">
+                <label><input type="checkbox" class="custom-control-input" v-model="b_resume"><p class="form-control-static custom-control-label">Continue the speedups and merges performed by hand</p></label>
+            </div>
+            <div v-if="this.b_resume">The search starts from the sequence of operations that produced this problem, with the given max labels, branching, and max steps.</div>
+
             <button type="button" class="btn btn-primary m-2" v-on:click="on_autolb">Automatic Lower Bound</button>
         </re-card>
     `
@@ -1268,6 +1649,8 @@ Vue.component('re-auto-ub',{
             coloring : (this.problem.active.degree.Finite != null && this.problem.passive.degree.Finite != null) ? (this.problem.active.degree.Finite*(this.problem.passive.degree.Finite - 1) +1) : 4,
             coloring_given_passive : false,
             coloring_passive : (this.problem.active.degree.Finite != null && this.problem.passive.degree.Finite != null) ? (this.problem.passive.degree.Finite*(this.problem.active.degree.Finite - 1) +1) : 4,
+            both_sides : false,
+            b_resume : false,
         }
     },
     watch: { 
@@ -1279,7 +1662,12 @@ Vue.component('re-auto-ub',{
     },
     methods: {
         on_autoub() {
-            call_api_generating_sequence(this.stuff,{type:"autoub"},autoub,[this.problem, this.b_max_labels, this.max_labels, this.b_branching, this.branching, this.b_max_steps, this.max_steps, this.coloring_given, this.coloring, this.coloring_given_passive, this.coloring_passive], false);
+            if( this.b_resume ){
+                let prefix = manual_sequence(this.stuff, this.problem, ["speedup", "hardenkeep", "hardenremove"]);
+                call_api_generating_sequence(this.stuff,{type:"autoub"},autoub_resume,[prefix, this.max_labels, this.branching, this.max_steps, this.coloring_given, this.coloring, this.coloring_given_passive, this.coloring_passive], false);
+                return;
+            }
+            call_api_generating_sequence(this.stuff,{type:"autoub"},autoub,[this.problem, this.b_max_labels, this.max_labels, this.b_branching, this.branching, this.b_max_steps, this.max_steps, this.coloring_given, this.coloring, this.coloring_given_passive, this.coloring_passive, this.both_sides], false);
         },
     },
     template: `
@@ -1310,6 +1698,15 @@ Vue.component('re-auto-ub',{
             </div>
             <div v-if="this.b_max_steps">Max Steps: <input class="form-control m-2" type="number" v-model="max_steps"></div>
 
+            <div class="custom-control custom-switch m-2" v-if="!this.b_resume">
+                <label><input type="checkbox" class="custom-control-input" v-model="both_sides"><p class="form-control-static custom-control-label">Also search on the problem with the sides swapped</p></label>
+            </div>
+
+            <div class="custom-control custom-switch m-2">
+                <label><input type="checkbox" class="custom-control-input" v-model="b_resume"><p class="form-control-static custom-control-label">Continue the speedups and hardenings performed by hand</p></label>
+            </div>
+            <div v-if="this.b_resume">The search starts from the sequence of operations that produced this problem, with the given max labels, branching, and max steps.</div>
+
             <button type="button" class="btn btn-primary m-2" v-on:click="on_autoub">Automatic Upper Bound</button>
         </re-card>
     `
@@ -1347,6 +1744,9 @@ Vue.component('re-tools', {
             <re-harden-remove :problem="problem" :stuff="stuff"></re-harden-remove>
             <re-group-harden :problem="problem" :stuff="stuff"></re-group-harden>
             <re-rename :problem="problem" :stuff="stuff"></re-rename>
+            <re-fold :problem="problem" :stuff="stuff"></re-fold>
+            <re-color-labels :problem="problem" :stuff="stuff"></re-color-labels>
+            <re-one-sided-labels :problem="problem" :stuff="stuff"></re-one-sided-labels>
             <re-fixpoint :problem="problem" :stuff="stuff"></re-fixpoint>
             <re-auto-lb :problem="problem" :stuff="stuff"></re-auto-lb>
             <re-auto-ub :problem="problem" :stuff="stuff"></re-auto-ub>
@@ -1360,7 +1760,8 @@ Vue.component('re-problem', {
     props: ["problem","stuff","handle"],
     data : function() {
         return {
-            mode : "renamed"
+            mode : "renamed",
+            folded : true
         }
     },
     methods: {
@@ -1382,6 +1783,9 @@ Vue.component('re-problem', {
                             <input type="radio" name="options" autocomplete="off" value="both" v-model="mode">Both</label>
                     </div>
                 </div>
+                <div v-if="this.problem.label_folds != null" class="custom-control custom-switch pt-3 pl-3">
+                    <label><input type="checkbox" class="custom-control-input" v-model="folded"><p class="form-control-static custom-control-label">Folded</p></label>
+                </div>
                 <div/>
                 <button type="button" class="close position-absolute top-0 end-0 p-2" aria-label="Close" v-on:click="on_close">
                     <span aria-hidden="true">&times;</span>
@@ -1390,10 +1794,10 @@ Vue.component('re-problem', {
             <re-problem-info :problem="this.problem"></re-problem-info>
             <div class="row p-0 m-2 align-items-start">
                 <re-card title="Active" subtitle="Any choice satisfies previous Passive" show="true">
-                    <re-constraint side="active" :mode="this.mode" :problem="this.problem"></re-constraint>
+                    <re-constraint side="active" :mode="this.mode" :folded="this.folded" :problem="this.problem"></re-constraint>
                 </re-card>
                 <re-card title="Passive" subtitle="Exists choice satisfying previous Active" show="true">
-                    <re-constraint side="passive" :mode="this.mode" :problem="this.problem"></re-constraint>
+                    <re-constraint side="passive" :mode="this.mode" :folded="this.folded" :problem="this.problem"></re-constraint>
                 </re-card>
                 <re-card title="Renaming" subtitle="Old and new labels" show="true" v-if="this.problem.mapping_label_oldlabels != null">
                     <re-renaming :problem="problem"></re-renaming>
@@ -1456,6 +1860,46 @@ Vue.component('re-label-picker', {
 })
 
 
+// a problem where each side can have several degrees, shown as text, with the operations that support it
+Vue.component('re-irregular', {
+    props: ["irregular","stuff","handle"],
+    data : function() {
+        return {
+            max_labels : 20,
+            max_steps : 5
+        }
+    },
+    methods: {
+        on_close() {
+            let idx = this.stuff.indexOf(this.handle);
+            this.stuff.splice(idx,1);
+        },
+        on_speedup() {
+            call_api_generating_what(this.stuff,{type:"irregularspeedup"},irregular_speedup,[this.irregular.problem],"irregular");
+        },
+        on_autolb() {
+            call_api_generating_what(this.stuff,{type:"irregularautolb"},irregular_autolb,[this.irregular.problem, this.max_labels, this.max_steps],"irregularsequence");
+        }
+    },
+    template: `
+        <div class="card card-body m-2 p-2 bg-light position-relative">
+            <button type="button" class="close position-absolute top-0 end-0 p-2" aria-label="Close" v-on:click="on_close">
+                <span aria-hidden="true">&times;</span>
+            </button>
+            <div class="m-2" v-if="this.irregular.trivial != null">{{ this.irregular.trivial.length > 0 ? "The problem is zero rounds solvable." : "The problem is not zero rounds solvable." }}</div>
+            <pre class="m-2">{{ this.irregular.text }}</pre>
+            <div class="m-2">
+                <button type="button" class="btn btn-primary m-1" v-on:click="on_speedup">Speedup</button>
+            </div>
+            <div class="m-2">
+                Max Labels: <input class="form-control m-1" type="number" v-model="max_labels">
+                Max Steps: <input class="form-control m-1" type="number" v-model="max_steps">
+                <button type="button" class="btn btn-primary m-1" v-on:click="on_autolb">Speedups until zero round solvable</button>
+            </div>
+        </div>
+    `
+})
+
 Vue.component('re-begin', {
     props: ["all"],
     data : function(){ return {
@@ -1472,6 +1916,9 @@ Vue.component('re-begin', {
         on_start() {
             call_api_generating_problem(this.stuff,{type:"initial"},new_problem,[this.active, this.passive]);
         },
+        on_start_irregular() {
+            call_api_generating_what(this.stuff,{type:"irregularinitial"},new_irregular_problem,[this.active, this.passive],"irregular");
+        },
         on_clear() {
             this.stuff.splice(0)
         }
@@ -1496,6 +1943,7 @@ Vue.component('re-begin', {
             </div>
             <div class="m-2 col-sm mt-auto text-right">
                 <button type="button" class="btn btn-primary" v-on:click="on_start">Start</button>
+                <button type="button" class="btn btn-secondary" v-on:click="on_start_irregular" title="Each side can contain lines of different degrees, for example the nodes of a graph of maximum degree Δ">Start (several degrees)</button>
                 <button type="button" class="btn btn-primary" v-on:click="on_clear">Clear</button>
                 <re-export :stuff="stuff" :active="active" :passive="passive"></re-export>
             </div>
@@ -1803,6 +2251,7 @@ Vue.component('re-stuff', {
                     <re-computing :action='elem.data' v-if='elem.type == "computing"'  :handle="elem"/></re-computing>
                     <re-error :stuff="stuff" :error='elem.data' v-if='elem.type == "error"'  :handle="elem"/></re-computing>
                     <re-problem :problem='elem.data' :stuff='stuff' v-if='elem.type == "problem"' :handle="elem"></re-problem>
+                    <re-irregular :irregular='elem.data' :stuff='stuff' v-if='elem.type == "irregular"' :handle="elem"></re-irregular>
                     <re-stuff :supstuff='stuff' :stuff='elem.data' v-if='elem.type == "sub"' :handle="elem"></re-stuff>
                 </div>
             </div>
@@ -1815,6 +2264,7 @@ Vue.component('re-stuff', {
                     <re-computing :action='elem.data' v-if='elem.type == "computing"'  :handle="elem"/></re-computing>
                     <re-error :stuff="stuff" :error='elem.data' v-if='elem.type == "error"'  :handle="elem"/></re-computing>
                     <re-problem :problem='elem.data' :stuff='stuff' v-if='elem.type == "problem"' :handle="elem"></re-problem>
+                    <re-irregular :irregular='elem.data' :stuff='stuff' v-if='elem.type == "irregular"' :handle="elem"></re-irregular>
                     <re-stuff :supstuff='stuff' :stuff='elem.data' v-if='elem.type == "sub"' :handle="elem"></re-stuff>
                 </div>
             </div>
@@ -1,5 +1,8 @@
 importScripts('./pkg/round_eliminator_wasm.js');
 
+// progress events are batched, and sent at most this many times per second
+const MAX_EVENTS_PER_SECOND = 10;
+
 
 async function init_wasm_in_worker() {
     await wasm_bindgen('./pkg/round_eliminator_wasm_bg.wasm');
@@ -10,7 +13,12 @@ async function init_wasm_in_worker() {
             self.postMessage(x);
         }
 
-        wasm_bindgen.request_json(r,f);
+        // requests sent as bytes are CBOR encoded, and so are their responses
+        if (r instanceof Uint8Array || r instanceof ArrayBuffer) {
+            wasm_bindgen.request_cbor_throttled(new Uint8Array(r),f,MAX_EVENTS_PER_SECOND);
+        } else {
+            wasm_bindgen.request_json_throttled(r,f,MAX_EVENTS_PER_SECOND);
+        }
        
     };
 